@@ -50,6 +50,21 @@ export function getLpPoolPDA(mint: PublicKey): [PublicKey, number] {
   );
 }
 
+export function getLpVaultPDA(mint: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("lp_vault"), mint.toBuffer()],
+    LEGASI_LP_PROGRAM_ID
+  );
+}
+
+// Signs protocol totals and LP pool updates on behalf of the lending program
+export function getProtocolWriterPDA(): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol_writer")],
+    LEGASI_LENDING_PROGRAM_ID
+  );
+}
+
 export function getPriceFeedPDA(mint: PublicKey): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
     [Buffer.from("price"), mint.toBuffer()],
@@ -71,6 +86,8 @@ export interface Position {
     totalRepaidUsd: BN;
     gadEvents: number;
     accountAgeDays: number;
+    lastCreditedAt: BN;
+    debtSince: BN;
   };
   bump: number;
 }
@@ -150,6 +167,11 @@ export class LegasiClient {
       .accounts({
         position: positionPDA,
         solVault: solVaultPDA,
+        solPriceFeed: getPriceFeedPDA(SOL_MINT)[0],
+        solMint: SOL_MINT,
+        protocol: getProtocolPDA()[0],
+        protocolWriter: getProtocolWriterPDA()[0],
+        coreProgram: LEGASI_CORE_PROGRAM_ID,
         owner: this.provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -164,11 +186,8 @@ export class LegasiClient {
     const [protocolPDA] = getProtocolPDA();
     const [priceFeedPDA] = getPriceFeedPDA(SOL_MINT);
     
-    // Use lending_vault (owned by lending program)
-    const lendingVaultPDA = PublicKey.findProgramAddressSync(
-      [Buffer.from("lending_vault"), USDC_MINT.toBuffer()],
-      LEGASI_LENDING_PROGRAM_ID
-    )[0];
+    // Borrows are paid out of the LP pool vault
+    const [lpVaultPDA] = getLpVaultPDA(USDC_MINT);
     
     // Get user's USDC ATA
     const userUsdcAta = await this.findAta(this.provider.wallet.publicKey, USDC_MINT);
@@ -180,11 +199,15 @@ export class LegasiClient {
       .accounts({
         position: positionPDA,
         protocol: protocolPDA,
+        protocolWriter: getProtocolWriterPDA()[0],
+        coreProgram: LEGASI_CORE_PROGRAM_ID,
         borrowableConfig: PublicKey.findProgramAddressSync(
           [Buffer.from("borrowable"), USDC_MINT.toBuffer()],
           LEGASI_CORE_PROGRAM_ID
         )[0],
-        borrowVault: lendingVaultPDA,
+        lpPool: getLpPoolPDA(USDC_MINT)[0],
+        lpProgram: LEGASI_LP_PROGRAM_ID,
+        borrowVault: lpVaultPDA,
         userTokenAccount: userUsdcAta,
         solPriceFeed: priceFeedPDA,
         solMint: SOL_MINT,
@@ -250,11 +273,8 @@ export class LegasiClient {
     
     const mint = assetType === 2 ? USDC_MINT : EURC_MINT;
     
-    // Use lending_vault (owned by lending program)
-    const lendingVaultPDA = PublicKey.findProgramAddressSync(
-      [Buffer.from("lending_vault"), mint.toBuffer()],
-      LEGASI_LENDING_PROGRAM_ID
-    )[0];
+    // Repayments go back to the LP pool vault
+    const [lpVaultPDA] = getLpVaultPDA(mint);
     
     const userTokenAta = await this.findAta(this.provider.wallet.publicKey, mint);
     
@@ -268,8 +288,13 @@ export class LegasiClient {
           [Buffer.from("borrowable"), mint.toBuffer()],
           LEGASI_CORE_PROGRAM_ID
         )[0],
-        repayVault: lendingVaultPDA,
+        lpPool: getLpPoolPDA(mint)[0],
+        repayVault: lpVaultPDA,
         userTokenAccount: userTokenAta,
+        protocol: getProtocolPDA()[0],
+        protocolWriter: getProtocolWriterPDA()[0],
+        coreProgram: LEGASI_CORE_PROGRAM_ID,
+        lpProgram: LEGASI_LP_PROGRAM_ID,
         owner: this.provider.wallet.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
@@ -297,6 +322,9 @@ export class LegasiClient {
         solVault: solVaultPDA,
         solPriceFeed: priceFeedPDA,
         solMint: SOL_MINT,
+        protocol: getProtocolPDA()[0],
+        protocolWriter: getProtocolWriterPDA()[0],
+        coreProgram: LEGASI_CORE_PROGRAM_ID,
         owner: this.provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -0,0 +1,50 @@
+//! legasi-flash calls
+//!
+//! `flash_borrow` checks, through the Instructions sysvar, that the transaction
+//! closes the loan with a later top-level `flash_repay` (built off-chain with
+//! `legasi_sdk::instructions::flash::flash_repay`), which pulls `repay_amount` from
+//! `accounts.user_token_account`. A program can borrow by CPI and use the funds in
+//! its instruction; the repay instruction comes after it, signed by the borrower,
+//! so the borrower is a wallet rather than a PDA.
+
+use anchor_lang::prelude::*;
+
+use crate::accounts::FlashBorrow;
+use crate::{check_program, LegasiError, FLASH_PROGRAM_ID};
+
+pub use legasi_flash::flash_loan_fee;
+
+/// Principal plus fee that `flash_repay` pulls for a loan of `amount`
+pub fn repay_amount(amount: u64) -> Result<u64> {
+    amount
+        .checked_add(flash_loan_fee(amount)?)
+        .ok_or_else(|| error!(LegasiError::MathOverflow))
+}
+
+/// Borrow `amount` from the pool; the loan is keyed by the current slot
+pub fn flash_borrow<'info>(
+    flash_program: &AccountInfo<'info>,
+    accounts: FlashBorrow<'info>,
+    amount: u64,
+) -> Result<()> {
+    check_program(flash_program, &FLASH_PROGRAM_ID)?;
+    legasi_flash::cpi::flash_borrow(
+        CpiContext::new(flash_program.clone(), accounts),
+        amount,
+        Clock::get()?.slot,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repay_amount() {
+        // 0.05% of 10,000 USDC
+        assert_eq!(repay_amount(10_000_000_000).unwrap(), 10_005_000_000);
+        // Minimum fee of 1 unit
+        assert_eq!(repay_amount(100).unwrap(), 101);
+        assert!(repay_amount(u64::MAX).is_err());
+    }
+}
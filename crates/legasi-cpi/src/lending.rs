@@ -0,0 +1,60 @@
+//! legasi-lending calls
+//!
+//! The position owner (`borrow` / `repay`) or the agent (`x402_pay`) signs; a
+//! program holding a position under its own PDA passes that PDA's seeds.
+
+use anchor_lang::prelude::*;
+
+use crate::accounts::{Borrow, Repay, X402Pay};
+use crate::{check_program, X402PaymentRequest, LENDING_PROGRAM_ID};
+
+/// Borrow `amount` of the pool's asset into `accounts.user_token_account`
+pub fn borrow<'info>(
+    lending_program: &AccountInfo<'info>,
+    accounts: Borrow<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+    reference: Option<[u8; 32]>,
+) -> Result<()> {
+    check_program(lending_program, &LENDING_PROGRAM_ID)?;
+    legasi_lending::cpi::borrow(
+        CpiContext::new_with_signer(lending_program.clone(), accounts, signer_seeds),
+        amount,
+        reference,
+    )
+}
+
+/// Repay `amount` of the pool's asset from `accounts.user_token_account`
+pub fn repay<'info>(
+    lending_program: &AccountInfo<'info>,
+    accounts: Repay<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+    reference: Option<[u8; 32]>,
+) -> Result<()> {
+    check_program(lending_program, &LENDING_PROGRAM_ID)?;
+    legasi_lending::cpi::repay(
+        CpiContext::new_with_signer(lending_program.clone(), accounts, signer_seeds),
+        amount,
+        reference,
+    )
+}
+
+/// Pay an x402 request from the agent's token account, borrowing the shortfall
+/// against the owner's position when `auto_borrow` is set
+pub fn x402_pay<'info>(
+    lending_program: &AccountInfo<'info>,
+    accounts: X402Pay<'info>,
+    signer_seeds: &[&[&[u8]]],
+    payment_request: X402PaymentRequest,
+    auto_borrow: bool,
+    reference: Option<[u8; 32]>,
+) -> Result<()> {
+    check_program(lending_program, &LENDING_PROGRAM_ID)?;
+    legasi_lending::cpi::x402_pay(
+        CpiContext::new_with_signer(lending_program.clone(), accounts, signer_seeds),
+        payment_request,
+        auto_borrow,
+        reference,
+    )
+}
@@ -0,0 +1,39 @@
+//! # Legasi CPI
+//!
+//! Interface for on-chain programs composing Legasi (e.g. an agent framework that
+//! borrows and pays for its agents), without copying account structs:
+//! - `lending` - `borrow`, `repay` and `x402_pay`
+//! - `flash` - `flash_borrow` and the amount its `flash_repay` pulls
+//! - `accounts` - the Anchor `cpi::accounts` structs each call takes
+//!
+//! Every wrapper takes the target program's account and its accounts struct, and
+//! checks the program ID before invoking. Lending calls also take the signer seeds
+//! of the caller's PDA that signs as owner or agent (empty when a wallet signs the
+//! outer transaction). Account addresses come from the client, built with
+//! `legasi_sdk::pda` like any other Legasi instruction.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+pub mod flash;
+pub mod lending;
+
+pub use legasi_core::ID as CORE_PROGRAM_ID;
+pub use legasi_flash::ID as FLASH_PROGRAM_ID;
+pub use legasi_lending::ID as LENDING_PROGRAM_ID;
+
+/// Arguments and state the wrapped instructions take or read
+pub use legasi_core::errors::LegasiError;
+pub use legasi_lending::{AgentConfig, Position, X402PaymentRequest};
+
+/// Anchor CPI account structs of the wrapped instructions
+pub mod accounts {
+    pub use legasi_flash::cpi::accounts::FlashBorrow;
+    pub use legasi_lending::cpi::accounts::{Borrow, Repay, X402Pay};
+}
+
+/// Fails unless `program` is the program at `id`
+fn check_program(program: &AccountInfo, id: &Pubkey) -> Result<()> {
+    require_keys_eq!(program.key(), *id, ErrorCode::InvalidProgramId);
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Account deserialization and fetching
+
+#[cfg(feature = "rpc")]
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Result};
+
+/// Deserialize an Anchor account (checks the discriminator)
+pub fn deserialize<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Fetch and deserialize an account over JSON-RPC
+#[cfg(feature = "rpc")]
+pub fn fetch<T: AccountDeserialize>(
+    client: &solana_client::rpc_client::RpcClient,
+    address: &Pubkey,
+) -> std::result::Result<T, Box<dyn std::error::Error>> {
+    let data = client.get_account_data(address)?;
+    Ok(deserialize(&data)?)
+}
+
+/// Fetch a user's lending position
+#[cfg(feature = "rpc")]
+pub fn fetch_position(
+    client: &solana_client::rpc_client::RpcClient,
+    owner: &Pubkey,
+) -> std::result::Result<legasi_lending::Position, Box<dyn std::error::Error>> {
+    fetch(client, &crate::pda::position(owner).0)
+}
+
+/// Fetch an LP pool by its borrowable mint
+#[cfg(feature = "rpc")]
+pub fn fetch_lp_pool(
+    client: &solana_client::rpc_client::RpcClient,
+    borrowable_mint: &Pubkey,
+) -> std::result::Result<legasi_lp::LpPool, Box<dyn std::error::Error>> {
+    fetch(client, &crate::pda::lp_pool(borrowable_mint).0)
+}
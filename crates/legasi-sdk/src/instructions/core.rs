@@ -0,0 +1,470 @@
+//! legasi-core instructions
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use legasi_core::admin::AdminOp;
+use legasi_core::gad_schedule::ThrottleWindow;
+use legasi_core::gate::GateKind;
+use legasi_core::jupiter_cpi;
+use legasi_core::listing::CollateralParams;
+use legasi_core::market::{GadProceedsMode, MarketParams};
+use legasi_core::state::AssetType;
+use legasi_core::{accounts, instruction};
+
+use super::build;
+use crate::{pda, CORE_PROGRAM_ID};
+
+/// Sync a price feed from its Pyth price account (permissionless)
+pub fn sync_pyth_price(mint: &Pubkey, pyth_price_account: &Pubkey) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::SyncPythPrice {
+            price_feed: pda::price_feed(mint).0,
+            mint: *mint,
+            collateral: pda::collateral(mint).0,
+            pyth_price_account: *pyth_price_account,
+        },
+        instruction::SyncPythPrice {},
+    )
+}
+
+/// Sync a price feed from the median of its configured oracle sources (permissionless)
+/// Pass every source set with `AdminOp::SetOracleSources`
+pub fn sync_oracle_price(
+    mint: &Pubkey,
+    pyth_price_account: Option<Pubkey>,
+    switchboard_feed: Option<Pubkey>,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::SyncOraclePrice {
+            price_feed: pda::price_feed(mint).0,
+            mint: *mint,
+            collateral: pda::collateral(mint).0,
+            pyth_price_account,
+            switchboard_feed,
+        },
+        instruction::SyncOraclePrice {},
+    )
+}
+
+/// Create the protocol state with `admin` as its admin
+pub fn initialize_protocol(admin: &Pubkey, treasury: &Pubkey) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::InitializeProtocol {
+            protocol: pda::protocol().0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeProtocol {
+            treasury: *treasury,
+        },
+    )
+}
+
+/// Register a collateral asset (admin only)
+pub fn register_collateral(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    params: instruction::RegisterCollateral,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::RegisterCollateral {
+            protocol: pda::protocol().0,
+            collateral: pda::collateral(mint).0,
+            mint: *mint,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        params,
+    )
+}
+
+/// Propose listing `mint` as collateral with `params`, posting the listing bond
+/// (permissionless)
+pub fn propose_collateral_listing(
+    proposer: &Pubkey,
+    mint: &Pubkey,
+    params: CollateralParams,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::ProposeCollateralListing {
+            proposal: pda::listing_proposal(mint).0,
+            collateral: pda::collateral(mint).0,
+            mint: *mint,
+            proposer: *proposer,
+            system_program: system_program::ID,
+        },
+        instruction::ProposeCollateralListing { params },
+    )
+}
+
+/// Register the collateral proposed for `mint`, refunding the bond (admin only)
+pub fn approve_collateral_listing(admin: &Pubkey, mint: &Pubkey, proposer: &Pubkey) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::ApproveCollateralListing {
+            protocol: pda::protocol().0,
+            proposal: pda::listing_proposal(mint).0,
+            collateral: pda::collateral(mint).0,
+            proposer: *proposer,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::ApproveCollateralListing {},
+    )
+}
+
+/// Reject the listing proposed for `mint` (admin only); `slash` sends the bond to
+/// `treasury`, the protocol treasury
+pub fn reject_collateral_listing(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    proposer: &Pubkey,
+    treasury: &Pubkey,
+    slash: bool,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::RejectCollateralListing {
+            protocol: pda::protocol().0,
+            proposal: pda::listing_proposal(mint).0,
+            treasury: *treasury,
+            proposer: *proposer,
+            admin: *admin,
+        },
+        instruction::RejectCollateralListing { slash },
+    )
+}
+
+/// Register a borrowable asset (admin only)
+pub fn register_borrowable(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    params: instruction::RegisterBorrowable,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::RegisterBorrowable {
+            protocol: pda::protocol().0,
+            borrowable: pda::borrowable(mint).0,
+            mint: *mint,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        params,
+    )
+}
+
+/// Create the price feed for `mint` (admin only)
+pub fn initialize_price_feed(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    asset_type: AssetType,
+    initial_price_usd: u64,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::InitializePriceFeed {
+            protocol: pda::protocol().0,
+            price_feed: pda::price_feed(mint).0,
+            mint: *mint,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::InitializePriceFeed {
+            asset_type,
+            initial_price_usd,
+        },
+    )
+}
+
+/// Post a failover price for `mint` (admin only, bounded once the feed has an oracle)
+pub fn update_price(admin: &Pubkey, mint: &Pubkey, price_usd: u64) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::UpdatePrice {
+            protocol: pda::protocol().0,
+            price_feed: pda::price_feed(mint).0,
+            mint: *mint,
+            collateral: pda::collateral(mint).0,
+            admin: *admin,
+        },
+        instruction::UpdatePrice { price_usd },
+    )
+}
+
+/// Apply a batch of protocol-level admin ops (admin only)
+/// Ops that touch a config or price feed need it passed as a writable remaining account
+pub fn execute_admin_ops(admin: &Pubkey, ops: Vec<AdminOp>) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::AdminOnly {
+            protocol: pda::protocol().0,
+            admin: *admin,
+        },
+        instruction::ExecuteAdminOps { ops },
+    )
+}
+
+/// Gate `mint`'s deposits and borrows behind an allowlist or a membership token (admin only)
+/// `membership_mint` is ignored for `GateKind::Allowlist`
+pub fn set_market_gate(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    kind: GateKind,
+    membership_mint: &Pubkey,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::SetMarketGate {
+            protocol: pda::protocol().0,
+            market_gate: pda::market_gate(mint).0,
+            mint: *mint,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::SetMarketGate {
+            kind,
+            membership_mint: *membership_mint,
+        },
+    )
+}
+
+/// Remove `mint`'s gate (admin only)
+pub fn remove_market_gate(admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::RemoveMarketGate {
+            protocol: pda::protocol().0,
+            market_gate: pda::market_gate(mint).0,
+            admin: *admin,
+        },
+        instruction::RemoveMarketGate {},
+    )
+}
+
+/// Allowlist `user` on `mint`'s gate (admin only)
+pub fn add_to_allowlist(admin: &Pubkey, mint: &Pubkey, user: &Pubkey) -> Instruction {
+    let market_gate = pda::market_gate(mint).0;
+    build(
+        CORE_PROGRAM_ID,
+        accounts::AddToAllowlist {
+            protocol: pda::protocol().0,
+            market_gate,
+            allowlist_entry: pda::allowlist_entry(&market_gate, user).0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::AddToAllowlist { user: *user },
+    )
+}
+
+/// Remove `user` from `mint`'s allowlist (admin only)
+pub fn remove_from_allowlist(admin: &Pubkey, mint: &Pubkey, user: &Pubkey) -> Instruction {
+    let market_gate = pda::market_gate(mint).0;
+    build(
+        CORE_PROGRAM_ID,
+        accounts::RemoveFromAllowlist {
+            protocol: pda::protocol().0,
+            allowlist_entry: pda::allowlist_entry(&market_gate, user).0,
+            admin: *admin,
+        },
+        instruction::RemoveFromAllowlist {},
+    )
+}
+
+/// Create market `market_id` for a registered collateral and borrowable (admin only)
+pub fn create_market(
+    admin: &Pubkey,
+    market_id: u16,
+    collateral_mint: &Pubkey,
+    borrow_mint: &Pubkey,
+    params: MarketParams,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::CreateMarket {
+            protocol: pda::protocol().0,
+            market: pda::market(market_id).0,
+            collateral: pda::collateral(collateral_mint).0,
+            borrowable: pda::borrowable(borrow_mint).0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::CreateMarket { market_id, params },
+    )
+}
+
+/// Replace market `market_id`'s parameters (admin only)
+pub fn update_market_params(admin: &Pubkey, market_id: u16, params: MarketParams) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::UpdateMarket {
+            protocol: pda::protocol().0,
+            market: pda::market(market_id).0,
+            admin: *admin,
+        },
+        instruction::UpdateMarketParams { params },
+    )
+}
+
+/// Switch market `market_id`, its borrowing, or its collateral on or off (admin only)
+pub fn toggle_market(
+    admin: &Pubkey,
+    market_id: u16,
+    is_active: bool,
+    borrow_enabled: bool,
+    collateral_enabled: bool,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::UpdateMarket {
+            protocol: pda::protocol().0,
+            market: pda::market(market_id).0,
+            admin: *admin,
+        },
+        instruction::ToggleMarket {
+            is_active,
+            borrow_enabled,
+            collateral_enabled,
+        },
+    )
+}
+
+/// Set what GAD does with collateral it liquidates in market `market_id`: hold it,
+/// swap it at once, or sell it over `twap_slices` cranks (admin only)
+pub fn set_market_gad_proceeds(
+    admin: &Pubkey,
+    market_id: u16,
+    mode: GadProceedsMode,
+    twap_slices: u8,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::UpdateMarket {
+            protocol: pda::protocol().0,
+            market: pda::market(market_id).0,
+            admin: *admin,
+        },
+        instruction::SetMarketGadProceeds { mode, twap_slices },
+    )
+}
+
+/// Set the weekly windows throttling GAD and hard liquidations (admin only)
+pub fn set_gad_schedule(
+    admin: &Pubkey,
+    windows: Vec<ThrottleWindow>,
+    hard_threshold_extra_bps: u16,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::SetGadSchedule {
+            protocol: pda::protocol().0,
+            gad_schedule: pda::gad_schedule().0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::SetGadSchedule {
+            windows,
+            hard_threshold_extra_bps,
+        },
+    )
+}
+
+/// Remove the GAD schedule (admin only)
+pub fn remove_gad_schedule(admin: &Pubkey) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::RemoveGadSchedule {
+            protocol: pda::protocol().0,
+            gad_schedule: pda::gad_schedule().0,
+            admin: *admin,
+        },
+        instruction::RemoveGadSchedule {},
+    )
+}
+
+/// Cap the collateral held of `mint` (admin only, native units, 0 = uncapped; wSOL for SOL)
+pub fn set_supply_cap(admin: &Pubkey, mint: &Pubkey, supply_cap: u64) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::SetAssetCaps {
+            protocol: pda::protocol().0,
+            asset_caps: pda::asset_caps(mint).0,
+            mint: *mint,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::SetSupplyCap { supply_cap },
+    )
+}
+
+/// Cap how much of `mint` is lent out at once (admin only, native units, 0 = uncapped)
+pub fn set_borrow_cap(admin: &Pubkey, mint: &Pubkey, borrow_cap: u64) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::SetAssetCaps {
+            protocol: pda::protocol().0,
+            asset_caps: pda::asset_caps(mint).0,
+            mint: *mint,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::SetBorrowCap { borrow_cap },
+    )
+}
+
+/// Create the treasury PDA's token account for `mint` (admin only)
+pub fn init_treasury_vault(admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::InitTreasuryVault {
+            protocol: pda::protocol().0,
+            treasury: pda::treasury().0,
+            vault: pda::treasury_vault(mint).0,
+            mint: *mint,
+            admin: *admin,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitTreasuryVault {},
+    )
+}
+
+/// Sell up to `amount_in` of the treasury's `source_mint` for USDC (permissionless).
+/// Append the Jupiter route accounts (selling from the source vault into the USDC vault)
+pub fn convert_treasury_to_usdc(
+    cranker: &Pubkey,
+    source_mint: &Pubkey,
+    usdc_mint: &Pubkey,
+    amount_in: u64,
+    min_out: u64,
+    jupiter_swap_data: Vec<u8>,
+) -> Instruction {
+    build(
+        CORE_PROGRAM_ID,
+        accounts::ConvertTreasuryToUsdc {
+            treasury: pda::treasury().0,
+            source_mint: *source_mint,
+            source_vault: pda::treasury_vault(source_mint).0,
+            source_price_feed: pda::price_feed(source_mint).0,
+            usdc_borrowable: pda::borrowable(usdc_mint).0,
+            usdc_vault: pda::treasury_vault(usdc_mint).0,
+            jupiter_program: jupiter_cpi::ID,
+            cranker: *cranker,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ConvertTreasuryToUsdc {
+            amount_in,
+            min_out,
+            jupiter_swap_data,
+        },
+    )
+}
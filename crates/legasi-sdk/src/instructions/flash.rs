@@ -0,0 +1,66 @@
+//! legasi-flash instructions
+//!
+//! A flash loan is `flash_borrow` and `flash_repay` in the same transaction,
+//! with the borrower's instructions in between. `flash_borrow` fails unless the
+//! matching `flash_repay` is a later instruction of the transaction.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use legasi_flash::{accounts, instruction};
+
+use super::build;
+use crate::{pda, CORE_PROGRAM_ID, FLASH_PROGRAM_ID};
+
+/// Borrow `amount` of `borrowable_mint`; `slot` must be the current slot
+pub fn flash_borrow(
+    borrower: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    amount: u64,
+    slot: u64,
+) -> Instruction {
+    build(
+        FLASH_PROGRAM_ID,
+        accounts::FlashBorrow {
+            flash_state: pda::flash_state(borrower, slot).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            borrowable: pda::borrowable(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            user_token_account: *user_token_account,
+            borrower: *borrower,
+            instructions: sysvar::instructions::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::FlashBorrow { amount, slot },
+    )
+}
+
+/// Repay the flash loan opened at `slot` (principal + fee)
+pub fn flash_repay(
+    borrower: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    slot: u64,
+) -> Instruction {
+    build(
+        FLASH_PROGRAM_ID,
+        accounts::FlashRepay {
+            flash_state: pda::flash_state(borrower, slot).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            insurance_vault: pda::insurance_vault(borrowable_mint).0,
+            user_token_account: *user_token_account,
+            borrower: *borrower,
+            protocol_writer: pda::protocol_writer(&FLASH_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            token_program: token::ID,
+        },
+        instruction::FlashRepay {},
+    )
+}
@@ -0,0 +1,292 @@
+//! legasi-gad instructions
+//!
+//! Builders that value the position take `borrowed_mints` like the lending ones (see
+//! `lending`), so GAD sees the debt as of now.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use anchor_spl::token;
+use legasi_core::swap_router::SwapRoute;
+use legasi_gad::{accounts, instruction};
+
+use super::build;
+use super::lending::debt_pools;
+use crate::{pda, CORE_PROGRAM_ID, GAD_PROGRAM_ID, LENDING_PROGRAM_ID, LP_PROGRAM_ID};
+
+/// Enable or disable GAD on the owner's position, leaving at least
+/// `min_collateral_floor` lamports of SOL collateral (at most half of it)
+pub fn configure_gad(owner: &Pubkey, enabled: bool, min_collateral_floor: u64) -> Instruction {
+    build(
+        GAD_PROGRAM_ID,
+        accounts::ConfigureGad {
+            position: pda::position(owner).0,
+            owner: *owner,
+            protocol_writer: pda::protocol_writer(&GAD_PROGRAM_ID).0,
+            lending_program: LENDING_PROGRAM_ID,
+        },
+        instruction::ConfigureGad {
+            enabled,
+            _custom_threshold_bps: None,
+            min_collateral_floor,
+        },
+    )
+}
+
+/// Sponsor the owner's position with a `lamports` backstop (owner and sponsor sign)
+pub fn sponsor_position(owner: &Pubkey, sponsor: &Pubkey, lamports: u64) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        GAD_PROGRAM_ID,
+        accounts::SponsorPosition {
+            position,
+            sponsorship: pda::sponsorship(&position).0,
+            sponsor_vault: pda::sponsor_vault(&position).0,
+            owner: *owner,
+            sponsor: *sponsor,
+            system_program: system_program::ID,
+        },
+        instruction::SponsorPosition { amount: lamports },
+    )
+}
+
+/// Add `lamports` to a position's sponsor backstop
+pub fn top_up_backstop(position_owner: &Pubkey, sponsor: &Pubkey, lamports: u64) -> Instruction {
+    let position = pda::position(position_owner).0;
+    build(
+        GAD_PROGRAM_ID,
+        accounts::TopUpBackstop {
+            sponsorship: pda::sponsorship(&position).0,
+            sponsor_vault: pda::sponsor_vault(&position).0,
+            sponsor: *sponsor,
+            system_program: system_program::ID,
+        },
+        instruction::TopUpBackstop { amount: lamports },
+    )
+}
+
+/// End the sponsorship of a debt-free position, returning the backstop
+pub fn end_sponsorship(position_owner: &Pubkey, sponsor: &Pubkey) -> Instruction {
+    let position = pda::position(position_owner).0;
+    build(
+        GAD_PROGRAM_ID,
+        accounts::EndSponsorship {
+            position,
+            sponsorship: pda::sponsorship(&position).0,
+            sponsor_vault: pda::sponsor_vault(&position).0,
+            sponsor: *sponsor,
+            system_program: system_program::ID,
+        },
+        instruction::EndSponsorship {},
+    )
+}
+
+/// Crank GAD on a position (permissionless)
+/// `eur_price_feed` is required once the position holds EURC debt, `cbbtc_price_feed`
+/// once it holds cbBTC. `market_id` is the market covering the position, whose GAD
+/// proceeds preference must be to hold. Only the non-LP shares of the liquidation
+/// split are paid out, so it fails while the split is all LP recovery
+#[allow(clippy::too_many_arguments)]
+pub fn crank_gad(
+    position_owner: &Pubkey,
+    treasury: &Pubkey,
+    sol_price_feed: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    cbbtc_price_feed: Option<Pubkey>,
+    market_id: Option<u16>,
+    borrowed_mints: &[Pubkey],
+    cranker: &Pubkey,
+) -> Instruction {
+    let position = pda::position(position_owner).0;
+    let mut ix = build(
+        GAD_PROGRAM_ID,
+        accounts::CrankGad {
+            position,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&GAD_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lending_program: LENDING_PROGRAM_ID,
+            sol_vault: pda::sol_vault(&position).0,
+            sponsor_vault: pda::sponsor_vault(&position).0,
+            treasury: *treasury,
+            repayment_schedule: pda::repayment_schedule(&position).0,
+            gad_schedule: pda::gad_schedule().0,
+            sol_price_feed: *sol_price_feed,
+            eur_price_feed,
+            cbbtc_price_feed,
+            market: market_id.map(|id| pda::market(id).0),
+            cranker: *cranker,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CrankGad {},
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Sell a slice of `position_owner`'s mSOL collateral through `route`, the collateral's
+/// configured swap route, into `output_vault` (permissionless). Append the route's
+/// accounts after the debt pools. `treasury_lst_account` and `cranker_lst_account` are required once the
+/// liquidation split pays the treasury and cranker; the price feeds are as for `crank_gad`
+#[allow(clippy::too_many_arguments)]
+pub fn crank_gad_lst_with_swap(
+    position_owner: &Pubkey,
+    lst_mint: &Pubkey,
+    route: SwapRoute,
+    output_vault: &Pubkey,
+    output_mint: &Pubkey,
+    sol_price_feed: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    cbbtc_price_feed: Option<Pubkey>,
+    market_id: Option<u16>,
+    borrowed_mints: &[Pubkey],
+    treasury_lst_account: Option<Pubkey>,
+    cranker_lst_account: Option<Pubkey>,
+    cranker: &Pubkey,
+    route_data: Vec<u8>,
+    min_out_amount: u64,
+) -> Instruction {
+    let position = pda::position(position_owner).0;
+    let mut ix = build(
+        GAD_PROGRAM_ID,
+        accounts::CrankGadLstWithSwap {
+            position,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&GAD_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lending_program: LENDING_PROGRAM_ID,
+            collateral_config: pda::collateral(lst_mint).0,
+            sol_vault: pda::sol_vault(&position).0,
+            sponsor_vault: pda::sponsor_vault(&position).0,
+            lst_vault: pda::msol_vault(&position).0,
+            output_vault: *output_vault,
+            treasury_lst_account,
+            cranker_lst_account,
+            output_mint: *output_mint,
+            output_price_feed: pda::price_feed(output_mint).0,
+            repayment_schedule: pda::repayment_schedule(&position).0,
+            gad_schedule: pda::gad_schedule().0,
+            sol_price_feed: *sol_price_feed,
+            eur_price_feed,
+            cbbtc_price_feed,
+            market: market_id.map(|id| pda::market(id).0),
+            swap_program: route.program_id(),
+            cranker: *cranker,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CrankGadLstWithSwap {
+            route_data,
+            min_out_amount,
+        },
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Open a liquidation auction of `position_owner`'s SOL against its `borrowable_mint` debt
+/// `cbbtc_price_feed` is required once the position holds cbBTC
+pub fn start_auction(
+    position_owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    sol_price_feed: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    cbbtc_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+    starter: &Pubkey,
+) -> Instruction {
+    let position = pda::position(position_owner).0;
+    let mut ix = build(
+        GAD_PROGRAM_ID,
+        accounts::StartAuction {
+            position,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            auction: pda::liquidation_auction(&position, borrowable_mint).0,
+            sol_price_feed: *sol_price_feed,
+            eur_price_feed,
+            cbbtc_price_feed,
+            gad_schedule: pda::gad_schedule().0,
+            starter: *starter,
+            system_program: system_program::ID,
+        },
+        instruction::StartAuction {},
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Bid on a liquidation auction: repay up to `amount` of `borrowable_mint` from
+/// `bidder_token_account` for at least `min_lamports_out` of SOL
+#[allow(clippy::too_many_arguments)]
+pub fn bid_auction(
+    position_owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    bidder: &Pubkey,
+    bidder_token_account: &Pubkey,
+    amount: u64,
+    min_lamports_out: u64,
+    sol_price_feed: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+) -> Instruction {
+    let position = pda::position(position_owner).0;
+    let mut ix = build(
+        GAD_PROGRAM_ID,
+        accounts::BidAuction {
+            position,
+            auction: pda::liquidation_auction(&position, borrowable_mint).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            repay_vault: pda::lp_vault(borrowable_mint).0,
+            bidder_token_account: *bidder_token_account,
+            sol_vault: pda::sol_vault(&position).0,
+            sol_price_feed: *sol_price_feed,
+            eur_price_feed,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&GAD_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            lending_program: LENDING_PROGRAM_ID,
+            bidder: *bidder,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::BidAuction {
+            amount,
+            min_lamports_out,
+        },
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Settle a liquidation auction (permissionless), refunding its rent to `starter`
+pub fn settle_auction(
+    position_owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    starter: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+) -> Instruction {
+    let position = pda::position(position_owner).0;
+    let mut ix = build(
+        GAD_PROGRAM_ID,
+        accounts::SettleAuction {
+            position,
+            auction: pda::liquidation_auction(&position, borrowable_mint).0,
+            starter: *starter,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            eur_price_feed,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&GAD_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            lending_program: LENDING_PROGRAM_ID,
+        },
+        instruction::SettleAuction {},
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
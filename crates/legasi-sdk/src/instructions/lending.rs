@@ -0,0 +1,1460 @@
+//! legasi-lending instructions
+//!
+//! Builders that value debt take `eur_price_feed`, the EURC price feed
+//! (`pda::price_feed(&eurc_mint)`). It is required once EURC debt is involved.
+//!
+//! Deposit and borrow builders take `gate_pass` for gated markets (see
+//! `legasi_core::gate`): the owner's `pda::allowlist_entry` or membership token
+//! account. Pass `None` for open markets.
+//!
+//! Builders for instructions that check the whole position without a pool of their
+//! own take `borrowed_mints`, the mint of every asset the position owes. Their
+//! `Borrowable` and LP pool go along so the interest on each is settled first.
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::system_program;
+use anchor_spl::{associated_token, token};
+use legasi_core::constants::WSOL_MINT;
+use legasi_core::jupiter_cpi;
+use legasi_core::market::EModeCategory;
+use legasi_lending::{accounts, instruction, marinade, DeleverageSwap, ProceedsMode};
+
+use super::build;
+use crate::{pda, CORE_PROGRAM_ID, LENDING_PROGRAM_ID, LP_PROGRAM_ID};
+
+fn wsol_mint() -> Pubkey {
+    Pubkey::from_str(WSOL_MINT).unwrap()
+}
+
+/// `Borrowable`, `LpPool` pair per borrowed mint, as remaining accounts
+pub(crate) fn debt_pools(borrowed_mints: &[Pubkey]) -> impl Iterator<Item = AccountMeta> + '_ {
+    borrowed_mints.iter().flat_map(|mint| {
+        [
+            AccountMeta::new_readonly(pda::borrowable(mint).0, false),
+            AccountMeta::new_readonly(pda::lp_pool(mint).0, false),
+        ]
+    })
+}
+
+/// Move a deprecated lending vault balance into the LP pool vault and close it
+pub fn migrate_lending_vault(admin: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::MigrateLendingVault {
+            protocol: pda::protocol().0,
+            lending_vault: pda::lending_vault(mint).0,
+            lp_vault: pda::lp_vault(mint).0,
+            admin: *admin,
+            token_program: token::ID,
+        },
+        instruction::MigrateLendingVault {},
+    )
+}
+
+/// Sweep lamports above rent exemption from lending-owned `targets` into the treasury
+pub fn sweep_excess_lamports(admin: &Pubkey, treasury: &Pubkey, targets: &[Pubkey]) -> Instruction {
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::SweepExcessLamports {
+            protocol: pda::protocol().0,
+            treasury: *treasury,
+            admin: *admin,
+        },
+        instruction::SweepExcessLamports {},
+    );
+    ix.accounts.extend(
+        targets
+            .iter()
+            .map(|target| AccountMeta::new(*target, false)),
+    );
+    ix
+}
+
+/// Create a lending position for `owner`, optionally referred by `referrer`
+pub fn initialize_position(owner: &Pubkey, referrer: Option<&Pubkey>) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::InitializePosition {
+            position: pda::position(owner).0,
+            referrer_account: referrer.map(|referrer| pda::referrer(referrer).0),
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::InitializePosition {},
+    )
+}
+
+/// Register `referrer` so borrowers can open positions under it
+pub fn register_referrer(referrer: &Pubkey) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::RegisterReferrer {
+            referrer_account: pda::referrer(referrer).0,
+            referrer: *referrer,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterReferrer {},
+    )
+}
+
+/// Open the referrer's reward vault for `mint`
+pub fn open_referral_vault(referrer: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::OpenReferralVault {
+            referrer_account: pda::referrer(referrer).0,
+            referral_vault: pda::referral_vault(referrer, mint).0,
+            mint: *mint,
+            referrer: *referrer,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::OpenReferralVault {},
+    )
+}
+
+/// Sweep the referrer's `mint` rewards into `destination`
+pub fn claim_referral_rewards(
+    referrer: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::ClaimReferralRewards {
+            referrer_account: pda::referrer(referrer).0,
+            referral_vault: pda::referral_vault(referrer, mint).0,
+            destination: *destination,
+            referrer: *referrer,
+            token_program: token::ID,
+        },
+        instruction::ClaimReferralRewards {},
+    )
+}
+
+/// Open the points ledger of `owner`'s position
+pub fn open_points_ledger(owner: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::OpenPointsLedger {
+            position,
+            points_ledger: pda::points_ledger(&position).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::OpenPointsLedger {},
+    )
+}
+
+/// Crank the points of `owner`'s position (any signer can send it)
+pub fn accrue_points(owner: &Pubkey, eur_price_feed: Option<Pubkey>) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::AccruePoints {
+            position,
+            points_ledger: pda::points_ledger(&position).0,
+            protocol: pda::protocol().0,
+            sol_price_feed: pda::price_feed(&wsol_mint()).0,
+            eur_price_feed,
+        },
+        instruction::AccruePoints {},
+    )
+}
+
+/// Post the Merkle root of every points ledger for `epoch` (admin)
+pub fn post_points_snapshot(
+    admin: &Pubkey,
+    epoch: u32,
+    merkle_root: [u8; 32],
+    total_points: u64,
+    leaf_count: u32,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::PostPointsSnapshot {
+            protocol: pda::protocol().0,
+            points_snapshot: pda::points_snapshot(epoch).0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::PostPointsSnapshot {
+            epoch,
+            merkle_root,
+            total_points,
+            leaf_count,
+        },
+    )
+}
+
+/// Open the statement ledger of `owner`'s position
+/// `measure_msol_yield` passes the Marinade State so the first period measures mSOL yield
+pub fn open_statement_ledger(
+    owner: &Pubkey,
+    borrowed_mints: &[Pubkey],
+    measure_msol_yield: bool,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::OpenStatementLedger {
+            position,
+            statement_ledger: pda::statement_ledger(&position).0,
+            marinade_state: measure_msol_yield.then_some(marinade::MARINADE_STATE),
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::OpenStatementLedger {},
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Emit the statement of `owner`'s position for the period just ended (any signer can
+/// send it). `eur_price_feed` is required once EURC has been charged
+pub fn crank_position_statement(
+    owner: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+    measure_msol_yield: bool,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::CrankPositionStatement {
+            position,
+            statement_ledger: pda::statement_ledger(&position).0,
+            sol_price_feed: pda::price_feed(&wsol_mint()).0,
+            eur_price_feed,
+            marinade_state: measure_msol_yield.then_some(marinade::MARINADE_STATE),
+        },
+        instruction::CrankPositionStatement {},
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Register `service` as a notification service charging `fee_lamports` per notice
+pub fn register_notify_service(service: &Pubkey, fee_lamports: u64) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::RegisterNotifyService {
+            notify_service: pda::notify_service(service).0,
+            service: *service,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterNotifyService { fee_lamports },
+    )
+}
+
+/// Change `service`'s fee for new subscriptions, or pause it
+pub fn update_notify_service(service: &Pubkey, fee_lamports: u64, active: bool) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::UpdateNotifyService {
+            notify_service: pda::notify_service(service).0,
+            service: *service,
+        },
+        instruction::UpdateNotifyService {
+            fee_lamports,
+            active,
+        },
+    )
+}
+
+/// Subscribe `owner`'s position to `service`'s notices from `alert_ltv_bps`, prepaying
+/// `prepaid_lamports` of fees (the vault keeps a rent-exempt minimum on top)
+pub fn subscribe_notifications(
+    owner: &Pubkey,
+    service: &Pubkey,
+    alert_ltv_bps: u16,
+    prepaid_lamports: u64,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let subscription = pda::notify_subscription(&position, service).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::SubscribeNotifications {
+            position,
+            notify_service: pda::notify_service(service).0,
+            subscription,
+            fee_vault: pda::notify_fee_vault(&subscription).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::SubscribeNotifications {
+            alert_ltv_bps,
+            prepaid_lamports,
+        },
+    )
+}
+
+/// Unsubscribe `owner`'s position from `service`, refunding the unspent fees
+pub fn unsubscribe_notifications(owner: &Pubkey, service: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    let subscription = pda::notify_subscription(&position, service).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::UnsubscribeNotifications {
+            subscription,
+            notify_service: pda::notify_service(service).0,
+            fee_vault: pda::notify_fee_vault(&subscription).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::UnsubscribeNotifications {},
+    )
+}
+
+/// Notify `service`'s subscriber `owner` that their position reached its alert LTV
+/// (any signer can send it)
+pub fn notify_subscriber(
+    owner: &Pubkey,
+    service: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let subscription = pda::notify_subscription(&position, service).0;
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::NotifySubscriberCrank {
+            position,
+            subscription,
+            notify_service: pda::notify_service(service).0,
+            fee_vault: pda::notify_fee_vault(&subscription).0,
+            service: *service,
+            sol_price_feed: pda::price_feed(&wsol_mint()).0,
+            eur_price_feed,
+            system_program: system_program::ID,
+        },
+        instruction::NotifySubscriber {},
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Snapshot `owner`'s voting power for the current `epoch` (`legasi_lending::voting_epoch`).
+/// `with_position` counts the net deposits of their position, `lp_token_account` their
+/// bUSDC in the `usdc_mint` pool
+pub fn snapshot_voting_power(
+    owner: &Pubkey,
+    usdc_mint: &Pubkey,
+    epoch: u32,
+    with_position: bool,
+    lp_token_account: Option<Pubkey>,
+    eur_price_feed: Option<Pubkey>,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::SnapshotVotingPower {
+            voting_power: pda::voting_power(owner, epoch).0,
+            position: with_position.then(|| pda::position(owner).0),
+            borrowable_config: pda::borrowable(usdc_mint).0,
+            lp_pool: pda::lp_pool(usdc_mint).0,
+            lp_token_account,
+            sol_price_feed: pda::price_feed(&wsol_mint()).0,
+            eur_price_feed,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::SnapshotVotingPower { epoch },
+    )
+}
+
+/// Burn `shares_amount` LP shares of the `borrowable_mint` pool into an off-ramp escrow
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_to_offramp(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_lp_token_account: &Pubkey,
+    request_id: u64,
+    shares_amount: u64,
+    destination_iban: &str,
+    destination_name: &str,
+    allowlisted: bool,
+) -> Instruction {
+    let offramp_request = pda::offramp_request(owner, request_id).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::WithdrawToOfframp {
+            offramp_request,
+            offramp_escrow: pda::offramp_escrow(&offramp_request).0,
+            borrowable_mint: *borrowable_mint,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            lp_vault: pda::lp_vault(borrowable_mint).0,
+            user_lp_token_account: *user_lp_token_account,
+            lp_allowlist_entry: super::lp::allowlist_entry(borrowable_mint, owner, allowlisted),
+            owner: *owner,
+            lp_program: LP_PROGRAM_ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::WithdrawToOfframp {
+            _request_id: request_id,
+            shares_amount,
+            destination_iban: destination_iban.to_string(),
+            destination_name: destination_name.to_string(),
+        },
+    )
+}
+
+/// Release `owner`'s off-ramp escrow to `destination` (admin); a refund must go to the owner
+pub fn settle_offramp_escrow(
+    admin: &Pubkey,
+    owner: &Pubkey,
+    request_id: u64,
+    destination: &Pubkey,
+    completed: bool,
+) -> Instruction {
+    let offramp_request = pda::offramp_request(owner, request_id).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::SettleOfframpEscrow {
+            protocol: pda::protocol().0,
+            offramp_request,
+            offramp_escrow: pda::offramp_escrow(&offramp_request).0,
+            destination: *destination,
+            admin: *admin,
+            token_program: token::ID,
+        },
+        instruction::SettleOfframpEscrow {
+            request_id,
+            completed,
+        },
+    )
+}
+
+/// Deposit SOL collateral (lamports)
+pub fn deposit_sol(owner: &Pubkey, amount: u64, gate_pass: Option<Pubkey>) -> Instruction {
+    let position = pda::position(owner).0;
+    let sol_mint = wsol_mint();
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::DepositSol {
+            position,
+            sol_vault: pda::sol_vault(&position).0,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            sol_mint,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            market_gate: pda::market_gate(&sol_mint).0,
+            gate_pass,
+            asset_caps: pda::asset_caps(&sol_mint).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::DepositSol { amount },
+    )
+}
+
+/// Withdraw SOL collateral (lamports), or with `as_wsol` wrapped into the owner's wSOL
+/// associated token account, created if needed
+/// `destination` receives the lamports instead of the owner (not with `as_wsol`)
+/// `cbbtc_price_feed` is required once the position holds cbBTC
+/// `market_id` is the market pricing the position, for its eMode LTV
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_sol(
+    owner: &Pubkey,
+    amount: u64,
+    as_wsol: bool,
+    destination: Option<Pubkey>,
+    eur_price_feed: Option<Pubkey>,
+    cbbtc_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+    market_id: Option<u16>,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let sol_mint = wsol_mint();
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::WithdrawSol {
+            position,
+            sol_vault: pda::sol_vault(&position).0,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            eur_price_feed,
+            cbbtc_price_feed,
+            market: market_id.map(|id| pda::market(id).0),
+            sol_mint,
+            credit_line: pda::credit_line(&position).0,
+            withdrawal_allowlist: pda::withdrawal_allowlist(&position).0,
+            destination,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            wsol_account: as_wsol
+                .then(|| associated_token::get_associated_token_address(owner, &sol_mint)),
+            owner: *owner,
+            token_program: as_wsol.then_some(token::ID),
+            associated_token_program: as_wsol.then_some(associated_token::ID),
+            system_program: system_program::ID,
+        },
+        instruction::WithdrawSol { amount, as_wsol },
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Borrow `amount` of `borrowable_mint` into `user_token_account`
+/// `market_id` is the market pricing the position, for its eMode LTV
+/// `cbbtc_price_feed` counts cbBTC collateral at its own price (left out, it counts as zero)
+/// `credit_msol_yield` passes the Marinade State so unrealized mSOL yield counts
+#[allow(clippy::too_many_arguments)]
+pub fn borrow(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+    cbbtc_price_feed: Option<Pubkey>,
+    reference: Option<[u8; 32]>,
+    gate_pass: Option<Pubkey>,
+    market_id: Option<u16>,
+    credit_msol_yield: bool,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        borrow_accounts(
+            owner,
+            borrowable_mint,
+            user_token_account,
+            eur_price_feed,
+            cbbtc_price_feed,
+            gate_pass,
+            market_id,
+            credit_msol_yield,
+        ),
+        instruction::Borrow { amount, reference },
+    )
+}
+
+/// `borrow` at a fixed rate for `term_secs`, failing if the quoted rate is above
+/// `max_rate_bps`. Other arguments as for `borrow`
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_fixed_term(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    amount: u64,
+    term_secs: i64,
+    max_rate_bps: u16,
+    eur_price_feed: Option<Pubkey>,
+    cbbtc_price_feed: Option<Pubkey>,
+    gate_pass: Option<Pubkey>,
+    market_id: Option<u16>,
+    credit_msol_yield: bool,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        borrow_accounts(
+            owner,
+            borrowable_mint,
+            user_token_account,
+            eur_price_feed,
+            cbbtc_price_feed,
+            gate_pass,
+            market_id,
+            credit_msol_yield,
+        ),
+        instruction::BorrowFixedTerm {
+            amount,
+            term_secs,
+            max_rate_bps,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn borrow_accounts(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    cbbtc_price_feed: Option<Pubkey>,
+    gate_pass: Option<Pubkey>,
+    market_id: Option<u16>,
+    credit_msol_yield: bool,
+) -> accounts::Borrow {
+    let sol_mint = wsol_mint();
+    accounts::Borrow {
+        position: pda::position(owner).0,
+        protocol: pda::protocol().0,
+        protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+        core_program: CORE_PROGRAM_ID,
+        borrowable_config: pda::borrowable(borrowable_mint).0,
+        lp_pool: pda::lp_pool(borrowable_mint).0,
+        lp_program: LP_PROGRAM_ID,
+        borrow_vault: pda::lp_vault(borrowable_mint).0,
+        user_token_account: *user_token_account,
+        sol_price_feed: pda::price_feed(&sol_mint).0,
+        eur_price_feed,
+        cbbtc_price_feed,
+        market: market_id.map(|id| pda::market(id).0),
+        marinade_state: credit_msol_yield.then_some(marinade::MARINADE_STATE),
+        sol_mint,
+        market_gate: pda::market_gate(borrowable_mint).0,
+        gate_pass,
+        asset_caps: pda::asset_caps(borrowable_mint).0,
+        owner: *owner,
+        token_program: token::ID,
+    }
+}
+
+/// Repay `amount` of `borrowable_mint` from `user_token_account`
+/// `referrer` is the position's referrer, if it was opened with one
+pub fn repay(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+    referrer: Option<&Pubkey>,
+    reference: Option<[u8; 32]>,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        repay_accounts(
+            owner,
+            borrowable_mint,
+            user_token_account,
+            eur_price_feed,
+            referrer,
+            false,
+        ),
+        instruction::Repay { amount, reference },
+    )
+}
+
+/// Like `repay`, counting toward the owner's committed repayment schedule
+pub fn repay_on_schedule(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+    referrer: Option<&Pubkey>,
+    reference: Option<[u8; 32]>,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        repay_accounts(
+            owner,
+            borrowable_mint,
+            user_token_account,
+            eur_price_feed,
+            referrer,
+            true,
+        ),
+        instruction::Repay { amount, reference },
+    )
+}
+
+/// Repay `borrowable_mint` from `user_token_account` down to `target_ltv_bps`
+/// With `swap`, SOL collateral is sold into `user_token_account` first; append the
+/// route's accounts to the instruction
+pub fn deleverage_to_ltv(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    target_ltv_bps: u16,
+    eur_price_feed: Option<Pubkey>,
+    referrer: Option<&Pubkey>,
+    swap: Option<DeleverageSwap>,
+) -> Instruction {
+    let sol_mint = wsol_mint();
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::DeleverageToLtv {
+            repay: repay_accounts(
+                owner,
+                borrowable_mint,
+                user_token_account,
+                eur_price_feed,
+                referrer,
+                false,
+            ),
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            sol_mint,
+            sol_vault: pda::sol_vault(&pda::position(owner).0).0,
+            jupiter_program: swap.is_some().then_some(jupiter_cpi::ID),
+        },
+        instruction::DeleverageToLtv {
+            target_ltv_bps,
+            swap,
+        },
+    )
+}
+
+/// Liquidate `owner`'s position past the hard threshold, repaying up to `amount` of
+/// `borrowable_mint` from `liquidator_token_account` for SOL collateral plus the bonus
+pub fn liquidate_position(
+    liquidator: &Pubkey,
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    liquidator_token_account: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        liquidation_accounts(
+            liquidator,
+            owner,
+            borrowable_mint,
+            liquidator_token_account,
+            eur_price_feed,
+        ),
+        instruction::LiquidatePosition { amount },
+    )
+}
+
+/// `liquidate_position` paid for by selling the seized SOL through `swap`, whose output
+/// goes to `liquidator_token_account`. Append the Jupiter route accounts
+pub fn flash_liquidate(
+    liquidator: &Pubkey,
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    liquidator_token_account: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+    swap: DeleverageSwap,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::FlashLiquidate {
+            liquidation: liquidation_accounts(
+                liquidator,
+                owner,
+                borrowable_mint,
+                liquidator_token_account,
+                eur_price_feed,
+            ),
+            jupiter_program: jupiter_cpi::ID,
+        },
+        instruction::FlashLiquidate { amount, swap },
+    )
+}
+
+/// Write off `owner`'s `borrowable_mint` debt once the position's collateral is dust
+/// `cbbtc_price_feed` is required while the position holds cbBTC
+pub fn write_off_bad_debt(
+    cranker: &Pubkey,
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    cbbtc_price_feed: Option<Pubkey>,
+    eur_price_feed: Option<Pubkey>,
+) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::WriteOffBadDebt {
+            position: pda::position(owner).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            sol_price_feed: pda::price_feed(&wsol_mint()).0,
+            cbbtc_price_feed,
+            eur_price_feed,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            cranker: *cranker,
+        },
+        instruction::WriteOffBadDebt {},
+    )
+}
+
+fn liquidation_accounts(
+    liquidator: &Pubkey,
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    liquidator_token_account: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+) -> accounts::LiquidatePosition {
+    let sol_mint = wsol_mint();
+    let position = pda::position(owner).0;
+    accounts::LiquidatePosition {
+        position,
+        sol_vault: pda::sol_vault(&position).0,
+        sol_collateral: pda::collateral(&sol_mint).0,
+        borrowable_config: pda::borrowable(borrowable_mint).0,
+        lp_pool: pda::lp_pool(borrowable_mint).0,
+        repay_vault: pda::lp_vault(borrowable_mint).0,
+        liquidator_token_account: *liquidator_token_account,
+        sol_price_feed: pda::price_feed(&sol_mint).0,
+        sol_mint,
+        eur_price_feed,
+        gad_schedule: pda::gad_schedule().0,
+        protocol: pda::protocol().0,
+        protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+        core_program: CORE_PROGRAM_ID,
+        lp_program: LP_PROGRAM_ID,
+        liquidator: *liquidator,
+        token_program: token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+/// Convert up to `amount` of `from_mint` debt into `to_mint` debt at the EUR/USD price
+pub fn convert_debt(
+    owner: &Pubkey,
+    from_mint: &Pubkey,
+    to_mint: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+) -> Instruction {
+    let sol_mint = wsol_mint();
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::ConvertDebt {
+            position: pda::position(owner).0,
+            from_borrowable: pda::borrowable(from_mint).0,
+            to_borrowable: pda::borrowable(to_mint).0,
+            from_lp_pool: pda::lp_pool(from_mint).0,
+            to_lp_pool: pda::lp_pool(to_mint).0,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            sol_mint,
+            eur_price_feed,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            owner: *owner,
+        },
+        instruction::ConvertDebt { amount },
+    )
+}
+
+/// Arm (or update) the position's auto-deleverage order
+pub fn set_auto_deleverage(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    trigger_ltv_bps: u16,
+    target_ltv_bps: u16,
+    keeper_tip_bps: u16,
+    proceeds: ProceedsMode,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let order = pda::auto_deleverage_order(&position).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::SetAutoDeleverage {
+            position,
+            order,
+            proceeds_account: pda::auto_deleverage_proceeds(&order).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            borrowable_mint: *borrowable_mint,
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::SetAutoDeleverage {
+            trigger_ltv_bps,
+            target_ltv_bps,
+            keeper_tip_bps,
+            proceeds,
+        },
+    )
+}
+
+pub fn cancel_auto_deleverage(owner: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    let order = pda::auto_deleverage_order(&position).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::CancelAutoDeleverage {
+            position,
+            order,
+            proceeds_account: pda::auto_deleverage_proceeds(&order).0,
+            owner: *owner,
+            token_program: token::ID,
+        },
+        instruction::CancelAutoDeleverage {},
+    )
+}
+
+/// Execute a triggered auto-deleverage order. Append the Jupiter route accounts
+/// (selling from the position's `sol_vault` into the order's proceeds account)
+pub fn crank_auto_deleverage(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_token_account: &Pubkey,
+    keeper: &Pubkey,
+    keeper_token_account: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    swap: DeleverageSwap,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let order = pda::auto_deleverage_order(&position).0;
+    let sol_mint = wsol_mint();
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::CrankAutoDeleverage {
+            position,
+            order,
+            proceeds_account: pda::auto_deleverage_proceeds(&order).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            repay_vault: pda::lp_vault(borrowable_mint).0,
+            owner_token_account: *owner_token_account,
+            keeper_token_account: *keeper_token_account,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            eur_price_feed,
+            sol_mint,
+            sol_vault: pda::sol_vault(&position).0,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            jupiter_program: jupiter_cpi::ID,
+            keeper: *keeper,
+            token_program: token::ID,
+        },
+        instruction::CrankAutoDeleverage { swap },
+    )
+}
+
+fn repay_accounts(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    referrer: Option<&Pubkey>,
+    on_schedule: bool,
+) -> accounts::Repay {
+    let position = pda::position(owner).0;
+    accounts::Repay {
+        position,
+        borrowable_config: pda::borrowable(borrowable_mint).0,
+        lp_pool: pda::lp_pool(borrowable_mint).0,
+        repay_vault: pda::lp_vault(borrowable_mint).0,
+        user_token_account: *user_token_account,
+        eur_price_feed,
+        protocol: pda::protocol().0,
+        protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+        core_program: CORE_PROGRAM_ID,
+        lp_program: LP_PROGRAM_ID,
+        repayment_schedule: on_schedule.then(|| pda::repayment_schedule(&position).0),
+        referral_vault: pda::referral_vault(
+            &referrer.copied().unwrap_or_default(),
+            borrowable_mint,
+        )
+        .0,
+        owner: *owner,
+        token_program: token::ID,
+    }
+}
+
+/// Commit the owner's position to repaying `amount_per_period` every week
+pub fn commit_repayment_schedule(
+    owner: &Pubkey,
+    amount_per_period: u64,
+    borrowed_mints: &[Pubkey],
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::CommitRepaymentSchedule {
+            position,
+            repayment_schedule: pda::repayment_schedule(&position).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::CommitRepaymentSchedule { amount_per_period },
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Close the owner's repayment schedule
+pub fn cancel_repayment_schedule(owner: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::CancelRepaymentSchedule {
+            position,
+            repayment_schedule: pda::repayment_schedule(&position).0,
+            owner: *owner,
+        },
+        instruction::CancelRepaymentSchedule {},
+    )
+}
+
+/// Cap the owner's total debt at `max_debt_usd` (6 decimals, 0 = no cap)
+pub fn set_debt_cap(owner: &Pubkey, max_debt_usd: u64) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::SetDebtCap {
+            position: pda::position(owner).0,
+            owner: *owner,
+        },
+        instruction::SetDebtCap { max_debt_usd },
+    )
+}
+
+/// Set the wallets the owner's collateral may be withdrawn to (empty = any). Applies at
+/// once while no list is in force, otherwise proposes the change
+pub fn set_withdrawal_allowlist(owner: &Pubkey, destinations: Vec<Pubkey>) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::SetWithdrawalAllowlist {
+            position,
+            withdrawal_allowlist: pda::withdrawal_allowlist(&position).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::SetWithdrawalAllowlist { destinations },
+    )
+}
+
+fn update_withdrawal_allowlist_accounts(owner: &Pubkey) -> accounts::UpdateWithdrawalAllowlist {
+    let position = pda::position(owner).0;
+    accounts::UpdateWithdrawalAllowlist {
+        position,
+        withdrawal_allowlist: pda::withdrawal_allowlist(&position).0,
+        owner: *owner,
+    }
+}
+
+/// Apply the owner's proposed allowlist once its delay has passed
+pub fn apply_withdrawal_allowlist(owner: &Pubkey) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        update_withdrawal_allowlist_accounts(owner),
+        instruction::ApplyWithdrawalAllowlist {},
+    )
+}
+
+/// Drop the owner's proposed allowlist change
+pub fn cancel_withdrawal_allowlist_change(owner: &Pubkey) -> Instruction {
+    build(
+        LENDING_PROGRAM_ID,
+        update_withdrawal_allowlist_accounts(owner),
+        instruction::CancelWithdrawalAllowlistChange {},
+    )
+}
+
+/// Name `beneficiary` to inherit the owner's position after `inactivity_period`
+/// seconds without a check-in (also counts as one)
+pub fn designate_beneficiary(
+    owner: &Pubkey,
+    beneficiary: &Pubkey,
+    inactivity_period: i64,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::DesignateBeneficiary {
+            position,
+            succession: pda::succession(&position).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::DesignateBeneficiary {
+            beneficiary: *beneficiary,
+            inactivity_period,
+        },
+    )
+}
+
+/// Check in as the owner, cancelling any open succession claim
+pub fn succession_check_in(owner: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::UpdateSuccession {
+            position,
+            succession: pda::succession(&position).0,
+            owner: *owner,
+        },
+        instruction::SuccessionCheckIn {},
+    )
+}
+
+/// Remove the owner's beneficiary
+pub fn revoke_beneficiary(owner: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::RevokeBeneficiary {
+            position,
+            succession: pda::succession(&position).0,
+            owner: *owner,
+        },
+        instruction::RevokeBeneficiary {},
+    )
+}
+
+/// Open `beneficiary`'s claim on `owner`'s position
+pub fn start_succession_claim(owner: &Pubkey, beneficiary: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::StartSuccessionClaim {
+            position,
+            succession: pda::succession(&position).0,
+            beneficiary: *beneficiary,
+        },
+        instruction::StartSuccessionClaim {},
+    )
+}
+
+/// Move `owner`'s position to `beneficiary` after the challenge window
+/// `with_msol` passes the mSOL vaults, required while the position holds mSOL
+pub fn complete_succession(owner: &Pubkey, beneficiary: &Pubkey, with_msol: bool) -> Instruction {
+    let position = pda::position(owner).0;
+    let new_position = pda::position(beneficiary).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::CompleteSuccession {
+            position,
+            succession: pda::succession(&position).0,
+            new_position,
+            sol_vault: pda::sol_vault(&position).0,
+            new_sol_vault: pda::sol_vault(&new_position).0,
+            msol_vault: with_msol.then(|| pda::msol_vault(&position).0),
+            new_msol_vault: with_msol.then(|| pda::msol_vault(&new_position).0),
+            msol_mint: with_msol.then_some(marinade::MSOL_MINT),
+            beneficiary: *beneficiary,
+            token_program: with_msol.then_some(token::ID),
+            system_program: system_program::ID,
+        },
+        instruction::CompleteSuccession {},
+    )
+}
+
+/// Set the owner's eMode category. While the position has debt, pass the market
+/// covering it, and the borrowed mints so their interest is settled first
+pub fn set_emode(
+    owner: &Pubkey,
+    category: EModeCategory,
+    market_id: Option<u16>,
+    eur_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+) -> Instruction {
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::SetEMode {
+            position: pda::position(owner).0,
+            market: market_id.map(|id| pda::market(id).0),
+            sol_price_feed: pda::price_feed(&wsol_mint()).0,
+            eur_price_feed,
+            owner: *owner,
+        },
+        instruction::SetEmode { category },
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Open a credit line of `limit` in `borrowable_mint`'s asset on the owner's position
+pub fn open_credit_line(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    limit: u64,
+    eur_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let sol_mint = wsol_mint();
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::OpenCreditLine {
+            position,
+            credit_line: pda::credit_line(&position).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            eur_price_feed,
+            sol_mint,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::OpenCreditLine { limit },
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Draw `amount` on the owner's credit line into `agent_token_account`
+pub fn draw_credit_line(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    agent_token_account: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+    gate_pass: Option<Pubkey>,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let sol_mint = wsol_mint();
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::DrawCreditLine {
+            position,
+            credit_line: pda::credit_line(&position).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            borrow_vault: pda::lp_vault(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            agent_token_account: *agent_token_account,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            eur_price_feed,
+            sol_mint,
+            market_gate: pda::market_gate(borrowable_mint).0,
+            gate_pass,
+            agent: *owner,
+            token_program: token::ID,
+        },
+        instruction::DrawCreditLine { amount },
+    )
+}
+
+/// Book the standby fee on a credit line's unused limit (permissionless)
+pub fn accrue_standby_fee(owner: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::AccrueStandbyFee {
+            position,
+            credit_line: pda::credit_line(&position).0,
+        },
+        instruction::AccrueStandbyFee {},
+    )
+}
+
+/// Close the owner's credit line
+pub fn close_credit_line(owner: &Pubkey) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::CloseCreditLine {
+            position,
+            credit_line: pda::credit_line(&position).0,
+            owner: *owner,
+        },
+        instruction::CloseCreditLine {},
+    )
+}
+
+/// Commit `amount` of `borrowable_mint` to `beneficiary`, claimable until `expires_at`
+#[allow(clippy::too_many_arguments)]
+pub fn issue_letter_of_credit(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    letter_id: u64,
+    beneficiary: &Pubkey,
+    amount: u64,
+    expires_at: i64,
+    eur_price_feed: Option<Pubkey>,
+    borrowed_mints: &[Pubkey],
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let sol_mint = wsol_mint();
+    let mut ix = build(
+        LENDING_PROGRAM_ID,
+        accounts::IssueLetterOfCredit {
+            position,
+            letter_of_credit: pda::letter_of_credit(&position, letter_id).0,
+            beneficiary: *beneficiary,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            eur_price_feed,
+            sol_mint,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::IssueLetterOfCredit {
+            letter_id,
+            amount,
+            expires_at,
+        },
+    );
+    ix.accounts.extend(debt_pools(borrowed_mints));
+    ix
+}
+
+/// Claim `owner`'s letter of credit into `beneficiary_token_account` (beneficiary)
+pub fn claim_letter_of_credit(
+    beneficiary: &Pubkey,
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    letter_id: u64,
+    beneficiary_token_account: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+    gate_pass: Option<Pubkey>,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let sol_mint = wsol_mint();
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::ClaimLetterOfCredit {
+            position,
+            letter_of_credit: pda::letter_of_credit(&position, letter_id).0,
+            owner: *owner,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            borrow_vault: pda::lp_vault(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            beneficiary_token_account: *beneficiary_token_account,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            eur_price_feed,
+            sol_mint,
+            market_gate: pda::market_gate(borrowable_mint).0,
+            gate_pass,
+            beneficiary: *beneficiary,
+            token_program: token::ID,
+        },
+        instruction::ClaimLetterOfCredit {},
+    )
+}
+
+/// Release `owner`'s letter of credit (beneficiary anytime, owner after expiry)
+pub fn release_letter_of_credit(authority: &Pubkey, owner: &Pubkey, letter_id: u64) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::ReleaseLetterOfCredit {
+            position,
+            letter_of_credit: pda::letter_of_credit(&position, letter_id).0,
+            owner: *owner,
+            authority: *authority,
+        },
+        instruction::ReleaseLetterOfCredit {},
+    )
+}
+
+/// Let `owner`'s position borrow as an agent, up to `daily_borrow_limit` USD (6 decimals)
+/// a day across pools
+pub fn configure_agent(
+    owner: &Pubkey,
+    daily_borrow_limit: u64,
+    auto_repay_enabled: bool,
+    x402_enabled: bool,
+    alert_threshold_bps: u16,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::ConfigureAgent {
+            position,
+            agent_config: pda::agent_config(&position).0,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::ConfigureAgent {
+            daily_borrow_limit,
+            auto_repay_enabled,
+            x402_enabled,
+            alert_threshold_bps,
+        },
+    )
+}
+
+/// Agent borrow of `amount` of `borrowable_mint` into `agent_token_account`
+pub fn agent_borrow(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    agent_token_account: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+    gate_pass: Option<Pubkey>,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    let sol_mint = wsol_mint();
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::AgentBorrow {
+            position,
+            agent_config: pda::agent_config(&position).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            borrow_vault: pda::lp_vault(borrowable_mint).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            agent_token_account: *agent_token_account,
+            sol_price_feed: pda::price_feed(&sol_mint).0,
+            eur_price_feed,
+            sol_mint,
+            market_gate: pda::market_gate(borrowable_mint).0,
+            gate_pass,
+            asset_caps: pda::asset_caps(borrowable_mint).0,
+            agent: *owner,
+            token_program: token::ID,
+        },
+        instruction::AgentBorrow { amount },
+    )
+}
+
+/// Agent repayment of up to `amount` of its `borrowable_mint` debt
+pub fn agent_auto_repay(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    agent_token_account: &Pubkey,
+    amount: u64,
+    eur_price_feed: Option<Pubkey>,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::AgentAutoRepay {
+            position,
+            agent_config: pda::agent_config(&position).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            borrow_vault: pda::lp_vault(borrowable_mint).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            agent_token_account: *agent_token_account,
+            eur_price_feed,
+            agent: *owner,
+            token_program: token::ID,
+        },
+        instruction::AgentAutoRepay { amount },
+    )
+}
+
+/// Sweep delegated funds from an agent's token account into repayment (permissionless)
+pub fn crank_auto_repay(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    agent_token_account: &Pubkey,
+    cranker: &Pubkey,
+    eur_price_feed: Option<Pubkey>,
+) -> Instruction {
+    let position = pda::position(owner).0;
+    build(
+        LENDING_PROGRAM_ID,
+        accounts::CrankAutoRepay {
+            position,
+            agent_config: pda::agent_config(&position).0,
+            borrowable_config: pda::borrowable(borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            borrow_vault: pda::lp_vault(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LENDING_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            lp_program: LP_PROGRAM_ID,
+            agent_token_account: *agent_token_account,
+            eur_price_feed,
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::CrankAutoRepay {},
+    )
+}
@@ -0,0 +1,801 @@
+//! legasi-lp instructions
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::system_program;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token;
+use legasi_lp::{accounts, instruction};
+
+use super::build;
+use crate::{pda, CORE_PROGRAM_ID, LP_PROGRAM_ID};
+
+/// Create the LP pool for `borrowable_mint` (step 1); a `permissioned` pool only
+/// serves LPs added with `add_lp_to_allowlist`
+pub fn initialize_pool(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    permissioned: bool,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::InitializePool {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            borrowable_mint: *borrowable_mint,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::InitializePool { permissioned },
+    )
+}
+
+/// Create the LP token mint, vault, and token metadata (step 2)
+pub fn initialize_pool_accounts(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let lp_token_mint = pda::lp_token_mint(borrowable_mint).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::InitializePoolAccounts {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            lp_token_mint,
+            vault: pda::lp_vault(borrowable_mint).0,
+            borrowable_mint: *borrowable_mint,
+            metadata: pda::token_metadata(&lp_token_mint).0,
+            admin: *admin,
+            token_program: token::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::InitializePoolAccounts { name, symbol, uri },
+    )
+}
+
+/// Deposit `amount` of `borrowable_mint`, receive LP tokens
+/// `allowlisted` passes the depositor's allowlist entry, required by permissioned pools
+pub fn deposit(
+    depositor: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    user_lp_token_account: &Pubkey,
+    amount: u64,
+    allowlisted: bool,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::LpDeposit {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            user_token_account: *user_token_account,
+            user_lp_token_account: *user_lp_token_account,
+            depositor: *depositor,
+            protocol: pda::protocol().0,
+            allowlist_entry: allowlist_entry(borrowable_mint, depositor, allowlisted),
+            token_program: token::ID,
+        },
+        instruction::Deposit { amount },
+    )
+}
+
+/// Burn `shares_amount` LP tokens, receive the underlying
+pub fn withdraw(
+    withdrawer: &Pubkey,
+    borrowable_mint: &Pubkey,
+    user_token_account: &Pubkey,
+    user_lp_token_account: &Pubkey,
+    shares_amount: u64,
+    allowlisted: bool,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::LpWithdraw {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            user_token_account: *user_token_account,
+            user_lp_token_account: *user_lp_token_account,
+            withdrawer: *withdrawer,
+            allowlist_entry: allowlist_entry(borrowable_mint, withdrawer, allowlisted),
+            token_program: token::ID,
+        },
+        instruction::Withdraw { shares_amount },
+    )
+}
+
+/// Set the pool outflow rate limit (admin only, 0 bps disables it)
+pub fn set_outflow_limit(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    max_outflow_bps: u16,
+    window_slots: u64,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::SetOutflowLimit {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+        },
+        instruction::SetOutflowLimit {
+            max_outflow_bps,
+            window_slots,
+        },
+    )
+}
+
+/// Set the pool's emergency utilization cap (admin only, 0 disables it)
+pub fn set_max_utilization(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    max_utilization_bps: u16,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::SetOutflowLimit {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+        },
+        instruction::SetMaxUtilization {
+            max_utilization_bps,
+        },
+    )
+}
+
+/// Turn the pool's protocol fee on repaid interest on or off (admin only)
+pub fn set_protocol_fee_switch(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::SetOutflowLimit {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+        },
+        instruction::SetProtocolFeeSwitch { enabled },
+    )
+}
+
+/// Set the cut of repaid interest diverted to the pool's reserves (admin only)
+pub fn set_reserve_factor(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    reserve_factor_bps: u16,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::SetOutflowLimit {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+        },
+        instruction::SetReserveFactor { reserve_factor_bps },
+    )
+}
+
+/// Create the pool's reserve vault (admin only)
+pub fn init_reserve_vault(admin: &Pubkey, borrowable_mint: &Pubkey) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::InitReserveVault {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            reserve_vault: pda::reserve_vault(borrowable_mint).0,
+            borrowable_mint: *borrowable_mint,
+            protocol: pda::protocol().0,
+            admin: *admin,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitReserveVault {},
+    )
+}
+
+/// Move the pool's pending reserves into its reserve vault; any `cranker` can submit it
+pub fn sweep_reserves(borrowable_mint: &Pubkey, cranker: &Pubkey) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::SweepReserves {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            reserve_vault: pda::reserve_vault(borrowable_mint).0,
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::SweepReserves {},
+    )
+}
+
+/// Release `amount` of the reserves back into the pool (admin only)
+pub fn release_reserves(admin: &Pubkey, borrowable_mint: &Pubkey, amount: u64) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::ReleaseReserves {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            reserve_vault: pda::reserve_vault(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+            token_program: token::ID,
+        },
+        instruction::ReleaseReserves { amount },
+    )
+}
+
+/// Create the pool's insurance vault (admin only)
+pub fn init_insurance_vault(admin: &Pubkey, borrowable_mint: &Pubkey) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::InitInsuranceVault {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            insurance_vault: pda::insurance_vault(borrowable_mint).0,
+            borrowable_mint: *borrowable_mint,
+            protocol: pda::protocol().0,
+            admin: *admin,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitInsuranceVault {},
+    )
+}
+
+/// Move the pool's pending insurance money into its insurance vault; any `cranker`
+/// can submit it
+pub fn sweep_insurance(borrowable_mint: &Pubkey, cranker: &Pubkey) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::SweepInsurance {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            insurance_vault: pda::insurance_vault(borrowable_mint).0,
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::SweepInsurance {},
+    )
+}
+
+/// Pay up to `amount` of the pool's bad debt back to its LPs from the insurance vault;
+/// any `cranker` can submit it
+pub fn cover_bad_debt(borrowable_mint: &Pubkey, cranker: &Pubkey, amount: u64) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::CoverBadDebt {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            insurance_vault: pda::insurance_vault(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LP_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::CoverBadDebt { amount },
+    )
+}
+
+/// Pay the pool's owed protocol fees to `treasury_token_account` (owned by the
+/// protocol treasury); any `cranker` can submit it
+pub fn claim_protocol_fees(
+    borrowable_mint: &Pubkey,
+    treasury_token_account: &Pubkey,
+    cranker: &Pubkey,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::ClaimProtocolFees {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            treasury_token_account: *treasury_token_account,
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::ClaimProtocolFees {},
+    )
+}
+
+/// Deposit `amount` from `source` every `interval` seconds; approve the
+/// `pda::deposit_schedule` PDA as delegate on `source` alongside it
+pub fn create_deposit_schedule(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    source: &Pubkey,
+    lp_token_account: &Pubkey,
+    amount: u64,
+    interval: i64,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::CreateDepositSchedule {
+            deposit_schedule: pda::deposit_schedule(owner, borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            source: *source,
+            lp_token_account: *lp_token_account,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        instruction::CreateDepositSchedule { amount, interval },
+    )
+}
+
+/// Crank `owner`'s due deposit into the `borrowable_mint` pool (any signer can send it)
+pub fn crank_scheduled_deposit(
+    cranker: &Pubkey,
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    source: &Pubkey,
+    lp_token_account: &Pubkey,
+    allowlisted: bool,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::CrankScheduledDeposit {
+            deposit_schedule: pda::deposit_schedule(owner, borrowable_mint).0,
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            source: *source,
+            lp_token_account: *lp_token_account,
+            protocol: pda::protocol().0,
+            allowlist_entry: allowlist_entry(borrowable_mint, owner, allowlisted),
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::CrankScheduledDeposit {},
+    )
+}
+
+/// Stop `owner`'s deposit schedule into the `borrowable_mint` pool
+pub fn cancel_deposit_schedule(owner: &Pubkey, borrowable_mint: &Pubkey) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::CancelDepositSchedule {
+            deposit_schedule: pda::deposit_schedule(owner, borrowable_mint).0,
+            owner: *owner,
+        },
+        instruction::CancelDepositSchedule {},
+    )
+}
+
+/// Allowlist `lp` on the permissioned `borrowable_mint` pool (admin only)
+pub fn add_lp_to_allowlist(admin: &Pubkey, borrowable_mint: &Pubkey, lp: &Pubkey) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::AddLpToAllowlist {
+            lp_pool,
+            allowlist_entry: pda::lp_allowlist_entry(&lp_pool, lp).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        instruction::AddLpToAllowlist { lp: *lp },
+    )
+}
+
+pub fn remove_lp_from_allowlist(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    lp: &Pubkey,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::RemoveLpFromAllowlist {
+            lp_pool,
+            allowlist_entry: pda::lp_allowlist_entry(&lp_pool, lp).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+        },
+        instruction::RemoveLpFromAllowlist {},
+    )
+}
+
+/// `lp`'s allowlist entry on the `borrowable_mint` pool, if it has one
+pub(crate) fn allowlist_entry(
+    borrowable_mint: &Pubkey,
+    lp: &Pubkey,
+    allowlisted: bool,
+) -> Option<Pubkey> {
+    allowlisted.then(|| pda::lp_allowlist_entry(&pda::lp_pool(borrowable_mint).0, lp).0)
+}
+
+/// Lock `shares` for `days` during a liquidity crisis, for a boosted share of interest
+pub fn lock_lp_shares(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_lp_token_account: &Pubkey,
+    shares: u64,
+    days: u16,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let lp_lock = pda::lp_lock(&lp_pool, owner).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::LockLpShares {
+            lp_pool,
+            lp_lock,
+            escrow: pda::lp_lock_escrow(&lp_lock).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            owner_lp_token_account: *owner_lp_token_account,
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::LockLpShares { shares, days },
+    )
+}
+
+/// Return `owner`'s expired lock with its rebate; any `cranker` can submit it
+pub fn unlock_lp_shares(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_lp_token_account: &Pubkey,
+    owner_token_account: &Pubkey,
+    cranker: &Pubkey,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let lp_lock = pda::lp_lock(&lp_pool, owner).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::UnlockLpShares {
+            lp_pool,
+            lp_lock,
+            escrow: pda::lp_lock_escrow(&lp_lock).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            owner_lp_token_account: *owner_lp_token_account,
+            owner_token_account: *owner_token_account,
+            owner: *owner,
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::UnlockLpShares {},
+    )
+}
+
+/// Deposit `amount` locked for `days` (30, 90 or 180) for boosted interest
+pub fn deposit_locked(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_token_account: &Pubkey,
+    amount: u64,
+    days: u16,
+    allowlisted: bool,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let locked_deposit = pda::locked_deposit(&lp_pool, owner).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::DepositLocked {
+            lp_pool,
+            locked_deposit,
+            escrow: pda::locked_deposit_escrow(&locked_deposit).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            owner_token_account: *owner_token_account,
+            protocol: pda::protocol().0,
+            allowlist_entry: allowlist_entry(borrowable_mint, owner, allowlisted),
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::DepositLocked { amount, days },
+    )
+}
+
+/// Redeem `owner`'s locked deposit, early for a penalty or with its boost once unlocked
+pub fn unlock_and_withdraw(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_token_account: &Pubkey,
+    allowlisted: bool,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let locked_deposit = pda::locked_deposit(&lp_pool, owner).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::UnlockAndWithdraw {
+            lp_pool,
+            locked_deposit,
+            escrow: pda::locked_deposit_escrow(&locked_deposit).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            owner_token_account: *owner_token_account,
+            allowlist_entry: allowlist_entry(borrowable_mint, owner, allowlisted),
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LP_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            owner: *owner,
+            token_program: token::ID,
+        },
+        instruction::UnlockAndWithdraw {},
+    )
+}
+
+/// Queue `shares` for withdrawal. `request_id` is the pool's `withdraw_queue.next_id`
+pub fn request_withdraw(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_lp_token_account: &Pubkey,
+    shares: u64,
+    request_id: u64,
+    allowlisted: bool,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let withdraw_request = pda::withdraw_request(&lp_pool, request_id).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::RequestWithdraw {
+            lp_pool,
+            withdraw_request,
+            escrow: pda::withdraw_request_escrow(&withdraw_request).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            owner_lp_token_account: *owner_lp_token_account,
+            allowlist_entry: allowlist_entry(borrowable_mint, owner, allowlisted),
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RequestWithdraw { shares },
+    )
+}
+
+/// Pay `owner`'s request `request_id`, the head of the pool's withdrawal queue; any
+/// `cranker` can submit it
+pub fn process_withdraw_queue(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    request_id: u64,
+    owner_token_account: &Pubkey,
+    cranker: &Pubkey,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let withdraw_request = pda::withdraw_request(&lp_pool, request_id).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::ProcessWithdrawQueue {
+            lp_pool,
+            withdraw_request,
+            escrow: pda::withdraw_request_escrow(&withdraw_request).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            owner_token_account: *owner_token_account,
+            owner: *owner,
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::ProcessWithdrawQueue {},
+    )
+}
+
+/// Open reward emissions in `reward_mint` for the pool (admin only)
+pub fn initialize_rewards(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    reward_mint: &Pubkey,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let reward_config = pda::reward_config(&lp_pool).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::InitializeRewards {
+            lp_pool,
+            reward_config,
+            reward_mint: *reward_mint,
+            reward_vault: pda::reward_vault(&reward_config).0,
+            stake_vault: pda::reward_stake_vault(&reward_config).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeRewards {},
+    )
+}
+
+/// Add `amount` of the reward token from `admin_token_account` to the pool's emissions
+pub fn fund_rewards(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    admin_token_account: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let reward_config = pda::reward_config(&pda::lp_pool(borrowable_mint).0).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::FundRewards {
+            reward_config,
+            reward_vault: pda::reward_vault(&reward_config).0,
+            admin_token_account: *admin_token_account,
+            protocol: pda::protocol().0,
+            admin: *admin,
+            token_program: token::ID,
+        },
+        instruction::FundRewards { amount },
+    )
+}
+
+/// Set the pool's reward tokens emitted per second (admin only)
+pub fn set_emission_rate(
+    admin: &Pubkey,
+    borrowable_mint: &Pubkey,
+    emission_rate: u64,
+) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::SetEmissionRate {
+            reward_config: pda::reward_config(&pda::lp_pool(borrowable_mint).0).0,
+            protocol: pda::protocol().0,
+            admin: *admin,
+        },
+        instruction::SetEmissionRate { emission_rate },
+    )
+}
+
+/// Stake `shares` from `owner_lp_token_account` for reward emissions
+pub fn stake_for_rewards(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_lp_token_account: &Pubkey,
+    shares: u64,
+) -> Instruction {
+    let reward_config = pda::reward_config(&pda::lp_pool(borrowable_mint).0).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::StakeForRewards {
+            reward_config,
+            user_reward_state: pda::user_reward_state(&reward_config, owner).0,
+            stake_vault: pda::reward_stake_vault(&reward_config).0,
+            owner_lp_token_account: *owner_lp_token_account,
+            owner: *owner,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::StakeForRewards { shares },
+    )
+}
+
+/// Return `shares` of the rewards stake to `owner_lp_token_account`
+pub fn unstake_from_rewards(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_lp_token_account: &Pubkey,
+    shares: u64,
+) -> Instruction {
+    let reward_config = pda::reward_config(&pda::lp_pool(borrowable_mint).0).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::UnstakeFromRewards {
+            reward_config,
+            user_reward_state: pda::user_reward_state(&reward_config, owner).0,
+            stake_vault: pda::reward_stake_vault(&reward_config).0,
+            owner_lp_token_account: *owner_lp_token_account,
+            owner: *owner,
+            token_program: token::ID,
+        },
+        instruction::UnstakeFromRewards { shares },
+    )
+}
+
+/// Claim `owner`'s emitted rewards into `owner_reward_account`
+pub fn claim_rewards(
+    owner: &Pubkey,
+    borrowable_mint: &Pubkey,
+    owner_reward_account: &Pubkey,
+) -> Instruction {
+    let reward_config = pda::reward_config(&pda::lp_pool(borrowable_mint).0).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::ClaimRewards {
+            reward_config,
+            user_reward_state: pda::user_reward_state(&reward_config, owner).0,
+            reward_vault: pda::reward_vault(&reward_config).0,
+            owner_reward_account: *owner_reward_account,
+            owner: *owner,
+            token_program: token::ID,
+        },
+        instruction::ClaimRewards {},
+    )
+}
+
+/// Publish the pool's utilization-based rates on its `Borrowable` (any signer can send it)
+pub fn refresh_rates(borrowable_mint: &Pubkey) -> Instruction {
+    build(
+        LP_PROGRAM_ID,
+        accounts::RefreshRates {
+            lp_pool: pda::lp_pool(borrowable_mint).0,
+            borrowable: pda::borrowable(borrowable_mint).0,
+            protocol_writer: pda::protocol_writer(&LP_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+        },
+        instruction::RefreshRates {},
+    )
+}
+
+/// Auction `lot` of the pool's insurance surplus for LP shares
+pub fn start_surplus_auction(starter: &Pubkey, borrowable_mint: &Pubkey, lot: u64) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let auction = pda::surplus_auction(&lp_pool).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::StartSurplusAuction {
+            lp_pool,
+            vault: pda::lp_vault(borrowable_mint).0,
+            auction,
+            bid_escrow: pda::surplus_bid_escrow(&auction).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LP_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            starter: *starter,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::StartSurplusAuction { lot },
+    )
+}
+
+/// Bid `shares` on the pool's surplus auction; pass the current top bidder's LP token
+/// account as `previous_bidder_lp_token_account` once there is a bid
+pub fn bid_surplus_auction(
+    bidder: &Pubkey,
+    borrowable_mint: &Pubkey,
+    bidder_lp_token_account: &Pubkey,
+    previous_bidder_lp_token_account: Option<&Pubkey>,
+    shares: u64,
+) -> Instruction {
+    let auction = pda::surplus_auction(&pda::lp_pool(borrowable_mint).0).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::BidSurplusAuction {
+            auction,
+            bid_escrow: pda::surplus_bid_escrow(&auction).0,
+            bidder_lp_token_account: *bidder_lp_token_account,
+            previous_bidder_lp_token_account: previous_bidder_lp_token_account.copied(),
+            bidder: *bidder,
+            token_program: token::ID,
+        },
+        instruction::BidSurplusAuction { shares },
+    )
+}
+
+/// Settle the pool's ended surplus auction; `winner_token_account` receives the lot
+/// and is required if anyone bid
+pub fn settle_surplus_auction(
+    cranker: &Pubkey,
+    borrowable_mint: &Pubkey,
+    starter: &Pubkey,
+    winner_token_account: Option<&Pubkey>,
+) -> Instruction {
+    let lp_pool = pda::lp_pool(borrowable_mint).0;
+    let auction = pda::surplus_auction(&lp_pool).0;
+    build(
+        LP_PROGRAM_ID,
+        accounts::SettleSurplusAuction {
+            lp_pool,
+            auction,
+            bid_escrow: pda::surplus_bid_escrow(&auction).0,
+            lp_token_mint: pda::lp_token_mint(borrowable_mint).0,
+            vault: pda::lp_vault(borrowable_mint).0,
+            winner_token_account: winner_token_account.copied(),
+            protocol: pda::protocol().0,
+            protocol_writer: pda::protocol_writer(&LP_PROGRAM_ID).0,
+            core_program: CORE_PROGRAM_ID,
+            starter: *starter,
+            cranker: *cranker,
+            token_program: token::ID,
+        },
+        instruction::SettleSurplusAuction {},
+    )
+}
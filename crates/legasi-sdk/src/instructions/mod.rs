@@ -0,0 +1,29 @@
+//! Typed instruction builders
+//!
+//! Builders derive every PDA and fill in program accounts, so callers only
+//! pass wallets, mints, and their own token accounts. Account structs and
+//! instruction data come from the programs' Anchor-generated client modules,
+//! so a mismatch with the on-chain layout fails to compile.
+
+pub mod core;
+pub mod flash;
+pub mod gad;
+pub mod lending;
+pub mod lp;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+
+/// Assemble an instruction from Anchor client accounts and data
+pub(crate) fn build(
+    program_id: Pubkey,
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
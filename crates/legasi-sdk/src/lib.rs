@@ -0,0 +1,27 @@
+//! # Legasi SDK
+//!
+//! Rust client for the Legasi programs, for bots and backends:
+//! - `pda` - PDA derivation for every program
+//! - `instructions` - typed instruction builders (no hand-rolled account lists)
+//! - `accounts` - account deserialization (and fetching with the `rpc` feature)
+//! - `math` - LTV / health / GAD math mirroring on-chain logic
+
+pub mod accounts;
+pub mod instructions;
+pub mod math;
+pub mod pda;
+
+pub use legasi_core::ID as CORE_PROGRAM_ID;
+pub use legasi_flash::ID as FLASH_PROGRAM_ID;
+pub use legasi_gad::ID as GAD_PROGRAM_ID;
+pub use legasi_lending::ID as LENDING_PROGRAM_ID;
+pub use legasi_leverage::ID as LEVERAGE_PROGRAM_ID;
+pub use legasi_lp::ID as LP_PROGRAM_ID;
+
+/// Re-exports of the on-chain crates (account types, events, errors)
+pub use legasi_core;
+pub use legasi_flash;
+pub use legasi_gad;
+pub use legasi_lending;
+pub use legasi_leverage;
+pub use legasi_lp;
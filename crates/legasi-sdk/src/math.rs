@@ -0,0 +1,295 @@
+//! LTV / health math mirroring on-chain logic
+//!
+//! All USD values use 6 decimals (`USD_MULTIPLIER`), ratios use basis points.
+
+use legasi_core::constants::*;
+use legasi_core::state::AssetType;
+use legasi_core::valuation;
+use legasi_lending::{BorrowedAmount, Position};
+use legasi_lp::LpPool;
+
+/// USD value (6 decimals) of lamports at a 6-decimal SOL price
+pub fn sol_to_usd(lamports: u64, sol_price_usd_6dec: u64) -> u64 {
+    ((lamports as u128) * (sol_price_usd_6dec as u128) / (LAMPORTS_PER_SOL as u128)) as u64
+}
+
+/// Collateral value as valued on-chain by `legasi_core::valuation::value_collaterals`:
+/// SOL and LSTs at the SOL price, cbBTC at `cbbtc_price_usd_6dec`. Held cbBTC without
+/// its price can't be valued on-chain either, so the position counts as 0
+pub fn collateral_value_usd(
+    position: &Position,
+    sol_price_usd_6dec: u64,
+    cbbtc_price_usd_6dec: Option<u64>,
+) -> u64 {
+    let mut book = valuation::PriceBook::new(sol_price_usd_6dec, None);
+    if let Some(price) = cbbtc_price_usd_6dec {
+        book = book.with_price(AssetType::CbBTC, price);
+    }
+    valuation::value_collaterals(
+        position
+            .collaterals
+            .iter()
+            .map(|c| (c.asset_type, c.amount)),
+        std::iter::empty(),
+        book,
+    )
+    .unwrap_or(0)
+}
+
+/// Total debt (principal + accrued interest), EURC at a 6-decimal EUR/USD price
+pub fn total_debt_usd(position: &Position, eur_usd_price_6dec: u64) -> u64 {
+    position
+        .borrows
+        .iter()
+        .map(|b| {
+            let owed = b.amount.saturating_add(b.accrued_interest);
+            match b.asset_type {
+                AssetType::EURC => {
+                    ((owed as u128) * (eur_usd_price_6dec as u128) / (USD_MULTIPLIER as u128))
+                        as u64
+                }
+                _ => owed,
+            }
+        })
+        .fold(0u64, u64::saturating_add)
+}
+
+/// What `borrow` owes (principal + interest) at `now`, with the interest accrued since
+/// the entry last accrued on-chain: `pool`'s borrow index gain, or a fixed-term loan's
+/// own rate. `pool` must be the entry's asset
+pub fn owed_at(borrow: &BorrowedAmount, pool: &LpPool, now: i64) -> u64 {
+    let mut projected = *borrow;
+    match pool
+        .borrow_index_at(now)
+        .and_then(|index| projected.accrue(index, now))
+    {
+        Ok(()) => projected.amount.saturating_add(projected.accrued_interest),
+        Err(_) => u64::MAX,
+    }
+}
+
+/// Max LTV including the reputation bonus
+pub fn effective_max_ltv_bps(position: &Position) -> u64 {
+    (DEFAULT_SOL_MAX_LTV_BPS as u64).saturating_add(position.reputation.get_ltv_bonus_bps() as u64)
+}
+
+/// Current LTV, `None` with no collateral
+pub fn ltv_bps(collateral_usd: u64, debt_usd: u64) -> Option<u64> {
+    if collateral_usd == 0 {
+        return None;
+    }
+    Some(((debt_usd as u128) * (BPS_DENOMINATOR as u128) / (collateral_usd as u128)) as u64)
+}
+
+/// Max total debt at `max_ltv_bps`
+pub fn max_borrow_usd(collateral_usd: u64, max_ltv_bps: u64) -> u64 {
+    ((collateral_usd as u128) * (max_ltv_bps as u128) / (BPS_DENOMINATOR as u128)) as u64
+}
+
+/// Remaining borrow capacity of a position, cbBTC at `cbbtc_price_usd_6dec`
+pub fn available_to_borrow_usd(
+    position: &Position,
+    sol_price_usd_6dec: u64,
+    cbbtc_price_usd_6dec: Option<u64>,
+    eur_usd_price_6dec: u64,
+) -> u64 {
+    let collateral_usd = collateral_value_usd(position, sol_price_usd_6dec, cbbtc_price_usd_6dec);
+    max_borrow_usd(collateral_usd, effective_max_ltv_bps(position))
+        .saturating_sub(total_debt_usd(position, eur_usd_price_6dec))
+}
+
+/// Health factor in bps (10000 = at max LTV, below 10000 = GAD territory)
+/// `None` with no debt
+pub fn health_factor_bps(collateral_usd: u64, debt_usd: u64, max_ltv_bps: u64) -> Option<u64> {
+    if debt_usd == 0 {
+        return None;
+    }
+    Some(
+        (max_borrow_usd(collateral_usd, max_ltv_bps) as u128 * BPS_DENOMINATOR as u128
+            / debt_usd as u128) as u64,
+    )
+}
+
+/// GAD daily rate: quadratic in the excess over max LTV, capped at 10%/day
+/// (the on-chain curve from `legasi_core::gad`)
+pub fn gad_rate_bps(current_ltv_bps: u64, max_ltv_bps: u64) -> u64 {
+    legasi_core::gad::rate_bps(current_ltv_bps, max_ltv_bps)
+}
+
+/// LP exchange rate (underlying per share, 6 decimals)
+pub fn lp_exchange_rate(total_deposits: u64, total_shares: u64) -> u64 {
+    if total_shares == 0 {
+        return USD_MULTIPLIER;
+    }
+    ((total_deposits as u128) * (USD_MULTIPLIER as u128) / (total_shares as u128)) as u64
+}
+
+/// LP pool utilization in bps (borrowed / total deposits, capped at 100%)
+pub fn lp_utilization_bps(total_deposits: u64, total_borrowed: u64) -> u64 {
+    if total_deposits == 0 {
+        return 0;
+    }
+    let utilization =
+        (total_borrowed as u128) * (BPS_DENOMINATOR as u128) / (total_deposits as u128);
+    std::cmp::min(utilization, BPS_DENOMINATOR as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use legasi_core::interest::BORROW_INDEX_PRECISION;
+    use legasi_core::state::OutflowLimiter;
+    use legasi_lending::{CollateralDeposit, Reputation};
+
+    fn position(sol: u64, debt: u64) -> Position {
+        Position {
+            owner: Default::default(),
+            collaterals: vec![CollateralDeposit {
+                asset_type: AssetType::SOL,
+                amount: sol,
+            }],
+            borrows: vec![BorrowedAmount::new(
+                AssetType::USDC,
+                debt,
+                BORROW_INDEX_PRECISION,
+                0,
+            )],
+            last_update: 0,
+            last_gad_crank: 0,
+            gad_enabled: true,
+            total_gad_liquidated_usd: 0,
+            reputation: Reputation::default(),
+            stake_provider: Default::default(),
+            gad_carried_secs: 0,
+            max_debt_usd: 0,
+            referrer: Default::default(),
+            committed_letters_usd: 0,
+            emode: Default::default(),
+            statement_totals: Default::default(),
+            gad_collateral_floor: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_position_health() {
+        // 10 SOL at $100, $500 debt
+        let position = position(10 * LAMPORTS_PER_SOL, 500 * USD_MULTIPLIER);
+        let price = 100 * USD_MULTIPLIER;
+
+        let collateral = collateral_value_usd(&position, price, None);
+        assert_eq!(collateral, 1_000 * USD_MULTIPLIER);
+        let eur = 1_080_000;
+        assert_eq!(
+            ltv_bps(collateral, total_debt_usd(&position, eur)),
+            Some(5_000)
+        );
+        assert_eq!(
+            available_to_borrow_usd(&position, price, None, eur),
+            250 * USD_MULTIPLIER
+        );
+        assert_eq!(
+            health_factor_bps(collateral, total_debt_usd(&position, eur), 7_500),
+            Some(15_000)
+        );
+    }
+
+    #[test]
+    fn test_eurc_debt_valued_in_usd() {
+        // 100 EURC at $1.08 plus $500 USDC
+        let mut position = position(10 * LAMPORTS_PER_SOL, 500 * USD_MULTIPLIER);
+        position.borrows.push(BorrowedAmount::new(
+            AssetType::EURC,
+            100 * USD_MULTIPLIER,
+            BORROW_INDEX_PRECISION,
+            0,
+        ));
+        assert_eq!(total_debt_usd(&position, 1_080_000), 608 * USD_MULTIPLIER);
+    }
+
+    #[test]
+    fn test_mixed_collateral_matches_on_chain() {
+        // 10 SOL at $100 plus 0.5 cbBTC at $60k
+        let mut position = position(10 * LAMPORTS_PER_SOL, 500 * USD_MULTIPLIER);
+        position.collaterals.push(CollateralDeposit {
+            asset_type: AssetType::CbBTC,
+            amount: 50_000_000,
+        });
+        let (sol_price, cbbtc_price) = (100 * USD_MULTIPLIER, 60_000 * USD_MULTIPLIER);
+
+        let collateral = collateral_value_usd(&position, sol_price, Some(cbbtc_price));
+        assert_eq!(collateral, 31_000 * USD_MULTIPLIER);
+        let book =
+            valuation::PriceBook::new(sol_price, None).with_price(AssetType::CbBTC, cbbtc_price);
+        assert_eq!(position.value_collateral(&book).unwrap(), collateral);
+
+        // Without the cbBTC price the position can't be valued
+        assert_eq!(collateral_value_usd(&position, sol_price, None), 0);
+    }
+
+    #[test]
+    fn test_owed_at() {
+        // $1,000 borrowed from a pool 80% lent out accrues 11% a year
+        let pool = LpPool {
+            borrowable_mint: Default::default(),
+            lp_token_mint: Default::default(),
+            total_deposits: 10_000 * USD_MULTIPLIER,
+            total_shares: 10_000 * USD_MULTIPLIER,
+            total_borrowed: 8_000 * USD_MULTIPLIER,
+            interest_earned: 0,
+            outflow_limiter: OutflowLimiter::default(),
+            max_utilization_bps: 0,
+            locked_shares: 0,
+            lock_rebate_per_share: 0,
+            permissioned: false,
+            bad_debt: 0,
+            borrow_index: BORROW_INDEX_PRECISION,
+            index_updated_at: 0,
+            withdraw_queue: Default::default(),
+            locked_deposit_weight: 0,
+            deposit_boost_per_weight: 0,
+            protocol_fee_enabled: false,
+            protocol_fees_owed: 0,
+            reserve_factor_bps: 0,
+            reserves_pending: 0,
+            insurance_pending: 0,
+            bump: 0,
+        };
+        let mut borrow = BorrowedAmount::new(
+            AssetType::USDC,
+            1_000 * USD_MULTIPLIER,
+            BORROW_INDEX_PRECISION,
+            0,
+        );
+        borrow.accrued_interest = 5 * USD_MULTIPLIER;
+        assert_eq!(owed_at(&borrow, &pool, 0), 1_005 * USD_MULTIPLIER);
+        assert_eq!(
+            owed_at(&borrow, &pool, SECONDS_PER_YEAR as i64),
+            1_115 * USD_MULTIPLIER
+        );
+
+        // The same loan fixed at 5% for the year accrues that instead
+        borrow.fixed_rate_bps = 500;
+        borrow.maturity = SECONDS_PER_YEAR as i64;
+        assert_eq!(
+            owed_at(&borrow, &pool, SECONDS_PER_YEAR as i64),
+            1_055 * USD_MULTIPLIER
+        );
+    }
+
+    #[test]
+    fn test_gad_rate() {
+        assert_eq!(gad_rate_bps(7_000, 7_500), 0);
+        // 500^2 / 100 = 2500, capped at 10%/day
+        assert_eq!(gad_rate_bps(8_000, 7_500), 1_000);
+        assert_eq!(gad_rate_bps(7_600, 7_500), 100);
+    }
+
+    #[test]
+    fn test_lp_exchange_rate() {
+        assert_eq!(lp_exchange_rate(0, 0), USD_MULTIPLIER);
+        assert_eq!(lp_exchange_rate(1_100, 1_000), 1_100_000);
+        assert_eq!(lp_utilization_bps(1_100, 800), 7_272);
+        assert_eq!(lp_utilization_bps(0, 800), 0);
+    }
+}
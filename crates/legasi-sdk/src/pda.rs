@@ -0,0 +1,24 @@
+//! PDA derivation
+//!
+//! Re-exports the canonical derivations of `legasi_core::pda`, built from the same
+//! seed consts (`legasi_core::seeds`) the programs check against.
+//! Every helper returns `(address, bump)`.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::metadata::mpl_token_metadata;
+
+pub use legasi_core::pda::*;
+
+/// Receipt proving `payer` paid `listing` for access window `period`
+/// (`ServiceListing::period_at`); providers grant access while it exists
+pub fn x402_access_receipt(listing: &Pubkey, payer: &Pubkey, period: u64) -> (Pubkey, u8) {
+    x402_receipt(&legasi_lending::listing_access_id(listing, payer, period))
+}
+
+/// Metaplex metadata account of a mint (e.g. an LP token)
+pub fn token_metadata(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+        &mpl_token_metadata::ID,
+    )
+}
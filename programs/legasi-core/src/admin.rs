@@ -0,0 +1,396 @@
+//! # Admin Operations
+//!
+//! Batched parameter changes for `execute_admin_ops`.
+//!
+//! Under a Squads multisig the admin is the vault PDA, which signs through a
+//! CPI from the Squads program. Each approved proposal costs a full signing
+//! round, so related changes (e.g., tightening LTVs across assets and pausing
+//! the protocol) are bundled into one instruction and applied atomically.
+//!
+//! Collateral/Borrowable configs and price feeds touched by an op are passed as
+//! writable `remaining_accounts` and located by their PDA.
+
+use anchor_lang::prelude::*;
+
+use crate::circuit_breaker::CircuitBreakerConfig;
+use crate::constants::{BPS_DENOMINATOR, MAX_CRANKER_REWARD_BPS, MAX_REFERRAL_FEE_BPS, PAUSE_ALL};
+use crate::errors::LegasiError;
+use crate::gad::LiquidationSplit;
+use crate::oracle::OracleSources;
+use crate::seeds::{BORROWABLE_SEED, COLLATERAL_SEED, PRICE_FEED_SEED};
+use crate::state::{Borrowable, Collateral, PriceFeed, Protocol};
+use crate::swap_router::SwapRoute;
+
+/// A single admin parameter change
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum AdminOp {
+    SetPaused {
+        paused: bool,
+    },
+    SetTreasury {
+        treasury: Pubkey,
+    },
+    SetCollateralParams {
+        mint: Pubkey,
+        max_ltv_bps: u16,
+        liquidation_threshold_bps: u16,
+        liquidation_bonus_bps: u16,
+        is_active: bool,
+    },
+    SetBorrowableParams {
+        mint: Pubkey,
+        interest_rate_bps: u16,
+        is_active: bool,
+    },
+    SetCollateralSwapRoute {
+        mint: Pubkey,
+        route: SwapRoute,
+    },
+    SetCrankerReward {
+        min_bps: u16,
+        max_bps: u16,
+    },
+    SetLeverageVolatilityCap {
+        max_bps: u16,
+    },
+    SetReferralFee {
+        fee_bps: u16,
+    },
+    SetPointsWeights {
+        deposit_bps: u16,
+        borrow_bps: u16,
+    },
+    SetPriceDeviationBand {
+        mint: Pubkey,
+        max_bps: u16,
+    },
+    SetInsuranceFundTarget {
+        target: u64,
+    },
+    SetLiquidationSplit {
+        split: LiquidationSplit,
+    },
+    /// Pause only some entry points (`PAUSE_*` bits, 0 = none)
+    SetPauseFlags {
+        flags: u8,
+    },
+    /// Blend a price feed from these oracles (default key = source unused, both
+    /// default = back to `sync_pyth_price`), see `oracle`
+    SetOracleSources {
+        mint: Pubkey,
+        pyth: Pubkey,
+        switchboard: Pubkey,
+        max_deviation_bps: u16,
+    },
+    /// Halt borrows and withdrawals against `mint` after a sharp price move,
+    /// see `circuit_breaker` (max_move_bps 0 = off)
+    SetCircuitBreaker {
+        mint: Pubkey,
+        config: CircuitBreakerConfig,
+    },
+}
+
+/// Validate collateral risk parameters: LTV < liquidation threshold <= 100%
+pub fn validate_collateral_params(
+    max_ltv_bps: u16,
+    liquidation_threshold_bps: u16,
+    liquidation_bonus_bps: u16,
+) -> Result<()> {
+    require!(
+        max_ltv_bps < liquidation_threshold_bps
+            && liquidation_threshold_bps as u64 <= BPS_DENOMINATOR
+            && (liquidation_bonus_bps as u64) < BPS_DENOMINATOR,
+        LegasiError::InvalidAmount
+    );
+    Ok(())
+}
+
+/// Find the writable config account at `seeds` among `accounts`
+fn find_config<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    seeds: &[&[u8]],
+) -> Result<&'a AccountInfo<'info>> {
+    let (address, _) = Pubkey::find_program_address(seeds, &crate::ID);
+    let account = accounts
+        .iter()
+        .find(|a| a.key() == address)
+        .ok_or(LegasiError::InvalidAdminOp)?;
+    require!(account.is_writable, LegasiError::InvalidAdminOp);
+    require_keys_eq!(*account.owner, crate::ID, LegasiError::InvalidAdminOp);
+    Ok(account)
+}
+
+/// Apply one op to the protocol or to a config in `accounts`
+pub fn apply_admin_op(
+    protocol: &mut Protocol,
+    op: &AdminOp,
+    accounts: &[AccountInfo],
+) -> Result<()> {
+    match op {
+        AdminOp::SetPaused { paused } => {
+            protocol.paused = *paused;
+        }
+        AdminOp::SetTreasury { treasury } => {
+            protocol.treasury = *treasury;
+        }
+        AdminOp::SetCollateralParams {
+            mint,
+            max_ltv_bps,
+            liquidation_threshold_bps,
+            liquidation_bonus_bps,
+            is_active,
+        } => {
+            validate_collateral_params(
+                *max_ltv_bps,
+                *liquidation_threshold_bps,
+                *liquidation_bonus_bps,
+            )?;
+
+            let account = find_config(accounts, &[COLLATERAL_SEED, mint.as_ref()])?;
+            let mut data = account.try_borrow_mut_data()?;
+            let mut collateral = Collateral::try_deserialize(&mut &data[..])?;
+            collateral.max_ltv_bps = *max_ltv_bps;
+            collateral.liquidation_threshold_bps = *liquidation_threshold_bps;
+            collateral.liquidation_bonus_bps = *liquidation_bonus_bps;
+            collateral.is_active = *is_active;
+            collateral.try_serialize(&mut &mut data[..])?;
+        }
+        AdminOp::SetBorrowableParams {
+            mint,
+            interest_rate_bps,
+            is_active,
+        } => {
+            require!(
+                (*interest_rate_bps as u64) <= BPS_DENOMINATOR,
+                LegasiError::InvalidAmount
+            );
+
+            let account = find_config(accounts, &[BORROWABLE_SEED, mint.as_ref()])?;
+            let mut data = account.try_borrow_mut_data()?;
+            let mut borrowable = Borrowable::try_deserialize(&mut &data[..])?;
+            borrowable.interest_rate_bps = *interest_rate_bps;
+            borrowable.is_active = *is_active;
+            borrowable.try_serialize(&mut &mut data[..])?;
+        }
+        AdminOp::SetCollateralSwapRoute { mint, route } => {
+            let account = find_config(accounts, &[COLLATERAL_SEED, mint.as_ref()])?;
+            let mut data = account.try_borrow_mut_data()?;
+            let mut collateral = Collateral::try_deserialize(&mut &data[..])?;
+            collateral.swap_route = *route;
+            collateral.try_serialize(&mut &mut data[..])?;
+        }
+        AdminOp::SetCrankerReward { min_bps, max_bps } => {
+            require!(
+                min_bps <= max_bps && *max_bps <= MAX_CRANKER_REWARD_BPS,
+                LegasiError::InvalidAmount
+            );
+            protocol.cranker_reward_min_bps = *min_bps;
+            protocol.cranker_reward_max_bps = *max_bps;
+        }
+        AdminOp::SetLeverageVolatilityCap { max_bps } => {
+            protocol.max_leverage_volatility_bps = *max_bps;
+        }
+        AdminOp::SetReferralFee { fee_bps } => {
+            require!(*fee_bps <= MAX_REFERRAL_FEE_BPS, LegasiError::InvalidAmount);
+            protocol.referral_fee_bps = *fee_bps;
+        }
+        AdminOp::SetPointsWeights {
+            deposit_bps,
+            borrow_bps,
+        } => {
+            protocol.points_deposit_weight_bps = *deposit_bps;
+            protocol.points_borrow_weight_bps = *borrow_bps;
+        }
+        AdminOp::SetPriceDeviationBand { mint, max_bps } => {
+            let account = find_config(accounts, &[PRICE_FEED_SEED, mint.as_ref()])?;
+            let mut data = account.try_borrow_mut_data()?;
+            let mut feed = PriceFeed::try_deserialize(&mut &data[..])?;
+            feed.max_deviation_bps = *max_bps;
+            feed.try_serialize(&mut &mut data[..])?;
+        }
+        AdminOp::SetInsuranceFundTarget { target } => {
+            protocol.insurance_fund_target = *target;
+        }
+        AdminOp::SetLiquidationSplit { split } => {
+            require!(split.is_valid(), LegasiError::InvalidLiquidationSplit);
+            protocol.liquidation_split = *split;
+        }
+        AdminOp::SetPauseFlags { flags } => {
+            require!(flags & !PAUSE_ALL == 0, LegasiError::InvalidAdminOp);
+            protocol.pause_flags = *flags;
+        }
+        AdminOp::SetOracleSources {
+            mint,
+            pyth,
+            switchboard,
+            max_deviation_bps,
+        } => {
+            require!(
+                (*max_deviation_bps as u64) < BPS_DENOMINATOR,
+                LegasiError::InvalidAdminOp
+            );
+            let account = find_config(accounts, &[PRICE_FEED_SEED, mint.as_ref()])?;
+            let mut data = account.try_borrow_mut_data()?;
+            let mut feed = PriceFeed::try_deserialize(&mut &data[..])?;
+            feed.oracle_sources = OracleSources {
+                pyth: *pyth,
+                switchboard: *switchboard,
+                max_deviation_bps: *max_deviation_bps,
+                ..Default::default()
+            };
+            feed.try_serialize(&mut &mut data[..])?;
+        }
+        AdminOp::SetCircuitBreaker { mint, config } => {
+            config.validate()?;
+            let account = find_config(accounts, &[COLLATERAL_SEED, mint.as_ref()])?;
+            let mut data = account.try_borrow_mut_data()?;
+            let mut collateral = Collateral::try_deserialize(&mut &data[..])?;
+            collateral.circuit_breaker = *config;
+            collateral.try_serialize(&mut &mut data[..])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protocol() -> Protocol {
+        Protocol {
+            admin: Pubkey::default(),
+            pending_admin: Pubkey::default(),
+            treasury: Pubkey::default(),
+            insurance_fund: 0,
+            total_collateral_usd: 0,
+            total_borrowed_usd: 0,
+            paused: false,
+            cranker_reward_min_bps: 0,
+            cranker_reward_max_bps: 0,
+            max_leverage_volatility_bps: 0,
+            referral_fee_bps: 0,
+            points_deposit_weight_bps: 0,
+            points_borrow_weight_bps: 0,
+            insurance_fund_target: 0,
+            liquidation_split: LiquidationSplit::default(),
+            pause_flags: 0,
+            total_sol_collateral: 0,
+            bad_debt: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_validate_collateral_params() {
+        assert!(validate_collateral_params(7500, 8000, 500).is_ok());
+        // LTV must stay below the liquidation threshold
+        assert!(validate_collateral_params(8000, 8000, 500).is_err());
+        assert!(validate_collateral_params(7500, 10_001, 500).is_err());
+        assert!(validate_collateral_params(7500, 8000, 10_000).is_err());
+    }
+
+    #[test]
+    fn test_apply_protocol_ops() {
+        let mut protocol = protocol();
+        let treasury = Pubkey::new_unique();
+
+        apply_admin_op(&mut protocol, &AdminOp::SetPaused { paused: true }, &[]).unwrap();
+        apply_admin_op(&mut protocol, &AdminOp::SetTreasury { treasury }, &[]).unwrap();
+
+        assert!(protocol.paused);
+        assert_eq!(protocol.treasury, treasury);
+    }
+
+    #[test]
+    fn test_set_pause_flags() {
+        use crate::constants::{PAUSE_BORROWS, PAUSE_DEPOSITS, PAUSE_FLASH_LOANS};
+
+        let mut protocol = protocol();
+        let op = AdminOp::SetPauseFlags {
+            flags: PAUSE_FLASH_LOANS,
+        };
+        apply_admin_op(&mut protocol, &op, &[]).unwrap();
+        assert!(protocol.require_not_paused(PAUSE_FLASH_LOANS).is_err());
+        assert!(protocol.require_not_paused(PAUSE_BORROWS).is_ok());
+        assert!(protocol.require_not_paused(PAUSE_DEPOSITS).is_ok());
+
+        // The global flag pauses everything
+        apply_admin_op(&mut protocol, &AdminOp::SetPaused { paused: true }, &[]).unwrap();
+        assert!(protocol.require_not_paused(PAUSE_DEPOSITS).is_err());
+
+        // Unknown bits are rejected
+        let op = AdminOp::SetPauseFlags { flags: 1 << 7 };
+        assert!(apply_admin_op(&mut protocol, &op, &[]).is_err());
+    }
+
+    #[test]
+    fn test_set_cranker_reward() {
+        let mut protocol = protocol();
+        let op = AdminOp::SetCrankerReward {
+            min_bps: 20,
+            max_bps: 300,
+        };
+        apply_admin_op(&mut protocol, &op, &[]).unwrap();
+        assert_eq!(protocol.cranker_reward_min_bps, 20);
+        assert_eq!(protocol.cranker_reward_max_bps, 300);
+
+        // Min above max, or max above the cap, are rejected
+        for (min_bps, max_bps) in [(300, 20), (0, MAX_CRANKER_REWARD_BPS + 1)] {
+            let op = AdminOp::SetCrankerReward { min_bps, max_bps };
+            assert!(apply_admin_op(&mut protocol, &op, &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_set_referral_fee() {
+        let mut protocol = protocol();
+        let op = AdminOp::SetReferralFee { fee_bps: 1500 };
+        apply_admin_op(&mut protocol, &op, &[]).unwrap();
+        assert_eq!(protocol.referral_fee_bps, 1500);
+
+        let op = AdminOp::SetReferralFee {
+            fee_bps: MAX_REFERRAL_FEE_BPS + 1,
+        };
+        assert!(apply_admin_op(&mut protocol, &op, &[]).is_err());
+    }
+
+    #[test]
+    fn test_set_liquidation_split() {
+        let mut protocol = protocol();
+        let split = LiquidationSplit {
+            lp_recovery_bps: 8_500,
+            treasury_bps: 500,
+            insurance_bps: 700,
+            cranker_bps: 300,
+        };
+        let op = AdminOp::SetLiquidationSplit { split };
+        apply_admin_op(&mut protocol, &op, &[]).unwrap();
+        assert_eq!(protocol.liquidation_split, split);
+
+        let op = AdminOp::SetLiquidationSplit {
+            split: LiquidationSplit {
+                treasury_bps: 600,
+                ..split
+            },
+        };
+        assert!(apply_admin_op(&mut protocol, &op, &[]).is_err());
+
+        // Back to unset
+        let op = AdminOp::SetLiquidationSplit {
+            split: LiquidationSplit::default(),
+        };
+        apply_admin_op(&mut protocol, &op, &[]).unwrap();
+        assert!(!protocol.liquidation_split.is_set());
+    }
+
+    #[test]
+    fn test_config_op_requires_account() {
+        let mut protocol = protocol();
+        let op = AdminOp::SetBorrowableParams {
+            mint: Pubkey::new_unique(),
+            interest_rate_bps: 500,
+            is_active: true,
+        };
+        assert!(apply_admin_op(&mut protocol, &op, &[]).is_err());
+    }
+}
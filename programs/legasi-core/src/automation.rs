@@ -0,0 +1,153 @@
+//! # Automation Threads
+//!
+//! Clockwork-style registry for the protocol's safety-critical loops
+//! (GAD cranks and oracle syncs).
+//!
+//! ## Flow
+//!
+//! 1. A sponsor creates a `FeeBudget` and funds it with SOL
+//! 2. The sponsor registers an `AutomationThread` per loop (kind + target + interval)
+//! 3. Any executor runs the target crank, then calls `execute_thread` in the same transaction
+//! 4. `execute_thread` verifies the target was actually advanced and pays the executor
+//!    `fee_per_exec` from the budget
+//!
+//! Execution is verified from the target's own timestamps, so an executor is only
+//! paid for work that landed on-chain.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use std::str::FromStr;
+
+use crate::constants::LENDING_PROGRAM_ID;
+use crate::errors::LegasiError;
+use crate::state::{AssetType, CollateralDeposit, Position, PriceFeed};
+
+/// Loop an automation thread keeps alive
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+#[repr(u8)]
+pub enum ThreadKind {
+    /// `legasi_gad::crank_gad` on a position
+    GadCrank = 0,
+    /// `legasi_core::sync_pyth_price` on a price feed
+    OracleSync = 1,
+}
+
+/// SOL budget that pays executors for running threads
+#[account]
+#[derive(InitSpace)]
+pub struct FeeBudget {
+    /// Sponsor who funds the budget and owns its threads
+    pub authority: Pubkey,
+    /// Lamports deposited over the budget's lifetime
+    pub total_funded: u64,
+    /// Lamports paid out to executors
+    pub total_spent: u64,
+    pub bump: u8,
+}
+
+/// A registered automation thread
+#[account]
+#[derive(InitSpace)]
+pub struct AutomationThread {
+    /// Fee budget paying for this thread
+    pub fee_budget: Pubkey,
+    pub kind: ThreadKind,
+    /// Position or price feed the thread cranks
+    pub target: Pubkey,
+    /// Minimum seconds between paid executions
+    pub interval_secs: i64,
+    /// Lamports paid to the executor per execution
+    pub fee_per_exec: u64,
+    /// Target timestamp observed at the last paid execution
+    pub last_exec: i64,
+    pub exec_count: u64,
+    pub is_active: bool,
+    pub bump: u8,
+}
+
+impl AutomationThread {
+    /// Thread is due if active and the interval has elapsed
+    pub fn is_due(&self, now: i64) -> bool {
+        self.is_active && now.saturating_sub(self.last_exec) >= self.interval_secs
+    }
+}
+
+/// Borrow entry as laid out in `legasi_lending::BorrowedAmount`: asset, amount,
+/// accrued interest, borrow index, last accrual, fixed rate, maturity. It carries
+/// more than `state::BorrowedAmount`, so `state::Position` misreads a lending position
+type LendingBorrowEntry = (AssetType, u64, u64, u128, i64, u16, i64);
+
+/// Leading fields of `legasi_lending::Position`: owner, collaterals, borrows,
+/// `last_update`, `last_gad_crank`
+type LendingPositionHead = (
+    Pubkey,
+    Vec<CollateralDeposit>,
+    Vec<LendingBorrowEntry>,
+    i64,
+    i64,
+);
+
+/// `last_gad_crank` of a serialized `legasi_lending::Position`
+fn lending_last_gad_crank(data: &[u8]) -> Result<i64> {
+    require!(
+        data.get(..8) == Some(&Position::DISCRIMINATOR[..]),
+        LegasiError::InvalidThreadTarget
+    );
+    LendingPositionHead::deserialize(&mut &data[8..])
+        .map(|(.., last_gad_crank)| last_gad_crank)
+        .map_err(|_| error!(LegasiError::InvalidThreadTarget))
+}
+
+/// Read the timestamp a thread's crank advances on its target account
+/// - GadCrank: `last_gad_crank` of a lending position
+/// - OracleSync: `PriceFeed.last_update`
+pub fn read_target_timestamp(kind: ThreadKind, target: &AccountInfo) -> Result<i64> {
+    let data = target.try_borrow_data()?;
+
+    match kind {
+        ThreadKind::GadCrank => {
+            let lending_program = Pubkey::from_str(LENDING_PROGRAM_ID).unwrap();
+            require_keys_eq!(
+                *target.owner,
+                lending_program,
+                LegasiError::InvalidThreadTarget
+            );
+            lending_last_gad_crank(&data)
+        }
+        ThreadKind::OracleSync => {
+            require_keys_eq!(*target.owner, crate::ID, LegasiError::InvalidThreadTarget);
+            let price_feed = PriceFeed::try_deserialize(&mut &data[..])?;
+            Ok(price_feed.last_update)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_lending_position_with_debt() {
+        // A lending position with one borrow entry, laid out field by field
+        let borrow: LendingBorrowEntry = (AssetType::USDC, 500, 7, 1_000_000_000_000, 90, 0, 0);
+        let head: LendingPositionHead = (
+            Pubkey::new_unique(),
+            vec![CollateralDeposit {
+                asset_type: AssetType::SOL,
+                amount: 1_000_000_000,
+            }],
+            vec![borrow],
+            100,
+            1_700_000_000,
+        );
+        let mut data = Position::DISCRIMINATOR.to_vec();
+        head.serialize(&mut data).unwrap();
+        // Trailing fields past the head don't matter
+        data.extend_from_slice(&[0; 64]);
+        assert_eq!(lending_last_gad_crank(&data).unwrap(), 1_700_000_000);
+
+        // Anything but a position is rejected
+        data[0] ^= 1;
+        assert!(lending_last_gad_crank(&data).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! # Asset Caps
+//!
+//! Governance can cap how much of a mint the protocol takes as collateral and lends
+//! out, in the mint's native units, with an `AssetCaps` under `[ASSET_CAPS_SEED, mint]`.
+//! Entry points that add exposure pass it as an unchecked PDA, so a mint without one
+//! is uncapped, as is either cap left at 0.
+//!
+//! Caps are checked against what is already out there, not a separate counter:
+//! - Borrows: the mint's `LpPool.total_borrowed`
+//! - SPL collateral: the balance of its shared token vault
+//! - SOL collateral, held in per-position vaults: `Protocol.total_sol_collateral`,
+//!   which every SOL flow reports through `record_sol_collateral`
+//!
+//! Lowering a cap below current usage blocks new exposure only; nothing is unwound.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::LegasiError;
+
+/// Supply and borrow caps of one mint
+#[account]
+#[derive(InitSpace, Default)]
+pub struct AssetCaps {
+    pub mint: Pubkey,
+    /// Most collateral the protocol holds of this mint (native units, 0 = uncapped)
+    pub supply_cap: u64,
+    /// Most of this mint lent out at once (native units, 0 = uncapped)
+    pub borrow_cap: u64,
+    pub bump: u8,
+}
+
+impl AssetCaps {
+    /// Read the unchecked `[ASSET_CAPS_SEED, mint]` account. Without one nothing is capped
+    pub fn load(caps: &AccountInfo) -> Result<Self> {
+        if caps.owner != &crate::ID || caps.data_is_empty() {
+            return Ok(Self::default());
+        }
+        AssetCaps::try_deserialize(&mut &caps.try_borrow_data()?[..])
+    }
+
+    /// Fails if depositing `amount` on top of `supplied` would pass the supply cap
+    pub fn require_supply(&self, supplied: u64, amount: u64) -> Result<()> {
+        require!(
+            within_cap(self.supply_cap, supplied, amount),
+            LegasiError::SupplyCapExceeded
+        );
+        Ok(())
+    }
+
+    /// Fails if lending `amount` on top of `borrowed` would pass the borrow cap
+    pub fn require_borrow(&self, borrowed: u64, amount: u64) -> Result<()> {
+        require!(
+            within_cap(self.borrow_cap, borrowed, amount),
+            LegasiError::BorrowCapExceeded
+        );
+        Ok(())
+    }
+}
+
+/// True if `used + amount` fits `cap` (0 = uncapped)
+pub fn within_cap(cap: u64, used: u64, amount: u64) -> bool {
+    cap == 0 || used.saturating_add(amount) <= cap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within_cap() {
+        assert!(within_cap(0, u64::MAX, 1));
+        assert!(within_cap(1_000, 400, 600));
+        assert!(!within_cap(1_000, 400, 601));
+        // Already past a lowered cap: nothing more goes in
+        assert!(!within_cap(1_000, 1_200, 1));
+    }
+
+    #[test]
+    fn test_caps_are_independent() {
+        let caps = AssetCaps {
+            supply_cap: 0,
+            borrow_cap: 500,
+            ..Default::default()
+        };
+        caps.require_supply(10_000, 10_000).unwrap();
+        caps.require_borrow(200, 300).unwrap();
+        assert!(caps.require_borrow(200, 301).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Circle CCTP (v1) receive helpers
+//!
+//! Lets users on Base/Ethereum fund Solana positions in one step:
+//! they burn USDC on the source chain with `mint_recipient` set to a
+//! protocol-controlled inbox token account, and a relayer submits the
+//! message + Circle attestation to a Legasi instruction that
+//! - CPIs into the MessageTransmitter `receive_message` (attestation is verified there,
+//!   and the used-nonce account prevents replays)
+//! - checks the burn message targets the expected inbox
+//! - checks the inbox balance grew by exactly the attested amount
+//! - credits the USDC as an LP deposit or a loan repayment
+//!
+//! Message layout (all integers big-endian):
+//! - header: version u32, source_domain u32, destination_domain u32, nonce u64,
+//!   sender [32], recipient [32], destination_caller [32]
+//! - burn body: version u32, burn_token [32], mint_recipient [32], amount u256, message_sender [32]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::pubkey;
+
+use crate::errors::LegasiError;
+use crate::jupiter_cpi::build_account_metas;
+
+/// CCTP MessageTransmitter program
+pub const MESSAGE_TRANSMITTER_ID: Pubkey = pubkey!("CCTPmbSD7gX1bxKPAmg77w8oFzNFpaQiQUWD43TKaecd");
+
+/// CCTP TokenMessengerMinter program
+pub const TOKEN_MESSENGER_MINTER_ID: Pubkey =
+    pubkey!("CCTPiPYPc6AsJuwueEnWgSgucamXDZwBd53dQ11YiKX3");
+
+/// Anchor discriminator for `receive_message` (sha256("global:receive_message")[..8])
+pub const RECEIVE_MESSAGE_DISCRIMINATOR: [u8; 8] = [38, 144, 127, 225, 31, 225, 238, 25];
+
+/// Solana CCTP domain
+pub const SOLANA_DOMAIN: u32 = 5;
+
+const HEADER_LEN: usize = 116;
+const SOURCE_DOMAIN_OFFSET: usize = 4;
+const DESTINATION_DOMAIN_OFFSET: usize = 8;
+const NONCE_OFFSET: usize = 12;
+const MINT_RECIPIENT_OFFSET: usize = HEADER_LEN + 36;
+const AMOUNT_OFFSET: usize = HEADER_LEN + 68;
+const MESSAGE_SENDER_OFFSET: usize = HEADER_LEN + 100;
+const MESSAGE_LEN: usize = HEADER_LEN + 132;
+
+/// Fields of a CCTP burn message needed to credit a deposit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BurnMessage {
+    pub source_domain: u32,
+    pub nonce: u64,
+    pub mint_recipient: Pubkey,
+    pub amount: u64,
+    /// Burner on the source chain (EVM address, left-padded)
+    pub message_sender: [u8; 32],
+}
+
+fn read_u32(message: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(message[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(message: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(message[offset..offset + 8].try_into().unwrap())
+}
+
+/// Parse a CCTP burn message destined for Solana
+pub fn parse_burn_message(message: &[u8]) -> Result<BurnMessage> {
+    require!(
+        message.len() == MESSAGE_LEN,
+        LegasiError::InvalidCctpMessage
+    );
+    require!(
+        read_u32(message, DESTINATION_DOMAIN_OFFSET) == SOLANA_DOMAIN,
+        LegasiError::InvalidCctpMessage
+    );
+
+    // Amount is a u256; anything above u64 cannot be a USDC amount on Solana
+    require!(
+        message[AMOUNT_OFFSET..AMOUNT_OFFSET + 24]
+            .iter()
+            .all(|b| *b == 0),
+        LegasiError::InvalidCctpMessage
+    );
+
+    Ok(BurnMessage {
+        source_domain: read_u32(message, SOURCE_DOMAIN_OFFSET),
+        nonce: read_u64(message, NONCE_OFFSET),
+        mint_recipient: Pubkey::new_from_array(
+            message[MINT_RECIPIENT_OFFSET..MINT_RECIPIENT_OFFSET + 32]
+                .try_into()
+                .unwrap(),
+        ),
+        amount: read_u64(message, AMOUNT_OFFSET + 24),
+        message_sender: message[MESSAGE_SENDER_OFFSET..MESSAGE_SENDER_OFFSET + 32]
+            .try_into()
+            .unwrap(),
+    })
+}
+
+/// CPI into MessageTransmitter `receive_message`
+/// `cctp_accounts` are the receive_message accounts followed by the
+/// TokenMessengerMinter `handle_receive_message` accounts, in CCTP order
+pub fn receive_message<'info>(
+    message_transmitter: &AccountInfo<'info>,
+    cctp_accounts: &[AccountInfo<'info>],
+    message: Vec<u8>,
+    attestation: Vec<u8>,
+) -> Result<()> {
+    require_keys_eq!(
+        message_transmitter.key(),
+        MESSAGE_TRANSMITTER_ID,
+        LegasiError::InvalidCctpMessage
+    );
+
+    let mut data = RECEIVE_MESSAGE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+    data.extend_from_slice(&message);
+    data.extend_from_slice(&(attestation.len() as u32).to_le_bytes());
+    data.extend_from_slice(&attestation);
+
+    invoke(
+        &Instruction {
+            program_id: MESSAGE_TRANSMITTER_ID,
+            accounts: build_account_metas(cctp_accounts, None),
+            data,
+        },
+        cctp_accounts,
+    )?;
+    Ok(())
+}
+
+/// Assert the inbox received exactly the attested amount
+pub fn assert_minted(balance_before: u64, balance_after: u64, amount: u64) -> Result<()> {
+    require!(
+        balance_after.checked_sub(balance_before) == Some(amount),
+        LegasiError::InvalidCctpMessage
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn burn_message(destination_domain: u32, recipient: Pubkey, amount: u64) -> Vec<u8> {
+        let mut message = vec![0u8; MESSAGE_LEN];
+        message[SOURCE_DOMAIN_OFFSET..SOURCE_DOMAIN_OFFSET + 4]
+            .copy_from_slice(&6u32.to_be_bytes());
+        message[DESTINATION_DOMAIN_OFFSET..DESTINATION_DOMAIN_OFFSET + 4]
+            .copy_from_slice(&destination_domain.to_be_bytes());
+        message[NONCE_OFFSET..NONCE_OFFSET + 8].copy_from_slice(&42u64.to_be_bytes());
+        message[MINT_RECIPIENT_OFFSET..MINT_RECIPIENT_OFFSET + 32]
+            .copy_from_slice(recipient.as_ref());
+        message[AMOUNT_OFFSET + 24..AMOUNT_OFFSET + 32].copy_from_slice(&amount.to_be_bytes());
+        message
+    }
+
+    #[test]
+    fn test_parse_burn_message() {
+        let recipient = Pubkey::new_unique();
+        let parsed =
+            parse_burn_message(&burn_message(SOLANA_DOMAIN, recipient, 1_500_000)).unwrap();
+        assert_eq!(parsed.source_domain, 6); // Base
+        assert_eq!(parsed.nonce, 42);
+        assert_eq!(parsed.mint_recipient, recipient);
+        assert_eq!(parsed.amount, 1_500_000);
+    }
+
+    #[test]
+    fn test_parse_burn_message_rejects_invalid() {
+        let recipient = Pubkey::new_unique();
+        // Wrong destination domain
+        assert!(parse_burn_message(&burn_message(0, recipient, 1)).is_err());
+        // Truncated
+        let message = burn_message(SOLANA_DOMAIN, recipient, 1);
+        assert!(parse_burn_message(&message[..MESSAGE_LEN - 1]).is_err());
+        // Amount above u64
+        let mut message = burn_message(SOLANA_DOMAIN, recipient, 1);
+        message[AMOUNT_OFFSET] = 1;
+        assert!(parse_burn_message(&message).is_err());
+    }
+
+    #[test]
+    fn test_assert_minted() {
+        assert!(assert_minted(100, 600, 500).is_ok());
+        assert!(assert_minted(100, 500, 500).is_err());
+        assert!(assert_minted(600, 100, 500).is_err());
+    }
+}
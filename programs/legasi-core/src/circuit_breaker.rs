@@ -0,0 +1,207 @@
+//! Price circuit breaker
+//!
+//! A sharp oracle move is when positions are most exposed to a bad print or a
+//! cascade, so each collateral asset can halt risk-increasing actions after one. The
+//! admin sets the asset's thresholds on its `Collateral` with
+//! `AdminOp::SetCircuitBreaker`: how far the price may move (`max_move_bps`) within
+//! `window` seconds, and how long to halt (`cooldown`) once it does.
+//!
+//! Every price sync (`update_price`, `sync_pyth_price`, `sync_oracle_price`) checks
+//! the new price against a reference anchored on the feed at the start of the
+//! current window: the price in force when the previous window ran out. A move past
+//! the threshold trips the breaker on the feed until `now + cooldown`. While tripped,
+//! new borrows, leverage opens and withdrawals of or against the asset fail with
+//! `CircuitBreakerTripped`. Repayments, deposits and liquidations stay open.
+//!
+//! Flow:
+//! 1. Admin sets the asset's thresholds (max_move_bps 0 = breaker off)
+//! 2. Syncs trip the breaker on a large enough move within the window
+//! 3. Borrow/withdraw/open paths call `require_clear` on the feed's breaker
+
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::errors::LegasiError;
+use crate::events::CircuitBreakerTripped;
+use crate::state::{Collateral, PriceFeed};
+
+/// Circuit breaker thresholds of a collateral asset
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct CircuitBreakerConfig {
+    /// Move from the window's reference price that trips the breaker (bps, 0 = off)
+    pub max_move_bps: u16,
+    /// Length of the window moves are measured over (seconds)
+    pub window: i64,
+    /// How long risk-increasing actions stay halted once tripped (seconds)
+    pub cooldown: i64,
+}
+
+impl CircuitBreakerConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.max_move_bps > 0
+    }
+
+    /// Thresholds must be below 100% with a positive window and cooldown
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            !self.is_enabled()
+                || ((self.max_move_bps as u64) < BPS_DENOMINATOR
+                    && self.window > 0
+                    && self.cooldown > 0),
+            LegasiError::InvalidAdminOp
+        );
+        Ok(())
+    }
+}
+
+/// Circuit breaker state of a `PriceFeed`
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct CircuitBreaker {
+    /// Price moves are measured from (0 = none yet)
+    pub reference_price: u64,
+    /// Start of the current window
+    pub reference_at: i64,
+    /// Risk-increasing actions are halted before this time (0 = never tripped)
+    pub tripped_until: i64,
+}
+
+impl CircuitBreaker {
+    /// Check a sync from `previous_price` to `price` against `config`, re-anchoring
+    /// the reference once the window has run out. Returns whether this sync tripped
+    /// the breaker
+    pub fn observe(
+        &mut self,
+        config: &CircuitBreakerConfig,
+        previous_price: u64,
+        price: u64,
+        now: i64,
+    ) -> bool {
+        if self.reference_price == 0 || now.saturating_sub(self.reference_at) >= config.window {
+            self.reference_price = if previous_price > 0 {
+                previous_price
+            } else {
+                price
+            };
+            self.reference_at = now;
+        }
+        if !config.is_enabled() {
+            return false;
+        }
+
+        let move_bps = price.abs_diff(self.reference_price) as u128 * BPS_DENOMINATOR as u128
+            / self.reference_price as u128;
+        if move_bps <= config.max_move_bps as u128 {
+            return false;
+        }
+        self.tripped_until = now.saturating_add(config.cooldown);
+        true
+    }
+
+    pub fn is_tripped(&self, now: i64) -> bool {
+        now < self.tripped_until
+    }
+
+    /// Fails while the breaker is tripped
+    pub fn require_clear(&self, now: i64) -> Result<()> {
+        require!(!self.is_tripped(now), LegasiError::CircuitBreakerTripped);
+        Ok(())
+    }
+}
+
+/// Thresholds on the mint's `Collateral` PDA (off when the mint isn't collateral)
+pub fn load_circuit_breaker_config(collateral: &AccountInfo) -> Result<CircuitBreakerConfig> {
+    if collateral.owner != &crate::ID || collateral.data_is_empty() {
+        return Ok(CircuitBreakerConfig::default());
+    }
+    let collateral = Collateral::try_deserialize(&mut &collateral.try_borrow_data()?[..])?;
+    Ok(collateral.circuit_breaker)
+}
+
+/// Run the sync of `feed` from `previous_price` to its current price through the
+/// breaker, emitting `CircuitBreakerTripped` when it trips
+pub fn check_price_move(
+    feed: &mut PriceFeed,
+    collateral: &AccountInfo,
+    mint: Pubkey,
+    previous_price: u64,
+    now: i64,
+) -> Result<()> {
+    let config = load_circuit_breaker_config(collateral)?;
+    let price = feed.price_usd_6dec;
+    let breaker = &mut feed.circuit_breaker;
+    if breaker.observe(&config, previous_price, price, now) {
+        emit!(CircuitBreakerTripped {
+            mint,
+            reference_price: breaker.reference_price,
+            price_usd_6dec: price,
+            tripped_until: breaker.tripped_until,
+        });
+        msg!("Circuit breaker tripped until {}", breaker.tripped_until);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            max_move_bps: 1_000,
+            window: 600,
+            cooldown: 3_600,
+        }
+    }
+
+    #[test]
+    fn test_trips_on_large_move_within_window() {
+        let mut breaker = CircuitBreaker::default();
+        assert!(!breaker.observe(&config(), 0, 100_000_000, 1_000));
+        // Two 6% drops add up to more than 10% within the window
+        assert!(!breaker.observe(&config(), 100_000_000, 94_000_000, 1_100));
+        assert!(breaker.observe(&config(), 94_000_000, 88_000_000, 1_200));
+        assert!(breaker.require_clear(1_200 + 3_599).is_err());
+        breaker.require_clear(1_200 + 3_600).unwrap();
+    }
+
+    #[test]
+    fn test_window_reanchors_reference() {
+        let mut breaker = CircuitBreaker::default();
+        breaker.observe(&config(), 0, 100_000_000, 1_000);
+        breaker.observe(&config(), 100_000_000, 94_000_000, 1_500);
+        // The next window measures from $94
+        assert!(!breaker.observe(&config(), 94_000_000, 88_000_000, 1_600));
+        assert_eq!(breaker.reference_price, 94_000_000);
+        assert!(!breaker.is_tripped(1_600));
+
+        // Disabled: never trips
+        let off = CircuitBreakerConfig::default();
+        assert!(!breaker.observe(&off, 88_000_000, 10_000_000, 1_700));
+    }
+
+    #[test]
+    fn test_validate() {
+        config().validate().unwrap();
+        CircuitBreakerConfig::default().validate().unwrap();
+        for bad in [
+            CircuitBreakerConfig {
+                max_move_bps: 10_000,
+                ..config()
+            },
+            CircuitBreakerConfig {
+                window: 0,
+                ..config()
+            },
+            CircuitBreakerConfig {
+                cooldown: 0,
+                ..config()
+            },
+        ] {
+            assert!(bad.validate().is_err());
+        }
+    }
+}
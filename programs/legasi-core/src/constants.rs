@@ -9,6 +9,9 @@ pub const USD_MULTIPLIER: u64 = 1_000_000;
 pub const SOL_DECIMALS: u8 = 9;
 pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 
+/// cbBTC decimals (8)
+pub const CBBTC_DECIMALS: u8 = 8;
+
 /// Default max LTV for collateral assets (basis points)
 /// Accepted collaterals: SOL, cbBTC
 pub const DEFAULT_SOL_MAX_LTV_BPS: u16 = 7500; // 75%
@@ -29,27 +32,114 @@ pub const MIN_GAD_CRANK_INTERVAL: i64 = 3600; // 1 hour
 /// Seconds per day
 pub const SECONDS_PER_DAY: i64 = 86400;
 
+/// Seconds per year (365.25 days)
+pub const SECONDS_PER_YEAR: u64 = 31_557_600;
+
 /// Insurance fund fee (basis points of interest)
 pub const INSURANCE_FEE_BPS: u64 = 500; // 5%
 
+/// Highest reserve factor the admin can set on a pool (basis points of interest)
+pub const MAX_RESERVE_FACTOR_BPS: u16 = 5000; // 50%
+
+/// Referrer's share of the interest a referred borrower pays (basis points), admin-configurable
+pub const DEFAULT_REFERRAL_FEE_BPS: u16 = 1000; // 10%
+/// Highest referral share the admin can configure
+pub const MAX_REFERRAL_FEE_BPS: u16 = 2000; // 20%
+
+/// Points per USD-day of collateral (basis points, 10000 = 1 point), admin-configurable
+pub const DEFAULT_POINTS_DEPOSIT_WEIGHT_BPS: u16 = 10_000;
+/// Points per USD-day of debt (basis points, 10000 = 1 point), admin-configurable
+pub const DEFAULT_POINTS_BORROW_WEIGHT_BPS: u16 = 20_000;
+
+/// `Protocol.pause_flags` bits, each pausing one kind of entry point
+/// Repay, withdraw and GAD stay open under every flag
+pub const PAUSE_DEPOSITS: u8 = 1 << 0;
+pub const PAUSE_BORROWS: u8 = 1 << 1;
+pub const PAUSE_FLASH_LOANS: u8 = 1 << 2;
+pub const PAUSE_ALL: u8 = PAUSE_DEPOSITS | PAUSE_BORROWS | PAUSE_FLASH_LOANS;
+
 /// Flash loan fee (basis points)
 pub const FLASH_LOAN_FEE_BPS: u64 = 5; // 0.05%
 
 /// Minimum flash loan fee (absolute)
 pub const MIN_FLASH_LOAN_FEE: u64 = 1;
 
-/// Cranker reward (basis points of liquidated amount)
-pub const CRANKER_REWARD_BPS: u64 = 50; // 0.5%
+/// Cranker reward bounds (basis points of liquidated amount), admin-configurable
+/// on `Protocol`; the reward scales between them with LTV excess and staleness
+pub const DEFAULT_CRANKER_REWARD_MIN_BPS: u16 = 10; // 0.1%
+pub const DEFAULT_CRANKER_REWARD_MAX_BPS: u16 = 200; // 2%
+/// Highest cranker reward the admin can configure
+pub const MAX_CRANKER_REWARD_BPS: u16 = 1000; // 10%
+/// Least share of a GAD slice a configured `LiquidationSplit` must put towards debt
+pub const MIN_GAD_LP_RECOVERY_BPS: u16 = 5000; // 50%
+
+/// Outflow rate limit: max share of pool TVL that can leave per window (basis points)
+pub const DEFAULT_MAX_OUTFLOW_BPS: u16 = 2000; // 20%
+
+/// Outflow rate limit window length (slots)
+pub const DEFAULT_OUTFLOW_WINDOW_SLOTS: u64 = 9000; // ~1 hour at 400ms slots
+
+/// Emergency pool utilization cap: new borrows and flash loans stop above it (basis points)
+pub const DEFAULT_MAX_UTILIZATION_BPS: u16 = 9800; // 98%
+
+/// Max shortfall of a treasury conversion's USDC against the oracle value sold (basis points)
+pub const TREASURY_MAX_SLIPPAGE_BPS: u16 = 100; // 1%
+
+/// Share of an LST's unrealized staking yield left out of its collateral value (basis points)
+pub const LST_YIELD_HAIRCUT_BPS: u16 = 2000; // 20%
 
 /// Price feed staleness threshold (seconds)
 pub const PRICE_STALENESS_THRESHOLD: i64 = 300; // 5 minutes
 
+/// Oracle silence after which the admin may post a failover price (seconds)
+pub const PRICE_FAILOVER_WINDOW: i64 = 900; // 15 minutes
+
+/// Max distance of a failover price from the last oracle price (basis points)
+pub const MAX_FAILOVER_DEVIATION_BPS: u64 = 1000; // 10%
+
+/// Price syncs kept on each feed for the realized volatility estimate
+pub const PRICE_HISTORY_LEN: usize = 8;
+
+/// Realized move above which new leverage is blocked (basis points), admin-configurable
+pub const DEFAULT_MAX_LEVERAGE_VOLATILITY_BPS: u16 = 1500; // 15%
+
+/// Keeper fee for unwinding an expired leverage position, out of the sale proceeds (basis points)
+pub const LEVERAGE_UNWIND_FEE_BPS: u16 = 50; // 0.5%
+
 /// Max collateral types per position
 pub const MAX_COLLATERAL_TYPES: usize = 8;
 
 /// Max borrow types per position
 pub const MAX_BORROW_TYPES: usize = 4;
 
+/// Minimum interval for automation threads (seconds)
+pub const MIN_THREAD_INTERVAL: i64 = 60;
+
+/// Max ops per execute_admin_ops batch
+pub const MAX_ADMIN_OPS: usize = 8;
+
+/// Metaplex token metadata limits
+pub const MAX_TOKEN_NAME_LEN: usize = 32;
+pub const MAX_TOKEN_SYMBOL_LEN: usize = 10;
+pub const MAX_TOKEN_URI_LEN: usize = 200;
+
+// ========== PROGRAM IDS ==========
+
+/// Lending program (owns Position accounts)
+pub const LENDING_PROGRAM_ID: &str = "9356RoSbLTzWE55ab6GktcTocaNhPuBEDZvsmqjkCZYw";
+
+/// GAD program
+pub const GAD_PROGRAM_ID: &str = "89E84ALdDdGGNuJAxho2H45aC25kqNdGg7QtwTJ3pngK";
+
+/// Leverage program
+pub const LEVERAGE_PROGRAM_ID: &str = "AVATHjGrdQ1KqtjHQ4gwRcuAYjwwScwgPsujLDpiA2g3";
+
+/// LP program
+pub const LP_PROGRAM_ID: &str = "CTwY4VSeueesSBc95G38X3WJYPriJEzyxjcCaZAc5LbY";
+
+/// Flash loan program
+pub const FLASH_PROGRAM_ID: &str = "Fj8CJNK1gBAuNR7dFbKLDckSstKmZn8ihTGwFXxfY93m";
+
 // ========== TOKEN MINTS (Devnet) ==========
 
 /// Native SOL (wrapped)
@@ -0,0 +1,324 @@
+//! # Decimal Handling
+//!
+//! Amounts live on three scales:
+//! - USD values at `USD_DECIMALS` (6), the unit of debt, limits and totals
+//! - Native token amounts at their mint's decimals: SOL and mSOL 9, cbBTC 8,
+//!   USDC and EURC 6
+//! - Prices in USD per whole token, at 6 decimals
+//!
+//! Every conversion between them goes through this module instead of dividing by a
+//! constant in place. A `TokenAmount` carries its own decimals, so a cbBTC amount is
+//! scaled by 10^8 and never by `LAMPORTS_PER_SOL`, and `UsdAmount` is what a price turns
+//! it into. The plain-`u64` helpers (`token_to_usd`, `sol_to_usd`, ...) wrap the types for
+//! call sites that hold raw amounts.
+//!
+//! Conversions widen to u128 and fail with `MathOverflow` rather than truncate. They
+//! round down, so the protocol never credits more than the price supports, except the
+//! `_ceil` variants used where an amount must cover a USD value.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::{CBBTC_DECIMALS, SOL_DECIMALS, USD_DECIMALS};
+use crate::errors::LegasiError;
+use crate::state::AssetType;
+
+/// A USD value (6 decimals)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UsdAmount(pub u64);
+
+/// A native amount of a token with `decimals`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenAmount {
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+/// Mint decimals of `asset_type`
+pub fn asset_decimals(asset_type: AssetType) -> u8 {
+    match asset_type {
+        AssetType::SOL | AssetType::MSOL => SOL_DECIMALS,
+        AssetType::CbBTC => CBBTC_DECIMALS,
+        AssetType::USDC | AssetType::EURC => USD_DECIMALS,
+    }
+}
+
+/// One whole token of `decimals`, in native units (10^decimals)
+pub fn unit(decimals: u8) -> Result<u128> {
+    10u128
+        .checked_pow(decimals as u32)
+        .ok_or_else(|| error!(LegasiError::MathOverflow))
+}
+
+/// `amount * mul / div`, rounded down
+pub fn mul_div(amount: u64, mul: u128, div: u128) -> Result<u64> {
+    require!(div > 0, LegasiError::MathOverflow);
+    let value = (amount as u128)
+        .checked_mul(mul)
+        .ok_or(LegasiError::MathOverflow)?
+        / div;
+    u64::try_from(value).map_err(|_| error!(LegasiError::MathOverflow))
+}
+
+/// `amount * mul / div`, rounded up
+pub fn mul_div_ceil(amount: u64, mul: u128, div: u128) -> Result<u64> {
+    require!(div > 0, LegasiError::MathOverflow);
+    let value = (amount as u128)
+        .checked_mul(mul)
+        .and_then(|product| product.checked_add(div - 1))
+        .ok_or(LegasiError::MathOverflow)?
+        / div;
+    u64::try_from(value).map_err(|_| error!(LegasiError::MathOverflow))
+}
+
+impl UsdAmount {
+    pub const ZERO: Self = Self(0);
+
+    /// Native amount of a token with `decimals` worth this at `price_usd_6dec`,
+    /// rounded down
+    pub fn to_tokens(self, price_usd_6dec: u64, decimals: u8) -> Result<TokenAmount> {
+        require!(price_usd_6dec > 0, LegasiError::InvalidOracle);
+        Ok(TokenAmount::new(
+            mul_div(self.0, unit(decimals)?, price_usd_6dec as u128)?,
+            decimals,
+        ))
+    }
+
+    /// `to_tokens`, rounded up so the tokens cover the value
+    pub fn to_tokens_ceil(self, price_usd_6dec: u64, decimals: u8) -> Result<TokenAmount> {
+        require!(price_usd_6dec > 0, LegasiError::InvalidOracle);
+        Ok(TokenAmount::new(
+            mul_div_ceil(self.0, unit(decimals)?, price_usd_6dec as u128)?,
+            decimals,
+        ))
+    }
+
+    pub fn checked_add(self, other: Self) -> Result<Self> {
+        self.0
+            .checked_add(other.0)
+            .map(Self)
+            .ok_or_else(|| error!(LegasiError::MathOverflow))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+}
+
+impl TokenAmount {
+    pub fn new(amount: u64, decimals: u8) -> Self {
+        Self { amount, decimals }
+    }
+
+    /// SOL amount in lamports
+    pub fn lamports(amount: u64) -> Self {
+        Self::new(amount, SOL_DECIMALS)
+    }
+
+    /// Native amount of `asset_type`
+    pub fn of(asset_type: AssetType, amount: u64) -> Self {
+        Self::new(amount, asset_decimals(asset_type))
+    }
+
+    /// USD value at `price_usd_6dec` per whole token, rounded down
+    pub fn to_usd(self, price_usd_6dec: u64) -> Result<UsdAmount> {
+        Ok(UsdAmount(mul_div(
+            self.amount,
+            price_usd_6dec as u128,
+            unit(self.decimals)?,
+        )?))
+    }
+
+    /// The same quantity at `decimals`, rounded down when dropping precision
+    pub fn rescale(self, decimals: u8) -> Result<Self> {
+        let amount = if decimals >= self.decimals {
+            mul_div(self.amount, unit(decimals - self.decimals)?, 1)?
+        } else {
+            mul_div(self.amount, 1, unit(self.decimals - decimals)?)?
+        };
+        Ok(Self::new(amount, decimals))
+    }
+}
+
+/// USD value (6 decimals) of `amount` of a token with `decimals` at `price_usd_6dec`
+pub fn token_to_usd(amount: u64, decimals: u8, price_usd_6dec: u64) -> Result<u64> {
+    Ok(TokenAmount::new(amount, decimals).to_usd(price_usd_6dec)?.0)
+}
+
+/// USD value (6 decimals) of `lamports` at `sol_price` (6 decimals)
+pub fn sol_to_usd(lamports: u64, sol_price: u64) -> Result<u64> {
+    Ok(TokenAmount::lamports(lamports).to_usd(sol_price)?.0)
+}
+
+/// Lamports worth `usd` (6 decimals) at `sol_price`, rounded down
+pub fn usd_to_lamports(usd: u64, sol_price: u64) -> Result<u64> {
+    Ok(UsdAmount(usd).to_tokens(sol_price, SOL_DECIMALS)?.amount)
+}
+
+/// Lamports worth `usd` (6 decimals) at `sol_price`, rounded up
+pub fn usd_to_lamports_ceil(usd: u64, sol_price: u64) -> Result<u64> {
+    Ok(UsdAmount(usd)
+        .to_tokens_ceil(sol_price, SOL_DECIMALS)?
+        .amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{LAMPORTS_PER_SOL, USD_MULTIPLIER};
+
+    const ALL_ASSETS: [AssetType; 5] = [
+        AssetType::USDC,
+        AssetType::EURC,
+        AssetType::SOL,
+        AssetType::CbBTC,
+        AssetType::MSOL,
+    ];
+
+    #[test]
+    fn test_asset_decimals() {
+        assert_eq!(asset_decimals(AssetType::SOL), 9);
+        assert_eq!(asset_decimals(AssetType::MSOL), 9);
+        assert_eq!(asset_decimals(AssetType::CbBTC), 8);
+        assert_eq!(asset_decimals(AssetType::USDC), 6);
+        assert_eq!(asset_decimals(AssetType::EURC), 6);
+        assert_eq!(unit(SOL_DECIMALS).unwrap(), LAMPORTS_PER_SOL as u128);
+        assert_eq!(unit(USD_DECIMALS).unwrap(), USD_MULTIPLIER as u128);
+    }
+
+    #[test]
+    fn test_one_whole_token_is_worth_its_price() {
+        for asset_type in ALL_ASSETS {
+            let one = unit(asset_decimals(asset_type)).unwrap() as u64;
+            assert_eq!(
+                TokenAmount::of(asset_type, one)
+                    .to_usd(123_456_789)
+                    .unwrap(),
+                UsdAmount(123_456_789),
+                "{:?}",
+                asset_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_cbbtc_is_not_scaled_like_sol() {
+        // 0.5 cbBTC at $60,000 is $30,000; read as lamports it would be $3,000
+        let half_btc = TokenAmount::of(AssetType::CbBTC, 50_000_000);
+        assert_eq!(
+            half_btc.to_usd(60_000_000_000).unwrap(),
+            UsdAmount(30_000_000_000)
+        );
+        assert_eq!(
+            sol_to_usd(50_000_000, 60_000_000_000).unwrap(),
+            3_000_000_000
+        );
+        assert_eq!(
+            UsdAmount(30_000_000_000)
+                .to_tokens(60_000_000_000, CBBTC_DECIMALS)
+                .unwrap(),
+            half_btc
+        );
+    }
+
+    #[test]
+    fn test_sol_conversions() {
+        // 2 SOL at $100
+        assert_eq!(
+            sol_to_usd(2 * LAMPORTS_PER_SOL, 100_000_000).unwrap(),
+            200_000_000
+        );
+        assert_eq!(
+            usd_to_lamports(200_000_000, 100_000_000).unwrap(),
+            2 * LAMPORTS_PER_SOL
+        );
+        // $1 at $3/SOL: 333,333,333.33 lamports
+        assert_eq!(usd_to_lamports(1_000_000, 3_000_000).unwrap(), 333_333_333);
+        assert_eq!(
+            usd_to_lamports_ceil(1_000_000, 3_000_000).unwrap(),
+            333_333_334
+        );
+        // Exact amounts round the same both ways
+        assert_eq!(
+            usd_to_lamports_ceil(200_000_000, 100_000_000).unwrap(),
+            2 * LAMPORTS_PER_SOL
+        );
+        assert!(usd_to_lamports(1, 0).is_err());
+        assert!(usd_to_lamports_ceil(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_stablecoin_conversions() {
+        // 500 EURC at $1.08
+        assert_eq!(
+            token_to_usd(500_000_000, USD_DECIMALS, 1_080_000).unwrap(),
+            540_000_000
+        );
+        // USDC at $1 is its own USD value
+        assert_eq!(
+            TokenAmount::of(AssetType::USDC, 1_234_567)
+                .to_usd(USD_MULTIPLIER)
+                .unwrap(),
+            UsdAmount(1_234_567)
+        );
+    }
+
+    #[test]
+    fn test_rounding_down() {
+        // 1 lamport at $100 is a tenth of a micro-dollar
+        assert_eq!(sol_to_usd(1, 100_000_000).unwrap(), 0);
+        assert_eq!(sol_to_usd(10, 100_000_000).unwrap(), 1);
+        assert_eq!(token_to_usd(1, CBBTC_DECIMALS, 99_999_999).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_round_trip_never_gains() {
+        let prices = [
+            1,
+            999_999,
+            1_000_000,
+            3_000_000,
+            173_123_456,
+            97_000_000_000,
+        ];
+        let amounts = [0, 1, 7, 999_999, LAMPORTS_PER_SOL, 123_456_789_012];
+        for asset_type in ALL_ASSETS {
+            let decimals = asset_decimals(asset_type);
+            for price in prices {
+                for amount in amounts {
+                    let tokens = TokenAmount::of(asset_type, amount);
+                    let usd = tokens.to_usd(price).unwrap();
+                    let back = usd.to_tokens(price, decimals).unwrap();
+                    assert!(back.amount <= amount);
+                    // Rounding up buys back at least the value
+                    let covering = usd.to_tokens_ceil(price, decimals).unwrap();
+                    assert!(covering.to_usd(price).unwrap() >= usd);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rescale() {
+        let one_sol = TokenAmount::lamports(LAMPORTS_PER_SOL);
+        assert_eq!(one_sol.rescale(6).unwrap(), TokenAmount::new(1_000_000, 6));
+        assert_eq!(TokenAmount::new(1_000_000, 6).rescale(9).unwrap(), one_sol);
+        // Dropping precision rounds down
+        assert_eq!(
+            TokenAmount::new(123_456_789, 8).rescale(6).unwrap(),
+            TokenAmount::new(1_234_567, 6)
+        );
+        assert_eq!(one_sol.rescale(9).unwrap(), one_sol);
+        assert!(TokenAmount::new(u64::MAX, 0).rescale(9).is_err());
+    }
+
+    #[test]
+    fn test_overflow_fails() {
+        assert!(token_to_usd(u64::MAX, 0, u64::MAX).is_err());
+        assert!(sol_to_usd(u64::MAX, u64::MAX).is_err());
+        assert!(usd_to_lamports(u64::MAX, 1).is_err());
+        assert!(unit(39).is_err());
+        assert!(mul_div(1, 1, 0).is_err());
+        assert!(UsdAmount(u64::MAX).checked_add(UsdAmount(1)).is_err());
+        assert_eq!(UsdAmount(1).saturating_sub(UsdAmount(2)), UsdAmount::ZERO);
+    }
+}
@@ -76,4 +76,235 @@ pub enum LegasiError {
 
     #[msg("Invalid slot provided")]
     InvalidSlot,
+
+    #[msg("Outflow limit exceeded for this window")]
+    OutflowLimitExceeded,
+
+    #[msg("Invalid swap program")]
+    InvalidSwapProgram,
+
+    #[msg("Position is staked with a different provider")]
+    StakeProviderMismatch,
+
+    #[msg("Automation thread is not due")]
+    ThreadNotDue,
+
+    #[msg("Thread target was not cranked")]
+    ThreadTargetNotAdvanced,
+
+    #[msg("Insufficient fee budget")]
+    InsufficientFeeBudget,
+
+    #[msg("Invalid automation thread target")]
+    InvalidThreadTarget,
+
+    #[msg("Invalid token metadata")]
+    InvalidTokenMetadata,
+
+    #[msg("Invalid CCTP message")]
+    InvalidCctpMessage,
+
+    #[msg("Invalid admin operation")]
+    InvalidAdminOp,
+
+    #[msg("GAD suppressed by a kept repayment schedule")]
+    GadSuppressedBySchedule,
+
+    #[msg("Payment does not match the service listing")]
+    ServiceListingMismatch,
+
+    #[msg("Exceeds credit line limit")]
+    ExceedsCreditLine,
+
+    #[msg("Sponsor backstop must be drawn before the borrower's collateral")]
+    BackstopNotExhausted,
+
+    #[msg("Sponsorship cannot end while the position has debt")]
+    SponsorshipLocked,
+
+    #[msg("Price volatility too high to open new leverage")]
+    VolatilityTooHigh,
+
+    #[msg("Borrowing paused: pool utilization above the emergency cap")]
+    UtilizationPaused,
+
+    #[msg("Exceeds the position's debt cap")]
+    ExceedsDebtCap,
+
+    #[msg("Off-ramp request already settled")]
+    OfframpNotPending,
+
+    #[msg("Scheduled deposit is not due")]
+    DepositNotDue,
+
+    #[msg("Letter of credit has expired")]
+    LetterOfCreditExpired,
+
+    #[msg("Letter of credit has not expired")]
+    LetterOfCreditActive,
+
+    #[msg("Market is gated: allowlist entry or membership token required")]
+    MarketGated,
+
+    #[msg("Price is outside the feed's deviation band")]
+    PriceDeviationTooHigh,
+
+    #[msg("Position is already at or below the target LTV")]
+    AlreadyBelowTargetLtv,
+
+    #[msg("Invalid auto-deleverage order: target must be below trigger, tip within the cap")]
+    InvalidAutoDeleverageOrder,
+
+    #[msg("Auto-deleverage order is not triggered")]
+    AutoDeleverageNotTriggered,
+
+    #[msg("Lock duration out of range")]
+    InvalidLockDuration,
+
+    #[msg("Utilization is below the crisis level for LP locks")]
+    NoLiquidityCrisis,
+
+    #[msg("LP shares are still locked")]
+    LpSharesLocked,
+
+    #[msg("Pool is permissioned: LP allowlist entry required")]
+    LpNotAllowlisted,
+
+    #[msg("Account is not owned by this program")]
+    NotProgramOwned,
+
+    #[msg("Insurance fund too small")]
+    InsufficientInsuranceFund,
+
+    #[msg("Insurance fund is not above its target")]
+    NoInsuranceSurplus,
+
+    #[msg("Surplus auction has ended")]
+    SurplusAuctionEnded,
+
+    #[msg("Surplus auction is still running")]
+    SurplusAuctionRunning,
+
+    #[msg("Bid too low")]
+    BidTooLow,
+
+    #[msg("Liquidation split must sum to 100%, mostly repay debt, and cap the cranker")]
+    InvalidLiquidationSplit,
+
+    #[msg("Liquidation split pays an account that was not passed")]
+    MissingProceedsAccount,
+
+    #[msg("Debt is already in that asset")]
+    SameDebtAsset,
+
+    #[msg("Position is not past the hard liquidation threshold")]
+    NotLiquidatable,
+
+    #[msg("Liquidation auction is still running")]
+    LiquidationAuctionRunning,
+
+    #[msg("wSOL withdrawal needs the owner's wSOL account and the token programs")]
+    MissingWsolAccount,
+
+    #[msg("A borrowed asset's Borrowable and LP pool were not passed")]
+    MissingDebtPool,
+
+    #[msg("Voting power can only be snapshotted during the current epoch")]
+    NotCurrentEpoch,
+
+    #[msg("Invalid market parameters")]
+    InvalidMarketParams,
+
+    #[msg("Treasury conversions sell a non-USDC holding into the USDC vault")]
+    InvalidTreasuryConversion,
+
+    #[msg("Treasury holds less than the amount to convert")]
+    InsufficientTreasuryBalance,
+
+    #[msg("Position's collateral or debt is outside the market or eMode category")]
+    EModeMismatch,
+
+    #[msg("Flash loan has no flash_repay later in the transaction")]
+    FlashRepayMissing,
+
+    #[msg("Agent action within its rate limit interval")]
+    AgentRateLimited,
+
+    #[msg("Oracle sources disagree beyond the feed's deviation threshold")]
+    OracleDeviation,
+
+    #[msg("Withdrawal destination is not on the position's allowlist")]
+    WithdrawalDestinationNotAllowed,
+
+    #[msg("No allowlist change is ready to apply")]
+    AllowlistChangeLocked,
+
+    #[msg("Owner active too recently, or the succession claim is not past its challenge window")]
+    SuccessionNotReady,
+
+    #[msg("Leverage position has no expiry or has not expired")]
+    LeverageNotExpired,
+
+    #[msg("Price circuit breaker tripped: borrows and withdrawals are halted")]
+    CircuitBreakerTripped,
+
+    #[msg("Statement period has not ended yet")]
+    StatementNotDue,
+
+    #[msg("LP withdrawals are queued: request_withdraw instead")]
+    WithdrawQueueActive,
+
+    #[msg("Withdrawal request is not at the head of the queue")]
+    NotQueueHead,
+
+    #[msg("Oracle synced within the failover window: no manual price")]
+    OracleNotStale,
+
+    #[msg("Failover price too far from the last oracle price")]
+    FailoverPriceOutOfBounds,
+
+    #[msg("No rewards to claim")]
+    NoRewardsToClaim,
+
+    #[msg("Crank doesn't match the market's GAD proceeds preference")]
+    GadProceedsModeMismatch,
+
+    #[msg("Position LTV below the subscription's alert level")]
+    LtvBelowAlert,
+
+    #[msg("Subscription notified too recently")]
+    NotificationTooSoon,
+
+    #[msg("Not enough prepaid fees for a notice")]
+    SubscriptionUnfunded,
+
+    #[msg("Notification service inactive")]
+    NotifyServiceInactive,
+
+    #[msg("No protocol fees owed")]
+    NoProtocolFeesOwed,
+
+    #[msg("Invalid GAD throttle window")]
+    InvalidThrottleWindow,
+
+    #[msg("Asset supply cap reached")]
+    SupplyCapExceeded,
+
+    #[msg("Asset borrow cap reached")]
+    BorrowCapExceeded,
+
+    #[msg("Collateral already listed")]
+    CollateralAlreadyListed,
+
+    #[msg("Position still holds collateral worth liquidating")]
+    DebtStillRecoverable,
+
+    #[msg("Asset is already borrowed on other terms")]
+    BorrowTermMismatch,
+
+    #[msg("Loan term out of range")]
+    InvalidLoanTerm,
+
+    #[msg("Fixed rate above the borrower's maximum")]
+    FixedRateAboveMax,
 }
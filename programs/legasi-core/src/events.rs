@@ -1,3 +1,6 @@
+use crate::automation::ThreadKind;
+use crate::gad_schedule::ThrottleWindow;
+use crate::market::GadProceedsMode;
 use crate::state::AssetType;
 use anchor_lang::prelude::*;
 
@@ -7,6 +10,18 @@ pub struct ProtocolInitialized {
     pub treasury: Pubkey,
 }
 
+#[event]
+pub struct AdminTransferred {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminOpsExecuted {
+    pub admin: Pubkey,
+    pub op_count: u8,
+}
+
 #[event]
 pub struct CollateralRegistered {
     pub mint: Pubkey,
@@ -44,6 +59,17 @@ pub struct CollateralWithdrawn {
     pub amount: u64,
 }
 
+#[event]
+pub struct DepositLotRecorded {
+    pub position: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub price_usd_6dec: u64,
+    /// Over the lots the receipt keeps, this one included
+    pub cost_basis_usd: u64,
+    pub avg_holding_secs: i64,
+}
+
 #[event]
 pub struct Borrowed {
     pub position: Pubkey,
@@ -51,6 +77,18 @@ pub struct Borrowed {
     pub asset_type: AssetType,
     pub amount: u64,
     pub new_ltv_bps: u64,
+    /// Integrator's invoice / memo hash, if given
+    pub reference: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct FixedTermBorrowed {
+    pub position: Pubkey,
+    pub asset_type: AssetType,
+    pub amount: u64,
+    pub fixed_rate_bps: u16,
+    /// When the unpaid balance rolls to the variable rate plus penalty
+    pub maturity: i64,
 }
 
 #[event]
@@ -60,6 +98,21 @@ pub struct Repaid {
     pub asset_type: AssetType,
     pub amount: u64,
     pub interest_paid: u64,
+    /// Integrator's invoice / memo hash, if given
+    pub reference: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct PositionLiquidated {
+    pub position: Pubkey,
+    pub liquidator: Pubkey,
+    pub asset_type: AssetType,
+    /// Debt the liquidator repaid (asset units)
+    pub repaid: u64,
+    pub collateral_seized_lamports: u64,
+    pub ltv_before_bps: u64,
+    /// Principal written off once no collateral was left (asset units)
+    pub bad_debt: u64,
 }
 
 #[event]
@@ -74,6 +127,15 @@ pub struct GadExecuted {
     pub cranker_reward: u64,
 }
 
+#[event]
+pub struct PriceAnomalyDetected {
+    pub asset_type: AssetType,
+    pub price_usd_6dec: u64,
+    pub reference_price_usd_6dec: u64,
+    pub deviation_bps: u64,
+    pub max_deviation_bps: u16,
+}
+
 #[event]
 pub struct LpDeposited {
     pub depositor: Pubkey,
@@ -90,6 +152,168 @@ pub struct LpWithdrawn {
     pub amount_received: u64,
 }
 
+#[event]
+pub struct LpWithdrawQueued {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub request_id: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct LpWithdrawRequestPaid {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub request_id: u64,
+    pub shares_burned: u64,
+    pub amount_received: u64,
+    /// Shares still queued on the request (0 = paid in full and closed)
+    pub shares_remaining: u64,
+}
+
+#[event]
+pub struct LpDepositLocked {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    /// Share multiplier of the term (bps)
+    pub multiplier_bps: u16,
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct LpDepositUnlocked {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub shares: u64,
+    /// Redemption net of the penalty, boost included
+    pub amount_received: u64,
+    /// Boost paid (0 on an early exit, which forfeits it)
+    pub boost: u64,
+    /// Early-exit penalty sent to the insurance fund
+    pub penalty: u64,
+}
+
+#[event]
+pub struct RewardsFunded {
+    pub pool: Pubkey,
+    pub reward_mint: Pubkey,
+    pub amount: u64,
+    /// Funded rewards not emitted yet, including `amount`
+    pub undistributed: u64,
+}
+
+#[event]
+pub struct EmissionRateSet {
+    pub pool: Pubkey,
+    pub reward_mint: Pubkey,
+    /// Reward tokens per second across all stakers
+    pub emission_rate: u64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub reward_mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ProtocolFeeSwitchSet {
+    pub pool: Pubkey,
+    pub enabled: bool,
+    /// Cut of repaid interest now taken for the treasury (bps)
+    pub protocol_fee_bps: u64,
+}
+
+#[event]
+pub struct ProtocolFeesClaimed {
+    pub pool: Pubkey,
+    pub treasury_token_account: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ReserveFactorSet {
+    pub pool: Pubkey,
+    /// Cut of repaid interest diverted to the pool's reserves (bps)
+    pub reserve_factor_bps: u16,
+}
+
+#[event]
+pub struct ReservesReleased {
+    pub pool: Pubkey,
+    pub amount: u64,
+    /// Left in the reserve vault
+    pub remaining: u64,
+}
+
+#[event]
+pub struct BadDebtCovered {
+    pub pool: Pubkey,
+    pub amount: u64,
+    /// Bad debt the pool's LPs still carry
+    pub remaining: u64,
+}
+
+#[event]
+pub struct RateUpdated {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub utilization_bps: u64,
+    /// APR borrows accrue at until utilization changes (bps)
+    pub borrow_rate_bps: u64,
+    /// APR LPs earn at that rate, net of the insurance cut (bps)
+    pub supply_rate_bps: u64,
+}
+
+#[event]
+pub struct SurplusAuctionStarted {
+    pub pool: Pubkey,
+    pub auction: Pubkey,
+    pub lot: u64,
+    pub ends_at: i64,
+}
+
+#[event]
+pub struct SurplusBid {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub shares: u64,
+    pub ends_at: i64,
+}
+
+#[event]
+pub struct SurplusAuctionSettled {
+    pub pool: Pubkey,
+    pub auction: Pubkey,
+    pub winner: Pubkey,
+    pub lot: u64,
+    pub shares_burned: u64,
+}
+
+#[event]
+pub struct BadDebtWrittenOff {
+    pub pool: Pubkey,
+    pub principal: u64,
+    /// Covered by the insurance fund; LPs absorb the rest
+    pub covered: u64,
+}
+
+#[event]
+pub struct BadDebtSocialized {
+    pub position: Pubkey,
+    pub asset_type: AssetType,
+    /// Principal written off (asset units); its unpaid interest is dropped
+    pub principal: u64,
+    /// Paid by the insurance fund
+    pub insurance_covered: u64,
+    /// Taken out of the pool's deposits, lowering its LP share price
+    pub socialized: u64,
+}
+
 #[event]
 pub struct FlashLoanInitiated {
     pub borrower: Pubkey,
@@ -126,6 +350,18 @@ pub struct LeverageClosed {
     pub pnl_usd: i64,
 }
 
+#[event]
+pub struct LeverageUnwound {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub keeper: Pubkey,
+    pub sol_sold: u64,
+    pub debt_repaid: u64,
+    pub keeper_fee: u64,
+    /// USDC left after the debt and fee, paid to the owner
+    pub surplus: u64,
+}
+
 #[event]
 pub struct PriceUpdated {
     pub asset_type: AssetType,
@@ -133,6 +369,14 @@ pub struct PriceUpdated {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct CircuitBreakerTripped {
+    pub mint: Pubkey,
+    pub reference_price: u64,
+    pub price_usd_6dec: u64,
+    pub tripped_until: i64,
+}
+
 // ========== MULTI-MARKET EVENTS ==========
 
 #[event]
@@ -162,6 +406,53 @@ pub struct MarketToggled {
     pub collateral_enabled: bool,
 }
 
+#[event]
+pub struct MarketGadProceedsSet {
+    pub market_id: u16,
+    pub mode: GadProceedsMode,
+    /// Cranks a `Twap` sale is spread over (0 otherwise)
+    pub twap_slices: u8,
+}
+
+#[event]
+pub struct GadScheduleSet {
+    pub windows: Vec<ThrottleWindow>,
+    pub hard_threshold_extra_bps: u16,
+}
+
+#[event]
+pub struct SupplyCapSet {
+    pub mint: Pubkey,
+    pub previous_cap: u64,
+    pub supply_cap: u64,
+}
+
+#[event]
+pub struct BorrowCapSet {
+    pub mint: Pubkey,
+    pub previous_cap: u64,
+    pub borrow_cap: u64,
+}
+
+#[event]
+pub struct CollateralListingProposed {
+    pub mint: Pubkey,
+    pub proposer: Pubkey,
+    pub asset_type: AssetType,
+    pub max_ltv_bps: u16,
+    pub liquidation_threshold_bps: u16,
+    pub bond_lamports: u64,
+}
+
+#[event]
+pub struct CollateralListingDecided {
+    pub mint: Pubkey,
+    pub proposer: Pubkey,
+    pub approved: bool,
+    /// Bond sent to the treasury (lamports, 0 = refunded)
+    pub bond_slashed: u64,
+}
+
 #[event]
 pub struct EModeSet {
     pub position: Pubkey,
@@ -169,3 +460,36 @@ pub struct EModeSet {
     pub old_category: u8,
     pub new_category: u8,
 }
+
+// ========== AUTOMATION EVENTS ==========
+
+#[event]
+pub struct ThreadRegistered {
+    pub thread: Pubkey,
+    pub fee_budget: Pubkey,
+    pub kind: ThreadKind,
+    pub target: Pubkey,
+    pub interval_secs: i64,
+    pub fee_per_exec: u64,
+}
+
+#[event]
+pub struct ThreadExecuted {
+    pub thread: Pubkey,
+    pub kind: ThreadKind,
+    pub target: Pubkey,
+    pub executor: Pubkey,
+    pub fee_paid: u64,
+}
+
+// ========== TREASURY EVENTS ==========
+
+#[event]
+pub struct TreasuryConverted {
+    pub source_mint: Pubkey,
+    pub amount_sold: u64,
+    pub usdc_received: u64,
+    /// Oracle value of `amount_sold` in USDC (6 decimals)
+    pub oracle_value_usdc: u64,
+    pub cranker: Pubkey,
+}
@@ -0,0 +1,529 @@
+//! # GAD Math
+//!
+//! Single source of truth for Gradual Auto-Deleveraging sizing, shared by every
+//! crank (`crank_gad`, `crank_gad_with_swap`, `crank_gad_lst_with_swap`) and
+//! mirrored by the SDK, so the curve and thresholds can't drift between callers.
+//!
+//! 1. `assess` - crank interval, LTV above max, daily rate from the curve
+//! 2. `liquidate_fraction_bps` - daily rate pro-rated to the time since the last crank,
+//!    or to `pace_sale`'s share of it in a market that sells over several cranks
+//! 3. `cranker_reward_bps` - keeper reward, higher for riskier and staler positions
+//! 4. `split_liquidation` - collateral slice, split between treasury and cranker
+//! 5. `LiquidationSplit::split_proceeds` - the non-cranker part, split between LP
+//!    recovery, treasury and insurance fund when the admin configured a split
+
+use anchor_lang::prelude::*;
+
+use crate::constants::{
+    BPS_DENOMINATOR, GAD_HARD_RATE_BPS, GAD_HARD_THRESHOLD_BPS, MAX_CRANKER_REWARD_BPS,
+    MIN_GAD_CRANK_INTERVAL, MIN_GAD_LP_RECOVERY_BPS, SECONDS_PER_DAY,
+};
+use crate::errors::LegasiError;
+
+/// GAD rate curve (bps per day) - continuous quadratic in the excess over max LTV,
+/// capped at `GAD_HARD_RATE_BPS`
+pub fn rate_bps(current_ltv_bps: u64, max_ltv_bps: u64) -> u64 {
+    if current_ltv_bps <= max_ltv_bps {
+        return 0;
+    }
+
+    let excess_bps = current_ltv_bps - max_ltv_bps;
+
+    // rate = (excess/100)^2
+    let rate = (excess_bps as u128).saturating_pow(2) / 100;
+
+    std::cmp::min(rate, GAD_HARD_RATE_BPS as u128) as u64
+}
+
+/// LTV in bps, `None` without collateral
+pub fn ltv_bps(debt_usd: u64, collateral_usd: u64) -> Option<u64> {
+    if collateral_usd == 0 {
+        return None;
+    }
+    Some(((debt_usd as u128) * (BPS_DENOMINATOR as u128) / (collateral_usd as u128)) as u64)
+}
+
+/// Fraction of collateral to liquidate for `elapsed` seconds at a daily GAD rate
+pub fn liquidate_fraction_bps(gad_rate_bps: u64, elapsed: i64) -> Result<u64> {
+    let time_fraction = (elapsed as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(LegasiError::MathOverflow)?
+        .checked_div(SECONDS_PER_DAY as u128)
+        .ok_or(LegasiError::MathOverflow)? as u64;
+
+    Ok((gad_rate_bps as u128)
+        .checked_mul(time_fraction as u128)
+        .ok_or(LegasiError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(LegasiError::MathOverflow)? as u64)
+}
+
+/// Split the time since the last crank, plus the time carried from earlier cranks,
+/// into the time liquidated now (1/`slices` of it) and the time carried to the next
+/// crank. One slice liquidates everything due
+pub fn pace_sale(elapsed: i64, carried: i64, slices: u8) -> (i64, i64) {
+    let due = elapsed.max(0).saturating_add(carried.max(0));
+    let now = due / slices.max(1) as i64;
+    (now, due - now)
+}
+
+/// Outcome of checking a position for GAD
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GadAssessment {
+    pub ltv_bps: u64,
+    /// Daily rate from the curve
+    pub rate_bps: u64,
+    /// Fraction of collateral to liquidate this crank
+    pub liquidate_fraction_bps: u64,
+}
+
+/// Check a position is eligible for GAD and size this crank.
+/// Fails if cranked too soon, without collateral, or at/below max LTV.
+pub fn assess(
+    collateral_usd: u64,
+    debt_usd: u64,
+    max_ltv_bps: u64,
+    elapsed: i64,
+) -> Result<GadAssessment> {
+    require!(elapsed >= MIN_GAD_CRANK_INTERVAL, LegasiError::CrankTooSoon);
+
+    let ltv_bps = ltv_bps(debt_usd, collateral_usd).ok_or(LegasiError::InsufficientCollateral)?;
+    require!(ltv_bps > max_ltv_bps, LegasiError::LtvBelowGadThreshold);
+
+    let rate_bps = rate_bps(ltv_bps, max_ltv_bps);
+    require!(rate_bps > 0, LegasiError::NothingToLiquidate);
+
+    Ok(GadAssessment {
+        ltv_bps,
+        rate_bps,
+        liquidate_fraction_bps: liquidate_fraction_bps(rate_bps, elapsed)?,
+    })
+}
+
+/// Cranker reward in bps of the slice, between the protocol's `min_bps` and `max_bps`
+/// Half the weight is LTV excess over max (full at `GAD_HARD_THRESHOLD_BPS` over),
+/// half is time since the last crank (full after a day), so keepers go to the
+/// riskiest, most neglected positions first
+pub fn cranker_reward_bps(
+    ltv_bps: u64,
+    max_ltv_bps: u64,
+    elapsed: i64,
+    min_bps: u16,
+    max_bps: u16,
+) -> u64 {
+    let hard_excess = GAD_HARD_THRESHOLD_BPS as u64;
+    let excess = std::cmp::min(ltv_bps.saturating_sub(max_ltv_bps), hard_excess);
+    let urgency = excess * BPS_DENOMINATOR / hard_excess;
+
+    let staleness =
+        (elapsed.clamp(0, SECONDS_PER_DAY) as u64) * BPS_DENOMINATOR / SECONDS_PER_DAY as u64;
+
+    let weight = (urgency + staleness) / 2;
+    let range = (max_bps as u64).saturating_sub(min_bps as u64);
+    (min_bps as u64) + range * weight / BPS_DENOMINATOR
+}
+
+/// One GAD liquidation slice, split between treasury and cranker
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GadSplit {
+    /// Collateral sent to treasury (covers debt)
+    pub to_treasury: u64,
+    /// Collateral paid to the cranker
+    pub cranker_reward: u64,
+    /// Total collateral removed from the position (to_treasury + cranker_reward)
+    pub total_deducted: u64,
+}
+
+/// Size a GAD slice so that transfers and bookkeeping always match.
+/// The cranker reward (`cranker_reward_bps` of the slice) is carved out of the slice
+/// rather than added on top, and the slice is capped so the remaining collateral
+/// never drops below the floor.
+pub fn split_liquidation(
+    collateral_amount: u64,
+    liquidate_fraction_bps: u64,
+    collateral_floor: u64,
+    cranker_reward_bps: u64,
+) -> Result<GadSplit> {
+    let fraction_bps = std::cmp::min(liquidate_fraction_bps, BPS_DENOMINATOR);
+    let slice = (collateral_amount as u128)
+        .checked_mul(fraction_bps as u128)
+        .ok_or(LegasiError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(LegasiError::MathOverflow)? as u64;
+
+    let available = collateral_amount.saturating_sub(collateral_floor);
+    let total_deducted = std::cmp::min(slice, available);
+
+    let cranker_reward = (total_deducted as u128)
+        .checked_mul(std::cmp::min(cranker_reward_bps, BPS_DENOMINATOR) as u128)
+        .ok_or(LegasiError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(LegasiError::MathOverflow)? as u64;
+    let to_treasury = total_deducted
+        .checked_sub(cranker_reward)
+        .ok_or(LegasiError::MathOverflow)?;
+
+    Ok(GadSplit {
+        to_treasury,
+        cranker_reward,
+        total_deducted,
+    })
+}
+
+/// Admin-configured split of GAD-liquidated value, in bps of the slice (`Protocol.liquidation_split`)
+/// All zero = unset: the cranker gets the `cranker_reward_bps` curve and the rest covers debt
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct LiquidationSplit {
+    /// Repays the position's debt, i.e. back to LPs
+    pub lp_recovery_bps: u16,
+    pub treasury_bps: u16,
+    /// Credited to `Protocol.insurance_fund`
+    pub insurance_bps: u16,
+    pub cranker_bps: u16,
+}
+
+/// The non-cranker part of a slice, split by a `LiquidationSplit`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProceedsSplit {
+    pub lp_recovery: u64,
+    pub treasury: u64,
+    pub insurance: u64,
+}
+
+impl LiquidationSplit {
+    pub fn is_set(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// A set split covers the whole slice, mostly repays debt, and keeps the
+    /// cranker under `MAX_CRANKER_REWARD_BPS`
+    pub fn is_valid(&self) -> bool {
+        if !self.is_set() {
+            return true;
+        }
+        let total = self.lp_recovery_bps as u64
+            + self.treasury_bps as u64
+            + self.insurance_bps as u64
+            + self.cranker_bps as u64;
+        total == BPS_DENOMINATOR
+            && self.lp_recovery_bps >= MIN_GAD_LP_RECOVERY_BPS
+            && self.cranker_bps <= MAX_CRANKER_REWARD_BPS
+    }
+
+    /// Cranker reward (bps of the slice): the configured share, else `curve_bps`
+    pub fn cranker_reward_bps(&self, curve_bps: u64) -> u64 {
+        if self.is_set() {
+            self.cranker_bps as u64
+        } else {
+            curve_bps
+        }
+    }
+
+    /// Split what is left of a slice after the cranker reward, pro rata to the
+    /// non-cranker shares. LP recovery takes the rounding; unset, it takes everything
+    pub fn split_proceeds(&self, amount: u64) -> ProceedsSplit {
+        let weights =
+            self.lp_recovery_bps as u128 + self.treasury_bps as u128 + self.insurance_bps as u128;
+        if !self.is_set() || weights == 0 {
+            return ProceedsSplit {
+                lp_recovery: amount,
+                treasury: 0,
+                insurance: 0,
+            };
+        }
+        let treasury = (amount as u128 * self.treasury_bps as u128 / weights) as u64;
+        let insurance = (amount as u128 * self.insurance_bps as u128 / weights) as u64;
+        ProceedsSplit {
+            lp_recovery: amount - treasury - insurance,
+            treasury,
+            insurance,
+        }
+    }
+
+    /// Split swap output that sold only the LP recovery and insurance shares
+    /// Returns (lp_recovery, insurance); unset, all of it is LP recovery
+    pub fn split_swap_output(&self, amount: u64) -> (u64, u64) {
+        let weights = self.lp_recovery_bps as u128 + self.insurance_bps as u128;
+        if !self.is_set() || weights == 0 {
+            return (amount, 0);
+        }
+        let insurance = (amount as u128 * self.insurance_bps as u128 / weights) as u64;
+        (amount - insurance, insurance)
+    }
+}
+
+/// Sources of one GAD slice when a sponsor posted a backstop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BackstopDraw {
+    pub treasury_from_backstop: u64,
+    pub reward_from_backstop: u64,
+    pub treasury_from_position: u64,
+    pub reward_from_position: u64,
+}
+
+impl BackstopDraw {
+    /// Total taken from the sponsor backstop
+    pub fn from_backstop(&self) -> u64 {
+        self.treasury_from_backstop + self.reward_from_backstop
+    }
+
+    /// Total taken from the position's own collateral
+    pub fn from_position(&self) -> u64 {
+        self.treasury_from_position + self.reward_from_position
+    }
+}
+
+/// Take a slice from the sponsor backstop (above its floor) before the position's collateral.
+/// The treasury part is drawn first, so the backstop covers debt before paying the cranker
+pub fn draw_backstop_first(
+    split: &GadSplit,
+    backstop_balance: u64,
+    backstop_floor: u64,
+) -> BackstopDraw {
+    let available = backstop_balance.saturating_sub(backstop_floor);
+    let treasury_from_backstop = std::cmp::min(split.to_treasury, available);
+    let reward_from_backstop =
+        std::cmp::min(split.cranker_reward, available - treasury_from_backstop);
+    BackstopDraw {
+        treasury_from_backstop,
+        reward_from_backstop,
+        treasury_from_position: split.to_treasury - treasury_from_backstop,
+        reward_from_position: split.cranker_reward - reward_from_backstop,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::LAMPORTS_PER_SOL;
+
+    /// Small xorshift PRNG so the property test needs no extra dependencies
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_rate_curve() {
+        // At or below max LTV: no GAD
+        assert_eq!(rate_bps(7_000, 7_500), 0);
+        assert_eq!(rate_bps(7_500, 7_500), 0);
+        // 1% over: 1%/day, 3% over: 9%/day
+        assert_eq!(rate_bps(7_600, 7_500), 100);
+        assert_eq!(rate_bps(7_800, 7_500), 900);
+        // 5% over is already past the cap
+        assert_eq!(rate_bps(8_000, 7_500), GAD_HARD_RATE_BPS);
+        assert_eq!(rate_bps(u64::MAX, 0), GAD_HARD_RATE_BPS);
+    }
+
+    #[test]
+    fn test_rate_curve_monotonic() {
+        let mut last = 0;
+        for ltv in 7_500..12_000 {
+            let rate = rate_bps(ltv, 7_500);
+            assert!(rate >= last);
+            last = rate;
+        }
+    }
+
+    #[test]
+    fn test_liquidate_fraction() {
+        // A full day at 10%/day liquidates 10%
+        assert_eq!(
+            liquidate_fraction_bps(1_000, SECONDS_PER_DAY).unwrap(),
+            1_000
+        );
+        // One hour at 10%/day
+        assert_eq!(liquidate_fraction_bps(1_000, 3_600).unwrap(), 41);
+    }
+
+    #[test]
+    fn test_pace_sale() {
+        // One slice sells everything due, carried time included
+        assert_eq!(pace_sale(3_600, 1_800, 1), (5_400, 0));
+        // A day's backlog over 4 cranks: a quarter now, the rest carried
+        assert_eq!(pace_sale(SECONDS_PER_DAY, 0, 4), (21_600, 64_800));
+        // Hourly cranks settle at selling an hour's worth each, three hours behind
+        assert_eq!(pace_sale(3_600, 3 * 3_600, 4), (3_600, 3 * 3_600));
+    }
+
+    #[test]
+    fn test_assess() {
+        // $1,000 collateral, $800 debt, 75% max: 80% LTV
+        let a = assess(1_000_000_000, 800_000_000, 7_500, SECONDS_PER_DAY).unwrap();
+        assert_eq!(a.ltv_bps, 8_000);
+        assert_eq!(a.rate_bps, rate_bps(8_000, 7_500));
+        assert_eq!(a.liquidate_fraction_bps, a.rate_bps);
+
+        // Healthy, too soon, and no collateral are all rejected
+        assert!(assess(1_000_000_000, 700_000_000, 7_500, SECONDS_PER_DAY).is_err());
+        assert!(assess(
+            1_000_000_000,
+            800_000_000,
+            7_500,
+            MIN_GAD_CRANK_INTERVAL - 1
+        )
+        .is_err());
+        assert!(assess(0, 800_000_000, 7_500, SECONDS_PER_DAY).is_err());
+    }
+
+    #[test]
+    fn test_split_invariants_random() {
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+
+        for _ in 0..10_000 {
+            let collateral = rng.next() >> (rng.next() % 64);
+            let fraction_bps = rng.next() % 20_000;
+            let floor = rng.next() >> (rng.next() % 64);
+            let reward_bps = rng.next() % 20_000;
+
+            let split = split_liquidation(collateral, fraction_bps, floor, reward_bps).unwrap();
+
+            // Transfers add up to exactly what is deducted from the position
+            assert_eq!(
+                split.to_treasury + split.cranker_reward,
+                split.total_deducted
+            );
+            // Never deduct more than the position holds, nor dip below the floor
+            assert!(split.total_deducted <= collateral);
+            assert!(split.total_deducted <= collateral.saturating_sub(floor));
+            // Reward is a fraction of the slice, never on top of it
+            assert!(split.cranker_reward <= split.total_deducted);
+        }
+    }
+
+    #[test]
+    fn test_split_example() {
+        // 10 SOL, 1% slice, no floor: 0.1 SOL removed, 0.5% of it to cranker
+        let split = split_liquidation(10 * LAMPORTS_PER_SOL, 100, 0, 50).unwrap();
+        assert_eq!(split.total_deducted, 100_000_000);
+        assert_eq!(split.cranker_reward, 500_000);
+        assert_eq!(split.to_treasury, 99_500_000);
+
+        // Floor caps the slice
+        let split = split_liquidation(1_000_000, 10_000, 890_880, 50).unwrap();
+        assert_eq!(split.total_deducted, 109_120);
+    }
+
+    #[test]
+    fn test_cranker_reward() {
+        // At max LTV with no time elapsed: the minimum
+        assert_eq!(cranker_reward_bps(7_500, 7_500, 0, 10, 200), 10);
+        assert_eq!(cranker_reward_bps(7_500, 7_500, 3_600, 10, 200), 13);
+        // 15% over max and a day stale: the maximum
+        assert_eq!(
+            cranker_reward_bps(9_000, 7_500, SECONDS_PER_DAY, 10, 200),
+            200
+        );
+        // Either factor alone gets half the range
+        assert_eq!(cranker_reward_bps(9_000, 7_500, 0, 10, 200), 105);
+        assert_eq!(
+            cranker_reward_bps(7_500, 7_500, SECONDS_PER_DAY, 10, 200),
+            105
+        );
+        // Riskier positions always pay at least as much
+        let mut last = 0;
+        for ltv in 7_500..10_000 {
+            let reward = cranker_reward_bps(ltv, 7_500, 3_600, 10, 200);
+            assert!(reward >= last && reward <= 200);
+            last = reward;
+        }
+    }
+
+    #[test]
+    fn test_backstop_drawn_first() {
+        let split = GadSplit {
+            to_treasury: 900,
+            cranker_reward: 100,
+            total_deducted: 1_000,
+        };
+
+        // Backstop covers the treasury part and some of the reward
+        let draw = draw_backstop_first(&split, 1_050, 100);
+        assert_eq!(
+            (draw.treasury_from_backstop, draw.reward_from_backstop),
+            (900, 50)
+        );
+        assert_eq!(draw.reward_from_position, 50);
+        assert_eq!(
+            draw.from_backstop() + draw.from_position(),
+            split.total_deducted
+        );
+
+        // Empty backstop: all from the position
+        let draw = draw_backstop_first(&split, 100, 100);
+        assert_eq!(draw.from_backstop(), 0);
+        assert_eq!(draw.from_position(), 1_000);
+    }
+
+    #[test]
+    fn test_liquidation_split() {
+        let unset = LiquidationSplit::default();
+        assert!(unset.is_valid());
+        assert_eq!(unset.cranker_reward_bps(150), 150);
+        assert_eq!(
+            unset.split_proceeds(1_000),
+            ProceedsSplit {
+                lp_recovery: 1_000,
+                treasury: 0,
+                insurance: 0,
+            }
+        );
+
+        let split = LiquidationSplit {
+            lp_recovery_bps: 8_000,
+            treasury_bps: 1_000,
+            insurance_bps: 500,
+            cranker_bps: 500,
+        };
+        assert!(split.is_valid());
+        assert_eq!(split.cranker_reward_bps(150), 500);
+
+        // 10,000 slice: 500 to the cranker, the rest 80:10:5
+        let gad_split = split_liquidation(10_000, BPS_DENOMINATOR, 0, 500).unwrap();
+        let proceeds = split.split_proceeds(gad_split.to_treasury);
+        assert_eq!(
+            (proceeds.lp_recovery, proceeds.treasury, proceeds.insurance),
+            (8_000, 1_000, 500)
+        );
+
+        // Swap cranks sell only the LP recovery and insurance shares: 80:5
+        assert_eq!(split.split_swap_output(8_500), (8_000, 500));
+        assert_eq!(unset.split_swap_output(8_500), (8_500, 0));
+
+        // Rounding goes to LP recovery, nothing is lost
+        let proceeds = split.split_proceeds(9_999);
+        assert_eq!(
+            proceeds.lp_recovery + proceeds.treasury + proceeds.insurance,
+            9_999
+        );
+
+        // Not summing to 100%, too little LP recovery, or too much for the cranker
+        for invalid in [
+            LiquidationSplit {
+                lp_recovery_bps: 9_000,
+                ..split
+            },
+            LiquidationSplit {
+                lp_recovery_bps: 4_000,
+                treasury_bps: 5_000,
+                ..split
+            },
+            LiquidationSplit {
+                lp_recovery_bps: 7_000,
+                cranker_bps: 1_500,
+                ..split
+            },
+        ] {
+            assert!(!invalid.is_valid());
+        }
+    }
+}
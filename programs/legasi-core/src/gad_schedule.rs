@@ -0,0 +1,195 @@
+//! # GAD Throttle Schedule
+//!
+//! Selling SOL collateral into thin weekend or overnight books costs borrowers more
+//! slippage than the risk it retires. Governance can set recurring weekly throttle
+//! windows in the `[GAD_SCHEDULE_SEED]` schedule: while one is open, GAD cranks
+//! liquidate at `THROTTLED_GAD_RATE_BPS` of their usual rate, and hard liquidations
+//! (lending's `liquidate_position`, GAD's liquidation auctions) need the LTV a further
+//! `hard_threshold_extra_bps` past their usual threshold.
+//!
+//! Windows are `[start, end)` in seconds since Monday 00:00 UTC, and wrap past the
+//! end of the week when `end < start` (e.g., Friday 22:00 to Monday 06:00 is one
+//! window). The cranks pass the schedule as an unchecked PDA, so without one nothing
+//! is throttled.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::{BPS_DENOMINATOR, SECONDS_PER_DAY};
+use crate::errors::LegasiError;
+
+/// Length of the schedule's week (seconds)
+pub const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+
+/// Share of the usual GAD rate applied inside a throttle window (bps)
+pub const THROTTLED_GAD_RATE_BPS: u64 = 5_000; // halved
+
+/// Most windows a schedule can hold
+pub const MAX_THROTTLE_WINDOWS: usize = 8;
+
+/// Cap on the extra LTV breach a window can demand of hard liquidations (bps)
+pub const MAX_THROTTLE_THRESHOLD_BPS: u16 = 1_000;
+
+/// Unix time 0 was a Thursday: Monday 00:00 UTC is 3 days later in the week
+const EPOCH_WEEK_OFFSET: i64 = 3 * SECONDS_PER_DAY;
+
+/// A recurring weekly window, in seconds since Monday 00:00 UTC
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct ThrottleWindow {
+    pub start: u32,
+    /// Exclusive; wraps past the end of the week when below `start`
+    pub end: u32,
+}
+
+impl ThrottleWindow {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            (self.start as i64) < SECONDS_PER_WEEK
+                && (self.end as i64) < SECONDS_PER_WEEK
+                && self.start != self.end,
+            LegasiError::InvalidThrottleWindow
+        );
+        Ok(())
+    }
+
+    /// True if `week_secs` (seconds since Monday 00:00 UTC) falls in the window
+    pub fn contains(&self, week_secs: u32) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&week_secs)
+        } else {
+            week_secs >= self.start || week_secs < self.end
+        }
+    }
+}
+
+/// Governance's GAD throttle windows
+#[account]
+#[derive(InitSpace)]
+pub struct GadSchedule {
+    #[max_len(8)]
+    pub windows: Vec<ThrottleWindow>,
+    /// Extra LTV breach (bps) hard liquidations need inside a window
+    pub hard_threshold_extra_bps: u16,
+    pub bump: u8,
+}
+
+impl GadSchedule {
+    pub fn validate(windows: &[ThrottleWindow], hard_threshold_extra_bps: u16) -> Result<()> {
+        require!(
+            windows.len() <= MAX_THROTTLE_WINDOWS,
+            LegasiError::InvalidThrottleWindow
+        );
+        require!(
+            hard_threshold_extra_bps <= MAX_THROTTLE_THRESHOLD_BPS,
+            LegasiError::InvalidAmount
+        );
+        windows.iter().try_for_each(ThrottleWindow::validate)
+    }
+
+    /// Throttle in force at `now`
+    pub fn throttle_at(&self, now: i64) -> Throttle {
+        let week_secs = (now + EPOCH_WEEK_OFFSET).rem_euclid(SECONDS_PER_WEEK) as u32;
+        if self.windows.iter().any(|w| w.contains(week_secs)) {
+            Throttle {
+                active: true,
+                hard_threshold_extra_bps: self.hard_threshold_extra_bps,
+            }
+        } else {
+            Throttle::default()
+        }
+    }
+}
+
+/// What a crank must apply at a given time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Throttle {
+    pub active: bool,
+    pub hard_threshold_extra_bps: u16,
+}
+
+impl Throttle {
+    /// Read the throttle at `now` from the unchecked `[GAD_SCHEDULE_SEED]` account.
+    /// Without a schedule nothing is throttled
+    pub fn load(schedule: &AccountInfo, now: i64) -> Result<Self> {
+        if schedule.owner != &crate::ID || schedule.data_is_empty() {
+            return Ok(Self::default());
+        }
+        let schedule = GadSchedule::try_deserialize(&mut &schedule.try_borrow_data()?[..])?;
+        Ok(schedule.throttle_at(now))
+    }
+
+    /// Share of collateral (bps) a GAD crank liquidates, for `fraction_bps` unthrottled
+    pub fn gad_fraction_bps(&self, fraction_bps: u64) -> u64 {
+        if self.active {
+            fraction_bps * THROTTLED_GAD_RATE_BPS / BPS_DENOMINATOR
+        } else {
+            fraction_bps
+        }
+    }
+
+    /// LTV (bps) from which a hard liquidation may run, for `threshold_bps` unthrottled
+    pub fn hard_threshold_bps(&self, threshold_bps: u64) -> u64 {
+        if self.active {
+            threshold_bps + self.hard_threshold_extra_bps as u64
+        } else {
+            threshold_bps
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u32 = 3_600;
+    const DAY: u32 = 24 * HOUR;
+
+    /// Friday 22:00 to Monday 06:00 UTC
+    fn weekend() -> GadSchedule {
+        GadSchedule {
+            windows: vec![ThrottleWindow {
+                start: 4 * DAY + 22 * HOUR,
+                end: 6 * HOUR,
+            }],
+            hard_threshold_extra_bps: 500,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_windows_wrap_the_week() {
+        let schedule = weekend();
+        // 2024-01-06 is a Saturday, 2024-01-08 a Monday, 2024-01-10 a Wednesday
+        let saturday_noon = 1_704_542_400;
+        let monday_5am = 1_704_690_000;
+        let monday_6am = monday_5am + HOUR as i64;
+        let wednesday = 1_704_888_000;
+        assert!(schedule.throttle_at(saturday_noon).active);
+        assert!(schedule.throttle_at(monday_5am).active);
+        assert!(!schedule.throttle_at(monday_6am).active);
+        assert!(!schedule.throttle_at(wednesday).active);
+    }
+
+    #[test]
+    fn test_throttle_effects() {
+        let throttle = weekend().throttle_at(1_704_542_400);
+        assert_eq!(throttle.gad_fraction_bps(300), 150);
+        assert_eq!(throttle.hard_threshold_bps(9_000), 9_500);
+
+        let open = Throttle::default();
+        assert_eq!(open.gad_fraction_bps(300), 300);
+        assert_eq!(open.hard_threshold_bps(9_000), 9_000);
+    }
+
+    #[test]
+    fn test_validate() {
+        GadSchedule::validate(&weekend().windows, 500).unwrap();
+        assert!(GadSchedule::validate(&weekend().windows, 1_001).is_err());
+        let empty = ThrottleWindow { start: 10, end: 10 };
+        assert!(GadSchedule::validate(&[empty], 0).is_err());
+        let past_week = ThrottleWindow {
+            start: 0,
+            end: 7 * DAY,
+        };
+        assert!(GadSchedule::validate(&[past_week], 0).is_err());
+    }
+}
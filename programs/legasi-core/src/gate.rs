@@ -0,0 +1,79 @@
+//! # Market Gates
+//!
+//! Restricts a market (a collateral or borrowable mint) to members, for
+//! institutional or RWA pools. While a `[MARKET_GATE_SEED, mint]` gate exists,
+//! lending's deposit and borrow instructions for that mint require the position
+//! owner to present a pass:
+//! - `Allowlist`: the owner's `[ALLOWLIST_SEED, gate, owner]` entry, added by the admin
+//! - `Membership`: a token account of the owner's holding the gate's membership mint
+//!   (e.g., a non-transferable membership NFT)
+//!
+//! Lending passes the gate as an unchecked PDA and the pass as an optional account,
+//! so ungated markets cost clients nothing beyond the gate address.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, TokenAccount};
+
+use crate::errors::LegasiError;
+use crate::seeds::ALLOWLIST_SEED;
+
+/// What a user must present to pass a gate
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum GateKind {
+    Allowlist,
+    Membership,
+}
+
+/// Access rule for one market
+#[account]
+#[derive(InitSpace)]
+pub struct MarketGate {
+    pub mint: Pubkey,
+    pub kind: GateKind,
+    /// Mint whose tokens pass a `Membership` gate (unused for `Allowlist`)
+    pub membership_mint: Pubkey,
+    pub bump: u8,
+}
+
+/// Allowlisted user of a gate
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistEntry {
+    pub gate: Pubkey,
+    pub user: Pubkey,
+    pub bump: u8,
+}
+
+/// Fails unless `user` passes the gate behind the unchecked `[MARKET_GATE_SEED, mint]`
+/// account. A market without a gate is open to everyone
+pub fn check_gate(gate: &AccountInfo, pass: Option<&AccountInfo>, user: &Pubkey) -> Result<()> {
+    if gate.owner != &crate::ID || gate.data_is_empty() {
+        return Ok(());
+    }
+    let market_gate = MarketGate::try_deserialize(&mut &gate.try_borrow_data()?[..])?;
+    let pass = pass.ok_or(LegasiError::MarketGated)?;
+
+    match market_gate.kind {
+        GateKind::Allowlist => {
+            let (entry, _) = Pubkey::find_program_address(
+                &[ALLOWLIST_SEED, gate.key.as_ref(), user.as_ref()],
+                &crate::ID,
+            );
+            require!(
+                pass.key == &entry && pass.owner == &crate::ID && !pass.data_is_empty(),
+                LegasiError::MarketGated
+            );
+        }
+        GateKind::Membership => {
+            require!(pass.owner == &token::ID, LegasiError::MarketGated);
+            let holding = TokenAccount::try_deserialize(&mut &pass.try_borrow_data()?[..])?;
+            require!(
+                holding.owner == *user
+                    && holding.mint == market_gate.membership_mint
+                    && holding.amount > 0,
+                LegasiError::MarketGated
+            );
+        }
+    }
+    Ok(())
+}
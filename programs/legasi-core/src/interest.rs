@@ -1,3 +1,5 @@
+use crate::constants::{BPS_DENOMINATOR, INSURANCE_FEE_BPS, SECONDS_PER_YEAR};
+
 /// Interest rate model parameters
 /// Uses a two-slope model like Aave/Compound
 
@@ -16,6 +18,10 @@ pub const OPTIMAL_UTILIZATION_BPS: u64 = 8000; // 80%
 /// Protocol fee on interest (in bps)
 pub const PROTOCOL_FEE_BPS: u64 = 2000; // 20% of interest goes to protocol
 
+/// Discount on interest repaid while utilization is above optimal (in bps)
+/// Funded from the protocol fee, so it is capped at the pool's protocol fee rate
+pub const REPAY_INCENTIVE_BPS: u64 = 250; // 2.5%
+
 /// Calculate borrow APR based on utilization
 /// Returns rate in basis points (e.g., 1000 = 10%)
 pub fn calculate_borrow_rate(total_deposits: u64, total_borrowed: u64) -> u64 {
@@ -92,6 +98,73 @@ pub fn calculate_protocol_fee(interest_amount: u64) -> u64 {
         .unwrap_or(0)
 }
 
+/// Calculate the insurance fund's cut of interest paid to LPs
+pub fn calculate_insurance_fee(interest_amount: u64) -> u64 {
+    interest_amount
+        .saturating_mul(INSURANCE_FEE_BPS)
+        .checked_div(10000)
+        .unwrap_or(0)
+}
+
+/// Interest waived for repaying `interest_repaid` at `utilization_bps`
+/// Zero at or below optimal utilization; above it, repaying pulls utilization back down.
+/// `protocol_fee_bps` is the pool's current fee, so nothing is waived with the switch off
+pub fn calculate_repay_incentive(
+    interest_repaid: u64,
+    utilization_bps: u64,
+    protocol_fee_bps: u64,
+) -> u64 {
+    if utilization_bps <= OPTIMAL_UTILIZATION_BPS {
+        return 0;
+    }
+    interest_repaid
+        .saturating_mul(std::cmp::min(REPAY_INCENTIVE_BPS, protocol_fee_bps))
+        .checked_div(10000)
+        .unwrap_or(0)
+}
+
+/// Simple interest on `principal` at `rate_bps` APR over `elapsed` seconds
+/// None on overflow; nothing accrues for a non-positive `elapsed`
+pub fn accrued_interest(principal: u64, rate_bps: u16, elapsed: i64) -> Option<u64> {
+    if elapsed <= 0 {
+        return Some(0);
+    }
+    // interest = principal * rate_bps * elapsed / (year * 10000)
+    let interest = (principal as u128)
+        .checked_mul(rate_bps as u128)?
+        .checked_mul(elapsed as u128)?
+        .checked_div(SECONDS_PER_YEAR as u128 * BPS_DENOMINATOR as u128)?;
+    u64::try_from(interest).ok()
+}
+
+/// Fixed-point scale of a pool's borrow index (1.0 = `BORROW_INDEX_PRECISION`)
+pub const BORROW_INDEX_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+/// Grow a borrow index by `rate_bps` APR over `elapsed` seconds
+/// Simple interest within the interval; compounding happens between intervals
+pub fn grow_borrow_index(index: u128, rate_bps: u64, elapsed: i64) -> Option<u128> {
+    if elapsed <= 0 {
+        return Some(index);
+    }
+    let growth = index
+        .checked_mul(rate_bps as u128)?
+        .checked_mul(elapsed as u128)?
+        .checked_div(SECONDS_PER_YEAR as u128 * BPS_DENOMINATOR as u128)?;
+    index.checked_add(growth)
+}
+
+/// Interest owed on `principal` borrowed at index `snapshot` now that it is `index`
+/// None on overflow; an index at or below the snapshot owes nothing
+pub fn interest_since_index(principal: u64, snapshot: u128, index: u128) -> Option<u64> {
+    if snapshot == 0 || index <= snapshot {
+        return Some(0);
+    }
+    let interest = (principal as u128)
+        .checked_mul(index - snapshot)?
+        .checked_div(snapshot)?;
+    u64::try_from(interest).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,4 +193,74 @@ mod tests {
         let supply = calculate_supply_rate(1000, 500);
         assert!(supply < borrow);
     }
+
+    #[test]
+    fn test_insurance_fee() {
+        assert_eq!(calculate_insurance_fee(1_000_000), 50_000); // 5%
+        assert_eq!(calculate_insurance_fee(19), 0);
+    }
+
+    #[test]
+    fn test_repay_incentive() {
+        assert_eq!(
+            calculate_repay_incentive(1_000_000, OPTIMAL_UTILIZATION_BPS, PROTOCOL_FEE_BPS),
+            0
+        );
+        assert_eq!(
+            calculate_repay_incentive(1_000_000, 9_000, PROTOCOL_FEE_BPS),
+            25_000
+        ); // 2.5%
+
+        // Nothing to fund it from with the fee switch off
+        assert_eq!(calculate_repay_incentive(1_000_000, 9_000, 0), 0);
+
+        // Never more than the protocol fee it is funded from
+        for interest in [1, 39, 40, 1_000_001] {
+            assert!(
+                calculate_repay_incentive(interest, 9_500, PROTOCOL_FEE_BPS)
+                    <= calculate_protocol_fee(interest)
+            );
+        }
+    }
+
+    #[test]
+    fn test_accrued_interest() {
+        // $400 at 8% for a year
+        assert_eq!(
+            accrued_interest(400_000_000, 800, SECONDS_PER_YEAR as i64),
+            Some(32_000_000)
+        );
+        assert_eq!(accrued_interest(400_000_000, 800, 0), Some(0));
+        assert_eq!(accrued_interest(400_000_000, 800, -60), Some(0));
+        assert_eq!(accrued_interest(u64::MAX, u16::MAX, i64::MAX), None);
+    }
+
+    #[test]
+    fn test_borrow_index() {
+        // A year at 3.4%
+        let year = SECONDS_PER_YEAR as i64;
+        let index = grow_borrow_index(BORROW_INDEX_PRECISION, 340, year).unwrap();
+        assert_eq!(index, BORROW_INDEX_PRECISION / 1_000 * 1_034);
+        assert_eq!(grow_borrow_index(index, 340, 0), Some(index));
+        assert_eq!(grow_borrow_index(index, 340, -60), Some(index));
+
+        // $400 borrowed at the start owes $13.60, borrowed now owes nothing yet
+        assert_eq!(
+            interest_since_index(400_000_000, BORROW_INDEX_PRECISION, index),
+            Some(13_600_000)
+        );
+        assert_eq!(interest_since_index(400_000_000, index, index), Some(0));
+
+        // Two half years compound: the second half accrues on the first half's interest
+        let half = grow_borrow_index(BORROW_INDEX_PRECISION, 1_000, year / 2).unwrap();
+        let full = grow_borrow_index(half, 1_000, year / 2).unwrap();
+        assert_eq!(
+            interest_since_index(1_000_000_000, BORROW_INDEX_PRECISION, full),
+            Some(102_500_000)
+        );
+        assert_eq!(
+            interest_since_index(1_000_000_000, half, full),
+            Some(50_000_000)
+        );
+    }
 }
@@ -0,0 +1,123 @@
+//! Jupiter Aggregator CPI helpers
+//!
+//! Shared plumbing for every program that swaps through Jupiter
+//! (GAD deleverage, leverage loops, collateral swaps):
+//! - account metas built from `remaining_accounts` with the PDA signer flagged
+//! - signed CPI into the aggregator with the route data built off-chain
+//! - wSOL wrapping for native SOL vaults
+//! - pre/post balance assertions so slippage is checked on deltas, not totals
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::token::{self, SyncNative, TokenAccount};
+
+use crate::errors::LegasiError;
+
+// Jupiter Aggregator v6 Program ID (mainnet)
+declare_id!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+/// Build Jupiter route account metas from `remaining_accounts`
+/// The PDA that signs via seeds is not a transaction signer, so it is flagged here
+pub fn build_account_metas(
+    accounts: &[AccountInfo],
+    pda_signer: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    accounts
+        .iter()
+        .map(|a| AccountMeta {
+            pubkey: a.key(),
+            is_signer: a.is_signer || pda_signer == Some(a.key),
+            is_writable: a.is_writable,
+        })
+        .collect()
+}
+
+/// Execute a Jupiter swap via CPI
+/// `route_data` is the serialized swap instruction returned by the Jupiter API
+pub fn swap<'info>(
+    jupiter_program: &AccountInfo<'info>,
+    route_accounts: &[AccountInfo<'info>],
+    route_data: Vec<u8>,
+    pda_signer: Option<&Pubkey>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require_keys_eq!(jupiter_program.key(), ID, LegasiError::InvalidSwapProgram);
+
+    invoke_signed(
+        &Instruction {
+            program_id: ID,
+            accounts: build_account_metas(route_accounts, pda_signer),
+            data: route_data,
+        },
+        route_accounts,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Wrap native SOL held by a PDA into its wSOL token account
+pub fn wrap_sol<'info>(
+    from: &AccountInfo<'info>,
+    wsol_account: &AccountInfo<'info>,
+    amount: u64,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    invoke_signed(
+        &system_instruction::transfer(from.key, wsol_account.key, amount),
+        &[from.clone(), wsol_account.clone(), system_program.clone()],
+        signer_seeds,
+    )?;
+
+    token::sync_native(CpiContext::new(
+        token_program.clone(),
+        SyncNative {
+            account: wsol_account.clone(),
+        },
+    ))
+}
+
+/// Read the current token balance of an SPL token account
+pub fn token_balance(account: &AccountInfo) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+}
+
+/// Assert the output account received at least `min_out`, returns the amount received
+pub fn assert_min_received(balance_before: u64, balance_after: u64, min_out: u64) -> Result<u64> {
+    let received = balance_after
+        .checked_sub(balance_before)
+        .ok_or(LegasiError::SlippageExceeded)?;
+    require!(received >= min_out, LegasiError::SlippageExceeded);
+    Ok(received)
+}
+
+/// Assert the input account spent at most `max_in`, returns the amount spent
+pub fn assert_max_spent(balance_before: u64, balance_after: u64, max_in: u64) -> Result<u64> {
+    let spent = balance_before
+        .checked_sub(balance_after)
+        .ok_or(LegasiError::MathOverflow)?;
+    require!(spent <= max_in, LegasiError::SlippageExceeded);
+    Ok(spent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balance_delta_assertions() {
+        // Received 1_000 against a 900 minimum
+        assert_eq!(assert_min_received(5_000, 6_000, 900).unwrap(), 1_000);
+        // Pre-existing balance does not count towards the minimum
+        assert!(assert_min_received(5_000, 5_500, 900).is_err());
+        // Balance went down
+        assert!(assert_min_received(5_000, 4_000, 0).is_err());
+
+        assert_eq!(assert_max_spent(5_000, 4_000, 1_000).unwrap(), 1_000);
+        assert!(assert_max_spent(5_000, 3_000, 1_000).is_err());
+    }
+}
@@ -1,20 +1,59 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::system_instruction;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
 
 declare_id!("4FW9iFaerNuX1GstRKSsWo9UfnTbjtqch3fEHkWMF1Uy");
 
+pub mod admin;
+pub mod automation;
+pub mod caps;
+pub mod cctp;
+pub mod circuit_breaker;
 pub mod constants;
+pub mod decimals;
 pub mod errors;
 pub mod events;
+pub mod gad;
+pub mod gad_schedule;
+pub mod gate;
 pub mod interest;
+pub mod jupiter_cpi;
+pub mod listing;
+pub mod market;
+pub mod oracle;
+#[cfg(feature = "pda")]
+pub mod pda;
 pub mod pyth;
+pub mod sanctum_cpi;
+pub mod seeds;
 pub mod state;
+pub mod swap_router;
+pub mod totals;
+pub mod treasury;
+pub mod twap;
+pub mod valuation;
 
+pub use admin::*;
+pub use automation::*;
+pub use caps::*;
+pub use circuit_breaker::*;
 pub use constants::*;
+pub use decimals::*;
 pub use errors::*;
 pub use events::*;
+pub use gad_schedule::*;
+pub use gate::*;
 pub use interest::*;
+pub use listing::*;
+pub use market::*;
+pub use oracle::*;
 pub use pyth::*;
+pub use seeds::*;
 pub use state::*;
+pub use swap_router::*;
+pub use totals::*;
+pub use twap::*;
 
 #[program]
 pub mod legasi_core {
@@ -29,6 +68,18 @@ pub mod legasi_core {
         protocol.total_collateral_usd = 0;
         protocol.total_borrowed_usd = 0;
         protocol.paused = false;
+        protocol.pending_admin = Pubkey::default();
+        protocol.cranker_reward_min_bps = DEFAULT_CRANKER_REWARD_MIN_BPS;
+        protocol.cranker_reward_max_bps = DEFAULT_CRANKER_REWARD_MAX_BPS;
+        protocol.max_leverage_volatility_bps = DEFAULT_MAX_LEVERAGE_VOLATILITY_BPS;
+        protocol.referral_fee_bps = DEFAULT_REFERRAL_FEE_BPS;
+        protocol.points_deposit_weight_bps = DEFAULT_POINTS_DEPOSIT_WEIGHT_BPS;
+        protocol.points_borrow_weight_bps = DEFAULT_POINTS_BORROW_WEIGHT_BPS;
+        protocol.insurance_fund_target = 0;
+        protocol.liquidation_split = gad::LiquidationSplit::default();
+        protocol.pause_flags = 0;
+        protocol.total_sol_collateral = 0;
+        protocol.bad_debt = 0;
         protocol.bump = ctx.bumps.protocol;
 
         msg!("Protocol initialized with admin: {}", protocol.admin);
@@ -45,17 +96,17 @@ pub mod legasi_core {
         decimals: u8,
         asset_type: AssetType,
     ) -> Result<()> {
-        let collateral = &mut ctx.accounts.collateral;
-        collateral.mint = ctx.accounts.mint.key();
-        collateral.oracle = oracle;
-        collateral.max_ltv_bps = max_ltv_bps;
-        collateral.liquidation_threshold_bps = liquidation_threshold_bps;
-        collateral.liquidation_bonus_bps = liquidation_bonus_bps;
-        collateral.decimals = decimals;
-        collateral.is_active = true;
-        collateral.total_deposited = 0;
-        collateral.asset_type = asset_type;
-        collateral.bump = ctx.bumps.collateral;
+        let params = CollateralParams {
+            oracle,
+            max_ltv_bps,
+            liquidation_threshold_bps,
+            liquidation_bonus_bps,
+            decimals,
+            asset_type,
+        };
+        ctx.accounts
+            .collateral
+            .list(ctx.accounts.mint.key(), &params, ctx.bumps.collateral);
 
         msg!("Collateral registered: {:?}", asset_type);
         Ok(())
@@ -84,6 +135,102 @@ pub mod legasi_core {
         Ok(())
     }
 
+    // ========== LISTING PROPOSALS ==========
+
+    /// Propose listing `mint` as collateral with `params`, posting a
+    /// `LISTING_BOND_LAMPORTS` bond (permissionless, see `listing`)
+    pub fn propose_collateral_listing(
+        ctx: Context<ProposeCollateralListing>,
+        params: CollateralParams,
+    ) -> Result<()> {
+        params.validate()?;
+        require!(
+            params.decimals == ctx.accounts.mint.decimals,
+            LegasiError::InvalidAmount
+        );
+        require!(
+            ctx.accounts.collateral.data_is_empty(),
+            LegasiError::CollateralAlreadyListed
+        );
+
+        invoke(
+            &system_instruction::transfer(
+                ctx.accounts.proposer.key,
+                &ctx.accounts.proposal.key(),
+                LISTING_BOND_LAMPORTS,
+            ),
+            &[
+                ctx.accounts.proposer.to_account_info(),
+                ctx.accounts.proposal.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.mint = ctx.accounts.mint.key();
+        proposal.params = params;
+        proposal.bond_lamports = LISTING_BOND_LAMPORTS;
+        proposal.proposed_at = Clock::get()?.unix_timestamp;
+        proposal.bump = ctx.bumps.proposal;
+
+        emit!(CollateralListingProposed {
+            mint: proposal.mint,
+            proposer: proposal.proposer,
+            asset_type: params.asset_type,
+            max_ltv_bps: params.max_ltv_bps,
+            liquidation_threshold_bps: params.liquidation_threshold_bps,
+            bond_lamports: proposal.bond_lamports,
+        });
+
+        msg!("Collateral listing proposed: {}", proposal.mint);
+        Ok(())
+    }
+
+    /// Register a proposed collateral with its proposed parameters (admin only). The
+    /// proposal closes, refunding the bond and rent to the proposer
+    pub fn approve_collateral_listing(ctx: Context<ApproveCollateralListing>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        ctx.accounts
+            .collateral
+            .list(proposal.mint, &proposal.params, ctx.bumps.collateral);
+
+        emit!(CollateralListingDecided {
+            mint: proposal.mint,
+            proposer: proposal.proposer,
+            approved: true,
+            bond_slashed: 0,
+        });
+
+        msg!("Collateral listing approved: {}", proposal.mint);
+        Ok(())
+    }
+
+    /// Turn down a listing proposal (admin only). With `slash` its bond goes to the
+    /// treasury; the rest of the proposal's lamports go back to the proposer
+    pub fn reject_collateral_listing(
+        ctx: Context<RejectCollateralListing>,
+        slash: bool,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let bond_slashed = if slash { proposal.bond_lamports } else { 0 };
+        if bond_slashed > 0 {
+            let proposal_info = proposal.to_account_info();
+            **proposal_info.try_borrow_mut_lamports()? -= bond_slashed;
+            **ctx.accounts.treasury.try_borrow_mut_lamports()? += bond_slashed;
+        }
+
+        emit!(CollateralListingDecided {
+            mint: proposal.mint,
+            proposer: proposal.proposer,
+            approved: false,
+            bond_slashed,
+        });
+
+        msg!("Collateral listing rejected: {}", proposal.mint);
+        Ok(())
+    }
+
     /// Initialize a price feed for a token (keyed by mint)
     pub fn initialize_price_feed(
         ctx: Context<InitializePriceFeed>,
@@ -92,9 +239,12 @@ pub mod legasi_core {
     ) -> Result<()> {
         let price_feed = &mut ctx.accounts.price_feed;
         price_feed.asset_type = asset_type;
-        price_feed.price_usd_6dec = initial_price_usd;
-        price_feed.last_update = Clock::get()?.unix_timestamp;
+        price_feed.record_price(initial_price_usd, Clock::get()?.unix_timestamp);
         price_feed.confidence = 0;
+        price_feed.max_deviation_bps = 0;
+        price_feed.oracle_sources = OracleSources::default();
+        price_feed.last_oracle_price = 0;
+        price_feed.last_oracle_update = 0;
         price_feed.bump = ctx.bumps.price_feed;
 
         msg!(
@@ -105,18 +255,34 @@ pub mod legasi_core {
         Ok(())
     }
 
-    /// Update price (admin only - for testing/fallback)
+    /// Post a failover price (admin only). Once the feed has an oracle, only after it
+    /// has been stale for `PRICE_FAILOVER_WINDOW` and within `MAX_FAILOVER_DEVIATION_BPS`
+    /// of the last oracle price (see `PriceFeed::check_failover_price`)
     pub fn update_price(ctx: Context<UpdatePrice>, price_usd: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
         let price_feed = &mut ctx.accounts.price_feed;
-        price_feed.price_usd_6dec = price_usd;
-        price_feed.last_update = Clock::get()?.unix_timestamp;
+        price_feed.check_failover_price(price_usd, now)?;
+        let previous_price = price_feed.price_usd_6dec;
+        price_feed.record_price(price_usd, now);
+        check_price_move(
+            price_feed,
+            &ctx.accounts.collateral,
+            ctx.accounts.mint.key(),
+            previous_price,
+            now,
+        )?;
 
         msg!("Price updated to ${}", price_usd as f64 / 1_000_000.0);
         Ok(())
     }
 
     /// Sync price from Pyth oracle (permissionless)
+    /// Feeds with aggregation sources sync through `sync_oracle_price` instead
     pub fn sync_pyth_price(ctx: Context<SyncPythPrice>) -> Result<()> {
+        require!(
+            !ctx.accounts.price_feed.oracle_sources.is_configured(),
+            LegasiError::InvalidOracle
+        );
         let pyth_data = ctx.accounts.pyth_price_account.try_borrow_data()?;
 
         let pyth_price = parse_pyth_price(&pyth_data).ok_or(LegasiError::InvalidOracle)?;
@@ -137,9 +303,16 @@ pub mod legasi_core {
 
         // Update our price feed
         let price_feed = &mut ctx.accounts.price_feed;
-        price_feed.price_usd_6dec = pyth_price.to_usd_6dec();
+        let previous_price = price_feed.price_usd_6dec;
+        price_feed.record_oracle_price(pyth_price.to_usd_6dec(), now);
         price_feed.confidence = pyth_price.conf;
-        price_feed.last_update = now;
+        check_price_move(
+            price_feed,
+            &ctx.accounts.collateral,
+            ctx.accounts.mint.key(),
+            previous_price,
+            now,
+        )?;
 
         msg!(
             "Synced Pyth price: ${}",
@@ -148,12 +321,682 @@ pub mod legasi_core {
         Ok(())
     }
 
+    /// Sync price from the median of the feed's oracle sources (permissionless)
+    /// Every configured source must be passed, fresh, and within the feed's deviation
+    /// threshold of the median (see `oracle`)
+    pub fn sync_oracle_price(ctx: Context<SyncOraclePrice>) -> Result<()> {
+        let sources = ctx.accounts.price_feed.oracle_sources;
+        require!(sources.is_configured(), LegasiError::InvalidOracle);
+        let now = Clock::get()?.unix_timestamp;
+        let mut prices = Vec::with_capacity(2);
+        let mut updated = sources;
+        let mut confidence = 0;
+
+        if sources.pyth != Pubkey::default() {
+            let account = ctx
+                .accounts
+                .pyth_price_account
+                .as_ref()
+                .ok_or(LegasiError::InvalidOracle)?;
+            let pyth_price =
+                parse_pyth_price(&account.try_borrow_data()?).ok_or(LegasiError::InvalidOracle)?;
+            require!(
+                !pyth_price.is_stale(now, MAX_PRICE_AGE),
+                LegasiError::StalePriceFeed
+            );
+            require!(
+                pyth_price.confidence_bps() <= MAX_CONFIDENCE_BPS,
+                LegasiError::InvalidOracle
+            );
+            prices.push(pyth_price.to_usd_6dec());
+            updated.pyth_published_at = pyth_price.publish_time;
+            confidence = pyth_price.conf;
+        }
+
+        if sources.switchboard != Pubkey::default() {
+            let account = ctx
+                .accounts
+                .switchboard_feed
+                .as_ref()
+                .ok_or(LegasiError::InvalidOracle)?;
+            let switchboard_price = parse_switchboard_price(&account.try_borrow_data()?)
+                .ok_or(LegasiError::InvalidOracle)?;
+            require!(
+                !switchboard_price.is_stale(now, MAX_PRICE_AGE),
+                LegasiError::StalePriceFeed
+            );
+            prices.push(switchboard_price.to_usd_6dec());
+            updated.switchboard_published_at = switchboard_price.last_update;
+        }
+
+        let price = median_price(&prices, sources.max_deviation_bps)?;
+        let price_feed = &mut ctx.accounts.price_feed;
+        let previous_price = price_feed.price_usd_6dec;
+        price_feed.record_oracle_price(price, now);
+        price_feed.confidence = confidence;
+        price_feed.oracle_sources = updated;
+        check_price_move(
+            price_feed,
+            &ctx.accounts.collateral,
+            ctx.accounts.mint.key(),
+            previous_price,
+            now,
+        )?;
+
+        emit!(PriceUpdated {
+            asset_type: price_feed.asset_type,
+            price_usd_6dec: price,
+            timestamp: now,
+        });
+
+        msg!(
+            "Synced median of {} oracles: ${}",
+            prices.len(),
+            price as f64 / 1_000_000.0
+        );
+        Ok(())
+    }
+
     /// Pause/unpause protocol (admin only)
     pub fn set_paused(ctx: Context<AdminOnly>, paused: bool) -> Result<()> {
         ctx.accounts.protocol.paused = paused;
         msg!("Protocol paused: {}", paused);
         Ok(())
     }
+
+    // ========== ADMIN / MULTISIG ==========
+    // `admin: Signer` also accepts a PDA that signs via CPI (e.g., a Squads vault),
+    // so every admin instruction can be executed from a multisig proposal.
+
+    /// Propose a new admin (admin only) - takes effect once the new admin accepts
+    pub fn transfer_admin(ctx: Context<AdminOnly>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.protocol.pending_admin = new_admin;
+        msg!("Admin transfer proposed to {}", new_admin);
+        Ok(())
+    }
+
+    /// Accept a pending admin transfer
+    /// Requiring the new admin's signature guards against rotating to a wrong key
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        let previous_admin = protocol.admin;
+        protocol.admin = ctx.accounts.new_admin.key();
+        protocol.pending_admin = Pubkey::default();
+
+        emit!(AdminTransferred {
+            previous_admin,
+            new_admin: protocol.admin,
+        });
+
+        msg!("Admin transferred to {}", protocol.admin);
+        Ok(())
+    }
+
+    /// Apply a batch of parameter changes atomically (admin only)
+    /// Collateral/Borrowable configs and price feeds touched by the batch are passed as writable remaining_accounts
+    pub fn execute_admin_ops(ctx: Context<AdminOnly>, ops: Vec<AdminOp>) -> Result<()> {
+        require!(
+            !ops.is_empty() && ops.len() <= MAX_ADMIN_OPS,
+            LegasiError::InvalidAdminOp
+        );
+
+        let protocol = &mut ctx.accounts.protocol;
+        for op in &ops {
+            apply_admin_op(protocol, op, ctx.remaining_accounts)?;
+        }
+
+        emit!(AdminOpsExecuted {
+            admin: ctx.accounts.admin.key(),
+            op_count: ops.len() as u8,
+        });
+
+        msg!("Executed {} admin ops", ops.len());
+        Ok(())
+    }
+
+    // ========== PROTOCOL TOTALS ==========
+
+    /// Move the global collateral/borrow totals by signed USD deltas
+    /// Called via CPI by lending, GAD, and leverage, signed by their protocol writer PDA
+    pub fn update_protocol_totals(
+        ctx: Context<UpdateProtocolTotals>,
+        collateral_delta_usd: i64,
+        borrowed_delta_usd: i64,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.total_collateral_usd =
+            apply_delta(protocol.total_collateral_usd, collateral_delta_usd);
+        protocol.total_borrowed_usd = apply_delta(protocol.total_borrowed_usd, borrowed_delta_usd);
+        Ok(())
+    }
+
+    /// Move the SOL collateral total by a signed lamport delta, the supply SOL's
+    /// `AssetCaps` caps. Called via CPI wherever SOL enters or leaves a position vault
+    pub fn record_sol_collateral(
+        ctx: Context<UpdateProtocolTotals>,
+        delta_lamports: i64,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.total_sol_collateral = apply_delta(protocol.total_sol_collateral, delta_lamports);
+        Ok(())
+    }
+
+    /// Credit the insurance fund with its cut of interest collected by lending
+    /// The tokens stay in the LP vault, outside `total_deposits`, until swept to the
+    /// pool's insurance vault (see `legasi_lp::sweep_insurance`)
+    pub fn record_insurance_fee(ctx: Context<UpdateProtocolTotals>, amount: u64) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.insurance_fund = protocol.insurance_fund.saturating_add(amount);
+        Ok(())
+    }
+
+    /// Debit the insurance fund for tokens paid out of an LP or insurance vault (e.g., an
+    /// auctioned surplus, see `legasi_lp::surplus_auction`)
+    pub fn record_insurance_payout(ctx: Context<UpdateProtocolTotals>, amount: u64) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.insurance_fund = protocol
+            .insurance_fund
+            .checked_sub(amount)
+            .ok_or(LegasiError::InsufficientInsuranceFund)?;
+        Ok(())
+    }
+
+    /// Add debt written off a position (USD, 6 decimals) to the protocol's bad debt
+    /// Called via CPI wherever lending or GAD writes off a borrow
+    pub fn record_bad_debt(ctx: Context<UpdateProtocolTotals>, amount_usd: u64) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol;
+        protocol.bad_debt = protocol.bad_debt.saturating_add(amount_usd);
+        Ok(())
+    }
+
+    /// Publish a pool's current borrow rate on its borrowable config (see
+    /// `legasi_lp::refresh_rates`)
+    pub fn record_borrow_rate(ctx: Context<RecordBorrowRate>, borrow_rate_bps: u16) -> Result<()> {
+        ctx.accounts.borrowable.interest_rate_bps = borrow_rate_bps;
+        Ok(())
+    }
+
+    // ========== AUTOMATION ==========
+
+    /// Create a fee budget that pays executors of automation threads
+    pub fn create_fee_budget(ctx: Context<CreateFeeBudget>) -> Result<()> {
+        let fee_budget = &mut ctx.accounts.fee_budget;
+        fee_budget.authority = ctx.accounts.authority.key();
+        fee_budget.total_funded = 0;
+        fee_budget.total_spent = 0;
+        fee_budget.bump = ctx.bumps.fee_budget;
+
+        msg!("Fee budget created for {}", fee_budget.authority);
+        Ok(())
+    }
+
+    /// Fund a fee budget with SOL (anyone can top up)
+    pub fn fund_fee_budget(ctx: Context<FundFeeBudget>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+
+        invoke(
+            &system_instruction::transfer(
+                ctx.accounts.funder.key,
+                &ctx.accounts.fee_budget.key(),
+                amount,
+            ),
+            &[
+                ctx.accounts.funder.to_account_info(),
+                ctx.accounts.fee_budget.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let fee_budget = &mut ctx.accounts.fee_budget;
+        fee_budget.total_funded = fee_budget
+            .total_funded
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        msg!("Fee budget funded with {} lamports", amount);
+        Ok(())
+    }
+
+    /// Withdraw unused SOL from a fee budget (authority only)
+    pub fn withdraw_fee_budget(ctx: Context<WithdrawFeeBudget>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+
+        let budget_info = ctx.accounts.fee_budget.to_account_info();
+        let rent_min = Rent::get()?.minimum_balance(budget_info.data_len());
+        require!(
+            budget_info.lamports().saturating_sub(rent_min) >= amount,
+            LegasiError::InsufficientFeeBudget
+        );
+
+        **budget_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.authority.try_borrow_mut_lamports()? += amount;
+
+        msg!("Withdrew {} lamports from fee budget", amount);
+        Ok(())
+    }
+
+    /// Register a GAD crank or oracle sync as an automation thread
+    pub fn register_thread(
+        ctx: Context<RegisterThread>,
+        kind: ThreadKind,
+        interval_secs: i64,
+        fee_per_exec: u64,
+    ) -> Result<()> {
+        require!(
+            interval_secs >= MIN_THREAD_INTERVAL,
+            LegasiError::InvalidAmount
+        );
+
+        // Validates the target and snapshots its timestamp,
+        // so the first payout requires a fresh crank
+        let last_exec = read_target_timestamp(kind, &ctx.accounts.target)?;
+
+        let thread = &mut ctx.accounts.thread;
+        thread.fee_budget = ctx.accounts.fee_budget.key();
+        thread.kind = kind;
+        thread.target = ctx.accounts.target.key();
+        thread.interval_secs = interval_secs;
+        thread.fee_per_exec = fee_per_exec;
+        thread.last_exec = last_exec;
+        thread.exec_count = 0;
+        thread.is_active = true;
+        thread.bump = ctx.bumps.thread;
+
+        emit!(ThreadRegistered {
+            thread: ctx.accounts.thread.key(),
+            fee_budget: ctx.accounts.fee_budget.key(),
+            kind,
+            target: ctx.accounts.target.key(),
+            interval_secs,
+            fee_per_exec,
+        });
+
+        msg!("Thread registered: {:?} every {}s", kind, interval_secs);
+        Ok(())
+    }
+
+    /// Cancel an automation thread (authority only)
+    pub fn cancel_thread(_ctx: Context<CancelThread>) -> Result<()> {
+        // Account is closed via close constraint
+        msg!("Thread cancelled");
+        Ok(())
+    }
+
+    /// Pay the executor of a due thread whose target was cranked in this transaction
+    /// Call right after the target crank instruction
+    pub fn execute_thread(ctx: Context<ExecuteThread>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let thread = &ctx.accounts.thread;
+        require!(thread.is_due(now), LegasiError::ThreadNotDue);
+
+        let observed = read_target_timestamp(thread.kind, &ctx.accounts.target)?;
+        require!(
+            observed > thread.last_exec,
+            LegasiError::ThreadTargetNotAdvanced
+        );
+
+        // Pay executor from the fee budget, keeping it rent-exempt
+        let fee = thread.fee_per_exec;
+        let budget_info = ctx.accounts.fee_budget.to_account_info();
+        let rent_min = Rent::get()?.minimum_balance(budget_info.data_len());
+        require!(
+            budget_info.lamports().saturating_sub(rent_min) >= fee,
+            LegasiError::InsufficientFeeBudget
+        );
+
+        **budget_info.try_borrow_mut_lamports()? -= fee;
+        **ctx.accounts.executor.try_borrow_mut_lamports()? += fee;
+
+        let fee_budget = &mut ctx.accounts.fee_budget;
+        fee_budget.total_spent = fee_budget.total_spent.saturating_add(fee);
+
+        let thread = &mut ctx.accounts.thread;
+        thread.last_exec = observed;
+        thread.exec_count = thread.exec_count.saturating_add(1);
+
+        emit!(ThreadExecuted {
+            thread: ctx.accounts.thread.key(),
+            kind: ctx.accounts.thread.kind,
+            target: ctx.accounts.thread.target,
+            executor: ctx.accounts.executor.key(),
+            fee_paid: fee,
+        });
+
+        msg!("Thread executed, paid {} lamports", fee);
+        Ok(())
+    }
+    // ========== MARKET GATES ==========
+
+    /// Restrict deposits and borrows of a mint to allowlisted users or membership holders
+    /// (admin only). Calling it again changes the gate's kind
+    pub fn set_market_gate(
+        ctx: Context<SetMarketGate>,
+        kind: GateKind,
+        membership_mint: Pubkey,
+    ) -> Result<()> {
+        let gate = &mut ctx.accounts.market_gate;
+        gate.mint = ctx.accounts.mint.key();
+        gate.kind = kind;
+        gate.membership_mint = membership_mint;
+        gate.bump = ctx.bumps.market_gate;
+
+        msg!("Market gate set: {:?}", kind);
+        Ok(())
+    }
+
+    /// Open a gated market to everyone again (admin only)
+    pub fn remove_market_gate(_ctx: Context<RemoveMarketGate>) -> Result<()> {
+        // Account is closed via close constraint
+        msg!("Market gate removed");
+        Ok(())
+    }
+
+    /// Allowlist a user on a gate (admin only)
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, user: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.allowlist_entry;
+        entry.gate = ctx.accounts.market_gate.key();
+        entry.user = user;
+        entry.bump = ctx.bumps.allowlist_entry;
+
+        msg!("Allowlisted {}", user);
+        Ok(())
+    }
+
+    /// Remove a user from a gate's allowlist (admin only)
+    pub fn remove_from_allowlist(_ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+        // Account is closed via close constraint
+        msg!("Removed from allowlist");
+        Ok(())
+    }
+
+    // ========== MARKETS ==========
+
+    /// Create market `market_id` pairing a registered collateral with a registered
+    /// borrowable (admin only). It starts active with borrowing and collateral enabled
+    pub fn create_market(
+        ctx: Context<CreateMarket>,
+        market_id: u16,
+        params: MarketParams,
+    ) -> Result<()> {
+        params.validate()?;
+        let now = Clock::get()?.unix_timestamp;
+
+        let market = &mut ctx.accounts.market;
+        market.market_id = market_id;
+        market.collateral_asset = ctx.accounts.collateral.asset_type;
+        market.collateral_mint = ctx.accounts.collateral.mint;
+        market.borrow_asset = ctx.accounts.borrowable.asset_type;
+        market.borrow_mint = ctx.accounts.borrowable.mint;
+        market.apply_params(&params);
+        market.is_active = true;
+        market.borrow_enabled = true;
+        market.collateral_enabled = true;
+        market.total_collateral = 0;
+        market.total_borrowed = 0;
+        market.created_at = now;
+        market.updated_at = now;
+        // GAD holds liquidated SOL by default; other collateral can only be sold
+        let gad_proceeds = if market.collateral_asset == AssetType::SOL {
+            GadProceedsMode::Hold
+        } else {
+            GadProceedsMode::Swap
+        };
+        market.set_gad_proceeds(gad_proceeds, 0)?;
+        market.bump = ctx.bumps.market;
+
+        emit!(MarketCreated {
+            market_id,
+            name: params.name.clone(),
+            collateral_asset: market.collateral_asset,
+            borrow_asset: market.borrow_asset,
+            base_max_ltv_bps: market.base_max_ltv_bps,
+            emode_max_ltv_bps: market.emode_max_ltv_bps,
+        });
+
+        msg!("Market {} created: {}", market_id, params.name);
+        Ok(())
+    }
+
+    /// Replace a market's risk, rate and cap parameters (admin only)
+    pub fn update_market_params(ctx: Context<UpdateMarket>, params: MarketParams) -> Result<()> {
+        params.validate()?;
+
+        let market = &mut ctx.accounts.market;
+        market.apply_params(&params);
+        market.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(MarketUpdated {
+            market_id: market.market_id,
+            base_max_ltv_bps: market.base_max_ltv_bps,
+            emode_max_ltv_bps: market.emode_max_ltv_bps,
+            supply_cap: market.supply_cap,
+            borrow_cap: market.borrow_cap,
+        });
+
+        msg!("Market {} updated", market.market_id);
+        Ok(())
+    }
+
+    /// Switch a market, its borrowing, or its collateral deposits on or off (admin only)
+    pub fn toggle_market(
+        ctx: Context<UpdateMarket>,
+        is_active: bool,
+        borrow_enabled: bool,
+        collateral_enabled: bool,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.is_active = is_active;
+        market.borrow_enabled = borrow_enabled;
+        market.collateral_enabled = collateral_enabled;
+        market.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(MarketToggled {
+            market_id: market.market_id,
+            is_active,
+            borrow_enabled,
+            collateral_enabled,
+        });
+
+        msg!("Market {} active: {}", market.market_id, is_active);
+        Ok(())
+    }
+
+    /// Set what GAD does with collateral it liquidates in a market: hold it, swap it at
+    /// once, or sell it over `twap_slices` cranks (admin only, see `market`)
+    pub fn set_market_gad_proceeds(
+        ctx: Context<UpdateMarket>,
+        mode: GadProceedsMode,
+        twap_slices: u8,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.set_gad_proceeds(mode, twap_slices)?;
+        market.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(MarketGadProceedsSet {
+            market_id: market.market_id,
+            mode,
+            twap_slices,
+        });
+
+        msg!(
+            "Market {} GAD proceeds: {:?} ({} slices)",
+            market.market_id,
+            mode,
+            twap_slices
+        );
+        Ok(())
+    }
+
+    // ========== GAD SCHEDULE ==========
+
+    /// Set the weekly windows in which GAD is throttled and hard liquidations need an
+    /// extra `hard_threshold_extra_bps` of LTV (admin only, see `gad_schedule`).
+    /// Calling it again replaces the windows
+    pub fn set_gad_schedule(
+        ctx: Context<SetGadSchedule>,
+        windows: Vec<ThrottleWindow>,
+        hard_threshold_extra_bps: u16,
+    ) -> Result<()> {
+        GadSchedule::validate(&windows, hard_threshold_extra_bps)?;
+        let schedule = &mut ctx.accounts.gad_schedule;
+        schedule.windows = windows.clone();
+        schedule.hard_threshold_extra_bps = hard_threshold_extra_bps;
+        schedule.bump = ctx.bumps.gad_schedule;
+
+        emit!(GadScheduleSet {
+            windows,
+            hard_threshold_extra_bps,
+        });
+
+        msg!("GAD schedule set: {} windows", schedule.windows.len());
+        Ok(())
+    }
+
+    /// Drop the GAD schedule, lifting every throttle (admin only)
+    pub fn remove_gad_schedule(_ctx: Context<RemoveGadSchedule>) -> Result<()> {
+        // Account is closed via close constraint
+        msg!("GAD schedule removed");
+        Ok(())
+    }
+
+    // ========== ASSET CAPS ==========
+
+    /// Cap the collateral the protocol holds of a mint (admin only, native units,
+    /// 0 = uncapped, see `caps`)
+    pub fn set_supply_cap(ctx: Context<SetAssetCaps>, supply_cap: u64) -> Result<()> {
+        let caps = &mut ctx.accounts.asset_caps;
+        let previous_cap = caps.supply_cap;
+        caps.mint = ctx.accounts.mint.key();
+        caps.supply_cap = supply_cap;
+        caps.bump = ctx.bumps.asset_caps;
+
+        emit!(SupplyCapSet {
+            mint: caps.mint,
+            previous_cap,
+            supply_cap,
+        });
+
+        msg!("Supply cap of {} set to {}", caps.mint, supply_cap);
+        Ok(())
+    }
+
+    /// Cap how much of a mint is lent out at once (admin only, native units,
+    /// 0 = uncapped, see `caps`)
+    pub fn set_borrow_cap(ctx: Context<SetAssetCaps>, borrow_cap: u64) -> Result<()> {
+        let caps = &mut ctx.accounts.asset_caps;
+        let previous_cap = caps.borrow_cap;
+        caps.mint = ctx.accounts.mint.key();
+        caps.borrow_cap = borrow_cap;
+        caps.bump = ctx.bumps.asset_caps;
+
+        emit!(BorrowCapSet {
+            mint: caps.mint,
+            previous_cap,
+            borrow_cap,
+        });
+
+        msg!("Borrow cap of {} set to {}", caps.mint, borrow_cap);
+        Ok(())
+    }
+
+    // ========== TREASURY ==========
+
+    /// Create the treasury PDA's token account for `mint` (admin only)
+    pub fn init_treasury_vault(ctx: Context<InitTreasuryVault>) -> Result<()> {
+        msg!("Treasury vault created for {}", ctx.accounts.mint.key());
+        Ok(())
+    }
+
+    /// Sell up to `amount_in` of a non-USDC treasury holding for USDC through Jupiter
+    /// (permissionless). wSOL is first topped up from the treasury PDA's lamports.
+    /// The route accounts are passed via `remaining_accounts`; the USDC received must
+    /// cover `min_out` and the oracle value sold less `TREASURY_MAX_SLIPPAGE_BPS`
+    pub fn convert_treasury_to_usdc(
+        ctx: Context<ConvertTreasuryToUsdc>,
+        amount_in: u64,
+        min_out: u64,
+        jupiter_swap_data: Vec<u8>,
+    ) -> Result<()> {
+        require!(amount_in > 0, LegasiError::InvalidAmount);
+        let source_feed = &ctx.accounts.source_price_feed;
+        require!(
+            source_feed.within_deviation_band(),
+            LegasiError::PriceDeviationTooHigh
+        );
+        let price = source_feed.get_checked_price(Clock::get()?.unix_timestamp)?;
+        let decimals = ctx.accounts.source_mint.decimals;
+
+        let seeds: &[&[u8]] = &[TREASURY_SEED, &[ctx.bumps.treasury]];
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+
+        // GAD pays the treasury in lamports; wrap what this conversion sells
+        let held = ctx.accounts.source_vault.amount;
+        if ctx.accounts.source_mint.key() == token::spl_token::native_mint::ID && held < amount_in {
+            let to_wrap = amount_in - held;
+            let spare = treasury_info
+                .lamports()
+                .saturating_sub(Rent::get()?.minimum_balance(0));
+            require!(spare >= to_wrap, LegasiError::InsufficientTreasuryBalance);
+            jupiter_cpi::wrap_sol(
+                &treasury_info,
+                &ctx.accounts.source_vault.to_account_info(),
+                to_wrap,
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.token_program.to_account_info(),
+                &[seeds],
+            )?;
+            ctx.accounts.source_vault.reload()?;
+        }
+        require!(
+            ctx.accounts.source_vault.amount >= amount_in,
+            LegasiError::InsufficientTreasuryBalance
+        );
+
+        let source_before = ctx.accounts.source_vault.amount;
+        let usdc_before = ctx.accounts.usdc_vault.amount;
+
+        jupiter_cpi::swap(
+            &ctx.accounts.jupiter_program.to_account_info(),
+            ctx.remaining_accounts,
+            jupiter_swap_data,
+            Some(treasury_info.key),
+            &[seeds],
+        )?;
+
+        ctx.accounts.source_vault.reload()?;
+        ctx.accounts.usdc_vault.reload()?;
+        let amount_sold = jupiter_cpi::assert_max_spent(
+            source_before,
+            ctx.accounts.source_vault.amount,
+            amount_in,
+        )?;
+        let usdc_received =
+            jupiter_cpi::assert_min_received(usdc_before, ctx.accounts.usdc_vault.amount, min_out)?;
+        // The floor is checked on what was actually sold, so a partial fill is held
+        // to the same price
+        let oracle_value = treasury::oracle_value_usdc(amount_sold, price, decimals)?;
+        require!(
+            usdc_received
+                >= treasury::min_usdc_out(amount_sold, price, decimals, TREASURY_MAX_SLIPPAGE_BPS)?,
+            LegasiError::SlippageExceeded
+        );
+
+        emit!(TreasuryConverted {
+            source_mint: ctx.accounts.source_mint.key(),
+            amount_sold,
+            usdc_received,
+            oracle_value_usdc: oracle_value,
+            cranker: ctx.accounts.cranker.key(),
+        });
+
+        msg!(
+            "Treasury converted {} of {} into {} USDC",
+            amount_sold,
+            ctx.accounts.source_mint.key(),
+            usdc_received
+        );
+        Ok(())
+    }
 }
 
 // ========== ACCOUNTS ==========
@@ -164,7 +1007,7 @@ pub struct InitializeProtocol<'info> {
         init,
         payer = admin,
         space = 8 + Protocol::INIT_SPACE,
-        seeds = [b"protocol"],
+        seeds = [PROTOCOL_SEED],
         bump
     )]
     pub protocol: Account<'info, Protocol>,
@@ -175,13 +1018,13 @@ pub struct InitializeProtocol<'info> {
 
 #[derive(Accounts)]
 pub struct RegisterCollateral<'info> {
-    #[account(seeds = [b"protocol"], bump = protocol.bump, has_one = admin)]
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
     pub protocol: Account<'info, Protocol>,
     #[account(
         init,
         payer = admin,
         space = 8 + Collateral::INIT_SPACE,
-        seeds = [b"collateral", mint.key().as_ref()],
+        seeds = [COLLATERAL_SEED, mint.key().as_ref()],
         bump
     )]
     pub collateral: Account<'info, Collateral>,
@@ -192,15 +1035,83 @@ pub struct RegisterCollateral<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ProposeCollateralListing<'info> {
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + ListingProposal::INIT_SPACE,
+        seeds = [LISTING_PROPOSAL_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, ListingProposal>,
+    /// CHECK: Collateral config PDA of the mint, must not exist yet
+    #[account(seeds = [COLLATERAL_SEED, mint.key().as_ref()], bump)]
+    pub collateral: UncheckedAccount<'info>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveCollateralListing<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [LISTING_PROPOSAL_SEED, proposal.mint.as_ref()],
+        bump = proposal.bump,
+        has_one = proposer
+    )]
+    pub proposal: Account<'info, ListingProposal>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Collateral::INIT_SPACE,
+        seeds = [COLLATERAL_SEED, proposal.mint.as_ref()],
+        bump
+    )]
+    pub collateral: Account<'info, Collateral>,
+    /// CHECK: Proposer, refunded the bond and rent
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RejectCollateralListing<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [LISTING_PROPOSAL_SEED, proposal.mint.as_ref()],
+        bump = proposal.bump,
+        has_one = proposer
+    )]
+    pub proposal: Account<'info, ListingProposal>,
+    /// CHECK: Protocol treasury, receives a slashed bond
+    #[account(mut, address = protocol.treasury)]
+    pub treasury: UncheckedAccount<'info>,
+    /// CHECK: Proposer, refunded the rest
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct RegisterBorrowable<'info> {
-    #[account(seeds = [b"protocol"], bump = protocol.bump, has_one = admin)]
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
     pub protocol: Account<'info, Protocol>,
     #[account(
         init,
         payer = admin,
         space = 8 + Borrowable::INIT_SPACE,
-        seeds = [b"borrowable", mint.key().as_ref()],
+        seeds = [BORROWABLE_SEED, mint.key().as_ref()],
         bump
     )]
     pub borrowable: Account<'info, Borrowable>,
@@ -213,13 +1124,13 @@ pub struct RegisterBorrowable<'info> {
 
 #[derive(Accounts)]
 pub struct InitializePriceFeed<'info> {
-    #[account(seeds = [b"protocol"], bump = protocol.bump, has_one = admin)]
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
     pub protocol: Account<'info, Protocol>,
     #[account(
         init,
         payer = admin,
         space = 8 + PriceFeed::INIT_SPACE,
-        seeds = [b"price", mint.key().as_ref()],
+        seeds = [PRICE_FEED_SEED, mint.key().as_ref()],
         bump
     )]
     pub price_feed: Account<'info, PriceFeed>,
@@ -232,37 +1143,420 @@ pub struct InitializePriceFeed<'info> {
 
 #[derive(Accounts)]
 pub struct UpdatePrice<'info> {
-    #[account(seeds = [b"protocol"], bump = protocol.bump, has_one = admin)]
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
     pub protocol: Account<'info, Protocol>,
     #[account(
         mut,
-        seeds = [b"price", mint.key().as_ref()],
+        seeds = [PRICE_FEED_SEED, mint.key().as_ref()],
         bump = price_feed.bump
     )]
     pub price_feed: Account<'info, PriceFeed>,
     /// CHECK: Token mint
     pub mint: UncheckedAccount<'info>,
+    /// CHECK: The mint's collateral config, read for its circuit breaker if registered
+    #[account(seeds = [COLLATERAL_SEED, mint.key().as_ref()], bump)]
+    pub collateral: UncheckedAccount<'info>,
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct AdminOnly<'info> {
-    #[account(mut, seeds = [b"protocol"], bump = protocol.bump, has_one = admin)]
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
     pub protocol: Account<'info, Protocol>,
     pub admin: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [PROTOCOL_SEED],
+        bump = protocol.bump,
+        constraint = protocol.pending_admin == new_admin.key() @ LegasiError::Unauthorized
+    )]
+    pub protocol: Account<'info, Protocol>,
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolTotals<'info> {
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump)]
+    pub protocol: Account<'info, Protocol>,
+    /// Protocol writer PDA of a Legasi program (signs via CPI)
+    #[account(constraint = is_protocol_writer(writer.key) @ LegasiError::Unauthorized)]
+    pub writer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordBorrowRate<'info> {
+    #[account(mut, seeds = [BORROWABLE_SEED, borrowable.mint.as_ref()], bump = borrowable.bump)]
+    pub borrowable: Account<'info, Borrowable>,
+    /// Protocol writer PDA of a Legasi program (signs via CPI)
+    #[account(constraint = is_protocol_writer(writer.key) @ LegasiError::Unauthorized)]
+    pub writer: Signer<'info>,
+}
+
 /// Sync price from Pyth oracle (permissionless - anyone can update)
 #[derive(Accounts)]
 pub struct SyncPythPrice<'info> {
     #[account(
         mut,
-        seeds = [b"price", mint.key().as_ref()],
+        seeds = [PRICE_FEED_SEED, mint.key().as_ref()],
         bump = price_feed.bump
     )]
     pub price_feed: Account<'info, PriceFeed>,
     /// CHECK: Token mint for this price feed
     pub mint: UncheckedAccount<'info>,
+    /// CHECK: The mint's collateral config, read for its circuit breaker if registered
+    #[account(seeds = [COLLATERAL_SEED, mint.key().as_ref()], bump)]
+    pub collateral: UncheckedAccount<'info>,
     /// CHECK: Pyth price account - verified by parsing
     pub pyth_price_account: UncheckedAccount<'info>,
 }
+
+#[derive(Accounts)]
+pub struct SyncOraclePrice<'info> {
+    #[account(
+        mut,
+        seeds = [PRICE_FEED_SEED, mint.key().as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+    /// CHECK: Token mint for this price feed
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: The mint's collateral config, read for its circuit breaker if registered
+    #[account(seeds = [COLLATERAL_SEED, mint.key().as_ref()], bump)]
+    pub collateral: UncheckedAccount<'info>,
+    /// CHECK: The feed's Pyth price account, required when configured
+    #[account(address = price_feed.oracle_sources.pyth @ LegasiError::InvalidOracle)]
+    pub pyth_price_account: Option<UncheckedAccount<'info>>,
+    /// CHECK: The feed's Switchboard pull feed, required when configured
+    #[account(address = price_feed.oracle_sources.switchboard @ LegasiError::InvalidOracle)]
+    pub switchboard_feed: Option<UncheckedAccount<'info>>,
+}
+
+// ========== AUTOMATION ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct CreateFeeBudget<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FeeBudget::INIT_SPACE,
+        seeds = [FEE_BUDGET_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub fee_budget: Account<'info, FeeBudget>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundFeeBudget<'info> {
+    #[account(
+        mut,
+        seeds = [FEE_BUDGET_SEED, fee_budget.authority.as_ref()],
+        bump = fee_budget.bump
+    )]
+    pub fee_budget: Account<'info, FeeBudget>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFeeBudget<'info> {
+    #[account(
+        mut,
+        seeds = [FEE_BUDGET_SEED, authority.key().as_ref()],
+        bump = fee_budget.bump,
+        has_one = authority
+    )]
+    pub fee_budget: Account<'info, FeeBudget>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(kind: ThreadKind)]
+pub struct RegisterThread<'info> {
+    #[account(
+        seeds = [FEE_BUDGET_SEED, authority.key().as_ref()],
+        bump = fee_budget.bump,
+        has_one = authority
+    )]
+    pub fee_budget: Account<'info, FeeBudget>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AutomationThread::INIT_SPACE,
+        seeds = [THREAD_SEED, fee_budget.key().as_ref(), target.key().as_ref(), &[kind as u8]],
+        bump
+    )]
+    pub thread: Account<'info, AutomationThread>,
+    /// CHECK: Position or price feed - validated by read_target_timestamp
+    pub target: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelThread<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = fee_budget
+    )]
+    pub thread: Account<'info, AutomationThread>,
+    #[account(
+        seeds = [FEE_BUDGET_SEED, authority.key().as_ref()],
+        bump = fee_budget.bump,
+        has_one = authority
+    )]
+    pub fee_budget: Account<'info, FeeBudget>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Execute a thread (permissionless - paid from the fee budget)
+#[derive(Accounts)]
+pub struct ExecuteThread<'info> {
+    #[account(mut, has_one = fee_budget, has_one = target)]
+    pub thread: Account<'info, AutomationThread>,
+    #[account(
+        mut,
+        seeds = [FEE_BUDGET_SEED, fee_budget.authority.as_ref()],
+        bump = fee_budget.bump
+    )]
+    pub fee_budget: Account<'info, FeeBudget>,
+    /// CHECK: Thread target - validated by has_one and read_target_timestamp
+    pub target: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub executor: Signer<'info>,
+}
+
+// ========== MARKET GATE ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct SetMarketGate<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + MarketGate::INIT_SPACE,
+        seeds = [MARKET_GATE_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub market_gate: Account<'info, MarketGate>,
+    /// CHECK: Collateral or borrowable mint
+    pub mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveMarketGate<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [MARKET_GATE_SEED, market_gate.mint.as_ref()],
+        bump = market_gate.bump
+    )]
+    pub market_gate: Account<'info, MarketGate>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        seeds = [MARKET_GATE_SEED, market_gate.mint.as_ref()],
+        bump = market_gate.bump
+    )]
+    pub market_gate: Account<'info, MarketGate>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [ALLOWLIST_SEED, market_gate.key().as_ref(), user.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [ALLOWLIST_SEED, allowlist_entry.gate.as_ref(), allowlist_entry.user.as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+// ========== MARKET ACCOUNTS ==========
+
+#[derive(Accounts)]
+#[instruction(market_id: u16)]
+pub struct CreateMarket<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [MARKET_SEED, &market_id.to_le_bytes()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(seeds = [COLLATERAL_SEED, collateral.mint.as_ref()], bump = collateral.bump)]
+    pub collateral: Account<'info, Collateral>,
+    #[account(seeds = [BORROWABLE_SEED, borrowable.mint.as_ref()], bump = borrowable.bump)]
+    pub borrowable: Account<'info, Borrowable>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarket<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, &market.market_id.to_le_bytes()],
+        bump = market.bump
+    )]
+    pub market: Account<'info, Market>,
+    pub admin: Signer<'info>,
+}
+
+// ========== GAD SCHEDULE ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct SetGadSchedule<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + GadSchedule::INIT_SPACE,
+        seeds = [GAD_SCHEDULE_SEED],
+        bump
+    )]
+    pub gad_schedule: Account<'info, GadSchedule>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveGadSchedule<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(mut, close = admin, seeds = [GAD_SCHEDULE_SEED], bump = gad_schedule.bump)]
+    pub gad_schedule: Account<'info, GadSchedule>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+// ========== ASSET CAP ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct SetAssetCaps<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + AssetCaps::INIT_SPACE,
+        seeds = [ASSET_CAPS_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub asset_caps: Account<'info, AssetCaps>,
+    /// CHECK: Collateral or borrowable mint (wSOL for SOL)
+    pub mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ========== TREASURY ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct InitTreasuryVault<'info> {
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    /// CHECK: Treasury PDA, owner of the vault
+    #[account(seeds = [TREASURY_SEED], bump)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [TREASURY_VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConvertTreasuryToUsdc<'info> {
+    /// CHECK: Treasury PDA, signs the swap and funds wSOL from its lamports
+    #[account(mut, seeds = [TREASURY_SEED], bump)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        constraint = source_mint.key() != usdc_borrowable.mint @ LegasiError::InvalidTreasuryConversion
+    )]
+    pub source_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [TREASURY_VAULT_SEED, source_mint.key().as_ref()],
+        bump
+    )]
+    pub source_vault: Box<Account<'info, TokenAccount>>,
+    #[account(seeds = [PRICE_FEED_SEED, source_mint.key().as_ref()], bump = source_price_feed.bump)]
+    pub source_price_feed: Box<Account<'info, PriceFeed>>,
+    #[account(
+        seeds = [BORROWABLE_SEED, usdc_borrowable.mint.as_ref()],
+        bump = usdc_borrowable.bump,
+        constraint = usdc_borrowable.asset_type == AssetType::USDC @ LegasiError::InvalidTreasuryConversion
+    )]
+    pub usdc_borrowable: Box<Account<'info, Borrowable>>,
+    #[account(
+        mut,
+        seeds = [TREASURY_VAULT_SEED, usdc_borrowable.mint.as_ref()],
+        bump
+    )]
+    pub usdc_vault: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Jupiter Aggregator v6
+    #[account(address = jupiter_cpi::ID)]
+    pub jupiter_program: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    // Jupiter route accounts passed via remaining_accounts
+}
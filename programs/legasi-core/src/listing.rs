@@ -0,0 +1,100 @@
+//! # Collateral Listing Proposals
+//!
+//! Anyone can ask for a new collateral asset with propose_collateral_listing: the
+//! proposal records the mint, its oracle and the proposed risk parameters in a
+//! `[LISTING_PROPOSAL_SEED, mint]` PDA, and holds a `LISTING_BOND_LAMPORTS` bond on
+//! top of its rent. The admin then decides:
+//! - approve_collateral_listing registers the collateral with the proposed parameters
+//!   and refunds the bond
+//! - reject_collateral_listing refunds it too, or with `slash` sends it to the treasury,
+//!   so spam proposals cost their author
+//!
+//! Either way the proposal closes and its rent goes back to the proposer. A mint has at
+//! most one open proposal, and none once it is registered.
+//!
+//! Flow:
+//! 1. Proposer calls propose_collateral_listing, posting the bond
+//! 2. Admin calls approve_collateral_listing or reject_collateral_listing
+
+use anchor_lang::prelude::*;
+
+use crate::admin::validate_collateral_params;
+use crate::circuit_breaker::CircuitBreakerConfig;
+use crate::constants::LAMPORTS_PER_SOL;
+use crate::state::{AssetType, Collateral};
+use crate::swap_router::SwapRoute;
+
+/// Bond a proposal posts, refunded unless the admin slashes it
+pub const LISTING_BOND_LAMPORTS: u64 = 5 * LAMPORTS_PER_SOL;
+
+/// Risk parameters a collateral is listed with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct CollateralParams {
+    pub oracle: Pubkey,
+    pub max_ltv_bps: u16,
+    pub liquidation_threshold_bps: u16,
+    pub liquidation_bonus_bps: u16,
+    pub decimals: u8,
+    pub asset_type: AssetType,
+}
+
+impl CollateralParams {
+    pub fn validate(&self) -> Result<()> {
+        validate_collateral_params(
+            self.max_ltv_bps,
+            self.liquidation_threshold_bps,
+            self.liquidation_bonus_bps,
+        )
+    }
+}
+
+/// A pending request to list a collateral asset
+#[account]
+#[derive(InitSpace)]
+pub struct ListingProposal {
+    pub proposer: Pubkey,
+    pub mint: Pubkey,
+    pub params: CollateralParams,
+    /// Bond held on the proposal on top of its rent (lamports)
+    pub bond_lamports: u64,
+    pub proposed_at: i64,
+    pub bump: u8,
+}
+
+impl Collateral {
+    /// Set up a newly registered collateral of `mint` with `params`
+    pub fn list(&mut self, mint: Pubkey, params: &CollateralParams, bump: u8) {
+        self.mint = mint;
+        self.oracle = params.oracle;
+        self.max_ltv_bps = params.max_ltv_bps;
+        self.liquidation_threshold_bps = params.liquidation_threshold_bps;
+        self.liquidation_bonus_bps = params.liquidation_bonus_bps;
+        self.decimals = params.decimals;
+        self.is_active = true;
+        self.total_deposited = 0;
+        self.asset_type = params.asset_type;
+        self.swap_route = SwapRoute::default();
+        self.circuit_breaker = CircuitBreakerConfig::default();
+        self.bump = bump;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_params() {
+        let mut params = CollateralParams {
+            oracle: Pubkey::default(),
+            max_ltv_bps: 6_000,
+            liquidation_threshold_bps: 7_000,
+            liquidation_bonus_bps: 800,
+            decimals: 8,
+            asset_type: AssetType::CbBTC,
+        };
+        params.validate().unwrap();
+        params.max_ltv_bps = 7_000;
+        assert!(params.validate().is_err());
+    }
+}
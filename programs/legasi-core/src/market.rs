@@ -25,21 +25,40 @@
 //!
 //! ## Security Considerations
 //!
-//! - eMode can only be changed when user has no active borrows
+//! - eMode can only be changed with debt open if the market covering it is in the new
+//!   category and the position fits its LTV (see `legasi_lending::emode`)
 //! - Supply/borrow caps prevent concentration risk
 //! - Each market has independent liquidation parameters
+//!
+//! ## GAD Proceeds
+//!
+//! Each market picks what GAD does with the collateral it liquidates (`GadProceedsMode`):
+//! hold it in the collateral asset (`crank_gad`), sell it for the borrow asset at once,
+//! or sell it over several cranks (`crank_gad_with_swap` / `crank_gad_lst_with_swap`).
+//! A crank passing the market covering the position must be the one its mode calls for
 
-use anchor_lang::prelude::*;
+use crate::constants::BPS_DENOMINATOR;
+use crate::errors::LegasiError;
 use crate::state::AssetType;
+use anchor_lang::prelude::*;
+
+/// Longest `Market.name` (bytes)
+pub const MAX_MARKET_NAME_LEN: usize = 32;
+
+/// Most cranks a `GadProceedsMode::Twap` market spreads a sale over
+pub const MAX_GAD_TWAP_SLICES: u8 = 24;
 
 // ========== EMODE CATEGORIES ==========
 
 /// Efficiency Mode categories for correlated assets
 /// Higher LTV allowed when collateral and borrow are in same category
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
 #[repr(u8)]
 pub enum EModeCategory {
     /// No eMode (standard parameters)
+    #[default]
     None = 0,
     /// Stablecoins (USDC, USDT, EURC) - highly correlated
     Stablecoins = 1,
@@ -51,10 +70,22 @@ pub enum EModeCategory {
     BtcCorrelated = 4,
 }
 
-impl Default for EModeCategory {
-    fn default() -> Self {
-        EModeCategory::None
-    }
+// ========== GAD PROCEEDS ==========
+
+/// What GAD does with the collateral it liquidates in a market
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default, InitSpace,
+)]
+#[repr(u8)]
+pub enum GadProceedsMode {
+    /// Kept in the collateral asset: `crank_gad` moves it to the treasury for LP recovery
+    #[default]
+    Hold = 0,
+    /// Sold for the borrow asset in the crank that liquidates it
+    Swap = 1,
+    /// Sold for the borrow asset over several cranks, each selling 1/`gad_twap_slices`
+    /// of the liquidation due and carrying the rest to the next one
+    Twap = 2,
 }
 
 // ========== MARKET CONFIG ==========
@@ -66,40 +97,37 @@ impl Default for EModeCategory {
 pub struct Market {
     /// Unique market identifier
     pub market_id: u16,
-    
+
     /// Market name (for UI display)
     #[max_len(32)]
     pub name: String,
-    
+
     // === Asset Configuration ===
-    
     /// Collateral asset type
     pub collateral_asset: AssetType,
     /// Collateral token mint
     pub collateral_mint: Pubkey,
-    
+
     /// Borrowable asset type
     pub borrow_asset: AssetType,
     /// Borrowable token mint
     pub borrow_mint: Pubkey,
-    
+
     // === Risk Parameters ===
-    
     /// Base max LTV (basis points) - without eMode
     pub base_max_ltv_bps: u16,
     /// eMode max LTV (basis points) - when both assets in same category
     pub emode_max_ltv_bps: u16,
-    
+
     /// GAD soft threshold (basis points above max LTV)
     pub gad_soft_threshold_bps: u16,
     /// GAD hard threshold (basis points above max LTV)
     pub gad_hard_threshold_bps: u16,
-    
+
     /// Liquidation bonus (basis points)
     pub liquidation_bonus_bps: u16,
-    
+
     // === Interest Rate Model ===
-    
     /// Base interest rate (APY in basis points)
     pub base_interest_rate_bps: u16,
     /// Slope 1 - rate increase per utilization before kink
@@ -108,67 +136,115 @@ pub struct Market {
     pub slope2_bps: u16,
     /// Optimal utilization rate (basis points)
     pub optimal_utilization_bps: u16,
-    
+
     // === eMode Configuration ===
-    
     /// eMode category (for correlated asset boost)
     pub emode_category: EModeCategory,
-    
+
     // === Caps & Limits ===
-    
     /// Supply cap (max total collateral)
     pub supply_cap: u64,
     /// Borrow cap (max total borrows)
     pub borrow_cap: u64,
     /// Min borrow amount
     pub min_borrow: u64,
-    
+
     // === State ===
-    
     /// Is market active
     pub is_active: bool,
     /// Is borrowing enabled
     pub borrow_enabled: bool,
     /// Is collateral enabled
     pub collateral_enabled: bool,
-    
+
     /// Total collateral deposited
     pub total_collateral: u64,
     /// Total borrowed
     pub total_borrowed: u64,
-    
+
     /// Creation timestamp
     pub created_at: i64,
     /// Last update timestamp
     pub updated_at: i64,
-    
+
+    // === GAD Proceeds ===
+    /// What GAD does with liquidated collateral
+    pub gad_proceeds: GadProceedsMode,
+    /// Cranks a `Twap` sale is spread over (0 unless `Twap`)
+    pub gad_twap_slices: u8,
+
     pub bump: u8,
 }
 
 impl Market {
+    /// Overwrite the market's parameters with `params` (already validated)
+    pub fn apply_params(&mut self, params: &MarketParams) {
+        self.name = params.name.clone();
+        self.base_max_ltv_bps = params.base_max_ltv_bps;
+        self.emode_max_ltv_bps = params.emode_max_ltv_bps;
+        self.gad_soft_threshold_bps = params.gad_soft_threshold_bps;
+        self.gad_hard_threshold_bps = params.gad_hard_threshold_bps;
+        self.liquidation_bonus_bps = params.liquidation_bonus_bps;
+        self.base_interest_rate_bps = params.base_interest_rate_bps;
+        self.slope1_bps = params.slope1_bps;
+        self.slope2_bps = params.slope2_bps;
+        self.optimal_utilization_bps = params.optimal_utilization_bps;
+        self.emode_category = params.emode_category;
+        self.supply_cap = params.supply_cap;
+        self.borrow_cap = params.borrow_cap;
+        self.min_borrow = params.min_borrow;
+    }
+
+    /// Set the GAD proceeds preference. `Twap` needs 2 to `MAX_GAD_TWAP_SLICES` slices,
+    /// and `Hold` a SOL collateral market, the only collateral `crank_gad` liquidates
+    pub fn set_gad_proceeds(&mut self, mode: GadProceedsMode, twap_slices: u8) -> Result<()> {
+        match mode {
+            GadProceedsMode::Twap => require!(
+                (2..=MAX_GAD_TWAP_SLICES).contains(&twap_slices),
+                LegasiError::InvalidMarketParams
+            ),
+            GadProceedsMode::Hold => require!(
+                twap_slices == 0 && self.collateral_asset == AssetType::SOL,
+                LegasiError::InvalidMarketParams
+            ),
+            GadProceedsMode::Swap => {
+                require!(twap_slices == 0, LegasiError::InvalidMarketParams)
+            }
+        }
+        self.gad_proceeds = mode;
+        self.gad_twap_slices = twap_slices;
+        Ok(())
+    }
+
+    /// Cranks GAD spreads a sale over in this market (1 = all at once)
+    pub fn gad_sale_slices(&self) -> u8 {
+        match self.gad_proceeds {
+            GadProceedsMode::Twap => self.gad_twap_slices.max(1),
+            _ => 1,
+        }
+    }
+
     /// Calculate effective max LTV based on eMode
     pub fn get_effective_max_ltv(&self, user_emode: EModeCategory) -> u16 {
-        if self.emode_category != EModeCategory::None 
-            && self.emode_category == user_emode 
-        {
+        if self.emode_category != EModeCategory::None && self.emode_category == user_emode {
             self.emode_max_ltv_bps
         } else {
             self.base_max_ltv_bps
         }
     }
-    
+
     /// Calculate current interest rate based on utilization
     pub fn calculate_interest_rate(&self) -> u16 {
         if self.total_collateral == 0 {
             return self.base_interest_rate_bps;
         }
-        
+
         // Utilization = total_borrowed / total_available
         let utilization_bps = (self.total_borrowed as u128)
             .saturating_mul(10000)
             .checked_div(self.total_collateral as u128)
             .unwrap_or(0) as u16;
-        
+
         if utilization_bps <= self.optimal_utilization_bps {
             // Below kink: base + slope1 * (utilization / optimal)
             let rate_increase = (self.slope1_bps as u32)
@@ -189,7 +265,7 @@ impl Market {
                 .saturating_add(rate_increase)
         }
     }
-    
+
     /// Check if supply cap allows more deposits
     pub fn can_supply(&self, amount: u64) -> bool {
         if self.supply_cap == 0 {
@@ -197,7 +273,7 @@ impl Market {
         }
         self.total_collateral.saturating_add(amount) <= self.supply_cap
     }
-    
+
     /// Check if borrow cap allows more borrows
     pub fn can_borrow(&self, amount: u64) -> bool {
         if !self.borrow_enabled {
@@ -220,14 +296,14 @@ impl MarketPreset {
     pub fn sol_usdc() -> MarketParams {
         MarketParams {
             name: "SOL/USDC".to_string(),
-            base_max_ltv_bps: 7500,       // 75%
-            emode_max_ltv_bps: 7500,      // No eMode boost
-            gad_soft_threshold_bps: 500,  // GAD at 80%
-            gad_hard_threshold_bps: 1500, // Hard at 90%
-            liquidation_bonus_bps: 500,   // 5%
-            base_interest_rate_bps: 200,  // 2%
-            slope1_bps: 400,              // 4%
-            slope2_bps: 7500,             // 75%
+            base_max_ltv_bps: 7500,        // 75%
+            emode_max_ltv_bps: 7500,       // No eMode boost
+            gad_soft_threshold_bps: 500,   // GAD at 80%
+            gad_hard_threshold_bps: 1500,  // Hard at 90%
+            liquidation_bonus_bps: 500,    // 5%
+            base_interest_rate_bps: 200,   // 2%
+            slope1_bps: 400,               // 4%
+            slope2_bps: 7500,              // 75%
             optimal_utilization_bps: 8000, // 80%
             emode_category: EModeCategory::None,
             supply_cap: 0,
@@ -235,17 +311,17 @@ impl MarketPreset {
             min_borrow: 1_000_000, // $1 USDC
         }
     }
-    
+
     /// USDC/USDT - Stablecoin eMode
     pub fn usdc_usdt_emode() -> MarketParams {
         MarketParams {
             name: "USDC/USDT eMode".to_string(),
-            base_max_ltv_bps: 9000,       // 90% base
-            emode_max_ltv_bps: 9700,      // 97% with eMode!
-            gad_soft_threshold_bps: 100,  // Very tight
+            base_max_ltv_bps: 9000,      // 90% base
+            emode_max_ltv_bps: 9700,     // 97% with eMode!
+            gad_soft_threshold_bps: 100, // Very tight
             gad_hard_threshold_bps: 300,
-            liquidation_bonus_bps: 100,   // Minimal bonus
-            base_interest_rate_bps: 50,   // Very low rates
+            liquidation_bonus_bps: 100, // Minimal bonus
+            base_interest_rate_bps: 50, // Very low rates
             slope1_bps: 100,
             slope2_bps: 3000,
             optimal_utilization_bps: 9500,
@@ -255,7 +331,7 @@ impl MarketPreset {
             min_borrow: 1_000_000,
         }
     }
-    
+
     /// cbBTC/USDC - Bitcoin market
     pub fn cbbtc_usdc() -> MarketParams {
         MarketParams {
@@ -296,9 +372,32 @@ pub struct MarketParams {
     pub min_borrow: u64,
 }
 
+impl MarketParams {
+    /// Reject parameters a market can't run on: a name that doesn't fit, an eMode LTV
+    /// below the base one, GAD thresholds out of order or past 100%, or a kink the
+    /// rate model can't divide by
+    pub fn validate(&self) -> Result<()> {
+        let bps = BPS_DENOMINATOR as u32;
+        require!(
+            !self.name.is_empty() && self.name.len() <= MAX_MARKET_NAME_LEN,
+            LegasiError::InvalidMarketParams
+        );
+        require!(
+            self.base_max_ltv_bps <= self.emode_max_ltv_bps
+                && self.gad_soft_threshold_bps < self.gad_hard_threshold_bps
+                && self.emode_max_ltv_bps as u32 + self.gad_hard_threshold_bps as u32 <= bps
+                && (self.liquidation_bonus_bps as u32) < bps
+                && self.optimal_utilization_bps > 0
+                && (self.optimal_utilization_bps as u32) < bps,
+            LegasiError::InvalidMarketParams
+        );
+        Ok(())
+    }
+}
+
 // ========== USER EMODE ==========
 
-/// User's eMode selection (stored in Position or separate account)
+/// User's eMode selection (stored on the lending `Position`)
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
 pub struct UserEMode {
     /// Selected eMode category
@@ -312,3 +411,37 @@ impl UserEMode {
         self.category != EModeCategory::None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_valid() {
+        for params in [
+            MarketPreset::sol_usdc(),
+            MarketPreset::usdc_usdt_emode(),
+            MarketPreset::cbbtc_usdc(),
+        ] {
+            assert!(params.validate().is_ok(), "{}", params.name);
+        }
+    }
+
+    #[test]
+    fn test_invalid_params_rejected() {
+        let cases: [fn(&mut MarketParams); 6] = [
+            |p| p.name = String::new(),
+            |p| p.name = "x".repeat(MAX_MARKET_NAME_LEN + 1),
+            |p| p.emode_max_ltv_bps = p.base_max_ltv_bps - 1,
+            |p| p.gad_soft_threshold_bps = p.gad_hard_threshold_bps,
+            // Hard threshold past 100% LTV
+            |p| p.gad_hard_threshold_bps = 2_600,
+            |p| p.optimal_utilization_bps = 10_000,
+        ];
+        for case in cases {
+            let mut params = MarketPreset::sol_usdc();
+            case(&mut params);
+            assert!(params.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Oracle aggregation
+//!
+//! A `PriceFeed` can blend several oracles instead of trusting one Pyth account. The
+//! admin records each feed's sources with `AdminOp::SetOracleSources`: a Pyth price
+//! account, a Switchboard On-Demand pull feed, or both, and how far apart they may
+//! be. `sync_oracle_price` (permissionless) reads every configured source, rejects
+//! stale ones, and writes their median to the feed, failing with `OracleDeviation`
+//! when a source is more than `max_deviation_bps` from it. The publish time of each
+//! source's price is kept on the feed.
+//!
+//! A feed with sources can't be synced from a single account with `sync_pyth_price`,
+//! so the cross-check can't be bypassed.
+//!
+//! Flow:
+//! 1. Admin sets the feed's sources and deviation threshold
+//! 2. Anyone calls sync_oracle_price with all configured source accounts
+
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::errors::LegasiError;
+
+/// Oracle sources blended into a `PriceFeed`
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct OracleSources {
+    /// Pyth price account (default = not used)
+    pub pyth: Pubkey,
+    /// Switchboard On-Demand pull feed (default = not used)
+    pub switchboard: Pubkey,
+    /// Max distance of a source from the median (basis points, 0 = unbounded)
+    pub max_deviation_bps: u16,
+    /// Publish time of the last Pyth price blended in
+    pub pyth_published_at: i64,
+    /// Publish time of the last Switchboard price blended in
+    pub switchboard_published_at: i64,
+}
+
+impl OracleSources {
+    /// Whether the feed is synced by aggregation rather than `sync_pyth_price`
+    pub fn is_configured(&self) -> bool {
+        self.pyth != Pubkey::default() || self.switchboard != Pubkey::default()
+    }
+}
+
+/// Median of the source `prices` (mean of the middle two for an even count),
+/// failing with `OracleDeviation` when one is more than `max_deviation_bps` from it
+pub fn median_price(prices: &[u64], max_deviation_bps: u16) -> Result<u64> {
+    require!(
+        !prices.is_empty() && prices.iter().all(|&price| price > 0),
+        LegasiError::InvalidOracle
+    );
+    let mut sorted = prices.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    let median = if sorted.len() % 2 == 0 {
+        ((sorted[mid - 1] as u128 + sorted[mid] as u128) / 2) as u64
+    } else {
+        sorted[mid]
+    };
+    if max_deviation_bps > 0 {
+        for &price in prices {
+            let deviation_bps =
+                price.abs_diff(median) as u128 * BPS_DENOMINATOR as u128 / median as u128;
+            require!(
+                deviation_bps <= max_deviation_bps as u128,
+                LegasiError::OracleDeviation
+            );
+        }
+    }
+    Ok(median)
+}
+
+/// Switchboard On-Demand pull feed result (simplified)
+/// In production, use the official switchboard-on-demand crate
+#[derive(Clone, Copy, Debug)]
+pub struct SwitchboardPrice {
+    /// Price in USD, 18 decimals
+    pub value: i128,
+    /// Unix time of the last update
+    pub last_update: i64,
+}
+
+/// Decimals of Switchboard On-Demand values
+pub const SWITCHBOARD_DECIMALS: u32 = 18;
+
+impl SwitchboardPrice {
+    /// Convert to our standard 6-decimal USD format (0 for a non-positive value)
+    pub fn to_usd_6dec(&self) -> u64 {
+        if self.value <= 0 {
+            return 0;
+        }
+        u64::try_from(self.value / 10i128.pow(SWITCHBOARD_DECIMALS - 6)).unwrap_or(0)
+    }
+
+    /// Check if price is stale (older than max_age seconds)
+    pub fn is_stale(&self, current_time: i64, max_age_seconds: i64) -> bool {
+        current_time - self.last_update > max_age_seconds
+    }
+}
+
+/// Parse a Switchboard On-Demand `PullFeedAccountData`
+/// This is a simplified version - in production use switchboard-on-demand
+pub fn parse_switchboard_price(data: &[u8]) -> Option<SwitchboardPrice> {
+    // Layout (simplified): discriminator (8), 32 oracle submissions (64 each),
+    // authority, queue, feed hash, then config; last_update_timestamp at 2216 and
+    // the current result's value (i128) at 2264
+    if data.len() < 2280 {
+        return None;
+    }
+
+    let last_update = i64::from_le_bytes(data[2216..2224].try_into().ok()?);
+    let value = i128::from_le_bytes(data[2264..2280].try_into().ok()?);
+
+    Some(SwitchboardPrice { value, last_update })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median_price() {
+        assert_eq!(median_price(&[100_000_000], 0).unwrap(), 100_000_000);
+        // Two sources blend to their mean
+        assert_eq!(
+            median_price(&[100_000_000, 101_000_000], 100).unwrap(),
+            100_500_000
+        );
+        assert_eq!(
+            median_price(&[101_000_000, 99_000_000, 100_000_000], 100).unwrap(),
+            100_000_000
+        );
+        // 2% apart: each is ~1% from the mean, past a 0.5% threshold
+        assert!(median_price(&[100_000_000, 102_000_000], 50).is_err());
+        assert!(median_price(&[100_000_000, 102_000_000], 0).is_ok());
+        assert!(median_price(&[], 0).is_err());
+        assert!(median_price(&[100_000_000, 0], 0).is_err());
+    }
+
+    #[test]
+    fn test_switchboard_price() {
+        let mut data = vec![0u8; 2280];
+        data[2216..2224].copy_from_slice(&1_000i64.to_le_bytes());
+        // $150.25 at 18 decimals
+        data[2264..2280].copy_from_slice(&150_250_000_000_000_000_000i128.to_le_bytes());
+        let price = parse_switchboard_price(&data).unwrap();
+        assert_eq!(price.to_usd_6dec(), 150_250_000);
+        assert!(!price.is_stale(1_060, 60));
+        assert!(price.is_stale(1_061, 60));
+        assert!(parse_switchboard_price(&data[..2279]).is_none());
+    }
+}
@@ -0,0 +1,509 @@
+//! # PDA Derivation
+//!
+//! Canonical derivation of every Legasi PDA, from the consts in `crate::seeds`, for
+//! clients (the SDK, keepers, tests). Behind the `pda` feature: programs check PDAs
+//! through their `#[account(seeds = ...)]` constraints instead.
+//! Every helper returns `(address, bump)`.
+
+use anchor_lang::prelude::Pubkey;
+use std::str::FromStr;
+
+use crate::automation::ThreadKind;
+use crate::constants::{FLASH_PROGRAM_ID, GAD_PROGRAM_ID, LENDING_PROGRAM_ID, LP_PROGRAM_ID};
+use crate::seeds::*;
+
+fn program(id: &str) -> Pubkey {
+    Pubkey::from_str(id).unwrap()
+}
+
+// ========== CORE ==========
+
+pub fn protocol() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROTOCOL_SEED], &crate::ID)
+}
+
+pub fn collateral(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[COLLATERAL_SEED, mint.as_ref()], &crate::ID)
+}
+
+pub fn borrowable(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BORROWABLE_SEED, mint.as_ref()], &crate::ID)
+}
+
+pub fn price_feed(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PRICE_FEED_SEED, mint.as_ref()], &crate::ID)
+}
+
+pub fn fee_budget(authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_BUDGET_SEED, authority.as_ref()], &crate::ID)
+}
+
+/// PDA a program signs `update_protocol_totals` with (see `crate::totals`)
+pub fn protocol_writer(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROTOCOL_WRITER_SEED], program_id)
+}
+
+pub fn automation_thread(fee_budget: &Pubkey, target: &Pubkey, kind: ThreadKind) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            THREAD_SEED,
+            fee_budget.as_ref(),
+            target.as_ref(),
+            &[kind as u8],
+        ],
+        &crate::ID,
+    )
+}
+
+/// Gate restricting `mint`'s market (see `crate::gate`)
+pub fn market_gate(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MARKET_GATE_SEED, mint.as_ref()], &crate::ID)
+}
+
+pub fn allowlist_entry(market_gate: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ALLOWLIST_SEED, market_gate.as_ref(), user.as_ref()],
+        &crate::ID,
+    )
+}
+
+pub fn market(market_id: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MARKET_SEED, &market_id.to_le_bytes()], &crate::ID)
+}
+
+/// Program-owned treasury, set as `Protocol.treasury` to let fee income be converted
+pub fn treasury() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED], &crate::ID)
+}
+
+/// Treasury's token account for `mint`
+pub fn treasury_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_VAULT_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// GAD throttle windows (see `crate::gad_schedule`)
+pub fn gad_schedule() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GAD_SCHEDULE_SEED], &crate::ID)
+}
+
+/// Supply and borrow caps of `mint` (see `crate::caps`)
+pub fn asset_caps(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ASSET_CAPS_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Pending collateral listing proposal of `mint` (see `crate::listing`)
+pub fn listing_proposal(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LISTING_PROPOSAL_SEED, mint.as_ref()], &crate::ID)
+}
+
+// ========== LENDING ==========
+
+pub fn position(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[POSITION_SEED, owner.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn sol_vault(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SOL_VAULT_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn msol_vault(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[MSOL_VAULT_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn token_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[TOKEN_VAULT_SEED, mint.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn deposit_receipt(position: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DEPOSIT_RECEIPT_SEED, position.as_ref(), mint.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn agent_config(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AGENT_CONFIG_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn agent_profile(agent_config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AGENT_PROFILE_SEED, agent_config.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+/// Deprecated per-mint vault, only read by `migrate_lending_vault`.
+/// Borrows and repays settle against `lp_vault`
+pub fn lending_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LENDING_VAULT_SEED, mint.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn repayment_schedule(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REPAYMENT_SCHEDULE_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn auto_deleverage_order(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AUTO_DELEVERAGE_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+/// Token account an auto-deleverage order's swaps pay into
+pub fn auto_deleverage_proceeds(order: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AUTO_DELEVERAGE_PROCEEDS_SEED, order.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn credit_line(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CREDIT_LINE_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn withdrawal_allowlist(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[WITHDRAWAL_ALLOWLIST_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn succession(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SUCCESSION_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn letter_of_credit(position: &Pubkey, letter_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            LETTER_OF_CREDIT_SEED,
+            position.as_ref(),
+            &letter_id.to_le_bytes(),
+        ],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn referrer(referrer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REFERRER_SEED, referrer.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn referral_vault(referrer: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REFERRAL_VAULT_SEED, referrer.as_ref(), mint.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn points_ledger(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[POINTS_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn points_snapshot(epoch: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[POINTS_SNAPSHOT_SEED, &epoch.to_le_bytes()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn statement_ledger(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[STATEMENT_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn notify_service(service: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[NOTIFY_SERVICE_SEED, service.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn notify_subscription(position: &Pubkey, service: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            NOTIFY_SUBSCRIPTION_SEED,
+            position.as_ref(),
+            service.as_ref(),
+        ],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn notify_fee_vault(subscription: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[NOTIFY_FEE_VAULT_SEED, subscription.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn voting_power(owner: &Pubkey, epoch: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VOTING_POWER_SEED, owner.as_ref(), &epoch.to_le_bytes()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn offramp_request(owner: &Pubkey, request_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[OFFRAMP_SEED, owner.as_ref(), &request_id.to_le_bytes()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn offramp_escrow(offramp_request: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[OFFRAMP_ESCROW_SEED, offramp_request.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn position_cctp_inbox(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CCTP_INBOX_SEED, position.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn x402_receipt(payment_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[X402_RECEIPT_SEED, payment_id.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn service_listing(provider: &Pubkey, service_id: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SERVICE_LISTING_SEED, provider.as_ref(), service_id.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+pub fn solana_pay_receipt(reference: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SOLANA_PAY_RECEIPT_SEED, reference.as_ref()],
+        &program(LENDING_PROGRAM_ID),
+    )
+}
+
+// ========== LP ==========
+
+pub fn lp_pool(borrowable_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LP_POOL_SEED, borrowable_mint.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn lp_token_mint(borrowable_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LP_TOKEN_SEED, borrowable_mint.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn lp_vault(borrowable_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LP_VAULT_SEED, borrowable_mint.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn reserve_vault(borrowable_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RESERVE_VAULT_SEED, borrowable_mint.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn insurance_vault(borrowable_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[INSURANCE_VAULT_SEED, borrowable_mint.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn deposit_schedule(owner: &Pubkey, borrowable_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            DEPOSIT_SCHEDULE_SEED,
+            owner.as_ref(),
+            borrowable_mint.as_ref(),
+        ],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn lp_allowlist_entry(lp_pool: &Pubkey, lp: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LP_ALLOWLIST_SEED, lp_pool.as_ref(), lp.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn lp_lock(lp_pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LP_LOCK_SEED, lp_pool.as_ref(), owner.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+/// Escrow holding a crisis lock's LP shares
+pub fn lp_lock_escrow(lp_lock: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LP_LOCK_ESCROW_SEED, lp_lock.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn locked_deposit(lp_pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LOCKED_DEPOSIT_SEED, lp_pool.as_ref(), owner.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn locked_deposit_escrow(locked_deposit: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LOCKED_DEPOSIT_ESCROW_SEED, locked_deposit.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn surplus_auction(lp_pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SURPLUS_AUCTION_SEED, lp_pool.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+/// Escrow holding a surplus auction's top bid
+pub fn surplus_bid_escrow(auction: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SURPLUS_BID_ESCROW_SEED, auction.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn withdraw_request(lp_pool: &Pubkey, id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[WITHDRAW_REQUEST_SEED, lp_pool.as_ref(), &id.to_le_bytes()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+/// Escrow holding a queued withdrawal's LP shares
+pub fn withdraw_request_escrow(withdraw_request: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[WITHDRAW_REQUEST_ESCROW_SEED, withdraw_request.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn reward_config(lp_pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REWARD_CONFIG_SEED, lp_pool.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+/// Token account holding a reward config's funded rewards
+pub fn reward_vault(reward_config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REWARD_VAULT_SEED, reward_config.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+/// Token account holding the LP shares staked for rewards
+pub fn reward_stake_vault(reward_config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REWARD_STAKE_VAULT_SEED, reward_config.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn user_reward_state(reward_config: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[USER_REWARD_SEED, reward_config.as_ref(), owner.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+pub fn lp_cctp_inbox(lp_pool: &Pubkey, beneficiary: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CCTP_INBOX_SEED, lp_pool.as_ref(), beneficiary.as_ref()],
+        &program(LP_PROGRAM_ID),
+    )
+}
+
+// ========== GAD ==========
+
+pub fn sponsorship(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SPONSORSHIP_SEED, position.as_ref()],
+        &program(GAD_PROGRAM_ID),
+    )
+}
+
+/// Sponsor's SOL backstop, drawn by `crank_gad` before the position's collateral
+pub fn sponsor_vault(position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SPONSOR_VAULT_SEED, position.as_ref()],
+        &program(GAD_PROGRAM_ID),
+    )
+}
+
+/// Liquidation auction of a position's collateral against its `borrowable_mint` debt
+pub fn liquidation_auction(position: &Pubkey, borrowable_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            LIQUIDATION_AUCTION_SEED,
+            position.as_ref(),
+            borrowable_mint.as_ref(),
+        ],
+        &program(GAD_PROGRAM_ID),
+    )
+}
+
+// ========== FLASH ==========
+
+pub fn flash_state(borrower: &Pubkey, slot: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[FLASH_SEED, borrower.as_ref(), &slot.to_le_bytes()],
+        &program(FLASH_PROGRAM_ID),
+    )
+}
@@ -0,0 +1,38 @@
+//! Sanctum Router CPI helpers
+//!
+//! LST-optimized swaps (mSOL, jitoSOL, ...) through Sanctum's stake-pool
+//! router. Large LST → SOL deleverages unstake through the pool instead of
+//! walking thin AMM books, so slippage stays flat with size.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::errors::LegasiError;
+use crate::jupiter_cpi::build_account_metas;
+
+// Sanctum Router Program ID (mainnet)
+declare_id!("stkitrT1Uoy18Dk1fTrgPw8W6MVzoCfYoAFT4MLsmhq");
+
+/// Execute a Sanctum router swap via CPI
+/// `route_data` is the serialized router instruction returned by the Sanctum API
+pub fn swap<'info>(
+    sanctum_program: &AccountInfo<'info>,
+    route_accounts: &[AccountInfo<'info>],
+    route_data: Vec<u8>,
+    pda_signer: Option<&Pubkey>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require_keys_eq!(sanctum_program.key(), ID, LegasiError::InvalidSwapProgram);
+
+    invoke_signed(
+        &Instruction {
+            program_id: ID,
+            accounts: build_account_metas(route_accounts, pda_signer),
+            data: route_data,
+        },
+        route_accounts,
+        signer_seeds,
+    )?;
+    Ok(())
+}
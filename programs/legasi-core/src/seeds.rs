@@ -0,0 +1,348 @@
+//! # PDA Seeds
+//!
+//! Every PDA seed prefix of the Legasi programs, in one place. Programs use these
+//! in their `#[account(seeds = ...)]` constraints and signer seeds, and the
+//! derivations in `crate::pda` (behind the `pda` feature) use the same consts, so a
+//! seed can't drift between a program and its clients.
+//!
+//! New seeds go in `ALL_SEEDS` too: a compile-time check rejects duplicates.
+
+// ========== CORE ==========
+
+/// Seed of the `[PROTOCOL_SEED]` singleton
+pub const PROTOCOL_SEED: &[u8] = b"protocol";
+
+/// Seed of the `[COLLATERAL_SEED, mint]` config PDA
+pub const COLLATERAL_SEED: &[u8] = b"collateral";
+
+/// Seed of the `[BORROWABLE_SEED, mint]` config PDA
+pub const BORROWABLE_SEED: &[u8] = b"borrowable";
+
+/// Seed of the `[PRICE_FEED_SEED, mint]` PDA
+pub const PRICE_FEED_SEED: &[u8] = b"price";
+
+/// Seed of the `[FEE_BUDGET_SEED, authority]` PDA
+pub const FEE_BUDGET_SEED: &[u8] = b"fee_budget";
+
+/// Seed of the `[THREAD_SEED, fee_budget, target, kind]` PDA
+pub const THREAD_SEED: &[u8] = b"thread";
+
+/// Seed of the PDA each writer program signs `update_protocol_totals` with
+pub const PROTOCOL_WRITER_SEED: &[u8] = b"protocol_writer";
+
+/// Seed of the `[MARKET_GATE_SEED, mint]` PDA
+pub const MARKET_GATE_SEED: &[u8] = b"market_gate";
+
+/// Seed of the `[ALLOWLIST_SEED, gate, user]` PDA
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+
+/// Seed of the `[MARKET_SEED, market_id]` PDA
+pub const MARKET_SEED: &[u8] = b"market";
+
+/// Seed of the `[TREASURY_SEED]` PDA that can hold the treasury's SOL and own its vaults
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+/// Seed of the `[TREASURY_VAULT_SEED, mint]` token account owned by the treasury PDA
+pub const TREASURY_VAULT_SEED: &[u8] = b"treasury_vault";
+
+/// Seed of the `[GAD_SCHEDULE_SEED]` PDA holding the GAD throttle windows
+pub const GAD_SCHEDULE_SEED: &[u8] = b"gad_schedule";
+
+/// Seed of the `[ASSET_CAPS_SEED, mint]` PDA holding a mint's supply and borrow caps
+pub const ASSET_CAPS_SEED: &[u8] = b"asset_caps";
+
+/// Seed of the `[LISTING_PROPOSAL_SEED, mint]` PDA holding a bonded collateral listing proposal
+pub const LISTING_PROPOSAL_SEED: &[u8] = b"listing_proposal";
+
+// ========== LENDING ==========
+
+/// Seed of the `[POSITION_SEED, owner]` PDA
+pub const POSITION_SEED: &[u8] = b"position";
+
+/// Seed of the `[SOL_VAULT_SEED, position]` PDA holding SOL collateral
+pub const SOL_VAULT_SEED: &[u8] = b"sol_vault";
+
+/// Seed of the `[MSOL_VAULT_SEED, position]` token account holding staked SOL
+pub const MSOL_VAULT_SEED: &[u8] = b"msol_vault";
+
+/// Seed of the `[TOKEN_VAULT_SEED, mint]` token account holding SPL collateral
+pub const TOKEN_VAULT_SEED: &[u8] = b"token_vault";
+
+/// Seed of the `[DEPOSIT_RECEIPT_SEED, position, mint]` PDA recording SPL collateral lots
+pub const DEPOSIT_RECEIPT_SEED: &[u8] = b"deposit_receipt";
+
+/// Seed of the `[AGENT_CONFIG_SEED, position]` PDA
+pub const AGENT_CONFIG_SEED: &[u8] = b"agent_config";
+
+/// Seed of the `[AGENT_PROFILE_SEED, agent_config]` PDA
+pub const AGENT_PROFILE_SEED: &[u8] = b"agent_profile";
+
+/// Seed of the deprecated `[LENDING_VAULT_SEED, mint]` token account
+pub const LENDING_VAULT_SEED: &[u8] = b"lending_vault";
+
+/// Seed of the `[REPAYMENT_SCHEDULE_SEED, position]` PDA
+pub const REPAYMENT_SCHEDULE_SEED: &[u8] = b"repayment_schedule";
+
+/// Seed of the `[AUTO_DELEVERAGE_SEED, position]` PDA
+pub const AUTO_DELEVERAGE_SEED: &[u8] = b"auto_deleverage";
+
+/// Seed of the `[AUTO_DELEVERAGE_PROCEEDS_SEED, order]` token account swaps pay into
+pub const AUTO_DELEVERAGE_PROCEEDS_SEED: &[u8] = b"auto_deleverage_proceeds";
+
+/// Seed of the `[CREDIT_LINE_SEED, position]` PDA
+pub const CREDIT_LINE_SEED: &[u8] = b"credit_line";
+
+/// Seed of the `[WITHDRAWAL_ALLOWLIST_SEED, position]` PDA
+pub const WITHDRAWAL_ALLOWLIST_SEED: &[u8] = b"withdrawal_allowlist";
+
+/// Seed of the `[SUCCESSION_SEED, position]` PDA
+pub const SUCCESSION_SEED: &[u8] = b"succession";
+
+/// Seed of the `[LETTER_OF_CREDIT_SEED, position, letter_id]` PDA
+pub const LETTER_OF_CREDIT_SEED: &[u8] = b"letter_of_credit";
+
+/// Seed of the `[REFERRER_SEED, referrer]` registry PDA
+pub const REFERRER_SEED: &[u8] = b"referrer";
+
+/// Seed of the `[REFERRAL_VAULT_SEED, referrer, mint]` token account PDA
+pub const REFERRAL_VAULT_SEED: &[u8] = b"referral_vault";
+
+/// Seed of the `[POINTS_SEED, position]` ledger PDA
+pub const POINTS_SEED: &[u8] = b"points";
+
+/// Seed of the `[POINTS_SNAPSHOT_SEED, epoch]` snapshot PDA
+pub const POINTS_SNAPSHOT_SEED: &[u8] = b"points_snapshot";
+
+/// Seed of the `[STATEMENT_SEED, position]` ledger PDA
+pub const STATEMENT_SEED: &[u8] = b"statement";
+
+/// Seed of the `[NOTIFY_SERVICE_SEED, service]` registry PDA
+pub const NOTIFY_SERVICE_SEED: &[u8] = b"notify_service";
+
+/// Seed of the `[NOTIFY_SUBSCRIPTION_SEED, position, service]` PDA
+pub const NOTIFY_SUBSCRIPTION_SEED: &[u8] = b"notify_subscription";
+
+/// Seed of the `[NOTIFY_FEE_VAULT_SEED, subscription]` PDA holding prepaid notice fees
+pub const NOTIFY_FEE_VAULT_SEED: &[u8] = b"notify_fee_vault";
+
+/// Seed of the `[VOTING_POWER_SEED, owner, epoch]` PDA
+pub const VOTING_POWER_SEED: &[u8] = b"voting_power";
+
+/// Seed of the `[OFFRAMP_SEED, owner, request_id]` PDA
+pub const OFFRAMP_SEED: &[u8] = b"offramp";
+
+/// Seed of the `[OFFRAMP_ESCROW_SEED, offramp_request]` token account
+pub const OFFRAMP_ESCROW_SEED: &[u8] = b"offramp_escrow";
+
+/// Seed of the CCTP inbox PDAs: `[CCTP_INBOX_SEED, position]` in lending,
+/// `[CCTP_INBOX_SEED, lp_pool, beneficiary]` in LP
+pub const CCTP_INBOX_SEED: &[u8] = b"cctp_inbox";
+
+/// Seed of the `[X402_RECEIPT_SEED, payment_id]` PDA
+pub const X402_RECEIPT_SEED: &[u8] = b"x402_receipt";
+
+/// Seed of the `[SERVICE_LISTING_SEED, provider, service_id]` PDA
+pub const SERVICE_LISTING_SEED: &[u8] = b"service_listing";
+
+/// Seed of the `[SOLANA_PAY_RECEIPT_SEED, reference]` PDA
+pub const SOLANA_PAY_RECEIPT_SEED: &[u8] = b"solana_pay_receipt";
+
+// ========== LP ==========
+
+/// Seed of the `[LP_POOL_SEED, borrowable_mint]` PDA
+pub const LP_POOL_SEED: &[u8] = b"lp_pool";
+
+/// Seed of the `[LP_TOKEN_SEED, borrowable_mint]` share mint
+pub const LP_TOKEN_SEED: &[u8] = b"lp_token";
+
+/// Seed of the `[LP_VAULT_SEED, borrowable_mint]` token account
+pub const LP_VAULT_SEED: &[u8] = b"lp_vault";
+
+/// Seed of the `[RESERVE_VAULT_SEED, borrowable_mint]` token account holding a pool's
+/// reserves
+pub const RESERVE_VAULT_SEED: &[u8] = b"reserve_vault";
+
+/// Seed of the `[INSURANCE_VAULT_SEED, borrowable_mint]` token account holding the
+/// insurance fund's tokens of that asset
+pub const INSURANCE_VAULT_SEED: &[u8] = b"insurance_vault";
+
+/// Seed of the `[DEPOSIT_SCHEDULE_SEED, owner, borrowable_mint]` PDA
+pub const DEPOSIT_SCHEDULE_SEED: &[u8] = b"deposit_schedule";
+
+/// Seed of the `[LP_ALLOWLIST_SEED, lp_pool, lp]` PDA
+pub const LP_ALLOWLIST_SEED: &[u8] = b"lp_allowlist";
+
+/// Seed of the `[LP_LOCK_SEED, lp_pool, owner]` PDA
+pub const LP_LOCK_SEED: &[u8] = b"lp_lock";
+
+/// Seed of the `[LP_LOCK_ESCROW_SEED, lp_lock]` token account holding locked shares
+pub const LP_LOCK_ESCROW_SEED: &[u8] = b"lp_lock_escrow";
+
+/// Seed of the `[LOCKED_DEPOSIT_SEED, lp_pool, owner]` time-locked deposit PDA
+pub const LOCKED_DEPOSIT_SEED: &[u8] = b"locked_deposit";
+
+/// Seed of the `[LOCKED_DEPOSIT_ESCROW_SEED, locked_deposit]` token account PDA
+pub const LOCKED_DEPOSIT_ESCROW_SEED: &[u8] = b"locked_deposit_escrow";
+
+/// Seed of the `[SURPLUS_AUCTION_SEED, lp_pool]` PDA, one auction per pool at a time
+pub const SURPLUS_AUCTION_SEED: &[u8] = b"surplus_auction";
+
+/// Seed of the `[SURPLUS_BID_ESCROW_SEED, auction]` token account holding the top bid
+pub const SURPLUS_BID_ESCROW_SEED: &[u8] = b"surplus_bid_escrow";
+
+/// Seed of the `[WITHDRAW_REQUEST_SEED, lp_pool, id]` queued withdrawal PDA
+pub const WITHDRAW_REQUEST_SEED: &[u8] = b"withdraw_request";
+
+/// Seed of the `[WITHDRAW_REQUEST_ESCROW_SEED, withdraw_request]` token account holding
+/// queued shares
+pub const WITHDRAW_REQUEST_ESCROW_SEED: &[u8] = b"withdraw_request_escrow";
+
+/// Seed of the `[REWARD_CONFIG_SEED, lp_pool]` reward emissions PDA
+pub const REWARD_CONFIG_SEED: &[u8] = b"reward_config";
+
+/// Seed of the `[REWARD_VAULT_SEED, reward_config]` token account holding funded rewards
+pub const REWARD_VAULT_SEED: &[u8] = b"reward_vault";
+
+/// Seed of the `[REWARD_STAKE_VAULT_SEED, reward_config]` token account holding staked
+/// shares
+pub const REWARD_STAKE_VAULT_SEED: &[u8] = b"reward_stake_vault";
+
+/// Seed of the `[USER_REWARD_SEED, reward_config, owner]` PDA
+pub const USER_REWARD_SEED: &[u8] = b"user_reward";
+
+// ========== GAD ==========
+
+/// Seed of the `[SPONSORSHIP_SEED, position]` PDA
+pub const SPONSORSHIP_SEED: &[u8] = b"sponsorship";
+
+/// Seed of the `[SPONSOR_VAULT_SEED, position]` PDA holding the sponsor's SOL backstop
+pub const SPONSOR_VAULT_SEED: &[u8] = b"sponsor_vault";
+
+/// Seed of the `[LIQUIDATION_AUCTION_SEED, position, borrowable_mint]` PDA
+pub const LIQUIDATION_AUCTION_SEED: &[u8] = b"liquidation_auction";
+
+// ========== LEVERAGE / FLASH ==========
+
+/// Seed of the `[LEVERAGE_SEED, position]` PDA
+pub const LEVERAGE_SEED: &[u8] = b"leverage";
+
+/// Seed of the `[FLASH_SEED, borrower, slot]` PDA
+pub const FLASH_SEED: &[u8] = b"flash";
+
+/// Every seed above
+pub const ALL_SEEDS: [&[u8]; 69] = [
+    PROTOCOL_SEED,
+    COLLATERAL_SEED,
+    BORROWABLE_SEED,
+    PRICE_FEED_SEED,
+    FEE_BUDGET_SEED,
+    THREAD_SEED,
+    PROTOCOL_WRITER_SEED,
+    MARKET_GATE_SEED,
+    ALLOWLIST_SEED,
+    MARKET_SEED,
+    TREASURY_SEED,
+    TREASURY_VAULT_SEED,
+    GAD_SCHEDULE_SEED,
+    ASSET_CAPS_SEED,
+    LISTING_PROPOSAL_SEED,
+    POSITION_SEED,
+    SOL_VAULT_SEED,
+    MSOL_VAULT_SEED,
+    TOKEN_VAULT_SEED,
+    DEPOSIT_RECEIPT_SEED,
+    AGENT_CONFIG_SEED,
+    AGENT_PROFILE_SEED,
+    LENDING_VAULT_SEED,
+    REPAYMENT_SCHEDULE_SEED,
+    AUTO_DELEVERAGE_SEED,
+    AUTO_DELEVERAGE_PROCEEDS_SEED,
+    CREDIT_LINE_SEED,
+    WITHDRAWAL_ALLOWLIST_SEED,
+    SUCCESSION_SEED,
+    LETTER_OF_CREDIT_SEED,
+    REFERRER_SEED,
+    REFERRAL_VAULT_SEED,
+    POINTS_SEED,
+    POINTS_SNAPSHOT_SEED,
+    STATEMENT_SEED,
+    NOTIFY_SERVICE_SEED,
+    NOTIFY_SUBSCRIPTION_SEED,
+    NOTIFY_FEE_VAULT_SEED,
+    VOTING_POWER_SEED,
+    OFFRAMP_SEED,
+    OFFRAMP_ESCROW_SEED,
+    CCTP_INBOX_SEED,
+    X402_RECEIPT_SEED,
+    SERVICE_LISTING_SEED,
+    SOLANA_PAY_RECEIPT_SEED,
+    LP_POOL_SEED,
+    LP_TOKEN_SEED,
+    LP_VAULT_SEED,
+    RESERVE_VAULT_SEED,
+    INSURANCE_VAULT_SEED,
+    DEPOSIT_SCHEDULE_SEED,
+    LP_ALLOWLIST_SEED,
+    LP_LOCK_SEED,
+    LP_LOCK_ESCROW_SEED,
+    LOCKED_DEPOSIT_SEED,
+    LOCKED_DEPOSIT_ESCROW_SEED,
+    SURPLUS_AUCTION_SEED,
+    SURPLUS_BID_ESCROW_SEED,
+    WITHDRAW_REQUEST_SEED,
+    WITHDRAW_REQUEST_ESCROW_SEED,
+    REWARD_CONFIG_SEED,
+    REWARD_VAULT_SEED,
+    REWARD_STAKE_VAULT_SEED,
+    USER_REWARD_SEED,
+    SPONSORSHIP_SEED,
+    SPONSOR_VAULT_SEED,
+    LIQUIDATION_AUCTION_SEED,
+    LEVERAGE_SEED,
+    FLASH_SEED,
+];
+
+const _: () = assert!(all_distinct(&ALL_SEEDS), "duplicate PDA seed");
+
+const fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// True if no two of `seeds` are equal
+pub const fn all_distinct(seeds: &[&[u8]]) -> bool {
+    let mut i = 0;
+    while i < seeds.len() {
+        let mut j = i + 1;
+        while j < seeds.len() {
+            if bytes_eq(seeds[i], seeds[j]) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_distinct() {
+        assert!(all_distinct(&ALL_SEEDS));
+        assert!(!all_distinct(&[POSITION_SEED, SOL_VAULT_SEED, b"position"]));
+        // Prefixes are distinct seeds
+        assert!(all_distinct(&[LP_LOCK_SEED, LP_LOCK_ESCROW_SEED]));
+    }
+}
@@ -1,7 +1,16 @@
 use anchor_lang::prelude::*;
 
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::constants::*;
+use crate::decimals::{asset_decimals, TokenAmount, UsdAmount};
+use crate::errors::LegasiError;
+use crate::gad::LiquidationSplit;
+use crate::oracle::OracleSources;
+use crate::swap_router::SwapRoute;
+use crate::twap::{TwapState, TWAP_WINDOW};
+
 /// Supported asset types
-/// Collaterals: SOL, cbBTC
+/// Collaterals: SOL, cbBTC, mSOL
 /// Borrowables: USDC, EURC
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
 #[repr(u8)]
@@ -12,6 +21,49 @@ pub enum AssetType {
     // Borrowables
     USDC = 2, // USD Coin
     EURC = 3, // Euro Coin
+    // Liquid staking tokens
+    MSOL = 4, // Marinade staked SOL
+}
+
+impl AssetType {
+    /// USD value (6 decimals) of `amount` of a borrowable
+    /// USDC is $1; EURC needs the EUR/USD price (6 decimals)
+    pub fn debt_to_usd(self, amount: u64, eur_usd_price: Option<u64>) -> Result<u64> {
+        match self {
+            AssetType::EURC => {
+                let price = eur_usd_price.ok_or(LegasiError::InvalidOracle)?;
+                Ok(TokenAmount::of(self, amount).to_usd(price)?.0)
+            }
+            _ => Ok(amount),
+        }
+    }
+
+    /// Amount of a borrowable worth `amount_usd` (inverse of `debt_to_usd`)
+    pub fn usd_to_debt(self, amount_usd: u64, eur_usd_price: Option<u64>) -> Result<u64> {
+        match self {
+            AssetType::EURC => {
+                let price = eur_usd_price.ok_or(LegasiError::InvalidOracle)?;
+                Ok(UsdAmount(amount_usd)
+                    .to_tokens(price, asset_decimals(self))?
+                    .amount)
+            }
+            _ => Ok(amount_usd),
+        }
+    }
+}
+
+/// Liquid staking provider used by `deposit_and_stake`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+#[repr(u8)]
+pub enum StakeProvider {
+    None = 0,
+    Marinade = 1,
+}
+
+impl Default for StakeProvider {
+    fn default() -> Self {
+        StakeProvider::None
+    }
 }
 
 /// Protocol global state
@@ -24,9 +76,51 @@ pub struct Protocol {
     pub total_collateral_usd: u64,
     pub total_borrowed_usd: u64,
     pub paused: bool,
+    /// Proposed admin (two-step transfer, e.g., to a Squads vault)
+    pub pending_admin: Pubkey,
+    /// GAD cranker reward bounds, see `gad::cranker_reward_bps`
+    pub cranker_reward_min_bps: u16,
+    pub cranker_reward_max_bps: u16,
+    /// New leverage is blocked while the feed's realized move exceeds this (bps, 0 = disabled)
+    pub max_leverage_volatility_bps: u16,
+    /// Share of interest paid that goes to the borrower's referrer (bps)
+    pub referral_fee_bps: u16,
+    /// Points weights per USD-day of collateral and of debt (bps, 10000 = 1 point)
+    pub points_deposit_weight_bps: u16,
+    pub points_borrow_weight_bps: u16,
+    /// Insurance fund size above which the surplus is auctioned for LP shares
+    /// (see `legasi_lp::surplus_auction`, 0 = disabled)
+    pub insurance_fund_target: u64,
+    /// Split of GAD-liquidated value (unset = cranker curve, rest covers debt)
+    pub liquidation_split: LiquidationSplit,
+    /// Granular pause, `PAUSE_*` bits; `paused` pauses everything they cover
+    pub pause_flags: u8,
+    /// SOL collateral across all position vaults (lamports), see `caps`
+    pub total_sol_collateral: u64,
+    /// Debt written off positions as unrecoverable, ever (USD, 6 decimals). The
+    /// insurance fund covers what it can, the pools' LPs the rest
+    pub bad_debt: u64,
     pub bump: u8,
 }
 
+impl Protocol {
+    /// Fails if the protocol is paused, or `flag` (a `PAUSE_*` bit) is set.
+    /// Entry points that add risk check this; repay, withdraw and GAD never do
+    pub fn require_not_paused(&self, flag: u8) -> Result<()> {
+        require!(
+            !self.paused && self.pause_flags & flag == 0,
+            LegasiError::ProtocolPaused
+        );
+        Ok(())
+    }
+
+    /// Whether new leverage may open against `feed`. Closes and deleveraging never check this
+    pub fn allows_new_leverage(&self, feed: &PriceFeed) -> bool {
+        self.max_leverage_volatility_bps == 0
+            || feed.volatility_bps() <= self.max_leverage_volatility_bps as u64
+    }
+}
+
 /// Collateral asset configuration
 #[account]
 #[derive(InitSpace)]
@@ -40,6 +134,10 @@ pub struct Collateral {
     pub is_active: bool,
     pub total_deposited: u64,
     pub asset_type: AssetType,
+    /// Swap venue GAD uses to sell this collateral
+    pub swap_route: SwapRoute,
+    /// Price move that halts borrows and withdrawals (see `circuit_breaker`)
+    pub circuit_breaker: CircuitBreakerConfig,
     pub bump: u8,
 }
 
@@ -66,9 +164,152 @@ pub struct PriceFeed {
     pub price_usd_6dec: u64,
     pub last_update: i64,
     pub confidence: u64,
+    /// Last `PRICE_HISTORY_LEN` synced prices, a ring buffer (0 = empty slot)
+    pub recent_prices: [u64; PRICE_HISTORY_LEN],
+    /// Next slot of `recent_prices` to overwrite
+    pub history_cursor: u8,
+    /// Max distance of the current price from the median of earlier syncs that GAD
+    /// will act on (basis points, 0 = unbounded), admin-configurable per feed
+    pub max_deviation_bps: u16,
+    /// Oracles blended by `sync_oracle_price` (none = synced from Pyth alone)
+    pub oracle_sources: OracleSources,
+    /// Time-weighted average of the synced prices (see `twap`)
+    pub twap: TwapState,
+    /// Tripped by a sharp move under the asset's `CircuitBreakerConfig`
+    pub circuit_breaker: CircuitBreaker,
+    /// Price of the last Pyth or aggregated oracle sync, bounding admin failover prices
+    pub last_oracle_price: u64,
+    /// Time of the last oracle sync (0 = never synced from an oracle)
+    pub last_oracle_update: i64,
     pub bump: u8,
 }
 
+impl PriceFeed {
+    /// Set the current price and push it into the volatility history
+    pub fn record_price(&mut self, price_usd_6dec: u64, now: i64) {
+        self.price_usd_6dec = price_usd_6dec;
+        self.last_update = now;
+        let cursor = self.history_cursor as usize % PRICE_HISTORY_LEN;
+        self.recent_prices[cursor] = price_usd_6dec;
+        self.history_cursor = ((cursor + 1) % PRICE_HISTORY_LEN) as u8;
+        self.twap.record(price_usd_6dec, now);
+    }
+
+    /// `record_price` from an oracle sync, the reference for admin failover prices
+    pub fn record_oracle_price(&mut self, price_usd_6dec: u64, now: i64) {
+        self.record_price(price_usd_6dec, now);
+        self.last_oracle_price = price_usd_6dec;
+        self.last_oracle_update = now;
+    }
+
+    /// Fails unless the admin may post `price_usd_6dec` manually: only once the oracle
+    /// has been silent for `PRICE_FAILOVER_WINDOW`, and within
+    /// `MAX_FAILOVER_DEVIATION_BPS` of the last oracle price. A feed never synced from
+    /// an oracle has no other source, so it takes any admin price
+    pub fn check_failover_price(&self, price_usd_6dec: u64, now: i64) -> Result<()> {
+        if self.last_oracle_update == 0 {
+            return Ok(());
+        }
+        require!(
+            now.saturating_sub(self.last_oracle_update) > PRICE_FAILOVER_WINDOW,
+            LegasiError::OracleNotStale
+        );
+        let deviation_bps = price_usd_6dec.abs_diff(self.last_oracle_price) as u128
+            * BPS_DENOMINATOR as u128
+            / self.last_oracle_price.max(1) as u128;
+        require!(
+            deviation_bps <= MAX_FAILOVER_DEVIATION_BPS as u128,
+            LegasiError::FailoverPriceOutOfBounds
+        );
+        Ok(())
+    }
+
+    /// Fails with `StalePriceFeed` unless the feed was synced within
+    /// `PRICE_STALENESS_THRESHOLD` of `now`
+    pub fn require_fresh(&self, now: i64) -> Result<()> {
+        require!(
+            now.saturating_sub(self.last_update) <= PRICE_STALENESS_THRESHOLD,
+            LegasiError::StalePriceFeed
+        );
+        Ok(())
+    }
+
+    /// Current price, failing on a stale feed. Every price consumer reads through
+    /// this or `get_checked_twap`
+    pub fn get_checked_price(&self, now: i64) -> Result<u64> {
+        self.require_fresh(now)?;
+        Ok(self.price_usd_6dec)
+    }
+
+    /// Average price over `TWAP_WINDOW` before `now` (the current price without history)
+    pub fn twap_price(&self, now: i64) -> u64 {
+        self.twap
+            .average(now, TWAP_WINDOW)
+            .unwrap_or(self.price_usd_6dec)
+    }
+
+    /// `twap_price`, failing on a stale feed
+    pub fn get_checked_twap(&self, now: i64) -> Result<u64> {
+        self.require_fresh(now)?;
+        Ok(self.twap_price(now))
+    }
+
+    /// This feed priced at its TWAP, for valuing collateral through
+    /// `PriceBook::with_feed` in GAD and liquidations. Fails on a stale feed
+    pub fn at_twap(&self, now: i64) -> Result<PriceFeed> {
+        Ok(PriceFeed {
+            price_usd_6dec: self.get_checked_twap(now)?,
+            ..self.clone()
+        })
+    }
+
+    /// Realized move over the recorded syncs: (max - min) / min, in bps
+    pub fn volatility_bps(&self) -> u64 {
+        let recorded = self.recent_prices.iter().filter(|&&p| p > 0);
+        let (min, max) = recorded.fold((u64::MAX, 0), |(min, max), &p| {
+            (std::cmp::min(min, p), std::cmp::max(max, p))
+        });
+        if max == 0 {
+            return 0;
+        }
+        ((max - min) as u128 * BPS_DENOMINATOR as u128 / min as u128) as u64
+    }
+
+    /// Median of the recorded syncs before the current one, 0 if there are none
+    pub fn reference_price(&self) -> u64 {
+        let latest = (self.history_cursor as usize + PRICE_HISTORY_LEN - 1) % PRICE_HISTORY_LEN;
+        let mut earlier = [0u64; PRICE_HISTORY_LEN];
+        let mut count = 0;
+        for (i, &price) in self.recent_prices.iter().enumerate() {
+            if i != latest && price > 0 {
+                earlier[count] = price;
+                count += 1;
+            }
+        }
+        if count == 0 {
+            return 0;
+        }
+        let earlier = &mut earlier[..count];
+        earlier.sort_unstable();
+        earlier[count / 2]
+    }
+
+    /// Distance of the current price from `reference_price`, in bps (0 without history)
+    pub fn deviation_bps(&self) -> u64 {
+        let reference = self.reference_price();
+        if reference == 0 {
+            return 0;
+        }
+        (self.price_usd_6dec.abs_diff(reference) as u128 * BPS_DENOMINATOR as u128
+            / reference as u128) as u64
+    }
+
+    /// Whether the current price is within the feed's deviation band
+    pub fn within_deviation_band(&self) -> bool {
+        self.max_deviation_bps == 0 || self.deviation_bps() <= self.max_deviation_bps as u64
+    }
+}
+
 /// User lending position (multi-collateral, multi-borrow)
 #[account]
 #[derive(InitSpace)]
@@ -83,6 +324,9 @@ pub struct Position {
     pub gad_enabled: bool,
     pub total_gad_liquidated_usd: u64,
     pub reputation: Reputation,
+    pub stake_provider: StakeProvider,
+    /// GAD liquidation time (seconds) carried to the next crank (see `gad::pace_sale`)
+    pub gad_carried_secs: i64,
     pub bump: u8,
 }
 
@@ -99,6 +343,10 @@ pub struct BorrowedAmount {
     pub asset_type: AssetType,
     pub amount: u64,
     pub accrued_interest: u64,
+    /// Rate (bps APR) owed until `maturity` on a fixed-term loan
+    pub fixed_rate_bps: u16,
+    /// When a fixed-term loan rolls to the variable rate plus penalty (0 = variable)
+    pub maturity: i64,
 }
 
 /// On-chain reputation score
@@ -129,17 +377,60 @@ impl Reputation {
     }
 }
 
-/// LP pool for a borrowable asset
-#[account]
-#[derive(InitSpace)]
-pub struct LpPool {
-    pub borrowable_mint: Pubkey,
-    pub lp_token_mint: Pubkey,
-    pub total_deposits: u64,
-    pub total_shares: u64,
-    pub total_borrowed: u64,
-    pub interest_earned: u64,
-    pub bump: u8,
+/// Per-pool outflow rate limit (bps of TVL per slot window)
+/// Bounds how much liquidity an oracle exploit or bug can drain before governance reacts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
+pub struct OutflowLimiter {
+    /// Max outflow per window in bps of TVL (0 = disabled)
+    pub max_outflow_bps: u16,
+    /// Window length in slots
+    pub window_slots: u64,
+    /// Slot the current window started at
+    pub window_start_slot: u64,
+    /// TVL snapshot at window start
+    pub window_tvl: u64,
+    /// Amount already sent out in the current window
+    pub window_outflow: u64,
+}
+
+impl OutflowLimiter {
+    pub fn new(max_outflow_bps: u16, window_slots: u64) -> Self {
+        Self {
+            max_outflow_bps,
+            window_slots,
+            ..Default::default()
+        }
+    }
+
+    /// Record an outflow, rolling the window if expired. Fails if the window cap is exceeded.
+    pub fn record_outflow(&mut self, amount: u64, tvl: u64, current_slot: u64) -> Result<()> {
+        if self.max_outflow_bps == 0 {
+            return Ok(());
+        }
+
+        if current_slot.saturating_sub(self.window_start_slot) >= self.window_slots {
+            self.window_start_slot = current_slot;
+            self.window_tvl = tvl;
+            self.window_outflow = 0;
+        }
+
+        // Deposits during the window raise the cap, withdrawals never lower it
+        self.window_tvl = std::cmp::max(self.window_tvl, tvl);
+        let cap = (self.window_tvl as u128)
+            .checked_mul(self.max_outflow_bps as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(LegasiError::MathOverflow)? as u64;
+
+        let new_outflow = self
+            .window_outflow
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
+        require!(new_outflow <= cap, LegasiError::OutflowLimitExceeded);
+
+        self.window_outflow = new_outflow;
+        Ok(())
+    }
 }
 
 /// Agent-specific position for x402 and autonomous operations
@@ -191,3 +482,239 @@ impl AgentConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outflow_limit_per_window() {
+        let mut limiter = OutflowLimiter::new(2000, 100); // 20% per 100 slots
+
+        // 20% of 1000 TVL = 200 per window
+        assert!(limiter.record_outflow(150, 1000, 10).is_ok());
+        assert!(limiter.record_outflow(50, 850, 20).is_ok());
+        assert!(limiter.record_outflow(1, 800, 30).is_err());
+
+        // New window resets the budget
+        assert!(limiter.record_outflow(160, 800, 110).is_ok());
+    }
+
+    #[test]
+    fn test_outflow_limit_disabled() {
+        let mut limiter = OutflowLimiter::new(0, 100);
+        assert!(limiter.record_outflow(u64::MAX, 0, 0).is_ok());
+    }
+
+    // Layout pins: flash, lending, GAD, and off-chain clients read these accounts.
+    // If one of these fails, every reader of the account needs a migration.
+
+    #[test]
+    fn test_eurc_debt_conversion() {
+        // $1.08 / EUR
+        let eur = Some(1_080_000);
+        assert_eq!(
+            AssetType::EURC.debt_to_usd(100_000_000, eur).unwrap(),
+            108_000_000
+        );
+        assert_eq!(
+            AssetType::EURC.usd_to_debt(108_000_000, eur).unwrap(),
+            100_000_000
+        );
+        assert_eq!(
+            AssetType::USDC.debt_to_usd(100_000_000, None).unwrap(),
+            100_000_000
+        );
+        assert!(AssetType::EURC.debt_to_usd(100_000_000, None).is_err());
+    }
+
+    #[test]
+    fn test_protocol_layout() {
+        assert_eq!(
+            Protocol::INIT_SPACE,
+            32 + 32 + 8 + 8 + 8 + 1 + 32 + 2 + 2 + 2 + 2 + 2 + 2 + 1
+        );
+    }
+
+    fn feed() -> PriceFeed {
+        PriceFeed {
+            asset_type: AssetType::SOL,
+            price_usd_6dec: 0,
+            last_update: 0,
+            confidence: 0,
+            recent_prices: [0; PRICE_HISTORY_LEN],
+            history_cursor: 0,
+            max_deviation_bps: 0,
+            oracle_sources: OracleSources::default(),
+            twap: TwapState::default(),
+            circuit_breaker: CircuitBreaker::default(),
+            last_oracle_price: 0,
+            last_oracle_update: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_volatility_over_recent_syncs() {
+        let mut feed = feed();
+        assert_eq!(feed.volatility_bps(), 0);
+
+        feed.record_price(100_000_000, 1);
+        assert_eq!(feed.volatility_bps(), 0);
+        feed.record_price(80_000_000, 2);
+        // $80 -> $100 is a 25% move
+        assert_eq!(feed.volatility_bps(), 2500);
+
+        // Once the window rolls past both, calm prices clear the estimate
+        for now in 3..3 + PRICE_HISTORY_LEN as i64 {
+            feed.record_price(90_000_000, now);
+        }
+        assert_eq!(feed.volatility_bps(), 0);
+        assert_eq!(feed.price_usd_6dec, 90_000_000);
+    }
+
+    #[test]
+    fn test_volatility_gates_new_leverage() {
+        let mut feed = feed();
+        feed.record_price(100_000_000, 1);
+        feed.record_price(80_000_000, 2);
+
+        let mut protocol = Protocol {
+            admin: Pubkey::default(),
+            treasury: Pubkey::default(),
+            insurance_fund: 0,
+            total_collateral_usd: 0,
+            total_borrowed_usd: 0,
+            paused: false,
+            pending_admin: Pubkey::default(),
+            cranker_reward_min_bps: 0,
+            cranker_reward_max_bps: 0,
+            max_leverage_volatility_bps: 1500,
+            referral_fee_bps: 0,
+            points_deposit_weight_bps: 0,
+            points_borrow_weight_bps: 0,
+            insurance_fund_target: 0,
+            liquidation_split: LiquidationSplit::default(),
+            pause_flags: 0,
+            total_sol_collateral: 0,
+            bad_debt: 0,
+            bump: 0,
+        };
+        assert!(!protocol.allows_new_leverage(&feed));
+        protocol.max_leverage_volatility_bps = 3000;
+        assert!(protocol.allows_new_leverage(&feed));
+        protocol.max_leverage_volatility_bps = 0;
+        assert!(protocol.allows_new_leverage(&feed));
+    }
+
+    #[test]
+    fn test_deviation_band_against_earlier_syncs() {
+        let mut feed = feed();
+        feed.record_price(100_000_000, 1);
+        // Nothing to compare the first sync against
+        assert_eq!(feed.deviation_bps(), 0);
+
+        for now in 2..5 {
+            feed.record_price(100_000_000, now);
+        }
+        feed.record_price(101_000_000, 5);
+        feed.max_deviation_bps = 1000;
+        assert_eq!(feed.reference_price(), 100_000_000);
+        assert_eq!(feed.deviation_bps(), 100);
+        assert!(feed.within_deviation_band());
+
+        // A flash-crash print is out of band
+        feed.record_price(60_000_000, 6);
+        assert_eq!(feed.deviation_bps(), 4000);
+        assert!(!feed.within_deviation_band());
+
+        // It reverts: the outlier alone doesn't move the median
+        feed.record_price(100_000_000, 7);
+        assert_eq!(feed.reference_price(), 100_000_000);
+        assert!(feed.within_deviation_band());
+
+        // Unbounded feeds accept any print
+        feed.max_deviation_bps = 0;
+        feed.record_price(10_000_000, 8);
+        assert!(feed.within_deviation_band());
+    }
+
+    #[test]
+    fn test_twap_price() {
+        let mut feed = feed();
+        feed.record_price(100_000_000, 1_000);
+        assert_eq!(feed.twap_price(1_000), 100_000_000);
+
+        // A flash-crash print synced this second doesn't move the TWAP
+        feed.record_price(60_000_000, 2_800);
+        assert_eq!(feed.price_usd_6dec, 60_000_000);
+        assert_eq!(feed.twap_price(2_800), 100_000_000);
+        let at_twap = feed.at_twap(2_800).unwrap();
+        assert_eq!(at_twap.price_usd_6dec, 100_000_000);
+        assert_eq!(at_twap.asset_type, AssetType::SOL);
+    }
+
+    #[test]
+    fn test_checked_price_rejects_stale_feed() {
+        let mut feed = feed();
+        feed.record_price(100_000_000, 1_000);
+        assert_eq!(feed.get_checked_price(1_000).unwrap(), 100_000_000);
+        assert_eq!(
+            feed.get_checked_price(1_000 + PRICE_STALENESS_THRESHOLD)
+                .unwrap(),
+            100_000_000
+        );
+        assert!(feed
+            .get_checked_price(1_000 + PRICE_STALENESS_THRESHOLD + 1)
+            .is_err());
+        assert!(feed
+            .get_checked_twap(1_000 + PRICE_STALENESS_THRESHOLD + 1)
+            .is_err());
+        assert!(feed.at_twap(1_000 + PRICE_STALENESS_THRESHOLD + 1).is_err());
+    }
+
+    #[test]
+    fn test_failover_price_needs_stale_oracle_and_stays_bounded() {
+        let mut feed = feed();
+        // Bootstrapping: no oracle yet, any admin price
+        feed.check_failover_price(1, 1_000).unwrap();
+
+        feed.record_oracle_price(100_000_000, 1_000);
+        assert!(feed
+            .check_failover_price(100_000_000, 1_000 + PRICE_FAILOVER_WINDOW)
+            .is_err());
+        let now = 1_000 + PRICE_FAILOVER_WINDOW + 1;
+        feed.check_failover_price(90_000_000, now).unwrap();
+        feed.check_failover_price(110_000_000, now).unwrap();
+        assert!(feed.check_failover_price(89_999_999, now).is_err());
+
+        // Successive failover prices can't walk away from the oracle
+        feed.record_price(90_000_000, now);
+        assert!(feed.check_failover_price(81_000_000, now + 1).is_err());
+    }
+
+    #[test]
+    fn test_borrowable_layout() {
+        assert_eq!(Borrowable::INIT_SPACE, 32 + 32 + 2 + 1 + 1 + 8 + 8 + 1 + 1);
+
+        let borrowable = Borrowable {
+            mint: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+            interest_rate_bps: 500,
+            decimals: 6,
+            is_active: true,
+            total_borrowed: u64::MAX,
+            total_available: u64::MAX,
+            asset_type: AssetType::EURC,
+            bump: 255,
+        };
+        let mut data = Vec::new();
+        borrowable.try_serialize(&mut data).unwrap();
+
+        // asset_type sits after the totals (discriminator + 84 bytes)
+        assert_eq!(data[8 + 84], AssetType::EURC as u8);
+        let decoded = Borrowable::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(decoded.asset_type, AssetType::EURC);
+        assert_eq!(decoded.mint, borrowable.mint);
+    }
+}
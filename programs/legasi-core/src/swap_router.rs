@@ -0,0 +1,60 @@
+//! Per-asset swap routing
+//!
+//! Each collateral config carries a `SwapRoute` preference used when GAD
+//! sells that collateral. Generic assets go through Jupiter; LSTs can be
+//! routed through Sanctum to cut slippage on large deleverages.
+
+use anchor_lang::prelude::*;
+
+use crate::{jupiter_cpi, sanctum_cpi};
+
+/// Swap venue used to sell a collateral asset
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+#[repr(u8)]
+pub enum SwapRoute {
+    Jupiter = 0,
+    Sanctum = 1,
+}
+
+impl Default for SwapRoute {
+    fn default() -> Self {
+        SwapRoute::Jupiter
+    }
+}
+
+impl SwapRoute {
+    /// Program the route data must target
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            SwapRoute::Jupiter => jupiter_cpi::ID,
+            SwapRoute::Sanctum => sanctum_cpi::ID,
+        }
+    }
+
+    /// Execute the swap through this route's program
+    pub fn swap<'info>(
+        &self,
+        swap_program: &AccountInfo<'info>,
+        route_accounts: &[AccountInfo<'info>],
+        route_data: Vec<u8>,
+        pda_signer: Option<&Pubkey>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        match self {
+            SwapRoute::Jupiter => jupiter_cpi::swap(
+                swap_program,
+                route_accounts,
+                route_data,
+                pda_signer,
+                signer_seeds,
+            ),
+            SwapRoute::Sanctum => sanctum_cpi::swap(
+                swap_program,
+                route_accounts,
+                route_data,
+                pda_signer,
+                signer_seeds,
+            ),
+        }
+    }
+}
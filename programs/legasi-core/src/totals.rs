@@ -0,0 +1,217 @@
+//! # Protocol Totals
+//!
+//! `Protocol.total_collateral_usd` / `total_borrowed_usd` are owned by core, but
+//! the flows that move them live in other programs. Those programs report
+//! signed USD deltas through `update_protocol_totals`, signing with their
+//! `[PROTOCOL_WRITER_SEED]` PDA so only Legasi programs can move the totals.
+//!
+//! Deltas are valued at the price in effect when the flow happens, so the
+//! totals are book values, not a live mark-to-market.
+
+use anchor_lang::prelude::*;
+use std::str::FromStr;
+
+use crate::constants::{
+    FLASH_PROGRAM_ID, GAD_PROGRAM_ID, LENDING_PROGRAM_ID, LEVERAGE_PROGRAM_ID, LP_PROGRAM_ID,
+};
+use crate::seeds::PROTOCOL_WRITER_SEED;
+
+/// Programs allowed to move protocol totals
+pub const PROTOCOL_WRITER_PROGRAMS: [&str; 5] = [
+    LENDING_PROGRAM_ID,
+    GAD_PROGRAM_ID,
+    LEVERAGE_PROGRAM_ID,
+    LP_PROGRAM_ID,
+    FLASH_PROGRAM_ID,
+];
+
+/// True if `writer` is the protocol writer PDA of an allowed program
+pub fn is_protocol_writer(writer: &Pubkey) -> bool {
+    PROTOCOL_WRITER_PROGRAMS.iter().any(|program| {
+        let program = Pubkey::from_str(program).unwrap();
+        Pubkey::find_program_address(&[PROTOCOL_WRITER_SEED], &program).0 == *writer
+    })
+}
+
+/// Apply a signed delta to a total, saturating at zero and `u64::MAX`
+pub fn apply_delta(total: u64, delta: i64) -> u64 {
+    if delta >= 0 {
+        total.saturating_add(delta as u64)
+    } else {
+        total.saturating_sub(delta.unsigned_abs())
+    }
+}
+
+/// Clamp a USD amount into a positive delta
+pub fn usd_delta(amount_usd: u64) -> i64 {
+    i64::try_from(amount_usd).unwrap_or(i64::MAX)
+}
+
+/// CPI into `update_protocol_totals`, signed by the caller's protocol writer PDA
+pub fn report<'info>(
+    core_program: &AccountInfo<'info>,
+    protocol: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    collateral_delta_usd: i64,
+    borrowed_delta_usd: i64,
+) -> Result<()> {
+    if collateral_delta_usd == 0 && borrowed_delta_usd == 0 {
+        return Ok(());
+    }
+    crate::cpi::update_protocol_totals(
+        CpiContext::new_with_signer(
+            core_program.clone(),
+            crate::cpi::accounts::UpdateProtocolTotals {
+                protocol: protocol.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        collateral_delta_usd,
+        borrowed_delta_usd,
+    )
+}
+
+/// CPI into `record_sol_collateral`, signed by the caller's protocol writer PDA
+pub fn report_sol_collateral<'info>(
+    core_program: &AccountInfo<'info>,
+    protocol: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    delta_lamports: i64,
+) -> Result<()> {
+    if delta_lamports == 0 {
+        return Ok(());
+    }
+    crate::cpi::record_sol_collateral(
+        CpiContext::new_with_signer(
+            core_program.clone(),
+            crate::cpi::accounts::UpdateProtocolTotals {
+                protocol: protocol.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        delta_lamports,
+    )
+}
+
+/// CPI into `record_insurance_fee`, signed by the caller's protocol writer PDA
+pub fn report_insurance_fee<'info>(
+    core_program: &AccountInfo<'info>,
+    protocol: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    crate::cpi::record_insurance_fee(
+        CpiContext::new_with_signer(
+            core_program.clone(),
+            crate::cpi::accounts::UpdateProtocolTotals {
+                protocol: protocol.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        amount,
+    )
+}
+
+/// CPI into `record_insurance_payout`, signed by the caller's protocol writer PDA
+pub fn report_insurance_payout<'info>(
+    core_program: &AccountInfo<'info>,
+    protocol: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    crate::cpi::record_insurance_payout(
+        CpiContext::new_with_signer(
+            core_program.clone(),
+            crate::cpi::accounts::UpdateProtocolTotals {
+                protocol: protocol.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        amount,
+    )
+}
+
+/// CPI into `record_bad_debt`, signed by the caller's protocol writer PDA
+pub fn report_bad_debt<'info>(
+    core_program: &AccountInfo<'info>,
+    protocol: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    amount_usd: u64,
+) -> Result<()> {
+    if amount_usd == 0 {
+        return Ok(());
+    }
+    crate::cpi::record_bad_debt(
+        CpiContext::new_with_signer(
+            core_program.clone(),
+            crate::cpi::accounts::UpdateProtocolTotals {
+                protocol: protocol.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        amount_usd,
+    )
+}
+
+/// CPI into `record_borrow_rate`, signed by the caller's protocol writer PDA
+pub fn report_borrow_rate<'info>(
+    core_program: &AccountInfo<'info>,
+    borrowable: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    borrow_rate_bps: u16,
+) -> Result<()> {
+    crate::cpi::record_borrow_rate(
+        CpiContext::new_with_signer(
+            core_program.clone(),
+            crate::cpi::accounts::RecordBorrowRate {
+                borrowable: borrowable.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        borrow_rate_bps,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_delta() {
+        assert_eq!(apply_delta(100, 50), 150);
+        assert_eq!(apply_delta(100, -50), 50);
+        // Saturates instead of wrapping
+        assert_eq!(apply_delta(100, -500), 0);
+        assert_eq!(apply_delta(u64::MAX, 1), u64::MAX);
+        assert_eq!(apply_delta(0, i64::MIN), 0);
+    }
+
+    #[test]
+    fn test_protocol_writer() {
+        let lending = Pubkey::from_str(LENDING_PROGRAM_ID).unwrap();
+        let (writer, _) = Pubkey::find_program_address(&[PROTOCOL_WRITER_SEED], &lending);
+        assert!(is_protocol_writer(&writer));
+
+        // Same seed under another program is not a writer
+        let (other, _) = Pubkey::find_program_address(&[PROTOCOL_WRITER_SEED], &crate::ID);
+        assert!(!is_protocol_writer(&other));
+    }
+}
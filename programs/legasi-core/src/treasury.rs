@@ -0,0 +1,80 @@
+//! Treasury reserves
+//!
+//! The protocol treasury can be the core program's `[TREASURY_SEED]` PDA: the admin
+//! points `Protocol.treasury` at it with `AdminOp::SetTreasury`, and fee income then
+//! lands under program control. SOL from GAD liquidations and the lamport sweep sits
+//! on the PDA itself; SPL fees (EURC, LSTs) go to the `[TREASURY_VAULT_SEED, mint]`
+//! token accounts it owns.
+//!
+//! `convert_treasury_to_usdc` is a permissionless crank that sells a non-USDC
+//! holding through Jupiter into the USDC vault, so reserves are kept in a stable
+//! unit. The route is built off-chain by whoever cranks, so the program bounds it:
+//! the USDC received must be within `TREASURY_MAX_SLIPPAGE_BPS` of the oracle value
+//! of what was sold.
+//!
+//! Flow:
+//! 1. Admin sets the treasury to `pda::treasury()` and calls init_treasury_vault for
+//!    USDC and every fee mint (wSOL for GAD's SOL)
+//! 2. Anyone calls convert_treasury_to_usdc with a Jupiter route for a holding
+
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::decimals::token_to_usd;
+use crate::errors::LegasiError;
+
+/// Oracle value in USDC (6 decimals) of `amount` of a token with `decimals`,
+/// priced at `price_usd_6dec`. USDC is taken at $1
+pub fn oracle_value_usdc(amount: u64, price_usd_6dec: u64, decimals: u8) -> Result<u64> {
+    token_to_usd(amount, decimals, price_usd_6dec)
+}
+
+/// Least USDC a conversion selling `amount_in` may return: its oracle value less
+/// `max_slippage_bps`
+pub fn min_usdc_out(
+    amount_in: u64,
+    price_usd_6dec: u64,
+    decimals: u8,
+    max_slippage_bps: u16,
+) -> Result<u64> {
+    let value = oracle_value_usdc(amount_in, price_usd_6dec, decimals)?;
+    let kept_bps = BPS_DENOMINATOR.saturating_sub(max_slippage_bps as u64);
+    Ok((value as u128 * kept_bps as u128 / BPS_DENOMINATOR as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oracle_value() {
+        // 2 SOL at $100
+        assert_eq!(
+            oracle_value_usdc(2_000_000_000, 100_000_000, 9).unwrap(),
+            200_000_000
+        );
+        // 500 EURC at $1.08
+        assert_eq!(
+            oracle_value_usdc(500_000_000, 1_080_000, 6).unwrap(),
+            540_000_000
+        );
+        assert!(oracle_value_usdc(u64::MAX, u64::MAX, 0).is_err());
+    }
+
+    #[test]
+    fn test_min_usdc_out() {
+        // 1% off $200 of SOL
+        assert_eq!(
+            min_usdc_out(2_000_000_000, 100_000_000, 9, 100).unwrap(),
+            198_000_000
+        );
+        assert_eq!(
+            min_usdc_out(2_000_000_000, 100_000_000, 9, 0).unwrap(),
+            200_000_000
+        );
+        assert_eq!(
+            min_usdc_out(2_000_000_000, 100_000_000, 9, 10_000).unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Time-weighted average price
+//!
+//! GAD cranks, liquidation auctions and liquidations value collateral and debt at a
+//! feed's TWAP over `TWAP_WINDOW` instead of its latest print, so a single
+//! manipulated print can't trigger mass deleveraging: a price only weighs in for as
+//! long as it stands, and a print synced this second weighs nothing yet.
+//!
+//! Each `PriceFeed` keeps a running sum of price × seconds (`TwapState`), advanced on
+//! every sync, and snapshots of that sum taken at most every
+//! `TWAP_OBSERVATION_INTERVAL`. The average since a snapshot is the growth of the sum
+//! over the time elapsed. Snapshots are rate limited, so syncing in a burst can't
+//! flush older prices out of the window.
+
+use anchor_lang::prelude::*;
+
+/// Period GAD and liquidations average prices over (30 minutes)
+pub const TWAP_WINDOW: i64 = 30 * 60;
+
+/// Min spacing of TWAP snapshots (5 minutes)
+pub const TWAP_OBSERVATION_INTERVAL: i64 = 5 * 60;
+
+/// Snapshots kept per feed, spanning at least `TWAP_WINDOW`
+pub const TWAP_OBSERVATIONS: usize = 8;
+
+/// Snapshot of a feed's price × seconds sum
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct TwapObservation {
+    /// Unix time of the snapshot (0 = empty slot)
+    pub timestamp: i64,
+    pub cumulative: u128,
+}
+
+/// Running price × seconds sum of a `PriceFeed`
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct TwapState {
+    /// Sum of price × seconds up to `last_update`
+    pub cumulative: u128,
+    /// Price in force since `last_update`
+    pub last_price: u64,
+    pub last_update: i64,
+    /// Ring buffer of snapshots of `cumulative`
+    pub observations: [TwapObservation; TWAP_OBSERVATIONS],
+    /// Next slot of `observations` to overwrite
+    pub cursor: u8,
+}
+
+impl TwapState {
+    /// Advance the sum to `now` at the price in force, then switch to `price`
+    pub fn record(&mut self, price: u64, now: i64) {
+        self.cumulative = self.cumulative_at(now);
+        self.last_price = price;
+        self.last_update = now;
+
+        let cursor = self.cursor as usize % TWAP_OBSERVATIONS;
+        let latest = self.observations[(cursor + TWAP_OBSERVATIONS - 1) % TWAP_OBSERVATIONS];
+        if latest.timestamp == 0
+            || now.saturating_sub(latest.timestamp) >= TWAP_OBSERVATION_INTERVAL
+        {
+            self.observations[cursor] = TwapObservation {
+                timestamp: now,
+                cumulative: self.cumulative,
+            };
+            self.cursor = ((cursor + 1) % TWAP_OBSERVATIONS) as u8;
+        }
+    }
+
+    /// Sum of price × seconds up to `now`
+    pub fn cumulative_at(&self, now: i64) -> u128 {
+        let elapsed = now.saturating_sub(self.last_update).max(0) as u128;
+        self.cumulative
+            .saturating_add(self.last_price as u128 * elapsed)
+    }
+
+    /// Average price since the newest snapshot at least `window` seconds before `now`,
+    /// or since the oldest one while the history is shorter. None without a snapshot
+    /// before `now`
+    pub fn average(&self, now: i64, window: i64) -> Option<u64> {
+        let start = now.saturating_sub(window);
+        let recorded = self
+            .observations
+            .iter()
+            .filter(|o| o.timestamp > 0 && o.timestamp < now);
+        let base = recorded
+            .clone()
+            .filter(|o| o.timestamp <= start)
+            .max_by_key(|o| o.timestamp)
+            .or_else(|| recorded.min_by_key(|o| o.timestamp))?;
+        let elapsed = (now - base.timestamp) as u128;
+        let average = self.cumulative_at(now).saturating_sub(base.cumulative) / elapsed;
+        Some(u64::try_from(average).unwrap_or(u64::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_weighs_prices_by_time() {
+        let mut twap = TwapState::default();
+        assert_eq!(twap.average(1_000, TWAP_WINDOW), None);
+
+        twap.record(100_000_000, 1_000);
+        // Nothing has stood yet
+        assert_eq!(twap.average(1_000, TWAP_WINDOW), None);
+        assert_eq!(twap.average(1_600, TWAP_WINDOW), Some(100_000_000));
+
+        // A crash print synced now weighs nothing until time passes
+        twap.record(50_000_000, 2_800);
+        assert_eq!(twap.average(2_800, TWAP_WINDOW), Some(100_000_000));
+        // 30 min at $100 then 5 min at $50
+        assert_eq!(twap.average(3_100, TWAP_WINDOW), Some(92_857_142));
+    }
+
+    #[test]
+    fn test_burst_of_syncs_keeps_the_window() {
+        let mut twap = TwapState::default();
+        for i in 0..8 {
+            twap.record(100_000_000, 1_000 + i * TWAP_OBSERVATION_INTERVAL);
+        }
+        let now = 1_000 + 8 * TWAP_OBSERVATION_INTERVAL;
+        // Many syncs of a manipulated price in the last minute
+        for i in 0..20 {
+            twap.record(10_000_000, now - 60 + i);
+        }
+        let average = twap.average(now, TWAP_WINDOW).unwrap();
+        assert!(average > 95_000_000, "{average}");
+    }
+}
@@ -0,0 +1,327 @@
+//! # Collateral Valuation
+//!
+//! Registry of how each collateral `AssetType` is priced, shared by lending, GAD and
+//! the SDK, so a new collateral type needs an entry in `pricing_method` (and its
+//! price or rate in the `PriceBook` the caller builds) instead of edits to every
+//! borrow, withdraw and crank path.
+//!
+//! 1. `DirectFeed` - amount × the asset's own feed price
+//! 2. `LstExchangeRate` - amount × SOL per LST × the SOL feed. Without a rate in the
+//!    book an LST counts 1:1 with SOL, a conservative floor since LSTs only gain on SOL.
+//!    `PriceBook::with_lst_yield` credits the staking yield accrued above 1:1, less
+//!    `LST_YIELD_HAIRCUT_BPS`, to holders who haven't realized it
+//! 3. `LpShareFairValue` - amount × the pool's underlying per share, valued like debt
+//!    in the underlying (USDC at $1, EURC at the EUR/USD price)
+//!
+//! A collateral whose price or rate is missing from the book counts as zero, except in
+//! `value_collaterals`, which prices each deposit from its own `PriceFeed` and fails
+//! when one is missing, for callers that must not undervalue a position.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::{BPS_DENOMINATOR, CBBTC_DECIMALS, LST_YIELD_HAIRCUT_BPS, SOL_DECIMALS};
+use crate::decimals::{sol_to_usd, token_to_usd};
+use crate::errors::LegasiError;
+use crate::state::{AssetType, PriceFeed};
+
+/// Fixed-point scale of exchange rates (underlying units per token unit)
+pub const RATE_PRECISION: u64 = 1_000_000_000;
+
+/// How a collateral asset is priced
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PricingMethod {
+    /// The asset's own feed, USD per whole token
+    DirectFeed { decimals: u8 },
+    /// Liquid staking token priced through its SOL exchange rate and the SOL feed
+    LstExchangeRate,
+    /// LP share priced at the pool's underlying per share
+    LpShareFairValue { underlying: AssetType },
+    /// Not accepted as collateral
+    NotCollateral,
+}
+
+/// Pricing method of each asset type
+pub fn pricing_method(asset_type: AssetType) -> PricingMethod {
+    match asset_type {
+        AssetType::SOL => PricingMethod::DirectFeed {
+            decimals: SOL_DECIMALS,
+        },
+        AssetType::CbBTC => PricingMethod::DirectFeed {
+            decimals: CBBTC_DECIMALS,
+        },
+        AssetType::MSOL => PricingMethod::LstExchangeRate,
+        AssetType::USDC | AssetType::EURC => PricingMethod::NotCollateral,
+    }
+}
+
+/// Prices and exchange rates a valuation may read
+#[derive(Clone, Debug, Default)]
+pub struct PriceBook {
+    /// SOL/USD (6 decimals)
+    pub sol_price: u64,
+    /// EUR/USD (6 decimals), for EURC-denominated values
+    pub eur_usd_price: Option<u64>,
+    /// Feed prices of other assets, USD per whole token (6 decimals)
+    pub prices: Vec<(AssetType, u64)>,
+    /// LST and LP share exchange rates (`RATE_PRECISION`)
+    pub rates: Vec<(AssetType, u64)>,
+}
+
+impl PriceBook {
+    /// Book with the SOL price only
+    pub fn new(sol_price: u64, eur_usd_price: Option<u64>) -> Self {
+        Self {
+            sol_price,
+            eur_usd_price,
+            ..Default::default()
+        }
+    }
+
+    /// Add an asset's feed price (USD per whole token, 6 decimals)
+    pub fn with_price(mut self, asset_type: AssetType, price_usd_6dec: u64) -> Self {
+        self.prices.push((asset_type, price_usd_6dec));
+        self
+    }
+
+    /// Add the price of `feed`'s asset; a SOL or EURC feed sets `sol_price` /
+    /// `eur_usd_price`
+    pub fn with_feed(mut self, feed: &PriceFeed) -> Self {
+        match feed.asset_type {
+            AssetType::SOL => self.sol_price = feed.price_usd_6dec,
+            AssetType::EURC => self.eur_usd_price = Some(feed.price_usd_6dec),
+            asset_type => return self.with_price(asset_type, feed.price_usd_6dec),
+        }
+        self
+    }
+
+    /// Add an LST or LP share exchange rate (`RATE_PRECISION`)
+    pub fn with_rate(mut self, asset_type: AssetType, rate: u64) -> Self {
+        self.rates.push((asset_type, rate));
+        self
+    }
+
+    /// Add an LST's exchange rate with its yield over 1:1 discounted by
+    /// `LST_YIELD_HAIRCUT_BPS` (see `yield_credit_rate`)
+    pub fn with_lst_yield(self, asset_type: AssetType, rate: u64) -> Self {
+        self.with_rate(asset_type, yield_credit_rate(rate, LST_YIELD_HAIRCUT_BPS))
+    }
+
+    /// Feed price of `asset_type`, if the book has it
+    pub fn price(&self, asset_type: AssetType) -> Option<u64> {
+        if asset_type == AssetType::SOL {
+            return Some(self.sol_price);
+        }
+        self.prices
+            .iter()
+            .find(|(asset, _)| *asset == asset_type)
+            .map(|(_, price)| *price)
+    }
+
+    /// Exchange rate of `asset_type`, if the book has it
+    pub fn rate(&self, asset_type: AssetType) -> Option<u64> {
+        self.rates
+            .iter()
+            .find(|(asset, _)| *asset == asset_type)
+            .map(|(_, rate)| *rate)
+    }
+}
+
+/// Exchange rate (`RATE_PRECISION`) crediting an LST's accrued yield, its gain over
+/// 1:1, less `haircut_bps`. A rate below 1:1 is floored at 1:1, as without a rate
+pub fn yield_credit_rate(rate: u64, haircut_bps: u16) -> u64 {
+    let accrued = rate.saturating_sub(RATE_PRECISION);
+    let kept_bps = BPS_DENOMINATOR.saturating_sub(haircut_bps as u64);
+    RATE_PRECISION + (accrued as u128 * kept_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/// `amount` converted at an exchange rate
+fn apply_rate(amount: u64, rate: u64) -> Result<u64> {
+    Ok((amount as u128)
+        .checked_mul(rate as u128)
+        .ok_or(LegasiError::MathOverflow)?
+        .checked_div(RATE_PRECISION as u128)
+        .ok_or(LegasiError::MathOverflow)? as u64)
+}
+
+/// USD value (6 decimals) of `amount` of collateral `asset_type`
+pub fn collateral_value_usd(asset_type: AssetType, amount: u64, book: &PriceBook) -> Result<u64> {
+    match pricing_method(asset_type) {
+        PricingMethod::DirectFeed { decimals } => match book.price(asset_type) {
+            Some(price) => token_to_usd(amount, decimals, price),
+            None => Ok(0),
+        },
+        PricingMethod::LstExchangeRate => {
+            let lamports = apply_rate(amount, book.rate(asset_type).unwrap_or(RATE_PRECISION))?;
+            sol_to_usd(lamports, book.sol_price)
+        }
+        PricingMethod::LpShareFairValue { underlying } => match book.rate(asset_type) {
+            Some(rate) => underlying.debt_to_usd(apply_rate(amount, rate)?, book.eur_usd_price),
+            None => Ok(0),
+        },
+        PricingMethod::NotCollateral => Ok(0),
+    }
+}
+
+/// USD value (6 decimals) of a position's `(asset_type, amount)` deposits
+pub fn total_collateral_value_usd(
+    deposits: impl IntoIterator<Item = (AssetType, u64)>,
+    book: &PriceBook,
+) -> Result<u64> {
+    let mut total: u64 = 0;
+    for (asset_type, amount) in deposits {
+        total = total
+            .checked_add(collateral_value_usd(asset_type, amount, book)?)
+            .ok_or(LegasiError::MathOverflow)?;
+    }
+    Ok(total)
+}
+
+/// USD value (6 decimals) of `(asset_type, amount)` deposits, each feed-priced asset
+/// at its own price among `feeds` and its decimals, LSTs and LP shares through the
+/// rates in `book`. Fails with `InvalidOracle` when a feed-priced deposit's feed is
+/// missing, so leaving a feed out can't undervalue a position
+pub fn value_collaterals<'a>(
+    deposits: impl IntoIterator<Item = (AssetType, u64)>,
+    feeds: impl IntoIterator<Item = &'a PriceFeed>,
+    book: PriceBook,
+) -> Result<u64> {
+    let book = feeds.into_iter().fold(book, PriceBook::with_feed);
+    let mut total: u64 = 0;
+    for (asset_type, amount) in deposits {
+        if amount > 0 {
+            let priced = match pricing_method(asset_type) {
+                PricingMethod::DirectFeed { .. } if asset_type != AssetType::SOL => {
+                    book.price(asset_type).is_some()
+                }
+                PricingMethod::DirectFeed { .. } | PricingMethod::LstExchangeRate => {
+                    book.sol_price > 0
+                }
+                _ => true,
+            };
+            require!(priced, LegasiError::InvalidOracle);
+        }
+        total = total
+            .checked_add(collateral_value_usd(asset_type, amount, &book)?)
+            .ok_or(LegasiError::MathOverflow)?;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{LAMPORTS_PER_SOL, PRICE_HISTORY_LEN};
+
+    fn feed(asset_type: AssetType, price_usd_6dec: u64) -> PriceFeed {
+        PriceFeed {
+            asset_type,
+            price_usd_6dec,
+            last_update: 0,
+            confidence: 0,
+            recent_prices: [0; PRICE_HISTORY_LEN],
+            history_cursor: 0,
+            max_deviation_bps: 0,
+            oracle_sources: Default::default(),
+            twap: Default::default(),
+            circuit_breaker: Default::default(),
+            last_oracle_price: 0,
+            last_oracle_update: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_direct_feed() {
+        let book = PriceBook::new(100_000_000, None).with_price(AssetType::CbBTC, 60_000_000_000);
+        assert_eq!(
+            collateral_value_usd(AssetType::SOL, 2 * LAMPORTS_PER_SOL, &book).unwrap(),
+            200_000_000
+        );
+        // 0.5 cbBTC at $60k
+        assert_eq!(
+            collateral_value_usd(AssetType::CbBTC, 50_000_000, &book).unwrap(),
+            30_000_000_000
+        );
+        // No cbBTC price in the book: not counted
+        let sol_only = PriceBook::new(100_000_000, None);
+        assert_eq!(
+            collateral_value_usd(AssetType::CbBTC, 50_000_000, &sol_only).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_lst_exchange_rate() {
+        // 1 mSOL at 1.25 SOL, $100 SOL: $125; without a rate it floors at 1 SOL
+        let book = PriceBook::new(100_000_000, None);
+        assert_eq!(
+            collateral_value_usd(AssetType::MSOL, LAMPORTS_PER_SOL, &book).unwrap(),
+            100_000_000
+        );
+        let book = book.with_rate(AssetType::MSOL, 1_250_000_000);
+        assert_eq!(
+            collateral_value_usd(AssetType::MSOL, LAMPORTS_PER_SOL, &book).unwrap(),
+            125_000_000
+        );
+    }
+
+    #[test]
+    fn test_yield_credit_rate() {
+        // 25% accrued, 20% of it held back
+        assert_eq!(yield_credit_rate(1_250_000_000, 2000), 1_200_000_000);
+        assert_eq!(yield_credit_rate(1_250_000_000, 0), 1_250_000_000);
+        assert_eq!(yield_credit_rate(1_250_000_000, 10_000), RATE_PRECISION);
+        // A depeg below 1:1 earns no credit
+        assert_eq!(yield_credit_rate(900_000_000, 2000), RATE_PRECISION);
+
+        let book = PriceBook::new(100_000_000, None).with_lst_yield(AssetType::MSOL, 1_250_000_000);
+        assert_eq!(
+            collateral_value_usd(AssetType::MSOL, LAMPORTS_PER_SOL, &book).unwrap(),
+            120_000_000
+        );
+    }
+
+    #[test]
+    fn test_total_skips_borrowables() {
+        let book = PriceBook::new(100_000_000, None);
+        let deposits = [
+            (AssetType::SOL, LAMPORTS_PER_SOL),
+            (AssetType::MSOL, LAMPORTS_PER_SOL),
+            (AssetType::USDC, 1_000_000),
+        ];
+        assert_eq!(
+            total_collateral_value_usd(deposits, &book).unwrap(),
+            200_000_000
+        );
+        assert_eq!(
+            pricing_method(AssetType::EURC),
+            PricingMethod::NotCollateral
+        );
+    }
+
+    #[test]
+    fn test_value_collaterals() {
+        let sol = feed(AssetType::SOL, 100_000_000);
+        let cbbtc = feed(AssetType::CbBTC, 60_000_000_000);
+        // 1 SOL at $100 + 0.01 cbBTC at $60k, each at its own price and decimals
+        let deposits = [
+            (AssetType::SOL, LAMPORTS_PER_SOL),
+            (AssetType::CbBTC, 1_000_000),
+        ];
+        assert_eq!(
+            value_collaterals(deposits, [&sol, &cbbtc], PriceBook::default()).unwrap(),
+            700_000_000
+        );
+        // A cbBTC deposit without its feed fails instead of counting as zero
+        assert!(value_collaterals(deposits, [&sol], PriceBook::default()).is_err());
+        assert_eq!(
+            value_collaterals(
+                [(AssetType::SOL, LAMPORTS_PER_SOL), (AssetType::CbBTC, 0)],
+                [&sol],
+                PriceBook::default()
+            )
+            .unwrap(),
+            100_000_000
+        );
+    }
+}
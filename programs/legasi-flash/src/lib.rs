@@ -1,57 +1,31 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::Discriminator;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-use legasi_core::{constants::*, errors::LegasiError, events::*, state::AssetType};
+use legasi_core::{
+    constants::*,
+    errors::LegasiError,
+    events::*,
+    program::LegasiCore,
+    seeds::*,
+    state::{AssetType, Borrowable, Protocol},
+    totals,
+};
+use legasi_lp::LpPool;
 
 declare_id!("Fj8CJNK1gBAuNR7dFbKLDckSstKmZn8ihTGwFXxfY93m");
 
-// ========== LOCAL STRUCTS (for cross-program account validation) ==========
-
-/// LP Pool (owned by LP program)
-#[account]
-#[derive(InitSpace)]
-pub struct LpPool {
-    pub borrowable_mint: Pubkey,
-    pub lp_token_mint: Pubkey,
-    pub total_deposits: u64,
-    pub total_shares: u64,
-    pub total_borrowed: u64,
-    pub interest_earned: u64,
-    pub bump: u8,
-}
-
-/// Borrowable config (owned by core program)
-#[account]
-#[derive(InitSpace)]
-pub struct Borrowable {
-    pub mint: Pubkey,
-    pub oracle: Pubkey,
-    pub interest_rate_bps: u16,
-    pub decimals: u8,
-    pub is_active: bool,
-    pub asset_type: AssetType,
-    pub bump: u8,
-}
-
-/// Protocol state (owned by core program)
-#[account]
-#[derive(InitSpace)]
-pub struct Protocol {
-    pub admin: Pubkey,
-    pub treasury: Pubkey,
-    pub insurance_fund: u64,
-    pub total_collateral_usd: u64,
-    pub total_borrowed_usd: u64,
-    pub paused: bool,
-    pub bump: u8,
-}
-
 /// Flash loan state (tracks outstanding loans in a transaction)
 #[account]
 #[derive(InitSpace)]
 pub struct FlashLoanState {
     pub borrower: Pubkey,
     pub asset_type: AssetType,
+    /// Pool lent from, the only one `flash_repay` accepts
+    pub lp_pool: Pubkey,
     pub amount: u64,
     pub fee: u64,
     pub initiated_slot: u64,
@@ -59,6 +33,35 @@ pub struct FlashLoanState {
     pub bump: u8,
 }
 
+/// Fee on a flash loan of `amount` (0.05%, minimum 1 token), repaid on top of it
+pub fn flash_loan_fee(amount: u64) -> Result<u64> {
+    Ok(std::cmp::max(
+        amount
+            .checked_mul(FLASH_LOAN_FEE_BPS)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR)
+            .ok_or(LegasiError::MathOverflow)?,
+        MIN_FLASH_LOAN_FEE,
+    ))
+}
+
+/// Fails unless an instruction after the current one in this transaction is
+/// `flash_repay` of `flash_state`. With the repay's own checks (same slot, right pool,
+/// principal plus fee transferred) the loan can't outlive the transaction
+pub fn require_repay_later(instructions: &AccountInfo, flash_state: &Pubkey) -> Result<()> {
+    let mut index = load_current_index_checked(instructions)? as usize + 1;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == crate::ID
+            && ix.data.get(..8) == Some(&instruction::FlashRepay::DISCRIMINATOR[..])
+            && ix.accounts.first().map(|meta| meta.pubkey) == Some(*flash_state)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(LegasiError::FlashRepayMissing)
+}
+
 #[program]
 pub mod legasi_flash {
     use super::*;
@@ -66,29 +69,35 @@ pub mod legasi_flash {
     /// Initiate a flash loan - must be repaid in same transaction
     pub fn flash_borrow(ctx: Context<FlashBorrow>, amount: u64, slot: u64) -> Result<()> {
         require!(amount > 0, LegasiError::InvalidAmount);
+        ctx.accounts
+            .protocol
+            .require_not_paused(PAUSE_FLASH_LOANS)?;
 
         // Verify slot matches current slot (prevents replay)
         let current_slot = Clock::get()?.slot;
         require!(slot == current_slot, LegasiError::InvalidSlot);
+        require_repay_later(&ctx.accounts.instructions, &ctx.accounts.flash_state.key())?;
         require!(
             ctx.accounts.vault.amount >= amount,
             LegasiError::InsufficientLiquidity
         );
+        // Paused while the pool is above its emergency utilization cap
+        ctx.accounts.lp_pool.check_utilization(0)?;
 
-        // Calculate fee (0.05%, minimum 1 token)
-        let fee = std::cmp::max(
-            amount
-                .checked_mul(FLASH_LOAN_FEE_BPS)
-                .ok_or(LegasiError::MathOverflow)?
-                .checked_div(BPS_DENOMINATOR)
-                .ok_or(LegasiError::MathOverflow)?,
-            MIN_FLASH_LOAN_FEE,
-        );
+        // Enforce outflow rate limit
+        let tvl = ctx.accounts.lp_pool.total_deposits;
+        ctx.accounts
+            .lp_pool
+            .outflow_limiter
+            .record_outflow(amount, tvl, current_slot)?;
+
+        let fee = flash_loan_fee(amount)?;
 
         // Initialize flash loan state
         let flash_state = &mut ctx.accounts.flash_state;
         flash_state.borrower = ctx.accounts.borrower.key();
         flash_state.asset_type = ctx.accounts.borrowable.asset_type;
+        flash_state.lp_pool = ctx.accounts.lp_pool.key();
         flash_state.amount = amount;
         flash_state.fee = fee;
         flash_state.initiated_slot = Clock::get()?.slot;
@@ -98,7 +107,7 @@ pub mod legasi_flash {
         // Transfer tokens to borrower
         let pool_bump = ctx.accounts.lp_pool.bump;
         let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
-        let seeds: &[&[u8]] = &[b"lp_pool", borrowable_mint.as_ref(), &[pool_bump]];
+        let seeds: &[&[u8]] = &[LP_POOL_SEED, borrowable_mint.as_ref(), &[pool_bump]];
 
         token::transfer(
             CpiContext::new_with_signer(
@@ -136,12 +145,20 @@ pub mod legasi_flash {
         );
         require!(!flash_state.repaid, LegasiError::FlashLoanNotRepaid);
 
-        let total_repayment = flash_state
+        let insurance_fee = flash_state
+            .fee
+            .checked_mul(INSURANCE_FEE_BPS)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR)
+            .ok_or(LegasiError::MathOverflow)?;
+        let lp_fee = flash_state.fee.saturating_sub(insurance_fee);
+        let pool_repayment = flash_state
             .amount
-            .checked_add(flash_state.fee)
+            .checked_add(lp_fee)
             .ok_or(LegasiError::MathOverflow)?;
 
-        // Transfer repayment from borrower to vault
+        // Transfer principal and the LP fee from borrower to vault, and the
+        // insurance cut to the insurance vault
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
@@ -151,8 +168,21 @@ pub mod legasi_flash {
                     authority: ctx.accounts.borrower.to_account_info(),
                 },
             ),
-            total_repayment,
+            pool_repayment,
         )?;
+        if insurance_fee > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.user_token_account.to_account_info(),
+                        to: ctx.accounts.insurance_vault.to_account_info(),
+                        authority: ctx.accounts.borrower.to_account_info(),
+                    },
+                ),
+                insurance_fee,
+            )?;
+        }
 
         // Mark as repaid
         let flash_state = &mut ctx.accounts.flash_state;
@@ -160,14 +190,7 @@ pub mod legasi_flash {
 
         // Fee goes to LP pool (increases LP token value)
         let lp_pool = &mut ctx.accounts.lp_pool;
-        let insurance_fee = flash_state
-            .fee
-            .checked_mul(INSURANCE_FEE_BPS)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(LegasiError::MathOverflow)?;
-
-        let lp_fee = flash_state.fee.saturating_sub(insurance_fee);
+        lp_pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
         lp_pool.total_deposits = lp_pool
             .total_deposits
             .checked_add(lp_fee)
@@ -177,12 +200,14 @@ pub mod legasi_flash {
             .checked_add(lp_fee)
             .ok_or(LegasiError::MathOverflow)?;
 
-        // Update protocol insurance
-        let protocol = &mut ctx.accounts.protocol;
-        protocol.insurance_fund = protocol
-            .insurance_fund
-            .checked_add(insurance_fee)
-            .ok_or(LegasiError::MathOverflow)?;
+        // Book the insurance cut on the protocol
+        totals::report_insurance_fee(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            insurance_fee,
+        )?;
 
         emit!(FlashLoanRepaid {
             borrower: ctx.accounts.borrower.key(),
@@ -219,7 +244,7 @@ pub struct FlashBorrow<'info> {
         init,
         payer = borrower,
         space = 8 + FlashLoanState::INIT_SPACE,
-        seeds = [b"flash", borrower.key().as_ref(), &slot.to_le_bytes()],
+        seeds = [FLASH_SEED, borrower.key().as_ref(), &slot.to_le_bytes()],
         bump
     )]
     pub flash_state: Account<'info, FlashLoanState>,
@@ -228,13 +253,24 @@ pub struct FlashBorrow<'info> {
     pub lp_pool: Account<'info, LpPool>,
     /// Borrowable config (owned by core program)
     pub borrowable: Account<'info, Borrowable>,
+    /// Protocol state (owned by core program), for the pause flags
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
     /// LP Vault
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
     pub vault: Account<'info, TokenAccount>,
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
     #[account(mut)]
     pub borrower: Signer<'info>,
+    /// CHECK: Instructions sysvar, searched for the matching `flash_repay`
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
@@ -243,24 +279,41 @@ pub struct FlashBorrow<'info> {
 pub struct FlashRepay<'info> {
     #[account(
         mut,
-        seeds = [b"flash", borrower.key().as_ref(), &flash_state.initiated_slot.to_le_bytes()],
+        seeds = [FLASH_SEED, borrower.key().as_ref(), &flash_state.initiated_slot.to_le_bytes()],
         bump = flash_state.bump,
         has_one = borrower
     )]
     pub flash_state: Account<'info, FlashLoanState>,
-    /// LP Pool (owned by LP program)
-    #[account(mut)]
+    /// LP Pool the loan came from (owned by LP program)
+    #[account(mut, address = flash_state.lp_pool)]
     pub lp_pool: Account<'info, LpPool>,
-    /// Protocol (owned by core program)
-    #[account(mut)]
+    /// Protocol (owned by core program - insurance fund updated via CPI)
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
     pub protocol: Account<'info, Protocol>,
     /// LP Vault
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
     pub vault: Account<'info, TokenAccount>,
+    /// Insurance vault of the pool's asset
+    #[account(
+        mut,
+        seeds = [INSURANCE_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
     #[account(mut)]
     pub borrower: Signer<'info>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
     pub token_program: Program<'info, Token>,
 }
 
@@ -269,7 +322,7 @@ pub struct CloseFlashState<'info> {
     #[account(
         mut,
         close = borrower,
-        seeds = [b"flash", borrower.key().as_ref(), &flash_state.initiated_slot.to_le_bytes()],
+        seeds = [FLASH_SEED, borrower.key().as_ref(), &flash_state.initiated_slot.to_le_bytes()],
         bump = flash_state.bump,
         has_one = borrower
     )]
@@ -1,235 +1,302 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
 use anchor_lang::solana_program::system_instruction;
-use anchor_spl::token::{Token, TokenAccount};
-
-use legasi_core::{constants::*, errors::LegasiError, events::*, state::*};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use legasi_core::{
+    constants::*,
+    decimals::{sol_to_usd, token_to_usd},
+    errors::LegasiError,
+    events::*,
+    gad,
+    gad_schedule::Throttle,
+    interest, jupiter_cpi,
+    market::{GadProceedsMode, Market},
+    program::LegasiCore,
+    seeds::*,
+    state::*,
+    swap_router::SwapRoute,
+    totals, valuation,
+};
+use legasi_lending::{
+    emode::market_covers, fixed_term, penalized_fraction_bps, program::LegasiLending,
+    GadDebtReduction, GadSettlement, Position, RepaymentSchedule,
+};
+use legasi_lp::{program::LegasiLp, LpPool};
+
+pub mod liquidation_auction;
+pub use liquidation_auction::*;
 
 declare_id!("89E84ALdDdGGNuJAxho2H45aC25kqNdGg7QtwTJ3pngK");
 
-// Jupiter Aggregator v6 Program ID (mainnet)
-// JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4
-pub mod jupiter {
-    use anchor_lang::prelude::*;
-    declare_id!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
-}
-
-/// GAD rate curve - continuous quadratic with capped max
-fn get_gad_rate_bps(current_ltv_bps: u64, max_ltv_bps: u64) -> u64 {
-    if current_ltv_bps <= max_ltv_bps {
-        return 0;
-    }
-
-    let excess_bps = current_ltv_bps.saturating_sub(max_ltv_bps);
-
-    // Quadratic curve: rate = (excess/100)^2, capped at 1000 bps/day (10%)
-    let rate = (excess_bps as u128).pow(2).checked_div(100).unwrap_or(0) as u64;
-
-    std::cmp::min(rate, 1000)
-}
-
 #[program]
 pub mod legasi_gad {
     use super::*;
 
     /// Configure GAD settings for a position
+    /// GAD can only be disabled while the position has no debt; lending refuses new
+    /// borrows until it is enabled again. Cranks leave `min_collateral_floor` lamports
+    /// of SOL collateral on the position, at most half of it (see
+    /// `legasi_lending::MAX_GAD_COLLATERAL_FLOOR_BPS`)
     pub fn configure_gad(
         ctx: Context<ConfigureGad>,
         enabled: bool,
         _custom_threshold_bps: Option<u16>,
+        min_collateral_floor: u64,
     ) -> Result<()> {
-        let position = &mut ctx.accounts.position;
-        position.gad_enabled = enabled;
+        legasi_lending::set_gad_config(
+            &ctx.accounts.lending_program.to_account_info(),
+            legasi_lending::cpi::accounts::SetGadConfig {
+                position: ctx.accounts.position.to_account_info(),
+                gad_writer: ctx.accounts.protocol_writer.to_account_info(),
+            },
+            ctx.bumps.protocol_writer,
+            enabled,
+            min_collateral_floor,
+        )?;
 
         // Custom threshold would need to be stored - for now just toggle
-        msg!("GAD configured: enabled={}", enabled);
+        msg!(
+            "GAD configured: enabled={}, floor={} lamports",
+            enabled,
+            min_collateral_floor
+        );
         Ok(())
     }
 
-    /// Crank GAD for a position - anyone can call
-    pub fn crank_gad(ctx: Context<CrankGad>) -> Result<()> {
-        let position = &ctx.accounts.position;
+    /// Sponsor a position: a third party (DAO, employer) posts a SOL backstop that GAD
+    /// draws before the borrower's collateral. The owner co-signs to accept the sponsor
+    /// The backstop does not raise the position's borrowing power
+    pub fn sponsor_position(ctx: Context<SponsorPosition>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+
+        let sponsorship = &mut ctx.accounts.sponsorship;
+        sponsorship.position = ctx.accounts.position.key();
+        sponsorship.sponsor = ctx.accounts.sponsor.key();
+        sponsorship.bump = ctx.bumps.sponsorship;
+
+        deposit_backstop(
+            &ctx.accounts.sponsor,
+            &ctx.accounts.sponsor_vault,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
 
-        // Check GAD is enabled
-        require!(position.gad_enabled, LegasiError::GadDisabled);
+        msg!("Position sponsored with {} lamports", amount);
+        Ok(())
+    }
 
-        // Check has debt
-        require!(
-            !position.borrows.is_empty(),
-            LegasiError::NoDebtToDeleverage
-        );
+    /// Add SOL to the sponsor backstop (sponsor only)
+    pub fn top_up_backstop(ctx: Context<TopUpBackstop>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+        deposit_backstop(
+            &ctx.accounts.sponsor,
+            &ctx.accounts.sponsor_vault,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
 
-        // Check minimum time since last crank
-        let now = Clock::get()?.unix_timestamp;
-        let elapsed = now.saturating_sub(position.last_gad_crank);
-        require!(elapsed >= MIN_GAD_CRANK_INTERVAL, LegasiError::CrankTooSoon);
+        msg!("Backstop topped up by {} lamports", amount);
+        Ok(())
+    }
 
-        // Calculate current LTV
-        let total_collateral_usd =
-            calculate_collateral_value(position, &ctx.accounts.sol_price_feed)?;
+    /// End a sponsorship once the position has no debt, returning what is left
+    /// of the backstop to the sponsor
+    pub fn end_sponsorship(ctx: Context<EndSponsorship>) -> Result<()> {
         require!(
-            total_collateral_usd > 0,
-            LegasiError::InsufficientCollateral
+            ctx.accounts.position.borrows.is_empty(),
+            LegasiError::SponsorshipLocked
         );
 
-        let total_borrow_usd = calculate_borrow_value(position)?;
+        let position_key = ctx.accounts.position.key();
+        let backstop_bump = ctx.bumps.sponsor_vault;
+        let backstop_seeds: &[&[u8]] =
+            &[SPONSOR_VAULT_SEED, position_key.as_ref(), &[backstop_bump]];
+        let remaining = ctx.accounts.sponsor_vault.lamports();
+        pay_from_pda(
+            &ctx.accounts.sponsor_vault.to_account_info(),
+            &ctx.accounts.sponsor.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            backstop_seeds,
+            remaining,
+        )?;
 
-        let current_ltv_bps = total_borrow_usd
-            .checked_mul(BPS_DENOMINATOR)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(total_collateral_usd)
-            .ok_or(LegasiError::MathOverflow)?;
+        msg!("Sponsorship ended, {} lamports returned", remaining);
+        Ok(())
+    }
+
+    /// Crank GAD for a position - anyone can call
+    /// With no swap to deliver it to the pool, the LP recovery share of the slice stays
+    /// on the position for a swap crank to sell, and no debt is repaid; the treasury,
+    /// insurance and cranker shares are paid out in SOL. Debt pools go in
+    /// remaining_accounts (see `legasi_lending::accrue_all_interest`)
+    pub fn crank_gad(ctx: Context<CrankGad>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        // LTV must see the debt as of now, not as of the last accrual
+        let mut projected = (**ctx.accounts.position).clone();
+        legasi_lending::accrue_all_interest(&mut projected, ctx.remaining_accounts, now)?;
+        let position = &projected;
 
-        // Check if LTV exceeds max (75% default for SOL)
-        let max_ltv_bps = DEFAULT_SOL_MAX_LTV_BPS as u64;
+        // Check GAD is enabled
+        require!(position.gad_enabled, LegasiError::GadDisabled);
+
+        // Check has debt
         require!(
-            current_ltv_bps > max_ltv_bps,
-            LegasiError::LtvBelowGadThreshold
+            !position.borrows.is_empty(),
+            LegasiError::NoDebtToDeleverage
         );
 
-        // Calculate GAD rate
-        let gad_rate_bps = get_gad_rate_bps(current_ltv_bps, max_ltv_bps);
-        require!(gad_rate_bps > 0, LegasiError::NothingToLiquidate);
-
-        // Calculate amount to liquidate (pro-rata based on time elapsed)
-        let time_fraction = (elapsed as u128)
-            .checked_mul(BPS_DENOMINATOR as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(SECONDS_PER_DAY as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
+        // Refuse to act on a print far from recent syncs (e.g., a flash crash)
+        require_price_in_band(&ctx.accounts.sol_price_feed)?;
+        if let Some(feed) = &ctx.accounts.eur_price_feed {
+            require_price_in_band(feed)?;
+        }
+        if let Some(feed) = &ctx.accounts.cbbtc_price_feed {
+            require_price_in_band(feed)?;
+        }
 
-        let liquidate_fraction_bps = (gad_rate_bps as u128)
-            .checked_mul(time_fraction as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
+        // Check crank interval and LTV above max (75% default for SOL), size the crank
+        let elapsed = now.saturating_sub(position.last_gad_crank);
+        let total_collateral_usd = calculate_collateral_value(
+            position,
+            &ctx.accounts.sol_price_feed,
+            &ctx.accounts.cbbtc_price_feed,
+            now,
+        )?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let total_borrow_usd = calculate_borrow_value(position, eur_price)?;
+        // A fixed-term loan past maturity makes the position eligible sooner
+        let max_ltv_bps = gad_max_ltv_bps(position, now);
+        let mut assessment =
+            gad::assess(total_collateral_usd, total_borrow_usd, max_ltv_bps, elapsed)?;
+        // Holding liquidates everything due at once, including time carried by earlier
+        // paced sales
+        let slices = market_sale_slices(
+            ctx.accounts.market.as_deref(),
+            position,
+            &[GadProceedsMode::Hold],
+        )?;
+        let carried_secs =
+            pace_assessment(&mut assessment, elapsed, position.gad_carried_secs, slices)?;
+        apply_repayment_schedule(
+            &ctx.accounts.repayment_schedule,
+            &mut assessment,
+            max_ltv_bps,
+            now,
+        )?;
+        // Thin-liquidity windows sell at a reduced rate
+        assessment.liquidate_fraction_bps = Throttle::load(&ctx.accounts.gad_schedule, now)?
+            .gad_fraction_bps(assessment.liquidate_fraction_bps);
 
-        // Find SOL collateral and calculate liquidation amount
+        // Find SOL collateral and size the liquidation slice
         let sol_deposit = position
             .collaterals
             .iter()
             .find(|c| c.asset_type == AssetType::SOL)
             .ok_or(LegasiError::InsufficientCollateral)?;
 
-        let sol_to_liquidate = (sol_deposit.amount as u128)
-            .checked_mul(liquidate_fraction_bps as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
+        // Vault must stay rent-exempt, so that balance is the collateral floor, or the
+        // owner's GAD floor if higher
+        let collateral_floor = Rent::get()?.minimum_balance(0);
+        let liquidation_split = ctx.accounts.protocol.liquidation_split;
+        let reward_bps = liquidation_split.cranker_reward_bps(gad::cranker_reward_bps(
+            assessment.ltv_bps,
+            max_ltv_bps,
+            elapsed,
+            ctx.accounts.protocol.cranker_reward_min_bps,
+            ctx.accounts.protocol.cranker_reward_max_bps,
+        ));
+        let split = gad::split_liquidation(
+            sol_deposit.amount,
+            assessment.liquidate_fraction_bps,
+            collateral_floor.max(position.gad_collateral_floor),
+            reward_bps,
+        )?;
+        require!(split.total_deducted > 0, LegasiError::NothingToLiquidate);
 
+        // Only the treasury and insurance shares go to the treasury; without a
+        // configured split all of it is LP recovery, so there is nothing to hold
+        let held = liquidation_split.split_proceeds(split.to_treasury);
+        let sol_to_liquidate = split.to_treasury - held.lp_recovery;
         require!(sol_to_liquidate > 0, LegasiError::NothingToLiquidate);
+        let split = gad::GadSplit {
+            to_treasury: sol_to_liquidate,
+            cranker_reward: split.cranker_reward,
+            total_deducted: sol_to_liquidate + split.cranker_reward,
+        };
+        let cranker_reward = split.cranker_reward;
 
-        // Calculate USD value of liquidated SOL
-        let sol_price = ctx.accounts.sol_price_feed.price_usd_6dec;
-        let liquidated_usd = (sol_to_liquidate as u128)
-            .checked_mul(sol_price as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(LAMPORTS_PER_SOL as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
-
-        // Reduce debt by liquidated amount
-        let debt_reduction = std::cmp::min(liquidated_usd, total_borrow_usd);
-
-        // Calculate cranker reward (0.5% of liquidated)
-        let cranker_reward = sol_to_liquidate
-            .checked_mul(CRANKER_REWARD_BPS)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(LegasiError::MathOverflow)?;
+        // A sponsor's backstop is drawn before the borrower's collateral
+        let draw = gad::draw_backstop_first(
+            &split,
+            ctx.accounts.sponsor_vault.lamports(),
+            collateral_floor,
+        );
+        let total_sol_deducted = draw.from_position();
 
-        let total_sol_deducted = sol_to_liquidate
-            .checked_add(cranker_reward)
-            .ok_or(LegasiError::MathOverflow)?;
+        // USD value of the borrower's collateral removed
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_twap(now)?;
+        let liquidated_usd = sol_to_usd(total_sol_deducted, sol_price)?;
 
-        // Transfer SOL to treasury
+        // Transfer SOL to treasury and the cranker, backstop first
         let position_key = ctx.accounts.position.key();
-        let vault_bump = ctx.bumps.sol_vault;
-        let seeds: &[&[u8]] = &[b"sol_vault", position_key.as_ref(), &[vault_bump]];
-
-        invoke_signed(
-            &system_instruction::transfer(
-                ctx.accounts.sol_vault.key,
-                ctx.accounts.treasury.key,
-                sol_to_liquidate,
-            ),
-            &[
-                ctx.accounts.sol_vault.to_account_info(),
-                ctx.accounts.treasury.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-            &[seeds],
-        )?;
-
-        // Transfer cranker reward
-        if cranker_reward > 0 {
-            invoke_signed(
-                &system_instruction::transfer(
-                    ctx.accounts.sol_vault.key,
-                    ctx.accounts.cranker.key,
-                    cranker_reward,
-                ),
-                &[
-                    ctx.accounts.sol_vault.to_account_info(),
-                    ctx.accounts.cranker.to_account_info(),
-                    ctx.accounts.system_program.to_account_info(),
-                ],
-                &[seeds],
-            )?;
-        }
-
-        // Update position
-        let position = &mut ctx.accounts.position;
-
-        // Reduce SOL collateral
-        if let Some(sol_deposit) = position
-            .collaterals
-            .iter_mut()
-            .find(|c| c.asset_type == AssetType::SOL)
-        {
-            sol_deposit.amount = sol_deposit.amount.saturating_sub(total_sol_deducted);
-        }
-
-        // Reduce debt (proportionally across all borrows)
-        let mut remaining_reduction = debt_reduction;
-        for borrow in position.borrows.iter_mut() {
-            if remaining_reduction == 0 {
-                break;
-            }
-            let borrow_total = borrow
-                .amount
-                .checked_add(borrow.accrued_interest)
-                .unwrap_or(0);
-            let reduction = std::cmp::min(remaining_reduction, borrow_total);
-
-            // First reduce interest, then principal
-            let interest_reduction = std::cmp::min(reduction, borrow.accrued_interest);
-            borrow.accrued_interest = borrow.accrued_interest.saturating_sub(interest_reduction);
-
-            let principal_reduction = reduction.saturating_sub(interest_reduction);
-            borrow.amount = borrow.amount.saturating_sub(principal_reduction);
+        let backstop_bump = ctx.bumps.sponsor_vault;
+        let backstop_seeds: &[&[u8]] =
+            &[SPONSOR_VAULT_SEED, position_key.as_ref(), &[backstop_bump]];
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let sponsor_vault = ctx.accounts.sponsor_vault.to_account_info();
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let cranker = ctx.accounts.cranker.to_account_info();
+        let vaults = CollateralVaults {
+            lending_program: ctx.accounts.lending_program.to_account_info(),
+            position: ctx.accounts.position.to_account_info(),
+            sol_vault: ctx.accounts.sol_vault.to_account_info(),
+            msol_vault: None,
+            writer: ctx.accounts.protocol_writer.to_account_info(),
+            writer_bump: ctx.bumps.protocol_writer,
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: system_program.clone(),
+        };
 
-            remaining_reduction = remaining_reduction.saturating_sub(reduction);
+        pay_from_pda(
+            &sponsor_vault,
+            &treasury,
+            &system_program,
+            backstop_seeds,
+            draw.treasury_from_backstop,
+        )?;
+        pay_from_pda(
+            &sponsor_vault,
+            &cranker,
+            &system_program,
+            backstop_seeds,
+            draw.reward_from_backstop,
+        )?;
+        vaults.release(AssetType::SOL, &treasury, draw.treasury_from_position)?;
+        vaults.release(AssetType::SOL, &cranker, draw.reward_from_position)?;
+
+        if draw.from_backstop() > 0 {
+            emit!(SponsorBackstopDrawn {
+                position: position_key,
+                lamports: draw.from_backstop(),
+                remaining: ctx.accounts.sponsor_vault.lamports(),
+            });
         }
 
-        // Update GAD stats
-        position.last_gad_crank = now;
-        position.total_gad_liquidated_usd = position
-            .total_gad_liquidated_usd
-            .saturating_add(liquidated_usd);
-        position.reputation.gad_events = position.reputation.gad_events.saturating_add(1);
-        position.last_update = now;
-
-        // Clean up empty entries
-        position.collaterals.retain(|c| c.amount > 0);
-        position
-            .borrows
-            .retain(|b| b.amount > 0 || b.accrued_interest > 0);
+        // Update GAD stats; the debt waits for a swap crank
+        vaults.settle(
+            ctx.remaining_accounts,
+            GadSettlement {
+                debt: GadDebtReduction::None,
+                liquidated_usd,
+                carried_secs: Some(carried_secs),
+            },
+        )?;
 
         // Calculate new LTV for event
         let new_collateral_usd = total_collateral_usd.saturating_sub(liquidated_usd);
-        let new_borrow_usd = total_borrow_usd.saturating_sub(debt_reduction);
         let ltv_after_bps = if new_collateral_usd > 0 {
-            new_borrow_usd
+            total_borrow_usd
                 .checked_mul(BPS_DENOMINATOR)
                 .unwrap_or(0)
                 .checked_div(new_collateral_usd)
@@ -238,13 +305,38 @@ pub mod legasi_gad {
             0
         };
 
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(liquidated_usd),
+            0,
+        )?;
+        totals::report_sol_collateral(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -(total_sol_deducted as i64),
+        )?;
+
+        emit!(GadProceedsSplit {
+            position: position_key,
+            lp_recovery_usd: 0,
+            treasury_usd: sol_to_usd(held.treasury, sol_price)?,
+            insurance_usd: sol_to_usd(held.insurance, sol_price)?,
+            cranker_usd: sol_to_usd(cranker_reward, sol_price)?,
+            insurance_credited: false,
+        });
+
         emit!(GadExecuted {
             position: ctx.accounts.position.key(),
             collateral_liquidated_usd: liquidated_usd,
-            debt_reduced_usd: debt_reduction,
-            ltv_before_bps: current_ltv_bps,
+            debt_reduced_usd: 0,
+            ltv_before_bps: assessment.ltv_bps,
             ltv_after_bps,
-            gad_rate_bps,
+            gad_rate_bps: assessment.rate_bps,
             cranker: ctx.accounts.cranker.key(),
             cranker_reward,
         });
@@ -257,14 +349,20 @@ pub mod legasi_gad {
         Ok(())
     }
 
-    /// Execute GAD with Jupiter swap - converts liquidated collateral to USDC
-    /// This is the production version that actually swaps via Jupiter
+    /// Execute GAD with Jupiter swap - converts liquidated collateral to USDC in the LP vault
+    /// This is the production version that actually swaps via Jupiter. Debt pools go
+    /// first in remaining_accounts, then the route accounts
     pub fn crank_gad_with_swap(
         ctx: Context<CrankGadWithSwap>,
         jupiter_swap_data: Vec<u8>, // Serialized Jupiter swap instruction data
         min_out_amount: u64,        // Minimum USDC to receive (slippage protection)
     ) -> Result<()> {
-        let position = &ctx.accounts.position;
+        let now = Clock::get()?.unix_timestamp;
+        // LTV must see the debt as of now, not as of the last accrual
+        let mut projected = (**ctx.accounts.position).clone();
+        let (debt_pools, route_accounts) = split_debt_pools(&projected, ctx.remaining_accounts)?;
+        legasi_lending::accrue_all_interest(&mut projected, debt_pools, now)?;
+        let position = &projected;
 
         require!(position.gad_enabled, LegasiError::GadDisabled);
         require!(
@@ -272,127 +370,1039 @@ pub mod legasi_gad {
             LegasiError::NoDebtToDeleverage
         );
 
-        // Calculate amount to liquidate (same logic as crank_gad)
+        // Same price checks, eligibility and sizing as crank_gad
+        require_price_in_band(&ctx.accounts.sol_price_feed)?;
+        if let Some(feed) = &ctx.accounts.eur_price_feed {
+            require_price_in_band(feed)?;
+        }
+        if let Some(feed) = &ctx.accounts.cbbtc_price_feed {
+            require_price_in_band(feed)?;
+        }
+        let elapsed = now.saturating_sub(position.last_gad_crank);
+        let total_collateral_usd = calculate_collateral_value(
+            position,
+            &ctx.accounts.sol_price_feed,
+            &ctx.accounts.cbbtc_price_feed,
+            now,
+        )?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let total_borrow_usd = calculate_borrow_value(position, eur_price)?;
+        // A fixed-term loan past maturity makes the position eligible sooner
+        let max_ltv_bps = gad_max_ltv_bps(position, now);
+        let mut assessment =
+            gad::assess(total_collateral_usd, total_borrow_usd, max_ltv_bps, elapsed)?;
+        // A market selling over several cranks sells its share of what is due now and
+        // carries the rest
+        let slices = market_sale_slices(
+            ctx.accounts.market.as_deref(),
+            position,
+            &[GadProceedsMode::Swap, GadProceedsMode::Twap],
+        )?;
+        let carried_secs =
+            pace_assessment(&mut assessment, elapsed, position.gad_carried_secs, slices)?;
+        apply_repayment_schedule(
+            &ctx.accounts.repayment_schedule,
+            &mut assessment,
+            max_ltv_bps,
+            now,
+        )?;
+        // Thin-liquidity windows sell at a reduced rate
+        assessment.liquidate_fraction_bps = Throttle::load(&ctx.accounts.gad_schedule, now)?
+            .gad_fraction_bps(assessment.liquidate_fraction_bps);
+
+        // The route may sell at most this crank's slice of SOL collateral
+        let sol_deposit = position
+            .collaterals
+            .iter()
+            .find(|c| c.asset_type == AssetType::SOL)
+            .ok_or(LegasiError::InsufficientCollateral)?;
+        let collateral_floor = Rent::get()?.minimum_balance(0);
+        require_backstop_exhausted(&ctx.accounts.sponsor_vault, collateral_floor)?;
+        let liquidation_split = ctx.accounts.protocol.liquidation_split;
+        let reward_bps = liquidation_split.cranker_reward_bps(gad::cranker_reward_bps(
+            assessment.ltv_bps,
+            max_ltv_bps,
+            elapsed,
+            ctx.accounts.protocol.cranker_reward_min_bps,
+            ctx.accounts.protocol.cranker_reward_max_bps,
+        ));
+        let split = gad::split_liquidation(
+            sol_deposit.amount,
+            assessment.liquidate_fraction_bps,
+            collateral_floor.max(position.gad_collateral_floor),
+            reward_bps,
+        )?;
+        require!(split.total_deducted > 0, LegasiError::NothingToLiquidate);
+
+        // A configured split pays the cranker and treasury shares in SOL, and only the
+        // LP recovery and insurance shares are sold
+        let (cranker_reward, treasury_fee) = if liquidation_split.is_set() {
+            (
+                split.cranker_reward,
+                liquidation_split.split_proceeds(split.to_treasury).treasury,
+            )
+        } else {
+            (0, 0)
+        };
+        let max_sol_in = split.total_deducted - cranker_reward - treasury_fee;
+
+        let position_key = ctx.accounts.position.key();
+        let vaults = CollateralVaults {
+            lending_program: ctx.accounts.lending_program.to_account_info(),
+            position: ctx.accounts.position.to_account_info(),
+            sol_vault: ctx.accounts.sol_vault.to_account_info(),
+            msol_vault: None,
+            writer: ctx.accounts.protocol_writer.to_account_info(),
+            writer_bump: ctx.bumps.protocol_writer,
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        vaults.release(
+            AssetType::SOL,
+            &ctx.accounts.cranker.to_account_info(),
+            cranker_reward,
+        )?;
+        vaults.release(
+            AssetType::SOL,
+            &ctx.accounts.treasury.to_account_info(),
+            treasury_fee,
+        )?;
+
+        // Execute Jupiter swap: SOL → USDC, signed by the SOL vault through lending
+        // Route accounts are passed via remaining_accounts, after the debt pools
+        let sol_before = ctx.accounts.sol_vault.lamports();
+        let usdc_before = ctx.accounts.usdc_vault.amount;
+
+        vaults.swap(
+            AssetType::SOL,
+            SwapRoute::Jupiter,
+            &ctx.accounts.jupiter_program.to_account_info(),
+            route_accounts,
+            jupiter_swap_data,
+            max_sol_in,
+        )?;
+
+        // Verify we received minimum USDC from this swap
+        ctx.accounts.usdc_vault.reload()?;
+        let usdc_received = jupiter_cpi::assert_min_received(
+            usdc_before,
+            ctx.accounts.usdc_vault.amount,
+            min_out_amount,
+        )?;
+        let sol_liquidated = jupiter_cpi::assert_max_spent(
+            sol_before,
+            ctx.accounts.sol_vault.lamports(),
+            max_sol_in,
+        )?;
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_twap(now)?;
+        let sol_removed = sol_liquidated + cranker_reward + treasury_fee;
+        let liquidated_usd = sol_to_usd(sol_removed, sol_price)?;
+        let (usdc_to_debt, usdc_to_insurance) = liquidation_split.split_swap_output(usdc_received);
+
+        // The sold and paid-out SOL left the position with the vault; reduce the USDC
+        // debt by the LP recovery USDC
+        let settlement = GadSettlement {
+            debt: GadDebtReduction::Asset {
+                asset_type: AssetType::USDC,
+                amount: usdc_to_debt,
+            },
+            liquidated_usd,
+            carried_secs: Some(carried_secs),
+        };
+        let (interest_reduced, principal_reduced) =
+            projected.apply_gad_settlement(&settlement, now)?;
+        vaults.settle(debt_pools, settlement)?;
+
+        let core_program = ctx.accounts.core_program.to_account_info();
+        let protocol = ctx.accounts.protocol.to_account_info();
+        let lp_program = ctx.accounts.lp_program.to_account_info();
+        let lp_pool = ctx.accounts.lp_pool.to_account_info();
+        let writer = ctx.accounts.protocol_writer.to_account_info();
+        let writer_bump = ctx.bumps.protocol_writer;
+
+        // The output is in the pool vault: write the principal off its borrows and
+        // credit the interest to LPs, as an auction bid does
+        legasi_lp::report_borrowed(
+            &lp_program,
+            &lp_pool,
+            &writer,
+            writer_bump,
+            -totals::usd_delta(principal_reduced),
+        )?;
+        legasi_lp::accrue_interest(
+            &lp_program,
+            &lp_pool,
+            &writer,
+            writer_bump,
+            interest_reduced,
+            0,
+        )?;
+        // The insurance share stays in the vault with the LP recovery, set aside for
+        // the insurance vault with the interest's insurance cut, and booked on the fund
+        legasi_lp::set_aside_insurance(
+            &lp_program,
+            &lp_pool,
+            &writer,
+            writer_bump,
+            usdc_to_insurance,
+        )?;
+        totals::report_insurance_fee(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            interest::calculate_insurance_fee(interest_reduced) + usdc_to_insurance,
+        )?;
+        totals::report(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            -totals::usd_delta(liquidated_usd),
+            -totals::usd_delta(principal_reduced),
+        )?;
+        totals::report_sol_collateral(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            -(sol_removed as i64),
+        )?;
+
+        if liquidation_split.is_set() {
+            emit!(GadProceedsSplit {
+                position: position_key,
+                lp_recovery_usd: usdc_to_debt,
+                treasury_usd: sol_to_usd(treasury_fee, sol_price)?,
+                insurance_usd: usdc_to_insurance,
+                cranker_usd: sol_to_usd(cranker_reward, sol_price)?,
+                insurance_credited: true,
+            });
+        }
+
+        emit!(GadSwapExecuted {
+            position: ctx.accounts.position.key(),
+            sol_liquidated,
+            usdc_received,
+            cranker: ctx.accounts.cranker.key(),
+        });
+
+        msg!("GAD swap executed: received {} USDC", usdc_received);
+        Ok(())
+    }
+
+    /// Execute GAD on staked SOL collateral (mSOL) through the asset's configured swap route
+    /// Sanctum-routed LSTs unstake through the pool instead of generic AMM routes,
+    /// which keeps slippage low on large deleverages. Route accounts are passed via
+    /// remaining_accounts after the debt pools; the output is valued at its price feed
+    /// to reduce debt
+    pub fn crank_gad_lst_with_swap(
+        ctx: Context<CrankGadLstWithSwap>,
+        route_data: Vec<u8>, // Serialized router instruction data (Jupiter or Sanctum)
+        min_out_amount: u64, // Minimum output to receive (slippage protection)
+    ) -> Result<()> {
         let now = Clock::get()?.unix_timestamp;
+        // LTV must see the debt as of now, not as of the last accrual
+        let mut projected = (**ctx.accounts.position).clone();
+        let (debt_pools, route_accounts) = split_debt_pools(&projected, ctx.remaining_accounts)?;
+        legasi_lending::accrue_all_interest(&mut projected, debt_pools, now)?;
+        let position = &projected;
+
+        require!(position.gad_enabled, LegasiError::GadDisabled);
+        require!(
+            !position.borrows.is_empty(),
+            LegasiError::NoDebtToDeleverage
+        );
+
+        // The output is valued at its own feed, so it is checked too
+        require_price_in_band(&ctx.accounts.sol_price_feed)?;
+        require_price_in_band(&ctx.accounts.output_price_feed)?;
+        if let Some(feed) = &ctx.accounts.eur_price_feed {
+            require_price_in_band(feed)?;
+        }
+        if let Some(feed) = &ctx.accounts.cbbtc_price_feed {
+            require_price_in_band(feed)?;
+        }
+
         let elapsed = now.saturating_sub(position.last_gad_crank);
-        require!(elapsed >= MIN_GAD_CRANK_INTERVAL, LegasiError::CrankTooSoon);
+        let total_collateral_usd = calculate_collateral_value(
+            position,
+            &ctx.accounts.sol_price_feed,
+            &ctx.accounts.cbbtc_price_feed,
+            now,
+        )?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let total_borrow_usd = calculate_borrow_value(position, eur_price)?;
+        // A fixed-term loan past maturity makes the position eligible sooner
+        let max_ltv_bps = gad_max_ltv_bps(position, now);
+        let mut assessment =
+            gad::assess(total_collateral_usd, total_borrow_usd, max_ltv_bps, elapsed)?;
+        // A market selling over several cranks sells its share of what is due now and
+        // carries the rest
+        let slices = market_sale_slices(
+            ctx.accounts.market.as_deref(),
+            position,
+            &[GadProceedsMode::Swap, GadProceedsMode::Twap],
+        )?;
+        let carried_secs =
+            pace_assessment(&mut assessment, elapsed, position.gad_carried_secs, slices)?;
+        apply_repayment_schedule(
+            &ctx.accounts.repayment_schedule,
+            &mut assessment,
+            max_ltv_bps,
+            now,
+        )?;
+        // Thin-liquidity windows sell at a reduced rate
+        assessment.liquidate_fraction_bps = Throttle::load(&ctx.accounts.gad_schedule, now)?
+            .gad_fraction_bps(assessment.liquidate_fraction_bps);
 
-        // ... (LTV calculation same as above)
+        require_backstop_exhausted(&ctx.accounts.sponsor_vault, Rent::get()?.minimum_balance(0))?;
 
-        // Execute Jupiter swap: SOL → USDC
-        // CPI to Jupiter aggregator
-        let jupiter_program = &ctx.accounts.jupiter_program;
-        let _swap_accounts = vec![
-            ctx.accounts.sol_vault.to_account_info(),
-            ctx.accounts.usdc_vault.to_account_info(),
-            ctx.accounts.token_program.to_account_info(),
-            // Jupiter requires additional accounts passed via remaining_accounts
-        ];
+        // Size the LST slice (token accounts have no rent floor)
+        let asset_type = ctx.accounts.collateral_config.asset_type;
+        let lst_deposit = position
+            .collaterals
+            .iter()
+            .find(|c| c.asset_type == asset_type)
+            .ok_or(LegasiError::InsufficientCollateral)?;
+        let liquidation_split = ctx.accounts.protocol.liquidation_split;
+        let reward_bps = liquidation_split.cranker_reward_bps(gad::cranker_reward_bps(
+            assessment.ltv_bps,
+            max_ltv_bps,
+            elapsed,
+            ctx.accounts.protocol.cranker_reward_min_bps,
+            ctx.accounts.protocol.cranker_reward_max_bps,
+        ));
+        let split = gad::split_liquidation(
+            lst_deposit.amount,
+            assessment.liquidate_fraction_bps,
+            0,
+            reward_bps,
+        )?;
+        require!(split.total_deducted > 0, LegasiError::NothingToLiquidate);
+
+        // As in crank_gad_with_swap, a configured split pays the cranker and treasury
+        // shares in the LST and sells the rest
+        let (cranker_reward, treasury_fee) = if liquidation_split.is_set() {
+            (
+                split.cranker_reward,
+                liquidation_split.split_proceeds(split.to_treasury).treasury,
+            )
+        } else {
+            (0, 0)
+        };
+        let max_lst_in = split.total_deducted - cranker_reward - treasury_fee;
 
-        // Build Jupiter CPI
+        let route = ctx.accounts.collateral_config.swap_route;
         let position_key = ctx.accounts.position.key();
-        let vault_bump = ctx.bumps.sol_vault;
-        let seeds: &[&[u8]] = &[b"sol_vault", position_key.as_ref(), &[vault_bump]];
-
-        anchor_lang::solana_program::program::invoke_signed(
-            &anchor_lang::solana_program::instruction::Instruction {
-                program_id: jupiter_program.key(),
-                accounts: ctx
-                    .remaining_accounts
-                    .iter()
-                    .map(|a| anchor_lang::solana_program::instruction::AccountMeta {
-                        pubkey: a.key(),
-                        is_signer: a.is_signer,
-                        is_writable: a.is_writable,
-                    })
-                    .collect(),
-                data: jupiter_swap_data,
+        let vaults = CollateralVaults {
+            lending_program: ctx.accounts.lending_program.to_account_info(),
+            position: ctx.accounts.position.to_account_info(),
+            sol_vault: ctx.accounts.sol_vault.to_account_info(),
+            msol_vault: Some(ctx.accounts.lst_vault.to_account_info()),
+            writer: ctx.accounts.protocol_writer.to_account_info(),
+            writer_bump: ctx.bumps.protocol_writer,
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        release_proceeds(
+            &vaults,
+            asset_type,
+            ctx.accounts.cranker_lst_account.as_deref(),
+            cranker_reward,
+        )?;
+        release_proceeds(
+            &vaults,
+            asset_type,
+            ctx.accounts.treasury_lst_account.as_deref(),
+            treasury_fee,
+        )?;
+        ctx.accounts.lst_vault.reload()?;
+
+        // Swap LST through the configured route, signed by the position PDA holding the
+        // vault through lending
+        let lst_before = ctx.accounts.lst_vault.amount;
+        let output_before = ctx.accounts.output_vault.amount;
+
+        vaults.swap(
+            asset_type,
+            route,
+            &ctx.accounts.swap_program.to_account_info(),
+            route_accounts,
+            route_data,
+            max_lst_in,
+        )?;
+
+        ctx.accounts.lst_vault.reload()?;
+        ctx.accounts.output_vault.reload()?;
+        let lst_liquidated =
+            jupiter_cpi::assert_max_spent(lst_before, ctx.accounts.lst_vault.amount, max_lst_in)?;
+        let output_received = jupiter_cpi::assert_min_received(
+            output_before,
+            ctx.accounts.output_vault.amount,
+            min_out_amount,
+        )?;
+
+        // Reduce debt by the LP recovery share of what the swap returned
+        let output_usd = token_to_usd(
+            output_received,
+            ctx.accounts.output_mint.decimals,
+            ctx.accounts.output_price_feed.get_checked_twap(now)?,
+        )?;
+        let (usd_to_debt, usd_to_insurance) = liquidation_split.split_swap_output(output_usd);
+        let debt_reduction = std::cmp::min(usd_to_debt, total_borrow_usd);
+
+        // Paid-out LST valued at the SOL price, the same floor collateral is valued at
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_twap(now)?;
+        let cranker_usd = sol_to_usd(cranker_reward, sol_price)?;
+        let treasury_usd = sol_to_usd(treasury_fee, sol_price)?;
+        let liquidated_usd = output_usd
+            .saturating_add(cranker_usd)
+            .saturating_add(treasury_usd);
+
+        // The sold and paid-out LST left the position with the vault
+        vaults.settle(
+            debt_pools,
+            GadSettlement {
+                debt: GadDebtReduction::Usd {
+                    amount_usd: debt_reduction,
+                    eur_usd_price: eur_price,
+                },
+                liquidated_usd,
+                carried_secs: Some(carried_secs),
             },
-            ctx.remaining_accounts,
-            &[seeds],
         )?;
 
-        // Verify we received minimum USDC
-        ctx.accounts.usdc_vault.reload()?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(liquidated_usd),
+            -totals::usd_delta(debt_reduction),
+        )?;
+        totals::report_insurance_fee(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            usd_to_insurance,
+        )?;
+
+        if liquidation_split.is_set() {
+            emit!(GadProceedsSplit {
+                position: position_key,
+                lp_recovery_usd: usd_to_debt,
+                treasury_usd,
+                insurance_usd: usd_to_insurance,
+                cranker_usd,
+                insurance_credited: true,
+            });
+        }
+
+        emit!(GadLstSwapExecuted {
+            position: position_key,
+            lst_mint: ctx.accounts.collateral_config.mint,
+            route,
+            lst_liquidated,
+            output_mint: ctx.accounts.output_mint.key(),
+            output_received,
+            debt_reduced_usd: debt_reduction,
+            cranker: ctx.accounts.cranker.key(),
+        });
+
+        msg!(
+            "GAD LST swap via {:?}: {} LST sold, debt reduced ${}",
+            route,
+            lst_liquidated,
+            debt_reduction as f64 / USD_MULTIPLIER as f64
+        );
+        Ok(())
+    }
+
+    /// Open a Dutch auction of a position's SOL collateral against its
+    /// `borrowable_config` debt once its LTV is past the hard threshold, or once it has
+    /// no collateral left to cover that debt (see `liquidation_auction`). Permissionless.
+    /// Debt pools go in remaining_accounts (see `legasi_lending::accrue_all_interest`)
+    pub fn start_auction(ctx: Context<StartAuction>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mut projected = (**ctx.accounts.position).clone();
+        legasi_lending::accrue_all_interest(&mut projected, ctx.remaining_accounts, now)?;
+        let position = &projected;
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
         require!(
-            ctx.accounts.usdc_vault.amount >= min_out_amount,
-            LegasiError::SlippageExceeded
+            position.total_owed(asset_type)? > 0,
+            LegasiError::NoDebtToDeleverage
         );
 
-        // Use received USDC to repay debt
-        let usdc_received = ctx.accounts.usdc_vault.amount;
+        require_price_in_band(&ctx.accounts.sol_price_feed)?;
+        if let Some(feed) = &ctx.accounts.eur_price_feed {
+            require_price_in_band(feed)?;
+        }
+        if let Some(feed) = &ctx.accounts.cbbtc_price_feed {
+            require_price_in_band(feed)?;
+        }
+        let collateral_usd = calculate_collateral_value(
+            position,
+            &ctx.accounts.sol_price_feed,
+            &ctx.accounts.cbbtc_price_feed,
+            now,
+        )?;
+        let borrow_usd =
+            calculate_borrow_value(position, eur_usd_price(&ctx.accounts.eur_price_feed, now)?)?;
+        // Debt without collateral is past any threshold: the auction settles straight
+        // into a write-off
+        let ltv_bps = gad::ltv_bps(borrow_usd, collateral_usd).unwrap_or(u64::MAX);
+        let throttle = Throttle::load(&ctx.accounts.gad_schedule, now)?;
+        require!(
+            ltv_bps >= throttle.hard_threshold_bps(LIQUIDATION_AUCTION_THRESHOLD_BPS),
+            LegasiError::NotLiquidatable
+        );
 
-        // Update position (reduce debt by USDC received)
-        let position = &mut ctx.accounts.position;
-        for borrow in position.borrows.iter_mut() {
-            if borrow.asset_type == AssetType::USDC {
-                let total_debt = borrow
-                    .amount
-                    .checked_add(borrow.accrued_interest)
-                    .unwrap_or(0);
-                let reduction = std::cmp::min(usdc_received, total_debt);
+        let auction = &mut ctx.accounts.auction;
+        auction.position = ctx.accounts.position.key();
+        auction.starter = ctx.accounts.starter.key();
+        auction.started_at = now;
+        auction.ends_at = now
+            .checked_add(LIQUIDATION_AUCTION_DURATION)
+            .ok_or(LegasiError::MathOverflow)?;
+        auction.ltv_at_start_bps = ltv_bps;
+        auction.collateral_sold = 0;
+        auction.debt_repaid = 0;
+        auction.bump = ctx.bumps.auction;
+
+        emit!(LiquidationAuctionStarted {
+            position: auction.position,
+            auction: auction.key(),
+            asset_type,
+            ltv_bps,
+            ends_at: auction.ends_at,
+        });
 
-                let interest_reduction = std::cmp::min(reduction, borrow.accrued_interest);
-                borrow.accrued_interest =
-                    borrow.accrued_interest.saturating_sub(interest_reduction);
-                borrow.amount = borrow
-                    .amount
-                    .saturating_sub(reduction.saturating_sub(interest_reduction));
-                break;
-            }
+        msg!("Liquidation auction started against {:?} debt", asset_type);
+        Ok(())
+    }
+
+    /// Repay up to `amount` of the auctioned debt from the bidder's account for SOL
+    /// collateral at the current ask, receiving at least `min_lamports_out`
+    /// A bid the collateral can't fill is filled in part. Debt pools go in
+    /// remaining_accounts (see `legasi_lending::accrue_all_interest`)
+    pub fn bid_auction(ctx: Context<BidAuction>, amount: u64, min_lamports_out: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_twap(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+
+        // The ask follows the oracle, so never sell on a flash-crash print
+        require_price_in_band(&ctx.accounts.sol_price_feed)?;
+        if let Some(feed) = &ctx.accounts.eur_price_feed {
+            require_price_in_band(feed)?;
         }
+        // Settled against the pools' indexes, exactly as lending settles it below
+        let mut projected = (**ctx.accounts.position).clone();
+        legasi_lending::accrue_all_interest(&mut projected, ctx.remaining_accounts, now)?;
+
+        let position = &projected;
+        let owed = position.total_owed(asset_type)?;
+        require!(owed > 0, LegasiError::NoDebtToDeleverage);
+        let requested = std::cmp::min(amount, owed);
+        let requested_usd = asset_type.debt_to_usd(requested, eur_price)?;
+
+        // Vault must stay rent-exempt, so that balance is the collateral floor
+        let collateral_floor = Rent::get()?.minimum_balance(0);
+        let sol_collateral = position
+            .collaterals
+            .iter()
+            .find(|c| c.asset_type == AssetType::SOL)
+            .map_or(0, |c| c.amount)
+            .min(
+                ctx.accounts
+                    .sol_vault
+                    .lamports()
+                    .saturating_sub(collateral_floor),
+            );
+        let discount_bps = ctx.accounts.auction.discount_bps(now);
+        let ask_price = ctx.accounts.auction.ask_price(sol_price, now);
+        let (repay_usd, lamports) = size_bid(requested_usd, sol_collateral, ask_price)?;
+        let repaid = if repay_usd == requested_usd {
+            requested
+        } else {
+            std::cmp::min(asset_type.usd_to_debt(repay_usd, eur_price)?, requested)
+        };
+        require!(repaid > 0 && lamports > 0, LegasiError::NothingToLiquidate);
+        require!(lamports >= min_lamports_out, LegasiError::SlippageExceeded);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.bidder_token_account.to_account_info(),
+                    to: ctx.accounts.repay_vault.to_account_info(),
+                    authority: ctx.accounts.bidder.to_account_info(),
+                },
+            ),
+            repaid,
+        )?;
+        let vaults = CollateralVaults {
+            lending_program: ctx.accounts.lending_program.to_account_info(),
+            position: ctx.accounts.position.to_account_info(),
+            sol_vault: ctx.accounts.sol_vault.to_account_info(),
+            msol_vault: None,
+            writer: ctx.accounts.protocol_writer.to_account_info(),
+            writer_bump: ctx.bumps.protocol_writer,
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        vaults.release(
+            AssetType::SOL,
+            &ctx.accounts.bidder.to_account_info(),
+            lamports,
+        )?;
 
-        position.last_gad_crank = now;
-        position.reputation.gad_events = position.reputation.gad_events.saturating_add(1);
+        let sold_usd = sol_to_usd(lamports, sol_price)?;
+        let settlement = GadSettlement {
+            debt: GadDebtReduction::Asset {
+                asset_type,
+                amount: repaid,
+            },
+            liquidated_usd: sold_usd,
+            carried_secs: None,
+        };
+        let (interest_paid, principal_paid) = projected.apply_gad_settlement(&settlement, now)?;
+        vaults.settle(ctx.remaining_accounts, settlement)?;
+
+        let auction = &mut ctx.accounts.auction;
+        auction.collateral_sold = auction.collateral_sold.saturating_add(lamports);
+        auction.debt_repaid = auction.debt_repaid.saturating_add(repaid);
+
+        let core_program = ctx.accounts.core_program.to_account_info();
+        let protocol = ctx.accounts.protocol.to_account_info();
+        let lp_program = ctx.accounts.lp_program.to_account_info();
+        let lp_pool = ctx.accounts.lp_pool.to_account_info();
+        let writer = ctx.accounts.protocol_writer.to_account_info();
+        let writer_bump = ctx.bumps.protocol_writer;
+
+        legasi_lp::report_borrowed(
+            &lp_program,
+            &lp_pool,
+            &writer,
+            writer_bump,
+            -totals::usd_delta(principal_paid),
+        )?;
+        // The interest repaid is credited to LPs, less the insurance cut
+        legasi_lp::accrue_interest(
+            &lp_program,
+            &lp_pool,
+            &writer,
+            writer_bump,
+            interest_paid,
+            0,
+        )?;
+        totals::report_insurance_fee(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            interest::calculate_insurance_fee(interest_paid),
+        )?;
+        totals::report(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            -totals::usd_delta(sold_usd),
+            -totals::usd_delta(asset_type.debt_to_usd(principal_paid, eur_price)?),
+        )?;
+        totals::report_sol_collateral(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            -(lamports as i64),
+        )?;
 
-        emit!(GadSwapExecuted {
-            position: ctx.accounts.position.key(),
-            sol_liquidated: 0, // TODO: track actual amount
-            usdc_received,
-            cranker: ctx.accounts.cranker.key(),
+        emit!(LiquidationAuctionBid {
+            auction: ctx.accounts.auction.key(),
+            bidder: ctx.accounts.bidder.key(),
+            repaid,
+            collateral_lamports: lamports,
+            discount_bps,
         });
 
-        msg!("GAD swap executed: received {} USDC", usdc_received);
+        msg!(
+            "Auction bid: repaid {} {:?} for {} lamports at {} bps discount",
+            repaid,
+            asset_type,
+            lamports,
+            discount_bps
+        );
+        Ok(())
+    }
+
+    /// Settle a liquidation auction once its discount has run out or the position's SOL
+    /// is sold (permissionless). With no collateral left, the rest of the debt is bad
+    /// debt: written off the pool, the insurance fund covering what it can. Debt pools
+    /// go in remaining_accounts (see `legasi_lending::accrue_all_interest`)
+    pub fn settle_auction(ctx: Context<SettleAuction>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let insurance_fund = ctx.accounts.protocol.insurance_fund;
+        // Settled against the pools' indexes, exactly as lending settles it below
+        let mut position = (**ctx.accounts.position).clone();
+        legasi_lending::accrue_all_interest(&mut position, ctx.remaining_accounts, now)?;
+
+        let sol_left = position
+            .collaterals
+            .iter()
+            .any(|c| c.asset_type == AssetType::SOL && c.amount > 0);
+        require!(
+            now >= ctx.accounts.auction.ends_at || !sol_left,
+            LegasiError::LiquidationAuctionRunning
+        );
+
+        // The interest on bad debt is dropped, its principal written off
+        let mut bad_debt = 0;
+        if position.collaterals.is_empty() {
+            let settlement = GadSettlement {
+                debt: GadDebtReduction::Asset {
+                    asset_type,
+                    amount: position.total_owed(asset_type)?,
+                },
+                liquidated_usd: 0,
+                carried_secs: None,
+            };
+            bad_debt = position.apply_gad_settlement(&settlement, now)?.1;
+            legasi_lending::settle_gad(
+                &ctx.accounts.lending_program.to_account_info(),
+                legasi_lending::cpi::accounts::SettleGad {
+                    position: ctx.accounts.position.to_account_info(),
+                    gad_writer: ctx.accounts.protocol_writer.to_account_info(),
+                },
+                ctx.remaining_accounts,
+                ctx.bumps.protocol_writer,
+                settlement,
+            )?;
+        }
+        let covered = ctx
+            .accounts
+            .lp_pool
+            .insurance_cover(bad_debt, insurance_fund);
+
+        let core_program = ctx.accounts.core_program.to_account_info();
+        let protocol = ctx.accounts.protocol.to_account_info();
+        let writer = ctx.accounts.protocol_writer.to_account_info();
+        let writer_bump = ctx.bumps.protocol_writer;
+        totals::report_insurance_payout(&core_program, &protocol, &writer, writer_bump, covered)?;
+        legasi_lp::write_off_bad_debt(
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.lp_pool.to_account_info(),
+            &writer,
+            writer_bump,
+            bad_debt,
+            covered,
+        )?;
+        let bad_debt_usd = asset_type.debt_to_usd(bad_debt, eur_price)?;
+        totals::report(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            0,
+            -totals::usd_delta(bad_debt_usd),
+        )?;
+        totals::report_bad_debt(&core_program, &protocol, &writer, writer_bump, bad_debt_usd)?;
+
+        let auction = &ctx.accounts.auction;
+        emit!(LiquidationAuctionSettled {
+            position: auction.position,
+            auction: auction.key(),
+            asset_type,
+            collateral_sold: auction.collateral_sold,
+            debt_repaid: auction.debt_repaid,
+            bad_debt,
+            insurance_covered: covered,
+        });
+
+        msg!(
+            "Liquidation auction settled: {} lamports sold, {} written off",
+            auction.collateral_sold,
+            bad_debt
+        );
         Ok(())
     }
 }
 
 // ========== HELPER FUNCTIONS ==========
 
-fn calculate_collateral_value(position: &Position, sol_price_feed: &PriceFeed) -> Result<u64> {
-    let mut total_usd: u64 = 0;
+/// Honor a lending repayment schedule, if the owner committed to one
+/// A kept schedule suppresses GAD below the hard threshold; a lapsed one enlarges the slice
+fn apply_repayment_schedule(
+    schedule: &AccountInfo,
+    assessment: &mut gad::GadAssessment,
+    max_ltv_bps: u64,
+    now: i64,
+) -> Result<()> {
+    if schedule.owner != &legasi_lending::ID || schedule.data_is_empty() {
+        return Ok(());
+    }
+    let schedule = RepaymentSchedule::try_deserialize(&mut &schedule.try_borrow_data()?[..])?;
 
-    for deposit in &position.collaterals {
-        match deposit.asset_type {
-            AssetType::SOL => {
-                let value = (deposit.amount as u128)
-                    .checked_mul(sol_price_feed.price_usd_6dec as u128)
-                    .ok_or(LegasiError::MathOverflow)?
-                    .checked_div(LAMPORTS_PER_SOL as u128)
-                    .ok_or(LegasiError::MathOverflow)? as u64;
-                total_usd = total_usd
-                    .checked_add(value)
-                    .ok_or(LegasiError::MathOverflow)?;
-            }
-            _ => {}
-        }
+    if schedule.is_lapsed(now) {
+        assessment.liquidate_fraction_bps =
+            penalized_fraction_bps(assessment.liquidate_fraction_bps);
+    } else {
+        require!(
+            assessment.ltv_bps >= max_ltv_bps + GAD_HARD_THRESHOLD_BPS as u64,
+            LegasiError::GadSuppressedBySchedule
+        );
     }
+    Ok(())
+}
 
-    Ok(total_usd)
+/// Cranks the sale is spread over under the GAD proceeds preference of `market`, which
+/// must cover the position and allow this crank (`allowed`). Without a market, any
+/// crank may run and sells everything due at once
+fn market_sale_slices(
+    market: Option<&Market>,
+    position: &Position,
+    allowed: &[GadProceedsMode],
+) -> Result<u8> {
+    let Some(market) = market else {
+        return Ok(1);
+    };
+    require!(
+        market_covers(
+            market.collateral_asset,
+            market.borrow_asset,
+            position.collaterals.iter().map(|c| c.asset_type),
+            position.borrows.iter().map(|b| b.asset_type),
+        ),
+        LegasiError::EModeMismatch
+    );
+    require!(
+        allowed.contains(&market.gad_proceeds),
+        LegasiError::GadProceedsModeMismatch
+    );
+    Ok(market.gad_sale_slices())
+}
+
+/// Re-size `assessment` to the share of the time due (`elapsed` plus `carried_secs`)
+/// liquidated now over `slices` cranks. Returns the time carried to the next crank
+fn pace_assessment(
+    assessment: &mut gad::GadAssessment,
+    elapsed: i64,
+    carried_secs: i64,
+    slices: u8,
+) -> Result<i64> {
+    let (now_secs, carried_secs) = gad::pace_sale(elapsed, carried_secs, slices);
+    assessment.liquidate_fraction_bps = gad::liquidate_fraction_bps(assessment.rate_bps, now_secs)?;
+    Ok(carried_secs)
+}
+
+/// Move `amount` lamports out of a system-owned PDA of this program (no-op for 0)
+fn pay_from_pda<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    invoke_signed(
+        &system_instruction::transfer(from.key, to.key, amount),
+        &[from.clone(), to.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    Ok(())
 }
 
-fn calculate_borrow_value(position: &Position) -> Result<u64> {
+/// A position's lending accounts GAD sells its collateral through, signing with the
+/// protocol writer PDA (see `legasi_lending::gad_settlement`)
+struct CollateralVaults<'info> {
+    lending_program: AccountInfo<'info>,
+    position: AccountInfo<'info>,
+    sol_vault: AccountInfo<'info>,
+    /// The position's mSOL vault, for cranks selling staked SOL
+    msol_vault: Option<AccountInfo<'info>>,
+    writer: AccountInfo<'info>,
+    writer_bump: u8,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+}
+
+impl<'info> CollateralVaults<'info> {
+    /// Pay `amount` of the position's `asset_type` collateral to `recipient` (no-op for 0)
+    fn release(
+        &self,
+        asset_type: AssetType,
+        recipient: &AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        legasi_lending::release_gad_collateral(
+            &self.lending_program,
+            legasi_lending::cpi::accounts::ReleaseGadCollateral {
+                position: self.position.clone(),
+                sol_vault: self.sol_vault.clone(),
+                msol_vault: self.msol_vault.clone(),
+                recipient: recipient.clone(),
+                gad_writer: self.writer.clone(),
+                token_program: self.token_program.clone(),
+                system_program: self.system_program.clone(),
+            },
+            self.writer_bump,
+            asset_type,
+            amount,
+        )
+    }
+
+    /// Sell up to `max_in` of the position's `asset_type` collateral through `route`
+    fn swap(
+        &self,
+        asset_type: AssetType,
+        route: SwapRoute,
+        swap_program: &AccountInfo<'info>,
+        route_accounts: &[AccountInfo<'info>],
+        route_data: Vec<u8>,
+        max_in: u64,
+    ) -> Result<()> {
+        legasi_lending::swap_gad_collateral(
+            &self.lending_program,
+            legasi_lending::cpi::accounts::SwapGadCollateral {
+                position: self.position.clone(),
+                sol_vault: self.sol_vault.clone(),
+                msol_vault: self.msol_vault.clone(),
+                swap_program: swap_program.clone(),
+                gad_writer: self.writer.clone(),
+            },
+            route_accounts,
+            self.writer_bump,
+            asset_type,
+            route,
+            route_data,
+            max_in,
+        )
+    }
+
+    /// Book `settlement` on the position, settling every borrow's interest against
+    /// `debt_pools` first
+    fn settle(&self, debt_pools: &[AccountInfo<'info>], settlement: GadSettlement) -> Result<()> {
+        legasi_lending::settle_gad(
+            &self.lending_program,
+            legasi_lending::cpi::accounts::SettleGad {
+                position: self.position.clone(),
+                gad_writer: self.writer.clone(),
+            },
+            debt_pools,
+            self.writer_bump,
+            settlement,
+        )
+    }
+}
+
+/// Pay `amount` of the position's LST to a proceeds account, required once the
+/// liquidation split pays anything (no-op for 0)
+fn release_proceeds<'info>(
+    vaults: &CollateralVaults<'info>,
+    asset_type: AssetType,
+    to: Option<&Account<'info, TokenAccount>>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let to = to.ok_or(LegasiError::MissingProceedsAccount)?;
+    vaults.release(asset_type, &to.to_account_info(), amount)
+}
+
+/// Move `amount` lamports from the sponsor into the backstop vault
+fn deposit_backstop<'info>(
+    sponsor: &Signer<'info>,
+    sponsor_vault: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    invoke(
+        &system_instruction::transfer(sponsor.key, sponsor_vault.key, amount),
+        &[
+            sponsor.to_account_info(),
+            sponsor_vault.to_account_info(),
+            system_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Swap cranks sell the borrower's collateral, so they wait until `crank_gad` has
+/// used up any sponsor backstop
+fn require_backstop_exhausted(sponsor_vault: &AccountInfo, floor: u64) -> Result<()> {
+    require!(
+        sponsor_vault.lamports() <= floor,
+        LegasiError::BackstopNotExhausted
+    );
+    Ok(())
+}
+
+/// Collateral value in USD (6 decimals), each asset at its own feed's TWAP (failing on
+/// a stale feed). A
+/// position holding cbBTC needs `cbbtc_price_feed`, so a cranker can't leave it out to
+/// make the position look underwater
+fn calculate_collateral_value(
+    position: &Position,
+    sol_price_feed: &PriceFeed,
+    cbbtc_price_feed: &Option<Box<Account<PriceFeed>>>,
+    now: i64,
+) -> Result<u64> {
+    let sol_price_feed = sol_price_feed.at_twap(now)?;
+    let cbbtc_price_feed = cbbtc_price_feed
+        .as_ref()
+        .map(|feed| feed.at_twap(now))
+        .transpose()?;
+    valuation::value_collaterals(
+        position
+            .collaterals
+            .iter()
+            .map(|c| (c.asset_type, c.amount)),
+        std::iter::once(&sol_price_feed).chain(cbbtc_price_feed.as_ref()),
+        valuation::PriceBook::default(),
+    )
+}
+
+/// Split the remaining accounts into the position's debt pools, a `Borrowable`,
+/// `LpPool` pair per borrow (see `legasi_lending::accrue_all_interest`), and the rest
+fn split_debt_pools<'a, 'info>(
+    position: &Position,
+    remaining: &'a [AccountInfo<'info>],
+) -> Result<(&'a [AccountInfo<'info>], &'a [AccountInfo<'info>])> {
+    let count = 2 * position.borrows.len();
+    require!(remaining.len() >= count, LegasiError::MissingDebtPool);
+    Ok(remaining.split_at(count))
+}
+
+/// LTV (bps) GAD starts from: the SOL max LTV, lower while a fixed-term loan is past
+/// maturity (see `legasi_lending::fixed_term`)
+fn gad_max_ltv_bps(position: &Position, now: i64) -> u64 {
+    let matured = position
+        .borrows
+        .iter()
+        .any(|b| fixed_term::is_matured(b.maturity, now));
+    fixed_term::gad_max_ltv_bps(DEFAULT_SOL_MAX_LTV_BPS as u64, matured)
+}
+
+fn calculate_borrow_value(position: &Position, eur_usd_price: Option<u64>) -> Result<u64> {
     let mut total_usd: u64 = 0;
 
     for borrow in &position.borrows {
         match borrow.asset_type {
             AssetType::USDC | AssetType::EURC => {
-                let value = borrow
+                let owed = borrow
                     .amount
                     .checked_add(borrow.accrued_interest)
                     .ok_or(LegasiError::MathOverflow)?;
                 total_usd = total_usd
-                    .checked_add(value)
+                    .checked_add(borrow.asset_type.debt_to_usd(owed, eur_usd_price)?)
                     .ok_or(LegasiError::MathOverflow)?;
             }
             _ => {}
@@ -402,6 +1412,48 @@ fn calculate_borrow_value(position: &Position) -> Result<u64> {
     Ok(total_usd)
 }
 
+/// Fail if `feed`'s price is outside its deviation band. The anomaly event stays in the
+/// failed transaction's logs, so monitoring sees the print that was refused
+fn require_price_in_band(feed: &PriceFeed) -> Result<()> {
+    if feed.within_deviation_band() {
+        return Ok(());
+    }
+    emit!(PriceAnomalyDetected {
+        asset_type: feed.asset_type,
+        price_usd_6dec: feed.price_usd_6dec,
+        reference_price_usd_6dec: feed.reference_price(),
+        deviation_bps: feed.deviation_bps(),
+        max_deviation_bps: feed.max_deviation_bps,
+    });
+    err!(LegasiError::PriceDeviationTooHigh)
+}
+
+/// EUR/USD TWAP (6 decimals) from the optional EURC price feed, for valuing EURC debt
+fn eur_usd_price(feed: &Option<Box<Account<PriceFeed>>>, now: i64) -> Result<Option<u64>> {
+    feed.as_ref()
+        .map(|feed| feed.get_checked_twap(now))
+        .transpose()
+}
+
+/// SOL backstop posted by a position's sponsor
+#[account]
+#[derive(InitSpace)]
+pub struct Sponsorship {
+    pub position: Pubkey,
+    /// Posted the backstop and gets the rest back when the sponsorship ends
+    pub sponsor: Pubkey,
+    pub bump: u8,
+}
+
+// Sponsor backstop used by a GAD crank
+#[event]
+pub struct SponsorBackstopDrawn {
+    pub position: Pubkey,
+    pub lamports: u64,
+    /// Backstop balance left (lamports, including the rent floor)
+    pub remaining: u64,
+}
+
 // GAD swap event
 #[event]
 pub struct GadSwapExecuted {
@@ -411,74 +1463,621 @@ pub struct GadSwapExecuted {
     pub cranker: Pubkey,
 }
 
+// GAD LST swap event
+#[event]
+pub struct GadLstSwapExecuted {
+    pub position: Pubkey,
+    pub lst_mint: Pubkey,
+    pub route: SwapRoute,
+    pub lst_liquidated: u64,
+    pub output_mint: Pubkey,
+    pub output_received: u64,
+    pub debt_reduced_usd: u64,
+    pub cranker: Pubkey,
+}
+
+/// Where a crank's liquidated value went under a configured `LiquidationSplit` (USD, 6 decimals)
+#[event]
+pub struct GadProceedsSplit {
+    pub position: Pubkey,
+    /// Zero for crank_gad, which leaves the LP recovery share on the position
+    pub lp_recovery_usd: u64,
+    pub treasury_usd: u64,
+    pub insurance_usd: u64,
+    pub cranker_usd: u64,
+    /// False for crank_gad, whose treasury holds the insurance share in SOL
+    pub insurance_credited: bool,
+}
+
+#[event]
+pub struct LiquidationAuctionStarted {
+    pub position: Pubkey,
+    pub auction: Pubkey,
+    /// Debt the auction repays
+    pub asset_type: AssetType,
+    pub ltv_bps: u64,
+    pub ends_at: i64,
+}
+
+#[event]
+pub struct LiquidationAuctionBid {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub repaid: u64,
+    pub collateral_lamports: u64,
+    /// Discount to the oracle price the bid was filled at
+    pub discount_bps: u64,
+}
+
+/// A liquidation auction closed; `bad_debt` is the principal written off the pool
+#[event]
+pub struct LiquidationAuctionSettled {
+    pub position: Pubkey,
+    pub auction: Pubkey,
+    pub asset_type: AssetType,
+    pub collateral_sold: u64,
+    pub debt_repaid: u64,
+    pub bad_debt: u64,
+    pub insurance_covered: u64,
+}
+
 // ========== ACCOUNTS ==========
 
 #[derive(Accounts)]
 pub struct ConfigureGad<'info> {
     #[account(
         mut,
-        seeds = [b"position", owner.key().as_ref()],
+        seeds = [POSITION_SEED, owner.key().as_ref()],
+        bump = position.bump,
+        seeds::program = legasi_lending::ID,
+        has_one = owner
+    )]
+    pub position: Box<Account<'info, Position>>,
+    pub owner: Signer<'info>,
+    /// CHECK: PDA that signs the GAD opt-in on lending
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub lending_program: Program<'info, LegasiLending>,
+}
+
+#[derive(Accounts)]
+pub struct SponsorPosition<'info> {
+    #[account(
+        seeds = [POSITION_SEED, owner.key().as_ref()],
         bump = position.bump,
+        seeds::program = legasi_lending::ID,
         has_one = owner
     )]
-    pub position: Account<'info, Position>,
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + Sponsorship::INIT_SPACE,
+        seeds = [SPONSORSHIP_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub sponsorship: Account<'info, Sponsorship>,
+    /// CHECK: Sponsor backstop vault PDA (system-owned, holds lamports)
+    #[account(
+        mut,
+        seeds = [SPONSOR_VAULT_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub sponsor_vault: UncheckedAccount<'info>,
+    /// Position owner, accepting the sponsor
     pub owner: Signer<'info>,
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpBackstop<'info> {
+    #[account(
+        seeds = [SPONSORSHIP_SEED, sponsorship.position.as_ref()],
+        bump = sponsorship.bump,
+        has_one = sponsor
+    )]
+    pub sponsorship: Account<'info, Sponsorship>,
+    /// CHECK: Sponsor backstop vault PDA
+    #[account(
+        mut,
+        seeds = [SPONSOR_VAULT_SEED, sponsorship.position.as_ref()],
+        bump
+    )]
+    pub sponsor_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EndSponsorship<'info> {
+    #[account(
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        close = sponsor,
+        seeds = [SPONSORSHIP_SEED, position.key().as_ref()],
+        bump = sponsorship.bump,
+        has_one = sponsor,
+        has_one = position
+    )]
+    pub sponsorship: Account<'info, Sponsorship>,
+    /// CHECK: Sponsor backstop vault PDA
+    #[account(
+        mut,
+        seeds = [SPONSOR_VAULT_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub sponsor_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct CrankGad<'info> {
+    /// Written by lending through the GAD CPIs
     #[account(
         mut,
-        seeds = [b"position", position.owner.as_ref()],
-        bump = position.bump
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        seeds = [PROTOCOL_SEED],
+        bump = protocol.bump,
+        seeds::program = legasi_core::ID,
+        has_one = treasury
     )]
-    pub position: Account<'info, Position>,
-    #[account(seeds = [b"protocol"], bump = protocol.bump, has_one = treasury)]
     pub protocol: Account<'info, Protocol>,
-    /// CHECK: SOL vault PDA
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lending_program: Program<'info, LegasiLending>,
+    /// CHECK: Lending SOL vault PDA
     #[account(
         mut,
-        seeds = [b"sol_vault", position.key().as_ref()],
-        bump
+        seeds = [SOL_VAULT_SEED, position.key().as_ref()],
+        bump,
+        seeds::program = legasi_lending::ID
     )]
     pub sol_vault: UncheckedAccount<'info>,
+    /// CHECK: Sponsor backstop vault PDA - drawn before sol_vault, may be empty
+    #[account(
+        mut,
+        seeds = [SPONSOR_VAULT_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub sponsor_vault: UncheckedAccount<'info>,
     /// CHECK: Treasury
     #[account(mut)]
     pub treasury: UncheckedAccount<'info>,
-    #[account(seeds = [b"price", &[AssetType::SOL as u8]], bump)]
+    /// CHECK: Lending repayment schedule PDA - may not exist, read in apply_repayment_schedule
+    #[account(
+        seeds = [REPAYMENT_SCHEDULE_SEED, position.key().as_ref()],
+        bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub repayment_schedule: UncheckedAccount<'info>,
+    /// CHECK: Core GAD schedule PDA - may not exist, read by `Throttle::load`
+    #[account(seeds = [GAD_SCHEDULE_SEED], bump, seeds::program = legasi_core::ID)]
+    pub gad_schedule: UncheckedAccount<'info>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
     pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once the position holds EURC debt
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// cbBTC price feed (owned by core program), required once the position holds cbBTC
+    #[account(constraint = cbbtc_price_feed.asset_type == AssetType::CbBTC @ LegasiError::InvalidOracle)]
+    pub cbbtc_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Market covering the position, whose GAD proceeds preference the crank follows
+    #[account(
+        seeds = [MARKET_SEED, &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub market: Option<Box<Account<'info, Market>>>,
     #[account(mut)]
     pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 /// Accounts for GAD with Jupiter swap
 #[derive(Accounts)]
 pub struct CrankGadWithSwap<'info> {
+    /// Written by lending through the GAD CPIs
     #[account(
         mut,
-        seeds = [b"position", position.owner.as_ref()],
-        bump = position.bump
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump,
+        seeds::program = legasi_lending::ID
     )]
-    pub position: Account<'info, Position>,
-    #[account(seeds = [b"protocol"], bump = protocol.bump)]
+    pub position: Box<Account<'info, Position>>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
     pub protocol: Account<'info, Protocol>,
-    /// CHECK: SOL vault PDA (source for swap)
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lending_program: Program<'info, LegasiLending>,
+    /// CHECK: Lending SOL vault PDA (source for swap)
     #[account(
         mut,
-        seeds = [b"sol_vault", position.key().as_ref()],
-        bump
+        seeds = [SOL_VAULT_SEED, position.key().as_ref()],
+        bump,
+        seeds::program = legasi_lending::ID
     )]
     pub sol_vault: UncheckedAccount<'info>,
-    /// USDC vault to receive swap output
-    #[account(mut)]
-    pub usdc_vault: Account<'info, TokenAccount>,
+    /// CHECK: Sponsor backstop vault PDA - must be used up by crank_gad first
+    #[account(seeds = [SPONSOR_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sponsor_vault: UncheckedAccount<'info>,
+    /// Borrowable config of USDC, the debt the swap output repays (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID,
+        constraint = borrowable_config.asset_type == AssetType::USDC @ LegasiError::AssetNotSupported
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    /// USDC LP pool (outstanding borrows and interest updated via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// USDC LP pool vault, receives the swap output
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, borrowable_config.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub usdc_vault: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Treasury, paid its liquidation split share in SOL
+    #[account(mut, address = protocol.treasury)]
+    pub treasury: UncheckedAccount<'info>,
+    /// CHECK: Lending repayment schedule PDA - may not exist, read in apply_repayment_schedule
+    #[account(
+        seeds = [REPAYMENT_SCHEDULE_SEED, position.key().as_ref()],
+        bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub repayment_schedule: UncheckedAccount<'info>,
+    /// CHECK: Core GAD schedule PDA - may not exist, read by `Throttle::load`
+    #[account(seeds = [GAD_SCHEDULE_SEED], bump, seeds::program = legasi_core::ID)]
+    pub gad_schedule: UncheckedAccount<'info>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once the position holds EURC debt
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// cbBTC price feed (owned by core program), required once the position holds cbBTC
+    #[account(constraint = cbbtc_price_feed.asset_type == AssetType::CbBTC @ LegasiError::InvalidOracle)]
+    pub cbbtc_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Market covering the position, whose GAD proceeds preference the crank follows
+    #[account(
+        seeds = [MARKET_SEED, &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub market: Option<Box<Account<'info, Market>>>,
     /// CHECK: Jupiter Aggregator v6
-    #[account(address = jupiter::ID)]
+    #[account(address = jupiter_cpi::ID)]
     pub jupiter_program: UncheckedAccount<'info>,
+    pub lp_program: Program<'info, LegasiLp>,
     #[account(mut)]
     pub cranker: Signer<'info>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
-    // Additional Jupiter accounts passed via remaining_accounts
+    // Debt pools, then additional Jupiter accounts, passed via remaining_accounts
+}
+
+#[derive(Accounts)]
+pub struct CrankGadLstWithSwap<'info> {
+    /// Written by lending through the GAD CPIs
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub position: Box<Account<'info, Position>>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lending_program: Program<'info, LegasiLending>,
+    /// Collateral config of the LST being sold (carries the swap route)
+    pub collateral_config: Box<Account<'info, Collateral>>,
+    /// CHECK: Lending SOL vault PDA
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, position.key().as_ref()],
+        bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// CHECK: Sponsor backstop vault PDA - must be used up by crank_gad first
+    #[account(seeds = [SPONSOR_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sponsor_vault: UncheckedAccount<'info>,
+    /// Lending mSOL vault (swap input), held by the position PDA, which signs the swap
+    #[account(
+        mut,
+        seeds = [MSOL_VAULT_SEED, position.key().as_ref()],
+        bump,
+        seeds::program = legasi_lending::ID,
+        token::authority = position,
+        constraint = lst_vault.mint == collateral_config.mint @ LegasiError::InvalidAmount
+    )]
+    pub lst_vault: Box<Account<'info, TokenAccount>>,
+    /// Vault receiving the swap output (USDC via Jupiter, wSOL via Sanctum)
+    #[account(mut)]
+    pub output_vault: Box<Account<'info, TokenAccount>>,
+    /// Treasury's account for the LST, required while the liquidation split pays the treasury
+    #[account(
+        mut,
+        constraint = treasury_lst_account.owner == protocol.treasury @ LegasiError::Unauthorized,
+        constraint = treasury_lst_account.mint == collateral_config.mint @ LegasiError::InvalidAmount
+    )]
+    pub treasury_lst_account: Option<Box<Account<'info, TokenAccount>>>,
+    /// Cranker's account for the LST, required while the liquidation split pays the cranker
+    #[account(
+        mut,
+        constraint = cranker_lst_account.owner == cranker.key() @ LegasiError::Unauthorized,
+        constraint = cranker_lst_account.mint == collateral_config.mint @ LegasiError::InvalidAmount
+    )]
+    pub cranker_lst_account: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(address = output_vault.mint)]
+    pub output_mint: Box<Account<'info, Mint>>,
+    /// Price feed of the output asset (owned by core program, keyed by mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, output_mint.key().as_ref()],
+        bump = output_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub output_price_feed: Box<Account<'info, PriceFeed>>,
+    /// CHECK: Lending repayment schedule PDA - may not exist, read in apply_repayment_schedule
+    #[account(
+        seeds = [REPAYMENT_SCHEDULE_SEED, position.key().as_ref()],
+        bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub repayment_schedule: UncheckedAccount<'info>,
+    /// CHECK: Core GAD schedule PDA - may not exist, read by `Throttle::load`
+    #[account(seeds = [GAD_SCHEDULE_SEED], bump, seeds::program = legasi_core::ID)]
+    pub gad_schedule: UncheckedAccount<'info>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// EURC price feed (owned by core program), required once the position holds EURC debt
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// cbBTC price feed (owned by core program), required once the position holds cbBTC
+    #[account(constraint = cbbtc_price_feed.asset_type == AssetType::CbBTC @ LegasiError::InvalidOracle)]
+    pub cbbtc_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Market covering the position, whose GAD proceeds preference the crank follows
+    #[account(
+        seeds = [MARKET_SEED, &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub market: Option<Box<Account<'info, Market>>>,
+    /// CHECK: Jupiter or Sanctum router - must match the collateral's swap route
+    #[account(address = collateral_config.swap_route.program_id() @ LegasiError::InvalidSwapProgram)]
+    pub swap_program: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    // Debt pools, then route accounts, passed via remaining_accounts
+}
+
+#[derive(Accounts)]
+pub struct StartAuction<'info> {
+    #[account(
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub position: Box<Account<'info, Position>>,
+    /// Borrowable config of the debt auctioned (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    #[account(
+        init,
+        payer = starter,
+        space = 8 + LiquidationAuction::INIT_SPACE,
+        seeds = [
+            LIQUIDATION_AUCTION_SEED,
+            position.key().as_ref(),
+            borrowable_config.mint.as_ref()
+        ],
+        bump
+    )]
+    pub auction: Account<'info, LiquidationAuction>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once the position holds EURC debt
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// cbBTC price feed (owned by core program), required once the position holds cbBTC
+    #[account(constraint = cbbtc_price_feed.asset_type == AssetType::CbBTC @ LegasiError::InvalidOracle)]
+    pub cbbtc_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: Core GAD schedule PDA - may not exist, read by `Throttle::load`
+    #[account(seeds = [GAD_SCHEDULE_SEED], bump, seeds::program = legasi_core::ID)]
+    pub gad_schedule: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub starter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BidAuction<'info> {
+    /// Written by lending through the GAD CPIs
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        seeds = [
+            LIQUIDATION_AUCTION_SEED,
+            position.key().as_ref(),
+            borrowable_config.mint.as_ref()
+        ],
+        bump = auction.bump,
+        has_one = position
+    )]
+    pub auction: Account<'info, LiquidationAuction>,
+    /// Borrowable config of the debt auctioned (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    /// LP pool for the repaid asset (outstanding borrows updated via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// LP pool vault the repayment goes to
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, borrowable_config.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub repay_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub bidder_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Lending SOL vault PDA the collateral is paid from
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, position.key().as_ref()],
+        bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once the position holds EURC debt
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    pub lending_program: Program<'info, LegasiLending>,
+    /// Receives the SOL bought
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    /// Written by lending through the GAD CPIs
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        close = starter,
+        seeds = [
+            LIQUIDATION_AUCTION_SEED,
+            position.key().as_ref(),
+            borrowable_config.mint.as_ref()
+        ],
+        bump = auction.bump,
+        has_one = position,
+        has_one = starter
+    )]
+    pub auction: Account<'info, LiquidationAuction>,
+    /// CHECK: Paid the auction's rent, gets it back
+    #[account(mut)]
+    pub starter: UncheckedAccount<'info>,
+    /// Borrowable config of the debt auctioned (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    /// LP pool the bad debt is written off
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// EURC price feed (owned by core program), required once the position holds EURC debt
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Protocol state (insurance fund and totals updated via CPI)
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    pub lending_program: Program<'info, LegasiLending>,
 }
@@ -0,0 +1,152 @@
+//! Liquidation auctions
+//!
+//! Once a position's LTV is `GAD_HARD_THRESHOLD_BPS` above the SOL max LTV, GAD's daily
+//! rate is too slow and a fixed liquidation bonus overpays liquidators. Instead anyone
+//! can open a Dutch auction of the position's SOL collateral against one of its debts:
+//! the ask starts at the oracle price and falls linearly to
+//! `LIQUIDATION_AUCTION_MAX_DISCOUNT_BPS` below it over `LIQUIDATION_AUCTION_DURATION`,
+//! so liquidators compete on the discount they accept. The ask tracks the live oracle,
+//! only the discount is fixed by the clock.
+//!
+//! Bids repay the debt (interest first) into the LP vault and take SOL at the current
+//! ask, in any size; a bid the collateral can't fill is filled in part. Once no
+//! collateral is left, settling writes the rest of that debt off the pool: the
+//! insurance fund covers what it can, the LPs the rest.
+//!
+//! Flow:
+//! 1. Anyone calls start_auction for a position and a borrowed asset
+//! 2. Liquidators call bid_auction; the discount grows until `ends_at`, then holds
+//! 3. Once `ends_at` passes or the collateral is gone, anyone calls settle_auction:
+//!    the shortfall is written off and the rent goes back to the starter
+
+use anchor_lang::prelude::*;
+use legasi_core::{
+    constants::{BPS_DENOMINATOR, DEFAULT_SOL_MAX_LTV_BPS, GAD_HARD_THRESHOLD_BPS},
+    decimals::{sol_to_usd, usd_to_lamports},
+    errors::LegasiError,
+};
+
+/// Time for the discount to reach its maximum (seconds)
+pub const LIQUIDATION_AUCTION_DURATION: i64 = 3_600; // 1 hour
+
+/// Largest discount to the oracle price the ask falls to (bps)
+pub const LIQUIDATION_AUCTION_MAX_DISCOUNT_BPS: u64 = 2_000; // 20%
+
+/// LTV (bps) from which an auction can be started
+pub const LIQUIDATION_AUCTION_THRESHOLD_BPS: u64 =
+    DEFAULT_SOL_MAX_LTV_BPS as u64 + GAD_HARD_THRESHOLD_BPS as u64;
+
+/// A running Dutch auction of a position's SOL collateral against one of its debts
+/// (the borrowed mint is part of the PDA seeds)
+#[account]
+#[derive(InitSpace)]
+pub struct LiquidationAuction {
+    pub position: Pubkey,
+    /// Paid the rent, refunded at settlement
+    pub starter: Pubkey,
+    pub started_at: i64,
+    /// The discount stops growing here, and the auction can be settled
+    pub ends_at: i64,
+    pub ltv_at_start_bps: u64,
+    /// SOL sold to bidders (lamports)
+    pub collateral_sold: u64,
+    /// Debt repaid by bidders, in the borrowed asset
+    pub debt_repaid: u64,
+    pub bump: u8,
+}
+
+impl LiquidationAuction {
+    /// Discount to the oracle price at `now` (bps)
+    pub fn discount_bps(&self, now: i64) -> u64 {
+        let duration = self.ends_at.saturating_sub(self.started_at);
+        if duration <= 0 {
+            return LIQUIDATION_AUCTION_MAX_DISCOUNT_BPS;
+        }
+        let elapsed = now.saturating_sub(self.started_at).clamp(0, duration);
+        (LIQUIDATION_AUCTION_MAX_DISCOUNT_BPS as u128 * elapsed as u128 / duration as u128) as u64
+    }
+
+    /// SOL ask price (6 decimals) at `now` for an oracle price of `sol_price`
+    pub fn ask_price(&self, sol_price: u64, now: i64) -> u64 {
+        let discount_bps = self.discount_bps(now);
+        (sol_price as u128 * (BPS_DENOMINATOR - discount_bps) as u128 / BPS_DENOMINATOR as u128)
+            as u64
+    }
+}
+
+/// Size a bid repaying `repay_usd` at `ask_price` against `collateral_lamports`
+/// Returns (USD repaid, lamports bought): both shrink together if the collateral
+/// can't fill the bid
+pub fn size_bid(repay_usd: u64, collateral_lamports: u64, ask_price: u64) -> Result<(u64, u64)> {
+    require!(ask_price > 0, LegasiError::InvalidOracle);
+    let lamports = usd_to_lamports(repay_usd, ask_price)?;
+    if lamports <= collateral_lamports {
+        return Ok((repay_usd, lamports));
+    }
+
+    let collateral_usd = sol_to_usd(collateral_lamports, ask_price)?;
+    Ok((collateral_usd, collateral_lamports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use legasi_core::constants::LAMPORTS_PER_SOL;
+
+    fn auction() -> LiquidationAuction {
+        LiquidationAuction {
+            position: Pubkey::default(),
+            starter: Pubkey::default(),
+            started_at: 1_000,
+            ends_at: 1_000 + LIQUIDATION_AUCTION_DURATION,
+            ltv_at_start_bps: 9_500,
+            collateral_sold: 0,
+            debt_repaid: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_discount_descends_then_holds() {
+        let auction = auction();
+        assert_eq!(auction.discount_bps(1_000), 0);
+        assert_eq!(
+            auction.discount_bps(1_000 + LIQUIDATION_AUCTION_DURATION / 2),
+            1_000
+        );
+        assert_eq!(
+            auction.discount_bps(1_000 + LIQUIDATION_AUCTION_DURATION),
+            2_000
+        );
+        assert_eq!(auction.discount_bps(1_000_000), 2_000);
+        // A clock behind the start never gives a premium
+        assert_eq!(auction.discount_bps(0), 0);
+    }
+
+    #[test]
+    fn test_ask_price() {
+        // Half way through, $100 SOL is offered at $90
+        let auction = auction();
+        assert_eq!(
+            auction.ask_price(100_000_000, 1_000 + LIQUIDATION_AUCTION_DURATION / 2),
+            90_000_000
+        );
+        assert_eq!(auction.ask_price(100_000_000, 1_000), 100_000_000);
+    }
+
+    #[test]
+    fn test_size_bid() {
+        // $90 at a $90 ask buys 1 SOL
+        assert_eq!(
+            size_bid(90_000_000, 10 * LAMPORTS_PER_SOL, 90_000_000).unwrap(),
+            (90_000_000, LAMPORTS_PER_SOL)
+        );
+        // Only 0.5 SOL left: the bid is filled for $45
+        assert_eq!(
+            size_bid(90_000_000, LAMPORTS_PER_SOL / 2, 90_000_000).unwrap(),
+            (45_000_000, LAMPORTS_PER_SOL / 2)
+        );
+        assert_eq!(size_bid(90_000_000, 0, 90_000_000).unwrap(), (0, 0));
+        assert!(size_bid(1, 1, 0).is_err());
+    }
+}
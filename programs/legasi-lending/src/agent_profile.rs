@@ -0,0 +1,40 @@
+//! Agent profiles
+//!
+//! A position owner can publish an optional `AgentProfile` next to its
+//! `AgentConfig`: hashes of the agent's display name and service URI, and a
+//! category. The strings themselves stay off-chain; anyone holding them can check
+//! them against the hashes. x402 payments made with the profile passed record it on
+//! the receipt and event, so recipients and dashboards can tell which agent paid,
+//! and gate or listing curators can filter agents by category.
+//!
+//! Flow:
+//! 1. Owner calls set_agent_profile (creates or overwrites it)
+//! 2. x402_pay takes the profile as an optional account
+//! 3. Owner calls close_agent_profile to take it down and reclaim the rent
+
+use anchor_lang::prelude::*;
+
+/// What an agent does, as declared by its owner
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum AgentCategory {
+    Other,
+    Trading,
+    Payments,
+    Research,
+    Infrastructure,
+    Commerce,
+}
+
+/// Public identity of a position's agent, at `[AGENT_PROFILE_SEED, agent_config]`
+#[account]
+#[derive(InitSpace)]
+pub struct AgentProfile {
+    pub agent_config: Pubkey,
+    /// SHA-256 of the agent's display name
+    pub name_hash: [u8; 32],
+    /// SHA-256 of the agent's service URI
+    pub service_uri_hash: [u8; 32],
+    pub category: AgentCategory,
+    pub updated_at: i64,
+    pub bump: u8,
+}
@@ -0,0 +1,93 @@
+//! Auto-deleverage orders
+//!
+//! A borrower's standing instruction: once the position's LTV exceeds
+//! `trigger_ltv_bps`, any keeper may sell SOL collateral through Jupiter, sized to land
+//! at `target_ltv_bps`, for `keeper_tip_bps` of the proceeds. Unlike GAD nothing is
+//! forced by the protocol, and the proceeds follow the order's `ProceedsMode`:
+//! - `Repay`: repay the debt, landing the position at the target
+//! - `Wallet`: paid to the owner's token account, leaving the debt as is (a stop-loss
+//!   into stablecoins). The sale must keep the position within its max LTV, and the
+//!   order disarms after one execution
+//!
+//! Flow:
+//! 1. Owner calls set_auto_deleverage (creates or updates and re-arms the order)
+//! 2. Keepers watch the LTV and call crank_auto_deleverage past the trigger
+//! 3. Owner calls cancel_auto_deleverage to close the order
+
+use anchor_lang::prelude::*;
+use legasi_core::constants::BPS_DENOMINATOR;
+use legasi_core::errors::LegasiError;
+
+/// Highest keeper tip an order can offer (bps of the swap proceeds)
+pub const MAX_KEEPER_TIP_BPS: u16 = 200; // 2%
+
+/// Where the proceeds of an executed order go
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ProceedsMode {
+    Repay,
+    Wallet,
+}
+
+/// Standing auto-deleverage order for a position
+#[account]
+#[derive(InitSpace)]
+pub struct AutoDeleverageOrder {
+    pub position: Pubkey,
+    /// Borrowable the collateral is sold for
+    pub mint: Pubkey,
+    pub trigger_ltv_bps: u16,
+    pub target_ltv_bps: u16,
+    pub keeper_tip_bps: u16,
+    pub proceeds: ProceedsMode,
+    /// Cleared once a `Wallet` order executes, until the owner sets it again
+    pub armed: bool,
+    pub bump: u8,
+}
+
+impl AutoDeleverageOrder {
+    /// Fails unless `target < trigger` and the tip leaves room to reach the target
+    pub fn validate(trigger_ltv_bps: u16, target_ltv_bps: u16, keeper_tip_bps: u16) -> Result<()> {
+        require!(
+            target_ltv_bps < trigger_ltv_bps
+                && trigger_ltv_bps as u64 <= BPS_DENOMINATOR
+                && keeper_tip_bps <= MAX_KEEPER_TIP_BPS
+                && (target_ltv_bps + keeper_tip_bps) as u64 <= BPS_DENOMINATOR,
+            LegasiError::InvalidAutoDeleverageOrder
+        );
+        Ok(())
+    }
+
+    /// Keeper's cut of `received`
+    pub fn keeper_tip(&self, received: u64) -> u64 {
+        (received as u128 * self.keeper_tip_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(AutoDeleverageOrder::validate(7000, 5000, 50).is_ok());
+        assert!(AutoDeleverageOrder::validate(5000, 5000, 50).is_err());
+        assert!(AutoDeleverageOrder::validate(7000, 5000, MAX_KEEPER_TIP_BPS + 1).is_err());
+        assert!(AutoDeleverageOrder::validate(10_001, 5000, 0).is_err());
+    }
+
+    #[test]
+    fn test_keeper_tip() {
+        let order = AutoDeleverageOrder {
+            position: Pubkey::default(),
+            mint: Pubkey::default(),
+            trigger_ltv_bps: 7000,
+            target_ltv_bps: 5000,
+            keeper_tip_bps: 50,
+            proceeds: ProceedsMode::Repay,
+            armed: true,
+            bump: 0,
+        };
+        assert_eq!(order.keeper_tip(400_000_000), 2_000_000);
+        assert_eq!(order.keeper_tip(199), 0);
+    }
+}
@@ -0,0 +1,185 @@
+//! Agent credit lines
+//!
+//! The owner commits collateral to a borrowing limit in one asset. The agent then
+//! draws and repays freely within the limit, with no daily cap, and pays a standby
+//! fee on the unused part. The fee is booked as interest on the position, so it
+//! reaches LPs (and the insurance fund) through the normal repay path.
+//!
+//! Flow:
+//! 1. Owner calls open_credit_line (collateral must back current debt + the unused limit)
+//! 2. Agent calls draw_credit_line; repaying uses plain `repay`
+//! 3. Anyone cranks accrue_standby_fee; draws and close accrue it too
+//! 4. While the line is open, withdrawals must leave the unused limit borrowable
+//! 5. Owner calls close_credit_line to release the collateral
+
+use anchor_lang::prelude::*;
+use legasi_core::{
+    constants::{BPS_DENOMINATOR, MAX_BORROW_TYPES, SECONDS_PER_YEAR},
+    errors::LegasiError,
+    state::AssetType,
+};
+
+use crate::{BorrowedAmount, Position};
+
+/// Standby fee APR on the unused limit (bps)
+pub const STANDBY_FEE_BPS: u64 = 50; // 0.5%
+
+/// Committed borrowing limit for a position
+#[account]
+#[derive(InitSpace)]
+pub struct CreditLine {
+    pub position: Pubkey,
+    /// Asset the line is drawn in
+    pub asset_type: AssetType,
+    /// Committed limit on that asset's principal (asset units)
+    pub limit: u64,
+    /// Standby fee is accrued up to here
+    pub last_fee_accrual: i64,
+    pub bump: u8,
+}
+
+impl CreditLine {
+    /// Principal of the line's asset the position owes, however it was borrowed
+    pub fn drawn(&self, position: &Position) -> u64 {
+        position
+            .borrows
+            .iter()
+            .find(|b| b.asset_type == self.asset_type)
+            .map_or(0, |b| b.amount)
+    }
+
+    /// Part of the limit not drawn yet
+    pub fn unused(&self, position: &Position) -> u64 {
+        self.limit.saturating_sub(self.drawn(position))
+    }
+
+    /// Book the standby fee on the unused limit since the last accrual as interest on
+    /// the line's asset. Returns the fee
+    pub fn accrue_fee(&mut self, position: &mut Position, now: i64) -> Result<u64> {
+        let elapsed = now.saturating_sub(self.last_fee_accrual);
+        if elapsed <= 0 {
+            return Ok(0);
+        }
+        // fee = unused * fee_bps * elapsed / (year * 10000)
+        let fee = (self.unused(position) as u128)
+            .checked_mul(STANDBY_FEE_BPS as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_mul(elapsed as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(SECONDS_PER_YEAR as u128 * BPS_DENOMINATOR as u128)
+            .ok_or(LegasiError::MathOverflow)? as u64;
+        self.last_fee_accrual = now;
+        if fee == 0 {
+            return Ok(0);
+        }
+
+        position.start_debt_clock(now)?;
+        match position
+            .borrows
+            .iter_mut()
+            .find(|b| b.asset_type == self.asset_type)
+        {
+            Some(borrow) => {
+                borrow.accrued_interest = borrow.accrued_interest.saturating_add(fee);
+            }
+            None => {
+                require!(
+                    position.borrows.len() < MAX_BORROW_TYPES,
+                    LegasiError::MaxBorrowTypesReached
+                );
+                // No principal, so no index yet: the next accrual against the pool
+                // sets it before anything is drawn
+                let mut borrow = BorrowedAmount::new(self.asset_type, 0, 0, now);
+                borrow.accrued_interest = fee;
+                position.borrows.push(borrow);
+            }
+        }
+        position.statement_totals.record_fee(self.asset_type, fee);
+        Ok(fee)
+    }
+}
+
+/// Credit line behind an unchecked `[CREDIT_LINE_SEED, position]` account, if one is open
+pub fn load_credit_line(info: &AccountInfo) -> Result<Option<CreditLine>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(CreditLine::try_deserialize(
+        &mut &info.try_borrow_data()?[..],
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Reputation;
+    use legasi_core::interest::BORROW_INDEX_PRECISION;
+
+    fn position(usdc_debt: u64) -> Position {
+        Position {
+            owner: Pubkey::default(),
+            collaterals: vec![],
+            borrows: vec![BorrowedAmount::new(
+                AssetType::USDC,
+                usdc_debt,
+                BORROW_INDEX_PRECISION,
+                0,
+            )],
+            last_update: 0,
+            last_gad_crank: 0,
+            gad_enabled: true,
+            total_gad_liquidated_usd: 0,
+            reputation: Reputation::default(),
+            stake_provider: Default::default(),
+            gad_carried_secs: 0,
+            max_debt_usd: 0,
+            referrer: Pubkey::default(),
+            committed_letters_usd: 0,
+            emode: Default::default(),
+            statement_totals: Default::default(),
+            gad_collateral_floor: 0,
+            bump: 0,
+        }
+    }
+
+    fn line(asset_type: AssetType) -> CreditLine {
+        CreditLine {
+            position: Pubkey::default(),
+            asset_type,
+            limit: 1_000_000_000,
+            last_fee_accrual: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_standby_fee_on_unused_limit() {
+        // $400 of a $1,000 line drawn: 0.5% of $600 for a year is $3
+        let mut position = position(400_000_000);
+        let mut line = line(AssetType::USDC);
+        assert_eq!(line.unused(&position), 600_000_000);
+        assert_eq!(
+            line.accrue_fee(&mut position, SECONDS_PER_YEAR as i64)
+                .unwrap(),
+            3_000_000
+        );
+        assert_eq!(position.borrows[0].accrued_interest, 3_000_000);
+        assert_eq!(
+            line.accrue_fee(&mut position, SECONDS_PER_YEAR as i64)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_standby_fee_opens_borrow_entry() {
+        // Nothing drawn in EURC yet: the fee becomes a new EURC entry
+        let mut position = position(400_000_000);
+        let mut line = line(AssetType::EURC);
+        line.accrue_fee(&mut position, SECONDS_PER_YEAR as i64)
+            .unwrap();
+        assert_eq!(position.borrows[1].asset_type, AssetType::EURC);
+        assert_eq!(position.borrows[1].amount, 0);
+        assert_eq!(position.borrows[1].accrued_interest, 5_000_000);
+    }
+}
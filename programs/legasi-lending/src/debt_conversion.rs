@@ -0,0 +1,247 @@
+//! Debt conversion
+//!
+//! A borrower can move debt between EURC and USDC without an external swap: part of
+//! one borrow entry is closed and the same value, at the EUR/USD oracle price, is
+//! opened in the other, plus `DEBT_CONVERSION_FEE_BPS`. The fee is booked as interest
+//! on the new entry, so it reaches LPs (and the insurance fund) through the normal
+//! repay path. Principal stays principal and interest stays interest, so the pools'
+//! `total_borrowed` moves with the principal only.
+//!
+//! Flow:
+//! 1. Owner calls convert_debt with the amount of the source asset to convert
+//! 2. The source entry gives up interest first, then principal, like a repay (but
+//!    without earning reputation)
+//! 3. The position must still be within LTV once the fee is added
+
+use anchor_lang::prelude::*;
+use legasi_core::{
+    constants::{BPS_DENOMINATOR, MAX_BORROW_TYPES},
+    errors::LegasiError,
+    state::AssetType,
+};
+
+use crate::{BorrowedAmount, Position};
+
+/// Fee on the converted amount, added to the new debt (bps)
+pub const DEBT_CONVERSION_FEE_BPS: u64 = 10; // 0.1%
+
+/// Debt moved by one conversion, in each entry's own units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DebtConversion {
+    /// Interest closed on the source entry
+    pub from_interest: u64,
+    /// Principal closed on the source entry
+    pub from_principal: u64,
+    /// Interest opened on the destination entry, fee included
+    pub to_interest: u64,
+    /// Principal opened on the destination entry
+    pub to_principal: u64,
+    /// Conversion fee, in destination units
+    pub fee: u64,
+}
+
+/// `amount` of `from` in units of `to` at `eur_usd_price`
+pub fn convert_amount(
+    from: AssetType,
+    to: AssetType,
+    amount: u64,
+    eur_usd_price: Option<u64>,
+) -> Result<u64> {
+    to.usd_to_debt(from.debt_to_usd(amount, eur_usd_price)?, eur_usd_price)
+}
+
+/// Conversion fee on `amount` of the destination asset
+pub fn conversion_fee(amount: u64) -> u64 {
+    (amount as u128 * DEBT_CONVERSION_FEE_BPS as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+impl Position {
+    /// Move up to `amount` of `from` debt (interest first) into `to` at `eur_usd_price`,
+    /// plus the conversion fee. Both entries must be accrued up to `now` first, and a new
+    /// `to` entry starts at `to_borrow_index`, the `to` pool's index at `now`
+    pub fn convert_debt(
+        &mut self,
+        from: AssetType,
+        to: AssetType,
+        amount: u64,
+        eur_usd_price: Option<u64>,
+        to_borrow_index: u128,
+        now: i64,
+    ) -> Result<DebtConversion> {
+        require!(from != to, LegasiError::SameDebtAsset);
+        let (from_interest, from_principal) = self.reduce_debt(from, amount)?;
+        require!(
+            from_interest > 0 || from_principal > 0,
+            LegasiError::InvalidAmount
+        );
+
+        let to_interest = convert_amount(from, to, from_interest, eur_usd_price)?;
+        let to_principal = convert_amount(from, to, from_principal, eur_usd_price)?;
+        let fee = conversion_fee(
+            to_interest
+                .checked_add(to_principal)
+                .ok_or(LegasiError::MathOverflow)?,
+        );
+        let to_interest = to_interest
+            .checked_add(fee)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        let destination = match self.borrows.iter().position(|b| b.asset_type == to) {
+            Some(index) => {
+                // Converted debt owes the variable rate, so it can't join a fixed term
+                require!(
+                    !self.borrows[index].is_fixed_term(),
+                    LegasiError::BorrowTermMismatch
+                );
+                &mut self.borrows[index]
+            }
+            None => {
+                require!(
+                    self.borrows.len() < MAX_BORROW_TYPES,
+                    LegasiError::MaxBorrowTypesReached
+                );
+                self.borrows
+                    .push(BorrowedAmount::new(to, 0, to_borrow_index, now));
+                self.borrows.last_mut().unwrap()
+            }
+        };
+        destination.amount = destination
+            .amount
+            .checked_add(to_principal)
+            .ok_or(LegasiError::MathOverflow)?;
+        destination.accrued_interest = destination
+            .accrued_interest
+            .checked_add(to_interest)
+            .ok_or(LegasiError::MathOverflow)?;
+        self.statement_totals.record_fee(to, fee);
+        self.last_update = now;
+
+        Ok(DebtConversion {
+            from_interest,
+            from_principal,
+            to_interest,
+            to_principal,
+            fee,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Reputation;
+    use legasi_core::interest::BORROW_INDEX_PRECISION;
+
+    const EUR_USD: Option<u64> = Some(1_080_000);
+
+    fn position(borrows: Vec<BorrowedAmount>) -> Position {
+        Position {
+            owner: Pubkey::default(),
+            collaterals: vec![],
+            borrows,
+            last_update: 0,
+            last_gad_crank: 0,
+            gad_enabled: true,
+            total_gad_liquidated_usd: 0,
+            reputation: Reputation::default(),
+            stake_provider: Default::default(),
+            gad_carried_secs: 0,
+            max_debt_usd: 0,
+            referrer: Pubkey::default(),
+            committed_letters_usd: 0,
+            emode: Default::default(),
+            statement_totals: Default::default(),
+            gad_collateral_floor: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_convert_amount() {
+        // 100 EURC at 1.08 is 108 USDC, and back
+        assert_eq!(
+            convert_amount(AssetType::EURC, AssetType::USDC, 100_000_000, EUR_USD).unwrap(),
+            108_000_000
+        );
+        assert_eq!(
+            convert_amount(AssetType::USDC, AssetType::EURC, 108_000_000, EUR_USD).unwrap(),
+            100_000_000
+        );
+        assert!(convert_amount(AssetType::EURC, AssetType::USDC, 1, None).is_err());
+        assert_eq!(conversion_fee(108_000_000), 108_000);
+    }
+
+    #[test]
+    fn test_convert_all_eurc_debt_to_usdc() {
+        let mut eurc = BorrowedAmount::new(AssetType::EURC, 100_000_000, BORROW_INDEX_PRECISION, 0);
+        eurc.accrued_interest = 1_000_000;
+        let mut position = position(vec![eurc]);
+
+        // Asking for more than is owed converts all of it
+        let conversion = position
+            .convert_debt(
+                AssetType::EURC,
+                AssetType::USDC,
+                u64::MAX,
+                EUR_USD,
+                BORROW_INDEX_PRECISION,
+                10,
+            )
+            .unwrap();
+        assert_eq!(conversion.from_interest, 1_000_000);
+        assert_eq!(conversion.from_principal, 100_000_000);
+        assert_eq!(conversion.to_principal, 108_000_000);
+        assert_eq!(conversion.fee, 109_080);
+        assert_eq!(conversion.to_interest, 1_080_000 + 109_080);
+
+        // The EURC entry is closed and its slot reused
+        assert_eq!(position.borrows.len(), 1);
+        assert_eq!(position.borrows[0].asset_type, AssetType::USDC);
+        assert_eq!(position.borrows[0].amount, 108_000_000);
+        assert_eq!(position.borrows[0].accrued_interest, 1_189_080);
+        assert_eq!(position.borrows[0].last_accrued, 10);
+    }
+
+    #[test]
+    fn test_partial_conversion_adds_to_existing_entry() {
+        let mut position = position(vec![
+            BorrowedAmount::new(AssetType::USDC, 50_000_000, BORROW_INDEX_PRECISION, 0),
+            BorrowedAmount::new(AssetType::EURC, 40_000_000, BORROW_INDEX_PRECISION, 0),
+        ]);
+        let conversion = position
+            .convert_debt(
+                AssetType::USDC,
+                AssetType::EURC,
+                10_800_000,
+                EUR_USD,
+                BORROW_INDEX_PRECISION,
+                0,
+            )
+            .unwrap();
+        assert_eq!(conversion.to_principal, 10_000_000);
+        assert_eq!(position.borrows[0].amount, 39_200_000);
+        assert_eq!(position.borrows[1].amount, 50_000_000);
+        assert_eq!(position.borrows[1].accrued_interest, 10_000);
+
+        assert!(position
+            .convert_debt(
+                AssetType::USDC,
+                AssetType::USDC,
+                1,
+                EUR_USD,
+                BORROW_INDEX_PRECISION,
+                0
+            )
+            .is_err());
+        assert!(position
+            .convert_debt(
+                AssetType::SOL,
+                AssetType::USDC,
+                1,
+                EUR_USD,
+                BORROW_INDEX_PRECISION,
+                0
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Deleverage to a target LTV
+//!
+//! `deleverage_to_ltv` sizes the repayment on-chain so the position lands at the
+//! target LTV, instead of callers computing amounts for `repay` themselves:
+//! - from the wallet: repay `debt - collateral * target`
+//! - by selling collateral through Jupiter: sell `x` of collateral and repay with the
+//!   proceeds, where `(debt - x) / (collateral - x) = target`
+//!
+//! Amounts are rounded up, so the position ends at or just below the target.
+
+use anchor_lang::prelude::*;
+use legasi_core::constants::BPS_DENOMINATOR;
+
+/// Jupiter route selling SOL collateral for the debt asset, built off-chain
+/// Its output must go to the token account the repayment is paid from (the owner's,
+/// or the liquidator's for `flash_liquidate`)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DeleverageSwap {
+    /// Serialized Jupiter swap instruction data
+    pub route_data: Vec<u8>,
+    /// Minimum debt asset received (slippage protection)
+    pub min_out_amount: u64,
+}
+
+/// Debt (USD) to repay from the wallet so `debt / collateral` falls to `target_ltv_bps`
+pub fn repay_to_target_usd(collateral_usd: u64, debt_usd: u64, target_ltv_bps: u16) -> u64 {
+    let allowed = collateral_usd as u128 * target_ltv_bps as u128 / BPS_DENOMINATOR as u128;
+    (debt_usd as u128).saturating_sub(allowed) as u64
+}
+
+/// Collateral (USD) to sell, repaying the proceeds, so the LTV falls to `target_ltv_bps`
+pub fn sell_to_target_usd(collateral_usd: u64, debt_usd: u64, target_ltv_bps: u16) -> u64 {
+    sell_to_target_net_usd(collateral_usd, debt_usd, target_ltv_bps, 0)
+}
+
+/// `sell_to_target_usd` when `fee_bps` of the proceeds is paid away before repaying
+pub fn sell_to_target_net_usd(
+    collateral_usd: u64,
+    debt_usd: u64,
+    target_ltv_bps: u16,
+    fee_bps: u16,
+) -> u64 {
+    let net = (BPS_DENOMINATOR as u128).saturating_sub(fee_bps as u128);
+    let target = (target_ltv_bps as u128).min(net.saturating_sub(1));
+    // x = (debt - collateral * target) / (1 - fee - target)
+    let excess = (debt_usd as u128 * BPS_DENOMINATOR as u128)
+        .saturating_sub(collateral_usd as u128 * target);
+    let keep = (net - target).max(1);
+    ((excess + keep - 1) / keep).min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repay_to_target() {
+        // $1,000 collateral, $700 debt: repay $200 to reach 50%
+        assert_eq!(
+            repay_to_target_usd(1_000_000_000, 700_000_000, 5000),
+            200_000_000
+        );
+        // Already below the target
+        assert_eq!(repay_to_target_usd(1_000_000_000, 400_000_000, 5000), 0);
+        // A 0% target repays everything
+        assert_eq!(
+            repay_to_target_usd(1_000_000_000, 700_000_000, 0),
+            700_000_000
+        );
+    }
+
+    #[test]
+    fn test_sell_to_target() {
+        // Sell $400: $600 collateral and $300 debt is 50%
+        let sold = sell_to_target_usd(1_000_000_000, 700_000_000, 5000);
+        assert_eq!(sold, 400_000_000);
+        assert_eq!((700_000_000 - sold) * 10_000 / (1_000_000_000 - sold), 5000);
+
+        // Rounded up: never lands above the target
+        let sold = sell_to_target_usd(1_000_000_000, 700_000_001, 6000);
+        assert!((700_000_001 - sold) as u128 * 10_000 <= (1_000_000_000 - sold) as u128 * 6000);
+        assert_eq!(sell_to_target_usd(1_000_000_000, 400_000_000, 5000), 0);
+    }
+
+    #[test]
+    fn test_sell_to_target_net_of_fee() {
+        // Sell $500 with a 10% fee: $450 repaid leaves $250 debt on $500 collateral
+        let sold = sell_to_target_net_usd(1_000_000_000, 700_000_000, 5000, 1000);
+        assert_eq!(sold, 500_000_000);
+        assert_eq!(
+            sell_to_target_net_usd(1_000_000_000, 700_000_000, 5000, 0),
+            400_000_000
+        );
+    }
+}
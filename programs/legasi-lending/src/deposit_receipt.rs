@@ -0,0 +1,129 @@
+//! Deposit receipts
+//!
+//! Every SPL collateral deposit is recorded as a lot (amount, USD price, time) in
+//! the position's `[DEPOSIT_RECEIPT_SEED, position, mint]` receipt, so the frontend
+//! can show PnL and users can report cost basis and holding period for taxes.
+//! The receipt keeps the last `DEPOSIT_LOTS` lots in a ring: once it is full, a
+//! new deposit overwrites the oldest lot, and the totals cover the lots kept.
+//!
+//! Flow:
+//! 1. deposit_token opens the receipt on the first deposit of a mint
+//! 2. Each deposit records a lot at the mint's feed price and emits `DepositLotRecorded`
+
+use anchor_lang::prelude::*;
+use legasi_core::state::AssetType;
+
+/// Lots kept per receipt
+pub const DEPOSIT_LOTS: usize = 8;
+
+/// One deposit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace, Default)]
+pub struct DepositLot {
+    /// Token units (0 = empty slot)
+    pub amount: u64,
+    /// Feed price at deposit, USD per whole token (6 decimals)
+    pub price_usd_6dec: u64,
+    pub deposited_at: i64,
+}
+
+/// Recent deposits of one collateral mint into one position
+#[account]
+#[derive(InitSpace)]
+pub struct DepositReceipt {
+    pub position: Pubkey,
+    pub mint: Pubkey,
+    pub asset_type: AssetType,
+    pub decimals: u8,
+    pub lots: [DepositLot; DEPOSIT_LOTS],
+    /// Slot the next lot is written to
+    pub cursor: u8,
+    pub bump: u8,
+}
+
+impl DepositReceipt {
+    /// Record a lot, overwriting the oldest one once the ring is full
+    pub fn record(&mut self, amount: u64, price_usd_6dec: u64, now: i64) {
+        let cursor = self.cursor as usize % DEPOSIT_LOTS;
+        self.lots[cursor] = DepositLot {
+            amount,
+            price_usd_6dec,
+            deposited_at: now,
+        };
+        self.cursor = ((cursor + 1) % DEPOSIT_LOTS) as u8;
+    }
+
+    /// USD paid for the lots kept (6 decimals), at each lot's deposit price
+    pub fn cost_basis_usd(&self) -> u64 {
+        let scale = 10u128.pow(self.decimals as u32);
+        let total: u128 = self
+            .lots
+            .iter()
+            .map(|lot| lot.amount as u128 * lot.price_usd_6dec as u128 / scale)
+            .sum();
+        total.min(u64::MAX as u128) as u64
+    }
+
+    /// Amount-weighted age of the lots kept at `now` (seconds)
+    pub fn avg_holding_secs(&self, now: i64) -> i64 {
+        let mut amount: u128 = 0;
+        let mut weighted: u128 = 0;
+        for lot in self.lots.iter().filter(|lot| lot.amount > 0) {
+            let age = now.saturating_sub(lot.deposited_at).max(0) as u128;
+            amount += lot.amount as u128;
+            weighted += lot.amount as u128 * age;
+        }
+        if amount == 0 {
+            return 0;
+        }
+        (weighted / amount) as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(decimals: u8) -> DepositReceipt {
+        DepositReceipt {
+            position: Pubkey::default(),
+            mint: Pubkey::default(),
+            asset_type: AssetType::CbBTC,
+            decimals,
+            lots: [DepositLot::default(); DEPOSIT_LOTS],
+            cursor: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_cost_basis_and_holding_period() {
+        let mut receipt = receipt(8);
+        assert_eq!(receipt.cost_basis_usd(), 0);
+        assert_eq!(receipt.avg_holding_secs(1_000), 0);
+
+        // 1 BTC at $60k, then 0.5 BTC at $70k a day later
+        receipt.record(100_000_000, 60_000_000_000, 0);
+        receipt.record(50_000_000, 70_000_000_000, 86_400);
+        assert_eq!(receipt.cost_basis_usd(), 95_000_000_000);
+
+        // Two days in: held 2 days and 1 day, weighted 2:1
+        assert_eq!(receipt.avg_holding_secs(172_800), 144_000);
+    }
+
+    #[test]
+    fn test_full_ring_overwrites_oldest_lot() {
+        let mut receipt = receipt(6);
+        for i in 0..DEPOSIT_LOTS as i64 {
+            receipt.record(1_000_000, 1_000_000, i);
+        }
+        assert_eq!(receipt.cost_basis_usd(), DEPOSIT_LOTS as u64 * 1_000_000);
+
+        receipt.record(2_000_000, 1_000_000, 100);
+        assert_eq!(receipt.cursor, 1);
+        assert_eq!(receipt.lots[0].deposited_at, 100);
+        assert_eq!(
+            receipt.cost_basis_usd(),
+            (DEPOSIT_LOTS as u64 + 1) * 1_000_000
+        );
+    }
+}
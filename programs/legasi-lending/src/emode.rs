@@ -0,0 +1,104 @@
+//! User eMode
+//!
+//! An owner opts their position into an eMode category (`legasi_core::market`) with
+//! set_emode. Borrows and SOL withdrawals that pass a `Market` then check the
+//! position against `Market::get_effective_max_ltv`: the market's eMode LTV when its
+//! category is the owner's, its base LTV otherwise. Without a market they keep the
+//! default SOL LTV.
+//!
+//! A market only prices a position whose collateral is all its collateral asset and
+//! whose debt is all its borrow asset, so eMode can't lift the LTV of debt outside
+//! the category. For the same reason set_emode, with debt open, needs the market
+//! covering that debt in the new category, and the position must fit the new LTV.
+//!
+//! Flow:
+//! 1. Owner calls set_emode with the category (and the market while in debt)
+//! 2. borrow / withdraw_sol take the market as an optional account
+
+use anchor_lang::prelude::*;
+use legasi_core::{
+    constants::DEFAULT_SOL_MAX_LTV_BPS,
+    errors::LegasiError,
+    market::{EModeCategory, Market},
+    state::AssetType,
+};
+
+use crate::Position;
+
+/// Whether a `collateral_asset` / `borrow_asset` market prices a position holding
+/// `collaterals` against `borrows`
+pub fn market_covers(
+    collateral_asset: AssetType,
+    borrow_asset: AssetType,
+    mut collaterals: impl Iterator<Item = AssetType>,
+    mut borrows: impl Iterator<Item = AssetType>,
+) -> bool {
+    collaterals.all(|asset| asset == collateral_asset) && borrows.all(|asset| asset == borrow_asset)
+}
+
+/// Max LTV (bps, before the reputation bonus) of `position` in `category`: the
+/// market's effective LTV, or the default SOL LTV without a market.
+/// `new_borrow` is an asset about to be borrowed, which the market must cover too
+pub fn max_ltv_bps(
+    position: &Position,
+    market: Option<&Market>,
+    category: EModeCategory,
+    new_borrow: Option<AssetType>,
+) -> Result<u16> {
+    let Some(market) = market else {
+        return Ok(DEFAULT_SOL_MAX_LTV_BPS);
+    };
+    require!(market.is_active, LegasiError::AssetNotActive);
+    require!(
+        market_covers(
+            market.collateral_asset,
+            market.borrow_asset,
+            position.collaterals.iter().map(|c| c.asset_type),
+            position
+                .borrows
+                .iter()
+                .map(|b| b.asset_type)
+                .chain(new_borrow),
+        ),
+        LegasiError::EModeMismatch
+    );
+    Ok(market.get_effective_max_ltv(category))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_market_covers() {
+        let sol = [AssetType::SOL];
+        let usdc = [AssetType::USDC];
+        assert!(market_covers(
+            AssetType::SOL,
+            AssetType::USDC,
+            sol.into_iter(),
+            usdc.into_iter()
+        ));
+        // An empty position fits any market
+        assert!(market_covers(
+            AssetType::CbBTC,
+            AssetType::EURC,
+            std::iter::empty(),
+            std::iter::empty()
+        ));
+        // EURC debt is outside a USDC market
+        assert!(!market_covers(
+            AssetType::SOL,
+            AssetType::USDC,
+            sol.into_iter(),
+            [AssetType::USDC, AssetType::EURC].into_iter()
+        ));
+        // mSOL collateral is outside a SOL market
+        assert!(!market_covers(
+            AssetType::SOL,
+            AssetType::USDC,
+            [AssetType::SOL, AssetType::MSOL].into_iter(),
+            usdc.into_iter()
+        ));
+    }
+}
@@ -0,0 +1,157 @@
+//! Fixed-term loans
+//!
+//! borrow_fixed_term opens a borrow at a fixed rate until a maturity date instead of the
+//! pool's variable rate. The rate is quoted when the loan opens: the pool's current
+//! borrow rate plus `FIXED_RATE_PREMIUM_BPS`, bounded by the borrower's `max_rate_bps`.
+//! Both are stored on the borrow entry, which then owes:
+//! - up to maturity: the fixed rate, whatever the pool's rate does
+//! - past maturity: the pool's variable rate plus `MATURITY_PENALTY_BPS` on the unpaid
+//!   principal, until it is repaid
+//!
+//! Past maturity the position also becomes GAD-eligible `MATURED_GAD_DISCOUNT_BPS`
+//! below the usual max LTV. A fixed-term entry can be repaid early at any time, but it
+//! is the position's only entry in its asset: variable borrows of that asset wait until
+//! it is repaid.
+//!
+//! Flow:
+//! 1. Owner calls borrow_fixed_term with the amount, term and max rate
+//! 2. Owner repays with repay before maturity
+//! 3. Otherwise the balance rolls to the variable rate plus the penalty, and GAD
+//!    starts from the lower threshold
+
+use legasi_core::constants::{SECONDS_PER_DAY, SECONDS_PER_YEAR};
+use legasi_core::interest::accrued_interest;
+
+/// Shortest term a fixed-rate loan can be taken for
+pub const MIN_FIXED_TERM: i64 = 7 * SECONDS_PER_DAY;
+
+/// Longest term a fixed-rate loan can be taken for
+pub const MAX_FIXED_TERM: i64 = SECONDS_PER_YEAR as i64;
+
+/// Fixed rate over the pool's variable rate when the loan opens (bps APR)
+pub const FIXED_RATE_PREMIUM_BPS: u16 = 200;
+
+/// Penalty on top of the variable rate once a fixed-term loan is past maturity (bps APR)
+pub const MATURITY_PENALTY_BPS: u16 = 500;
+
+/// How much lower GAD's threshold is while a fixed-term loan is past maturity (bps LTV)
+pub const MATURED_GAD_DISCOUNT_BPS: u16 = 1_000;
+
+/// Fixed rate (bps APR) quoted against the pool's current variable rate
+pub fn fixed_rate_bps(variable_rate_bps: u64) -> u16 {
+    variable_rate_bps
+        .saturating_add(FIXED_RATE_PREMIUM_BPS as u64)
+        .min(u16::MAX as u64) as u16
+}
+
+/// True once a borrow entry with `maturity` (0 = variable) is past it
+pub fn is_matured(maturity: i64, now: i64) -> bool {
+    maturity != 0 && now >= maturity
+}
+
+/// Interest on a fixed-term `principal` from `from` to `to`: `fixed_rate_bps` up to
+/// `maturity`, then the time past it at the variable rate plus `MATURITY_PENALTY_BPS`.
+/// `variable` is what the variable rate charged over the whole span (e.g. from the
+/// pool's borrow index), prorated to the part past maturity. None on overflow
+pub fn term_interest(
+    principal: u64,
+    fixed_rate_bps: u16,
+    maturity: i64,
+    from: i64,
+    to: i64,
+    variable: u64,
+) -> Option<u64> {
+    if to <= from {
+        return Some(0);
+    }
+    let fixed_until = to.min(maturity).max(from);
+    let fixed = accrued_interest(principal, fixed_rate_bps, fixed_until - from)?;
+    let overdue = to - fixed_until;
+    if overdue == 0 {
+        return Some(fixed);
+    }
+    let variable = (variable as u128 * overdue as u128 / (to - from) as u128) as u64;
+    let penalty = accrued_interest(principal, MATURITY_PENALTY_BPS, overdue)?;
+    fixed.checked_add(variable)?.checked_add(penalty)
+}
+
+/// LTV (bps) GAD starts from: `max_ltv_bps`, `MATURED_GAD_DISCOUNT_BPS` lower when the
+/// position has a fixed-term loan past maturity
+pub fn gad_max_ltv_bps(max_ltv_bps: u64, matured: bool) -> u64 {
+    if matured {
+        max_ltv_bps.saturating_sub(MATURED_GAD_DISCOUNT_BPS as u64)
+    } else {
+        max_ltv_bps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THIRTY_DAYS: i64 = 30 * SECONDS_PER_DAY;
+
+    #[test]
+    fn test_fixed_rate_quote() {
+        assert_eq!(fixed_rate_bps(340), 540);
+        assert_eq!(fixed_rate_bps(u64::MAX), u16::MAX);
+    }
+
+    #[test]
+    fn test_is_matured() {
+        assert!(!is_matured(0, i64::MAX));
+        assert!(!is_matured(1_000, 999));
+        assert!(is_matured(1_000, 1_000));
+    }
+
+    #[test]
+    fn test_fixed_rate_until_maturity() {
+        // $1,000 at 5.4% for 30 days, whatever the variable rate charged
+        let interest =
+            term_interest(1_000_000_000, 540, THIRTY_DAYS, 0, THIRTY_DAYS, 99_000_000).unwrap();
+        assert_eq!(
+            interest,
+            accrued_interest(1_000_000_000, 540, THIRTY_DAYS).unwrap()
+        );
+        assert_eq!(
+            term_interest(1_000_000_000, 540, THIRTY_DAYS, 10, 10, 5),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_rolls_to_variable_plus_penalty() {
+        // Half the span before maturity at 5.4%, half after at the variable rate
+        // (half of the $10 it charged) plus the 5% penalty
+        let interest = term_interest(
+            1_000_000_000,
+            540,
+            THIRTY_DAYS,
+            0,
+            2 * THIRTY_DAYS,
+            10_000_000,
+        )
+        .unwrap();
+        let fixed = accrued_interest(1_000_000_000, 540, THIRTY_DAYS).unwrap();
+        let penalty = accrued_interest(1_000_000_000, MATURITY_PENALTY_BPS, THIRTY_DAYS).unwrap();
+        assert_eq!(interest, fixed + 5_000_000 + penalty);
+
+        // Entirely past maturity: all of the variable interest
+        let interest = term_interest(
+            1_000_000_000,
+            540,
+            THIRTY_DAYS,
+            THIRTY_DAYS,
+            2 * THIRTY_DAYS,
+            10_000_000,
+        )
+        .unwrap();
+        assert_eq!(interest, 10_000_000 + penalty);
+    }
+
+    #[test]
+    fn test_gad_threshold_drops_past_maturity() {
+        assert_eq!(gad_max_ltv_bps(7_500, false), 7_500);
+        assert_eq!(gad_max_ltv_bps(7_500, true), 6_500);
+    }
+}
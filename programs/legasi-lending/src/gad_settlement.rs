@@ -0,0 +1,363 @@
+//! GAD settlement
+//!
+//! Positions, their SOL vaults and mSOL vaults belong to lending, so the GAD program
+//! can't move collateral or write a position itself. It sizes each sale, then has
+//! lending carry it out through instructions only its `[PROTOCOL_WRITER_SEED]` PDA can
+//! sign for:
+//! - `set_gad_config`: the owner's GAD opt-in and collateral floor, set through GAD's
+//!   `configure_gad`
+//! - `release_gad_collateral`: pay collateral out of the position's vault (cranker
+//!   reward, treasury share, an auction bidder's SOL)
+//! - `swap_gad_collateral`: sell collateral through a swap route, signed by the vault
+//! - `settle_gad`: book what the sale repaid (`GadSettlement`)
+//!
+//! Collateral leaves the position as it leaves its vault, so the two never drift
+//! apart. GAD runs the settlement on a copy of the position first to size its events
+//! and pool reports, which `Position::apply_gad_settlement` keeps deterministic.
+
+use anchor_lang::prelude::*;
+use legasi_core::{
+    constants::{BPS_DENOMINATOR, GAD_PROGRAM_ID},
+    errors::LegasiError,
+    seeds::PROTOCOL_WRITER_SEED,
+    state::AssetType,
+    swap_router::SwapRoute,
+};
+use std::str::FromStr;
+
+use crate::Position;
+
+/// Most of a position's SOL collateral its GAD floor may hold back (50%), so GAD can
+/// always sell the rest
+pub const MAX_GAD_COLLATERAL_FLOOR_BPS: u64 = 5_000;
+
+/// True if `writer` is the GAD program's protocol writer PDA
+pub fn is_gad_writer(writer: &Pubkey) -> bool {
+    let gad_program = Pubkey::from_str(GAD_PROGRAM_ID).unwrap();
+    Pubkey::find_program_address(&[PROTOCOL_WRITER_SEED], &gad_program).0 == *writer
+}
+
+/// Debt a GAD sale takes off the position
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GadDebtReduction {
+    None,
+    /// USD (6 decimals) across the borrows in order, interest first, EURC at
+    /// `eur_usd_price`
+    Usd {
+        amount_usd: u64,
+        eur_usd_price: Option<u64>,
+    },
+    /// `amount` off the `asset_type` borrow, interest first
+    Asset {
+        asset_type: AssetType,
+        amount: u64,
+    },
+}
+
+/// What a GAD crank or auction fill repaid, booked by `settle_gad`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GadSettlement {
+    pub debt: GadDebtReduction,
+    /// USD value (6 decimals) of the collateral sold, added to `total_gad_liquidated_usd`
+    pub liquidated_usd: u64,
+    /// Set by GAD cranks: stamps `last_gad_crank`, counts a GAD event and carries this
+    /// much time to the next crank (see `legasi_core::gad::pace_sale`)
+    pub carried_secs: Option<i64>,
+}
+
+impl Position {
+    /// True if `gad_collateral_floor` is at most `MAX_GAD_COLLATERAL_FLOOR_BPS` of the
+    /// SOL collateral. Checked when the floor is set and whenever SOL is withdrawn
+    pub fn gad_floor_in_bounds(&self) -> bool {
+        let sol = self
+            .collaterals
+            .iter()
+            .find(|c| c.asset_type == AssetType::SOL)
+            .map_or(0, |c| c.amount);
+        self.gad_collateral_floor as u128
+            <= sol as u128 * MAX_GAD_COLLATERAL_FLOOR_BPS as u128 / BPS_DENOMINATOR as u128
+    }
+
+    /// Take `amount_usd` of debt off the borrows in order, interest first, EURC at
+    /// `eur_usd_price`. Unlike `apply_repayment` this earns no reputation
+    pub fn reduce_debt_usd(&mut self, amount_usd: u64, eur_usd_price: Option<u64>) -> Result<()> {
+        let mut remaining_usd = amount_usd;
+        for borrow in self.borrows.iter_mut() {
+            if remaining_usd == 0 {
+                break;
+            }
+            let owed = borrow
+                .amount
+                .checked_add(borrow.accrued_interest)
+                .ok_or(LegasiError::MathOverflow)?;
+            let owed_usd = borrow.asset_type.debt_to_usd(owed, eur_usd_price)?;
+            let reduction_usd = std::cmp::min(remaining_usd, owed_usd);
+            let reduction = if reduction_usd == owed_usd {
+                owed
+            } else {
+                borrow
+                    .asset_type
+                    .usd_to_debt(reduction_usd, eur_usd_price)?
+            };
+
+            let interest = std::cmp::min(reduction, borrow.accrued_interest);
+            borrow.accrued_interest -= interest;
+            borrow.amount = borrow.amount.saturating_sub(reduction - interest);
+            remaining_usd -= reduction_usd;
+        }
+        self.borrows
+            .retain(|b| b.amount > 0 || b.accrued_interest > 0);
+        Ok(())
+    }
+
+    /// Book `settlement` at `now`. The collateral sold has already left the position
+    /// with its vault. Returns the (interest, principal) an `Asset` reduction took
+    pub fn apply_gad_settlement(
+        &mut self,
+        settlement: &GadSettlement,
+        now: i64,
+    ) -> Result<(u64, u64)> {
+        let repaid = match settlement.debt {
+            GadDebtReduction::None => (0, 0),
+            GadDebtReduction::Usd {
+                amount_usd,
+                eur_usd_price,
+            } => {
+                self.reduce_debt_usd(amount_usd, eur_usd_price)?;
+                (0, 0)
+            }
+            GadDebtReduction::Asset { asset_type, amount } => {
+                if self.borrows.iter().any(|b| b.asset_type == asset_type) {
+                    self.reduce_debt(asset_type, amount)?
+                } else {
+                    (0, 0)
+                }
+            }
+        };
+
+        self.total_gad_liquidated_usd = self
+            .total_gad_liquidated_usd
+            .saturating_add(settlement.liquidated_usd);
+        if let Some(carried_secs) = settlement.carried_secs {
+            self.last_gad_crank = now;
+            self.gad_carried_secs = carried_secs;
+            self.reputation.gad_events = self.reputation.gad_events.saturating_add(1);
+        }
+        self.last_update = now;
+        Ok(repaid)
+    }
+}
+
+/// CPI into `set_gad_config`, signed by GAD's protocol writer PDA
+pub fn set_gad_config<'info>(
+    lending_program: &AccountInfo<'info>,
+    accounts: crate::cpi::accounts::SetGadConfig<'info>,
+    writer_bump: u8,
+    enabled: bool,
+    min_collateral_floor: u64,
+) -> Result<()> {
+    crate::cpi::set_gad_config(
+        CpiContext::new_with_signer(
+            lending_program.clone(),
+            accounts,
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        enabled,
+        min_collateral_floor,
+    )
+}
+
+/// CPI into `release_gad_collateral`, signed by GAD's protocol writer PDA (no-op for 0)
+pub fn release_gad_collateral<'info>(
+    lending_program: &AccountInfo<'info>,
+    accounts: crate::cpi::accounts::ReleaseGadCollateral<'info>,
+    writer_bump: u8,
+    asset_type: AssetType,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    crate::cpi::release_gad_collateral(
+        CpiContext::new_with_signer(
+            lending_program.clone(),
+            accounts,
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        asset_type,
+        amount,
+    )
+}
+
+/// CPI into `swap_gad_collateral`, signed by GAD's protocol writer PDA, with the route
+/// accounts passed through
+#[allow(clippy::too_many_arguments)]
+pub fn swap_gad_collateral<'info>(
+    lending_program: &AccountInfo<'info>,
+    accounts: crate::cpi::accounts::SwapGadCollateral<'info>,
+    route_accounts: &[AccountInfo<'info>],
+    writer_bump: u8,
+    asset_type: AssetType,
+    route: SwapRoute,
+    route_data: Vec<u8>,
+    max_in: u64,
+) -> Result<()> {
+    crate::cpi::swap_gad_collateral(
+        CpiContext::new_with_signer(
+            lending_program.clone(),
+            accounts,
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        )
+        .with_remaining_accounts(route_accounts.to_vec()),
+        asset_type,
+        route,
+        route_data,
+        max_in,
+    )
+}
+
+/// CPI into `settle_gad`, signed by GAD's protocol writer PDA, with the position's
+/// debt pools passed through (see `accrue_all_interest`)
+pub fn settle_gad<'info>(
+    lending_program: &AccountInfo<'info>,
+    accounts: crate::cpi::accounts::SettleGad<'info>,
+    debt_pools: &[AccountInfo<'info>],
+    writer_bump: u8,
+    settlement: GadSettlement,
+) -> Result<()> {
+    crate::cpi::settle_gad(
+        CpiContext::new_with_signer(
+            lending_program.clone(),
+            accounts,
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        )
+        .with_remaining_accounts(debt_pools.to_vec()),
+        settlement,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BorrowedAmount, CollateralDeposit, Reputation};
+    use legasi_core::interest::BORROW_INDEX_PRECISION;
+
+    const EUR_USD: Option<u64> = Some(1_080_000);
+
+    fn position(borrows: Vec<BorrowedAmount>) -> Position {
+        Position {
+            owner: Pubkey::default(),
+            collaterals: vec![],
+            borrows,
+            last_update: 0,
+            last_gad_crank: 0,
+            gad_enabled: true,
+            total_gad_liquidated_usd: 0,
+            reputation: Reputation::default(),
+            stake_provider: Default::default(),
+            gad_carried_secs: 0,
+            max_debt_usd: 0,
+            referrer: Pubkey::default(),
+            committed_letters_usd: 0,
+            emode: Default::default(),
+            statement_totals: Default::default(),
+            gad_collateral_floor: 0,
+            bump: 0,
+        }
+    }
+
+    fn borrow(asset_type: AssetType, amount: u64, interest: u64) -> BorrowedAmount {
+        let mut borrow = BorrowedAmount::new(asset_type, amount, BORROW_INDEX_PRECISION, 0);
+        borrow.accrued_interest = interest;
+        borrow
+    }
+
+    #[test]
+    fn test_gad_floor_is_capped_at_half_the_sol() {
+        let mut position = position(vec![]);
+        position.collaterals.push(CollateralDeposit {
+            asset_type: AssetType::SOL,
+            amount: 10_000_000_000,
+        });
+        position.gad_collateral_floor = 5_000_000_000;
+        assert!(position.gad_floor_in_bounds());
+        position.gad_collateral_floor += 1;
+        assert!(!position.gad_floor_in_bounds());
+
+        // Other collateral doesn't count towards the cap
+        position.collaterals[0].asset_type = AssetType::MSOL;
+        position.gad_collateral_floor = 1;
+        assert!(!position.gad_floor_in_bounds());
+        position.gad_collateral_floor = 0;
+        assert!(position.gad_floor_in_bounds());
+    }
+
+    #[test]
+    fn test_usd_reduction_runs_across_borrows_interest_first() {
+        let mut position = position(vec![
+            borrow(AssetType::USDC, 50_000_000, 1_000_000),
+            borrow(AssetType::EURC, 100_000_000, 0),
+        ]);
+        // $51 clears the USDC entry, the next $10.80 is 10 EURC
+        position.reduce_debt_usd(61_800_000, EUR_USD).unwrap();
+        assert_eq!(position.borrows.len(), 1);
+        assert_eq!(position.borrows[0].asset_type, AssetType::EURC);
+        assert_eq!(position.borrows[0].amount, 90_000_000);
+
+        // Without the EUR/USD price EURC debt can't be valued
+        assert!(position.reduce_debt_usd(1, None).is_err());
+    }
+
+    #[test]
+    fn test_crank_settlement_stamps_the_crank() {
+        let mut position = position(vec![borrow(AssetType::USDC, 50_000_000, 2_000_000)]);
+        let settlement = GadSettlement {
+            debt: GadDebtReduction::Usd {
+                amount_usd: 3_000_000,
+                eur_usd_price: None,
+            },
+            liquidated_usd: 4_000_000,
+            carried_secs: Some(600),
+        };
+        assert_eq!(
+            position.apply_gad_settlement(&settlement, 100).unwrap(),
+            (0, 0)
+        );
+        assert_eq!(position.borrows[0].accrued_interest, 0);
+        assert_eq!(position.borrows[0].amount, 49_000_000);
+        assert_eq!(position.total_gad_liquidated_usd, 4_000_000);
+        assert_eq!(position.last_gad_crank, 100);
+        assert_eq!(position.gad_carried_secs, 600);
+        assert_eq!(position.reputation.gad_events, 1);
+    }
+
+    #[test]
+    fn test_asset_settlement_returns_the_split() {
+        let mut position = position(vec![borrow(AssetType::USDC, 50_000_000, 2_000_000)]);
+        let fill = GadSettlement {
+            debt: GadDebtReduction::Asset {
+                asset_type: AssetType::USDC,
+                amount: 5_000_000,
+            },
+            liquidated_usd: 5_000_000,
+            carried_secs: None,
+        };
+        assert_eq!(
+            position.apply_gad_settlement(&fill, 100).unwrap(),
+            (2_000_000, 3_000_000)
+        );
+        // An auction fill is not a crank
+        assert_eq!(position.last_gad_crank, 0);
+        assert_eq!(position.reputation.gad_events, 0);
+
+        // Debt in an asset the position no longer owes repays nothing
+        let fill = GadSettlement {
+            debt: GadDebtReduction::Asset {
+                asset_type: AssetType::EURC,
+                amount: 5_000_000,
+            },
+            ..fill
+        };
+        assert_eq!(position.apply_gad_settlement(&fill, 100).unwrap(), (0, 0));
+    }
+}
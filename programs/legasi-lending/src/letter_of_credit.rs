@@ -0,0 +1,53 @@
+//! Letters of credit
+//!
+//! The owner commits to a borrow in favor of a named beneficiary, who can claim it
+//! until expiry. Nothing is lent at issue: the claim borrows against the position's
+//! collateral and pays the beneficiary. Until then the commitment is reserved like
+//! debt (`Position.committed_letters_usd`), so borrows and withdrawals can't use the
+//! collateral behind it.
+//!
+//! Flow:
+//! 1. Owner calls issue_letter_of_credit (collateral must back debt + every open letter)
+//! 2. Beneficiary calls claim_letter_of_credit before expiry
+//! 3. Beneficiary can release it at any time, the owner only once it has expired
+
+use anchor_lang::prelude::*;
+use legasi_core::state::AssetType;
+
+use crate::Position;
+
+/// Borrow commitment in favor of a beneficiary
+#[account]
+#[derive(InitSpace)]
+pub struct LetterOfCredit {
+    pub position: Pubkey,
+    pub beneficiary: Pubkey,
+    pub letter_id: u64,
+    pub asset_type: AssetType,
+    /// Paid to the beneficiary on claim (asset units)
+    pub amount: u64,
+    /// USD value reserved on the position at issue (6 decimals)
+    pub committed_usd: u64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl LetterOfCredit {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expires_at
+    }
+
+    /// Reserve the letter on `position`
+    pub fn commit(&self, position: &mut Position) {
+        position.committed_letters_usd = position
+            .committed_letters_usd
+            .saturating_add(self.committed_usd);
+    }
+
+    /// Release the letter's reservation on `position` (claimed or closed)
+    pub fn release(&self, position: &mut Position) {
+        position.committed_letters_usd = position
+            .committed_letters_usd
+            .saturating_sub(self.committed_usd);
+    }
+}
@@ -1,17 +1,81 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program::invoke;
 use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::program_option::COption;
 use anchor_lang::solana_program::system_instruction;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
 
 // Import only read-only types from core (not Position, AgentConfig, etc. which are init'ed here)
+use legasi_core::program::LegasiCore;
 use legasi_core::{
+    caps::AssetCaps,
+    cctp,
     constants::*,
+    decimals::{sol_to_usd, usd_to_lamports_ceil},
     errors::LegasiError,
-    state::{Protocol, Borrowable, Collateral, PriceFeed, AssetType, LpPool},
+    events::{
+        BadDebtSocialized, Borrowed, DepositLotRecorded, EModeSet, FixedTermBorrowed,
+        PositionLiquidated, Repaid,
+    },
+    gad,
+    gad_schedule::Throttle,
+    gate,
+    interest::{calculate_insurance_fee, calculate_repay_incentive, interest_since_index},
+    jupiter_cpi,
+    market::{EModeCategory, Market, UserEMode},
+    seeds::*,
+    state::{AssetType, Borrowable, Collateral, PriceFeed, Protocol, StakeProvider},
+    swap_router::SwapRoute,
+    totals,
+    valuation::{self, PriceBook},
 };
-
+use legasi_lp::{program::LegasiLp, LpPool};
+
+pub mod agent_profile;
+pub mod auto_deleverage;
+pub mod credit_line;
+pub mod debt_conversion;
+pub mod deleverage;
+pub mod deposit_receipt;
+pub mod emode;
+pub mod fixed_term;
+pub mod gad_settlement;
+pub mod letter_of_credit;
+pub mod liquidation;
+pub mod marinade;
+pub mod notify;
+pub mod points;
+pub mod rate_limit;
+pub mod referral;
+pub mod schedule;
+pub mod solana_pay;
+pub mod statement;
+pub mod succession;
+pub mod voting;
+pub mod withdrawal_allowlist;
 pub mod x402;
+pub use agent_profile::*;
+pub use auto_deleverage::*;
+pub use credit_line::*;
+pub use debt_conversion::*;
+pub use deleverage::*;
+pub use deposit_receipt::*;
+pub use emode::*;
+pub use fixed_term::*;
+pub use gad_settlement::*;
+pub use letter_of_credit::*;
+pub use liquidation::*;
+pub use notify::*;
+pub use points::*;
+pub use rate_limit::*;
+pub use referral::*;
+pub use schedule::*;
+pub use solana_pay::*;
+pub use statement::*;
+pub use succession::*;
+pub use voting::*;
+pub use withdrawal_allowlist::*;
 pub use x402::*;
 
 declare_id!("9356RoSbLTzWE55ab6GktcTocaNhPuBEDZvsmqjkCZYw");
@@ -33,9 +97,265 @@ pub struct Position {
     pub gad_enabled: bool,
     pub total_gad_liquidated_usd: u64,
     pub reputation: Reputation,
+    pub stake_provider: StakeProvider,
+    /// GAD liquidation time (seconds) a market selling over several cranks carried to
+    /// the next crank (see `legasi_core::gad::pace_sale`). Sits in the prefix GAD shares
+    /// with `legasi_core::state::Position`
+    pub gad_carried_secs: i64,
+    /// Owner's hard cap on total debt in USD (6 decimals), 0 = no cap
+    pub max_debt_usd: u64,
+    /// Referrer paid a share of the interest on repay (default = none)
+    pub referrer: Pubkey,
+    /// USD value of open letters of credit (6 decimals), reserved like debt
+    pub committed_letters_usd: u64,
+    /// Owner's eMode category, applied by markets of the same category (see `emode`)
+    pub emode: UserEMode,
+    /// Running interest and fee totals statements are measured from (see `statement`)
+    pub statement_totals: StatementTotals,
+    /// SOL collateral (lamports) GAD cranks leave on the position, at most
+    /// `MAX_GAD_COLLATERAL_FLOOR_BPS` of it (see `gad_settlement`)
+    pub gad_collateral_floor: u64,
     pub bump: u8,
 }
 
+impl Position {
+    /// Collateral value in USD (6 decimals) at `sol_price`, each asset priced by
+    /// `legasi_core::valuation` (cbBTC needs its own feed, so it counts as zero here)
+    pub fn collateral_value_usd(&self, sol_price: u64) -> Result<u64> {
+        self.value_collateral(&PriceBook::new(sol_price, None))
+    }
+
+    /// Collateral value in USD (6 decimals) against `book`
+    pub fn value_collateral(&self, book: &PriceBook) -> Result<u64> {
+        valuation::total_collateral_value_usd(
+            self.collaterals.iter().map(|c| (c.asset_type, c.amount)),
+            book,
+        )
+    }
+
+    /// Total debt (principal + interest) across all borrows
+    pub fn total_debt(&self) -> Result<u64> {
+        let mut total: u64 = 0;
+        for borrow in &self.borrows {
+            total = total
+                .checked_add(borrow.amount)
+                .and_then(|t| t.checked_add(borrow.accrued_interest))
+                .ok_or(LegasiError::MathOverflow)?;
+        }
+        Ok(total)
+    }
+
+    /// Total debt (principal + interest) in USD (6 decimals), EURC at `eur_usd_price`
+    pub fn debt_usd(&self, eur_usd_price: Option<u64>) -> Result<u64> {
+        let mut total: u64 = 0;
+        for borrow in &self.borrows {
+            let owed = borrow
+                .amount
+                .checked_add(borrow.accrued_interest)
+                .ok_or(LegasiError::MathOverflow)?;
+            total = total
+                .checked_add(borrow.asset_type.debt_to_usd(owed, eur_usd_price)?)
+                .ok_or(LegasiError::MathOverflow)?;
+        }
+        Ok(total)
+    }
+
+    /// Max total debt in USD (6 decimals) at the SOL max LTV plus the reputation bonus
+    pub fn max_borrow_usd(&self, sol_price: u64) -> Result<u64> {
+        self.max_borrow_usd_at(&PriceBook::new(sol_price, None), DEFAULT_SOL_MAX_LTV_BPS)
+    }
+
+    /// Max total debt in USD (6 decimals) against `book` at `max_ltv_bps` plus the
+    /// reputation bonus
+    pub fn max_borrow_usd_at(&self, book: &PriceBook, max_ltv_bps: u16) -> Result<u64> {
+        let effective_ltv =
+            (max_ltv_bps as u64).saturating_add(self.reputation.get_ltv_bonus_bps() as u64);
+        Ok((self.value_collateral(book)? as u128)
+            .checked_mul(effective_ltv as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(LegasiError::MathOverflow)? as u64)
+    }
+
+    /// Current LTV in bps at `sol_price`, `u64::MAX` for debt without collateral
+    pub fn ltv_bps(&self, sol_price: u64, eur_usd_price: Option<u64>) -> Result<u64> {
+        let debt = self.debt_usd(eur_usd_price)?;
+        if debt == 0 {
+            return Ok(0);
+        }
+        Ok(gad::ltv_bps(debt, self.collateral_value_usd(sol_price)?).unwrap_or(u64::MAX))
+    }
+
+    /// Fails if a total debt of `new_debt_usd` would break the owner's debt cap
+    pub fn require_within_debt_cap(&self, new_debt_usd: u64) -> Result<()> {
+        require!(
+            self.max_debt_usd == 0 || new_debt_usd <= self.max_debt_usd,
+            LegasiError::ExceedsDebtCap
+        );
+        Ok(())
+    }
+
+    /// Fails unless borrowing `amount` more of `asset_type` keeps the position within
+    /// `max_borrow_usd` and the owner's debt cap, with open letters of credit counted
+    /// as debt. Every borrow path goes through here
+    pub fn require_within_ltv(
+        &self,
+        asset_type: AssetType,
+        amount: u64,
+        sol_price: u64,
+        eur_usd_price: Option<u64>,
+    ) -> Result<()> {
+        self.require_within_ltv_at(
+            asset_type,
+            amount,
+            &PriceBook::new(sol_price, eur_usd_price),
+            DEFAULT_SOL_MAX_LTV_BPS,
+        )
+    }
+
+    /// `require_within_ltv` against `book` at `max_ltv_bps` instead of the SOL
+    /// default, for borrows priced by a market (see `emode::max_ltv_bps`) or crediting
+    /// LST yield (see `marinade`)
+    pub fn require_within_ltv_at(
+        &self,
+        asset_type: AssetType,
+        amount: u64,
+        book: &PriceBook,
+        max_ltv_bps: u16,
+    ) -> Result<()> {
+        let eur_usd_price = book.eur_usd_price;
+        let amount_usd = asset_type.debt_to_usd(amount, eur_usd_price)?;
+        let new_total_borrow = self
+            .debt_usd(eur_usd_price)?
+            .checked_add(self.committed_letters_usd)
+            .and_then(|total| total.checked_add(amount_usd))
+            .ok_or(LegasiError::MathOverflow)?;
+        require!(
+            new_total_borrow <= self.max_borrow_usd_at(book, max_ltv_bps)?,
+            LegasiError::ExceedsLTV
+        );
+        self.require_within_debt_cap(new_total_borrow)
+    }
+
+    /// Settle interest on the `asset_type` borrow up to `lp_pool`'s borrow index at
+    /// `now`, then stamp `last_update`. Run before anything that reads or changes that
+    /// borrow; the index is global, so no position needs cranking in between
+    pub fn accrue_interest(
+        &mut self,
+        asset_type: AssetType,
+        lp_pool: &LpPool,
+        now: i64,
+    ) -> Result<()> {
+        let borrow_index = lp_pool.borrow_index_at(now)?;
+        for borrow in self
+            .borrows
+            .iter_mut()
+            .filter(|b| b.asset_type == asset_type)
+        {
+            let before = borrow.accrued_interest;
+            borrow.accrue(borrow_index, now)?;
+            self.statement_totals
+                .record_interest(asset_type, borrow.accrued_interest - before);
+        }
+        self.last_update = now;
+        Ok(())
+    }
+
+    /// Outstanding debt (principal + interest) for a borrowed asset
+    pub fn total_owed(&self, asset_type: AssetType) -> Result<u64> {
+        for borrow in &self.borrows {
+            if borrow.asset_type == asset_type {
+                return Ok(borrow
+                    .amount
+                    .checked_add(borrow.accrued_interest)
+                    .ok_or(LegasiError::MathOverflow)?);
+            }
+        }
+        Ok(0)
+    }
+
+    /// Start the reputation debt clock if the position has no debt yet
+    /// Call before adding a borrow
+    pub fn start_debt_clock(&mut self, now: i64) -> Result<()> {
+        if self.total_debt()? == 0 {
+            self.reputation.debt_since = now;
+        }
+        Ok(())
+    }
+
+    /// Apply a repayment (interest first, then principal) and record it in reputation
+    /// Returns the part of `repay_amount` that paid interest
+    pub fn apply_repayment(&mut self, asset_type: AssetType, repay_amount: u64, now: i64) -> u64 {
+        let debt_before = self.total_debt().unwrap_or(u64::MAX);
+        let mut interest_payment = 0;
+        for borrow in self.borrows.iter_mut() {
+            if borrow.asset_type == asset_type {
+                interest_payment = std::cmp::min(repay_amount, borrow.accrued_interest);
+                borrow.accrued_interest = borrow.accrued_interest.saturating_sub(interest_payment);
+                let principal = repay_amount.saturating_sub(interest_payment);
+                borrow.amount = borrow.amount.saturating_sub(principal);
+                break;
+            }
+        }
+
+        // Remove empty borrows
+        self.borrows
+            .retain(|b| b.amount > 0 || b.accrued_interest > 0);
+
+        self.reputation
+            .record_repayment(repay_amount, debt_before, now);
+        self.last_update = now;
+        interest_payment
+    }
+
+    /// Take up to `amount` off the `asset_type` borrow (interest first), dropping it once
+    /// empty. Unlike `apply_repayment` this earns no reputation
+    /// Returns the (interest, principal) taken
+    pub fn reduce_debt(&mut self, asset_type: AssetType, amount: u64) -> Result<(u64, u64)> {
+        let borrow = self
+            .borrows
+            .iter_mut()
+            .find(|b| b.asset_type == asset_type)
+            .ok_or(LegasiError::PositionNotFound)?;
+        let interest = std::cmp::min(amount, borrow.accrued_interest);
+        let principal = std::cmp::min(amount - interest, borrow.amount);
+        borrow.accrued_interest -= interest;
+        borrow.amount -= principal;
+        self.borrows
+            .retain(|b| b.amount > 0 || b.accrued_interest > 0);
+        Ok((interest, principal))
+    }
+
+    /// Take `lamports` of SOL collateral off the position (sold or withdrawn)
+    pub fn remove_sol_collateral(&mut self, lamports: u64) -> Result<()> {
+        self.remove_collateral(AssetType::SOL, lamports)
+    }
+
+    /// Take `amount` of `asset_type` collateral off the position (sold or withdrawn),
+    /// clearing the stake provider with the last staked SOL
+    pub fn remove_collateral(&mut self, asset_type: AssetType, amount: u64) -> Result<()> {
+        let deposit = self
+            .collaterals
+            .iter_mut()
+            .find(|c| c.asset_type == asset_type)
+            .ok_or(LegasiError::InsufficientCollateral)?;
+        deposit.amount = deposit
+            .amount
+            .checked_sub(amount)
+            .ok_or(LegasiError::InsufficientCollateral)?;
+        self.collaterals.retain(|c| c.amount > 0);
+        if asset_type == AssetType::MSOL
+            && !self
+                .collaterals
+                .iter()
+                .any(|c| c.asset_type == AssetType::MSOL)
+        {
+            self.stake_provider = StakeProvider::None;
+        }
+        Ok(())
+    }
+}
+
 /// Single collateral deposit entry
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
 pub struct CollateralDeposit {
@@ -49,6 +369,57 @@ pub struct BorrowedAmount {
     pub asset_type: AssetType,
     pub amount: u64,
     pub accrued_interest: u64,
+    /// Pool borrow index `accrued_interest` is settled up to
+    pub borrow_index: u128,
+    /// Interest on this entry is accrued up to here
+    pub last_accrued: i64,
+    /// Rate (bps APR) owed until `maturity` on a fixed-term loan (see `fixed_term`)
+    pub fixed_rate_bps: u16,
+    /// When a fixed-term loan rolls to the variable rate plus penalty (0 = variable)
+    pub maturity: i64,
+}
+
+impl BorrowedAmount {
+    /// New debt entry owing interest from the pool's `borrow_index` on
+    pub fn new(asset_type: AssetType, amount: u64, borrow_index: u128, now: i64) -> Self {
+        Self {
+            asset_type,
+            amount,
+            accrued_interest: 0,
+            borrow_index,
+            last_accrued: now,
+            fixed_rate_bps: 0,
+            maturity: 0,
+        }
+    }
+
+    /// True for a fixed-term loan, before or past maturity
+    pub fn is_fixed_term(&self) -> bool {
+        self.maturity != 0
+    }
+
+    /// Book the interest the pool's index gained since the snapshot (on a fixed-term
+    /// loan, its fixed rate up to maturity instead), then move the snapshot up to
+    /// `borrow_index`
+    pub fn accrue(&mut self, borrow_index: u128, now: i64) -> Result<()> {
+        let mut interest = interest_since_index(self.amount, self.borrow_index, borrow_index)
+            .ok_or(LegasiError::MathOverflow)?;
+        if self.is_fixed_term() {
+            interest = term_interest(
+                self.amount,
+                self.fixed_rate_bps,
+                self.maturity,
+                self.last_accrued,
+                now,
+                interest,
+            )
+            .ok_or(LegasiError::MathOverflow)?;
+        }
+        self.accrued_interest = self.accrued_interest.saturating_add(interest);
+        self.borrow_index = std::cmp::max(self.borrow_index, borrow_index);
+        self.last_accrued = now;
+        Ok(())
+    }
 }
 
 /// On-chain reputation score
@@ -58,9 +429,32 @@ pub struct Reputation {
     pub total_repaid_usd: u64,
     pub gad_events: u32,
     pub account_age_days: u32,
+    /// Last repayment that counted towards `successful_repayments`
+    pub last_credited_at: i64,
+    /// When the position last went from no debt to some debt
+    pub debt_since: i64,
 }
 
 impl Reputation {
+    /// Record a repayment of `repay_amount` against `debt_before` (total debt)
+    /// It only counts towards the score if it is a meaningful share of the debt, the debt
+    /// has been open for a while, and nothing was credited in the last interval, so
+    /// looping tiny borrow/repay pairs can't farm LTV bonus
+    pub fn record_repayment(&mut self, repay_amount: u64, debt_before: u64, now: i64) {
+        self.total_repaid_usd = self.total_repaid_usd.saturating_add(repay_amount);
+
+        let min_repay = ((debt_before as u128) * (MIN_REPUTATION_REPAY_BPS as u128)
+            / (BPS_DENOMINATOR as u128)) as u64;
+        let earns_credit = repay_amount > 0
+            && repay_amount >= min_repay
+            && now.saturating_sub(self.debt_since) >= MIN_REPUTATION_DEBT_AGE
+            && now.saturating_sub(self.last_credited_at) >= REPUTATION_CREDIT_INTERVAL;
+        if earns_credit {
+            self.successful_repayments = self.successful_repayments.saturating_add(1);
+            self.last_credited_at = now;
+        }
+    }
+
     pub fn get_score(&self) -> u32 {
         let base = std::cmp::min(self.successful_repayments * 50, 500);
         let age_bonus = std::cmp::min(self.account_age_days / 30 * 10, 100);
@@ -85,6 +479,7 @@ impl Reputation {
 pub struct AgentConfig {
     pub position: Pubkey,
     pub operator: Pubkey,
+    /// Daily cap on agent borrows across pools, in USD (6 decimals)
     pub daily_borrow_limit: u64,
     pub daily_borrowed: u64,
     pub period_start: i64,
@@ -92,6 +487,8 @@ pub struct AgentConfig {
     pub x402_enabled: bool,
     pub alerts_enabled: bool,
     pub alert_threshold_bps: u16,
+    /// Minimum spacing of agent borrows and payments (see `rate_limit`)
+    pub activity_limit: ActivityLimit,
     pub bump: u8,
 }
 
@@ -105,6 +502,11 @@ impl AgentConfig {
         self.daily_borrowed.saturating_add(amount) <= self.daily_borrow_limit
     }
 
+    /// True if alerts are on and `ltv_bps` is above the alert threshold
+    pub fn alert_breached(&self, ltv_bps: u64) -> bool {
+        self.alerts_enabled && ltv_bps > self.alert_threshold_bps as u64
+    }
+
     /// Record a borrow against daily limit
     pub fn record_borrow(&mut self, amount: u64, current_time: i64) {
         let seconds_per_day: i64 = 86400;
@@ -127,21 +529,759 @@ pub struct X402Receipt {
     pub amount: u64,
     pub paid_at: i64,
     pub tx_signature: [u8; 64],
+    /// Integrator's invoice / memo hash, if given
+    pub reference: Option<[u8; 32]>,
+    /// Payer's `AgentProfile`, if it was passed
+    pub agent_profile: Option<Pubkey>,
     pub bump: u8,
 }
 
+/// Repayments below this share of total debt (bps) earn no reputation
+const MIN_REPUTATION_REPAY_BPS: u64 = 1_000;
+/// Debt must be open this long before repaying it earns reputation
+const MIN_REPUTATION_DEBT_AGE: i64 = SECONDS_PER_DAY;
+/// At most one reputation-earning repayment per interval
+const REPUTATION_CREDIT_INTERVAL: i64 = SECONDS_PER_DAY;
+
+/// USD value of an open credit line's unused limit and of open letters of credit,
+/// counted as debt when withdrawing
+fn committed_debt_usd(
+    credit_line: &AccountInfo,
+    position: &Position,
+    eur_usd_price: Option<u64>,
+) -> Result<u64> {
+    let unused_line = match load_credit_line(credit_line)? {
+        Some(line) => line
+            .asset_type
+            .debt_to_usd(line.unused(position), eur_usd_price)?,
+        None => 0,
+    };
+    Ok(unused_line.saturating_add(position.committed_letters_usd))
+}
+
+/// EUR/USD price (6 decimals) from the optional EURC price feed, for valuing EURC debt.
+/// Fails on a stale feed
+fn eur_usd_price(feed: &Option<Box<Account<PriceFeed>>>, now: i64) -> Result<Option<u64>> {
+    feed.as_ref()
+        .map(|feed| feed.get_checked_price(now))
+        .transpose()
+}
+
+/// Fails while the circuit breaker of a feed pricing the position's collateral is
+/// tripped: SOL and mSOL at the SOL feed, cbBTC at its own feed when passed
+fn require_breakers_clear(
+    position: &Position,
+    sol_price_feed: &PriceFeed,
+    cbbtc_price_feed: &Option<Box<Account<PriceFeed>>>,
+    now: i64,
+) -> Result<()> {
+    for deposit in &position.collaterals {
+        let feed = match deposit.asset_type {
+            AssetType::CbBTC => cbbtc_price_feed.as_deref().map(|feed| &**feed),
+            _ => Some(sol_price_feed),
+        };
+        if let Some(feed) = feed {
+            feed.circuit_breaker.require_clear(now)?;
+        }
+    }
+    Ok(())
+}
+
+/// Price book for a borrow's LTV check: SOL, EUR/USD, cbBTC at its own feed when
+/// passed and, when the Marinade State is passed, the mSOL rate with its unrealized
+/// staking yield credited less the haircut. Fails on a stale cbBTC feed
+fn borrow_price_book(
+    sol_price: u64,
+    eur_usd_price: Option<u64>,
+    cbbtc_price_feed: &Option<Box<Account<PriceFeed>>>,
+    marinade_state: &Option<UncheckedAccount>,
+    now: i64,
+) -> Result<PriceBook> {
+    let mut book = PriceBook::new(sol_price, eur_usd_price);
+    if let Some(feed) = cbbtc_price_feed {
+        feed.require_fresh(now)?;
+        book = book.with_feed(feed);
+    }
+    Ok(match read_msol_rate(marinade_state)? {
+        Some(rate) => book.with_lst_yield(AssetType::MSOL, rate),
+        None => book,
+    })
+}
+
+/// mSOL rate (`RATE_PRECISION`) from the Marinade State, when passed
+fn read_msol_rate(marinade_state: &Option<UncheckedAccount>) -> Result<Option<u64>> {
+    let Some(state) = marinade_state else {
+        return Ok(None);
+    };
+    let rate = marinade::parse_msol_price(&state.try_borrow_data()?)
+        .and_then(marinade::msol_rate)
+        .ok_or(LegasiError::InvalidOracle)?;
+    Ok(Some(rate))
+}
+
+/// Settle every borrow on `position` against its pool's borrow index, for checks that
+/// read the whole debt without a pool of their own (GAD runs it on its copy of the
+/// position). `pools` (remaining accounts) holds a `Borrowable`, `LpPool` pair per
+/// borrowed asset; a borrow left out fails rather than count at a stale value
+pub fn accrue_all_interest(position: &mut Position, pools: &[AccountInfo], now: i64) -> Result<()> {
+    require!(pools.len() % 2 == 0, LegasiError::MissingDebtPool);
+    for pair in pools.chunks(2) {
+        require_keys_eq!(
+            *pair[0].owner,
+            legasi_core::ID,
+            LegasiError::MissingDebtPool
+        );
+        require_keys_eq!(*pair[1].owner, legasi_lp::ID, LegasiError::MissingDebtPool);
+        let borrowable = Borrowable::try_deserialize(&mut &pair[0].try_borrow_data()?[..])?;
+        let lp_pool = LpPool::try_deserialize(&mut &pair[1].try_borrow_data()?[..])?;
+        require_keys_eq!(
+            lp_pool.borrowable_mint,
+            borrowable.mint,
+            LegasiError::MissingDebtPool
+        );
+        position.accrue_interest(borrowable.asset_type, &lp_pool, now)?;
+    }
+    require!(
+        position.borrows.iter().all(|b| b.last_accrued == now),
+        LegasiError::MissingDebtPool
+    );
+    Ok(())
+}
+
+/// Credit the interest part of a repayment (already in the LP vault) to bUSDC holders
+/// and book the insurance cut on the protocol
+fn credit_interest<'info>(
+    core_program: &AccountInfo<'info>,
+    protocol: &AccountInfo<'info>,
+    lp_program: &AccountInfo<'info>,
+    lp_pool: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    interest_paid: u64,
+) -> Result<()> {
+    legasi_lp::accrue_interest(lp_program, lp_pool, writer, writer_bump, interest_paid, 0)?;
+    totals::report_insurance_fee(
+        core_program,
+        protocol,
+        writer,
+        writer_bump,
+        calculate_insurance_fee(interest_paid),
+    )
+}
+
+/// Jupiter route `flash_liquidate` sells the seized SOL through
+struct LiquidationSwap<'a, 'info> {
+    jupiter_program: &'a AccountInfo<'info>,
+    route_accounts: &'a [AccountInfo<'info>],
+    swap: DeleverageSwap,
+}
+
+/// Liquidate up to `amount` of the position's `borrowable_config` debt for SOL
+/// collateral plus the bonus, paid from the liquidator's token account (filled first by
+/// `swap` when given). Shared by `liquidate_position` and `flash_liquidate`
+fn settle_liquidation<'info>(
+    accounts: &mut LiquidatePosition<'info>,
+    bumps: &LiquidatePositionBumps,
+    amount: u64,
+    swap: Option<LiquidationSwap<'_, 'info>>,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let asset_type = accounts.borrowable_config.asset_type;
+    // Priced at the TWAP, so one manipulated print can't make a position liquidatable
+    let sol_price = accounts.sol_price_feed.get_checked_twap(now)?;
+    let eur_price = accounts
+        .eur_price_feed
+        .as_ref()
+        .map(|feed| feed.get_checked_twap(now))
+        .transpose()?;
+    let max_ltv_bps = accounts.sol_collateral.max_ltv_bps;
+    let bonus_bps = accounts.sol_collateral.liquidation_bonus_bps;
+    let insurance_fund = accounts.protocol.insurance_fund;
+    // Like GAD cranks, never seize collateral on a flash-crash print
+    require!(
+        accounts.sol_price_feed.within_deviation_band(),
+        LegasiError::PriceDeviationTooHigh
+    );
+
+    let position = &mut accounts.position;
+    position.accrue_interest(asset_type, &accounts.lp_pool, now)?;
+    let ltv_before_bps = position.ltv_bps(sol_price, eur_price)?;
+    let throttle = Throttle::load(&accounts.gad_schedule, now)?;
+    require!(
+        ltv_before_bps >= throttle.hard_threshold_bps(liquidation_threshold_bps(max_ltv_bps)),
+        LegasiError::NotLiquidatable
+    );
+    let owed = position.total_owed(asset_type)?;
+    require!(owed > 0, LegasiError::PositionNotFound);
+
+    // Size the repayment against the SOL collateral, bonus included
+    let requested = std::cmp::min(amount, owed);
+    let requested_usd = asset_type.debt_to_usd(requested, eur_price)?;
+    let sol_collateral = position
+        .collaterals
+        .iter()
+        .find(|c| c.asset_type == AssetType::SOL)
+        .map_or(0, |c| c.amount);
+    let (repay_usd, seized) =
+        size_liquidation(requested_usd, sol_collateral, sol_price, bonus_bps)?;
+    let repaid = if repay_usd == requested_usd {
+        requested
+    } else {
+        std::cmp::min(asset_type.usd_to_debt(repay_usd, eur_price)?, requested)
+    };
+
+    let (interest_paid, principal_paid) = position.reduce_debt(asset_type, repaid)?;
+    if seized > 0 {
+        position.remove_sol_collateral(seized)?;
+    }
+    position.last_update = now;
+
+    // Nothing left to seize: the rest of this asset's debt is bad debt
+    let mut bad_debt = 0;
+    if position.collaterals.is_empty() {
+        let remaining = position.total_owed(asset_type)?;
+        if remaining > 0 {
+            bad_debt = position.reduce_debt(asset_type, remaining)?.1;
+        }
+    }
+    require!(
+        repaid > 0 || owed > position.total_owed(asset_type)?,
+        LegasiError::InvalidAmount
+    );
+
+    // With a swap the seized SOL is sold first and the proceeds pay the debt, so the
+    // liquidator fronts nothing; SOL the route doesn't sell is paid out as is
+    let position_key = accounts.position.key();
+    let vault_seeds: &[&[u8]] = &[SOL_VAULT_SEED, position_key.as_ref(), &[bumps.sol_vault]];
+    let mut seized_paid = seized;
+    if let Some(LiquidationSwap {
+        jupiter_program,
+        route_accounts,
+        swap,
+    }) = swap
+    {
+        let sol_before = accounts.sol_vault.lamports();
+        let out_before = accounts.liquidator_token_account.amount;
+        jupiter_cpi::swap(
+            jupiter_program,
+            route_accounts,
+            swap.route_data,
+            Some(accounts.sol_vault.key),
+            &[vault_seeds],
+        )?;
+        accounts.liquidator_token_account.reload()?;
+        jupiter_cpi::assert_min_received(
+            out_before,
+            accounts.liquidator_token_account.amount,
+            std::cmp::max(swap.min_out_amount, repaid),
+        )?;
+        let sold =
+            jupiter_cpi::assert_max_spent(sol_before, accounts.sol_vault.lamports(), seized)?;
+        seized_paid = seized - sold;
+    }
+
+    if repaid > 0 {
+        token::transfer(
+            CpiContext::new(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: accounts.liquidator_token_account.to_account_info(),
+                    to: accounts.repay_vault.to_account_info(),
+                    authority: accounts.liquidator.to_account_info(),
+                },
+            ),
+            repaid,
+        )?;
+    }
+    if seized_paid > 0 {
+        invoke_signed(
+            &system_instruction::transfer(
+                accounts.sol_vault.key,
+                accounts.liquidator.key,
+                seized_paid,
+            ),
+            &[
+                accounts.sol_vault.to_account_info(),
+                accounts.liquidator.to_account_info(),
+                accounts.system_program.to_account_info(),
+            ],
+            &[vault_seeds],
+        )?;
+    }
+
+    let core_program = accounts.core_program.to_account_info();
+    let protocol = accounts.protocol.to_account_info();
+    let lp_program = accounts.lp_program.to_account_info();
+    let lp_pool = accounts.lp_pool.to_account_info();
+    let writer = accounts.protocol_writer.to_account_info();
+    let writer_bump = bumps.protocol_writer;
+
+    // The insurance fund covers the written-off principal first, from what it still
+    // holds in the pool vault
+    let covered = accounts.lp_pool.insurance_cover(bad_debt, insurance_fund);
+    totals::report_insurance_payout(&core_program, &protocol, &writer, writer_bump, covered)?;
+    legasi_lp::write_off_bad_debt(
+        &lp_program,
+        &lp_pool,
+        &writer,
+        writer_bump,
+        bad_debt,
+        covered,
+    )?;
+    legasi_lp::report_borrowed(
+        &lp_program,
+        &lp_pool,
+        &writer,
+        writer_bump,
+        -totals::usd_delta(principal_paid),
+    )?;
+    credit_interest(
+        &core_program,
+        &protocol,
+        &lp_program,
+        &lp_pool,
+        &writer,
+        writer_bump,
+        interest_paid,
+    )?;
+    totals::report(
+        &core_program,
+        &protocol,
+        &writer,
+        writer_bump,
+        -totals::usd_delta(sol_to_usd(seized, sol_price)?),
+        -totals::usd_delta(asset_type.debt_to_usd(principal_paid + bad_debt, eur_price)?),
+    )?;
+    totals::report_bad_debt(
+        &core_program,
+        &protocol,
+        &writer,
+        writer_bump,
+        asset_type.debt_to_usd(bad_debt, eur_price)?,
+    )?;
+    totals::report_sol_collateral(
+        &core_program,
+        &protocol,
+        &writer,
+        writer_bump,
+        -(seized as i64),
+    )?;
+
+    emit!(PositionLiquidated {
+        position: accounts.position.key(),
+        liquidator: accounts.liquidator.key(),
+        asset_type,
+        repaid,
+        collateral_seized_lamports: seized,
+        ltv_before_bps,
+        bad_debt,
+    });
+
+    msg!(
+        "Liquidated: repaid {} {:?} for {} lamports, {} written off",
+        repaid,
+        asset_type,
+        seized,
+        bad_debt
+    );
+    Ok(())
+}
+
+/// Fixed rate and maturity of a borrow opened by `borrow_fixed_term`
+#[derive(Clone, Copy)]
+struct FixedTerm {
+    rate_bps: u16,
+    maturity: i64,
+}
+
+/// Lend `amount` of the borrowable to the owner after the gate, cap and LTV checks,
+/// adding it to the position's entry in that asset, or opening a fixed-term entry for
+/// `term`. Shared by `borrow` and `borrow_fixed_term`
+fn settle_borrow<'info>(
+    accounts: &mut Borrow<'info>,
+    bumps: &BorrowBumps,
+    amount: u64,
+    reference: Option<[u8; 32]>,
+    term: Option<FixedTerm>,
+) -> Result<()> {
+    require!(amount > 0, LegasiError::InvalidAmount);
+    accounts.protocol.require_not_paused(PAUSE_BORROWS)?;
+    require!(
+        accounts.borrowable_config.is_active,
+        LegasiError::AssetNotActive
+    );
+    gate::check_gate(
+        &accounts.market_gate,
+        accounts.gate_pass.as_deref(),
+        accounts.owner.key,
+    )?;
+    require!(
+        accounts.borrow_vault.amount >= amount,
+        LegasiError::InsufficientLiquidity
+    );
+    AssetCaps::load(&accounts.asset_caps)?
+        .require_borrow(accounts.lp_pool.total_borrowed, amount)?;
+
+    let asset_type = accounts.borrowable_config.asset_type;
+    let now = Clock::get()?.unix_timestamp;
+    accounts
+        .position
+        .accrue_interest(asset_type, &accounts.lp_pool, now)?;
+    require_breakers_clear(
+        &accounts.position,
+        &accounts.sol_price_feed,
+        &accounts.cbbtc_price_feed,
+        now,
+    )?;
+
+    let sol_price = accounts.sol_price_feed.get_checked_price(now)?;
+
+    // Check LTV (EURC debt valued at the EUR/USD price, cbBTC counted at its own
+    // feed and mSOL yield credited when passed), at the market's effective LTV when
+    // one is passed
+    let eur_price = eur_usd_price(&accounts.eur_price_feed, now)?;
+    let book = borrow_price_book(
+        sol_price,
+        eur_price,
+        &accounts.cbbtc_price_feed,
+        &accounts.marinade_state,
+        now,
+    )?;
+    let market = accounts.market.as_deref();
+    if let Some(market) = market {
+        require!(market.borrow_enabled, LegasiError::AssetNotActive);
+    }
+    let position = &accounts.position;
+    let max_ltv_bps =
+        emode::max_ltv_bps(position, market, position.emode.category, Some(asset_type))?;
+    position.require_within_ltv_at(asset_type, amount, &book, max_ltv_bps)?;
+
+    // Transfer tokens from the LP pool vault
+    legasi_lp::lend(
+        &accounts.lp_program.to_account_info(),
+        legasi_lp::cpi::accounts::Lend {
+            lp_pool: accounts.lp_pool.to_account_info(),
+            vault: accounts.borrow_vault.to_account_info(),
+            destination: accounts.user_token_account.to_account_info(),
+            writer: accounts.protocol_writer.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+        },
+        bumps.protocol_writer,
+        amount,
+    )?;
+
+    // Update position
+    let position = &mut accounts.position;
+    require!(position.gad_enabled, LegasiError::GadDisabled);
+    position.start_debt_clock(now)?;
+
+    // A fixed-term loan is its asset's only entry; variable borrows add to theirs
+    let existing = position
+        .borrows
+        .iter_mut()
+        .find(|b| b.asset_type == asset_type);
+    match (existing, term) {
+        (Some(borrow), None) if !borrow.is_fixed_term() => {
+            borrow.amount = borrow
+                .amount
+                .checked_add(amount)
+                .ok_or(LegasiError::MathOverflow)?;
+        }
+        (Some(_), _) => return err!(LegasiError::BorrowTermMismatch),
+        (None, term) => {
+            require!(
+                position.borrows.len() < MAX_BORROW_TYPES,
+                LegasiError::MaxBorrowTypesReached
+            );
+            let mut borrow = BorrowedAmount::new(
+                asset_type,
+                amount,
+                accounts.lp_pool.borrow_index_at(now)?,
+                now,
+            );
+            if let Some(term) = term {
+                borrow.fixed_rate_bps = term.rate_bps;
+                borrow.maturity = term.maturity;
+            }
+            position.borrows.push(borrow);
+        }
+    }
+
+    position.last_update = now;
+
+    totals::report(
+        &accounts.core_program.to_account_info(),
+        &accounts.protocol.to_account_info(),
+        &accounts.protocol_writer.to_account_info(),
+        bumps.protocol_writer,
+        0,
+        totals::usd_delta(asset_type.debt_to_usd(amount, eur_price)?),
+    )?;
+
+    emit!(Borrowed {
+        position: accounts.position.key(),
+        owner: accounts.owner.key(),
+        asset_type,
+        amount,
+        new_ltv_bps: accounts.position.ltv_bps(sol_price, eur_price)?,
+        reference,
+    });
+    if let Some(term) = term {
+        emit!(FixedTermBorrowed {
+            position: accounts.position.key(),
+            asset_type,
+            amount,
+            fixed_rate_bps: term.rate_bps,
+            maturity: term.maturity,
+        });
+    }
+
+    msg!("Borrowed {} {:?}", amount, asset_type);
+    Ok(())
+}
+
+/// Repay up to `amount` of the borrowed asset from the owner's token account: interest
+/// first, less the repay incentive, with the referral cut and insurance fee booked.
+/// Shared by `repay` and `deleverage_to_ltv`
+fn settle_repay<'info>(
+    accounts: &mut Repay<'info>,
+    bumps: &RepayBumps,
+    amount: u64,
+    reference: Option<[u8; 32]>,
+) -> Result<()> {
+    require!(amount > 0, LegasiError::InvalidAmount);
+
+    let asset_type = accounts.borrowable_config.asset_type;
+    accounts.position.accrue_interest(
+        asset_type,
+        &accounts.lp_pool,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let total_owed = accounts.position.total_owed(asset_type)?;
+    require!(total_owed > 0, LegasiError::PositionNotFound);
+
+    // Above optimal utilization part of the interest repaid is waived, to pull
+    // liquidity back into the pool. It comes out of the protocol fee, so there is
+    // none while the fee switch is off
+    let interest_due = accounts
+        .position
+        .borrows
+        .iter()
+        .find(|b| b.asset_type == asset_type)
+        .map_or(0, |b| b.accrued_interest);
+    let incentive = calculate_repay_incentive(
+        std::cmp::min(amount, interest_due),
+        accounts.lp_pool.utilization_bps(),
+        accounts.lp_pool.protocol_fee_bps(),
+    );
+
+    let repay_amount = std::cmp::min(amount, total_owed - incentive);
+
+    // Update position, crediting the incentive as if it were paid
+    let now = Clock::get()?.unix_timestamp;
+    let credited = repay_amount + incentive;
+    let interest_paid = accounts.position.apply_repayment(asset_type, credited, now);
+    let principal_paid = credited.saturating_sub(interest_paid);
+
+    // The referrer's cut of the interest actually paid skips the pool
+    let referral = if referral_vault_open(&accounts.referral_vault) {
+        referral_cut(
+            interest_paid - incentive,
+            accounts.protocol.referral_fee_bps,
+        )
+    } else {
+        0
+    };
+    if referral > 0 {
+        token::transfer(
+            CpiContext::new(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from: accounts.user_token_account.to_account_info(),
+                    to: accounts.referral_vault.to_account_info(),
+                    authority: accounts.owner.to_account_info(),
+                },
+            ),
+            referral,
+        )?;
+    }
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.user_token_account.to_account_info(),
+                to: accounts.repay_vault.to_account_info(),
+                authority: accounts.owner.to_account_info(),
+            },
+        ),
+        repay_amount - referral,
+    )?;
+
+    // Only EURC reads the EUR/USD price, so a stale EURC feed can't block USDC repays
+    let eur_price = match asset_type {
+        AssetType::EURC => eur_usd_price(&accounts.eur_price_feed, now)?,
+        _ => None,
+    };
+    totals::report(
+        &accounts.core_program.to_account_info(),
+        &accounts.protocol.to_account_info(),
+        &accounts.protocol_writer.to_account_info(),
+        bumps.protocol_writer,
+        0,
+        -totals::usd_delta(asset_type.debt_to_usd(principal_paid, eur_price)?),
+    )?;
+    legasi_lp::report_borrowed(
+        &accounts.lp_program.to_account_info(),
+        &accounts.lp_pool.to_account_info(),
+        &accounts.protocol_writer.to_account_info(),
+        bumps.protocol_writer,
+        -totals::usd_delta(principal_paid),
+    )?;
+    // LPs and insurance get their full share of the interest left after the
+    // referral cut; the pool takes the incentive out of the protocol fee
+    let pool_interest = interest_paid - referral;
+    legasi_lp::accrue_interest(
+        &accounts.lp_program.to_account_info(),
+        &accounts.lp_pool.to_account_info(),
+        &accounts.protocol_writer.to_account_info(),
+        bumps.protocol_writer,
+        pool_interest,
+        incentive,
+    )?;
+    totals::report_insurance_fee(
+        &accounts.core_program.to_account_info(),
+        &accounts.protocol.to_account_info(),
+        &accounts.protocol_writer.to_account_info(),
+        bumps.protocol_writer,
+        calculate_insurance_fee(pool_interest),
+    )?;
+
+    if let Some(schedule) = accounts.repayment_schedule.as_mut() {
+        schedule.record_payment(repay_amount, now);
+    }
+
+    emit!(Repaid {
+        position: accounts.position.key(),
+        owner: accounts.owner.key(),
+        asset_type,
+        amount: repay_amount,
+        interest_paid,
+        reference,
+    });
+
+    msg!(
+        "Repaid {} {:?} ({} interest waived)",
+        repay_amount,
+        asset_type,
+        incentive
+    );
+    Ok(())
+}
+
 #[program]
 pub mod legasi_lending {
     use super::*;
 
-    /// Initialize the lending vault for a borrowable asset
-    pub fn initialize_lending_vault(_ctx: Context<InitializeLendingVault>) -> Result<()> {
-        msg!("Lending vault initialized");
+    /// Move a deprecated `[b"lending_vault", mint]` balance into the LP pool vault and close it
+    /// Every borrow and repay path now settles against the LP vault, so this runs once
+    /// per borrowable (admin only). The moved balance is not credited to LP shares
+    pub fn migrate_lending_vault(ctx: Context<MigrateLendingVault>) -> Result<()> {
+        let mint = ctx.accounts.lending_vault.mint;
+        let vault_bump = ctx.bumps.lending_vault;
+        let seeds: &[&[u8]] = &[LENDING_VAULT_SEED, mint.as_ref(), &[vault_bump]];
+
+        let amount = ctx.accounts.lending_vault.amount;
+        if amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.lending_vault.to_account_info(),
+                        to: ctx.accounts.lp_vault.to_account_info(),
+                        authority: ctx.accounts.lending_vault.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                amount,
+            )?;
+        }
+
+        // Rent goes back to the admin
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.lending_vault.to_account_info(),
+                destination: ctx.accounts.admin.to_account_info(),
+                authority: ctx.accounts.lending_vault.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        msg!("Migrated {} from lending vault to LP vault", amount);
+        Ok(())
+    }
+
+    /// Sweep lamports above rent exemption from lending-owned accounts, passed writable
+    /// in remaining_accounts, into the treasury (admin only). None of them holds SOL of
+    /// its own - collateral sits in system-owned `sol_vault`s - so any excess is stray
+    /// transfers or dust
+    pub fn sweep_excess_lamports(ctx: Context<SweepExcessLamports>) -> Result<()> {
+        let rent = Rent::get()?;
+        let treasury = ctx.accounts.treasury.to_account_info();
+        let mut swept_accounts: u32 = 0;
+        let mut total: u64 = 0;
+
+        for account in ctx.remaining_accounts {
+            require_keys_eq!(*account.owner, crate::ID, LegasiError::NotProgramOwned);
+            let excess = account
+                .lamports()
+                .saturating_sub(rent.minimum_balance(account.data_len()));
+            if excess == 0 {
+                continue;
+            }
+
+            **account.try_borrow_mut_lamports()? -= excess;
+            **treasury.try_borrow_mut_lamports()? += excess;
+            swept_accounts += 1;
+            total = total.checked_add(excess).ok_or(LegasiError::MathOverflow)?;
+
+            emit!(LamportsSwept {
+                account: account.key(),
+                amount: excess,
+            });
+        }
+
+        emit!(ExcessLamportsSwept {
+            treasury: treasury.key(),
+            accounts: swept_accounts,
+            total,
+        });
+
+        msg!(
+            "Swept {} lamports from {} accounts to treasury",
+            total,
+            swept_accounts
+        );
         Ok(())
     }
 
     /// Initialize a user position
+    /// Passing a referrer's registry account credits them a share of the interest paid
     pub fn initialize_position(ctx: Context<InitializePosition>) -> Result<()> {
+        let referrer = match ctx.accounts.referrer_account.as_mut() {
+            Some(registry) => {
+                require_keys_neq!(
+                    registry.referrer,
+                    ctx.accounts.owner.key(),
+                    LegasiError::Unauthorized
+                );
+                registry.referred_positions = registry.referred_positions.saturating_add(1);
+                registry.referrer
+            }
+            None => Pubkey::default(),
+        };
+
         let position = &mut ctx.accounts.position;
         position.owner = ctx.accounts.owner.key();
         position.collaterals = Vec::new();
@@ -151,6 +1291,13 @@ pub mod legasi_lending {
         position.gad_enabled = true;
         position.total_gad_liquidated_usd = 0;
         position.reputation = Reputation::default();
+        position.stake_provider = StakeProvider::None;
+        position.gad_carried_secs = 0;
+        position.max_debt_usd = 0;
+        position.referrer = referrer;
+        position.committed_letters_usd = 0;
+        position.emode = UserEMode::default();
+        position.statement_totals = StatementTotals::default();
         position.bump = ctx.bumps.position;
 
         msg!("Position initialized for {}", ctx.accounts.owner.key());
@@ -160,6 +1307,14 @@ pub mod legasi_lending {
     /// Deposit SOL as collateral
     pub fn deposit_sol(ctx: Context<DepositSol>, amount: u64) -> Result<()> {
         require!(amount > 0, LegasiError::InvalidAmount);
+        ctx.accounts.protocol.require_not_paused(PAUSE_DEPOSITS)?;
+        gate::check_gate(
+            &ctx.accounts.market_gate,
+            ctx.accounts.gate_pass.as_deref(),
+            ctx.accounts.owner.key,
+        )?;
+        AssetCaps::load(&ctx.accounts.asset_caps)?
+            .require_supply(ctx.accounts.protocol.total_sol_collateral, amount)?;
 
         invoke(
             &system_instruction::transfer(
@@ -200,7 +1355,24 @@ pub mod legasi_lending {
             });
         }
 
-        position.last_update = Clock::get()?.unix_timestamp;
+        let now = Clock::get()?.unix_timestamp;
+        let deposit_usd = sol_to_usd(amount, ctx.accounts.sol_price_feed.get_checked_price(now)?)?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            totals::usd_delta(deposit_usd),
+            0,
+        )?;
+        totals::report_sol_collateral(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            amount as i64,
+        )?;
+
         msg!("Deposited {} lamports", amount);
         Ok(())
     }
@@ -208,10 +1380,20 @@ pub mod legasi_lending {
     /// Deposit SPL token as collateral (cbBTC)
     pub fn deposit_token(ctx: Context<DepositToken>, amount: u64) -> Result<()> {
         require!(amount > 0, LegasiError::InvalidAmount);
+        ctx.accounts.protocol.require_not_paused(PAUSE_DEPOSITS)?;
         require!(
             ctx.accounts.collateral_config.is_active,
             LegasiError::AssetNotActive
         );
+        gate::check_gate(
+            &ctx.accounts.market_gate,
+            ctx.accounts.gate_pass.as_deref(),
+            ctx.accounts.owner.key,
+        )?;
+
+        // The shared vault holds every position's deposits of this mint
+        AssetCaps::load(&ctx.accounts.asset_caps)?
+            .require_supply(ctx.accounts.token_vault.amount, amount)?;
 
         let asset_type = ctx.accounts.collateral_config.asset_type;
 
@@ -251,7 +1433,7 @@ pub mod legasi_lending {
                 .push(CollateralDeposit { asset_type, amount });
         }
 
-        position.last_update = Clock::get()?.unix_timestamp;
+        let now = Clock::get()?.unix_timestamp;
 
         let collateral_config = &mut ctx.accounts.collateral_config;
         collateral_config.total_deposited = collateral_config
@@ -259,95 +1441,108 @@ pub mod legasi_lending {
             .checked_add(amount)
             .ok_or(LegasiError::MathOverflow)?;
 
+        let price_usd_6dec = ctx.accounts.price_feed.get_checked_price(now)?;
+        let receipt = &mut ctx.accounts.deposit_receipt;
+        receipt.position = ctx.accounts.position.key();
+        receipt.mint = collateral_config.mint;
+        receipt.asset_type = asset_type;
+        receipt.decimals = collateral_config.decimals;
+        receipt.bump = ctx.bumps.deposit_receipt;
+        receipt.record(amount, price_usd_6dec, now);
+        emit!(DepositLotRecorded {
+            position: receipt.position,
+            mint: receipt.mint,
+            amount,
+            price_usd_6dec,
+            cost_basis_usd: receipt.cost_basis_usd(),
+            avg_holding_secs: receipt.avg_holding_secs(now),
+        });
+
         msg!("Deposited {} {:?}", amount, asset_type);
         Ok(())
     }
 
-    /// Borrow stablecoins (USDC, EURC)
-    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+    /// Deposit SOL and liquid-stake it with the chosen provider
+    /// The minted LST is held by the position's vault as collateral
+    pub fn deposit_and_stake(
+        ctx: Context<DepositAndStake>,
+        amount: u64,
+        provider: StakeProvider,
+    ) -> Result<()> {
         require!(amount > 0, LegasiError::InvalidAmount);
+        ctx.accounts.protocol.require_not_paused(PAUSE_DEPOSITS)?;
         require!(
-            ctx.accounts.borrowable_config.is_active,
-            LegasiError::AssetNotActive
+            provider == StakeProvider::Marinade,
+            LegasiError::AssetNotSupported
         );
+        let current_provider = ctx.accounts.position.stake_provider;
         require!(
-            ctx.accounts.borrow_vault.amount >= amount,
-            LegasiError::InsufficientLiquidity
+            current_provider == StakeProvider::None || current_provider == provider,
+            LegasiError::StakeProviderMismatch
         );
+        gate::check_gate(
+            &ctx.accounts.market_gate,
+            ctx.accounts.gate_pass.as_deref(),
+            ctx.accounts.owner.key,
+        )?;
 
-        let asset_type = ctx.accounts.borrowable_config.asset_type;
-        let sol_price = ctx.accounts.sol_price_feed.price_usd_6dec;
+        let msol_before = ctx.accounts.msol_vault.amount;
 
-        // Calculate collateral value
-        let mut total_collateral_usd: u64 = 0;
-        for deposit in &ctx.accounts.position.collaterals {
-            if deposit.asset_type == AssetType::SOL || deposit.asset_type == AssetType::CbBTC {
-                let value = (deposit.amount as u128)
-                    .checked_mul(sol_price as u128)
-                    .ok_or(LegasiError::MathOverflow)?
-                    .checked_div(LAMPORTS_PER_SOL as u128)
-                    .ok_or(LegasiError::MathOverflow)? as u64;
-                total_collateral_usd = total_collateral_usd
-                    .checked_add(value)
-                    .ok_or(LegasiError::MathOverflow)?;
-            }
-        }
+        // SOL → mSOL via Marinade, minted straight into the position vault
+        invoke(
+            &marinade::deposit_ix(
+                &marinade::MarinadeDepositAccounts {
+                    state: ctx.accounts.marinade_state.key(),
+                    msol_mint: ctx.accounts.msol_mint.key(),
+                    liq_pool_sol_leg_pda: ctx.accounts.liq_pool_sol_leg_pda.key(),
+                    liq_pool_msol_leg: ctx.accounts.liq_pool_msol_leg.key(),
+                    liq_pool_msol_leg_authority: ctx.accounts.liq_pool_msol_leg_authority.key(),
+                    reserve_pda: ctx.accounts.reserve_pda.key(),
+                    transfer_from: ctx.accounts.owner.key(),
+                    mint_to: ctx.accounts.msol_vault.key(),
+                    msol_mint_authority: ctx.accounts.msol_mint_authority.key(),
+                    system_program: ctx.accounts.system_program.key(),
+                    token_program: ctx.accounts.token_program.key(),
+                },
+                amount,
+            ),
+            &[
+                ctx.accounts.marinade_state.to_account_info(),
+                ctx.accounts.msol_mint.to_account_info(),
+                ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+                ctx.accounts.liq_pool_msol_leg.to_account_info(),
+                ctx.accounts.liq_pool_msol_leg_authority.to_account_info(),
+                ctx.accounts.reserve_pda.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.msol_vault.to_account_info(),
+                ctx.accounts.msol_mint_authority.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.marinade_program.to_account_info(),
+            ],
+        )?;
 
-        // Calculate borrow value
-        let mut current_borrow_usd: u64 = 0;
-        for borrow in &ctx.accounts.position.borrows {
-            let value = borrow
-                .amount
-                .checked_add(borrow.accrued_interest)
-                .ok_or(LegasiError::MathOverflow)?;
-            current_borrow_usd = current_borrow_usd
-                .checked_add(value)
-                .ok_or(LegasiError::MathOverflow)?;
-        }
-        let new_borrow_usd = current_borrow_usd
-            .checked_add(amount)
+        ctx.accounts.msol_vault.reload()?;
+        let msol_received = ctx
+            .accounts
+            .msol_vault
+            .amount
+            .checked_sub(msol_before)
             .ok_or(LegasiError::MathOverflow)?;
+        require!(msol_received > 0, LegasiError::InvalidAmount);
 
-        // Check LTV
-        let base_ltv = DEFAULT_SOL_MAX_LTV_BPS as u64;
-        let reputation_bonus = ctx.accounts.position.reputation.get_ltv_bonus_bps() as u64;
-        let effective_max_ltv = base_ltv.saturating_add(reputation_bonus);
+        let msol_price =
+            marinade::parse_msol_price(&ctx.accounts.marinade_state.try_borrow_data()?)
+                .ok_or(LegasiError::InvalidOracle)?;
 
-        let max_borrow = total_collateral_usd
-            .checked_mul(effective_max_ltv)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(LegasiError::MathOverflow)?;
-
-        require!(new_borrow_usd <= max_borrow, LegasiError::ExceedsLTV);
-
-        // Transfer tokens from lending vault
-        let mint = ctx.accounts.borrowable_config.mint;
-        let vault_bump = ctx.bumps.borrow_vault;
-        let seeds: &[&[u8]] = &[b"lending_vault", mint.as_ref(), &[vault_bump]];
-
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.borrow_vault.to_account_info(),
-                    to: ctx.accounts.user_token_account.to_account_info(),
-                    authority: ctx.accounts.borrow_vault.to_account_info(),
-                },
-                &[seeds],
-            ),
-            amount,
-        )?;
-
-        // Update position
-        let position = &mut ctx.accounts.position;
+        let position = &mut ctx.accounts.position;
 
         let mut found = false;
-        for borrow in position.borrows.iter_mut() {
-            if borrow.asset_type == asset_type {
-                borrow.amount = borrow
+        for deposit in position.collaterals.iter_mut() {
+            if deposit.asset_type == AssetType::MSOL {
+                deposit.amount = deposit
                     .amount
-                    .checked_add(amount)
+                    .checked_add(msol_received)
                     .ok_or(LegasiError::MathOverflow)?;
                 found = true;
                 break;
@@ -356,89 +1551,661 @@ pub mod legasi_lending {
 
         if !found {
             require!(
-                position.borrows.len() < MAX_BORROW_TYPES,
-                LegasiError::MaxBorrowTypesReached
+                position.collaterals.len() < MAX_COLLATERAL_TYPES,
+                LegasiError::MaxCollateralTypesReached
             );
-            position.borrows.push(BorrowedAmount {
-                asset_type,
-                amount,
-                accrued_interest: 0,
+            position.collaterals.push(CollateralDeposit {
+                asset_type: AssetType::MSOL,
+                amount: msol_received,
             });
         }
 
-        position.last_update = Clock::get()?.unix_timestamp;
-        msg!("Borrowed {} {:?}", amount, asset_type);
+        position.stake_provider = provider;
+
+        emit!(StakeDeposited {
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            provider,
+            lamports: amount,
+            lst_received: msol_received,
+            lst_price: msol_price,
+        });
+
+        msg!("Staked {} lamports for {} mSOL", amount, msol_received);
         Ok(())
     }
 
-    /// Repay borrowed amount
-    pub fn repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
+    /// Withdraw staked collateral (mSOL) from the position vault
+    /// Debt pools go in remaining_accounts (see `accrue_all_interest`)
+    pub fn withdraw_staked(ctx: Context<WithdrawStaked>, amount: u64) -> Result<()> {
         require!(amount > 0, LegasiError::InvalidAmount);
 
-        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts
+            .sol_price_feed
+            .circuit_breaker
+            .require_clear(now)?;
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
 
-        // Find borrow
-        let mut total_owed: u64 = 0;
-        for borrow in &ctx.accounts.position.borrows {
-            if borrow.asset_type == asset_type {
-                total_owed = borrow
-                    .amount
-                    .checked_add(borrow.accrued_interest)
-                    .ok_or(LegasiError::MathOverflow)?;
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+
+        let mut msol_amount: u64 = 0;
+        for deposit in &ctx.accounts.position.collaterals {
+            if deposit.asset_type == AssetType::MSOL {
+                msol_amount = deposit.amount;
                 break;
             }
         }
-        require!(total_owed > 0, LegasiError::PositionNotFound);
+        require!(msol_amount >= amount, LegasiError::InsufficientCollateral);
+        require_allowed_destination(
+            &ctx.accounts.withdrawal_allowlist,
+            &ctx.accounts.user_msol_account.owner,
+        )?;
+
+        // An open credit line keeps its unused limit backed
+        let committed = committed_debt_usd(
+            &ctx.accounts.credit_line,
+            &ctx.accounts.position,
+            eur_usd_price(&ctx.accounts.eur_price_feed, now)?,
+        )?;
+
+        // Check LTV after withdrawal if has borrows (mSOL valued at the SOL price floor)
+        if !ctx.accounts.position.borrows.is_empty() || committed > 0 {
+            let mut remaining_lamports: u64 = 0;
+            for deposit in &ctx.accounts.position.collaterals {
+                let remaining = match deposit.asset_type {
+                    AssetType::SOL => deposit.amount,
+                    AssetType::MSOL => deposit.amount.saturating_sub(amount),
+                    _ => 0,
+                };
+                remaining_lamports = remaining_lamports
+                    .checked_add(remaining)
+                    .ok_or(LegasiError::MathOverflow)?;
+            }
+            let remaining_value = sol_to_usd(remaining_lamports, sol_price)?;
+
+            let total_borrow = ctx
+                .accounts
+                .position
+                .debt_usd(eur_usd_price(&ctx.accounts.eur_price_feed, now)?)?
+                .checked_add(committed)
+                .ok_or(LegasiError::MathOverflow)?;
+
+            let max_borrow = remaining_value
+                .checked_mul(DEFAULT_SOL_MAX_LTV_BPS as u64)
+                .ok_or(LegasiError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR)
+                .ok_or(LegasiError::MathOverflow)?;
+
+            require!(total_borrow <= max_borrow, LegasiError::ExceedsLTV);
+        }
 
-        let repay_amount = std::cmp::min(amount, total_owed);
+        // Transfer mSOL from vault (position PDA is the vault authority)
+        let owner_key = ctx.accounts.owner.key();
+        let position_bump = ctx.accounts.position.bump;
+        let seeds: &[&[u8]] = &[POSITION_SEED, owner_key.as_ref(), &[position_bump]];
 
         token::transfer(
-            CpiContext::new(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.user_token_account.to_account_info(),
-                    to: ctx.accounts.repay_vault.to_account_info(),
-                    authority: ctx.accounts.owner.to_account_info(),
+                    from: ctx.accounts.msol_vault.to_account_info(),
+                    to: ctx.accounts.user_msol_account.to_account_info(),
+                    authority: ctx.accounts.position.to_account_info(),
                 },
+                &[seeds],
             ),
-            repay_amount,
+            amount,
         )?;
 
-        // Update position
         let position = &mut ctx.accounts.position;
-
-        for borrow in position.borrows.iter_mut() {
-            if borrow.asset_type == asset_type {
-                let interest_payment = std::cmp::min(repay_amount, borrow.accrued_interest);
-                borrow.accrued_interest = borrow.accrued_interest.saturating_sub(interest_payment);
-                let principal = repay_amount.saturating_sub(interest_payment);
-                borrow.amount = borrow.amount.saturating_sub(principal);
+        for deposit in position.collaterals.iter_mut() {
+            if deposit.asset_type == AssetType::MSOL {
+                deposit.amount = deposit.amount.saturating_sub(amount);
                 break;
             }
         }
+        position.collaterals.retain(|c| c.amount > 0);
+        if !position
+            .collaterals
+            .iter()
+            .any(|c| c.asset_type == AssetType::MSOL)
+        {
+            position.stake_provider = StakeProvider::None;
+        }
+        position.last_update = now;
 
-        // Remove empty borrows
-        position
-            .borrows
-            .retain(|b| b.amount > 0 || b.accrued_interest > 0);
+        msg!("Withdrew {} mSOL", amount);
+        Ok(())
+    }
 
-        position.reputation.successful_repayments =
-            position.reputation.successful_repayments.saturating_add(1);
-        position.reputation.total_repaid_usd = position
-            .reputation
-            .total_repaid_usd
-            .saturating_add(repay_amount);
-        position.last_update = Clock::get()?.unix_timestamp;
+    /// Borrow stablecoins (USDC, EURC)
+    /// `reference` (e.g. an invoice hash) is only echoed in the `Borrowed` event
+    pub fn borrow(ctx: Context<Borrow>, amount: u64, reference: Option<[u8; 32]>) -> Result<()> {
+        settle_borrow(ctx.accounts, &ctx.bumps, amount, reference, None)
+    }
+
+    /// Borrow at a fixed rate for `term_secs` (see `fixed_term`): the pool's current
+    /// rate plus `FIXED_RATE_PREMIUM_BPS`, failing above `max_rate_bps`. Past maturity
+    /// the unpaid balance owes the variable rate plus `MATURITY_PENALTY_BPS`
+    pub fn borrow_fixed_term(
+        ctx: Context<Borrow>,
+        amount: u64,
+        term_secs: i64,
+        max_rate_bps: u16,
+    ) -> Result<()> {
+        require!(
+            (MIN_FIXED_TERM..=MAX_FIXED_TERM).contains(&term_secs),
+            LegasiError::InvalidLoanTerm
+        );
+        let rate_bps = fixed_rate_bps(ctx.accounts.lp_pool.borrow_rate_bps());
+        require!(rate_bps <= max_rate_bps, LegasiError::FixedRateAboveMax);
+        let term = FixedTerm {
+            rate_bps,
+            maturity: Clock::get()?
+                .unix_timestamp
+                .checked_add(term_secs)
+                .ok_or(LegasiError::MathOverflow)?,
+        };
+        settle_borrow(ctx.accounts, &ctx.bumps, amount, None, Some(term))
+    }
+
+    /// Repay borrowed amount
+    /// `reference` (e.g. an invoice hash) is only echoed in the `Repaid` event
+    pub fn repay(ctx: Context<Repay>, amount: u64, reference: Option<[u8; 32]>) -> Result<()> {
+        settle_repay(ctx.accounts, &ctx.bumps, amount, reference)
+    }
+
+    /// Convert up to `amount` of debt in one borrowable into the other at the EUR/USD
+    /// oracle price plus `DEBT_CONVERSION_FEE_BPS`, without an external swap
+    /// Both pools' outstanding borrows move with the principal
+    pub fn convert_debt(ctx: Context<ConvertDebt>, amount: u64) -> Result<()> {
+        require!(
+            ctx.accounts.to_borrowable.is_active,
+            LegasiError::AssetNotActive
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let from = ctx.accounts.from_borrowable.asset_type;
+        let to = ctx.accounts.to_borrowable.asset_type;
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+
+        let to_borrow_index = ctx.accounts.to_lp_pool.borrow_index_at(now)?;
+        let position = &mut ctx.accounts.position;
+        position.accrue_interest(from, &ctx.accounts.from_lp_pool, now)?;
+        position.accrue_interest(to, &ctx.accounts.to_lp_pool, now)?;
+        let conversion =
+            position.convert_debt(from, to, amount, eur_price, to_borrow_index, now)?;
+        position.require_within_ltv(to, 0, sol_price, eur_price)?;
+
+        // The destination pool takes on the principal, subject to its utilization cap
+        legasi_lp::report_borrowed(
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.from_lp_pool.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(conversion.from_principal),
+        )?;
+        legasi_lp::report_borrowed(
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.to_lp_pool.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            totals::usd_delta(conversion.to_principal),
+        )?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            0,
+            totals::usd_delta(to.debt_to_usd(conversion.to_principal, eur_price)?)
+                - totals::usd_delta(from.debt_to_usd(conversion.from_principal, eur_price)?),
+        )?;
+
+        emit!(DebtConverted {
+            position: ctx.accounts.position.key(),
+            from,
+            to,
+            from_amount: conversion.from_principal + conversion.from_interest,
+            to_amount: conversion.to_principal + conversion.to_interest,
+            fee: conversion.fee,
+            eur_usd_price: eur_price.unwrap_or(0),
+        });
+
+        msg!(
+            "Converted {} {:?} debt into {} {:?}",
+            conversion.from_principal + conversion.from_interest,
+            from,
+            conversion.to_principal + conversion.to_interest,
+            to
+        );
+        Ok(())
+    }
+
+    /// Liquidate a position past the hard threshold: repay up to `amount` of its
+    /// `borrowable_config` debt from the liquidator's account for SOL collateral plus the
+    /// liquidation bonus. With no collateral left, the rest of that debt is written off
+    /// (see `liquidation`). Permissionless
+    pub fn liquidate_position(ctx: Context<LiquidatePosition>, amount: u64) -> Result<()> {
+        settle_liquidation(ctx.accounts, &ctx.bumps, amount, None)
+    }
+
+    /// `liquidate_position` for a liquidator without capital: the seized SOL is sold
+    /// through Jupiter (route accounts in remaining_accounts) into the liquidator's token
+    /// account before the repayment is pulled from it, so the proceeds pay the debt in
+    /// the same instruction and the liquidator keeps the surplus and any SOL left unsold
+    pub fn flash_liquidate<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashLiquidate<'info>>,
+        amount: u64,
+        swap: DeleverageSwap,
+    ) -> Result<()> {
+        let jupiter_program = ctx.accounts.jupiter_program.to_account_info();
+        settle_liquidation(
+            &mut ctx.accounts.liquidation,
+            &ctx.bumps.liquidation,
+            amount,
+            Some(LiquidationSwap {
+                jupiter_program: &jupiter_program,
+                route_accounts: ctx.remaining_accounts,
+                swap,
+            }),
+        )
+    }
+
+    /// Write off a position's `borrowable_config` debt once its collateral is worth less
+    /// than `BAD_DEBT_MAX_COLLATERAL_USD` at the TWAP, so nothing is left to liquidate
+    /// it with. The interest is dropped and the principal written off the pool: the
+    /// insurance fund covers what it can, the LPs the rest. Permissionless
+    pub fn write_off_bad_debt(ctx: Context<WriteOffBadDebt>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let insurance_fund = ctx.accounts.protocol.insurance_fund;
+        // Like liquidation, a manipulated print can't make collateral look like dust
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_twap(now)?;
+        require!(
+            ctx.accounts.sol_price_feed.within_deviation_band(),
+            LegasiError::PriceDeviationTooHigh
+        );
+        let mut book = PriceBook::new(sol_price, eur_price);
+        if let Some(feed) = &ctx.accounts.cbbtc_price_feed {
+            book = book.with_price(AssetType::CbBTC, feed.get_checked_twap(now)?);
+        }
 
-        msg!("Repaid {} {:?}", repay_amount, asset_type);
+        let position = &mut ctx.accounts.position;
+        position.accrue_interest(asset_type, &ctx.accounts.lp_pool, now)?;
+        let owed = position.total_owed(asset_type)?;
+        require!(owed > 0, LegasiError::PositionNotFound);
+        // A held asset without its feed fails, so cbBTC can't be left out to pass
+        let collateral_usd = valuation::value_collaterals(
+            position
+                .collaterals
+                .iter()
+                .map(|c| (c.asset_type, c.amount)),
+            std::iter::empty(),
+            book,
+        )?;
+        require!(
+            collateral_usd < BAD_DEBT_MAX_COLLATERAL_USD,
+            LegasiError::DebtStillRecoverable
+        );
+        let principal = position.reduce_debt(asset_type, owed)?.1;
+        position.last_update = now;
+        let principal_usd = asset_type.debt_to_usd(principal, eur_price)?;
+        let covered = ctx
+            .accounts
+            .lp_pool
+            .insurance_cover(principal, insurance_fund);
+
+        let core_program = ctx.accounts.core_program.to_account_info();
+        let protocol = ctx.accounts.protocol.to_account_info();
+        let writer = ctx.accounts.protocol_writer.to_account_info();
+        let writer_bump = ctx.bumps.protocol_writer;
+        totals::report_insurance_payout(&core_program, &protocol, &writer, writer_bump, covered)?;
+        legasi_lp::write_off_bad_debt(
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.lp_pool.to_account_info(),
+            &writer,
+            writer_bump,
+            principal,
+            covered,
+        )?;
+        totals::report(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            0,
+            -totals::usd_delta(principal_usd),
+        )?;
+        totals::report_bad_debt(
+            &core_program,
+            &protocol,
+            &writer,
+            writer_bump,
+            principal_usd,
+        )?;
+
+        emit!(BadDebtSocialized {
+            position: ctx.accounts.position.key(),
+            asset_type,
+            principal,
+            insurance_covered: covered,
+            socialized: principal - covered,
+        });
+
+        msg!(
+            "Wrote off {} {:?} of bad debt ({} covered by insurance)",
+            principal,
+            asset_type,
+            covered
+        );
+        Ok(())
+    }
+
+    /// GAD program only (see `gad_settlement`): turn GAD on or off for the position and
+    /// set the SOL collateral GAD leaves on it. GAD can only be turned off while the
+    /// position has no debt; borrows are refused until it is on again
+    pub fn set_gad_config(
+        ctx: Context<SetGadConfig>,
+        enabled: bool,
+        min_collateral_floor: u64,
+    ) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        // Opting out with open debt, or a floor holding back all the collateral, would
+        // make the position un-liquidatable
+        require!(
+            enabled || position.borrows.is_empty(),
+            LegasiError::InvalidGadConfig
+        );
+        position.gad_collateral_floor = min_collateral_floor;
+        require!(
+            position.gad_floor_in_bounds(),
+            LegasiError::InvalidGadConfig
+        );
+        position.gad_enabled = enabled;
+        Ok(())
+    }
+
+    /// GAD program only: pay `amount` of the position's SOL (from its SOL vault) or
+    /// mSOL (from its mSOL vault) collateral to `recipient`
+    pub fn release_gad_collateral(
+        ctx: Context<ReleaseGadCollateral>,
+        asset_type: AssetType,
+        amount: u64,
+    ) -> Result<()> {
+        let position_key = ctx.accounts.position.key();
+        match asset_type {
+            AssetType::SOL => {
+                let vault_seeds: &[&[u8]] = &[
+                    SOL_VAULT_SEED,
+                    position_key.as_ref(),
+                    &[ctx.bumps.sol_vault],
+                ];
+                invoke_signed(
+                    &system_instruction::transfer(
+                        ctx.accounts.sol_vault.key,
+                        ctx.accounts.recipient.key,
+                        amount,
+                    ),
+                    &[
+                        ctx.accounts.sol_vault.to_account_info(),
+                        ctx.accounts.recipient.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
+                    ],
+                    &[vault_seeds],
+                )?;
+            }
+            AssetType::MSOL => {
+                let msol_vault = ctx
+                    .accounts
+                    .msol_vault
+                    .as_ref()
+                    .ok_or(LegasiError::AssetNotSupported)?;
+                let owner_key = ctx.accounts.position.owner;
+                let position_seeds: &[&[u8]] = &[
+                    POSITION_SEED,
+                    owner_key.as_ref(),
+                    &[ctx.accounts.position.bump],
+                ];
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: msol_vault.to_account_info(),
+                            to: ctx.accounts.recipient.to_account_info(),
+                            authority: ctx.accounts.position.to_account_info(),
+                        },
+                        &[position_seeds],
+                    ),
+                    amount,
+                )?;
+            }
+            _ => return err!(LegasiError::AssetNotSupported),
+        }
+        ctx.accounts.position.remove_collateral(asset_type, amount)
+    }
+
+    /// GAD program only: sell up to `max_in` of the position's SOL or mSOL collateral
+    /// through `route`, signed by the vault's authority. Route accounts go in
+    /// remaining_accounts; GAD checks what the route returned
+    pub fn swap_gad_collateral(
+        ctx: Context<SwapGadCollateral>,
+        asset_type: AssetType,
+        route: SwapRoute,
+        route_data: Vec<u8>,
+        max_in: u64,
+    ) -> Result<()> {
+        let position_key = ctx.accounts.position.key();
+        let owner_key = ctx.accounts.position.owner;
+        let vault_seeds: &[&[u8]] = &[
+            SOL_VAULT_SEED,
+            position_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        let position_seeds: &[&[u8]] = &[
+            POSITION_SEED,
+            owner_key.as_ref(),
+            &[ctx.accounts.position.bump],
+        ];
+        let swap_program = ctx.accounts.swap_program.to_account_info();
+
+        let sold = match asset_type {
+            AssetType::SOL => {
+                let before = ctx.accounts.sol_vault.lamports();
+                route.swap(
+                    &swap_program,
+                    ctx.remaining_accounts,
+                    route_data,
+                    Some(ctx.accounts.sol_vault.key),
+                    &[vault_seeds],
+                )?;
+                jupiter_cpi::assert_max_spent(before, ctx.accounts.sol_vault.lamports(), max_in)?
+            }
+            AssetType::MSOL => {
+                let msol_vault = ctx
+                    .accounts
+                    .msol_vault
+                    .as_mut()
+                    .ok_or(LegasiError::AssetNotSupported)?;
+                let before = msol_vault.amount;
+                route.swap(
+                    &swap_program,
+                    ctx.remaining_accounts,
+                    route_data,
+                    Some(&position_key),
+                    &[position_seeds],
+                )?;
+                msol_vault.reload()?;
+                jupiter_cpi::assert_max_spent(before, msol_vault.amount, max_in)?
+            }
+            _ => return err!(LegasiError::AssetNotSupported),
+        };
+        ctx.accounts.position.remove_collateral(asset_type, sold)
+    }
+
+    /// GAD program only: book a GAD crank or auction fill (see `GadSettlement`)
+    /// Every borrow's interest is settled first, so the reduction never lands on a
+    /// stale debt. Debt pools go in remaining_accounts (see `accrue_all_interest`)
+    pub fn settle_gad(ctx: Context<SettleGad>, settlement: GadSettlement) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+        ctx.accounts
+            .position
+            .apply_gad_settlement(&settlement, now)?;
+        Ok(())
+    }
+
+    /// Repay just enough to bring the position's LTV down to `target_ltv_bps`
+    /// Without `swap` the owner repays from their token account. With it, SOL collateral
+    /// is sold through Jupiter (route accounts in remaining_accounts) into that account
+    /// and the proceeds repay the debt
+    pub fn deleverage_to_ltv(
+        ctx: Context<DeleverageToLtv>,
+        target_ltv_bps: u16,
+        swap: Option<DeleverageSwap>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let repay = &mut ctx.accounts.repay;
+        repay
+            .position
+            .accrue_interest(repay.borrowable_config.asset_type, &repay.lp_pool, now)?;
+
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.repay.eur_price_feed, now)?;
+        let position = &ctx.accounts.repay.position;
+        require!(
+            position.ltv_bps(sol_price, eur_price)? > target_ltv_bps as u64,
+            LegasiError::AlreadyBelowTargetLtv
+        );
+        let collateral_usd = position.collateral_value_usd(sol_price)?;
+        let debt_usd = position.debt_usd(eur_price)?;
+        let asset_type = ctx.accounts.repay.borrowable_config.asset_type;
+
+        let amount = match swap {
+            None => asset_type.usd_to_debt(
+                repay_to_target_usd(collateral_usd, debt_usd, target_ltv_bps),
+                eur_price,
+            )?,
+            Some(swap) => {
+                let jupiter_program = ctx
+                    .accounts
+                    .jupiter_program
+                    .as_ref()
+                    .ok_or(LegasiError::InvalidSwapProgram)?;
+                let max_sol_in = usd_to_lamports_ceil(
+                    sell_to_target_usd(collateral_usd, debt_usd, target_ltv_bps),
+                    sol_price,
+                )?;
+
+                let position_key = ctx.accounts.repay.position.key();
+                let vault_bump = ctx.bumps.sol_vault;
+                let seeds: &[&[u8]] = &[SOL_VAULT_SEED, position_key.as_ref(), &[vault_bump]];
+                let sol_before = ctx.accounts.sol_vault.lamports();
+                let out_before = ctx.accounts.repay.user_token_account.amount;
+
+                jupiter_cpi::swap(
+                    &jupiter_program.to_account_info(),
+                    ctx.remaining_accounts,
+                    swap.route_data,
+                    Some(ctx.accounts.sol_vault.key),
+                    &[seeds],
+                )?;
+
+                ctx.accounts.repay.user_token_account.reload()?;
+                let received = jupiter_cpi::assert_min_received(
+                    out_before,
+                    ctx.accounts.repay.user_token_account.amount,
+                    swap.min_out_amount,
+                )?;
+                let sold = jupiter_cpi::assert_max_spent(
+                    sol_before,
+                    ctx.accounts.sol_vault.lamports(),
+                    max_sol_in,
+                )?;
+
+                ctx.accounts.repay.position.remove_sol_collateral(sold)?;
+
+                let repay = &ctx.accounts.repay;
+                totals::report(
+                    &repay.core_program.to_account_info(),
+                    &repay.protocol.to_account_info(),
+                    &repay.protocol_writer.to_account_info(),
+                    ctx.bumps.repay.protocol_writer,
+                    -totals::usd_delta(sol_to_usd(sold, sol_price)?),
+                    0,
+                )?;
+                totals::report_sol_collateral(
+                    &repay.core_program.to_account_info(),
+                    &repay.protocol.to_account_info(),
+                    &repay.protocol_writer.to_account_info(),
+                    ctx.bumps.repay.protocol_writer,
+                    -(sold as i64),
+                )?;
+                received
+            }
+        };
+
+        settle_repay(&mut ctx.accounts.repay, &ctx.bumps.repay, amount, None)
+    }
+
+    /// Commit to repaying a fixed amount every week
+    /// While the schedule is kept, GAD only runs past the hard threshold
+    /// Debt pools go in remaining_accounts (see `accrue_all_interest`)
+    pub fn commit_repayment_schedule(
+        ctx: Context<CommitRepaymentSchedule>,
+        amount_per_period: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+
+        let total_debt = ctx.accounts.position.total_debt()?;
+        require!(total_debt > 0, LegasiError::PositionNotFound);
+        let min_amount = (total_debt as u128)
+            .checked_mul(MIN_SCHEDULE_REPAY_BPS as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        require!(
+            amount_per_period as u128 >= min_amount && amount_per_period > 0,
+            LegasiError::InvalidAmount
+        );
+
+        let schedule = &mut ctx.accounts.repayment_schedule;
+        schedule.position = ctx.accounts.position.key();
+        schedule.amount_per_period = amount_per_period;
+        schedule.period_end = now
+            .checked_add(REPAYMENT_PERIOD)
+            .ok_or(LegasiError::MathOverflow)?;
+        schedule.paid_this_period = 0;
+        schedule.lapsed = false;
+        schedule.bump = ctx.bumps.repayment_schedule;
+
+        msg!("Committed to repay {} per week", amount_per_period);
         Ok(())
     }
 
-    /// Withdraw SOL collateral
-    pub fn withdraw_sol(ctx: Context<WithdrawSol>, amount: u64) -> Result<()> {
+    /// Drop the repayment schedule and return to plain GAD
+    pub fn cancel_repayment_schedule(_ctx: Context<CancelRepaymentSchedule>) -> Result<()> {
+        msg!("Repayment schedule cancelled");
+        Ok(())
+    }
+
+    /// Withdraw SOL collateral, as lamports or, with `as_wsol`, as wSOL in the owner's
+    /// associated token account (created if needed) for a following swap
+    /// Debt pools go in remaining_accounts (see `accrue_all_interest`)
+    pub fn withdraw_sol(ctx: Context<WithdrawSol>, amount: u64, as_wsol: bool) -> Result<()> {
         require!(amount > 0, LegasiError::InvalidAmount);
 
-        let sol_price = ctx.accounts.sol_price_feed.price_usd_6dec;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts
+            .sol_price_feed
+            .circuit_breaker
+            .require_clear(now)?;
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
 
         // Find SOL deposit
         let mut sol_amount: u64 = 0;
@@ -450,29 +2217,59 @@ pub mod legasi_lending {
         }
         require!(sol_amount >= amount, LegasiError::InsufficientCollateral);
 
-        // Check LTV after withdrawal if has borrows
-        if !ctx.accounts.position.borrows.is_empty() {
+        // Lamports go to `destination` (default: the owner), wSOL to the owner's ATA
+        let recipient = match &ctx.accounts.destination {
+            Some(destination) => {
+                require!(!as_wsol, LegasiError::InvalidAmount);
+                destination.to_account_info()
+            }
+            None => ctx.accounts.owner.to_account_info(),
+        };
+        require_allowed_destination(&ctx.accounts.withdrawal_allowlist, recipient.key)?;
+
+        // An open credit line keeps its unused limit backed
+        let committed = committed_debt_usd(
+            &ctx.accounts.credit_line,
+            &ctx.accounts.position,
+            eur_usd_price(&ctx.accounts.eur_price_feed, now)?,
+        )?;
+
+        // Check LTV after withdrawal if has borrows, at the market's effective LTV
+        // when one is passed
+        if !ctx.accounts.position.borrows.is_empty() || committed > 0 {
+            let position = &ctx.accounts.position;
+            let max_ltv_bps = emode::max_ltv_bps(
+                position,
+                ctx.accounts.market.as_deref(),
+                position.emode.category,
+                None,
+            )?;
             let remaining = sol_amount
                 .checked_sub(amount)
                 .ok_or(LegasiError::MathOverflow)?;
-            let remaining_value = (remaining as u128)
-                .checked_mul(sol_price as u128)
-                .ok_or(LegasiError::MathOverflow)?
-                .checked_div(LAMPORTS_PER_SOL as u128)
-                .ok_or(LegasiError::MathOverflow)? as u64;
-
-            let mut total_borrow: u64 = 0;
-            for borrow in &ctx.accounts.position.borrows {
-                total_borrow = total_borrow
-                    .checked_add(borrow.amount)
-                    .ok_or(LegasiError::MathOverflow)?;
-                total_borrow = total_borrow
-                    .checked_add(borrow.accrued_interest)
-                    .ok_or(LegasiError::MathOverflow)?;
+            if let Some(feed) = &ctx.accounts.cbbtc_price_feed {
+                feed.require_fresh(now)?;
             }
+            // Every deposit at its own feed's price, SOL less the withdrawal
+            let remaining_value = valuation::value_collaterals(
+                position.collaterals.iter().map(|c| match c.asset_type {
+                    AssetType::SOL => (c.asset_type, remaining),
+                    asset_type => (asset_type, c.amount),
+                }),
+                std::iter::once(&*ctx.accounts.sol_price_feed)
+                    .chain(ctx.accounts.cbbtc_price_feed.as_ref().map(|feed| &***feed)),
+                PriceBook::default(),
+            )?;
+
+            let total_borrow = ctx
+                .accounts
+                .position
+                .debt_usd(eur_usd_price(&ctx.accounts.eur_price_feed, now)?)?
+                .checked_add(committed)
+                .ok_or(LegasiError::MathOverflow)?;
 
             let max_borrow = remaining_value
-                .checked_mul(DEFAULT_SOL_MAX_LTV_BPS as u64)
+                .checked_mul(max_ltv_bps as u64)
                 .ok_or(LegasiError::MathOverflow)?
                 .checked_div(BPS_DENOMINATOR)
                 .ok_or(LegasiError::MathOverflow)?;
@@ -480,24 +2277,52 @@ pub mod legasi_lending {
             require!(total_borrow <= max_borrow, LegasiError::ExceedsLTV);
         }
 
-        // Transfer SOL
+        // Transfer SOL, or wrap it into the owner's wSOL account
         let position_key = ctx.accounts.position.key();
         let vault_bump = ctx.bumps.sol_vault;
-        let seeds: &[&[u8]] = &[b"sol_vault", position_key.as_ref(), &[vault_bump]];
-
-        invoke_signed(
-            &system_instruction::transfer(
-                ctx.accounts.sol_vault.key,
-                ctx.accounts.owner.key,
+        let seeds: &[&[u8]] = &[SOL_VAULT_SEED, position_key.as_ref(), &[vault_bump]];
+
+        if as_wsol {
+            let accounts = &ctx.accounts;
+            let (wsol_account, token_program, associated_token_program) = match (
+                &accounts.wsol_account,
+                &accounts.token_program,
+                &accounts.associated_token_program,
+            ) {
+                (Some(wsol), Some(token), Some(ata)) => (wsol, token, ata),
+                _ => return err!(LegasiError::MissingWsolAccount),
+            };
+            // No-op if the owner already has the account
+            associated_token::create_idempotent(CpiContext::new(
+                associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: accounts.owner.to_account_info(),
+                    associated_token: wsol_account.to_account_info(),
+                    authority: accounts.owner.to_account_info(),
+                    mint: accounts.sol_mint.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    token_program: token_program.to_account_info(),
+                },
+            ))?;
+            jupiter_cpi::wrap_sol(
+                &accounts.sol_vault.to_account_info(),
+                &wsol_account.to_account_info(),
                 amount,
-            ),
-            &[
-                ctx.accounts.sol_vault.to_account_info(),
-                ctx.accounts.owner.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-            &[seeds],
-        )?;
+                &accounts.system_program.to_account_info(),
+                &token_program.to_account_info(),
+                &[seeds],
+            )?;
+        } else {
+            invoke_signed(
+                &system_instruction::transfer(ctx.accounts.sol_vault.key, recipient.key, amount),
+                &[
+                    ctx.accounts.sol_vault.to_account_info(),
+                    recipient,
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[seeds],
+            )?;
+        }
 
         // Update position
         let position = &mut ctx.accounts.position;
@@ -509,55 +2334,285 @@ pub mod legasi_lending {
             }
         }
         position.collaterals.retain(|c| c.amount > 0);
-        position.last_update = Clock::get()?.unix_timestamp;
+        position.last_update = now;
+        // The GAD floor must stay within its share of what is left
+        require!(
+            position.gad_floor_in_bounds(),
+            LegasiError::BelowCollateralFloor
+        );
+
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(sol_to_usd(amount, sol_price)?),
+            0,
+        )?;
+        totals::report_sol_collateral(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -(amount as i64),
+        )?;
 
         msg!("Withdrew {} lamports", amount);
         Ok(())
     }
 
-    /// Accrue interest on a position's borrows
-    /// Can be called by anyone (cranker) to update interest
-    pub fn accrue_position_interest(ctx: Context<AccruePositionInterest>) -> Result<()> {
-        let position = &mut ctx.accounts.position;
+    /// Set a hard cap on the position's total debt in USD (owner only, 0 = no cap)
+    /// Every borrow path (manual, agent, x402, credit lines, leverage) checks it; a cap
+    /// below the current debt only blocks new borrowing
+    pub fn set_debt_cap(ctx: Context<SetDebtCap>, max_debt_usd: u64) -> Result<()> {
+        ctx.accounts.position.max_debt_usd = max_debt_usd;
+        msg!("Debt cap set to ${}", max_debt_usd as f64 / 1_000_000.0);
+        Ok(())
+    }
+
+    /// Set the wallets collateral may be withdrawn to (empty = any, see
+    /// `withdrawal_allowlist`). Applies at once while no list is in force; otherwise
+    /// proposes the change, applied after `ALLOWLIST_CHANGE_DELAY`
+    pub fn set_withdrawal_allowlist(
+        ctx: Context<SetWithdrawalAllowlist>,
+        destinations: Vec<Pubkey>,
+    ) -> Result<()> {
         let now = Clock::get()?.unix_timestamp;
-        let elapsed = now.saturating_sub(position.last_update);
+        let allowlist = &mut ctx.accounts.withdrawal_allowlist;
+        allowlist.position = ctx.accounts.position.key();
+        allowlist.bump = ctx.bumps.withdrawal_allowlist;
 
-        // Skip if updated recently (< 1 hour)
-        if elapsed < 3600 {
-            return Ok(());
+        if allowlist.destinations.is_empty() {
+            require!(
+                destinations.len() <= MAX_WITHDRAWAL_DESTINATIONS,
+                LegasiError::InvalidAmount
+            );
+            allowlist.cancel();
+            allowlist.destinations = destinations;
+            msg!(
+                "Withdrawal allowlist set: {} destinations",
+                allowlist.destinations.len()
+            );
+        } else {
+            allowlist.propose(destinations, now)?;
+            msg!(
+                "Withdrawal allowlist change proposed, applies at {}",
+                allowlist.pending_after
+            );
         }
+        Ok(())
+    }
 
-        for borrow in position.borrows.iter_mut() {
-            // Get interest rate for this asset (from borrowable config)
-            let annual_rate_bps = match borrow.asset_type {
-                AssetType::USDC => 800, // 8% APR
-                AssetType::EURC => 700, // 7% APR
-                _ => 0,
-            };
+    /// Apply the proposed allowlist once its delay has passed
+    pub fn apply_withdrawal_allowlist(ctx: Context<UpdateWithdrawalAllowlist>) -> Result<()> {
+        let allowlist = &mut ctx.accounts.withdrawal_allowlist;
+        allowlist.apply(Clock::get()?.unix_timestamp)?;
+        msg!(
+            "Withdrawal allowlist applied: {} destinations",
+            allowlist.destinations.len()
+        );
+        Ok(())
+    }
 
-            if annual_rate_bps == 0 || borrow.amount == 0 {
-                continue;
-            }
+    /// Drop a proposed allowlist change
+    pub fn cancel_withdrawal_allowlist_change(
+        ctx: Context<UpdateWithdrawalAllowlist>,
+    ) -> Result<()> {
+        ctx.accounts.withdrawal_allowlist.cancel();
+        msg!("Withdrawal allowlist change cancelled");
+        Ok(())
+    }
+
+    /// Name the wallet that inherits the position after `inactivity_period` seconds
+    /// without an owner check-in (see `succession`). Counts as a check-in
+    pub fn designate_beneficiary(
+        ctx: Context<DesignateBeneficiary>,
+        beneficiary: Pubkey,
+        inactivity_period: i64,
+    ) -> Result<()> {
+        require!(
+            inactivity_period >= MIN_INACTIVITY_PERIOD,
+            LegasiError::InvalidAmount
+        );
+        require_keys_neq!(
+            beneficiary,
+            ctx.accounts.owner.key(),
+            LegasiError::Unauthorized
+        );
+        let succession = &mut ctx.accounts.succession;
+        succession.position = ctx.accounts.position.key();
+        succession.beneficiary = beneficiary;
+        succession.inactivity_period = inactivity_period;
+        succession.bump = ctx.bumps.succession;
+        succession.check_in(Clock::get()?.unix_timestamp);
+        msg!(
+            "Beneficiary {} designated after {}s of inactivity",
+            beneficiary,
+            inactivity_period
+        );
+        Ok(())
+    }
+
+    /// Prove the owner is active, cancelling any open succession claim
+    pub fn succession_check_in(ctx: Context<UpdateSuccession>) -> Result<()> {
+        ctx.accounts
+            .succession
+            .check_in(Clock::get()?.unix_timestamp);
+        msg!("Owner checked in");
+        Ok(())
+    }
+
+    /// Remove the beneficiary, closing the succession account
+    pub fn revoke_beneficiary(_ctx: Context<RevokeBeneficiary>) -> Result<()> {
+        msg!("Beneficiary revoked");
+        Ok(())
+    }
+
+    /// Open a succession claim (beneficiary only) once the owner has been inactive
+    /// for the designated period. The owner can cancel it by checking in during
+    /// `SUCCESSION_CHALLENGE_WINDOW`
+    pub fn start_succession_claim(ctx: Context<StartSuccessionClaim>) -> Result<()> {
+        let succession = &mut ctx.accounts.succession;
+        succession.start_claim(Clock::get()?.unix_timestamp)?;
+        msg!(
+            "Succession claim opened, completable at {}",
+            succession.claim_started_at + SUCCESSION_CHALLENGE_WINDOW
+        );
+        Ok(())
+    }
+
+    /// Move the position to the beneficiary once an unchallenged claim has run through
+    /// the challenge window: collateral, debt and reputation go to the beneficiary's
+    /// new position along with the SOL and mSOL vault balances, and the old position
+    /// is left empty. The mSOL accounts are required while the position holds mSOL
+    pub fn complete_succession(ctx: Context<CompleteSuccession>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.succession.require_claimable(now)?;
+        require!(
+            ctx.accounts.position.committed_letters_usd == 0,
+            LegasiError::LetterOfCreditActive
+        );
+
+        let mut inherited = Position::clone(&ctx.accounts.position);
+        inherited.owner = ctx.accounts.beneficiary.key();
+        inherited.last_update = now;
+        inherited.bump = ctx.bumps.new_position;
+        ctx.accounts.new_position.set_inner(inherited);
+
+        // SOL collateral follows the position to its new vault
+        let position_key = ctx.accounts.position.key();
+        let vault_seeds: &[&[u8]] = &[
+            SOL_VAULT_SEED,
+            position_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        let lamports = ctx.accounts.sol_vault.lamports();
+        if lamports > 0 {
+            invoke_signed(
+                &system_instruction::transfer(
+                    ctx.accounts.sol_vault.key,
+                    ctx.accounts.new_sol_vault.key,
+                    lamports,
+                ),
+                &[
+                    ctx.accounts.sol_vault.to_account_info(),
+                    ctx.accounts.new_sol_vault.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[vault_seeds],
+            )?;
+        }
 
-            // Calculate interest: principal * rate * time / year
-            // Using seconds: interest = amount * rate_bps * elapsed / (365.25 * 24 * 3600 * 10000)
-            let seconds_per_year: u128 = 31_557_600; // 365.25 days
-            let interest = (borrow.amount as u128)
-                .checked_mul(annual_rate_bps as u128)
-                .unwrap_or(0)
-                .checked_mul(elapsed as u128)
-                .unwrap_or(0)
-                .checked_div(seconds_per_year)
-                .unwrap_or(0)
-                .checked_div(BPS_DENOMINATOR as u128)
-                .unwrap_or(0) as u64;
-
-            borrow.accrued_interest = borrow.accrued_interest.saturating_add(interest);
+        // mSOL vaults are owned by their position PDA
+        let holds_msol = ctx
+            .accounts
+            .position
+            .collaterals
+            .iter()
+            .any(|c| c.asset_type == AssetType::MSOL);
+        let accounts = &ctx.accounts;
+        match (
+            &accounts.msol_vault,
+            &accounts.new_msol_vault,
+            &accounts.token_program,
+        ) {
+            (Some(msol_vault), Some(new_msol_vault), Some(token_program)) => {
+                let old_owner = accounts.position.owner;
+                let position_seeds: &[&[u8]] =
+                    &[POSITION_SEED, old_owner.as_ref(), &[accounts.position.bump]];
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        token_program.to_account_info(),
+                        Transfer {
+                            from: msol_vault.to_account_info(),
+                            to: new_msol_vault.to_account_info(),
+                            authority: accounts.position.to_account_info(),
+                        },
+                        &[position_seeds],
+                    ),
+                    msol_vault.amount,
+                )?;
+            }
+            _ => require!(!holds_msol, LegasiError::InvalidAmount),
         }
 
+        let position = &mut ctx.accounts.position;
+        position.collaterals.clear();
+        position.borrows.clear();
+        position.stake_provider = StakeProvider::None;
         position.last_update = now;
 
-        msg!("Interest accrued for position");
+        msg!(
+            "Position {} inherited by {}",
+            position_key,
+            ctx.accounts.beneficiary.key()
+        );
+        Ok(())
+    }
+
+    /// Opt the position into an eMode category (owner only, `EModeCategory::None` to
+    /// leave). With debt open, the market covering it must be passed and be in the new
+    /// category, and the position must fit the market's LTV for that category
+    /// Debt pools go in remaining_accounts (see `accrue_all_interest`)
+    pub fn set_emode(ctx: Context<SetEMode>, category: EModeCategory) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+
+        let position = &ctx.accounts.position;
+        if !position.borrows.is_empty() || position.committed_letters_usd > 0 {
+            let market = ctx.accounts.market.as_deref();
+            if category != EModeCategory::None {
+                require!(
+                    market.is_some_and(|m| m.emode_category == category),
+                    LegasiError::EModeMismatch
+                );
+            }
+            let max_ltv_bps = emode::max_ltv_bps(position, market, category, None)?;
+            let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+            let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+            position.require_within_ltv_at(
+                AssetType::USDC,
+                0,
+                &PriceBook::new(sol_price, eur_price),
+                max_ltv_bps,
+            )?;
+        }
+
+        let position = &mut ctx.accounts.position;
+        let old_category = position.emode.category;
+        position.emode = UserEMode {
+            category,
+            entered_at: now,
+        };
+
+        emit!(EModeSet {
+            position: position.key(),
+            owner: position.owner,
+            old_category: old_category as u8,
+            new_category: category as u8,
+        });
+
+        msg!("eMode set to {:?}", category);
         Ok(())
     }
 
@@ -573,11 +2628,12 @@ pub mod legasi_lending {
         require!(amount > 0, LegasiError::InvalidAmount);
         require!(destination_iban.len() > 10, LegasiError::InvalidAmount); // Basic IBAN validation
 
-        // Check user has borrowed this amount
+        // Check user has borrowed this amount of the burned stablecoin
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
         let position = &ctx.accounts.position;
         let mut borrowed_amount: u64 = 0;
         for borrow in &position.borrows {
-            if borrow.asset_type == AssetType::USDC || borrow.asset_type == AssetType::EURC {
+            if borrow.asset_type == asset_type {
                 borrowed_amount = borrowed_amount.saturating_add(borrow.amount);
             }
         }
@@ -613,685 +2669,4406 @@ pub mod legasi_lending {
             owner: ctx.accounts.owner.key(),
             amount,
             destination_iban,
-            asset_type: AssetType::USDC, // TODO: detect from mint
+            asset_type,
         });
 
         msg!(
-            "Off-ramp requested: {} USDC to {}",
+            "Off-ramp requested: {} {:?} to {}",
             amount,
+            asset_type,
             destination_name
         );
         Ok(())
     }
 
-    // ========== AGENT FUNCTIONS ==========
-
-    /// Configure agent settings for a position
-    /// Only the position owner can call this
-    pub fn configure_agent(
-        ctx: Context<ConfigureAgent>,
-        daily_borrow_limit: u64,
-        auto_repay_enabled: bool,
-        x402_enabled: bool,
-        alert_threshold_bps: u16,
-    ) -> Result<()> {
-        let agent_config = &mut ctx.accounts.agent_config;
-        agent_config.position = ctx.accounts.position.key();
-        agent_config.operator = ctx.accounts.owner.key();
-        agent_config.daily_borrow_limit = daily_borrow_limit;
-        agent_config.daily_borrowed = 0;
-        agent_config.period_start = Clock::get()?.unix_timestamp;
-        agent_config.auto_repay_enabled = auto_repay_enabled;
-        agent_config.x402_enabled = x402_enabled;
-        agent_config.alerts_enabled = true;
-        agent_config.alert_threshold_bps = alert_threshold_bps;
-        agent_config.bump = ctx.bumps.agent_config;
-
-        msg!("Agent configured with {} daily limit", daily_borrow_limit);
-        Ok(())
-    }
-
-    /// Update existing agent configuration
-    pub fn update_agent_config(
-        ctx: Context<UpdateAgentConfig>,
-        daily_borrow_limit: u64,
-        auto_repay_enabled: bool,
-        x402_enabled: bool,
-        alert_threshold_bps: u16,
+    /// Off-ramp LP savings in one step: burn `shares_amount` LP shares into an escrow
+    /// held by the request, for the bridge operator to pay out to the bank account
+    /// Partial fills follow `legasi_lp::withdraw`; the request records what was escrowed
+    pub fn withdraw_to_offramp(
+        ctx: Context<WithdrawToOfframp>,
+        _request_id: u64,
+        shares_amount: u64,
+        destination_iban: String,
+        destination_name: String,
     ) -> Result<()> {
-        let agent_config = &mut ctx.accounts.agent_config;
-        agent_config.daily_borrow_limit = daily_borrow_limit;
-        agent_config.auto_repay_enabled = auto_repay_enabled;
-        agent_config.x402_enabled = x402_enabled;
-        agent_config.alert_threshold_bps = alert_threshold_bps;
+        require!(destination_iban.len() > 10, LegasiError::InvalidAmount); // Basic IBAN validation
 
-        msg!("Agent config updated: {} daily limit", daily_borrow_limit);
-        Ok(())
-    }
+        legasi_lp::cpi::withdraw(
+            CpiContext::new(
+                ctx.accounts.lp_program.to_account_info(),
+                legasi_lp::cpi::accounts::LpWithdraw {
+                    lp_pool: ctx.accounts.lp_pool.to_account_info(),
+                    lp_token_mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    vault: ctx.accounts.lp_vault.to_account_info(),
+                    user_token_account: ctx.accounts.offramp_escrow.to_account_info(),
+                    user_lp_token_account: ctx.accounts.user_lp_token_account.to_account_info(),
+                    withdrawer: ctx.accounts.owner.to_account_info(),
+                    allowlist_entry: ctx
+                        .accounts
+                        .lp_allowlist_entry
+                        .as_ref()
+                        .map(|entry| entry.to_account_info()),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            ),
+            shares_amount,
+        )?;
+        ctx.accounts.offramp_escrow.reload()?;
+        let amount = ctx.accounts.offramp_escrow.amount;
 
-    /// Agent borrow - respects daily limits
-    /// Can be called by the agent (position owner) autonomously
-    pub fn agent_borrow(ctx: Context<AgentBorrow>, amount: u64) -> Result<()> {
-        require!(amount > 0, LegasiError::InvalidAmount);
+        let offramp = &mut ctx.accounts.offramp_request;
+        offramp.owner = ctx.accounts.owner.key();
+        offramp.amount = amount;
+        offramp.destination_iban = destination_iban.clone();
+        offramp.destination_name = destination_name.clone();
+        offramp.status = OfframpStatus::Pending;
+        offramp.created_at = Clock::get()?.unix_timestamp;
+        offramp.bump = ctx.bumps.offramp_request;
 
-        let agent_config = &ctx.accounts.agent_config;
-        let now = Clock::get()?.unix_timestamp;
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        emit!(OfframpRequested {
+            owner: ctx.accounts.owner.key(),
+            amount,
+            destination_iban,
+            asset_type,
+        });
 
-        // Check daily limit
-        require!(
-            agent_config.can_borrow(amount, now),
-            LegasiError::ExceedsLTV // Reuse error for "exceeds limit"
+        msg!(
+            "LP off-ramp requested: {} {:?} escrowed for {}",
+            amount,
+            asset_type,
+            destination_name
         );
+        Ok(())
+    }
 
-        // Get price and calculate max borrow (same as regular borrow)
-        let sol_price = ctx.accounts.sol_price_feed.price_usd_6dec;
-
-        let mut total_collateral_usd: u64 = 0;
-        for deposit in &ctx.accounts.position.collaterals {
-            if deposit.asset_type == AssetType::SOL || deposit.asset_type == AssetType::CbBTC {
-                let value = (deposit.amount as u128)
-                    .checked_mul(sol_price as u128)
-                    .ok_or(LegasiError::MathOverflow)?
-                    .checked_div(LAMPORTS_PER_SOL as u128)
-                    .ok_or(LegasiError::MathOverflow)? as u64;
-                total_collateral_usd = total_collateral_usd
-                    .checked_add(value)
-                    .ok_or(LegasiError::MathOverflow)?;
-            }
-        }
-
-        let mut current_borrow_usd: u64 = 0;
-        for borrow in &ctx.accounts.position.borrows {
-            let value = borrow
-                .amount
-                .checked_add(borrow.accrued_interest)
-                .ok_or(LegasiError::MathOverflow)?;
-            current_borrow_usd = current_borrow_usd
-                .checked_add(value)
-                .ok_or(LegasiError::MathOverflow)?;
+    /// Release an LP off-ramp escrow (admin): to the bridge deposit account once the
+    /// fiat transfer is initiated, or back to the owner if it failed
+    pub fn settle_offramp_escrow(
+        ctx: Context<SettleOfframpEscrow>,
+        request_id: u64,
+        completed: bool,
+    ) -> Result<()> {
+        if !completed {
+            require_keys_eq!(
+                ctx.accounts.destination.owner,
+                ctx.accounts.offramp_request.owner,
+                LegasiError::Unauthorized
+            );
         }
 
-        // Apply reputation bonus to LTV
-        let base_ltv = DEFAULT_SOL_MAX_LTV_BPS as u64;
-        let reputation_bonus = ctx.accounts.position.reputation.get_ltv_bonus_bps() as u64;
-        let effective_ltv = base_ltv.saturating_add(reputation_bonus);
-
-        let max_borrow = total_collateral_usd
-            .checked_mul(effective_ltv)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(LegasiError::MathOverflow)?;
-
-        let new_total_borrow = current_borrow_usd
-            .checked_add(amount)
-            .ok_or(LegasiError::MathOverflow)?;
-        require!(new_total_borrow <= max_borrow, LegasiError::ExceedsLTV);
-
-        // Transfer from vault to agent
-        let pool_bump = ctx.accounts.lp_pool.bump;
-        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
-        let seeds: &[&[u8]] = &[b"lp_pool", borrowable_mint.as_ref(), &[pool_bump]];
-
+        let owner = ctx.accounts.offramp_request.owner;
+        let request_id_bytes = request_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[
+            OFFRAMP_SEED,
+            owner.as_ref(),
+            &request_id_bytes,
+            &[ctx.accounts.offramp_request.bump],
+        ];
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.borrow_vault.to_account_info(),
-                    to: ctx.accounts.agent_token_account.to_account_info(),
-                    authority: ctx.accounts.lp_pool.to_account_info(),
+                    from: ctx.accounts.offramp_escrow.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.offramp_request.to_account_info(),
                 },
                 &[seeds],
             ),
-            amount,
+            ctx.accounts.offramp_escrow.amount,
         )?;
 
-        // Update position
-        let position = &mut ctx.accounts.position;
-        let asset_type = AssetType::USDC; // Default to USDC for agents
+        let offramp = &mut ctx.accounts.offramp_request;
+        offramp.status = if completed {
+            OfframpStatus::Completed
+        } else {
+            OfframpStatus::Failed
+        };
+        offramp.completed_at = Clock::get()?.unix_timestamp;
 
-        let mut found = false;
-        for borrow in position.borrows.iter_mut() {
-            if borrow.asset_type == asset_type {
-                borrow.amount = borrow
-                    .amount
-                    .checked_add(amount)
-                    .ok_or(LegasiError::MathOverflow)?;
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            require!(
-                position.borrows.len() < 4,
-                LegasiError::MaxBorrowTypesReached
-            );
-            position.borrows.push(BorrowedAmount {
-                asset_type,
-                amount,
-                accrued_interest: 0,
-            });
-        }
-        position.last_update = now;
+        msg!(
+            "Off-ramp {} of {} settled: {:?}",
+            request_id,
+            owner,
+            offramp.status
+        );
+        Ok(())
+    }
 
-        // Update agent config daily borrowed
-        let agent_config = &mut ctx.accounts.agent_config;
-        agent_config.record_borrow(amount, now);
+    // ========== REFERRALS ==========
 
-        // Update pool
-        let lp_pool = &mut ctx.accounts.lp_pool;
-        lp_pool.total_borrowed = lp_pool
-            .total_borrowed
-            .checked_add(amount)
-            .ok_or(LegasiError::MathOverflow)?;
+    /// Register as a referrer; borrowers pass this account to initialize_position
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let registry = &mut ctx.accounts.referrer_account;
+        registry.referrer = ctx.accounts.referrer.key();
+        registry.referred_positions = 0;
+        registry.total_claimed = 0;
+        registry.bump = ctx.bumps.referrer_account;
 
-        emit!(AgentBorrowed {
-            position: ctx.accounts.position.key(),
-            amount,
-            daily_remaining: agent_config
-                .daily_borrow_limit
-                .saturating_sub(agent_config.daily_borrowed),
-        });
+        msg!("Referrer registered: {}", registry.referrer);
+        Ok(())
+    }
 
-        msg!("Agent borrowed {} USDC", amount);
+    /// Open the vault that collects referral rewards in one borrowable asset
+    /// Until it exists, the referral cut on that asset stays with LPs
+    pub fn open_referral_vault(ctx: Context<OpenReferralVault>) -> Result<()> {
+        msg!(
+            "Referral vault opened for {} in {}",
+            ctx.accounts.referrer.key(),
+            ctx.accounts.mint.key()
+        );
         Ok(())
     }
 
-    /// Agent auto-repay - automatically repay debt when USDC is received
-    pub fn agent_auto_repay(ctx: Context<AgentAutoRepay>, amount: u64) -> Result<()> {
+    /// Sweep a referral vault to the referrer
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let amount = ctx.accounts.referral_vault.amount;
         require!(amount > 0, LegasiError::InvalidAmount);
-        require!(
-            ctx.accounts.agent_config.auto_repay_enabled,
-            LegasiError::Unauthorized
-        );
 
-        // Transfer from agent to vault
+        let referrer = ctx.accounts.referrer.key();
+        let seeds: &[&[u8]] = &[
+            REFERRER_SEED,
+            referrer.as_ref(),
+            &[ctx.accounts.referrer_account.bump],
+        ];
         token::transfer(
-            CpiContext::new(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.agent_token_account.to_account_info(),
-                    to: ctx.accounts.borrow_vault.to_account_info(),
-                    authority: ctx.accounts.agent.to_account_info(),
+                    from: ctx.accounts.referral_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.referrer_account.to_account_info(),
                 },
+                &[seeds],
             ),
             amount,
         )?;
 
-        // Reduce debt
-        let position = &mut ctx.accounts.position;
-        let mut remaining = amount;
+        let registry = &mut ctx.accounts.referrer_account;
+        registry.total_claimed = registry.total_claimed.saturating_add(amount);
 
-        for borrow in position.borrows.iter_mut() {
-            if remaining == 0 {
-                break;
-            }
+        msg!("Claimed {} referral rewards", amount);
+        Ok(())
+    }
 
-            // First reduce interest
-            let interest_payment = std::cmp::min(remaining, borrow.accrued_interest);
-            borrow.accrued_interest = borrow.accrued_interest.saturating_sub(interest_payment);
-            remaining = remaining.saturating_sub(interest_payment);
+    // ========== POINTS ==========
 
-            // Then principal
-            let principal_payment = std::cmp::min(remaining, borrow.amount);
-            borrow.amount = borrow.amount.saturating_sub(principal_payment);
-            remaining = remaining.saturating_sub(principal_payment);
-        }
+    /// Open the points ledger of a position; points start with the first accrual after it
+    pub fn open_points_ledger(ctx: Context<OpenPointsLedger>) -> Result<()> {
+        let ledger = &mut ctx.accounts.points_ledger;
+        ledger.position = ctx.accounts.position.key();
+        ledger.owner = ctx.accounts.owner.key();
+        ledger.points = 0;
+        ledger.last_accrual = Clock::get()?.unix_timestamp;
+        ledger.collateral_usd = 0;
+        ledger.debt_usd = 0;
+        ledger.bump = ctx.bumps.points_ledger;
 
-        position
-            .borrows
-            .retain(|b| b.amount > 0 || b.accrued_interest > 0);
-        position.last_update = Clock::get()?.unix_timestamp;
-        position.reputation.successful_repayments =
-            position.reputation.successful_repayments.saturating_add(1);
-        position.reputation.total_repaid_usd =
-            position.reputation.total_repaid_usd.saturating_add(amount);
+        msg!("Points ledger opened for {}", ledger.position);
+        Ok(())
+    }
 
-        // Update pool
-        let lp_pool = &mut ctx.accounts.lp_pool;
-        lp_pool.total_borrowed = lp_pool
-            .total_borrowed
-            .saturating_sub(amount.saturating_sub(remaining));
+    /// Credit a position's points up to now (permissionless crank)
+    pub fn accrue_points(ctx: Context<AccruePoints>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let position = &ctx.accounts.position;
+        let collateral_usd =
+            position.collateral_value_usd(ctx.accounts.sol_price_feed.get_checked_price(now)?)?;
+        let debt_usd = position.debt_usd(eur_usd_price(&ctx.accounts.eur_price_feed, now)?)?;
+
+        let protocol = &ctx.accounts.protocol;
+        let earned = ctx.accounts.points_ledger.accrue(
+            collateral_usd,
+            debt_usd,
+            protocol.points_deposit_weight_bps,
+            protocol.points_borrow_weight_bps,
+            now,
+        );
 
         msg!(
-            "Agent auto-repaid {} USDC",
-            amount.saturating_sub(remaining)
+            "Accrued {} points, {} total",
+            earned,
+            ctx.accounts.points_ledger.points
         );
         Ok(())
     }
 
-    // ========== x402 PAYMENT FUNCTIONS ==========
-
-    /// Process an x402 payment request
-    /// Agent pays for a service, borrowing if needed
-    pub fn x402_pay(
-        ctx: Context<X402Pay>,
-        payment_request: X402PaymentRequest,
-        auto_borrow: bool, // Borrow if insufficient balance
+    /// Record the Merkle root of every points ledger at the end of `epoch`
+    pub fn post_points_snapshot(
+        ctx: Context<PostPointsSnapshot>,
+        epoch: u32,
+        merkle_root: [u8; 32],
+        total_points: u64,
+        leaf_count: u32,
     ) -> Result<()> {
-        let now = Clock::get()?.unix_timestamp;
+        require!(leaf_count > 0, LegasiError::InvalidAmount);
 
-        // Verify request is valid
-        require!(payment_request.is_valid(now), LegasiError::InvalidAmount);
-        require!(
-            ctx.accounts.agent_config.x402_enabled,
-            LegasiError::Unauthorized
-        );
+        let snapshot = &mut ctx.accounts.points_snapshot;
+        snapshot.epoch = epoch;
+        snapshot.merkle_root = merkle_root;
+        snapshot.total_points = total_points;
+        snapshot.leaf_count = leaf_count;
+        snapshot.created_at = Clock::get()?.unix_timestamp;
+        snapshot.bump = ctx.bumps.points_snapshot;
 
-        let amount = payment_request.amount;
+        msg!(
+            "Points snapshot {}: {} points over {} positions",
+            epoch,
+            total_points,
+            leaf_count
+        );
+        Ok(())
+    }
 
-        // Check agent has enough balance
-        let agent_balance = ctx.accounts.agent_token_account.amount;
+    // ========== STATEMENTS ==========
 
-        if agent_balance < amount && auto_borrow {
-            // Need to borrow the difference
-            let borrow_amount = amount.saturating_sub(agent_balance);
+    /// Open the position's statement ledger, its first period starting now. The mSOL
+    /// rate is only measured with the Marinade State passed
+    /// Debt pools go in remaining_accounts (see `accrue_all_interest`)
+    pub fn open_statement_ledger(ctx: Context<OpenStatementLedger>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+        let msol_rate = read_msol_rate(&ctx.accounts.marinade_state)?;
 
-            // Check daily limit
-            require!(
-                ctx.accounts.agent_config.can_borrow(borrow_amount, now),
-                LegasiError::ExceedsLTV
-            );
+        let position = &ctx.accounts.position;
+        let ledger = &mut ctx.accounts.statement_ledger;
+        ledger.position = position.key();
+        ledger.owner = position.owner;
+        ledger.period_start = now;
+        ledger.totals_at_start = position.statement_totals;
+        ledger.gad_liquidated_at_start = position.total_gad_liquidated_usd;
+        ledger.msol_rate_at_start = msol_rate.unwrap_or(0);
+        ledger.statements = 0;
+        ledger.bump = ctx.bumps.statement_ledger;
+
+        msg!("Statement ledger opened for {}", ledger.position);
+        Ok(())
+    }
 
-            // Borrow from pool
-            let pool_bump = ctx.accounts.lp_pool.bump;
-            let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
-            let seeds: &[&[u8]] = &[b"lp_pool", borrowable_mint.as_ref(), &[pool_bump]];
+    /// Emit the `PositionStatement` of the period just ended and start the next one
+    /// (permissionless crank, once per `STATEMENT_PERIOD`)
+    /// Debt pools go in remaining_accounts (see `accrue_all_interest`)
+    pub fn crank_position_statement(ctx: Context<CrankPositionStatement>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.statement_ledger.require_period_over(now)?;
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let msol_rate = read_msol_rate(&ctx.accounts.marinade_state)?;
 
-            token::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.borrow_vault.to_account_info(),
-                        to: ctx.accounts.agent_token_account.to_account_info(),
-                        authority: ctx.accounts.lp_pool.to_account_info(),
-                    },
-                    &[seeds],
+        let position = &ctx.accounts.position;
+        let ledger = &mut ctx.accounts.statement_ledger;
+        let charges = position
+            .statement_totals
+            .usd_since(&ledger.totals_at_start, eur_price)?;
+        let yield_earned_usd = match msol_rate {
+            Some(rate) => {
+                let msol = position
+                    .collaterals
+                    .iter()
+                    .filter(|c| c.asset_type == AssetType::MSOL)
+                    .map(|c| c.amount)
+                    .sum();
+                staking_yield_usd(msol, ledger.msol_rate_at_start, rate, sol_price)?
+            }
+            None => 0,
+        };
+        let gad_deducted_usd = position
+            .total_gad_liquidated_usd
+            .saturating_sub(ledger.gad_liquidated_at_start);
+
+        emit!(PositionStatement {
+            position: position.key(),
+            owner: position.owner,
+            period_start: ledger.period_start,
+            period_end: now,
+            interest_accrued_usd: charges.interest,
+            yield_earned_usd,
+            gad_deducted_usd,
+            fees_usd: charges.fees,
+        });
+        ledger.roll(
+            position.statement_totals,
+            position.total_gad_liquidated_usd,
+            msol_rate,
+            now,
+        );
+
+        msg!(
+            "Statement {}: {} interest, {} fees, {} GAD",
+            ledger.statements,
+            charges.interest,
+            charges.fees,
+            gad_deducted_usd
+        );
+        Ok(())
+    }
+
+    // ========== NOTIFICATIONS ==========
+
+    /// Register the signer as a notification service charging `fee_lamports` per notice
+    pub fn register_notify_service(
+        ctx: Context<RegisterNotifyService>,
+        fee_lamports: u64,
+    ) -> Result<()> {
+        let notify_service = &mut ctx.accounts.notify_service;
+        notify_service.service = ctx.accounts.service.key();
+        notify_service.fee_lamports = fee_lamports;
+        notify_service.active = true;
+        notify_service.subscribers = 0;
+        notify_service.notices_sent = 0;
+        notify_service.bump = ctx.bumps.notify_service;
+
+        msg!(
+            "Notification service {} registered at {} lamports",
+            notify_service.service,
+            fee_lamports
+        );
+        Ok(())
+    }
+
+    /// Change the service's fee for new subscriptions, or pause it. Existing
+    /// subscriptions keep their fee
+    pub fn update_notify_service(
+        ctx: Context<UpdateNotifyService>,
+        fee_lamports: u64,
+        active: bool,
+    ) -> Result<()> {
+        let notify_service = &mut ctx.accounts.notify_service;
+        notify_service.fee_lamports = fee_lamports;
+        notify_service.active = active;
+
+        msg!(
+            "Notification service {}: {} lamports, active {}",
+            notify_service.service,
+            fee_lamports,
+            active
+        );
+        Ok(())
+    }
+
+    /// Subscribe the position to a service's notices from `alert_ltv_bps`, prepaying
+    /// `prepaid_lamports` of fees into the subscription's fee vault
+    pub fn subscribe_notifications(
+        ctx: Context<SubscribeNotifications>,
+        alert_ltv_bps: u16,
+        prepaid_lamports: u64,
+    ) -> Result<()> {
+        require!(alert_ltv_bps > 0, LegasiError::InvalidAmount);
+        require!(
+            ctx.accounts.notify_service.active,
+            LegasiError::NotifyServiceInactive
+        );
+
+        if prepaid_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    ctx.accounts.owner.key,
+                    ctx.accounts.fee_vault.key,
+                    prepaid_lamports,
                 ),
-                borrow_amount,
+                &[
+                    ctx.accounts.owner.to_account_info(),
+                    ctx.accounts.fee_vault.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
             )?;
+        }
 
-            // Update position debt
-            let position = &mut ctx.accounts.position;
-            let asset_type = AssetType::USDC;
+        let notify_service = &mut ctx.accounts.notify_service;
+        notify_service.subscribers = notify_service.subscribers.saturating_add(1);
 
-            let mut found = false;
-            for borrow in position.borrows.iter_mut() {
-                if borrow.asset_type == asset_type {
-                    borrow.amount = borrow
-                        .amount
-                        .checked_add(borrow_amount)
-                        .ok_or(LegasiError::MathOverflow)?;
-                    found = true;
-                    break;
-                }
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.position = ctx.accounts.position.key();
+        subscription.owner = ctx.accounts.owner.key();
+        subscription.service = notify_service.service;
+        subscription.alert_ltv_bps = alert_ltv_bps;
+        subscription.fee_lamports = notify_service.fee_lamports;
+        subscription.last_notified = 0;
+        subscription.notices = 0;
+        subscription.bump = ctx.bumps.subscription;
+
+        msg!(
+            "Position {} subscribed to {} from {} bps",
+            subscription.position,
+            subscription.service,
+            alert_ltv_bps
+        );
+        Ok(())
+    }
+
+    /// Close the subscription, returning its unspent fees and rent to the owner
+    pub fn unsubscribe_notifications(ctx: Context<UnsubscribeNotifications>) -> Result<()> {
+        let refund = ctx.accounts.fee_vault.lamports();
+        if refund > 0 {
+            let subscription_key = ctx.accounts.subscription.key();
+            let seeds: &[&[u8]] = &[
+                NOTIFY_FEE_VAULT_SEED,
+                subscription_key.as_ref(),
+                &[ctx.bumps.fee_vault],
+            ];
+            invoke_signed(
+                &system_instruction::transfer(
+                    ctx.accounts.fee_vault.key,
+                    ctx.accounts.owner.key,
+                    refund,
+                ),
+                &[
+                    ctx.accounts.fee_vault.to_account_info(),
+                    ctx.accounts.owner.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[seeds],
+            )?;
+        }
+
+        let notify_service = &mut ctx.accounts.notify_service;
+        notify_service.subscribers = notify_service.subscribers.saturating_sub(1);
+
+        msg!(
+            "Position {} unsubscribed from {}, {} lamports refunded",
+            ctx.accounts.subscription.position,
+            notify_service.service,
+            refund
+        );
+        Ok(())
+    }
+
+    /// Pay the service its fee and emit `NotifySubscriber` for a position at its alert
+    /// LTV (permissionless crank, once per `NOTIFY_COOLDOWN`)
+    /// Debt pools go in remaining_accounts (see `accrue_all_interest`)
+    pub fn notify_subscriber(ctx: Context<NotifySubscriberCrank>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.notify_service.active,
+            LegasiError::NotifyServiceInactive
+        );
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let ltv_bps = ctx.accounts.position.ltv_bps(sol_price, eur_price)?;
+        ctx.accounts.subscription.require_due(ltv_bps, now)?;
+
+        // The vault keeps its rent-exempt minimum until unsubscribed
+        let fee = ctx.accounts.subscription.fee_lamports;
+        let available = ctx
+            .accounts
+            .fee_vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(available >= fee, LegasiError::SubscriptionUnfunded);
+        if fee > 0 {
+            let subscription_key = ctx.accounts.subscription.key();
+            let seeds: &[&[u8]] = &[
+                NOTIFY_FEE_VAULT_SEED,
+                subscription_key.as_ref(),
+                &[ctx.bumps.fee_vault],
+            ];
+            invoke_signed(
+                &system_instruction::transfer(
+                    ctx.accounts.fee_vault.key,
+                    ctx.accounts.service.key,
+                    fee,
+                ),
+                &[
+                    ctx.accounts.fee_vault.to_account_info(),
+                    ctx.accounts.service.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[seeds],
+            )?;
+        }
+
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.last_notified = now;
+        subscription.notices = subscription.notices.saturating_add(1);
+        let notify_service = &mut ctx.accounts.notify_service;
+        notify_service.notices_sent = notify_service.notices_sent.saturating_add(1);
+
+        emit!(NotifySubscriber {
+            service: subscription.service,
+            position: subscription.position,
+            owner: subscription.owner,
+            ltv_bps,
+            alert_ltv_bps: subscription.alert_ltv_bps,
+            fee_paid: fee,
+        });
+
+        msg!(
+            "Notified {} of position {} at {} bps",
+            subscription.service,
+            subscription.position,
+            ltv_bps
+        );
+        Ok(())
+    }
+
+    // ========== GOVERNANCE ==========
+
+    /// Record the owner's bUSDC and net deposits as voting power for the current `epoch`
+    pub fn snapshot_voting_power(ctx: Context<SnapshotVotingPower>, epoch: u32) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(epoch == voting_epoch(now), LegasiError::NotCurrentEpoch);
+
+        let lp_shares = ctx
+            .accounts
+            .lp_token_account
+            .as_ref()
+            .map_or(0, |a| a.amount);
+        let lp_value = if lp_shares > 0 {
+            ctx.accounts.lp_pool.tokens_for_shares(lp_shares)?
+        } else {
+            0
+        };
+        let net_deposits = match &ctx.accounts.position {
+            Some(position) => net_deposits_usd(
+                position
+                    .collateral_value_usd(ctx.accounts.sol_price_feed.get_checked_price(now)?)?,
+                position.debt_usd(eur_usd_price(&ctx.accounts.eur_price_feed, now)?)?,
+            ),
+            None => 0,
+        };
+
+        let power = &mut ctx.accounts.voting_power;
+        power.owner = ctx.accounts.owner.key();
+        power.epoch = epoch;
+        power.lp_shares = lp_shares;
+        power.lp_value = lp_value;
+        power.net_deposits_usd = net_deposits;
+        power.voting_power = lp_value
+            .checked_add(net_deposits)
+            .ok_or(LegasiError::MathOverflow)?;
+        power.snapshot_at = now;
+        power.bump = ctx.bumps.voting_power;
+
+        msg!(
+            "Voting power at epoch {}: {} ({} bUSDC, {} net deposits)",
+            epoch,
+            power.voting_power,
+            lp_value,
+            net_deposits
+        );
+        Ok(())
+    }
+
+    // ========== AGENT FUNCTIONS ==========
+
+    /// Configure agent settings for a position
+    /// Only the position owner can call this
+    pub fn configure_agent(
+        ctx: Context<ConfigureAgent>,
+        daily_borrow_limit: u64,
+        auto_repay_enabled: bool,
+        x402_enabled: bool,
+        alert_threshold_bps: u16,
+    ) -> Result<()> {
+        let agent_config = &mut ctx.accounts.agent_config;
+        agent_config.position = ctx.accounts.position.key();
+        agent_config.operator = ctx.accounts.owner.key();
+        agent_config.daily_borrow_limit = daily_borrow_limit;
+        agent_config.daily_borrowed = 0;
+        agent_config.period_start = Clock::get()?.unix_timestamp;
+        agent_config.auto_repay_enabled = auto_repay_enabled;
+        agent_config.x402_enabled = x402_enabled;
+        agent_config.alerts_enabled = true;
+        agent_config.alert_threshold_bps = alert_threshold_bps;
+        agent_config.activity_limit = ActivityLimit::default();
+        agent_config.bump = ctx.bumps.agent_config;
+
+        msg!("Agent configured with {} daily limit", daily_borrow_limit);
+        Ok(())
+    }
+
+    /// Update existing agent configuration
+    pub fn update_agent_config(
+        ctx: Context<UpdateAgentConfig>,
+        daily_borrow_limit: u64,
+        auto_repay_enabled: bool,
+        x402_enabled: bool,
+        alert_threshold_bps: u16,
+    ) -> Result<()> {
+        let agent_config = &mut ctx.accounts.agent_config;
+        agent_config.daily_borrow_limit = daily_borrow_limit;
+        agent_config.auto_repay_enabled = auto_repay_enabled;
+        agent_config.x402_enabled = x402_enabled;
+        agent_config.alert_threshold_bps = alert_threshold_bps;
+
+        msg!("Agent config updated: {} daily limit", daily_borrow_limit);
+        Ok(())
+    }
+
+    /// Space the agent's borrows and payments at least `min_interval` seconds apart,
+    /// allowing `max_batch` of them in one slot (0 interval = no limit)
+    pub fn set_agent_rate_limit(
+        ctx: Context<UpdateAgentConfig>,
+        min_interval: i64,
+        max_batch: u8,
+    ) -> Result<()> {
+        require!(min_interval >= 0, LegasiError::InvalidAmount);
+        let limit = &mut ctx.accounts.agent_config.activity_limit;
+        limit.min_interval = min_interval;
+        limit.max_batch = max_batch;
+
+        msg!(
+            "Agent rate limit: {}s interval, {} per batch",
+            min_interval,
+            max_batch
+        );
+        Ok(())
+    }
+
+    /// Publish or overwrite the agent's profile (hashes of its name and service URI)
+    pub fn set_agent_profile(
+        ctx: Context<SetAgentProfile>,
+        name_hash: [u8; 32],
+        service_uri_hash: [u8; 32],
+        category: AgentCategory,
+    ) -> Result<()> {
+        let profile = &mut ctx.accounts.agent_profile;
+        profile.agent_config = ctx.accounts.agent_config.key();
+        profile.name_hash = name_hash;
+        profile.service_uri_hash = service_uri_hash;
+        profile.category = category;
+        profile.updated_at = Clock::get()?.unix_timestamp;
+        profile.bump = ctx.bumps.agent_profile;
+
+        msg!("Agent profile set: {:?}", category);
+        Ok(())
+    }
+
+    /// Take the agent's profile down and refund its rent
+    pub fn close_agent_profile(_ctx: Context<CloseAgentProfile>) -> Result<()> {
+        msg!("Agent profile closed");
+        Ok(())
+    }
+
+    /// Agent borrow - respects daily limits
+    /// Can be called by the agent (position owner) autonomously
+    pub fn agent_borrow(ctx: Context<AgentBorrow>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+        ctx.accounts.protocol.require_not_paused(PAUSE_BORROWS)?;
+        require!(
+            ctx.accounts.borrowable_config.is_active,
+            LegasiError::AssetNotActive
+        );
+        gate::check_gate(
+            &ctx.accounts.market_gate,
+            ctx.accounts.gate_pass.as_deref(),
+            &ctx.accounts.position.owner,
+        )?;
+        AssetCaps::load(&ctx.accounts.asset_caps)?
+            .require_borrow(ctx.accounts.lp_pool.total_borrowed, amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts
+            .agent_config
+            .activity_limit
+            .record(now, Clock::get()?.slot)?;
+        // Borrow the pool's asset (USDC or EURC)
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        ctx.accounts
+            .position
+            .accrue_interest(asset_type, &ctx.accounts.lp_pool, now)?;
+
+        // Check daily limit (USD, across pools)
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let amount_usd = asset_type.debt_to_usd(amount, eur_price)?;
+        require!(
+            ctx.accounts.agent_config.can_borrow(amount_usd, now),
+            LegasiError::ExceedsLTV // Reuse error for "exceeds limit"
+        );
+
+        require_breakers_clear(
+            &ctx.accounts.position,
+            &ctx.accounts.sol_price_feed,
+            &None,
+            now,
+        )?;
+
+        // Get price and calculate max borrow (same as regular borrow)
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        ctx.accounts
+            .position
+            .require_within_ltv(asset_type, amount, sol_price, eur_price)?;
+
+        // Transfer from vault to agent
+        legasi_lp::lend(
+            &ctx.accounts.lp_program.to_account_info(),
+            legasi_lp::cpi::accounts::Lend {
+                lp_pool: ctx.accounts.lp_pool.to_account_info(),
+                vault: ctx.accounts.borrow_vault.to_account_info(),
+                destination: ctx.accounts.agent_token_account.to_account_info(),
+                writer: ctx.accounts.protocol_writer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+            ctx.bumps.protocol_writer,
+            amount,
+        )?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            0,
+            totals::usd_delta(amount_usd),
+        )?;
+
+        // Update position
+        let position = &mut ctx.accounts.position;
+        require!(position.gad_enabled, LegasiError::GadDisabled);
+        position.start_debt_clock(now)?;
+
+        let mut found = false;
+        for borrow in position.borrows.iter_mut() {
+            if borrow.asset_type == asset_type {
+                require!(!borrow.is_fixed_term(), LegasiError::BorrowTermMismatch);
+                borrow.amount = borrow
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(LegasiError::MathOverflow)?;
+                found = true;
+                break;
             }
-            if !found {
-                position.borrows.push(BorrowedAmount {
+        }
+        if !found {
+            require!(
+                position.borrows.len() < 4,
+                LegasiError::MaxBorrowTypesReached
+            );
+            position.borrows.push(BorrowedAmount::new(
+                asset_type,
+                amount,
+                ctx.accounts.lp_pool.borrow_index_at(now)?,
+                now,
+            ));
+        }
+        position.last_update = now;
+
+        // Update agent config daily borrowed
+        let agent_config = &mut ctx.accounts.agent_config;
+        agent_config.record_borrow(amount_usd, now);
+
+        let ltv_bps = ctx.accounts.position.ltv_bps(sol_price, eur_price)?;
+        emit!(AgentBorrowed {
+            position: ctx.accounts.position.key(),
+            amount,
+            daily_remaining: agent_config
+                .daily_borrow_limit
+                .saturating_sub(agent_config.daily_borrowed),
+            ltv_bps,
+            alert_threshold_breached: agent_config.alert_breached(ltv_bps),
+        });
+
+        msg!("Agent borrowed {} {:?}", amount, asset_type);
+        Ok(())
+    }
+
+    /// Commit collateral to a borrowing limit in the borrowable's asset
+    /// The agent can then draw up to `limit` with no daily cap; the unused part pays
+    /// a standby fee and stays backed by collateral until the line is closed
+    pub fn open_credit_line(ctx: Context<OpenCreditLine>, limit: u64) -> Result<()> {
+        require!(limit > 0, LegasiError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+
+        let line = &mut ctx.accounts.credit_line;
+        line.position = ctx.accounts.position.key();
+        line.asset_type = ctx.accounts.borrowable_config.asset_type;
+        line.limit = limit;
+        line.last_fee_accrual = now;
+        line.bump = ctx.bumps.credit_line;
+
+        // Collateral must back existing debt plus the whole unused limit
+        ctx.accounts.position.require_within_ltv(
+            line.asset_type,
+            line.unused(&ctx.accounts.position),
+            ctx.accounts.sol_price_feed.get_checked_price(now)?,
+            eur_usd_price(&ctx.accounts.eur_price_feed, now)?,
+        )?;
+
+        msg!("Credit line opened: {} {:?}", limit, line.asset_type);
+        Ok(())
+    }
+
+    /// Draw on the credit line (agent / position owner)
+    pub fn draw_credit_line(ctx: Context<DrawCreditLine>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+        ctx.accounts.protocol.require_not_paused(PAUSE_BORROWS)?;
+        gate::check_gate(
+            &ctx.accounts.market_gate,
+            ctx.accounts.gate_pass.as_deref(),
+            &ctx.accounts.position.owner,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        // The fee may open the entry the draw adds to, so it goes first and the
+        // accrual gives that entry the pool's index
+        ctx.accounts
+            .credit_line
+            .accrue_fee(&mut ctx.accounts.position, now)?;
+        ctx.accounts.position.accrue_interest(
+            ctx.accounts.borrowable_config.asset_type,
+            &ctx.accounts.lp_pool,
+            now,
+        )?;
+
+        let asset_type = ctx.accounts.credit_line.asset_type;
+        require!(
+            amount <= ctx.accounts.credit_line.unused(&ctx.accounts.position),
+            LegasiError::ExceedsCreditLine
+        );
+
+        require_breakers_clear(
+            &ctx.accounts.position,
+            &ctx.accounts.sol_price_feed,
+            &None,
+            now,
+        )?;
+
+        // The limit was backed at open, but prices move
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        ctx.accounts
+            .position
+            .require_within_ltv(asset_type, amount, sol_price, eur_price)?;
+
+        legasi_lp::lend(
+            &ctx.accounts.lp_program.to_account_info(),
+            legasi_lp::cpi::accounts::Lend {
+                lp_pool: ctx.accounts.lp_pool.to_account_info(),
+                vault: ctx.accounts.borrow_vault.to_account_info(),
+                destination: ctx.accounts.agent_token_account.to_account_info(),
+                writer: ctx.accounts.protocol_writer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+            ctx.bumps.protocol_writer,
+            amount,
+        )?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            0,
+            totals::usd_delta(asset_type.debt_to_usd(amount, eur_price)?),
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        require!(position.gad_enabled, LegasiError::GadDisabled);
+        position.start_debt_clock(now)?;
+        match position
+            .borrows
+            .iter_mut()
+            .find(|b| b.asset_type == asset_type)
+        {
+            Some(borrow) => {
+                require!(!borrow.is_fixed_term(), LegasiError::BorrowTermMismatch);
+                borrow.amount = borrow
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(LegasiError::MathOverflow)?;
+            }
+            None => {
+                require!(
+                    position.borrows.len() < MAX_BORROW_TYPES,
+                    LegasiError::MaxBorrowTypesReached
+                );
+                position.borrows.push(BorrowedAmount::new(
                     asset_type,
-                    amount: borrow_amount,
-                    accrued_interest: 0,
-                });
+                    amount,
+                    ctx.accounts.lp_pool.borrow_index_at(now)?,
+                    now,
+                ));
             }
+        }
+        position.last_update = now;
 
-            // Update agent config
-            let agent_config = &mut ctx.accounts.agent_config;
-            agent_config.record_borrow(borrow_amount, now);
+        let ltv_bps = ctx.accounts.position.ltv_bps(sol_price, eur_price)?;
+        emit!(CreditLineDrawn {
+            position: ctx.accounts.position.key(),
+            asset_type,
+            amount,
+            unused: ctx.accounts.credit_line.unused(&ctx.accounts.position),
+            ltv_bps,
+        });
+
+        msg!("Drew {} {:?} on credit line", amount, asset_type);
+        Ok(())
+    }
+
+    /// Book the standby fee on the unused limit (anyone can crank)
+    pub fn accrue_standby_fee(ctx: Context<AccrueStandbyFee>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let fee = ctx
+            .accounts
+            .credit_line
+            .accrue_fee(&mut ctx.accounts.position, now)?;
+
+        msg!("Standby fee accrued: {}", fee);
+        Ok(())
+    }
+
+    /// Close the credit line, releasing the collateral behind its unused limit
+    /// Drawn debt stays on the position
+    pub fn close_credit_line(ctx: Context<CloseCreditLine>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts
+            .credit_line
+            .accrue_fee(&mut ctx.accounts.position, now)?;
+
+        msg!("Credit line closed");
+        Ok(())
+    }
+
+    /// Agent auto-repay - automatically repay debt when USDC is received
+    pub fn agent_auto_repay(ctx: Context<AgentAutoRepay>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+        require!(
+            ctx.accounts.agent_config.auto_repay_enabled,
+            LegasiError::Unauthorized
+        );
+
+        // Repay the pool's asset only
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts
+            .position
+            .accrue_interest(asset_type, &ctx.accounts.lp_pool, now)?;
+        let total_owed = ctx.accounts.position.total_owed(asset_type)?;
+        let amount = std::cmp::min(amount, total_owed);
+        require!(amount > 0, LegasiError::InvalidAmount);
+
+        // Transfer from agent to vault
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.agent_token_account.to_account_info(),
+                    to: ctx.accounts.borrow_vault.to_account_info(),
+                    authority: ctx.accounts.agent.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // Reduce debt, interest first
+        let interest_paid = ctx
+            .accounts
+            .position
+            .apply_repayment(asset_type, amount, now);
+        let principal_paid = amount.saturating_sub(interest_paid);
+
+        let eur_price = match asset_type {
+            AssetType::EURC => eur_usd_price(&ctx.accounts.eur_price_feed, now)?,
+            _ => None,
+        };
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            0,
+            -totals::usd_delta(asset_type.debt_to_usd(principal_paid, eur_price)?),
+        )?;
+        legasi_lp::report_borrowed(
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.lp_pool.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(principal_paid),
+        )?;
+        credit_interest(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.lp_pool.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            interest_paid,
+        )?;
+
+        msg!("Agent auto-repaid {} {:?}", amount, asset_type);
+        Ok(())
+    }
+
+    /// Crank auto-repay - sweep funds that arrived at an agent's token account into repayment
+    /// The agent approves its agent_config PDA as delegate on the account once;
+    /// any keeper can then crank repayments as USDC comes in, capped at the outstanding debt
+    pub fn crank_auto_repay(ctx: Context<CrankAutoRepay>) -> Result<()> {
+        require!(
+            ctx.accounts.agent_config.auto_repay_enabled,
+            LegasiError::Unauthorized
+        );
+
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        ctx.accounts.position.accrue_interest(
+            asset_type,
+            &ctx.accounts.lp_pool,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let total_owed = ctx.accounts.position.total_owed(asset_type)?;
+        require!(total_owed > 0, LegasiError::PositionNotFound);
+
+        // Only the delegated allowance can be swept
+        let agent_token_account = &ctx.accounts.agent_token_account;
+        let available =
+            if agent_token_account.delegate == COption::Some(ctx.accounts.agent_config.key()) {
+                std::cmp::min(
+                    agent_token_account.amount,
+                    agent_token_account.delegated_amount,
+                )
+            } else {
+                0
+            };
+        let repay_amount = std::cmp::min(available, total_owed);
+        require!(repay_amount > 0, LegasiError::InvalidAmount);
+
+        // Transfer from agent to vault, signed by the agent_config delegate
+        let position_key = ctx.accounts.position.key();
+        let agent_config_bump = ctx.accounts.agent_config.bump;
+        let seeds: &[&[u8]] = &[
+            AGENT_CONFIG_SEED,
+            position_key.as_ref(),
+            &[agent_config_bump],
+        ];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.agent_token_account.to_account_info(),
+                    to: ctx.accounts.borrow_vault.to_account_info(),
+                    authority: ctx.accounts.agent_config.to_account_info(),
+                },
+                &[seeds],
+            ),
+            repay_amount,
+        )?;
+
+        // Update position
+        let now = Clock::get()?.unix_timestamp;
+        let interest_paid = ctx
+            .accounts
+            .position
+            .apply_repayment(asset_type, repay_amount, now);
+        let principal_paid = repay_amount.saturating_sub(interest_paid);
+
+        let eur_price = match asset_type {
+            AssetType::EURC => eur_usd_price(&ctx.accounts.eur_price_feed, now)?,
+            _ => None,
+        };
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            0,
+            -totals::usd_delta(asset_type.debt_to_usd(principal_paid, eur_price)?),
+        )?;
+        legasi_lp::report_borrowed(
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.lp_pool.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(principal_paid),
+        )?;
+        credit_interest(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.lp_pool.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            interest_paid,
+        )?;
+
+        emit!(AutoRepaid {
+            position: position_key,
+            asset_type,
+            amount: repay_amount,
+            remaining_debt: total_owed.saturating_sub(repay_amount),
+            cranker: ctx.accounts.cranker.key(),
+        });
+
+        msg!("Auto-repaid {} {:?}", repay_amount, asset_type);
+        Ok(())
+    }
+
+    // ========== AUTO-DELEVERAGE ORDERS ==========
+
+    /// Create or update the position's auto-deleverage order, re-arming it
+    /// Past `trigger_ltv_bps`, keepers sell SOL collateral for the borrowable down to
+    /// `target_ltv_bps` and earn `keeper_tip_bps` of the proceeds
+    pub fn set_auto_deleverage(
+        ctx: Context<SetAutoDeleverage>,
+        trigger_ltv_bps: u16,
+        target_ltv_bps: u16,
+        keeper_tip_bps: u16,
+        proceeds: ProceedsMode,
+    ) -> Result<()> {
+        AutoDeleverageOrder::validate(trigger_ltv_bps, target_ltv_bps, keeper_tip_bps)?;
+
+        let order = &mut ctx.accounts.order;
+        order.position = ctx.accounts.position.key();
+        order.mint = ctx.accounts.borrowable_mint.key();
+        order.trigger_ltv_bps = trigger_ltv_bps;
+        order.target_ltv_bps = target_ltv_bps;
+        order.keeper_tip_bps = keeper_tip_bps;
+        order.proceeds = proceeds;
+        order.armed = true;
+        order.bump = ctx.bumps.order;
+
+        msg!(
+            "Auto-deleverage past {} bps down to {} bps",
+            trigger_ltv_bps,
+            target_ltv_bps
+        );
+        Ok(())
+    }
+
+    /// Close the auto-deleverage order and its (empty) proceeds account
+    pub fn cancel_auto_deleverage(ctx: Context<CancelAutoDeleverage>) -> Result<()> {
+        let position_key = ctx.accounts.position.key();
+        let order_bump = ctx.accounts.order.bump;
+        let seeds: &[&[u8]] = &[AUTO_DELEVERAGE_SEED, position_key.as_ref(), &[order_bump]];
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.proceeds_account.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.order.to_account_info(),
+            },
+            &[seeds],
+        ))?;
+
+        msg!("Auto-deleverage order cancelled");
+        Ok(())
+    }
+
+    /// Execute a triggered auto-deleverage order (permissionless, for the order's tip)
+    /// SOL collateral is sold through Jupiter (route accounts in remaining_accounts) into
+    /// the order's proceeds account, then split between the keeper, the debt and the owner
+    pub fn crank_auto_deleverage(
+        ctx: Context<CrankAutoDeleverage>,
+        swap: DeleverageSwap,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.order.armed,
+            LegasiError::AutoDeleverageNotTriggered
+        );
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.position.accrue_interest(
+            ctx.accounts.borrowable_config.asset_type,
+            &ctx.accounts.lp_pool,
+            now,
+        )?;
+
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let order = &ctx.accounts.order;
+        let position = &ctx.accounts.position;
+        require!(
+            position.ltv_bps(sol_price, eur_price)? > order.trigger_ltv_bps as u64,
+            LegasiError::AutoDeleverageNotTriggered
+        );
+        let max_sol_in = usd_to_lamports_ceil(
+            sell_to_target_net_usd(
+                position.collateral_value_usd(sol_price)?,
+                position.debt_usd(eur_price)?,
+                order.target_ltv_bps,
+                order.keeper_tip_bps,
+            ),
+            sol_price,
+        )?;
+
+        // Sell collateral into the proceeds account
+        let position_key = position.key();
+        let vault_bump = ctx.bumps.sol_vault;
+        let vault_seeds: &[&[u8]] = &[SOL_VAULT_SEED, position_key.as_ref(), &[vault_bump]];
+        let sol_before = ctx.accounts.sol_vault.lamports();
+        let out_before = ctx.accounts.proceeds_account.amount;
+
+        jupiter_cpi::swap(
+            &ctx.accounts.jupiter_program.to_account_info(),
+            ctx.remaining_accounts,
+            swap.route_data,
+            Some(ctx.accounts.sol_vault.key),
+            &[vault_seeds],
+        )?;
+
+        ctx.accounts.proceeds_account.reload()?;
+        let received = jupiter_cpi::assert_min_received(
+            out_before,
+            ctx.accounts.proceeds_account.amount,
+            swap.min_out_amount,
+        )?;
+        let sold = jupiter_cpi::assert_max_spent(
+            sol_before,
+            ctx.accounts.sol_vault.lamports(),
+            max_sol_in,
+        )?;
+
+        ctx.accounts.position.remove_sol_collateral(sold)?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(sol_to_usd(sold, sol_price)?),
+            0,
+        )?;
+        totals::report_sol_collateral(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -(sold as i64),
+        )?;
+
+        // Split the proceeds, signed by the order
+        let order_bump = ctx.accounts.order.bump;
+        let order_seeds: &[&[u8]] = &[AUTO_DELEVERAGE_SEED, position_key.as_ref(), &[order_bump]];
+        let tip = ctx.accounts.order.keeper_tip(received);
+        if tip > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.proceeds_account.to_account_info(),
+                        to: ctx.accounts.keeper_token_account.to_account_info(),
+                        authority: ctx.accounts.order.to_account_info(),
+                    },
+                    &[order_seeds],
+                ),
+                tip,
+            )?;
+        }
+        let net = received - tip;
+
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        let proceeds = ctx.accounts.order.proceeds;
+        let repaid = match proceeds {
+            ProceedsMode::Repay => {
+                let repay_amount =
+                    std::cmp::min(net, ctx.accounts.position.total_owed(asset_type)?);
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.proceeds_account.to_account_info(),
+                            to: ctx.accounts.repay_vault.to_account_info(),
+                            authority: ctx.accounts.order.to_account_info(),
+                        },
+                        &[order_seeds],
+                    ),
+                    repay_amount,
+                )?;
+
+                let interest_paid =
+                    ctx.accounts
+                        .position
+                        .apply_repayment(asset_type, repay_amount, now);
+                let principal_paid = repay_amount.saturating_sub(interest_paid);
+
+                totals::report(
+                    &ctx.accounts.core_program.to_account_info(),
+                    &ctx.accounts.protocol.to_account_info(),
+                    &ctx.accounts.protocol_writer.to_account_info(),
+                    ctx.bumps.protocol_writer,
+                    0,
+                    -totals::usd_delta(asset_type.debt_to_usd(principal_paid, eur_price)?),
+                )?;
+                legasi_lp::report_borrowed(
+                    &ctx.accounts.lp_program.to_account_info(),
+                    &ctx.accounts.lp_pool.to_account_info(),
+                    &ctx.accounts.protocol_writer.to_account_info(),
+                    ctx.bumps.protocol_writer,
+                    -totals::usd_delta(principal_paid),
+                )?;
+                credit_interest(
+                    &ctx.accounts.core_program.to_account_info(),
+                    &ctx.accounts.protocol.to_account_info(),
+                    &ctx.accounts.lp_program.to_account_info(),
+                    &ctx.accounts.lp_pool.to_account_info(),
+                    &ctx.accounts.protocol_writer.to_account_info(),
+                    ctx.bumps.protocol_writer,
+                    interest_paid,
+                )?;
+                repay_amount
+            }
+            ProceedsMode::Wallet => {
+                // The debt stays, so the smaller collateral must still back it
+                ctx.accounts
+                    .position
+                    .require_within_ltv(asset_type, 0, sol_price, eur_price)?;
+                ctx.accounts.order.armed = false;
+                0
+            }
+        };
+
+        // Whatever isn't repaid goes to the owner
+        let to_owner = net - repaid;
+        if to_owner > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.proceeds_account.to_account_info(),
+                        to: ctx.accounts.owner_token_account.to_account_info(),
+                        authority: ctx.accounts.order.to_account_info(),
+                    },
+                    &[order_seeds],
+                ),
+                to_owner,
+            )?;
+        }
+
+        emit!(AutoDeleveraged {
+            position: position_key,
+            proceeds,
+            collateral_sold: sold,
+            received,
+            repaid,
+            keeper_tip: tip,
+            new_ltv_bps: ctx.accounts.position.ltv_bps(sol_price, eur_price)?,
+            keeper: ctx.accounts.keeper.key(),
+        });
+
+        msg!("Auto-deleveraged {} lamports for {}", sold, received);
+        Ok(())
+    }
+
+    // ========== LETTERS OF CREDIT ==========
+
+    /// Commit to lend `amount` of the borrowable to `beneficiary`, claimable until `expires_at`
+    /// Collateral must back current debt plus every open letter, this one included
+    pub fn issue_letter_of_credit(
+        ctx: Context<IssueLetterOfCredit>,
+        letter_id: u64,
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, LegasiError::InvalidAmount);
+        accrue_all_interest(&mut ctx.accounts.position, ctx.remaining_accounts, now)?;
+        require!(ctx.accounts.position.gad_enabled, LegasiError::GadDisabled);
+
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        ctx.accounts.position.require_within_ltv(
+            asset_type,
+            amount,
+            ctx.accounts.sol_price_feed.get_checked_price(now)?,
+            eur_price,
+        )?;
+
+        let letter = &mut ctx.accounts.letter_of_credit;
+        letter.position = ctx.accounts.position.key();
+        letter.beneficiary = ctx.accounts.beneficiary.key();
+        letter.letter_id = letter_id;
+        letter.asset_type = asset_type;
+        letter.amount = amount;
+        letter.committed_usd = asset_type.debt_to_usd(amount, eur_price)?;
+        letter.expires_at = expires_at;
+        letter.bump = ctx.bumps.letter_of_credit;
+        letter.commit(&mut ctx.accounts.position);
+
+        msg!(
+            "Letter of credit {}: {} {:?} to {} until {}",
+            letter_id,
+            amount,
+            asset_type,
+            letter.beneficiary,
+            expires_at
+        );
+        Ok(())
+    }
+
+    /// Claim a letter of credit (beneficiary, before expiry)
+    /// Borrows the amount against the position's collateral now and pays it out
+    pub fn claim_letter_of_credit(ctx: Context<ClaimLetterOfCredit>) -> Result<()> {
+        ctx.accounts.protocol.require_not_paused(PAUSE_BORROWS)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            !ctx.accounts.letter_of_credit.is_expired(now),
+            LegasiError::LetterOfCreditExpired
+        );
+        gate::check_gate(
+            &ctx.accounts.market_gate,
+            ctx.accounts.gate_pass.as_deref(),
+            &ctx.accounts.position.owner,
+        )?;
+        ctx.accounts.position.accrue_interest(
+            ctx.accounts.borrowable_config.asset_type,
+            &ctx.accounts.lp_pool,
+            now,
+        )?;
+
+        require_breakers_clear(
+            &ctx.accounts.position,
+            &ctx.accounts.sol_price_feed,
+            &None,
+            now,
+        )?;
+
+        // The reservation becomes the borrow itself
+        let letter = &ctx.accounts.letter_of_credit;
+        let (asset_type, amount) = (letter.asset_type, letter.amount);
+        letter.release(&mut ctx.accounts.position);
+
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        ctx.accounts
+            .position
+            .require_within_ltv(asset_type, amount, sol_price, eur_price)?;
+
+        legasi_lp::lend(
+            &ctx.accounts.lp_program.to_account_info(),
+            legasi_lp::cpi::accounts::Lend {
+                lp_pool: ctx.accounts.lp_pool.to_account_info(),
+                vault: ctx.accounts.borrow_vault.to_account_info(),
+                destination: ctx.accounts.beneficiary_token_account.to_account_info(),
+                writer: ctx.accounts.protocol_writer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+            ctx.bumps.protocol_writer,
+            amount,
+        )?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            0,
+            totals::usd_delta(asset_type.debt_to_usd(amount, eur_price)?),
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        require!(position.gad_enabled, LegasiError::GadDisabled);
+        position.start_debt_clock(now)?;
+        match position
+            .borrows
+            .iter_mut()
+            .find(|b| b.asset_type == asset_type)
+        {
+            Some(borrow) => {
+                require!(!borrow.is_fixed_term(), LegasiError::BorrowTermMismatch);
+                borrow.amount = borrow
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(LegasiError::MathOverflow)?;
+            }
+            None => {
+                require!(
+                    position.borrows.len() < MAX_BORROW_TYPES,
+                    LegasiError::MaxBorrowTypesReached
+                );
+                position.borrows.push(BorrowedAmount::new(
+                    asset_type,
+                    amount,
+                    ctx.accounts.lp_pool.borrow_index_at(now)?,
+                    now,
+                ));
+            }
+        }
+        position.last_update = now;
+
+        msg!(
+            "Letter of credit {} claimed: {} {:?}",
+            ctx.accounts.letter_of_credit.letter_id,
+            amount,
+            asset_type
+        );
+        Ok(())
+    }
+
+    /// Close a letter of credit without drawing it, releasing its collateral
+    /// The beneficiary can waive it at any time, the owner only after expiry
+    pub fn release_letter_of_credit(ctx: Context<ReleaseLetterOfCredit>) -> Result<()> {
+        let letter = &ctx.accounts.letter_of_credit;
+        let authority = ctx.accounts.authority.key();
+        if authority != letter.beneficiary {
+            require_keys_eq!(
+                authority,
+                ctx.accounts.position.owner,
+                LegasiError::Unauthorized
+            );
+            require!(
+                letter.is_expired(Clock::get()?.unix_timestamp),
+                LegasiError::LetterOfCreditActive
+            );
+        }
+        letter.release(&mut ctx.accounts.position);
+
+        msg!("Letter of credit {} released", letter.letter_id);
+        Ok(())
+    }
+
+    // ========== CROSS-CHAIN (CCTP) ==========
+
+    /// Repay a loan with USDC bridged via CCTP (e.g., burned on Base/Ethereum)
+    /// The burn's mint_recipient must be this position's CCTP inbox; the attested USDC
+    /// repays debt and any excess is forwarded to the owner's token account.
+    /// CCTP receive accounts are passed via remaining_accounts
+    pub fn receive_cctp_repayment(
+        ctx: Context<ReceiveCctpRepayment>,
+        message: Vec<u8>,
+        attestation: Vec<u8>,
+    ) -> Result<()> {
+        let burn = cctp::parse_burn_message(&message)?;
+        require_keys_eq!(
+            burn.mint_recipient,
+            ctx.accounts.cctp_inbox.key(),
+            LegasiError::InvalidCctpMessage
+        );
+
+        // Mint the attested USDC into the inbox
+        let inbox_before = ctx.accounts.cctp_inbox.amount;
+        cctp::receive_message(
+            &ctx.accounts.message_transmitter.to_account_info(),
+            ctx.remaining_accounts,
+            message,
+            attestation,
+        )?;
+        ctx.accounts.cctp_inbox.reload()?;
+        cctp::assert_minted(inbox_before, ctx.accounts.cctp_inbox.amount, burn.amount)?;
+
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        ctx.accounts.position.accrue_interest(
+            asset_type,
+            &ctx.accounts.lp_pool,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let total_owed = ctx.accounts.position.total_owed(asset_type)?;
+        let repay_amount = std::cmp::min(burn.amount, total_owed);
+        let refund_amount = burn.amount.saturating_sub(repay_amount);
+
+        // Inbox is owned by the position PDA
+        let owner_key = ctx.accounts.position.owner;
+        let position_bump = ctx.accounts.position.bump;
+        let seeds: &[&[u8]] = &[POSITION_SEED, owner_key.as_ref(), &[position_bump]];
+
+        if repay_amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.cctp_inbox.to_account_info(),
+                        to: ctx.accounts.repay_vault.to_account_info(),
+                        authority: ctx.accounts.position.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                repay_amount,
+            )?;
+
+            let now = Clock::get()?.unix_timestamp;
+            let interest_paid =
+                ctx.accounts
+                    .position
+                    .apply_repayment(asset_type, repay_amount, now);
+            let principal_paid = repay_amount.saturating_sub(interest_paid);
+
+            totals::report(
+                &ctx.accounts.core_program.to_account_info(),
+                &ctx.accounts.protocol.to_account_info(),
+                &ctx.accounts.protocol_writer.to_account_info(),
+                ctx.bumps.protocol_writer,
+                0,
+                -totals::usd_delta(principal_paid),
+            )?;
+            legasi_lp::report_borrowed(
+                &ctx.accounts.lp_program.to_account_info(),
+                &ctx.accounts.lp_pool.to_account_info(),
+                &ctx.accounts.protocol_writer.to_account_info(),
+                ctx.bumps.protocol_writer,
+                -totals::usd_delta(principal_paid),
+            )?;
+            credit_interest(
+                &ctx.accounts.core_program.to_account_info(),
+                &ctx.accounts.protocol.to_account_info(),
+                &ctx.accounts.lp_program.to_account_info(),
+                &ctx.accounts.lp_pool.to_account_info(),
+                &ctx.accounts.protocol_writer.to_account_info(),
+                ctx.bumps.protocol_writer,
+                interest_paid,
+            )?;
+        }
+
+        if refund_amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.cctp_inbox.to_account_info(),
+                        to: ctx.accounts.owner_token_account.to_account_info(),
+                        authority: ctx.accounts.position.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                refund_amount,
+            )?;
+        }
+
+        emit!(CctpRepaymentReceived {
+            position: ctx.accounts.position.key(),
+            source_domain: burn.source_domain,
+            nonce: burn.nonce,
+            amount: burn.amount,
+            repaid: repay_amount,
+            refunded: refund_amount,
+        });
+
+        msg!(
+            "CCTP repayment: {} received, {} repaid, {} refunded",
+            burn.amount,
+            repay_amount,
+            refund_amount
+        );
+        Ok(())
+    }
+
+    // ========== SOLANA PAY ==========
+
+    /// Fulfill a Solana Pay transfer request funded by the agent's borrow line
+    /// Borrows the amount against collateral straight into the merchant's token account
+    /// Extra reference keys are passed as read-only remaining accounts
+    pub fn solana_pay(ctx: Context<SolanaPay>, request: SolanaPayRequest) -> Result<()> {
+        ctx.accounts.protocol.require_not_paused(PAUSE_BORROWS)?;
+        let now = Clock::get()?.unix_timestamp;
+        let amount = request.amount;
+        ctx.accounts.position.accrue_interest(
+            ctx.accounts.borrowable_config.asset_type,
+            &ctx.accounts.lp_pool,
+            now,
+        )?;
+
+        require!(request.is_valid(), LegasiError::InvalidAmount);
+        gate::check_gate(
+            &ctx.accounts.market_gate,
+            ctx.accounts.gate_pass.as_deref(),
+            &ctx.accounts.position.owner,
+        )?;
+        require!(
+            ctx.remaining_accounts.len() <= MAX_SOLANA_PAY_REFERENCES,
+            LegasiError::InvalidAmount
+        );
+        ctx.accounts
+            .agent_config
+            .activity_limit
+            .record(now, Clock::get()?.slot)?;
+
+        require_breakers_clear(
+            &ctx.accounts.position,
+            &ctx.accounts.sol_price_feed,
+            &None,
+            now,
+        )?;
+
+        // Check daily limit (USD, across pools)
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+        let amount_usd = asset_type.debt_to_usd(amount, eur_price)?;
+        require!(
+            ctx.accounts.agent_config.can_borrow(amount_usd, now),
+            LegasiError::ExceedsLTV
+        );
+
+        // Check LTV (same as agent_borrow)
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        ctx.accounts
+            .position
+            .require_within_ltv(asset_type, amount, sol_price, eur_price)?;
+
+        // Pay the merchant straight from the pool
+        let borrowable_mint = ctx.accounts.borrowable_config.mint;
+        legasi_lp::lend(
+            &ctx.accounts.lp_program.to_account_info(),
+            legasi_lp::cpi::accounts::Lend {
+                lp_pool: ctx.accounts.lp_pool.to_account_info(),
+                vault: ctx.accounts.borrow_vault.to_account_info(),
+                destination: ctx.accounts.recipient_token_account.to_account_info(),
+                writer: ctx.accounts.protocol_writer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+            ctx.bumps.protocol_writer,
+            amount,
+        )?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            0,
+            totals::usd_delta(amount_usd),
+        )?;
+
+        if let Some(memo) = &request.memo {
+            invoke(
+                &memo_ix(memo),
+                &[ctx.accounts.memo_program.to_account_info()],
+            )?;
+        }
+
+        // Update position debt
+        let position = &mut ctx.accounts.position;
+        require!(position.gad_enabled, LegasiError::GadDisabled);
+        position.start_debt_clock(now)?;
+
+        let mut found = false;
+        for borrow in position.borrows.iter_mut() {
+            if borrow.asset_type == asset_type {
+                require!(!borrow.is_fixed_term(), LegasiError::BorrowTermMismatch);
+                borrow.amount = borrow
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(LegasiError::MathOverflow)?;
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            require!(
+                position.borrows.len() < MAX_BORROW_TYPES,
+                LegasiError::MaxBorrowTypesReached
+            );
+            position.borrows.push(BorrowedAmount::new(
+                asset_type,
+                amount,
+                ctx.accounts.lp_pool.borrow_index_at(now)?,
+                now,
+            ));
+        }
+        position.last_update = now;
+
+        // Update agent config daily borrowed
+        let agent_config = &mut ctx.accounts.agent_config;
+        agent_config.record_borrow(amount_usd, now);
+
+        // Create receipt
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.reference = ctx.accounts.reference.key();
+        receipt.payer = ctx.accounts.agent.key();
+        receipt.recipient = request.recipient;
+        receipt.mint = borrowable_mint;
+        receipt.amount = amount;
+        receipt.paid_at = now;
+        receipt.bump = ctx.bumps.receipt;
+
+        emit!(SolanaPayCompleted {
+            payer: ctx.accounts.agent.key(),
+            recipient: request.recipient,
+            reference: ctx.accounts.reference.key(),
+            mint: borrowable_mint,
+            amount,
+        });
+
+        msg!("Solana Pay: {} to {}", amount, request.recipient);
+        Ok(())
+    }
+
+    // ========== x402 PAYMENT FUNCTIONS ==========
+
+    /// Register an API as an x402 paywall
+    /// One payment of `price` unlocks `access_period` seconds for the payer
+    pub fn register_service_listing(
+        ctx: Context<RegisterServiceListing>,
+        service_id: [u8; 32],
+        recipient: Pubkey,
+        asset: u8,
+        price: u64,
+        access_period: i64,
+    ) -> Result<()> {
+        require!(price > 0, LegasiError::InvalidAmount);
+        require!(
+            asset == AssetType::USDC as u8 || asset == AssetType::EURC as u8,
+            LegasiError::InvalidAmount
+        );
+        require!(
+            (MIN_ACCESS_PERIOD..=MAX_ACCESS_PERIOD).contains(&access_period),
+            LegasiError::InvalidAmount
+        );
+
+        let listing = &mut ctx.accounts.service_listing;
+        listing.provider = ctx.accounts.provider.key();
+        listing.service_id = service_id;
+        listing.recipient = recipient;
+        listing.asset = asset;
+        listing.price = price;
+        listing.access_period = access_period;
+        listing.is_active = true;
+        listing.bump = ctx.bumps.service_listing;
+
+        msg!("Service listed: {} per {}s", price, access_period);
+        Ok(())
+    }
+
+    /// Change a listing's price, recipient or availability (provider only)
+    /// The access period is fixed, so receipts already paid keep their window
+    pub fn update_service_listing(
+        ctx: Context<UpdateServiceListing>,
+        recipient: Pubkey,
+        price: u64,
+        is_active: bool,
+    ) -> Result<()> {
+        require!(price > 0, LegasiError::InvalidAmount);
+
+        let listing = &mut ctx.accounts.service_listing;
+        listing.recipient = recipient;
+        listing.price = price;
+        listing.is_active = is_active;
+
+        msg!("Service listing updated: {} (active: {})", price, is_active);
+        Ok(())
+    }
+
+    /// Process an x402 payment request
+    /// Agent pays for a service, borrowing if needed
+    pub fn x402_pay(
+        ctx: Context<X402Pay>,
+        payment_request: X402PaymentRequest,
+        auto_borrow: bool,           // Borrow if insufficient balance
+        reference: Option<[u8; 32]>, // Invoice / memo hash, kept on the receipt
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.position.accrue_interest(
+            ctx.accounts.borrowable_config.asset_type,
+            &ctx.accounts.lp_pool,
+            now,
+        )?;
+
+        // Verify request is valid
+        require!(payment_request.is_valid(now), LegasiError::InvalidAmount);
+        require!(
+            ctx.accounts.agent_config.x402_enabled,
+            LegasiError::Unauthorized
+        );
+        ctx.accounts
+            .agent_config
+            .activity_limit
+            .record(now, Clock::get()?.slot)?;
+
+        // A listing payment must follow its terms, and its ID is the payer's access
+        // ID for the current window so the receipt doubles as the access pass
+        if let Some(listing) = &ctx.accounts.service_listing {
+            require!(
+                listing.matches(
+                    &listing.key(),
+                    &ctx.accounts.agent.key(),
+                    &payment_request,
+                    now
+                ),
+                LegasiError::ServiceListingMismatch
+            );
+        }
+
+        // Pay in the pool's asset (USDC or EURC), as the request asks
+        let asset_type = ctx.accounts.borrowable_config.asset_type;
+        require!(
+            payment_request.asset == asset_type as u8,
+            LegasiError::InvalidAmount
+        );
+
+        let amount = payment_request.amount;
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
+        let eur_price = eur_usd_price(&ctx.accounts.eur_price_feed, now)?;
+
+        // Check agent has enough balance
+        let agent_balance = ctx.accounts.agent_token_account.amount;
+
+        if agent_balance < amount && auto_borrow {
+            // Need to borrow the difference
+            let borrow_amount = amount.saturating_sub(agent_balance);
+
+            ctx.accounts.protocol.require_not_paused(PAUSE_BORROWS)?;
+            gate::check_gate(
+                &ctx.accounts.market_gate,
+                ctx.accounts.gate_pass.as_deref(),
+                &ctx.accounts.position.owner,
+            )?;
+
+            require_breakers_clear(
+                &ctx.accounts.position,
+                &ctx.accounts.sol_price_feed,
+                &None,
+                now,
+            )?;
+
+            // Check daily limit (USD, across pools)
+            let borrow_usd = asset_type.debt_to_usd(borrow_amount, eur_price)?;
+            require!(
+                ctx.accounts.agent_config.can_borrow(borrow_usd, now),
+                LegasiError::ExceedsLTV
+            );
+
+            // The daily limit caps spend, not risk: check collateral and LTV
+            // like agent_borrow so an unbacked agent can't draw on the pool
+            ctx.accounts.position.require_within_ltv(
+                asset_type,
+                borrow_amount,
+                sol_price,
+                eur_price,
+            )?;
+
+            // Borrow from pool
+            legasi_lp::lend(
+                &ctx.accounts.lp_program.to_account_info(),
+                legasi_lp::cpi::accounts::Lend {
+                    lp_pool: ctx.accounts.lp_pool.to_account_info(),
+                    vault: ctx.accounts.borrow_vault.to_account_info(),
+                    destination: ctx.accounts.agent_token_account.to_account_info(),
+                    writer: ctx.accounts.protocol_writer.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+                ctx.bumps.protocol_writer,
+                borrow_amount,
+            )?;
+            totals::report(
+                &ctx.accounts.core_program.to_account_info(),
+                &ctx.accounts.protocol.to_account_info(),
+                &ctx.accounts.protocol_writer.to_account_info(),
+                ctx.bumps.protocol_writer,
+                0,
+                totals::usd_delta(borrow_usd),
+            )?;
+
+            // Update position debt
+            let position = &mut ctx.accounts.position;
+            require!(position.gad_enabled, LegasiError::GadDisabled);
+            position.start_debt_clock(now)?;
+
+            let mut found = false;
+            for borrow in position.borrows.iter_mut() {
+                if borrow.asset_type == asset_type {
+                    require!(!borrow.is_fixed_term(), LegasiError::BorrowTermMismatch);
+                    borrow.amount = borrow
+                        .amount
+                        .checked_add(borrow_amount)
+                        .ok_or(LegasiError::MathOverflow)?;
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                position.borrows.push(BorrowedAmount::new(
+                    asset_type,
+                    borrow_amount,
+                    ctx.accounts.lp_pool.borrow_index_at(now)?,
+                    now,
+                ));
+            }
+
+            // Update agent config
+            let agent_config = &mut ctx.accounts.agent_config;
+            agent_config.record_borrow(borrow_usd, now);
+        }
+
+        // Now pay the recipient
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.agent_token_account.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.agent.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // Create receipt
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.payment_id = payment_request.payment_id;
+        receipt.payer = ctx.accounts.agent.key();
+        receipt.recipient = payment_request.recipient;
+        receipt.amount = amount;
+        receipt.paid_at = now;
+        receipt.tx_signature = [0u8; 64]; // Filled by runtime
+        receipt.reference = reference;
+        receipt.agent_profile = ctx.accounts.agent_profile.as_ref().map(|p| p.key());
+        receipt.bump = ctx.bumps.receipt;
+
+        let ltv_bps = ctx.accounts.position.ltv_bps(sol_price, eur_price)?;
+        emit!(X402PaymentMade {
+            payer: ctx.accounts.agent.key(),
+            recipient: payment_request.recipient,
+            amount,
+            payment_id: payment_request.payment_id,
+            borrowed: agent_balance < amount,
+            ltv_bps,
+            alert_threshold_breached: ctx.accounts.agent_config.alert_breached(ltv_bps),
+            reference,
+            agent_profile: receipt.agent_profile,
+            agent_category: ctx.accounts.agent_profile.as_ref().map(|p| p.category),
+        });
+
+        msg!("x402 payment: {} to {}", amount, payment_request.recipient);
+        Ok(())
+    }
+}
+
+#[event]
+pub struct X402PaymentMade {
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub payment_id: [u8; 32],
+    pub borrowed: bool,
+    /// Position LTV after the payment (bps)
+    pub ltv_bps: u64,
+    /// `ltv_bps` is above the agent's `alert_threshold_bps` (alerts enabled)
+    pub alert_threshold_breached: bool,
+    /// Integrator's invoice / memo hash, if given
+    pub reference: Option<[u8; 32]>,
+    /// Payer's `AgentProfile` and its category, if it was passed
+    pub agent_profile: Option<Pubkey>,
+    pub agent_category: Option<AgentCategory>,
+}
+
+#[event]
+pub struct DebtConverted {
+    pub position: Pubkey,
+    pub from: AssetType,
+    pub to: AssetType,
+    /// Debt closed, in `from` units (interest included)
+    pub from_amount: u64,
+    /// Debt opened, in `to` units (interest and fee included)
+    pub to_amount: u64,
+    pub fee: u64,
+    /// EUR/USD price used (6 decimals, 0 if no EURC was involved)
+    pub eur_usd_price: u64,
+}
+
+#[event]
+pub struct CctpRepaymentReceived {
+    pub position: Pubkey,
+    /// CCTP domain the USDC was burned on
+    pub source_domain: u32,
+    pub nonce: u64,
+    pub amount: u64,
+    pub repaid: u64,
+    pub refunded: u64,
+}
+
+#[event]
+pub struct SolanaPayCompleted {
+    pub payer: Pubkey,
+    pub recipient: Pubkey,
+    pub reference: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AgentBorrowed {
+    pub position: Pubkey,
+    pub amount: u64,
+    pub daily_remaining: u64,
+    /// Position LTV after the borrow (bps)
+    pub ltv_bps: u64,
+    /// `ltv_bps` is above the agent's `alert_threshold_bps` (alerts enabled)
+    pub alert_threshold_breached: bool,
+}
+
+#[event]
+pub struct CreditLineDrawn {
+    pub position: Pubkey,
+    pub asset_type: AssetType,
+    pub amount: u64,
+    /// Limit left to draw after this draw
+    pub unused: u64,
+    /// Position LTV after the draw (bps)
+    pub ltv_bps: u64,
+}
+
+#[event]
+pub struct AutoRepaid {
+    pub position: Pubkey,
+    pub asset_type: AssetType,
+    pub amount: u64,
+    pub remaining_debt: u64,
+    pub cranker: Pubkey,
+}
+
+#[event]
+pub struct LamportsSwept {
+    pub account: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ExcessLamportsSwept {
+    pub treasury: Pubkey,
+    pub accounts: u32,
+    pub total: u64,
+}
+
+#[event]
+pub struct AutoDeleveraged {
+    pub position: Pubkey,
+    pub proceeds: ProceedsMode,
+    pub collateral_sold: u64,
+    pub received: u64,
+    pub repaid: u64,
+    pub keeper_tip: u64,
+    pub new_ltv_bps: u64,
+    pub keeper: Pubkey,
+}
+
+#[event]
+pub struct StakeDeposited {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub provider: StakeProvider,
+    pub lamports: u64,
+    pub lst_received: u64,
+    /// LST price (SOL per LST, fixed-point) at deposit time
+    pub lst_price: u64,
+}
+
+#[event]
+pub struct PositionStatement {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub period_start: i64,
+    pub period_end: i64,
+    /// Interest booked on the position's debt, USD (6 decimals)
+    pub interest_accrued_usd: u64,
+    /// Staking yield on its mSOL collateral, USD (6 decimals)
+    pub yield_earned_usd: u64,
+    /// Collateral sold by GAD, USD (6 decimals)
+    pub gad_deducted_usd: u64,
+    /// Standby and debt conversion fees, USD (6 decimals)
+    pub fees_usd: u64,
+}
+
+/// A position reached its alert LTV: addressed to `service`, which was paid `fee_paid`
+/// to notify `owner`
+#[event]
+pub struct NotifySubscriber {
+    pub service: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub ltv_bps: u64,
+    pub alert_ltv_bps: u16,
+    pub fee_paid: u64,
+}
+
+/// Off-ramp request status
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum OfframpStatus {
+    Pending,
+    Processing,
+    Completed,
+    Failed,
+}
+
+/// Off-ramp request account
+#[account]
+#[derive(InitSpace)]
+pub struct OfframpRequest {
+    pub owner: Pubkey,
+    pub amount: u64,
+    #[max_len(34)]
+    pub destination_iban: String,
+    #[max_len(100)]
+    pub destination_name: String,
+    pub status: OfframpStatus,
+    pub created_at: i64,
+    pub completed_at: i64,
+    pub bump: u8,
+}
+
+#[event]
+pub struct OfframpRequested {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub destination_iban: String,
+    pub asset_type: AssetType,
+}
+
+// ========== ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct MigrateLendingVault<'info> {
+    #[account(
+        seeds = [PROTOCOL_SEED],
+        bump = protocol.bump,
+        seeds::program = legasi_core::ID,
+        has_one = admin
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// Deprecated lending vault (closed by this instruction)
+    #[account(
+        mut,
+        seeds = [LENDING_VAULT_SEED, lending_vault.mint.as_ref()],
+        bump
+    )]
+    pub lending_vault: Account<'info, TokenAccount>,
+    /// Canonical LP pool vault for the same mint
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lending_vault.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SweepExcessLamports<'info> {
+    #[account(
+        seeds = [PROTOCOL_SEED],
+        bump = protocol.bump,
+        seeds::program = legasi_core::ID,
+        has_one = admin
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: protocol treasury, receives the swept lamports
+    #[account(mut, address = protocol.treasury)]
+    pub treasury: UncheckedAccount<'info>,
+    pub admin: Signer<'info>,
+    // Lending-owned accounts to sweep are passed via remaining_accounts
+}
+
+#[derive(Accounts)]
+pub struct InitializePosition<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [POSITION_SEED, owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    /// Referrer's registry, if the borrower was referred
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, referrer_account.referrer.as_ref()],
+        bump = referrer_account.bump
+    )]
+    pub referrer_account: Option<Account<'info, Referrer>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    /// CHECK: SOL vault PDA
+    #[account(mut, seeds = [SOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, sol_mint.key().as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
+    /// CHECK: asset caps PDA, enforced only if it exists (see `legasi_core::caps`)
+    #[account(seeds = [ASSET_CAPS_SEED, sol_mint.key().as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub asset_caps: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositToken<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut, seeds = [COLLATERAL_SEED, collateral_config.mint.as_ref()], bump = collateral_config.bump)]
+    pub collateral_config: Account<'info, Collateral>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [TOKEN_VAULT_SEED, collateral_config.mint.as_ref()], bump)]
+    pub token_vault: Account<'info, TokenAccount>,
+    /// Lots of this mint deposited into the position
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + DepositReceipt::INIT_SPACE,
+        seeds = [DEPOSIT_RECEIPT_SEED, position.key().as_ref(), collateral_config.mint.as_ref()],
+        bump
+    )]
+    pub deposit_receipt: Box<Account<'info, DepositReceipt>>,
+    /// Collateral price feed, prices the lot
+    #[account(
+        seeds = [PRICE_FEED_SEED, collateral_config.mint.as_ref()],
+        bump = price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+    /// Protocol state (owned by core program), for the pause flags
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, collateral_config.mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
+    /// CHECK: asset caps PDA, enforced only if it exists (see `legasi_core::caps`)
+    #[account(seeds = [ASSET_CAPS_SEED, collateral_config.mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub asset_caps: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositAndStake<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Box<Account<'info, Position>>,
+    /// mSOL vault (authority = position PDA)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        token::mint = msol_mint,
+        token::authority = position,
+        seeds = [MSOL_VAULT_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub msol_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = marinade::MSOL_MINT)]
+    pub msol_mint: Box<Account<'info, Mint>>,
+    /// CHECK: Marinade state - owner checked, rest validated by Marinade
+    #[account(mut, owner = marinade::ID)]
+    pub marinade_state: UncheckedAccount<'info>,
+    /// CHECK: Validated by Marinade
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+    /// CHECK: Validated by Marinade
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+    /// CHECK: Validated by Marinade
+    pub liq_pool_msol_leg_authority: UncheckedAccount<'info>,
+    /// CHECK: Validated by Marinade
+    #[account(mut)]
+    pub reserve_pda: UncheckedAccount<'info>,
+    /// CHECK: Validated by Marinade
+    pub msol_mint_authority: UncheckedAccount<'info>,
+    /// CHECK: Marinade liquid staking program
+    #[account(address = marinade::ID)]
+    pub marinade_program: UncheckedAccount<'info>,
+    /// Protocol state (owned by core program), for the pause flags
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, token::spl_token::native_mint::ID.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStaked<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(mut, seeds = [MSOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub msol_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_msol_account: Account<'info, TokenAccount>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: credit line PDA, read only if open (its unused limit must stay backed)
+    #[account(seeds = [CREDIT_LINE_SEED, position.key().as_ref()], bump)]
+    pub credit_line: UncheckedAccount<'info>,
+    /// CHECK: withdrawal allowlist PDA, enforced only if set
+    #[account(seeds = [WITHDRAWAL_ALLOWLIST_SEED, position.key().as_ref()], bump)]
+    pub withdrawal_allowlist: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    /// Borrowable config (owned by core program - no seeds validation)
+    pub borrowable_config: Account<'info, Borrowable>,
+    /// LP pool for the borrowed asset (utilization and outflow limit updated via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    pub lp_program: Program<'info, LegasiLp>,
+    /// LP pool vault (owned by the LP program, lent out via CPI)
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, borrowable_config.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub borrow_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// cbBTC price feed (owned by core program), for cbBTC collateral at its own price
+    #[account(constraint = cbbtc_price_feed.asset_type == AssetType::CbBTC @ LegasiError::InvalidOracle)]
+    pub cbbtc_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Market pricing the position, for its effective (eMode) LTV (see `emode`)
+    #[account(
+        seeds = [MARKET_SEED, &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub market: Option<Box<Account<'info, Market>>>,
+    /// CHECK: Marinade State, read for the mSOL price to credit staking yield
+    #[account(address = marinade::MARINADE_STATE, owner = marinade::ID)]
+    pub marinade_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: SOL mint
+    pub sol_mint: UncheckedAccount<'info>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, borrowable_config.mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
+    /// CHECK: asset caps PDA, enforced only if it exists (see `legasi_core::caps`)
+    #[account(seeds = [ASSET_CAPS_SEED, borrowable_config.mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub asset_caps: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Repay<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    /// Borrowable config (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Account<'info, Borrowable>,
+    /// LP pool for the repaid asset (outstanding borrows updated via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// LP pool vault the borrow was paid out of
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, borrowable_config.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub repay_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    /// Repayment schedule, if the owner committed to one
+    #[account(
+        mut,
+        seeds = [REPAYMENT_SCHEDULE_SEED, position.key().as_ref()],
+        bump = repayment_schedule.bump
+    )]
+    pub repayment_schedule: Option<Account<'info, RepaymentSchedule>>,
+    /// CHECK: the position referrer's vault for this asset; the referral cut is only
+    /// paid if it has been opened
+    #[account(
+        mut,
+        seeds = [
+            REFERRAL_VAULT_SEED,
+            position.referrer.as_ref(),
+            borrowable_config.mint.as_ref()
+        ],
+        bump
+    )]
+    pub referral_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Repay accounts, plus what sizing and an optional collateral swap need
+#[derive(Accounts)]
+pub struct DeleverageToLtv<'info> {
+    pub repay: Repay<'info>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// CHECK: SOL vault PDA (swap input, signs the route)
+    #[account(mut, seeds = [SOL_VAULT_SEED, repay.position.key().as_ref()], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// CHECK: Jupiter Aggregator v6, required to deleverage through a swap
+    #[account(address = jupiter_cpi::ID)]
+    pub jupiter_program: Option<UncheckedAccount<'info>>,
+    // Jupiter route accounts are passed via remaining_accounts
+}
+
+#[derive(Accounts)]
+pub struct ConvertDebt<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    /// Borrowable config of the debt converted from (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, from_borrowable.mint.as_ref()],
+        bump = from_borrowable.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub from_borrowable: Box<Account<'info, Borrowable>>,
+    /// Borrowable config of the debt converted into (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, to_borrowable.mint.as_ref()],
+        bump = to_borrowable.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub to_borrowable: Box<Account<'info, Borrowable>>,
+    /// LP pool giving up the principal (outstanding borrows updated via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, from_borrowable.mint.as_ref()],
+        bump = from_lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub from_lp_pool: Box<Account<'info, LpPool>>,
+    /// LP pool taking on the principal (outstanding borrows updated via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, to_borrowable.mint.as_ref()],
+        bump = to_lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub to_lp_pool: Box<Account<'info, LpPool>>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidatePosition<'info> {
+    #[account(mut, seeds = [POSITION_SEED, position.owner.as_ref()], bump = position.bump)]
+    pub position: Account<'info, Position>,
+    /// CHECK: SOL vault PDA the seized collateral is paid from
+    #[account(mut, seeds = [SOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// SOL collateral config (owned by core program): max LTV and liquidation bonus
+    #[account(
+        seeds = [COLLATERAL_SEED, sol_mint.key().as_ref()],
+        bump = sol_collateral.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_collateral: Box<Account<'info, Collateral>>,
+    /// Borrowable config of the debt repaid (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    /// LP pool for the repaid asset (outstanding borrows updated via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// LP pool vault the repayment goes to
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, borrowable_config.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub repay_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub liquidator_token_account: Box<Account<'info, TokenAccount>>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: Core GAD schedule PDA - may not exist, read by `Throttle::load`
+    #[account(seeds = [GAD_SCHEDULE_SEED], bump, seeds::program = legasi_core::ID)]
+    pub gad_schedule: UncheckedAccount<'info>,
+    /// Protocol state (owned by core program - totals and insurance fund updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    /// Receives the seized SOL
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WriteOffBadDebt<'info> {
+    #[account(mut, seeds = [POSITION_SEED, position.owner.as_ref()], bump = position.bump)]
+    pub position: Account<'info, Position>,
+    /// Borrowable config of the debt written off (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    /// LP pool for the written-off asset (bad debt written off via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// cbBTC price feed (owned by core program), required while the position holds cbBTC
+    #[account(constraint = cbbtc_price_feed.asset_type == AssetType::CbBTC @ LegasiError::InvalidOracle)]
+    pub cbbtc_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Protocol state (owned by core program - totals, insurance fund and bad debt
+    /// updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetGadConfig<'info> {
+    #[account(mut, seeds = [POSITION_SEED, position.owner.as_ref()], bump = position.bump)]
+    pub position: Box<Account<'info, Position>>,
+    /// GAD's protocol writer PDA (see `gad_settlement`)
+    #[account(constraint = is_gad_writer(gad_writer.key) @ LegasiError::Unauthorized)]
+    pub gad_writer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseGadCollateral<'info> {
+    #[account(mut, seeds = [POSITION_SEED, position.owner.as_ref()], bump = position.bump)]
+    pub position: Box<Account<'info, Position>>,
+    /// CHECK: SOL vault PDA, pays SOL collateral
+    #[account(mut, seeds = [SOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// mSOL vault (authority = position PDA), required to pay mSOL collateral
+    #[account(mut, seeds = [MSOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub msol_vault: Option<Box<Account<'info, TokenAccount>>>,
+    /// CHECK: Wallet (SOL) or mSOL token account (checked by the token program) paid
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    /// GAD's protocol writer PDA (see `gad_settlement`)
+    #[account(constraint = is_gad_writer(gad_writer.key) @ LegasiError::Unauthorized)]
+    pub gad_writer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(asset_type: AssetType, route: SwapRoute)]
+pub struct SwapGadCollateral<'info> {
+    #[account(mut, seeds = [POSITION_SEED, position.owner.as_ref()], bump = position.bump)]
+    pub position: Box<Account<'info, Position>>,
+    /// CHECK: SOL vault PDA, sells SOL collateral
+    #[account(mut, seeds = [SOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// mSOL vault (authority = position PDA), required to sell mSOL collateral
+    #[account(mut, seeds = [MSOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub msol_vault: Option<Box<Account<'info, TokenAccount>>>,
+    /// CHECK: Jupiter or Sanctum router, as `route` says
+    #[account(address = route.program_id() @ LegasiError::InvalidSwapProgram)]
+    pub swap_program: UncheckedAccount<'info>,
+    /// GAD's protocol writer PDA (see `gad_settlement`)
+    #[account(constraint = is_gad_writer(gad_writer.key) @ LegasiError::Unauthorized)]
+    pub gad_writer: Signer<'info>,
+    // Route accounts are passed via remaining_accounts
+}
+
+#[derive(Accounts)]
+pub struct SettleGad<'info> {
+    #[account(mut, seeds = [POSITION_SEED, position.owner.as_ref()], bump = position.bump)]
+    pub position: Box<Account<'info, Position>>,
+    /// GAD's protocol writer PDA (see `gad_settlement`)
+    #[account(constraint = is_gad_writer(gad_writer.key) @ LegasiError::Unauthorized)]
+    pub gad_writer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlashLiquidate<'info> {
+    pub liquidation: LiquidatePosition<'info>,
+    /// CHECK: Jupiter Aggregator v6, sells the seized SOL
+    #[account(address = jupiter_cpi::ID)]
+    pub jupiter_program: UncheckedAccount<'info>,
+    // Jupiter route accounts are passed via remaining_accounts
+}
+
+#[derive(Accounts)]
+pub struct CommitRepaymentSchedule<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + RepaymentSchedule::INIT_SPACE,
+        seeds = [REPAYMENT_SCHEDULE_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub repayment_schedule: Account<'info, RepaymentSchedule>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRepaymentSchedule<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [REPAYMENT_SCHEDULE_SEED, position.key().as_ref()],
+        bump = repayment_schedule.bump
+    )]
+    pub repayment_schedule: Account<'info, RepaymentSchedule>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSol<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    /// CHECK: SOL vault PDA
+    #[account(mut, seeds = [SOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// cbBTC price feed (owned by core program), for cbBTC collateral at its own price
+    #[account(constraint = cbbtc_price_feed.asset_type == AssetType::CbBTC @ LegasiError::InvalidOracle)]
+    pub cbbtc_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// Market pricing the position, for its effective (eMode) LTV (see `emode`)
+    #[account(
+        seeds = [MARKET_SEED, &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub market: Option<Box<Account<'info, Market>>>,
+    /// CHECK: SOL mint
+    pub sol_mint: UncheckedAccount<'info>,
+    /// CHECK: credit line PDA, read only if open (its unused limit must stay backed)
+    #[account(seeds = [CREDIT_LINE_SEED, position.key().as_ref()], bump)]
+    pub credit_line: UncheckedAccount<'info>,
+    /// CHECK: withdrawal allowlist PDA, enforced only if set
+    #[account(seeds = [WITHDRAWAL_ALLOWLIST_SEED, position.key().as_ref()], bump)]
+    pub withdrawal_allowlist: UncheckedAccount<'info>,
+    /// CHECK: wallet receiving the lamports (default: the owner), not used with `as_wsol`
+    #[account(mut)]
+    pub destination: Option<UncheckedAccount<'info>>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    /// CHECK: Owner's wSOL associated token account, created if needed - required with `as_wsol`
+    #[account(
+        mut,
+        address = associated_token::get_associated_token_address(
+            &owner.key(),
+            &token::spl_token::native_mint::ID
+        )
+    )]
+    pub wsol_account: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(request_id: u64)]
+pub struct OfframpViaBridge<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + OfframpRequest::INIT_SPACE,
+        seeds = [OFFRAMP_SEED, owner.key().as_ref(), &request_id.to_le_bytes()],
+        bump
+    )]
+    pub offramp_request: Account<'info, OfframpRequest>,
+    #[account(mut)]
+    pub stablecoin_mint: Account<'info, Mint>,
+    /// Borrowable config of the burned stablecoin (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, stablecoin_mint.key().as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Account<'info, Borrowable>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(request_id: u64)]
+pub struct WithdrawToOfframp<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + OfframpRequest::INIT_SPACE,
+        seeds = [OFFRAMP_SEED, owner.key().as_ref(), &request_id.to_le_bytes()],
+        bump
+    )]
+    pub offramp_request: Box<Account<'info, OfframpRequest>>,
+    /// Withdrawn liquidity held until the request is settled (authority = request PDA)
+    #[account(
+        init,
+        payer = owner,
+        token::mint = borrowable_mint,
+        token::authority = offramp_request,
+        seeds = [OFFRAMP_ESCROW_SEED, offramp_request.key().as_ref()],
+        bump
+    )]
+    pub offramp_escrow: Box<Account<'info, TokenAccount>>,
+    pub borrowable_mint: Box<Account<'info, Mint>>,
+    /// Borrowable config of the withdrawn asset (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_mint.key().as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_mint.key().as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// CHECK: LP share mint, validated by the LP program
+    #[account(mut)]
+    pub lp_token_mint: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, borrowable_mint.key().as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_lp_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: owner's LP allowlist entry for permissioned pools, validated by the LP program
+    pub lp_allowlist_entry: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub lp_program: Program<'info, LegasiLp>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(request_id: u64)]
+pub struct SettleOfframpEscrow<'info> {
+    #[account(
+        seeds = [PROTOCOL_SEED],
+        bump = protocol.bump,
+        seeds::program = legasi_core::ID,
+        has_one = admin
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    #[account(
+        mut,
+        seeds = [OFFRAMP_SEED, offramp_request.owner.as_ref(), &request_id.to_le_bytes()],
+        bump = offramp_request.bump,
+        constraint = offramp_request.status == OfframpStatus::Pending @ LegasiError::OfframpNotPending
+    )]
+    pub offramp_request: Account<'info, OfframpRequest>,
+    #[account(
+        mut,
+        seeds = [OFFRAMP_ESCROW_SEED, offramp_request.key().as_ref()],
+        bump
+    )]
+    pub offramp_escrow: Account<'info, TokenAccount>,
+    /// Bridge deposit account, or the owner's own account when refunding
+    #[account(mut, token::mint = offramp_escrow.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetDebtCap<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawalAllowlist<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + WithdrawalAllowlist::INIT_SPACE,
+        seeds = [WITHDRAWAL_ALLOWLIST_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub withdrawal_allowlist: Account<'info, WithdrawalAllowlist>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateWithdrawalAllowlist<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_ALLOWLIST_SEED, position.key().as_ref()],
+        bump = withdrawal_allowlist.bump
+    )]
+    pub withdrawal_allowlist: Account<'info, WithdrawalAllowlist>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DesignateBeneficiary<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + Succession::INIT_SPACE,
+        seeds = [SUCCESSION_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub succession: Account<'info, Succession>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSuccession<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [SUCCESSION_SEED, position.key().as_ref()],
+        bump = succession.bump
+    )]
+    pub succession: Account<'info, Succession>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeBeneficiary<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [SUCCESSION_SEED, position.key().as_ref()],
+        bump = succession.bump
+    )]
+    pub succession: Account<'info, Succession>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartSuccessionClaim<'info> {
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [SUCCESSION_SEED, position.key().as_ref()],
+        bump = succession.bump,
+        has_one = beneficiary
+    )]
+    pub succession: Account<'info, Succession>,
+    pub beneficiary: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteSuccession<'info> {
+    #[account(mut, seeds = [POSITION_SEED, position.owner.as_ref()], bump = position.bump)]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        close = beneficiary,
+        seeds = [SUCCESSION_SEED, position.key().as_ref()],
+        bump = succession.bump,
+        has_one = beneficiary
+    )]
+    pub succession: Account<'info, Succession>,
+    /// The beneficiary's position, which must not exist yet
+    #[account(
+        init,
+        payer = beneficiary,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [POSITION_SEED, beneficiary.key().as_ref()],
+        bump
+    )]
+    pub new_position: Box<Account<'info, Position>>,
+    /// CHECK: SOL vault PDA of the old position
+    #[account(mut, seeds = [SOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// CHECK: SOL vault PDA of the new position
+    #[account(mut, seeds = [SOL_VAULT_SEED, new_position.key().as_ref()], bump)]
+    pub new_sol_vault: UncheckedAccount<'info>,
+    #[account(mut, seeds = [MSOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub msol_vault: Option<Box<Account<'info, TokenAccount>>>,
+    /// mSOL vault of the new position (authority = new position PDA)
+    #[account(
+        init,
+        payer = beneficiary,
+        token::mint = msol_mint,
+        token::authority = new_position,
+        seeds = [MSOL_VAULT_SEED, new_position.key().as_ref()],
+        bump
+    )]
+    pub new_msol_vault: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(address = marinade::MSOL_MINT)]
+    pub msol_mint: Option<Box<Account<'info, Mint>>>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetEMode<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    /// Market covering the position's debt, required while it has any
+    #[account(
+        seeds = [MARKET_SEED, &market.market_id.to_le_bytes()],
+        bump = market.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub market: Option<Box<Account<'info, Market>>>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    pub owner: Signer<'info>,
+}
+
+// ========== REFERRAL ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + Referrer::INIT_SPACE,
+        seeds = [REFERRER_SEED, referrer.key().as_ref()],
+        bump
+    )]
+    pub referrer_account: Account<'info, Referrer>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenReferralVault<'info> {
+    #[account(
+        seeds = [REFERRER_SEED, referrer.key().as_ref()],
+        bump = referrer_account.bump,
+        has_one = referrer
+    )]
+    pub referrer_account: Account<'info, Referrer>,
+    /// Referral rewards in `mint` (authority = referrer registry PDA)
+    #[account(
+        init,
+        payer = referrer,
+        token::mint = mint,
+        token::authority = referrer_account,
+        seeds = [REFERRAL_VAULT_SEED, referrer.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub referral_vault: Account<'info, TokenAccount>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, referrer.key().as_ref()],
+        bump = referrer_account.bump,
+        has_one = referrer
+    )]
+    pub referrer_account: Account<'info, Referrer>,
+    #[account(
+        mut,
+        seeds = [REFERRAL_VAULT_SEED, referrer.key().as_ref(), referral_vault.mint.as_ref()],
+        bump
+    )]
+    pub referral_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = referral_vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+    pub referrer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+// ========== POINTS ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct OpenPointsLedger<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PointsLedger::INIT_SPACE,
+        seeds = [POINTS_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub points_ledger: Account<'info, PointsLedger>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AccruePoints<'info> {
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [POINTS_SEED, position.key().as_ref()],
+        bump = points_ledger.bump
+    )]
+    pub points_ledger: Account<'info, PointsLedger>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    #[account(constraint = sol_price_feed.asset_type == AssetType::SOL @ LegasiError::InvalidOracle)]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u32)]
+pub struct PostPointsSnapshot<'info> {
+    #[account(
+        seeds = [PROTOCOL_SEED],
+        bump = protocol.bump,
+        seeds::program = legasi_core::ID,
+        has_one = admin
+    )]
+    pub protocol: Box<Account<'info, Protocol>>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PointsSnapshot::INIT_SPACE,
+        seeds = [POINTS_SNAPSHOT_SEED, &epoch.to_le_bytes()],
+        bump
+    )]
+    pub points_snapshot: Account<'info, PointsSnapshot>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ========== STATEMENT ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct OpenStatementLedger<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StatementLedger::INIT_SPACE,
+        seeds = [STATEMENT_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub statement_ledger: Account<'info, StatementLedger>,
+    /// CHECK: Marinade State, read for the mSOL rate the first period starts from
+    #[account(address = marinade::MARINADE_STATE, owner = marinade::ID)]
+    pub marinade_state: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CrankPositionStatement<'info> {
+    #[account(mut)]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [STATEMENT_SEED, position.key().as_ref()],
+        bump = statement_ledger.bump
+    )]
+    pub statement_ledger: Account<'info, StatementLedger>,
+    #[account(constraint = sol_price_feed.asset_type == AssetType::SOL @ LegasiError::InvalidOracle)]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC has been charged
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: Marinade State, read for the mSOL rate to measure staking yield
+    #[account(address = marinade::MARINADE_STATE, owner = marinade::ID)]
+    pub marinade_state: Option<UncheckedAccount<'info>>,
+}
+
+// ========== NOTIFICATION ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct RegisterNotifyService<'info> {
+    #[account(
+        init,
+        payer = service,
+        space = 8 + NotifyService::INIT_SPACE,
+        seeds = [NOTIFY_SERVICE_SEED, service.key().as_ref()],
+        bump
+    )]
+    pub notify_service: Account<'info, NotifyService>,
+    #[account(mut)]
+    pub service: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateNotifyService<'info> {
+    #[account(
+        mut,
+        seeds = [NOTIFY_SERVICE_SEED, service.key().as_ref()],
+        bump = notify_service.bump,
+        has_one = service
+    )]
+    pub notify_service: Account<'info, NotifyService>,
+    pub service: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SubscribeNotifications<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [NOTIFY_SERVICE_SEED, notify_service.service.as_ref()],
+        bump = notify_service.bump
+    )]
+    pub notify_service: Account<'info, NotifyService>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + NotifySubscription::INIT_SPACE,
+        seeds = [
+            NOTIFY_SUBSCRIPTION_SEED,
+            position.key().as_ref(),
+            notify_service.service.as_ref()
+        ],
+        bump
+    )]
+    pub subscription: Account<'info, NotifySubscription>,
+    /// CHECK: fee vault PDA (system-owned, holds the prepaid fees)
+    #[account(mut, seeds = [NOTIFY_FEE_VAULT_SEED, subscription.key().as_ref()], bump)]
+    pub fee_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnsubscribeNotifications<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            NOTIFY_SUBSCRIPTION_SEED,
+            subscription.position.as_ref(),
+            subscription.service.as_ref()
+        ],
+        bump = subscription.bump,
+        has_one = owner
+    )]
+    pub subscription: Account<'info, NotifySubscription>,
+    #[account(
+        mut,
+        seeds = [NOTIFY_SERVICE_SEED, subscription.service.as_ref()],
+        bump = notify_service.bump
+    )]
+    pub notify_service: Account<'info, NotifyService>,
+    /// CHECK: fee vault PDA (system-owned, drained to the owner)
+    #[account(mut, seeds = [NOTIFY_FEE_VAULT_SEED, subscription.key().as_ref()], bump)]
+    pub fee_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct NotifySubscriberCrank<'info> {
+    #[account(mut)]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [
+            NOTIFY_SUBSCRIPTION_SEED,
+            position.key().as_ref(),
+            subscription.service.as_ref()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, NotifySubscription>,
+    #[account(
+        mut,
+        seeds = [NOTIFY_SERVICE_SEED, subscription.service.as_ref()],
+        bump = notify_service.bump
+    )]
+    pub notify_service: Account<'info, NotifyService>,
+    /// CHECK: fee vault PDA (system-owned, pays the fee)
+    #[account(mut, seeds = [NOTIFY_FEE_VAULT_SEED, subscription.key().as_ref()], bump)]
+    pub fee_vault: UncheckedAccount<'info>,
+    /// CHECK: the subscription's service, receives the fee
+    #[account(mut, address = subscription.service)]
+    pub service: UncheckedAccount<'info>,
+    #[account(constraint = sol_price_feed.asset_type == AssetType::SOL @ LegasiError::InvalidOracle)]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    pub system_program: Program<'info, System>,
+}
 
-            // Update pool
-            let lp_pool = &mut ctx.accounts.lp_pool;
-            lp_pool.total_borrowed = lp_pool
-                .total_borrowed
-                .checked_add(borrow_amount)
-                .ok_or(LegasiError::MathOverflow)?;
-        }
+// ========== GOVERNANCE ACCOUNTS ==========
 
-        // Now pay the recipient
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.agent_token_account.to_account_info(),
-                    to: ctx.accounts.recipient_token_account.to_account_info(),
-                    authority: ctx.accounts.agent.to_account_info(),
-                },
-            ),
-            amount,
-        )?;
+#[derive(Accounts)]
+#[instruction(epoch: u32)]
+pub struct SnapshotVotingPower<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + VotingPower::INIT_SPACE,
+        seeds = [VOTING_POWER_SEED, owner.key().as_ref(), &epoch.to_le_bytes()],
+        bump
+    )]
+    pub voting_power: Account<'info, VotingPower>,
+    /// Owner's position, if they have one
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Option<Account<'info, Position>>,
+    /// USDC borrowable config (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID,
+        constraint = borrowable_config.asset_type == AssetType::USDC @ LegasiError::AssetNotSupported
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    #[account(
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// Owner's bUSDC, if they hold any
+    #[account(
+        constraint = lp_token_account.mint == lp_pool.lp_token_mint @ LegasiError::InvalidAmount,
+        constraint = lp_token_account.owner == owner.key() @ LegasiError::Unauthorized
+    )]
+    pub lp_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(constraint = sol_price_feed.asset_type == AssetType::SOL @ LegasiError::InvalidOracle)]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
 
-        // Create receipt
-        let receipt = &mut ctx.accounts.receipt;
-        receipt.payment_id = payment_request.payment_id;
-        receipt.payer = ctx.accounts.agent.key();
-        receipt.recipient = payment_request.recipient;
-        receipt.amount = amount;
-        receipt.paid_at = now;
-        receipt.tx_signature = [0u8; 64]; // Filled by runtime
-        receipt.bump = ctx.bumps.receipt;
+// ========== LETTER OF CREDIT ACCOUNTS ==========
 
-        emit!(X402PaymentMade {
-            payer: ctx.accounts.agent.key(),
-            recipient: payment_request.recipient,
-            amount,
-            payment_id: payment_request.payment_id,
-            borrowed: agent_balance < amount,
-        });
+#[derive(Accounts)]
+#[instruction(letter_id: u64)]
+pub struct IssueLetterOfCredit<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LetterOfCredit::INIT_SPACE,
+        seeds = [LETTER_OF_CREDIT_SEED, position.key().as_ref(), &letter_id.to_le_bytes()],
+        bump
+    )]
+    pub letter_of_credit: Account<'info, LetterOfCredit>,
+    /// CHECK: any wallet or program account can be named beneficiary
+    pub beneficiary: UncheckedAccount<'info>,
+    /// Borrowable config of the letter's asset (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID,
+        constraint = borrowable_config.is_active @ LegasiError::AssetNotActive
+    )]
+    pub borrowable_config: Account<'info, Borrowable>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
 
-        msg!("x402 payment: {} to {}", amount, payment_request.recipient);
-        Ok(())
-    }
+#[derive(Accounts)]
+pub struct ClaimLetterOfCredit<'info> {
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            LETTER_OF_CREDIT_SEED,
+            position.key().as_ref(),
+            &letter_of_credit.letter_id.to_le_bytes()
+        ],
+        bump = letter_of_credit.bump,
+        has_one = beneficiary
+    )]
+    pub letter_of_credit: Account<'info, LetterOfCredit>,
+    /// CHECK: position owner, receives the letter's rent
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    /// Borrowable config of the pool's asset, must be the letter's asset
+    #[account(
+        seeds = [BORROWABLE_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID,
+        constraint = borrowable_config.asset_type == letter_of_credit.asset_type @ LegasiError::InvalidAmount
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub borrow_vault: Box<Account<'info, TokenAccount>>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    #[account(mut, token::mint = lp_pool.borrowable_mint)]
+    pub beneficiary_token_account: Box<Account<'info, TokenAccount>>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, lp_pool.borrowable_mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
+    pub beneficiary: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[event]
-pub struct X402PaymentMade {
-    pub payer: Pubkey,
-    pub recipient: Pubkey,
-    pub amount: u64,
-    pub payment_id: [u8; 32],
-    pub borrowed: bool,
+#[derive(Accounts)]
+pub struct ReleaseLetterOfCredit<'info> {
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            LETTER_OF_CREDIT_SEED,
+            position.key().as_ref(),
+            &letter_of_credit.letter_id.to_le_bytes()
+        ],
+        bump = letter_of_credit.bump
+    )]
+    pub letter_of_credit: Account<'info, LetterOfCredit>,
+    /// CHECK: position owner, receives the letter's rent
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    /// Beneficiary, or the owner once the letter has expired
+    pub authority: Signer<'info>,
 }
 
-#[event]
-pub struct AgentBorrowed {
-    pub position: Pubkey,
-    pub amount: u64,
-    pub daily_remaining: u64,
+// ========== AGENT ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct ConfigureAgent<'info> {
+    #[account(
+        seeds = [POSITION_SEED, owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AgentConfig::INIT_SPACE,
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub agent_config: Account<'info, AgentConfig>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-/// Off-ramp request status
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
-pub enum OfframpStatus {
-    Pending,
-    Processing,
-    Completed,
-    Failed,
+#[derive(Accounts)]
+pub struct UpdateAgentConfig<'info> {
+    #[account(
+        seeds = [POSITION_SEED, owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
+        bump = agent_config.bump
+    )]
+    pub agent_config: Account<'info, AgentConfig>,
+    pub owner: Signer<'info>,
 }
 
-/// Off-ramp request account
-#[account]
-#[derive(InitSpace)]
-pub struct OfframpRequest {
-    pub owner: Pubkey,
-    pub amount: u64,
-    #[max_len(34)]
-    pub destination_iban: String,
-    #[max_len(100)]
-    pub destination_name: String,
-    pub status: OfframpStatus,
-    pub created_at: i64,
-    pub completed_at: i64,
-    pub bump: u8,
+#[derive(Accounts)]
+pub struct SetAgentProfile<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
+        bump = agent_config.bump
+    )]
+    pub agent_config: Account<'info, AgentConfig>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AgentProfile::INIT_SPACE,
+        seeds = [AGENT_PROFILE_SEED, agent_config.key().as_ref()],
+        bump
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[event]
-pub struct OfframpRequested {
-    pub owner: Pubkey,
-    pub amount: u64,
-    pub destination_iban: String,
-    pub asset_type: AssetType,
+#[derive(Accounts)]
+pub struct CloseAgentProfile<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
+    #[account(
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
+        bump = agent_config.bump
+    )]
+    pub agent_config: Account<'info, AgentConfig>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [AGENT_PROFILE_SEED, agent_config.key().as_ref()],
+        bump = agent_profile.bump
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
 }
 
-// ========== ACCOUNTS ==========
+#[derive(Accounts)]
+pub struct AgentBorrow<'info> {
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
+        bump = agent_config.bump,
+        constraint = agent_config.position == position.key()
+    )]
+    pub agent_config: Account<'info, AgentConfig>,
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub borrow_vault: Account<'info, TokenAccount>,
+    /// Borrowable config of the pool's asset (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    #[account(
+        mut,
+        constraint = agent_token_account.mint == lp_pool.borrowable_mint @ LegasiError::InvalidAmount
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, lp_pool.borrowable_mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
+    /// CHECK: asset caps PDA, enforced only if it exists (see `legasi_core::caps`)
+    #[account(seeds = [ASSET_CAPS_SEED, lp_pool.borrowable_mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub asset_caps: UncheckedAccount<'info>,
+    /// The agent (position owner) executing the borrow
+    #[account(constraint = agent.key() == position.owner)]
+    pub agent: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
 
 #[derive(Accounts)]
-pub struct InitializeLendingVault<'info> {
+pub struct OpenCreditLine<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
+    pub position: Account<'info, Position>,
     #[account(
         init,
-        payer = admin,
-        token::mint = mint,
-        token::authority = lending_vault,
-        seeds = [b"lending_vault", mint.key().as_ref()],
+        payer = owner,
+        space = 8 + CreditLine::INIT_SPACE,
+        seeds = [CREDIT_LINE_SEED, position.key().as_ref()],
         bump
     )]
-    pub lending_vault: Account<'info, TokenAccount>,
-    pub mint: Account<'info, Mint>,
+    pub credit_line: Account<'info, CreditLine>,
+    /// Borrowable config of the line's asset (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_config.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID,
+        constraint = borrowable_config.is_active @ LegasiError::AssetNotActive
+    )]
+    pub borrowable_config: Account<'info, Borrowable>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
     #[account(mut)]
-    pub admin: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct InitializePosition<'info> {
+pub struct DrawCreditLine<'info> {
     #[account(
-        init,
-        payer = owner,
-        space = 8 + Position::INIT_SPACE,
-        seeds = [b"position", owner.key().as_ref()],
-        bump
+        mut,
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump
     )]
-    pub position: Account<'info, Position>,
+    pub position: Box<Account<'info, Position>>,
+    #[account(
+        mut,
+        seeds = [CREDIT_LINE_SEED, position.key().as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
+    /// Borrowable config of the pool's asset, must be the line's asset
+    #[account(
+        seeds = [BORROWABLE_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID,
+        constraint = borrowable_config.asset_type == credit_line.asset_type @ LegasiError::InvalidAmount
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub borrow_vault: Account<'info, TokenAccount>,
+    /// Protocol state (owned by core program - totals updated via CPI)
     #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    #[account(mut)]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, lp_pool.borrowable_mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
+    /// The agent (position owner) drawing on the line
+    #[account(constraint = agent.key() == position.owner)]
+    pub agent: Signer<'info>,
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct DepositSol<'info> {
-    #[account(mut, seeds = [b"position", owner.key().as_ref()], bump = position.bump, has_one = owner)]
+pub struct AccrueStandbyFee<'info> {
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump
+    )]
     pub position: Account<'info, Position>,
-    /// CHECK: SOL vault PDA
-    #[account(mut, seeds = [b"sol_vault", position.key().as_ref()], bump)]
-    pub sol_vault: UncheckedAccount<'info>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [CREDIT_LINE_SEED, position.key().as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
 }
 
 #[derive(Accounts)]
-pub struct DepositToken<'info> {
-    #[account(mut, seeds = [b"position", owner.key().as_ref()], bump = position.bump, has_one = owner)]
+pub struct CloseCreditLine<'info> {
+    #[account(mut, seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
     pub position: Account<'info, Position>,
-    #[account(mut, seeds = [b"collateral", collateral_config.mint.as_ref()], bump = collateral_config.bump)]
-    pub collateral_config: Account<'info, Collateral>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut, seeds = [b"token_vault", collateral_config.mint.as_ref()], bump)]
-    pub token_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [CREDIT_LINE_SEED, position.key().as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
     #[account(mut)]
     pub owner: Signer<'info>,
-    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct Borrow<'info> {
-    #[account(mut, seeds = [b"position", owner.key().as_ref()], bump = position.bump, has_one = owner)]
+pub struct AgentAutoRepay<'info> {
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump
+    )]
     pub position: Account<'info, Position>,
-    /// Protocol state (owned by core program - no seeds validation)
-    pub protocol: Account<'info, Protocol>,
-    /// Borrowable config (owned by core program - no seeds validation)
-    pub borrowable_config: Account<'info, Borrowable>,
-    /// Lending vault (owned by this program)
+    #[account(
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
+        bump = agent_config.bump,
+        constraint = agent_config.position == position.key()
+    )]
+    pub agent_config: Account<'info, AgentConfig>,
     #[account(
         mut,
-        seeds = [b"lending_vault", borrowable_config.mint.as_ref()],
-        bump
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
     )]
     pub borrow_vault: Account<'info, TokenAccount>,
+    /// Borrowable config of the pool's asset (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    /// Protocol state (owned by core program - totals updated via CPI)
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    /// Price feed (owned by core program - no seeds validation)
-    pub sol_price_feed: Account<'info, PriceFeed>,
-    /// CHECK: SOL mint
-    pub sol_mint: UncheckedAccount<'info>,
-    pub owner: Signer<'info>,
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    #[account(mut)]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    /// EURC price feed (owned by core program), required to repay EURC debt
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// The agent executing auto-repay
+    #[account(constraint = agent.key() == position.owner)]
+    pub agent: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
+/// Crank auto-repay (permissionless - funds move via the agent_config delegation)
 #[derive(Accounts)]
-pub struct Repay<'info> {
-    #[account(mut, seeds = [b"position", owner.key().as_ref()], bump = position.bump, has_one = owner)]
+pub struct CrankAutoRepay<'info> {
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, position.owner.as_ref()],
+        bump = position.bump
+    )]
     pub position: Account<'info, Position>,
+    #[account(
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
+        bump = agent_config.bump,
+        constraint = agent_config.position == position.key()
+    )]
+    pub agent_config: Account<'info, AgentConfig>,
     /// Borrowable config (owned by core program)
     pub borrowable_config: Account<'info, Borrowable>,
-    /// LP vault to transfer repaid tokens to
-    #[account(mut)]
-    pub repay_vault: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, borrowable_config.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub borrow_vault: Account<'info, TokenAccount>,
+    /// Protocol state (owned by core program - totals updated via CPI)
     #[account(mut)]
-    pub owner: Signer<'info>,
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    #[account(
+        mut,
+        constraint = agent_token_account.owner == position.owner @ LegasiError::Unauthorized,
+        constraint = agent_token_account.mint == borrowable_config.mint @ LegasiError::InvalidAmount
+    )]
+    pub agent_token_account: Account<'info, TokenAccount>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    pub cranker: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawSol<'info> {
-    #[account(mut, seeds = [b"position", owner.key().as_ref()], bump = position.bump, has_one = owner)]
+pub struct SetAutoDeleverage<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
     pub position: Account<'info, Position>,
-    /// CHECK: SOL vault PDA
-    #[account(mut, seeds = [b"sol_vault", position.key().as_ref()], bump)]
-    pub sol_vault: UncheckedAccount<'info>,
-    /// Price feed (owned by core - no seeds validation)
-    pub sol_price_feed: Account<'info, PriceFeed>,
-    /// CHECK: SOL mint
-    pub sol_mint: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + AutoDeleverageOrder::INIT_SPACE,
+        seeds = [AUTO_DELEVERAGE_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub order: Account<'info, AutoDeleverageOrder>,
+    /// Swap output, drained on every execution (authority = order PDA)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        token::mint = borrowable_mint,
+        token::authority = order,
+        seeds = [AUTO_DELEVERAGE_PROCEEDS_SEED, order.key().as_ref()],
+        bump
+    )]
+    pub proceeds_account: Account<'info, TokenAccount>,
+    /// Borrowable config of the asset collateral is sold for (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, borrowable_mint.key().as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Account<'info, Borrowable>,
+    pub borrowable_mint: Account<'info, Mint>,
     #[account(mut)]
     pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(request_id: u64)]
-pub struct OfframpViaBridge<'info> {
-    #[account(mut, seeds = [b"position", owner.key().as_ref()], bump = position.bump, has_one = owner)]
+pub struct CancelAutoDeleverage<'info> {
+    #[account(seeds = [POSITION_SEED, owner.key().as_ref()], bump = position.bump, has_one = owner)]
     pub position: Account<'info, Position>,
     #[account(
-        init,
-        payer = owner,
-        space = 8 + OfframpRequest::INIT_SPACE,
-        seeds = [b"offramp", owner.key().as_ref(), &request_id.to_le_bytes()],
-        bump
+        mut,
+        close = owner,
+        seeds = [AUTO_DELEVERAGE_SEED, position.key().as_ref()],
+        bump = order.bump
     )]
-    pub offramp_request: Account<'info, OfframpRequest>,
-    #[account(mut)]
-    pub stablecoin_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub order: Account<'info, AutoDeleverageOrder>,
+    #[account(mut, seeds = [AUTO_DELEVERAGE_PROCEEDS_SEED, order.key().as_ref()], bump)]
+    pub proceeds_account: Account<'info, TokenAccount>,
     #[account(mut)]
     pub owner: Signer<'info>,
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
 }
 
-/// Accrue interest on a position (permissionless - anyone can crank)
+/// Crank an auto-deleverage order (permissionless - the keeper earns the order's tip)
 #[derive(Accounts)]
-pub struct AccruePositionInterest<'info> {
+pub struct CrankAutoDeleverage<'info> {
     #[account(
         mut,
-        seeds = [b"position", position.owner.as_ref()],
+        seeds = [POSITION_SEED, position.owner.as_ref()],
         bump = position.bump
     )]
-    pub position: Account<'info, Position>,
-}
-
-// ========== AGENT ACCOUNTS ==========
-
-#[derive(Accounts)]
-pub struct ConfigureAgent<'info> {
+    pub position: Box<Account<'info, Position>>,
     #[account(
-        seeds = [b"position", owner.key().as_ref()],
-        bump = position.bump,
-        has_one = owner
+        mut,
+        seeds = [AUTO_DELEVERAGE_SEED, position.key().as_ref()],
+        bump = order.bump,
+        has_one = position
     )]
-    pub position: Account<'info, Position>,
+    pub order: Box<Account<'info, AutoDeleverageOrder>>,
+    #[account(mut, seeds = [AUTO_DELEVERAGE_PROCEEDS_SEED, order.key().as_ref()], bump)]
+    pub proceeds_account: Box<Account<'info, TokenAccount>>,
+    /// Borrowable config of the order's asset (owned by core program)
     #[account(
-        init,
-        payer = owner,
-        space = 8 + AgentConfig::INIT_SPACE,
-        seeds = [b"agent_config", position.key().as_ref()],
-        bump
+        seeds = [BORROWABLE_SEED, order.mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
     )]
-    pub agent_config: Account<'info, AgentConfig>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct UpdateAgentConfig<'info> {
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
     #[account(
-        seeds = [b"position", owner.key().as_ref()],
-        bump = position.bump,
-        has_one = owner
+        mut,
+        seeds = [LP_POOL_SEED, order.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
     )]
-    pub position: Account<'info, Position>,
+    pub lp_pool: Box<Account<'info, LpPool>>,
     #[account(
         mut,
-        seeds = [b"agent_config", position.key().as_ref()],
-        bump = agent_config.bump
+        seeds = [LP_VAULT_SEED, order.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
     )]
-    pub agent_config: Account<'info, AgentConfig>,
-    pub owner: Signer<'info>,
+    pub repay_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == position.owner @ LegasiError::Unauthorized,
+        constraint = owner_token_account.mint == order.mint @ LegasiError::InvalidAmount
+    )]
+    pub owner_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = order.mint)]
+    pub keeper_token_account: Box<Account<'info, TokenAccount>>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// CHECK: SOL vault PDA (swap input, signs the route)
+    #[account(mut, seeds = [SOL_VAULT_SEED, position.key().as_ref()], bump)]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    /// CHECK: Jupiter Aggregator v6
+    #[account(address = jupiter_cpi::ID)]
+    pub jupiter_program: UncheckedAccount<'info>,
+    pub keeper: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    // Jupiter route accounts are passed via remaining_accounts
 }
 
+/// Receive a CCTP repayment (permissionless - any relayer can submit the attestation)
 #[derive(Accounts)]
-pub struct AgentBorrow<'info> {
+pub struct ReceiveCctpRepayment<'info> {
     #[account(
         mut,
-        seeds = [b"position", position.owner.as_ref()],
+        seeds = [POSITION_SEED, position.owner.as_ref()],
         bump = position.bump
     )]
-    pub position: Account<'info, Position>,
+    pub position: Box<Account<'info, Position>>,
+    /// Borrowable config (owned by core program)
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
     #[account(
         mut,
-        seeds = [b"agent_config", position.key().as_ref()],
-        bump = agent_config.bump,
-        constraint = agent_config.position == position.key()
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
     )]
-    pub agent_config: Account<'info, AgentConfig>,
+    pub lp_pool: Box<Account<'info, LpPool>>,
     #[account(
         mut,
-        seeds = [b"lp_pool", lp_pool.borrowable_mint.as_ref()],
-        bump = lp_pool.bump
+        seeds = [LP_VAULT_SEED, borrowable_config.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
     )]
-    pub lp_pool: Account<'info, LpPool>,
+    pub repay_vault: Box<Account<'info, TokenAccount>>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    /// Position's CCTP inbox - the mint_recipient of the source-chain burn
     #[account(
-        mut,
-        seeds = [b"lp_vault", lp_pool.borrowable_mint.as_ref()],
+        init_if_needed,
+        payer = relayer,
+        token::mint = usdc_mint,
+        token::authority = position,
+        seeds = [CCTP_INBOX_SEED, position.key().as_ref()],
         bump
     )]
-    pub borrow_vault: Account<'info, TokenAccount>,
+    pub cctp_inbox: Box<Account<'info, TokenAccount>>,
+    #[account(address = borrowable_config.mint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == position.owner @ LegasiError::Unauthorized,
+        constraint = owner_token_account.mint == borrowable_config.mint @ LegasiError::InvalidAmount
+    )]
+    pub owner_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: CCTP MessageTransmitter - validated in cctp::receive_message
+    pub message_transmitter: UncheckedAccount<'info>,
     #[account(mut)]
-    pub agent_token_account: Account<'info, TokenAccount>,
-    #[account(seeds = [b"price", &[AssetType::SOL as u8]], bump = sol_price_feed.bump)]
-    pub sol_price_feed: Account<'info, PriceFeed>,
-    /// The agent (position owner) executing the borrow
-    #[account(constraint = agent.key() == position.owner)]
-    pub agent: Signer<'info>,
+    pub relayer: Signer<'info>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    // CCTP receive_message accounts passed via remaining_accounts
 }
 
 #[derive(Accounts)]
-pub struct AgentAutoRepay<'info> {
+#[instruction(request: SolanaPayRequest)]
+pub struct SolanaPay<'info> {
     #[account(
         mut,
-        seeds = [b"position", position.owner.as_ref()],
+        seeds = [POSITION_SEED, position.owner.as_ref()],
         bump = position.bump
     )]
-    pub position: Account<'info, Position>,
+    pub position: Box<Account<'info, Position>>,
     #[account(
-        seeds = [b"agent_config", position.key().as_ref()],
+        mut,
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
         bump = agent_config.bump,
         constraint = agent_config.position == position.key()
     )]
-    pub agent_config: Account<'info, AgentConfig>,
+    pub agent_config: Box<Account<'info, AgentConfig>>,
+    /// Borrowable config (owned by core program)
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
     #[account(
         mut,
-        seeds = [b"lp_pool", lp_pool.borrowable_mint.as_ref()],
-        bump = lp_pool.bump
+        seeds = [LP_POOL_SEED, borrowable_config.mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
     )]
-    pub lp_pool: Account<'info, LpPool>,
+    pub lp_pool: Box<Account<'info, LpPool>>,
     #[account(
         mut,
-        seeds = [b"lp_vault", lp_pool.borrowable_mint.as_ref()],
-        bump
+        seeds = [LP_VAULT_SEED, borrowable_config.mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
     )]
     pub borrow_vault: Account<'info, TokenAccount>,
+    /// Protocol state (owned by core program - totals updated via CPI)
     #[account(mut)]
-    pub agent_token_account: Account<'info, TokenAccount>,
-    /// The agent executing auto-repay
-    #[account(constraint = agent.key() == position.owner)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    #[account(
+        mut,
+        constraint = recipient_token_account.owner == request.recipient,
+        constraint = recipient_token_account.mint == borrowable_config.mint
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Solana Pay reference key - only used to tag the transaction and seed the receipt
+    pub reference: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = agent,
+        space = 8 + SolanaPayReceipt::INIT_SPACE,
+        seeds = [SOLANA_PAY_RECEIPT_SEED, reference.key().as_ref()],
+        bump
+    )]
+    pub receipt: Box<Account<'info, SolanaPayReceipt>>,
+    /// CHECK: SPL Memo program
+    #[account(address = MEMO_PROGRAM_ID)]
+    pub memo_program: UncheckedAccount<'info>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, borrowable_config.mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
+    /// The agent making the payment
+    #[account(mut, constraint = agent.key() == position.owner)]
     pub agent: Signer<'info>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(service_id: [u8; 32])]
+pub struct RegisterServiceListing<'info> {
+    #[account(
+        init,
+        payer = provider,
+        space = 8 + ServiceListing::INIT_SPACE,
+        seeds = [SERVICE_LISTING_SEED, provider.key().as_ref(), service_id.as_ref()],
+        bump
+    )]
+    pub service_listing: Account<'info, ServiceListing>,
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateServiceListing<'info> {
+    #[account(
+        mut,
+        seeds = [SERVICE_LISTING_SEED, provider.key().as_ref(), service_listing.service_id.as_ref()],
+        bump = service_listing.bump,
+        has_one = provider
+    )]
+    pub service_listing: Account<'info, ServiceListing>,
+    pub provider: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -1299,31 +7076,66 @@ pub struct AgentAutoRepay<'info> {
 pub struct X402Pay<'info> {
     #[account(
         mut,
-        seeds = [b"position", position.owner.as_ref()],
+        seeds = [POSITION_SEED, position.owner.as_ref()],
         bump = position.bump
     )]
     pub position: Box<Account<'info, Position>>,
     #[account(
         mut,
-        seeds = [b"agent_config", position.key().as_ref()],
+        seeds = [AGENT_CONFIG_SEED, position.key().as_ref()],
         bump = agent_config.bump,
         constraint = agent_config.position == position.key()
     )]
     pub agent_config: Box<Account<'info, AgentConfig>>,
     #[account(
         mut,
-        seeds = [b"lp_pool", lp_pool.borrowable_mint.as_ref()],
-        bump = lp_pool.bump
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
     )]
     pub lp_pool: Box<Account<'info, LpPool>>,
     #[account(
         mut,
-        seeds = [b"lp_vault", lp_pool.borrowable_mint.as_ref()],
-        bump
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
     )]
     pub borrow_vault: Account<'info, TokenAccount>,
+    /// Borrowable config of the pool's asset (owned by core program)
+    #[account(
+        seeds = [BORROWABLE_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = borrowable_config.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable_config: Box<Account<'info, Borrowable>>,
+    /// Protocol state (owned by core program - totals updated via CPI)
+    #[account(mut)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
     #[account(mut)]
     pub agent_token_account: Account<'info, TokenAccount>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, sol_mint.key().as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Box<Account<'info, PriceFeed>>,
+    /// EURC price feed (owned by core program), required once EURC debt is involved
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: wSOL mint
+    #[account(address = token::spl_token::native_mint::ID)]
+    pub sol_mint: UncheckedAccount<'info>,
+    /// Paywall being paid, if any (terms are checked against the request)
+    pub service_listing: Option<Box<Account<'info, ServiceListing>>>,
+    /// Agent's profile, recorded on the receipt so the recipient can tell who paid
+    #[account(seeds = [AGENT_PROFILE_SEED, agent_config.key().as_ref()], bump = agent_profile.bump)]
+    pub agent_profile: Option<Box<Account<'info, AgentProfile>>>,
     #[account(
         mut,
         constraint = recipient_token_account.owner == payment_request.recipient
@@ -1333,10 +7145,15 @@ pub struct X402Pay<'info> {
         init,
         payer = agent,
         space = 8 + X402Receipt::INIT_SPACE,
-        seeds = [b"x402_receipt", payment_request.payment_id.as_ref()],
+        seeds = [X402_RECEIPT_SEED, payment_request.payment_id.as_ref()],
         bump
     )]
     pub receipt: Box<Account<'info, X402Receipt>>,
+    /// CHECK: market gate PDA, enforced only if it exists (see `legasi_core::gate`)
+    #[account(seeds = [MARKET_GATE_SEED, lp_pool.borrowable_mint.as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub market_gate: UncheckedAccount<'info>,
+    /// CHECK: allowlist entry or membership token account, validated by `check_gate`
+    pub gate_pass: Option<UncheckedAccount<'info>>,
     /// The agent making the payment
     #[account(mut, constraint = agent.key() == position.owner)]
     pub agent: Signer<'info>,
@@ -0,0 +1,96 @@
+//! Hard liquidation
+//!
+//! GAD sells at most `GAD_HARD_RATE_BPS` of collateral a day, too slow for a price
+//! crash. Once a position's LTV is `GAD_HARD_THRESHOLD_BPS` above the SOL max LTV,
+//! anyone can repay its debt in one asset and take SOL collateral worth the repayment
+//! plus the SOL collateral's `liquidation_bonus_bps`, in one call. When the collateral
+//! can't pay the bonus on the whole amount, the repayment shrinks to what it can pay.
+//!
+//! Once the position has no collateral left, the debt still owed in that asset is bad
+//! debt: its interest is dropped, and its principal is written off the pool
+//! (`legasi_lp::write_off_bad_debt`), the insurance fund covering what it can and the
+//! LPs the rest.
+//!
+//! Debt can also be left behind a position whose collateral isn't gone but is worth
+//! too little to liquidate: dust GAD left, or collateral liquidation doesn't seize.
+//! Once it is worth less than `BAD_DEBT_MAX_COLLATERAL_USD`, anyone can write that
+//! debt off the same way with write_off_bad_debt; the dust stays with the owner.
+//! Every write-off adds its USD value to `Protocol.bad_debt`.
+//!
+//! Flow:
+//! 1. Liquidator calls liquidate_position with the borrowed asset and an amount
+//! 2. The repayment goes to the LP vault (interest first), the SOL to the liquidator
+//! 3. With no collateral left, that asset's remaining debt is written off; other
+//!    assets are written off by liquidating them in turn, or with write_off_bad_debt
+
+use anchor_lang::prelude::*;
+use legasi_core::{
+    constants::{BPS_DENOMINATOR, GAD_HARD_THRESHOLD_BPS, USD_MULTIPLIER},
+    decimals::{sol_to_usd, usd_to_lamports},
+    errors::LegasiError,
+};
+
+/// Collateral (USD, 6 decimals) below which a position's debt can be written off
+pub const BAD_DEBT_MAX_COLLATERAL_USD: u64 = USD_MULTIPLIER;
+
+/// LTV (bps) from which a position can be liquidated, for a collateral max LTV
+pub fn liquidation_threshold_bps(max_ltv_bps: u16) -> u64 {
+    max_ltv_bps as u64 + GAD_HARD_THRESHOLD_BPS as u64
+}
+
+/// Size a liquidation repaying `repay_usd` against `collateral_lamports` of SOL
+/// Returns (USD repaid, lamports seized): the seizure is the repayment plus
+/// `bonus_bps`, and both shrink together if the collateral can't cover it
+pub fn size_liquidation(
+    repay_usd: u64,
+    collateral_lamports: u64,
+    sol_price: u64,
+    bonus_bps: u16,
+) -> Result<(u64, u64)> {
+    require!(sol_price > 0, LegasiError::InvalidOracle);
+    let bonus_factor = BPS_DENOMINATOR as u128 + bonus_bps as u128;
+    let seize_usd = repay_usd as u128 * bonus_factor / BPS_DENOMINATOR as u128;
+    let seize_usd = u64::try_from(seize_usd).map_err(|_| error!(LegasiError::MathOverflow))?;
+    let seize = usd_to_lamports(seize_usd, sol_price)?;
+    if seize <= collateral_lamports {
+        return Ok((repay_usd, seize));
+    }
+
+    let collateral_usd = sol_to_usd(collateral_lamports, sol_price)?;
+    let repay_usd = collateral_usd as u128 * BPS_DENOMINATOR as u128 / bonus_factor;
+    Ok((repay_usd as u64, collateral_lamports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use legasi_core::constants::LAMPORTS_PER_SOL;
+
+    #[test]
+    fn test_liquidation_threshold() {
+        assert_eq!(liquidation_threshold_bps(7_500), 9_000);
+    }
+
+    #[test]
+    fn test_seizure_includes_bonus() {
+        // $100 repaid at $50/SOL with a 5% bonus: $105 = 2.1 SOL
+        assert_eq!(
+            size_liquidation(100_000_000, 10 * LAMPORTS_PER_SOL, 50_000_000, 500).unwrap(),
+            (100_000_000, 2_100_000_000)
+        );
+    }
+
+    #[test]
+    fn test_short_collateral_shrinks_repayment() {
+        // 2 SOL at $50 is $100: it pays the bonus on $95.23 of debt, no more
+        assert_eq!(
+            size_liquidation(500_000_000, 2 * LAMPORTS_PER_SOL, 50_000_000, 500).unwrap(),
+            (95_238_095, 2 * LAMPORTS_PER_SOL)
+        );
+        assert_eq!(
+            size_liquidation(500_000_000, 0, 50_000_000, 500).unwrap(),
+            (0, 0)
+        );
+        assert!(size_liquidation(1, 1, 0, 500).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! Marinade liquid staking integration
+//!
+//! `deposit_and_stake` with `StakeProvider::Marinade` deposits SOL into Marinade
+//! and keeps the minted mSOL in the position's mSOL vault as collateral.
+//! Staking yield accrues through the mSOL price (SOL per mSOL), which only grows.
+//! There is deliberately no claim instruction: the yield is realized by
+//! `withdraw_staked`, never paid out of a vault holding anyone's principal.
+//! Until then, borrow credits the unrealized yield, less a haircut, when passed
+//! the Marinade State (`msol_rate`, `PriceBook::with_lst_yield`).
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey;
+use legasi_core::valuation::RATE_PRECISION;
+
+// Marinade liquid staking program (mainnet)
+declare_id!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+
+/// mSOL mint (mainnet)
+pub const MSOL_MINT: Pubkey = pubkey!("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So");
+
+/// Marinade State account (mainnet)
+pub const MARINADE_STATE: Pubkey = pubkey!("8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC");
+
+/// Anchor discriminator for Marinade `deposit` (sha256("global:deposit")[..8])
+pub const DEPOSIT_DISCRIMINATOR: [u8; 8] = [242, 35, 198, 137, 82, 225, 242, 182];
+
+/// Marinade prices are fixed-point with this denominator
+pub const MSOL_PRICE_DENOMINATOR: u64 = 0x1_0000_0000;
+
+/// Offset of `msol_price` in the Marinade State account (simplified layout)
+/// In production, deserialize with the marinade-cpi crate
+pub const MSOL_PRICE_OFFSET: usize = 512;
+
+/// Build the Marinade `deposit` instruction (SOL in, mSOL out)
+pub fn deposit_ix(accounts: &MarinadeDepositAccounts, lamports: u64) -> Instruction {
+    let mut data = DEPOSIT_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(accounts.state, false),
+            AccountMeta::new(accounts.msol_mint, false),
+            AccountMeta::new(accounts.liq_pool_sol_leg_pda, false),
+            AccountMeta::new(accounts.liq_pool_msol_leg, false),
+            AccountMeta::new_readonly(accounts.liq_pool_msol_leg_authority, false),
+            AccountMeta::new(accounts.reserve_pda, false),
+            AccountMeta::new(accounts.transfer_from, true),
+            AccountMeta::new(accounts.mint_to, false),
+            AccountMeta::new_readonly(accounts.msol_mint_authority, false),
+            AccountMeta::new_readonly(accounts.system_program, false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+        ],
+        data,
+    }
+}
+
+/// Account keys for the Marinade `deposit` instruction
+pub struct MarinadeDepositAccounts {
+    pub state: Pubkey,
+    pub msol_mint: Pubkey,
+    pub liq_pool_sol_leg_pda: Pubkey,
+    pub liq_pool_msol_leg: Pubkey,
+    pub liq_pool_msol_leg_authority: Pubkey,
+    pub reserve_pda: Pubkey,
+    pub transfer_from: Pubkey,
+    pub mint_to: Pubkey,
+    pub msol_mint_authority: Pubkey,
+    pub system_program: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// Read the mSOL price (SOL per mSOL, fixed-point) from Marinade State data
+pub fn parse_msol_price(data: &[u8]) -> Option<u64> {
+    let bytes = data.get(MSOL_PRICE_OFFSET..MSOL_PRICE_OFFSET + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Convert an mSOL amount to lamports at the given mSOL price
+pub fn msol_to_lamports(msol_amount: u64, msol_price: u64) -> Option<u64> {
+    (msol_amount as u128)
+        .checked_mul(msol_price as u128)?
+        .checked_div(MSOL_PRICE_DENOMINATOR as u128)
+        .map(|v| v as u64)
+}
+
+/// mSOL price as a `valuation` exchange rate (`RATE_PRECISION`)
+pub fn msol_rate(msol_price: u64) -> Option<u64> {
+    (msol_price as u128)
+        .checked_mul(RATE_PRECISION as u128)?
+        .checked_div(MSOL_PRICE_DENOMINATOR as u128)
+        .and_then(|v| u64::try_from(v).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msol_rate() {
+        let price = MSOL_PRICE_DENOMINATOR + MSOL_PRICE_DENOMINATOR / 4;
+        assert_eq!(msol_rate(price), Some(1_250_000_000));
+        assert_eq!(msol_rate(MSOL_PRICE_DENOMINATOR), Some(RATE_PRECISION));
+    }
+
+    #[test]
+    fn test_msol_to_lamports() {
+        // 1 mSOL at 1.25 SOL/mSOL
+        let price = MSOL_PRICE_DENOMINATOR + MSOL_PRICE_DENOMINATOR / 4;
+        assert_eq!(msol_to_lamports(1_000_000_000, price), Some(1_250_000_000));
+    }
+
+    #[test]
+    fn test_deposit_ix_data() {
+        let key = Pubkey::default();
+        let accounts = MarinadeDepositAccounts {
+            state: key,
+            msol_mint: key,
+            liq_pool_sol_leg_pda: key,
+            liq_pool_msol_leg: key,
+            liq_pool_msol_leg_authority: key,
+            reserve_pda: key,
+            transfer_from: key,
+            mint_to: key,
+            msol_mint_authority: key,
+            system_program: key,
+            token_program: key,
+        };
+        let ix = deposit_ix(&accounts, 42);
+        assert_eq!(ix.accounts.len(), 11);
+        assert_eq!(&ix.data[..8], &DEPOSIT_DISCRIMINATOR);
+        assert_eq!(&ix.data[8..], &42u64.to_le_bytes());
+    }
+}
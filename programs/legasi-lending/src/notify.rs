@@ -0,0 +1,102 @@
+//! Position health notifications
+//!
+//! An open registry for off-chain alerting: a notification service registers its key
+//! and a per-notice fee in a `NotifyService`, and a position owner subscribes to the
+//! service with an alert LTV, prepaying fees into a system-owned fee vault. Once the
+//! position's LTV reaches the alert level, anyone can crank notify_subscriber: it pays
+//! the service its fee from the vault and emits a `NotifySubscriber` event carrying
+//! the service key, which the service watches for. The protocol runs no alerting
+//! infrastructure of its own.
+//!
+//! The fee is fixed at subscription time, so a service raising its fee only charges
+//! new subscribers. Notices are rate limited to one per `NOTIFY_COOLDOWN` per
+//! subscription, so a position lingering above its alert level does not drain the
+//! vault.
+//!
+//! Flow:
+//! 1. Service calls register_notify_service (update_notify_service to change it)
+//! 2. Owner calls subscribe_notifications, topping up the fee vault at will
+//! 3. Anyone cranks notify_subscriber while the position is at its alert LTV
+//! 4. Owner calls unsubscribe_notifications for the unspent fees
+
+use anchor_lang::prelude::*;
+use legasi_core::errors::LegasiError;
+
+/// Minimum time between two notices of a subscription (1 hour)
+pub const NOTIFY_COOLDOWN: i64 = 3600;
+
+/// A notification service's registry entry
+#[account]
+#[derive(InitSpace)]
+pub struct NotifyService {
+    /// Key the service watches `NotifySubscriber` events for, and is paid to
+    pub service: Pubkey,
+    /// Fee per notice charged to new subscriptions (lamports)
+    pub fee_lamports: u64,
+    /// Whether the service takes notices
+    pub active: bool,
+    pub subscribers: u32,
+    pub notices_sent: u64,
+    pub bump: u8,
+}
+
+/// A position's subscription to a notification service
+#[account]
+#[derive(InitSpace)]
+pub struct NotifySubscription {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub service: Pubkey,
+    /// LTV (bps) at which the position is notified
+    pub alert_ltv_bps: u16,
+    /// Fee per notice, the service's at subscription time (lamports)
+    pub fee_lamports: u64,
+    /// Last notice (0 = never)
+    pub last_notified: i64,
+    pub notices: u32,
+    pub bump: u8,
+}
+
+impl NotifySubscription {
+    /// Fails unless a position at `ltv_bps` is due a notice at `now`
+    pub fn require_due(&self, ltv_bps: u64, now: i64) -> Result<()> {
+        require!(
+            ltv_bps >= self.alert_ltv_bps as u64,
+            LegasiError::LtvBelowAlert
+        );
+        require!(
+            self.last_notified == 0 || now.saturating_sub(self.last_notified) >= NOTIFY_COOLDOWN,
+            LegasiError::NotificationTooSoon
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_require_due() {
+        let mut subscription = NotifySubscription {
+            position: Pubkey::default(),
+            owner: Pubkey::default(),
+            service: Pubkey::default(),
+            alert_ltv_bps: 7_000,
+            fee_lamports: 10_000,
+            last_notified: 0,
+            notices: 0,
+            bump: 0,
+        };
+        assert!(subscription.require_due(6_999, 1_000).is_err());
+        subscription.require_due(7_000, 1_000).unwrap();
+
+        subscription.last_notified = 1_000;
+        assert!(subscription
+            .require_due(8_000, 1_000 + NOTIFY_COOLDOWN - 1)
+            .is_err());
+        subscription
+            .require_due(8_000, 1_000 + NOTIFY_COOLDOWN)
+            .unwrap();
+    }
+}
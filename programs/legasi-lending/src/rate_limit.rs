@@ -0,0 +1,98 @@
+//! Agent activity rate limit
+//!
+//! An agent runtime stuck in a loop can borrow and pay as fast as it can sign. The
+//! owner bounds it on-chain with `set_agent_rate_limit`: agent borrows, x402 and
+//! Solana Pay payments must be `min_interval` seconds apart. Payments batched into one
+//! transaction land in the same slot and count as one burst, up to `max_batch`
+//! actions, so a batch doesn't need one transaction per interval.
+//!
+//! Flow:
+//! 1. Owner calls set_agent_rate_limit (0 interval = no limit)
+//! 2. agent_borrow / x402_pay / solana_pay record each action, failing inside the interval
+
+use anchor_lang::prelude::*;
+use legasi_core::errors::LegasiError;
+
+/// Rate limit and last activity of an agent (stored on `AgentConfig`)
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct ActivityLimit {
+    /// Seconds between bursts of agent actions (0 = unlimited)
+    pub min_interval: i64,
+    /// Actions allowed in one burst, i.e. one slot (0 counts as 1)
+    pub max_batch: u8,
+    /// Start of the last burst
+    pub last_at: i64,
+    pub last_slot: u64,
+    /// Actions taken in the last burst
+    pub batch_count: u8,
+}
+
+impl ActivityLimit {
+    /// Count an action at `now` / `slot`, failing if it breaks the limit
+    pub fn record(&mut self, now: i64, slot: u64) -> Result<()> {
+        if self.min_interval > 0 {
+            if slot == self.last_slot {
+                require!(
+                    self.batch_count < self.max_batch.max(1),
+                    LegasiError::AgentRateLimited
+                );
+            } else {
+                require!(
+                    now.saturating_sub(self.last_at) >= self.min_interval,
+                    LegasiError::AgentRateLimited
+                );
+            }
+        }
+        if slot == self.last_slot {
+            self.batch_count = self.batch_count.saturating_add(1);
+        } else {
+            self.last_at = now;
+            self.last_slot = slot;
+            self.batch_count = 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval() {
+        let mut limit = ActivityLimit {
+            min_interval: 60,
+            ..Default::default()
+        };
+        limit.record(1_000, 10).unwrap();
+        assert!(limit.record(1_030, 20).is_err());
+        limit.record(1_060, 30).unwrap();
+        // A second action in the same slot needs a batch allowance
+        assert!(limit.record(1_060, 30).is_err());
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut limit = ActivityLimit {
+            min_interval: 60,
+            max_batch: 3,
+            ..Default::default()
+        };
+        for _ in 0..3 {
+            limit.record(1_000, 10).unwrap();
+        }
+        assert!(limit.record(1_000, 10).is_err());
+        assert!(limit.record(1_001, 11).is_err());
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let mut limit = ActivityLimit::default();
+        for slot in [1, 1, 1, 2] {
+            limit.record(1_000, slot).unwrap();
+        }
+        assert_eq!(limit.last_slot, 2);
+    }
+}
@@ -0,0 +1,52 @@
+//! Borrower referrals
+//!
+//! A referrer registers once, then opens a vault per borrowable asset. Positions
+//! created with the referrer's registry account remember it, and every `repay` on
+//! them sends `Protocol.referral_fee_bps` of the interest paid to that vault instead
+//! of the LP pool. The borrower pays the same either way.
+//!
+//! Flow:
+//! 1. Referrer calls register_referrer, then open_referral_vault for each asset
+//! 2. Borrower passes the registry account to initialize_position
+//! 3. `repay` pays the referral cut into the vault, if the referrer opened one for the asset
+//! 4. Referrer calls claim_referral_rewards to sweep a vault
+
+use anchor_lang::prelude::*;
+use anchor_spl::token;
+use legasi_core::constants::BPS_DENOMINATOR;
+
+/// Registered referrer
+#[account]
+#[derive(InitSpace)]
+pub struct Referrer {
+    pub referrer: Pubkey,
+    /// Positions opened with this referrer
+    pub referred_positions: u32,
+    /// Rewards claimed so far, across assets (asset units)
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+/// Referrer's share of `interest_paid` at `fee_bps`
+pub fn referral_cut(interest_paid: u64, fee_bps: u16) -> u64 {
+    ((interest_paid as u128) * (fee_bps as u128) / (BPS_DENOMINATOR as u128)) as u64
+}
+
+/// Whether the unchecked `[REFERRAL_VAULT_SEED, referrer, mint]` account has been opened
+pub fn referral_vault_open(info: &AccountInfo) -> bool {
+    info.owner == &token::ID && !info.data_is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referral_cut() {
+        // 10% of $68 interest
+        assert_eq!(referral_cut(68_000_000, 1_000), 6_800_000);
+        assert_eq!(referral_cut(9, 1_000), 0);
+        assert_eq!(referral_cut(u64::MAX, 10_000), u64::MAX);
+        assert_eq!(referral_cut(68_000_000, 0), 0);
+    }
+}
@@ -0,0 +1,133 @@
+//! Repayment schedules (partial GAD opt-out)
+//!
+//! A borrower can commit to repaying a fixed amount every week instead of being
+//! gradually deleveraged. While every elapsed week has been paid, the GAD cranks
+//! skip the position unless it is past the hard threshold. Missing a week lapses
+//! the schedule for good: GAD runs again, with a larger slice per crank.
+//!
+//! Flow:
+//! 1. Owner calls commit_repayment_schedule (needs open debt)
+//! 2. Each `repay` passing the schedule counts toward the current week
+//! 3. GAD cranks read the schedule PDA and skip, or apply the penalty if lapsed
+//! 4. Owner can cancel_repayment_schedule at any time to return to plain GAD
+
+use anchor_lang::prelude::*;
+use legasi_core::constants::{BPS_DENOMINATOR, SECONDS_PER_DAY};
+
+/// Length of one repayment period
+pub const REPAYMENT_PERIOD: i64 = 7 * SECONDS_PER_DAY;
+
+/// Weekly commitment must be at least this share of total debt (bps)
+pub const MIN_SCHEDULE_REPAY_BPS: u64 = 100; // 1%
+
+/// Extra GAD slice once a schedule lapses (bps of the normal slice)
+pub const MISSED_SCHEDULE_PENALTY_BPS: u64 = 5_000; // +50%
+
+/// Committed repayment schedule for a position
+#[account]
+#[derive(InitSpace)]
+pub struct RepaymentSchedule {
+    pub position: Pubkey,
+    /// Amount owed every period (borrowed asset units)
+    pub amount_per_period: u64,
+    /// End of the current period
+    pub period_end: i64,
+    /// Repaid so far in the current period
+    pub paid_this_period: u64,
+    /// A period was missed; the schedule no longer suppresses GAD
+    pub lapsed: bool,
+    pub bump: u8,
+}
+
+impl RepaymentSchedule {
+    /// True once any elapsed period was not paid in full
+    pub fn is_lapsed(&self, now: i64) -> bool {
+        if self.lapsed {
+            return true;
+        }
+        if now < self.period_end {
+            return false;
+        }
+        // The current period is over: it must be paid, and the next one can't be over too
+        self.paid_this_period < self.amount_per_period
+            || now >= self.period_end.saturating_add(REPAYMENT_PERIOD)
+    }
+
+    /// Count a repayment toward the current period, rolling into the next one
+    /// when the previous period was paid. Lapsing is sticky
+    pub fn record_payment(&mut self, amount: u64, now: i64) {
+        if self.is_lapsed(now) {
+            self.lapsed = true;
+            return;
+        }
+        if now >= self.period_end {
+            self.period_end = self.period_end.saturating_add(REPAYMENT_PERIOD);
+            self.paid_this_period = 0;
+        }
+        self.paid_this_period = self.paid_this_period.saturating_add(amount);
+    }
+}
+
+/// GAD slice fraction after the lapsed-schedule penalty, capped at 100%
+pub fn penalized_fraction_bps(liquidate_fraction_bps: u64) -> u64 {
+    let penalty =
+        liquidate_fraction_bps.saturating_mul(MISSED_SCHEDULE_PENALTY_BPS) / BPS_DENOMINATOR;
+    std::cmp::min(
+        liquidate_fraction_bps.saturating_add(penalty),
+        BPS_DENOMINATOR,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> RepaymentSchedule {
+        RepaymentSchedule {
+            position: Pubkey::default(),
+            amount_per_period: 100,
+            period_end: REPAYMENT_PERIOD,
+            paid_this_period: 0,
+            lapsed: false,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_schedule_met() {
+        let mut s = schedule();
+        s.record_payment(60, 1_000);
+        s.record_payment(40, 2_000);
+        assert!(!s.is_lapsed(REPAYMENT_PERIOD + 1));
+
+        // Paying in week two rolls the period
+        s.record_payment(100, REPAYMENT_PERIOD + 1);
+        assert_eq!(s.period_end, 2 * REPAYMENT_PERIOD);
+        assert_eq!(s.paid_this_period, 100);
+        assert!(!s.is_lapsed(2 * REPAYMENT_PERIOD + 1));
+    }
+
+    #[test]
+    fn test_schedule_missed() {
+        let mut s = schedule();
+        s.record_payment(50, 1_000);
+        assert!(!s.is_lapsed(REPAYMENT_PERIOD - 1));
+        assert!(s.is_lapsed(REPAYMENT_PERIOD));
+
+        // Catching up late doesn't restore it
+        s.record_payment(500, REPAYMENT_PERIOD + 1);
+        assert!(s.lapsed);
+        assert!(s.is_lapsed(REPAYMENT_PERIOD + 1));
+
+        // A paid week followed by a silent one also lapses
+        let mut s = schedule();
+        s.record_payment(100, 1_000);
+        assert!(s.is_lapsed(2 * REPAYMENT_PERIOD));
+    }
+
+    #[test]
+    fn test_penalty() {
+        assert_eq!(penalized_fraction_bps(100), 150);
+        assert_eq!(penalized_fraction_bps(8_000), BPS_DENOMINATOR);
+    }
+}
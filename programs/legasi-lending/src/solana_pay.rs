@@ -0,0 +1,104 @@
+//! Solana Pay Integration
+//!
+//! Lets point-of-sale merchants accept payments backed by a borrower's collateral.
+//! The merchant encodes a transfer request (recipient, amount, reference, memo)
+//! in a QR code; the agent's wallet fulfills it with `solana_pay`, which borrows
+//! the amount from the pool straight into the merchant's token account.
+//!
+//! Flow:
+//! 1. Merchant generates a `solana:` URL with a unique reference key
+//! 2. Agent calls solana_pay with the request (reference + extra references as accounts)
+//! 3. Legasi borrows, pays the merchant, writes the memo and a receipt
+//! 4. Merchant finds the transaction via getSignaturesForAddress(reference)
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey;
+
+/// SPL Memo program
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// Max memo length accepted in a payment
+pub const MAX_SOLANA_PAY_MEMO_LEN: usize = 256;
+
+/// Max additional reference keys (passed as remaining accounts)
+pub const MAX_SOLANA_PAY_REFERENCES: usize = 4;
+
+/// Solana Pay transfer request (parsed from the `solana:` URL)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SolanaPayRequest {
+    /// Merchant wallet
+    pub recipient: Pubkey,
+    /// Amount in smallest unit (e.g., USDC with 6 decimals)
+    pub amount: u64,
+    /// Optional memo, written via the SPL Memo program
+    pub memo: Option<String>,
+}
+
+impl SolanaPayRequest {
+    pub fn is_valid(&self) -> bool {
+        self.amount > 0
+            && self
+                .memo
+                .as_ref()
+                .map_or(true, |m| m.len() <= MAX_SOLANA_PAY_MEMO_LEN)
+    }
+}
+
+/// Solana Pay receipt (proof of payment, keyed by the request reference)
+#[account]
+#[derive(InitSpace)]
+pub struct SolanaPayReceipt {
+    /// Reference key from the transfer request
+    pub reference: Pubkey,
+    /// Who paid
+    pub payer: Pubkey,
+    /// Merchant who received
+    pub recipient: Pubkey,
+    /// Token paid
+    pub mint: Pubkey,
+    /// Amount paid
+    pub amount: u64,
+    /// When paid
+    pub paid_at: i64,
+    pub bump: u8,
+}
+
+/// Build an SPL Memo instruction with no signers
+pub fn memo_ix(memo: &str) -> Instruction {
+    Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: vec![],
+        data: memo.as_bytes().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_validation() {
+        let mut request = SolanaPayRequest {
+            recipient: Pubkey::default(),
+            amount: 1_000_000,
+            memo: Some("order #42".to_string()),
+        };
+        assert!(request.is_valid());
+
+        request.memo = Some("x".repeat(MAX_SOLANA_PAY_MEMO_LEN + 1));
+        assert!(!request.is_valid());
+
+        request.memo = None;
+        request.amount = 0;
+        assert!(!request.is_valid());
+    }
+
+    #[test]
+    fn test_memo_ix() {
+        let ix = memo_ix("order #42");
+        assert_eq!(ix.program_id, MEMO_PROGRAM_ID);
+        assert!(ix.accounts.is_empty());
+        assert_eq!(ix.data, b"order #42");
+    }
+}
@@ -0,0 +1,261 @@
+//! Position statements
+//!
+//! Consumer apps render monthly statements from one `PositionStatement` event per
+//! period instead of replaying every transaction. The position keeps running totals
+//! of the interest and fees booked on each borrowable (`StatementTotals`): interest
+//! as borrows accrue, the credit line standby fee and the debt conversion fee as they
+//! are charged. A statement ledger snapshots those totals, the position's GAD total
+//! and the mSOL rate at the start of each period; the crank reports the growth of
+//! each since then, in USD, and starts the next period.
+//!
+//! Staking yield is the mSOL rate's gain over the period on the mSOL held at the
+//! crank. It is only measured when the Marinade State is passed, and counts as zero
+//! for a period that started without a rate.
+//!
+//! Flow:
+//! 1. Owner calls open_statement_ledger
+//! 2. Anyone cranks crank_position_statement once `STATEMENT_PERIOD` has passed
+
+use anchor_lang::prelude::*;
+use legasi_core::{
+    decimals::sol_to_usd, errors::LegasiError, state::AssetType, valuation::RATE_PRECISION,
+};
+
+/// Length of a statement period (30 days)
+pub const STATEMENT_PERIOD: i64 = 30 * 86_400;
+
+/// Interest and fees booked on one borrowable, in its own units
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct DebtCharges {
+    pub interest: u64,
+    pub fees: u64,
+}
+
+/// Running totals of the interest and fees booked on a position
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct StatementTotals {
+    pub usdc: DebtCharges,
+    pub eurc: DebtCharges,
+}
+
+impl StatementTotals {
+    fn charges_mut(&mut self, asset_type: AssetType) -> Option<&mut DebtCharges> {
+        match asset_type {
+            AssetType::USDC => Some(&mut self.usdc),
+            AssetType::EURC => Some(&mut self.eurc),
+            _ => None,
+        }
+    }
+
+    pub fn record_interest(&mut self, asset_type: AssetType, amount: u64) {
+        if let Some(charges) = self.charges_mut(asset_type) {
+            charges.interest = charges.interest.saturating_add(amount);
+        }
+    }
+
+    pub fn record_fee(&mut self, asset_type: AssetType, amount: u64) {
+        if let Some(charges) = self.charges_mut(asset_type) {
+            charges.fees = charges.fees.saturating_add(amount);
+        }
+    }
+
+    /// Interest and fees booked since `start`, in USD (6 decimals), EURC at
+    /// `eur_usd_price` (only needed if EURC was charged)
+    pub fn usd_since(
+        &self,
+        start: &StatementTotals,
+        eur_usd_price: Option<u64>,
+    ) -> Result<DebtCharges> {
+        let mut usd = DebtCharges::default();
+        for (asset_type, now, then) in [
+            (AssetType::USDC, self.usdc, start.usdc),
+            (AssetType::EURC, self.eurc, start.eurc),
+        ] {
+            usd.interest = usd
+                .interest
+                .checked_add(charge_usd(
+                    asset_type,
+                    now.interest.saturating_sub(then.interest),
+                    eur_usd_price,
+                )?)
+                .ok_or(LegasiError::MathOverflow)?;
+            usd.fees = usd
+                .fees
+                .checked_add(charge_usd(
+                    asset_type,
+                    now.fees.saturating_sub(then.fees),
+                    eur_usd_price,
+                )?)
+                .ok_or(LegasiError::MathOverflow)?;
+        }
+        Ok(usd)
+    }
+}
+
+fn charge_usd(asset_type: AssetType, amount: u64, eur_usd_price: Option<u64>) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    asset_type.debt_to_usd(amount, eur_usd_price)
+}
+
+/// USD value (6 decimals) of the rate gain from `rate_at_start` to `rate` (both
+/// `RATE_PRECISION`) on `msol_amount`, at `sol_price`
+pub fn staking_yield_usd(
+    msol_amount: u64,
+    rate_at_start: u64,
+    rate: u64,
+    sol_price: u64,
+) -> Result<u64> {
+    if rate_at_start == 0 {
+        return Ok(0);
+    }
+    let lamports = (msol_amount as u128)
+        .checked_mul(rate.saturating_sub(rate_at_start) as u128)
+        .ok_or(LegasiError::MathOverflow)?
+        / RATE_PRECISION as u128;
+    let lamports = u64::try_from(lamports).map_err(|_| error!(LegasiError::MathOverflow))?;
+    sol_to_usd(lamports, sol_price)
+}
+
+/// Start-of-period snapshot a position's statements are measured from
+#[account]
+#[derive(InitSpace)]
+pub struct StatementLedger {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub period_start: i64,
+    /// Position's `statement_totals` at `period_start`
+    pub totals_at_start: StatementTotals,
+    /// Position's `total_gad_liquidated_usd` at `period_start`
+    pub gad_liquidated_at_start: u64,
+    /// mSOL rate (`RATE_PRECISION`) at `period_start` (0 = not measured)
+    pub msol_rate_at_start: u64,
+    /// Statements emitted so far
+    pub statements: u32,
+    pub bump: u8,
+}
+
+impl StatementLedger {
+    /// Fails until a full period has passed since `period_start`
+    pub fn require_period_over(&self, now: i64) -> Result<()> {
+        require!(
+            now.saturating_sub(self.period_start) >= STATEMENT_PERIOD,
+            LegasiError::StatementNotDue
+        );
+        Ok(())
+    }
+
+    /// Start the next period at `now` from the position's current figures. Without a
+    /// fresh mSOL rate the last one is kept
+    pub fn roll(
+        &mut self,
+        totals: StatementTotals,
+        gad_liquidated_usd: u64,
+        msol_rate: Option<u64>,
+        now: i64,
+    ) {
+        self.period_start = now;
+        self.totals_at_start = totals;
+        self.gad_liquidated_at_start = gad_liquidated_usd;
+        if let Some(rate) = msol_rate {
+            self.msol_rate_at_start = rate;
+        }
+        self.statements = self.statements.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use legasi_core::constants::{LAMPORTS_PER_SOL, USD_MULTIPLIER};
+
+    const EUR_USD: Option<u64> = Some(1_080_000);
+
+    #[test]
+    fn test_usd_since() {
+        let mut totals = StatementTotals::default();
+        totals.record_interest(AssetType::USDC, 5_000_000);
+        let start = totals;
+
+        totals.record_interest(AssetType::USDC, 2_000_000);
+        totals.record_fee(AssetType::USDC, 300_000);
+        // Only USDC charged: no EUR price needed
+        assert_eq!(
+            totals.usd_since(&start, None).unwrap(),
+            DebtCharges {
+                interest: 2_000_000,
+                fees: 300_000,
+            }
+        );
+
+        totals.record_interest(AssetType::EURC, 1_000_000);
+        totals.record_fee(AssetType::SOL, 1_000_000);
+        assert!(totals.usd_since(&start, None).is_err());
+        assert_eq!(
+            totals.usd_since(&start, EUR_USD).unwrap(),
+            DebtCharges {
+                interest: 3_080_000,
+                fees: 300_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_staking_yield_usd() {
+        // 10 mSOL gaining 0.01 SOL each, at $100
+        assert_eq!(
+            staking_yield_usd(
+                10 * LAMPORTS_PER_SOL,
+                1_100_000_000,
+                1_110_000_000,
+                100 * USD_MULTIPLIER
+            )
+            .unwrap(),
+            10 * USD_MULTIPLIER
+        );
+        // No rate at the start of the period
+        assert_eq!(
+            staking_yield_usd(
+                10 * LAMPORTS_PER_SOL,
+                0,
+                1_110_000_000,
+                100 * USD_MULTIPLIER
+            )
+            .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_period() {
+        let mut ledger = StatementLedger {
+            position: Pubkey::default(),
+            owner: Pubkey::default(),
+            period_start: 1_000,
+            totals_at_start: StatementTotals::default(),
+            gad_liquidated_at_start: 0,
+            msol_rate_at_start: 1_100_000_000,
+            statements: 0,
+            bump: 0,
+        };
+        assert!(ledger
+            .require_period_over(1_000 + STATEMENT_PERIOD - 1)
+            .is_err());
+        ledger
+            .require_period_over(1_000 + STATEMENT_PERIOD)
+            .unwrap();
+
+        let now = 1_000 + STATEMENT_PERIOD;
+        ledger.roll(StatementTotals::default(), 50, None, now);
+        assert_eq!(ledger.period_start, now);
+        assert_eq!(ledger.gad_liquidated_at_start, 50);
+        assert_eq!(ledger.msol_rate_at_start, 1_100_000_000);
+        assert_eq!(ledger.statements, 1);
+        assert!(ledger.require_period_over(now + 1).is_err());
+    }
+}
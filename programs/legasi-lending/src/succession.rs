@@ -0,0 +1,118 @@
+//! Position succession (dead-man switch)
+//!
+//! An owner can name a beneficiary who inherits the position if the owner goes
+//! silent. The owner proves liveness with `succession_check_in`. Once
+//! `inactivity_period` passes without one, the beneficiary can open a claim, and
+//! after `SUCCESSION_CHALLENGE_WINDOW` without a check-in complete it. A check-in
+//! during the window cancels the claim.
+//!
+//! Positions are PDAs of their owner's key, so completing moves the position rather
+//! than rewriting its owner: collateral, debt, reputation and settings are copied to
+//! the beneficiary's position (which must not exist yet), the SOL and mSOL vault
+//! balances follow, and the old position is left empty. PDAs keyed by the old
+//! position (credit line, agent config, repayment schedule, leverage) are not carried
+//! over, and a position with open letters of credit can't be inherited until they are
+//! released.
+//!
+//! Flow:
+//! 1. Owner calls designate_beneficiary (and succession_check_in from time to time)
+//! 2. Beneficiary calls start_succession_claim after the inactivity period
+//! 3. Beneficiary calls complete_succession after the challenge window
+
+use anchor_lang::prelude::*;
+use legasi_core::errors::LegasiError;
+
+/// Time the owner has to check in once a claim is opened (7 days)
+pub const SUCCESSION_CHALLENGE_WINDOW: i64 = 7 * 86_400;
+
+/// Shortest inactivity period an owner can set (30 days)
+pub const MIN_INACTIVITY_PERIOD: i64 = 30 * 86_400;
+
+/// Beneficiary designation of a position
+#[account]
+#[derive(InitSpace)]
+pub struct Succession {
+    pub position: Pubkey,
+    pub beneficiary: Pubkey,
+    /// Silence (seconds) after which the beneficiary can claim
+    pub inactivity_period: i64,
+    pub last_check_in: i64,
+    /// When the beneficiary opened a claim (0 = none)
+    pub claim_started_at: i64,
+    pub bump: u8,
+}
+
+impl Succession {
+    /// Record the owner's liveness, cancelling any open claim
+    pub fn check_in(&mut self, now: i64) {
+        self.last_check_in = now;
+        self.claim_started_at = 0;
+    }
+
+    /// Open the beneficiary's claim once the owner has been silent long enough
+    pub fn start_claim(&mut self, now: i64) -> Result<()> {
+        require!(
+            self.claim_started_at == 0
+                && now.saturating_sub(self.last_check_in) >= self.inactivity_period,
+            LegasiError::SuccessionNotReady
+        );
+        self.claim_started_at = now;
+        Ok(())
+    }
+
+    /// Fails unless a claim has run through the challenge window
+    pub fn require_claimable(&self, now: i64) -> Result<()> {
+        require!(
+            self.claim_started_at != 0
+                && now.saturating_sub(self.claim_started_at) >= SUCCESSION_CHALLENGE_WINDOW,
+            LegasiError::SuccessionNotReady
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn succession() -> Succession {
+        Succession {
+            position: Pubkey::default(),
+            beneficiary: Pubkey::default(),
+            inactivity_period: MIN_INACTIVITY_PERIOD,
+            last_check_in: 1_000,
+            claim_started_at: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_claim_after_inactivity() {
+        let mut s = succession();
+        assert!(s.start_claim(1_000 + MIN_INACTIVITY_PERIOD - 1).is_err());
+        assert!(s.require_claimable(1_000 + MIN_INACTIVITY_PERIOD).is_err());
+
+        let opened = 1_000 + MIN_INACTIVITY_PERIOD;
+        s.start_claim(opened).unwrap();
+        assert!(s.start_claim(opened + 1).is_err());
+        assert!(s
+            .require_claimable(opened + SUCCESSION_CHALLENGE_WINDOW - 1)
+            .is_err());
+        s.require_claimable(opened + SUCCESSION_CHALLENGE_WINDOW)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_check_in_cancels_claim() {
+        let mut s = succession();
+        let opened = 1_000 + MIN_INACTIVITY_PERIOD;
+        s.start_claim(opened).unwrap();
+        s.check_in(opened + 1);
+        assert!(s
+            .require_claimable(opened + SUCCESSION_CHALLENGE_WINDOW)
+            .is_err());
+        // The inactivity period restarts from the check-in
+        assert!(s.start_claim(opened + MIN_INACTIVITY_PERIOD).is_err());
+        s.start_claim(opened + 1 + MIN_INACTIVITY_PERIOD).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+//! Voting power snapshots
+//!
+//! Anyone can record their protocol participation for the current voting epoch in a
+//! `VotingPower` account: the USDC value of their bUSDC plus their position's net
+//! deposits (collateral minus debt). An external governance program, or a future
+//! token, weights votes by reading these accounts at an epoch instead of trusting an
+//! off-chain indexer.
+//!
+//! A snapshot can only be taken during its own epoch and only once per owner, so
+//! it can't be backdated or refreshed after the fact. It is a point-in-time read:
+//! governance that wants to discount balances held only for the snapshot should
+//! weight it with time-based measures like points.
+//!
+//! Flow:
+//! 1. Owner calls snapshot_voting_power with the current epoch
+//! 2. Governance reads `VotingPower` at `[VOTING_POWER_SEED, owner, epoch]`
+
+use anchor_lang::prelude::*;
+use legasi_core::constants::SECONDS_PER_DAY;
+
+/// Length of a voting epoch (seconds)
+pub const VOTING_EPOCH_DURATION: i64 = 7 * SECONDS_PER_DAY; // 1 week
+
+/// Participation of one owner at an epoch
+#[account]
+#[derive(InitSpace)]
+pub struct VotingPower {
+    pub owner: Pubkey,
+    pub epoch: u32,
+    /// bUSDC held in the snapshotted token account
+    pub lp_shares: u64,
+    /// `lp_shares` redeemed at the pool's rate, USDC (6 decimals)
+    pub lp_value: u64,
+    /// Collateral minus debt of the owner's position, USD (6 decimals), 0 if none
+    pub net_deposits_usd: u64,
+    /// `lp_value + net_deposits_usd`
+    pub voting_power: u64,
+    pub snapshot_at: i64,
+    pub bump: u8,
+}
+
+/// Voting epoch `now` falls in (0 before the Unix epoch)
+pub fn voting_epoch(now: i64) -> u32 {
+    (now.max(0) / VOTING_EPOCH_DURATION).min(u32::MAX as i64) as u32
+}
+
+/// Net deposits of a position, USD (6 decimals): an underwater position counts as zero
+pub fn net_deposits_usd(collateral_usd: u64, debt_usd: u64) -> u64 {
+    collateral_usd.saturating_sub(debt_usd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_voting_epoch() {
+        assert_eq!(voting_epoch(0), 0);
+        assert_eq!(voting_epoch(VOTING_EPOCH_DURATION - 1), 0);
+        assert_eq!(voting_epoch(VOTING_EPOCH_DURATION), 1);
+        assert_eq!(voting_epoch(10 * VOTING_EPOCH_DURATION + 5), 10);
+        assert_eq!(voting_epoch(-5), 0);
+        assert_eq!(voting_epoch(i64::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn test_net_deposits() {
+        // $1,000 collateral against $400 debt
+        assert_eq!(net_deposits_usd(1_000_000_000, 400_000_000), 600_000_000);
+        assert_eq!(net_deposits_usd(300_000_000, 400_000_000), 0);
+    }
+}
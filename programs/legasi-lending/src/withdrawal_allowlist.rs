@@ -0,0 +1,144 @@
+//! Withdrawal allowlist
+//!
+//! An owner can pin where collateral may be withdrawn to. With a non-empty allowlist,
+//! withdraw_sol only pays lamports to (or wraps wSOL for) a listed wallet, and
+//! withdraw_staked only pays mSOL, the collateral that carries staking yield, to a
+//! token account a listed wallet owns.
+//!
+//! A list set while none is in force applies at once. Changing or clearing a list in
+//! force is only proposed and applies after `ALLOWLIST_CHANGE_DELAY`, so a stolen
+//! owner key can't redirect withdrawals before the owner sees the change and cancels it.
+//!
+//! Flow:
+//! 1. Owner calls set_withdrawal_allowlist (applies at once while no list is in force)
+//! 2. Later calls propose a new list; apply_withdrawal_allowlist after the delay,
+//!    or cancel_withdrawal_allowlist_change
+//! 3. Withdrawals pass the allowlist PDA, and fail for other destinations
+
+use anchor_lang::prelude::*;
+use legasi_core::errors::LegasiError;
+
+/// Delay before a proposed allowlist change applies (48 hours)
+pub const ALLOWLIST_CHANGE_DELAY: i64 = 48 * 3600;
+
+/// Max wallets on an allowlist
+pub const MAX_WITHDRAWAL_DESTINATIONS: usize = 4;
+
+/// Wallets a position's collateral may be withdrawn to
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalAllowlist {
+    pub position: Pubkey,
+    /// Allowed wallets (empty = any destination)
+    #[max_len(4)]
+    pub destinations: Vec<Pubkey>,
+    /// Proposed replacement for `destinations`
+    #[max_len(4)]
+    pub pending: Vec<Pubkey>,
+    /// When `pending` can be applied (0 = no change proposed)
+    pub pending_after: i64,
+    pub bump: u8,
+}
+
+impl WithdrawalAllowlist {
+    /// Whether collateral may be withdrawn to `wallet`
+    pub fn allows(&self, wallet: &Pubkey) -> bool {
+        self.destinations.is_empty() || self.destinations.contains(wallet)
+    }
+
+    /// Propose `destinations` to replace the list after the delay
+    pub fn propose(&mut self, destinations: Vec<Pubkey>, now: i64) -> Result<()> {
+        require!(
+            destinations.len() <= MAX_WITHDRAWAL_DESTINATIONS,
+            LegasiError::InvalidAmount
+        );
+        self.pending = destinations;
+        self.pending_after = now
+            .checked_add(ALLOWLIST_CHANGE_DELAY)
+            .ok_or(LegasiError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Apply the proposed list once its delay has passed
+    pub fn apply(&mut self, now: i64) -> Result<()> {
+        require!(
+            self.pending_after != 0 && now >= self.pending_after,
+            LegasiError::AllowlistChangeLocked
+        );
+        self.destinations = std::mem::take(&mut self.pending);
+        self.pending_after = 0;
+        Ok(())
+    }
+
+    /// Drop the proposed list
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+        self.pending_after = 0;
+    }
+}
+
+/// Load the allowlist PDA if the owner has set one
+pub fn load_withdrawal_allowlist(info: &AccountInfo) -> Result<Option<WithdrawalAllowlist>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(WithdrawalAllowlist::try_deserialize(
+        &mut &info.try_borrow_data()?[..],
+    )?))
+}
+
+/// Fails unless the position's allowlist, if any, allows `wallet`
+pub fn require_allowed_destination(allowlist: &AccountInfo, wallet: &Pubkey) -> Result<()> {
+    if let Some(allowlist) = load_withdrawal_allowlist(allowlist)? {
+        require!(
+            allowlist.allows(wallet),
+            LegasiError::WithdrawalDestinationNotAllowed
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist(destinations: Vec<Pubkey>) -> WithdrawalAllowlist {
+        WithdrawalAllowlist {
+            position: Pubkey::default(),
+            destinations,
+            pending: Vec::new(),
+            pending_after: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_allows() {
+        let cold = Pubkey::new_unique();
+        assert!(allowlist(vec![]).allows(&cold));
+        assert!(allowlist(vec![cold]).allows(&cold));
+        assert!(!allowlist(vec![cold]).allows(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_timelocked_change() {
+        let cold = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+        let mut list = allowlist(vec![cold]);
+        assert!(list.apply(1_000).is_err());
+
+        list.propose(vec![attacker], 1_000).unwrap();
+        assert!(list.apply(1_000 + ALLOWLIST_CHANGE_DELAY - 1).is_err());
+        assert!(!list.allows(&attacker));
+
+        // The owner cancels in time
+        list.cancel();
+        assert!(list.apply(1_000 + ALLOWLIST_CHANGE_DELAY).is_err());
+
+        // Clearing the list is timelocked too
+        list.propose(vec![], 2_000).unwrap();
+        list.apply(2_000 + ALLOWLIST_CHANGE_DELAY).unwrap();
+        assert!(list.allows(&attacker));
+        assert!(list.propose(vec![cold; 5], 3_000).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 
 /// x402 Payment Protocol Integration
 ///
@@ -10,6 +11,11 @@ use anchor_lang::prelude::*;
 /// 2. Agent calls x402_pay with the payment request
 /// 3. Legasi verifies, borrows if needed, and sends payment
 /// 4. Agent retries API call with payment proof
+///
+/// Paywalls: a provider registers a `ServiceListing` (price, recipient, access
+/// period). Paying it through x402_pay creates the receipt for
+/// `listing_access_id(listing, payer, period)`, so the provider grants access by
+/// checking that receipt PDA exists for the current period.
 
 /// x402 payment request (parsed from 402 response)
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -34,6 +40,62 @@ impl X402PaymentRequest {
     }
 }
 
+/// Paid access window bounds for a listing
+pub const MIN_ACCESS_PERIOD: i64 = 60;
+pub const MAX_ACCESS_PERIOD: i64 = 365 * 24 * 60 * 60;
+
+/// API registered by a provider as an x402 paywall
+#[account]
+#[derive(InitSpace)]
+pub struct ServiceListing {
+    /// Can update the listing
+    pub provider: Pubkey,
+    /// Provider-chosen ID (e.g. hash of the endpoint URL)
+    pub service_id: [u8; 32],
+    /// Owner of the token account payments go to
+    pub recipient: Pubkey,
+    /// Asset type payments are made in (USDC or EURC)
+    pub asset: u8,
+    /// Price of one access period, smallest unit
+    pub price: u64,
+    /// Length of the window one payment unlocks (seconds)
+    pub access_period: i64,
+    pub is_active: bool,
+    pub bump: u8,
+}
+
+impl ServiceListing {
+    /// Index of the access window containing `now`
+    pub fn period_at(&self, now: i64) -> u64 {
+        (now.max(0) / self.access_period) as u64
+    }
+
+    /// End of the access window containing `now`
+    pub fn period_end(&self, now: i64) -> i64 {
+        (self.period_at(now) as i64 + 1) * self.access_period
+    }
+
+    /// Checks a payment request against the listing's terms for the current window
+    pub fn matches(
+        &self,
+        listing: &Pubkey,
+        payer: &Pubkey,
+        request: &X402PaymentRequest,
+        now: i64,
+    ) -> bool {
+        self.is_active
+            && request.recipient == self.recipient
+            && request.amount == self.price
+            && request.asset == self.asset
+            && request.payment_id == listing_access_id(listing, payer, self.period_at(now))
+    }
+}
+
+/// Payment ID (and so receipt PDA seed) of a payer's access to a listing for one window
+pub fn listing_access_id(listing: &Pubkey, payer: &Pubkey, period: u64) -> [u8; 32] {
+    hashv(&[listing.as_ref(), payer.as_ref(), &period.to_le_bytes()]).to_bytes()
+}
+
 /// x402 payment receipt (proof of payment)
 #[account]
 #[derive(InitSpace)]
@@ -50,6 +112,8 @@ pub struct X402Receipt {
     pub paid_at: i64,
     /// Transaction signature (for verification)
     pub tx_signature: [u8; 64],
+    /// Integrator's invoice / memo hash, if given
+    pub reference: Option<[u8; 32]>,
     pub bump: u8,
 }
 
@@ -73,3 +137,49 @@ pub fn verify_x402_request(request: &X402PaymentRequest, _signature: &[u8]) -> b
     // Production: verify cryptographic signature from service
     request.amount > 0 && request.amount < 1_000_000_000_000 // Max 1M USDC
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing() -> ServiceListing {
+        ServiceListing {
+            provider: Pubkey::new_unique(),
+            service_id: [1; 32],
+            recipient: Pubkey::new_unique(),
+            asset: 0,
+            price: 1_000_000,
+            access_period: 3_600,
+            is_active: true,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_listing_periods() {
+        let listing = listing();
+        assert_eq!(listing.period_at(3_599), 0);
+        assert_eq!(listing.period_at(3_600), 1);
+        assert_eq!(listing.period_end(4_000), 7_200);
+    }
+
+    #[test]
+    fn test_listing_matches_current_window_only() {
+        let listing = listing();
+        let (key, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut request = X402PaymentRequest {
+            recipient: listing.recipient,
+            amount: listing.price,
+            asset: listing.asset,
+            payment_id: listing_access_id(&key, &payer, 1),
+            expires_at: 7_200,
+            callback_url_hash: [0; 32],
+        };
+        assert!(listing.matches(&key, &payer, &request, 4_000));
+        // Last window's ID, another payer, or a different price don't match
+        assert!(!listing.matches(&key, &payer, &request, 7_200));
+        assert!(!listing.matches(&key, &Pubkey::new_unique(), &request, 4_000));
+        request.amount -= 1;
+        assert!(!listing.matches(&key, &payer, &request, 4_000));
+    }
+}
@@ -1,9 +1,14 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
 use anchor_lang::solana_program::system_instruction;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
-use legasi_core::{constants::*, errors::LegasiError, events::*, state::*};
+use legasi_core::{
+    caps::AssetCaps, constants::*, decimals::sol_to_usd, errors::LegasiError, events::*,
+    jupiter_cpi, program::LegasiCore, seeds::*, state::*, totals,
+};
+use legasi_lending::DeleverageSwap;
+use legasi_lp::{program::LegasiLp, LpPool};
 
 declare_id!("AVATHjGrdQ1KqtjHQ4gwRcuAYjwwScwgPsujLDpiA2g3");
 
@@ -23,9 +28,18 @@ pub struct LeveragePosition {
     pub is_long: bool,
     pub is_active: bool,
     pub opened_at: i64,
+    /// When any keeper may unwind the position at market (0 = no expiry)
+    pub expires_at: i64,
     pub bump: u8,
 }
 
+impl LeveragePosition {
+    /// Whether a fixed-term position has reached its expiry
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+}
+
 #[program]
 pub mod legasi_leverage {
     use super::*;
@@ -33,38 +47,84 @@ pub mod legasi_leverage {
     /// Open a leveraged long position
     /// Example: 5 SOL at 3x = deposit 5 SOL, borrow USDC, swap to SOL, deposit again (loop)
     /// Result: 15 SOL exposure, 10 SOL worth of USDC debt
+    /// `max_duration` (seconds, 0 = none) makes it fixed-term: once it passes, any
+    /// keeper can unwind the position with `unwind_expired`
     pub fn open_long(
         ctx: Context<OpenLong>,
         initial_collateral: u64,
         leverage_multiplier: u8,
         min_collateral_received: u64, // Slippage protection
+        max_duration: i64,
     ) -> Result<()> {
         require!(initial_collateral > 0, LegasiError::InvalidAmount);
+        require!(max_duration >= 0, LegasiError::InvalidAmount);
         require!(
             leverage_multiplier >= 2 && leverage_multiplier <= 5,
             LegasiError::InvalidAmount
         );
+        // Opening deposits collateral and borrows against it; closes stay open when paused
+        ctx.accounts
+            .protocol
+            .require_not_paused(PAUSE_DEPOSITS | PAUSE_BORROWS)?;
+        // Kill-switch: no new leverage while SOL is moving too fast (closes are never blocked)
+        require!(
+            ctx.accounts
+                .protocol
+                .allows_new_leverage(&ctx.accounts.sol_price_feed),
+            LegasiError::VolatilityTooHigh
+        );
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts
+            .sol_price_feed
+            .circuit_breaker
+            .require_clear(now)?;
 
-        let sol_price = ctx.accounts.sol_price_feed.price_usd_6dec;
+        let sol_price = ctx.accounts.sol_price_feed.get_checked_price(now)?;
 
         // Calculate amounts
         // For 3x leverage: borrow 2x of initial collateral value
         let borrow_multiplier = (leverage_multiplier - 1) as u64;
-        let collateral_value_usd = (initial_collateral as u128)
-            .checked_mul(sol_price as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(LAMPORTS_PER_SOL as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
+        let collateral_value_usd = sol_to_usd(initial_collateral, sol_price)?;
 
         let usdc_to_borrow = collateral_value_usd
             .checked_mul(borrow_multiplier)
             .ok_or(LegasiError::MathOverflow)?;
 
+        // The owner's debt cap on their lending position bounds leverage debt too
+        let eur_price = ctx
+            .accounts
+            .eur_price_feed
+            .as_ref()
+            .map(|feed| feed.get_checked_price(now))
+            .transpose()?;
+        let leverage_debt = ctx
+            .accounts
+            .position
+            .borrows
+            .iter()
+            .map(|b| b.amount.saturating_add(b.accrued_interest))
+            .fold(0u64, u64::saturating_add);
+        let total_debt_usd = ctx
+            .accounts
+            .lending_position
+            .debt_usd(eur_price)?
+            .saturating_add(leverage_debt)
+            .saturating_add(usdc_to_borrow);
+        ctx.accounts
+            .lending_position
+            .require_within_debt_cap(total_debt_usd)?;
+
         // Check liquidity
         require!(
-            ctx.accounts.usdc_vault.amount >= usdc_to_borrow,
+            ctx.accounts.lp_vault.amount >= usdc_to_borrow,
             LegasiError::InsufficientLiquidity
         );
+        AssetCaps::load(&ctx.accounts.sol_caps)?.require_supply(
+            ctx.accounts.protocol.total_sol_collateral,
+            initial_collateral,
+        )?;
+        AssetCaps::load(&ctx.accounts.usdc_caps)?
+            .require_borrow(ctx.accounts.lp_pool.total_borrowed, usdc_to_borrow)?;
 
         // 1. Transfer initial SOL collateral from user
         invoke(
@@ -80,22 +140,35 @@ pub mod legasi_leverage {
             ],
         )?;
 
-        // 2. Borrow USDC (sent to user for swap)
-        let protocol_bump = ctx.accounts.protocol.bump;
-        let seeds: &[&[u8]] = &[b"protocol", &[protocol_bump]];
-
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.usdc_vault.to_account_info(),
-                    to: ctx.accounts.user_usdc_account.to_account_info(),
-                    authority: ctx.accounts.protocol.to_account_info(),
-                },
-                &[seeds],
-            ),
+        // 2. Borrow USDC from the LP pool vault (sent to user for swap),
+        // the same path every lending borrow takes
+        legasi_lp::lend(
+            &ctx.accounts.lp_program.to_account_info(),
+            legasi_lp::cpi::accounts::Lend {
+                lp_pool: ctx.accounts.lp_pool.to_account_info(),
+                vault: ctx.accounts.lp_vault.to_account_info(),
+                destination: ctx.accounts.user_usdc_account.to_account_info(),
+                writer: ctx.accounts.protocol_writer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+            ctx.bumps.protocol_writer,
             usdc_to_borrow,
         )?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            totals::usd_delta(collateral_value_usd),
+            totals::usd_delta(usdc_to_borrow),
+        )?;
+        totals::report_sol_collateral(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            initial_collateral as i64,
+        )?;
 
         // 3. User swaps USDC → SOL off-chain (via Jupiter/Raydium)
         // 4. User deposits additional SOL via deposit_sol instruction
@@ -123,7 +196,15 @@ pub mod legasi_leverage {
         leverage_pos.entry_price_usd = sol_price;
         leverage_pos.is_long = true;
         leverage_pos.is_active = true;
-        leverage_pos.opened_at = Clock::get()?.unix_timestamp;
+        leverage_pos.opened_at = now;
+        leverage_pos.expires_at = if max_duration == 0 {
+            0
+        } else {
+            leverage_pos
+                .opened_at
+                .checked_add(max_duration)
+                .ok_or(LegasiError::MathOverflow)?
+        };
         leverage_pos.bump = ctx.bumps.leverage_position;
 
         // Update main position
@@ -169,10 +250,12 @@ pub mod legasi_leverage {
                 asset_type: AssetType::USDC,
                 amount: usdc_to_borrow,
                 accrued_interest: 0,
+                fixed_rate_bps: 0,
+                maturity: 0,
             });
         }
 
-        position.last_update = Clock::get()?.unix_timestamp;
+        position.last_update = now;
 
         emit!(LeverageOpened {
             position: ctx.accounts.position.key(),
@@ -199,20 +282,15 @@ pub mod legasi_leverage {
         let leverage_pos = &ctx.accounts.leverage_position;
         require!(leverage_pos.is_active, LegasiError::PositionNotFound);
 
-        let sol_price = ctx.accounts.sol_price_feed.price_usd_6dec;
+        let sol_price = ctx
+            .accounts
+            .sol_price_feed
+            .get_checked_price(Clock::get()?.unix_timestamp)?;
 
         // Calculate PnL
-        let entry_value_usd = (leverage_pos.total_collateral as u128)
-            .checked_mul(leverage_pos.entry_price_usd as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(LAMPORTS_PER_SOL as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
-
-        let current_value_usd = (leverage_pos.total_collateral as u128)
-            .checked_mul(sol_price as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(LAMPORTS_PER_SOL as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
+        let entry_value_usd =
+            sol_to_usd(leverage_pos.total_collateral, leverage_pos.entry_price_usd)?;
+        let current_value_usd = sol_to_usd(leverage_pos.total_collateral, sol_price)?;
 
         // PnL = current_value - entry_value - debt
         let pnl_usd: i64 = (current_value_usd as i64)
@@ -232,18 +310,33 @@ pub mod legasi_leverage {
             .checked_add(usdc_borrow.accrued_interest)
             .ok_or(LegasiError::MathOverflow)?;
 
-        // Transfer USDC from user to repay
+        // Transfer USDC from user back to the LP pool vault
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
                     from: ctx.accounts.user_usdc_account.to_account_info(),
-                    to: ctx.accounts.usdc_vault.to_account_info(),
+                    to: ctx.accounts.lp_vault.to_account_info(),
                     authority: ctx.accounts.owner.to_account_info(),
                 },
             ),
             total_owed,
         )?;
+        legasi_lp::report_borrowed(
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.lp_pool.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(total_owed),
+        )?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            0,
+            -totals::usd_delta(total_owed),
+        )?;
 
         // Update position - remove debt
         let position = &mut ctx.accounts.position;
@@ -276,6 +369,154 @@ pub mod legasi_leverage {
         Ok(())
     }
 
+    /// Unwind an expired fixed-term position at market (any keeper). The position's SOL
+    /// is sold through Jupiter (route accounts in remaining_accounts) into the keeper's
+    /// USDC account; the debt is repaid from it, the keeper keeps
+    /// `LEVERAGE_UNWIND_FEE_BPS` of the proceeds, and the rest of the USDC and any SOL
+    /// the route didn't sell go to the owner
+    pub fn unwind_expired<'info>(
+        ctx: Context<'_, '_, '_, 'info, UnwindExpired<'info>>,
+        swap: DeleverageSwap,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let leverage_pos = &ctx.accounts.leverage_position;
+        require!(leverage_pos.is_active, LegasiError::PositionNotFound);
+        require!(
+            leverage_pos.is_expired(now),
+            LegasiError::LeverageNotExpired
+        );
+
+        let total_owed = ctx
+            .accounts
+            .position
+            .borrows
+            .iter()
+            .find(|b| b.asset_type == AssetType::USDC)
+            .map_or(0, |b| b.amount.saturating_add(b.accrued_interest));
+
+        // Sell the SOL into the keeper's USDC account, signed by the vault
+        let position_key = ctx.accounts.position.key();
+        let vault_seeds: &[&[u8]] = &[
+            SOL_VAULT_SEED,
+            position_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        let sol_before = ctx.accounts.sol_vault.lamports();
+        let out_before = ctx.accounts.keeper_usdc_account.amount;
+        jupiter_cpi::swap(
+            &ctx.accounts.jupiter_program.to_account_info(),
+            ctx.remaining_accounts,
+            swap.route_data,
+            Some(ctx.accounts.sol_vault.key),
+            &[vault_seeds],
+        )?;
+        ctx.accounts.keeper_usdc_account.reload()?;
+        let received = jupiter_cpi::assert_min_received(
+            out_before,
+            ctx.accounts.keeper_usdc_account.amount,
+            swap.min_out_amount,
+        )?;
+        let sol_sold = jupiter_cpi::assert_max_spent(
+            sol_before,
+            ctx.accounts.sol_vault.lamports(),
+            sol_before,
+        )?;
+
+        // The sale must cover the debt and the keeper's fee
+        let keeper_fee =
+            (received as u128 * LEVERAGE_UNWIND_FEE_BPS as u128 / BPS_DENOMINATOR as u128) as u64;
+        let surplus = received
+            .checked_sub(total_owed)
+            .and_then(|rest| rest.checked_sub(keeper_fee))
+            .ok_or(LegasiError::SlippageExceeded)?;
+
+        let keeper_transfer = |to: AccountInfo<'info>, amount: u64| {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.keeper_usdc_account.to_account_info(),
+                        to,
+                        authority: ctx.accounts.keeper.to_account_info(),
+                    },
+                ),
+                amount,
+            )
+        };
+        keeper_transfer(ctx.accounts.lp_vault.to_account_info(), total_owed)?;
+        keeper_transfer(ctx.accounts.owner_usdc_account.to_account_info(), surplus)?;
+
+        // SOL the route didn't sell goes back to the owner
+        let sol_left = ctx.accounts.sol_vault.lamports();
+        if sol_left > 0 {
+            invoke_signed(
+                &system_instruction::transfer(
+                    ctx.accounts.sol_vault.key,
+                    ctx.accounts.owner.key,
+                    sol_left,
+                ),
+                &[
+                    ctx.accounts.sol_vault.to_account_info(),
+                    ctx.accounts.owner.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[vault_seeds],
+            )?;
+        }
+
+        let collateral_usd = sol_to_usd(
+            sol_sold + sol_left,
+            ctx.accounts.sol_price_feed.get_checked_price(now)?,
+        )?;
+        legasi_lp::report_borrowed(
+            &ctx.accounts.lp_program.to_account_info(),
+            &ctx.accounts.lp_pool.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(total_owed),
+        )?;
+        totals::report(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -totals::usd_delta(collateral_usd),
+            -totals::usd_delta(total_owed),
+        )?;
+        totals::report_sol_collateral(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            -((sol_sold + sol_left) as i64),
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        position.borrows.retain(|b| b.asset_type != AssetType::USDC);
+        position
+            .collaterals
+            .retain(|c| c.asset_type != AssetType::SOL);
+        position.last_update = now;
+        ctx.accounts.leverage_position.is_active = false;
+
+        emit!(LeverageUnwound {
+            position: position_key,
+            owner: ctx.accounts.owner.key(),
+            keeper: ctx.accounts.keeper.key(),
+            sol_sold,
+            debt_repaid: total_owed,
+            keeper_fee,
+            surplus,
+        });
+
+        msg!(
+            "Unwound expired leverage: sold {} SOL, repaid {} USDC",
+            sol_sold as f64 / LAMPORTS_PER_SOL as f64,
+            total_owed as f64 / USD_MULTIPLIER as f64
+        );
+        Ok(())
+    }
+
     /// Update collateral amount after swap (called after user swaps and deposits)
     pub fn update_leverage_collateral(
         ctx: Context<UpdateLeverageCollateral>,
@@ -306,39 +547,79 @@ pub struct OpenLong<'info> {
         init,
         payer = owner,
         space = 8 + LeveragePosition::INIT_SPACE,
-        seeds = [b"leverage", position.key().as_ref()],
+        seeds = [LEVERAGE_SEED, position.key().as_ref()],
         bump
     )]
     pub leverage_position: Account<'info, LeveragePosition>,
     #[account(
         mut,
-        seeds = [b"position", owner.key().as_ref()],
+        seeds = [POSITION_SEED, owner.key().as_ref()],
         bump = position.bump,
         has_one = owner
     )]
     pub position: Account<'info, Position>,
-    #[account(seeds = [b"protocol"], bump = protocol.bump)]
-    pub protocol: Account<'info, Protocol>,
+    /// Owner's lending position, whose debt cap also bounds leverage
+    #[account(
+        seeds = [POSITION_SEED, owner.key().as_ref()],
+        bump = lending_position.bump,
+        seeds::program = legasi_lending::ID
+    )]
+    pub lending_position: Box<Account<'info, legasi_lending::Position>>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
     /// CHECK: SOL vault PDA
     #[account(
         mut,
-        seeds = [b"sol_vault", position.key().as_ref()],
+        seeds = [SOL_VAULT_SEED, position.key().as_ref()],
         bump
     )]
     pub sol_vault: UncheckedAccount<'info>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    /// LP pool for USDC (owned by LP program - updated via CPI)
     #[account(
         mut,
-        seeds = [b"borrow_vault", usdc_mint.key().as_ref()],
-        bump
+        seeds = [LP_POOL_SEED, usdc_mint.key().as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
     )]
-    pub usdc_vault: Account<'info, TokenAccount>,
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// LP pool vault - the shared USDC liquidity
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, usdc_mint.key().as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_vault: Box<Account<'info, TokenAccount>>,
     pub usdc_mint: Account<'info, anchor_spl::token::Mint>,
     #[account(mut)]
     pub user_usdc_account: Account<'info, TokenAccount>,
-    #[account(seeds = [b"price", &[AssetType::SOL as u8]], bump)]
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
     pub sol_price_feed: Account<'info, PriceFeed>,
+    /// EURC price feed (owned by core), required once the lending position has EURC debt
+    #[account(constraint = eur_price_feed.asset_type == AssetType::EURC @ LegasiError::InvalidOracle)]
+    pub eur_price_feed: Option<Box<Account<'info, PriceFeed>>>,
+    /// CHECK: SOL's asset caps PDA, enforced only if it exists (see `legasi_core::caps`)
+    #[account(
+        seeds = [ASSET_CAPS_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_caps: UncheckedAccount<'info>,
+    /// CHECK: USDC's asset caps PDA, enforced only if it exists (see `legasi_core::caps`)
+    #[account(seeds = [ASSET_CAPS_SEED, usdc_mint.key().as_ref()], bump, seeds::program = legasi_core::ID)]
+    pub usdc_caps: UncheckedAccount<'info>,
     #[account(mut)]
     pub owner: Signer<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
@@ -347,45 +628,136 @@ pub struct OpenLong<'info> {
 pub struct ClosePosition<'info> {
     #[account(
         mut,
-        seeds = [b"leverage", position.key().as_ref()],
+        seeds = [LEVERAGE_SEED, position.key().as_ref()],
         bump = leverage_position.bump,
         has_one = owner
     )]
     pub leverage_position: Account<'info, LeveragePosition>,
     #[account(
         mut,
-        seeds = [b"position", owner.key().as_ref()],
+        seeds = [POSITION_SEED, owner.key().as_ref()],
         bump = position.bump,
         has_one = owner
     )]
     pub position: Account<'info, Position>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    /// LP pool for USDC (owned by LP program - updated via CPI)
     #[account(
         mut,
-        seeds = [b"borrow_vault", usdc_mint.key().as_ref()],
-        bump
+        seeds = [LP_POOL_SEED, usdc_mint.key().as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
     )]
-    pub usdc_vault: Account<'info, TokenAccount>,
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// LP pool vault - the shared USDC liquidity
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, usdc_mint.key().as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_vault: Box<Account<'info, TokenAccount>>,
     pub usdc_mint: Account<'info, anchor_spl::token::Mint>,
     #[account(mut)]
     pub user_usdc_account: Account<'info, TokenAccount>,
-    #[account(seeds = [b"price", &[AssetType::SOL as u8]], bump)]
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
     pub sol_price_feed: Account<'info, PriceFeed>,
     #[account(mut)]
     pub owner: Signer<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UnwindExpired<'info> {
+    #[account(
+        mut,
+        seeds = [LEVERAGE_SEED, position.key().as_ref()],
+        bump = leverage_position.bump,
+        has_one = owner
+    )]
+    pub leverage_position: Account<'info, LeveragePosition>,
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: SOL vault PDA
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, position.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: UncheckedAccount<'info>,
+    /// CHECK: PDA that signs protocol and LP pool updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    /// LP pool for USDC (owned by LP program - updated via CPI)
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, usdc_mint.key().as_ref()],
+        bump = lp_pool.bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    /// LP pool vault - the shared USDC liquidity
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, usdc_mint.key().as_ref()],
+        bump,
+        seeds::program = legasi_lp::ID
+    )]
+    pub lp_vault: Box<Account<'info, TokenAccount>>,
+    pub usdc_mint: Account<'info, anchor_spl::token::Mint>,
+    /// Receives the swap output; the repayment and the owner's surplus are paid from it
+    #[account(mut, token::mint = usdc_mint, token::authority = keeper)]
+    pub keeper_usdc_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = usdc_mint, token::authority = owner)]
+    pub owner_usdc_account: Box<Account<'info, TokenAccount>>,
+    /// SOL price feed (owned by core program, keyed by the wSOL mint)
+    #[account(
+        seeds = [PRICE_FEED_SEED, token::spl_token::native_mint::ID.as_ref()],
+        bump = sol_price_feed.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub sol_price_feed: Account<'info, PriceFeed>,
+    /// CHECK: position owner, receives the SOL the route didn't sell
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    pub keeper: Signer<'info>,
+    /// CHECK: Jupiter program, checked in `jupiter_cpi::swap`
+    pub jupiter_program: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub lp_program: Program<'info, LegasiLp>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct UpdateLeverageCollateral<'info> {
     #[account(
         mut,
-        seeds = [b"leverage", position.key().as_ref()],
+        seeds = [LEVERAGE_SEED, position.key().as_ref()],
         bump = leverage_position.bump,
         has_one = owner
     )]
     pub leverage_position: Account<'info, LeveragePosition>,
     #[account(
-        seeds = [b"position", owner.key().as_ref()],
+        seeds = [POSITION_SEED, owner.key().as_ref()],
         bump = position.bump,
         has_one = owner
     )]
@@ -0,0 +1,18 @@
+//! Permissioned pools
+//!
+//! A pool created with `permissioned = true` only takes deposits from, and pays
+//! withdrawals to, LPs the admin has allowlisted (e.g., a fintech partner's KYC'd
+//! customers). Share accounting is the same as in an open pool: every deposit and
+//! withdraw path takes the LP's `[LP_ALLOWLIST_SEED, lp_pool, lp]` entry as an optional
+//! account, required only when the pool is permissioned. The flag is fixed at creation.
+
+use anchor_lang::prelude::*;
+
+/// Allowlisted LP of a permissioned pool
+#[account]
+#[derive(InitSpace)]
+pub struct LpAllowlistEntry {
+    pub lp_pool: Pubkey,
+    pub lp: Pubkey,
+    pub bump: u8,
+}
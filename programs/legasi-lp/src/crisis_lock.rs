@@ -0,0 +1,106 @@
+//! Crisis locks
+//!
+//! While utilization is at or above `CRISIS_UTILIZATION_BPS`, an LP can promise not
+//! to withdraw part of its shares for `MIN_LOCK_DAYS..=MAX_LOCK_DAYS` days. Locked
+//! shares keep their normal share of interest and, on top, split a
+//! `CRISIS_LOCK_BOOST_BPS` cut of the LP interest accrued while they are locked,
+//! taken from unlocked LPs. The shares sit in an escrow owned by the lock until it
+//! expires, so `withdraw` can't reach them.
+//!
+//! Flow:
+//! 1. LP calls lock_lp_shares during high utilization
+//! 2. Each repay's interest feeds the boost into `LpPool.lock_rebate_per_share`
+//! 3. Once `unlock_at` passes, anyone calls unlock_lp_shares: the shares go back to
+//!    the LP with the rebate, and the boost stops
+
+use anchor_lang::prelude::*;
+use legasi_core::constants::BPS_DENOMINATOR;
+
+/// Utilization at which LPs may lock (bps)
+pub const CRISIS_UTILIZATION_BPS: u64 = 9_000; // 90%
+
+/// Cut of the LP interest split among locked shares (bps)
+pub const CRISIS_LOCK_BOOST_BPS: u64 = 2_000; // 20%
+
+pub const MIN_LOCK_DAYS: u16 = 7;
+pub const MAX_LOCK_DAYS: u16 = 90;
+
+/// Fixed-point scale of `LpPool.lock_rebate_per_share`
+pub const REBATE_PRECISION: u128 = 1_000_000_000_000;
+
+/// An LP's locked shares in one pool
+#[account]
+#[derive(InitSpace)]
+pub struct LpLock {
+    pub owner: Pubkey,
+    pub lp_pool: Pubkey,
+    pub shares: u64,
+    pub unlock_at: i64,
+    /// `lock_rebate_per_share` already accounted for at lock time (scaled)
+    pub rebate_debt: u128,
+    pub bump: u8,
+}
+
+impl LpLock {
+    /// Rebate earned so far (underlying units)
+    pub fn rebate(&self, rebate_per_share: u128) -> u64 {
+        (self.shares as u128 * rebate_per_share / REBATE_PRECISION).saturating_sub(self.rebate_debt)
+            as u64
+    }
+}
+
+/// Split LP interest into (credited to every share, boost for locked shares)
+/// Nothing is diverted while no shares are locked
+pub fn split_lock_boost(lp_interest: u64, locked_shares: u64) -> (u64, u64) {
+    if locked_shares == 0 {
+        return (lp_interest, 0);
+    }
+    let boost =
+        (lp_interest as u128 * CRISIS_LOCK_BOOST_BPS as u128 / BPS_DENOMINATOR as u128) as u64;
+    (lp_interest - boost, boost)
+}
+
+/// Increase of `lock_rebate_per_share` for a `boost` split among `locked_shares`
+pub fn rebate_per_share_delta(boost: u64, locked_shares: u64) -> u128 {
+    if locked_shares == 0 {
+        return 0;
+    }
+    boost as u128 * REBATE_PRECISION / locked_shares as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boost_only_with_locked_shares() {
+        assert_eq!(split_lock_boost(1_000, 0), (1_000, 0));
+        assert_eq!(split_lock_boost(1_000, 500), (800, 200));
+    }
+
+    #[test]
+    fn test_rebate_since_lock() {
+        // 200 split over 500 locked shares
+        let index = rebate_per_share_delta(200, 500);
+
+        // Locked from the start: all of it
+        let early = LpLock {
+            owner: Pubkey::default(),
+            lp_pool: Pubkey::default(),
+            shares: 500,
+            unlock_at: 0,
+            rebate_debt: 0,
+            bump: 0,
+        };
+        assert_eq!(early.rebate(index), 200);
+
+        // Locked after that accrual: nothing from it
+        let late = LpLock {
+            rebate_debt: 100 * index / REBATE_PRECISION,
+            shares: 100,
+            ..early
+        };
+        assert_eq!(late.rebate(index), 0);
+        assert_eq!(late.rebate(index + rebate_per_share_delta(60, 600)), 10);
+    }
+}
@@ -1,12 +1,46 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::metadata::{
+    create_metadata_accounts_v3, mpl_token_metadata::types::DataV2, CreateMetadataAccountsV3,
+    Metadata,
+};
+use anchor_spl::token::{self, Burn, CloseAccount, Mint, MintTo, Token, TokenAccount, Transfer};
 
-use legasi_core::{constants::*, errors::LegasiError, events::*, state::Protocol};
-// Note: LpPool defined locally to avoid cross-program ownership issues
+use legasi_core::{
+    cctp,
+    constants::*,
+    errors::LegasiError,
+    events::*,
+    interest::{
+        calculate_borrow_rate, calculate_insurance_fee, calculate_protocol_fee, grow_borrow_index,
+        BORROW_INDEX_PRECISION, PROTOCOL_FEE_BPS,
+    },
+    program::LegasiCore,
+    seeds::*,
+    state::{Borrowable, OutflowLimiter, Protocol},
+    totals,
+};
+
+pub mod allowlist;
+pub mod crisis_lock;
+pub mod locked_deposit;
+pub mod rewards;
+pub mod savings;
+pub mod surplus_auction;
+pub mod withdraw_queue;
+pub use allowlist::*;
+pub use crisis_lock::*;
+pub use locked_deposit::*;
+pub use rewards::*;
+pub use savings::*;
+pub use surplus_auction::*;
+pub use withdraw_queue::*;
 
 declare_id!("CTwY4VSeueesSBc95G38X3WJYPriJEzyxjcCaZAc5LbY");
 
-/// LP Pool state - defined locally for proper program ownership
+/// LP Pool state - owned by this program
+/// Lending and flash import this type (via the `cpi` feature) rather than
+/// redeclaring it, so `Account<LpPool>` checks the right owner and layout
 #[account]
 #[derive(InitSpace)]
 pub struct LpPool {
@@ -16,16 +50,343 @@ pub struct LpPool {
     pub total_shares: u64,
     pub total_borrowed: u64,
     pub interest_earned: u64,
+    pub outflow_limiter: OutflowLimiter,
+    /// Emergency utilization cap: new borrows and flash loans stop above it (bps, 0 = disabled)
+    pub max_utilization_bps: u16,
+    /// Shares in crisis locks (see `crisis_lock`)
+    pub locked_shares: u64,
+    /// Boosted interest per locked share, scaled by `REBATE_PRECISION`
+    pub lock_rebate_per_share: u128,
+    /// Only allowlisted LPs may deposit and withdraw (see `allowlist`), fixed at creation
+    pub permissioned: bool,
+    /// Written-off principal the insurance fund didn't cover, absorbed by LPs, less what
+    /// `cover_bad_debt` has paid back
+    pub bad_debt: u64,
+    /// Growth of one unit borrowed since the pool opened, scaled by
+    /// `BORROW_INDEX_PRECISION`. Borrows snapshot it and owe the growth since
+    pub borrow_index: u128,
+    /// `borrow_index` is current as of here
+    pub index_updated_at: i64,
+    /// Queued LP withdrawals (see `withdraw_queue`)
+    pub withdraw_queue: WithdrawQueue,
+    /// Bonus weight of time-locked deposits (see `locked_deposit`)
+    pub locked_deposit_weight: u64,
+    /// Boosted interest per unit of bonus weight, scaled by `REBATE_PRECISION`
+    pub deposit_boost_per_weight: u128,
+    /// Fee switch: `PROTOCOL_FEE_BPS` of repaid interest goes to the treasury
+    pub protocol_fee_enabled: bool,
+    /// Protocol fees in the vault (uncounted) not claimed to the treasury yet
+    pub protocol_fees_owed: u64,
+    /// Cut of repaid interest diverted to the pool's reserves (bps)
+    pub reserve_factor_bps: u16,
+    /// Reserves in the vault (uncounted) not swept to the reserve vault yet
+    pub reserves_pending: u64,
+    /// Insurance cut of interest and lock penalties in the vault (uncounted) not swept
+    /// to the insurance vault yet
+    pub insurance_pending: u64,
     pub bump: u8,
 }
 
+impl LpPool {
+    /// LP shares minted for a deposit
+    /// If first deposit: 1:1
+    /// Otherwise: shares = amount * total_shares / total_deposits
+    pub fn shares_for_deposit(&self, amount: u64) -> Result<u64> {
+        if self.total_shares == 0 {
+            return Ok(amount);
+        }
+        Ok((amount as u128)
+            .checked_mul(self.total_shares as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(self.total_deposits as u128)
+            .ok_or(LegasiError::MathOverflow)? as u64)
+    }
+
+    /// Underlying owed for `shares`: shares * total_deposits / total_shares
+    /// `total_deposits` includes what is lent out, so the rate is unaffected by borrows
+    pub fn tokens_for_shares(&self, shares: u64) -> Result<u64> {
+        require!(self.total_shares > 0, LegasiError::NoLpShares);
+        Ok((shares as u128)
+            .checked_mul(self.total_deposits as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(self.total_shares as u128)
+            .ok_or(LegasiError::MathOverflow)? as u64)
+    }
+
+    /// Deposits not currently lent out
+    pub fn available_liquidity(&self) -> u64 {
+        self.total_deposits.saturating_sub(self.total_borrowed)
+    }
+
+    /// Share of deposits lent out, in bps
+    pub fn utilization_bps(&self) -> u64 {
+        if self.total_deposits == 0 {
+            return 0;
+        }
+        let utilization = (self.total_borrowed as u128) * (BPS_DENOMINATOR as u128)
+            / (self.total_deposits as u128);
+        std::cmp::min(utilization, BPS_DENOMINATOR as u128) as u64
+    }
+
+    /// Borrow APR (bps) at the current utilization
+    pub fn borrow_rate_bps(&self) -> u64 {
+        calculate_borrow_rate(self.total_deposits, self.total_borrowed)
+    }
+
+    /// APR (bps) LPs earn at the current utilization, net of the insurance cut, the
+    /// protocol fee if switched on and the reserve factor
+    pub fn supply_rate_bps(&self) -> u64 {
+        let lp_share_bps = BPS_DENOMINATOR
+            - INSURANCE_FEE_BPS
+            - self.protocol_fee_bps()
+            - self.reserve_factor_bps as u64;
+        ((self.borrow_rate_bps() as u128)
+            * (self.utilization_bps() as u128)
+            * (lp_share_bps as u128)
+            / (BPS_DENOMINATOR as u128 * BPS_DENOMINATOR as u128)) as u64
+    }
+
+    /// Cut of repaid interest taken for the treasury (bps)
+    pub fn protocol_fee_bps(&self) -> u64 {
+        if self.protocol_fee_enabled {
+            PROTOCOL_FEE_BPS
+        } else {
+            0
+        }
+    }
+
+    /// Reserve cut of `interest_amount` of repaid interest
+    pub fn reserve_cut(&self, interest_amount: u64) -> u64 {
+        (interest_amount as u128 * self.reserve_factor_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    /// Protocol fee on `interest_amount` of repaid interest (0 while switched off)
+    pub fn protocol_fee(&self, interest_amount: u64) -> u64 {
+        if self.protocol_fee_enabled {
+            calculate_protocol_fee(interest_amount)
+        } else {
+            0
+        }
+    }
+
+    /// `borrow_index` grown at the current rate up to `now`. Utilization only changes
+    /// after `update_borrow_index`, so this is exact without writing the pool
+    pub fn borrow_index_at(&self, now: i64) -> Result<u128> {
+        let elapsed = now.saturating_sub(self.index_updated_at);
+        Ok(
+            grow_borrow_index(self.borrow_index, self.borrow_rate_bps(), elapsed)
+                .ok_or(LegasiError::MathOverflow)?,
+        )
+    }
+
+    /// Roll the index forward to `now` at the rate that applied since the last roll
+    /// Run before anything that changes `total_deposits` or `total_borrowed`
+    pub fn update_borrow_index(&mut self, now: i64) -> Result<()> {
+        self.borrow_index = self.borrow_index_at(now)?;
+        self.index_updated_at = std::cmp::max(self.index_updated_at, now);
+        Ok(())
+    }
+
+    /// Fail if lending `amount` more would take utilization above the emergency cap,
+    /// so the last `BPS_DENOMINATOR - max_utilization_bps` of deposits stays free for LP
+    /// exits. Borrowing resumes on its own once repays or deposits bring it back under
+    pub fn check_utilization(&self, amount: u64) -> Result<()> {
+        if self.max_utilization_bps == 0 {
+            return Ok(());
+        }
+        let borrowed = (self.total_borrowed as u128).saturating_add(amount as u128);
+        require!(
+            borrowed * (BPS_DENOMINATOR as u128)
+                <= (self.total_deposits as u128) * (self.max_utilization_bps as u128),
+            LegasiError::UtilizationPaused
+        );
+        Ok(())
+    }
+
+    /// Vault balance borrows may draw on: what is left once the withdrawal queue's
+    /// claim is set aside, so new debt can't take the liquidity queued LPs wait for
+    pub fn lendable(&self, vault_balance: u64) -> Result<u64> {
+        if self.withdraw_queue.queued_shares == 0 {
+            return Ok(vault_balance);
+        }
+        let queued = self.tokens_for_shares(self.withdraw_queue.queued_shares)?;
+        Ok(vault_balance.saturating_sub(queued))
+    }
+
+    /// Fail if the pool is permissioned and the LP didn't present its allowlist entry
+    /// (seeds are checked on the optional account itself)
+    pub fn check_lp_access(&self, entry: Option<&Account<LpAllowlistEntry>>) -> Result<()> {
+        require!(
+            !self.permissioned || entry.is_some(),
+            LegasiError::LpNotAllowlisted
+        );
+        Ok(())
+    }
+
+    /// Redeem up to `shares`, limited to liquidity that is both unlent and in the vault
+    /// Returns (shares burned, tokens paid). When liquidity is short only the shares
+    /// that can be paid are burned, rounding down so the pool never overpays
+    pub fn redeem(&self, shares: u64, vault_balance: u64) -> Result<(u64, u64)> {
+        let owed = self.tokens_for_shares(shares)?;
+        let liquid = std::cmp::min(self.available_liquidity(), vault_balance);
+        if owed <= liquid {
+            return Ok((shares, owed));
+        }
+
+        let shares = (liquid as u128)
+            .checked_mul(self.total_shares as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(self.total_deposits as u128)
+            .ok_or(LegasiError::MathOverflow)? as u64;
+        Ok((shares, self.tokens_for_shares(shares)?))
+    }
+
+    /// Insurance money a write-off of `bad_debt` can use: what the fund holds, up to
+    /// what is still in this pool's vault. The rest waits for `cover_bad_debt`
+    pub fn insurance_cover(&self, bad_debt: u64, insurance_fund: u64) -> u64 {
+        bad_debt.min(insurance_fund).min(self.insurance_pending)
+    }
+
+    /// Drop `principal` of unrecoverable borrows. The `covered` part is insurance money
+    /// already in the vault, so only the rest comes out of `total_deposits` (the LPs)
+    pub fn write_off(&mut self, principal: u64, covered: u64) -> Result<()> {
+        require!(covered <= principal, LegasiError::InvalidAmount);
+        let loss = principal - covered;
+        self.total_borrowed = self.total_borrowed.saturating_sub(principal);
+        self.total_deposits = self.total_deposits.saturating_sub(loss);
+        self.insurance_pending = self.insurance_pending.saturating_sub(covered);
+        self.bad_debt = self
+            .bad_debt
+            .checked_add(loss)
+            .ok_or(LegasiError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// CPI into `update_total_borrowed`, signed by the caller's protocol writer PDA
+pub fn report_borrowed<'info>(
+    lp_program: &AccountInfo<'info>,
+    lp_pool: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    borrowed_delta: i64,
+) -> Result<()> {
+    if borrowed_delta == 0 {
+        return Ok(());
+    }
+    cpi::update_total_borrowed(
+        CpiContext::new_with_signer(
+            lp_program.clone(),
+            cpi::accounts::UpdateTotalBorrowed {
+                lp_pool: lp_pool.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        borrowed_delta,
+    )
+}
+
+/// CPI into `write_off_bad_debt`, signed by the caller's protocol writer PDA
+pub fn write_off_bad_debt<'info>(
+    lp_program: &AccountInfo<'info>,
+    lp_pool: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    principal: u64,
+    covered: u64,
+) -> Result<()> {
+    if principal == 0 {
+        return Ok(());
+    }
+    cpi::write_off_bad_debt(
+        CpiContext::new_with_signer(
+            lp_program.clone(),
+            cpi::accounts::UpdateTotalBorrowed {
+                lp_pool: lp_pool.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        principal,
+        covered,
+    )
+}
+
+/// CPI into `set_aside_insurance`, signed by the caller's protocol writer PDA
+pub fn set_aside_insurance<'info>(
+    lp_program: &AccountInfo<'info>,
+    lp_pool: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    cpi::set_aside_insurance(
+        CpiContext::new_with_signer(
+            lp_program.clone(),
+            cpi::accounts::UpdateTotalBorrowed {
+                lp_pool: lp_pool.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        amount,
+    )
+}
+
+/// CPI into `lend`, signed by the caller's protocol writer PDA
+pub fn lend<'info>(
+    lp_program: &AccountInfo<'info>,
+    accounts: cpi::accounts::Lend<'info>,
+    writer_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    cpi::lend(
+        CpiContext::new_with_signer(
+            lp_program.clone(),
+            accounts,
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        amount,
+    )
+}
+
+/// CPI into `accrue_interest`, signed by the caller's protocol writer PDA
+pub fn accrue_interest<'info>(
+    lp_program: &AccountInfo<'info>,
+    lp_pool: &AccountInfo<'info>,
+    writer: &AccountInfo<'info>,
+    writer_bump: u8,
+    interest_amount: u64,
+    waived: u64,
+) -> Result<()> {
+    if interest_amount == 0 {
+        return Ok(());
+    }
+    cpi::accrue_interest(
+        CpiContext::new_with_signer(
+            lp_program.clone(),
+            cpi::accounts::AccrueInterest {
+                lp_pool: lp_pool.clone(),
+                writer: writer.clone(),
+            },
+            &[&[PROTOCOL_WRITER_SEED, &[writer_bump]]],
+        ),
+        interest_amount,
+        waived,
+    )
+}
+
 #[program]
 pub mod legasi_lp {
     use super::*;
 
     /// Initialize an LP pool for a borrowable asset (e.g., USDC → bUSDC)
-    /// Step 1: Create the pool PDA
-    pub fn initialize_pool(ctx: Context<InitializePool>) -> Result<()> {
+    /// Step 1: Create the pool PDA. A `permissioned` pool only serves allowlisted LPs
+    pub fn initialize_pool(ctx: Context<InitializePool>, permissioned: bool) -> Result<()> {
         let pool = &mut ctx.accounts.lp_pool;
         pool.borrowable_mint = ctx.accounts.borrowable_mint.key();
         pool.lp_token_mint = Pubkey::default(); // Set in step 2
@@ -33,40 +394,105 @@ pub mod legasi_lp {
         pool.total_shares = 0;
         pool.total_borrowed = 0;
         pool.interest_earned = 0;
+        pool.outflow_limiter =
+            OutflowLimiter::new(DEFAULT_MAX_OUTFLOW_BPS, DEFAULT_OUTFLOW_WINDOW_SLOTS);
+        pool.max_utilization_bps = DEFAULT_MAX_UTILIZATION_BPS;
+        pool.locked_shares = 0;
+        pool.lock_rebate_per_share = 0;
+        pool.permissioned = permissioned;
+        pool.bad_debt = 0;
+        pool.borrow_index = BORROW_INDEX_PRECISION;
+        pool.index_updated_at = Clock::get()?.unix_timestamp;
+        pool.withdraw_queue = WithdrawQueue::default();
+        pool.locked_deposit_weight = 0;
+        pool.deposit_boost_per_weight = 0;
+        pool.protocol_fee_enabled = false;
+        pool.protocol_fees_owed = 0;
+        pool.reserve_factor_bps = 0;
+        pool.reserves_pending = 0;
+        pool.insurance_pending = 0;
         pool.bump = ctx.bumps.lp_pool;
 
-        msg!("LP pool created for {}", ctx.accounts.borrowable_mint.key());
+        msg!(
+            "LP pool created for {} (permissioned: {})",
+            ctx.accounts.borrowable_mint.key(),
+            permissioned
+        );
         Ok(())
     }
 
     /// Initialize LP pool accounts (mint + vault)
-    /// Step 2: Create the LP token mint and vault
-    pub fn initialize_pool_accounts(ctx: Context<InitializePoolAccounts>) -> Result<()> {
+    /// Step 2: Create the LP token mint and vault, and register its
+    /// Metaplex metadata (e.g., "Legasi USDC" / "bUSDC") so wallets display it
+    pub fn initialize_pool_accounts(
+        ctx: Context<InitializePoolAccounts>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= MAX_TOKEN_NAME_LEN,
+            LegasiError::InvalidTokenMetadata
+        );
+        require!(
+            !symbol.is_empty() && symbol.len() <= MAX_TOKEN_SYMBOL_LEN,
+            LegasiError::InvalidTokenMetadata
+        );
+        require!(
+            uri.len() <= MAX_TOKEN_URI_LEN,
+            LegasiError::InvalidTokenMetadata
+        );
+
         let pool = &mut ctx.accounts.lp_pool;
         pool.lp_token_mint = ctx.accounts.lp_token_mint.key();
 
-        msg!("LP pool accounts initialized");
+        // LP pool PDA is mint authority, so it signs as update authority too
+        let pool_bump = ctx.accounts.lp_pool.bump;
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[LP_POOL_SEED, borrowable_mint.as_ref(), &[pool_bump]];
+
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    mint_authority: ctx.accounts.lp_pool.to_account_info(),
+                    payer: ctx.accounts.admin.to_account_info(),
+                    update_authority: ctx.accounts.lp_pool.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                &[seeds],
+            ),
+            DataV2 {
+                name: name.clone(),
+                symbol: symbol.clone(),
+                uri,
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true, // is_mutable: logo/URI can be updated later
+            true, // update_authority_is_signer
+            None,
+        )?;
+
+        msg!("LP pool accounts initialized: {} ({})", name, symbol);
         Ok(())
     }
 
     /// Deposit stablecoins, receive LP tokens (e.g., deposit USDC, get bUSDC)
     pub fn deposit(ctx: Context<LpDeposit>, amount: u64) -> Result<()> {
         require!(amount > 0, LegasiError::InvalidAmount);
-
-        let pool = &ctx.accounts.lp_pool;
+        ctx.accounts.protocol.require_not_paused(PAUSE_DEPOSITS)?;
+        ctx.accounts
+            .lp_pool
+            .check_lp_access(ctx.accounts.allowlist_entry.as_ref())?;
 
         // Calculate shares to mint
-        // If first deposit: 1:1
-        // Otherwise: shares = amount * total_shares / total_deposits
-        let shares_to_mint = if pool.total_shares == 0 {
-            amount
-        } else {
-            (amount as u128)
-                .checked_mul(pool.total_shares as u128)
-                .ok_or(LegasiError::MathOverflow)?
-                .checked_div(pool.total_deposits as u128)
-                .ok_or(LegasiError::MathOverflow)? as u64
-        };
+        let shares_to_mint = ctx.accounts.lp_pool.shares_for_deposit(amount)?;
 
         require!(shares_to_mint > 0, LegasiError::InvalidAmount);
 
@@ -86,7 +512,7 @@ pub mod legasi_lp {
         // Mint LP tokens to user
         let pool_bump = ctx.accounts.lp_pool.bump;
         let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
-        let seeds: &[&[u8]] = &[b"lp_pool", borrowable_mint.as_ref(), &[pool_bump]];
+        let seeds: &[&[u8]] = &[LP_POOL_SEED, borrowable_mint.as_ref(), &[pool_bump]];
 
         token::mint_to(
             CpiContext::new_with_signer(
@@ -103,6 +529,7 @@ pub mod legasi_lp {
 
         // Update pool state
         let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
         pool.total_deposits = pool
             .total_deposits
             .checked_add(amount)
@@ -127,26 +554,127 @@ pub mod legasi_lp {
         Ok(())
     }
 
+    /// Deposit USDC bridged via CCTP (e.g., burned on Base/Ethereum) on behalf of a beneficiary
+    /// The burn's mint_recipient must be the beneficiary's CCTP inbox; LP tokens are
+    /// minted to the beneficiary. CCTP receive accounts are passed via remaining_accounts
+    pub fn receive_cctp_deposit(
+        ctx: Context<ReceiveCctpDeposit>,
+        message: Vec<u8>,
+        attestation: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.protocol.require_not_paused(PAUSE_DEPOSITS)?;
+        ctx.accounts
+            .lp_pool
+            .check_lp_access(ctx.accounts.allowlist_entry.as_deref())?;
+        let burn = cctp::parse_burn_message(&message)?;
+        require_keys_eq!(
+            burn.mint_recipient,
+            ctx.accounts.cctp_inbox.key(),
+            LegasiError::InvalidCctpMessage
+        );
+        require!(burn.amount > 0, LegasiError::InvalidAmount);
+
+        // Mint the attested USDC into the inbox
+        let inbox_before = ctx.accounts.cctp_inbox.amount;
+        cctp::receive_message(
+            &ctx.accounts.message_transmitter.to_account_info(),
+            ctx.remaining_accounts,
+            message,
+            attestation,
+        )?;
+        ctx.accounts.cctp_inbox.reload()?;
+        cctp::assert_minted(inbox_before, ctx.accounts.cctp_inbox.amount, burn.amount)?;
+
+        let shares_to_mint = ctx.accounts.lp_pool.shares_for_deposit(burn.amount)?;
+        require!(shares_to_mint > 0, LegasiError::InvalidAmount);
+
+        // Move inbox to vault, then mint LP tokens to the beneficiary
+        let pool_bump = ctx.accounts.lp_pool.bump;
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[LP_POOL_SEED, borrowable_mint.as_ref(), &[pool_bump]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.cctp_inbox.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            burn.amount,
+        )?;
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    to: ctx.accounts.beneficiary_lp_token_account.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares_to_mint,
+        )?;
+
+        // Update pool state
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        pool.total_deposits = pool
+            .total_deposits
+            .checked_add(burn.amount)
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.total_shares = pool
+            .total_shares
+            .checked_add(shares_to_mint)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        emit!(LpDeposited {
+            depositor: ctx.accounts.beneficiary.key(),
+            pool: ctx.accounts.lp_pool.key(),
+            amount: burn.amount,
+            shares_minted: shares_to_mint,
+        });
+
+        msg!(
+            "CCTP deposit from domain {} (nonce {}): {} tokens, {} LP shares",
+            burn.source_domain,
+            burn.nonce,
+            burn.amount,
+            shares_to_mint
+        );
+        Ok(())
+    }
+
     /// Withdraw by burning LP tokens (e.g., burn bUSDC, get USDC + yield)
+    /// If part of the pool is lent out, the withdrawal is filled up to the available
+    /// liquidity and only the shares paid out are burned; retry once borrows are repaid,
+    /// or queue with request_withdraw. Closed while the queue holds requests
     pub fn withdraw(ctx: Context<LpWithdraw>, shares_amount: u64) -> Result<()> {
         require!(shares_amount > 0, LegasiError::InvalidAmount);
 
         let pool = &ctx.accounts.lp_pool;
         require!(pool.total_shares > 0, LegasiError::NoLpShares);
-
-        // Calculate tokens to return
-        // tokens = shares * total_deposits / total_shares
-        let tokens_to_return = (shares_amount as u128)
-            .checked_mul(pool.total_deposits as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(pool.total_shares as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
-
-        require!(tokens_to_return > 0, LegasiError::InvalidAmount);
         require!(
-            ctx.accounts.vault.amount >= tokens_to_return,
-            LegasiError::InsufficientLiquidity
+            pool.withdraw_queue.is_empty(),
+            LegasiError::WithdrawQueueActive
         );
+        pool.check_lp_access(ctx.accounts.allowlist_entry.as_ref())?;
+
+        // Pay out what is liquid now, the rest of the shares stay with the LP
+        let (shares_amount, tokens_to_return) =
+            pool.redeem(shares_amount, ctx.accounts.vault.amount)?;
+        require!(tokens_to_return > 0, LegasiError::InsufficientLiquidity);
+
+        // Enforce outflow rate limit
+        let current_slot = Clock::get()?.slot;
+        let tvl = ctx.accounts.lp_pool.total_deposits;
+        ctx.accounts
+            .lp_pool
+            .outflow_limiter
+            .record_outflow(tokens_to_return, tvl, current_slot)?;
 
         // Burn LP tokens from user
         token::burn(
@@ -164,7 +692,7 @@ pub mod legasi_lp {
         // Transfer tokens from vault to user
         let pool_bump = ctx.accounts.lp_pool.bump;
         let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
-        let seeds: &[&[u8]] = &[b"lp_pool", borrowable_mint.as_ref(), &[pool_bump]];
+        let seeds: &[&[u8]] = &[LP_POOL_SEED, borrowable_mint.as_ref(), &[pool_bump]];
 
         token::transfer(
             CpiContext::new_with_signer(
@@ -181,6 +709,7 @@ pub mod legasi_lp {
 
         // Update pool state
         let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
         pool.total_deposits = pool.total_deposits.saturating_sub(tokens_to_return);
         pool.total_shares = pool.total_shares.saturating_sub(shares_amount);
 
@@ -192,198 +721,2983 @@ pub mod legasi_lp {
         });
 
         msg!(
-            "Withdrew {} LP shares, received {} tokens",
+            "Withdrew {} LP shares, received {} tokens (utilization {} bps)",
             shares_amount,
-            tokens_to_return
+            tokens_to_return,
+            ctx.accounts.lp_pool.utilization_bps()
         );
         Ok(())
     }
 
-    /// Accrue interest to the pool (called by lending program)
-    pub fn accrue_interest(ctx: Context<AccrueInterest>, interest_amount: u64) -> Result<()> {
+    /// Credit interest repaid by a borrower to the pool (called by lending on repay)
+    /// The repayment is already in the vault; the LP share raises `total_deposits`
+    /// (and so the bUSDC rate), the insurance cut stays in the vault uncounted until
+    /// swept to the insurance vault, and is booked on `Protocol.insurance_fund` by
+    /// lending. With the fee switch on, the protocol fee stays in the vault too, owed
+    /// to the treasury, and so does the reserve cut until swept to the reserve vault.
+    /// `waived` is the part of `interest_amount` forgiven as a repay incentive: it never
+    /// reached the vault, so it comes out of the protocol fee
+    pub fn accrue_interest(
+        ctx: Context<AccrueInterest>,
+        interest_amount: u64,
+        waived: u64,
+    ) -> Result<()> {
         require!(interest_amount > 0, LegasiError::InvalidAmount);
 
-        // Calculate insurance fee (5%)
-        let insurance_fee = interest_amount
-            .checked_mul(INSURANCE_FEE_BPS)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(LegasiError::MathOverflow)?;
-
-        let lp_interest = interest_amount.saturating_sub(insurance_fee);
+        let insurance_fee = calculate_insurance_fee(interest_amount);
+        let protocol_fee = ctx.accounts.lp_pool.protocol_fee(interest_amount);
+        require!(waived <= protocol_fee, LegasiError::InvalidAmount);
+        let reserve_cut = ctx.accounts.lp_pool.reserve_cut(interest_amount);
+        let lp_interest = interest_amount
+            .saturating_sub(insurance_fee)
+            .saturating_sub(protocol_fee)
+            .saturating_sub(reserve_cut);
 
         // Update pool - interest increases total_deposits without changing shares
         // This automatically increases the value of each LP token
         let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        pool.protocol_fees_owed = pool
+            .protocol_fees_owed
+            .checked_add(protocol_fee - waived)
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.reserves_pending = pool
+            .reserves_pending
+            .checked_add(reserve_cut)
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.insurance_pending = pool
+            .insurance_pending
+            .checked_add(insurance_fee)
+            .ok_or(LegasiError::MathOverflow)?;
+        // Crisis-locked shares split a boost on top, and time-locked deposits the
+        // interest of their bonus weight, both left in the vault uncounted until
+        // they unlock
+        let (credited, lock_boost) = split_lock_boost(lp_interest, pool.locked_shares);
+        let (credited, deposit_boost) =
+            split_deposit_boost(credited, pool.total_shares, pool.locked_deposit_weight);
         pool.total_deposits = pool
             .total_deposits
-            .checked_add(lp_interest)
+            .checked_add(credited)
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.lock_rebate_per_share = pool
+            .lock_rebate_per_share
+            .checked_add(rebate_per_share_delta(lock_boost, pool.locked_shares))
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.deposit_boost_per_weight = pool
+            .deposit_boost_per_weight
+            .checked_add(rebate_per_share_delta(
+                deposit_boost,
+                pool.locked_deposit_weight,
+            ))
             .ok_or(LegasiError::MathOverflow)?;
         pool.interest_earned = pool
             .interest_earned
             .checked_add(lp_interest)
             .ok_or(LegasiError::MathOverflow)?;
 
-        // Update protocol insurance fund
-        let protocol = &mut ctx.accounts.protocol;
-        protocol.insurance_fund = protocol
-            .insurance_fund
-            .checked_add(insurance_fee)
-            .ok_or(LegasiError::MathOverflow)?;
-
         msg!(
-            "Accrued {} interest ({} to LPs, {} to insurance)",
+            "Accrued {} interest ({} to LPs, {} lock boost, {} deposit boost, {} to insurance, {} protocol fee, {} waived, {} to reserves)",
             interest_amount,
-            lp_interest,
-            insurance_fee
+            credited,
+            lock_boost,
+            deposit_boost,
+            insurance_fee,
+            protocol_fee - waived,
+            waived,
+            reserve_cut
         );
         Ok(())
     }
 
-    /// Get current exchange rate (tokens per LP share)
-    pub fn get_exchange_rate(ctx: Context<GetExchangeRate>) -> Result<u64> {
-        let pool = &ctx.accounts.lp_pool;
+    /// Lend `amount` out of the pool vault (called by lending on every borrow path)
+    /// The pool vault is the single source of borrowed liquidity; only a protocol
+    /// writer PDA can sign, see `legasi_core::totals`
+    pub fn lend(ctx: Context<Lend>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+        require!(
+            ctx.accounts.lp_pool.lendable(ctx.accounts.vault.amount)? >= amount,
+            LegasiError::InsufficientLiquidity
+        );
+        ctx.accounts.lp_pool.check_utilization(amount)?;
 
-        if pool.total_shares == 0 {
-            return Ok(USD_MULTIPLIER); // 1:1 for empty pool
+        // Borrows count against the pool outflow limit, same as LP withdrawals
+        let current_slot = Clock::get()?.slot;
+        let tvl = ctx.accounts.lp_pool.total_deposits;
+        ctx.accounts
+            .lp_pool
+            .outflow_limiter
+            .record_outflow(amount, tvl, current_slot)?;
+
+        let pool_bump = ctx.accounts.lp_pool.bump;
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[LP_POOL_SEED, borrowable_mint.as_ref(), &[pool_bump]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        pool.total_borrowed = pool
+            .total_borrowed
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        msg!("Lent {} (pool borrowed: {})", amount, pool.total_borrowed);
+        Ok(())
+    }
+
+    /// Move the pool's outstanding borrows (called by lending on repay)
+    /// Only a protocol writer PDA can sign, see `legasi_core::totals`.
+    /// New borrows count against the pool outflow limit, same as LP withdrawals
+    pub fn update_total_borrowed(
+        ctx: Context<UpdateTotalBorrowed>,
+        borrowed_delta: i64,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.lp_pool;
+        let clock = Clock::get()?;
+        pool.update_borrow_index(clock.unix_timestamp)?;
+        if borrowed_delta > 0 {
+            pool.check_utilization(borrowed_delta as u64)?;
+            let current_slot = clock.slot;
+            let tvl = pool.total_deposits;
+            pool.outflow_limiter
+                .record_outflow(borrowed_delta as u64, tvl, current_slot)?;
         }
+        pool.total_borrowed = totals::apply_delta(pool.total_borrowed, borrowed_delta);
 
-        let rate = (pool.total_deposits as u128)
-            .checked_mul(USD_MULTIPLIER as u128)
-            .ok_or(LegasiError::MathOverflow)?
-            .checked_div(pool.total_shares as u128)
-            .ok_or(LegasiError::MathOverflow)? as u64;
+        msg!(
+            "Pool borrowed: {} ({:+})",
+            pool.total_borrowed,
+            borrowed_delta
+        );
+        Ok(())
+    }
 
-        Ok(rate)
+    /// Write off borrows lending could not recover (a liquidated position left with
+    /// debt and no collateral). Only a protocol writer PDA can sign
+    pub fn write_off_bad_debt(
+        ctx: Context<UpdateTotalBorrowed>,
+        principal: u64,
+        covered: u64,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        pool.write_off(principal, covered)?;
+
+        emit!(BadDebtWrittenOff {
+            pool: pool.key(),
+            principal,
+            covered,
+        });
+
+        msg!(
+            "Wrote off {} of bad debt ({} covered by insurance)",
+            principal,
+            covered
+        );
+        Ok(())
     }
-}
 
-// ========== ACCOUNTS ==========
+    /// Set aside `amount` already in the pool vault for the insurance vault (a GAD
+    /// swap crank's insurance share), booked on `Protocol.insurance_fund` by the
+    /// caller. Only a protocol writer PDA can sign
+    pub fn set_aside_insurance(ctx: Context<UpdateTotalBorrowed>, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.insurance_pending = pool
+            .insurance_pending
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
 
-#[derive(Accounts)]
-pub struct InitializePool<'info> {
-    #[account(
-        init,
-        payer = admin,
-        space = 8 + LpPool::INIT_SPACE,
-        seeds = [b"lp_pool", borrowable_mint.key().as_ref()],
-        bump
-    )]
-    pub lp_pool: Account<'info, LpPool>,
-    pub borrowable_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub admin: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        msg!("Set aside {} for insurance", amount);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct InitializePoolAccounts<'info> {
-    #[account(
-        mut,
-        seeds = [b"lp_pool", lp_pool.borrowable_mint.as_ref()],
-        bump = lp_pool.bump
-    )]
-    pub lp_pool: Box<Account<'info, LpPool>>,
-    #[account(
-        init,
-        payer = admin,
-        mint::decimals = 6,
-        mint::authority = lp_pool,
-        seeds = [b"lp_token", lp_pool.borrowable_mint.as_ref()],
-        bump
-    )]
+    /// Set the pool outflow rate limit (admin only)
+    /// max_outflow_bps = 0 disables the limit
+    pub fn set_outflow_limit(
+        ctx: Context<SetOutflowLimit>,
+        max_outflow_bps: u16,
+        window_slots: u64,
+    ) -> Result<()> {
+        require!(
+            max_outflow_bps as u64 <= BPS_DENOMINATOR,
+            LegasiError::InvalidAmount
+        );
+        require!(window_slots > 0, LegasiError::InvalidAmount);
+
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.outflow_limiter = OutflowLimiter::new(max_outflow_bps, window_slots);
+
+        msg!(
+            "Outflow limit set: {} bps per {} slots",
+            max_outflow_bps,
+            window_slots
+        );
+        Ok(())
+    }
+
+    /// Set the emergency utilization cap above which new borrows and flash loans stop
+    /// (admin only). max_utilization_bps = 0 disables it
+    pub fn set_max_utilization(
+        ctx: Context<SetOutflowLimit>,
+        max_utilization_bps: u16,
+    ) -> Result<()> {
+        require!(
+            max_utilization_bps as u64 <= BPS_DENOMINATOR,
+            LegasiError::InvalidAmount
+        );
+        ctx.accounts.lp_pool.max_utilization_bps = max_utilization_bps;
+
+        msg!("Max utilization set: {} bps", max_utilization_bps);
+        Ok(())
+    }
+
+    /// Turn the protocol fee on repaid interest on or off (admin only)
+    pub fn set_protocol_fee_switch(ctx: Context<SetOutflowLimit>, enabled: bool) -> Result<()> {
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.protocol_fee_enabled = enabled;
+
+        emit!(ProtocolFeeSwitchSet {
+            pool: pool.key(),
+            enabled,
+            protocol_fee_bps: pool.protocol_fee_bps(),
+        });
+
+        msg!("Protocol fee switch: {}", enabled);
+        Ok(())
+    }
+
+    /// Pay the pool's owed protocol fees to the treasury's token account (permissionless)
+    pub fn claim_protocol_fees(ctx: Context<ClaimProtocolFees>) -> Result<()> {
+        let amount = ctx.accounts.lp_pool.protocol_fees_owed;
+        require!(amount > 0, LegasiError::NoProtocolFeesOwed);
+
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[
+            LP_POOL_SEED,
+            borrowable_mint.as_ref(),
+            &[ctx.accounts.lp_pool.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.treasury_token_account.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        ctx.accounts.lp_pool.protocol_fees_owed = 0;
+
+        emit!(ProtocolFeesClaimed {
+            pool: ctx.accounts.lp_pool.key(),
+            treasury_token_account: ctx.accounts.treasury_token_account.key(),
+            amount,
+        });
+
+        msg!("Claimed {} protocol fees to the treasury", amount);
+        Ok(())
+    }
+
+    /// Set the cut of repaid interest diverted to the pool's reserves (admin only, up to
+    /// `MAX_RESERVE_FACTOR_BPS`)
+    pub fn set_reserve_factor(
+        ctx: Context<SetOutflowLimit>,
+        reserve_factor_bps: u16,
+    ) -> Result<()> {
+        require!(
+            reserve_factor_bps <= MAX_RESERVE_FACTOR_BPS,
+            LegasiError::InvalidAmount
+        );
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.reserve_factor_bps = reserve_factor_bps;
+
+        emit!(ReserveFactorSet {
+            pool: pool.key(),
+            reserve_factor_bps,
+        });
+
+        msg!("Reserve factor set: {} bps", reserve_factor_bps);
+        Ok(())
+    }
+
+    /// Create the pool's reserve vault (admin only, once)
+    pub fn init_reserve_vault(_ctx: Context<InitReserveVault>) -> Result<()> {
+        msg!("Reserve vault created");
+        Ok(())
+    }
+
+    /// Move the reserves set aside in the pool vault into the reserve vault
+    /// (permissionless)
+    pub fn sweep_reserves(ctx: Context<SweepReserves>) -> Result<()> {
+        let amount = ctx.accounts.lp_pool.reserves_pending;
+        require!(amount > 0, LegasiError::InvalidAmount);
+
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[
+            LP_POOL_SEED,
+            borrowable_mint.as_ref(),
+            &[ctx.accounts.lp_pool.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.reserve_vault.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        ctx.accounts.lp_pool.reserves_pending = 0;
+
+        msg!("Swept {} to the reserve vault", amount);
+        Ok(())
+    }
+
+    /// Release `amount` of the reserves back into the pool to backstop it (admin only).
+    /// It raises `total_deposits`, so it goes to the pool's LPs, e.g. after a write-off
+    pub fn release_reserves(ctx: Context<ReleaseReserves>, amount: u64) -> Result<()> {
+        require!(
+            amount > 0 && amount <= ctx.accounts.reserve_vault.amount,
+            LegasiError::InvalidAmount
+        );
+
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[
+            LP_POOL_SEED,
+            borrowable_mint.as_ref(),
+            &[ctx.accounts.lp_pool.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reserve_vault.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        pool.total_deposits = pool
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        emit!(ReservesReleased {
+            pool: pool.key(),
+            amount,
+            remaining: ctx.accounts.reserve_vault.amount - amount,
+        });
+
+        msg!("Released {} of reserves into the pool", amount);
+        Ok(())
+    }
+
+    /// Create the pool's insurance vault (admin only, once)
+    pub fn init_insurance_vault(_ctx: Context<InitInsuranceVault>) -> Result<()> {
+        msg!("Insurance vault created");
+        Ok(())
+    }
+
+    /// Move the insurance money set aside in the pool vault into the insurance vault
+    /// (permissionless). It stays booked on `Protocol.insurance_fund`
+    pub fn sweep_insurance(ctx: Context<SweepInsurance>) -> Result<()> {
+        let amount = ctx.accounts.lp_pool.insurance_pending;
+        require!(amount > 0, LegasiError::InvalidAmount);
+
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[
+            LP_POOL_SEED,
+            borrowable_mint.as_ref(),
+            &[ctx.accounts.lp_pool.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.insurance_vault.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        ctx.accounts.lp_pool.insurance_pending = 0;
+
+        msg!("Swept {} to the insurance vault", amount);
+        Ok(())
+    }
+
+    /// Pay up to `amount` of the pool's bad debt (what a liquidation left uncovered)
+    /// back to its LPs from the insurance vault (permissionless, e.g. an automation
+    /// bot). Debited from the insurance fund, it raises `total_deposits`
+    pub fn cover_bad_debt(ctx: Context<CoverBadDebt>, amount: u64) -> Result<()> {
+        let amount = amount
+            .min(ctx.accounts.lp_pool.bad_debt)
+            .min(ctx.accounts.insurance_vault.amount);
+        require!(amount > 0, LegasiError::InvalidAmount);
+
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[
+            LP_POOL_SEED,
+            borrowable_mint.as_ref(),
+            &[ctx.accounts.lp_pool.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.insurance_vault.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+        totals::report_insurance_payout(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            amount,
+        )?;
+
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        pool.total_deposits = pool
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.bad_debt -= amount;
+
+        emit!(BadDebtCovered {
+            pool: pool.key(),
+            amount,
+            remaining: pool.bad_debt,
+        });
+
+        msg!("Covered {} of bad debt from the insurance vault", amount);
+        Ok(())
+    }
+
+    /// Schedule a recurring deposit of `amount` every `interval` seconds, the first one
+    /// due now. The owner must approve the schedule PDA as delegate on `source`
+    pub fn create_deposit_schedule(
+        ctx: Context<CreateDepositSchedule>,
+        amount: u64,
+        interval: i64,
+    ) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+        require!(interval >= MIN_DEPOSIT_INTERVAL, LegasiError::InvalidAmount);
+
+        let schedule = &mut ctx.accounts.deposit_schedule;
+        schedule.owner = ctx.accounts.owner.key();
+        schedule.borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        schedule.source = ctx.accounts.source.key();
+        schedule.lp_token_account = ctx.accounts.lp_token_account.key();
+        schedule.amount = amount;
+        schedule.interval = interval;
+        schedule.next_deposit_at = Clock::get()?.unix_timestamp;
+        schedule.deposits_made = 0;
+        schedule.bump = ctx.bumps.deposit_schedule;
+
+        msg!("Deposit schedule: {} every {}s", amount, interval);
+        Ok(())
+    }
+
+    /// Crank a due scheduled deposit (permissionless)
+    /// Pulls the amount from the source through the schedule's delegate approval
+    /// and mints the shares to the owner
+    pub fn crank_scheduled_deposit(ctx: Context<CrankScheduledDeposit>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let schedule = &ctx.accounts.deposit_schedule;
+        require!(schedule.is_due(now), LegasiError::DepositNotDue);
+        ctx.accounts.protocol.require_not_paused(PAUSE_DEPOSITS)?;
+        ctx.accounts
+            .lp_pool
+            .check_lp_access(ctx.accounts.allowlist_entry.as_ref())?;
+
+        let amount = schedule.amount;
+        let source = &ctx.accounts.source;
+        require!(
+            source.delegate == COption::Some(schedule.key())
+                && source.delegated_amount >= amount
+                && source.amount >= amount,
+            LegasiError::InvalidAmount
+        );
+
+        let shares_to_mint = ctx.accounts.lp_pool.shares_for_deposit(amount)?;
+        require!(shares_to_mint > 0, LegasiError::InvalidAmount);
+
+        // Pull from the source, signed by the schedule delegate
+        let owner = schedule.owner;
+        let borrowable_mint = schedule.borrowable_mint;
+        let schedule_seeds: &[&[u8]] = &[
+            DEPOSIT_SCHEDULE_SEED,
+            owner.as_ref(),
+            borrowable_mint.as_ref(),
+            &[schedule.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.deposit_schedule.to_account_info(),
+                },
+                &[schedule_seeds],
+            ),
+            amount,
+        )?;
+
+        // Mint LP tokens to the owner
+        let pool_seeds: &[&[u8]] = &[
+            LP_POOL_SEED,
+            borrowable_mint.as_ref(),
+            &[ctx.accounts.lp_pool.bump],
+        ];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    to: ctx.accounts.lp_token_account.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[pool_seeds],
+            ),
+            shares_to_mint,
+        )?;
+
+        // Update pool state
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        pool.total_deposits = pool
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.total_shares = pool
+            .total_shares
+            .checked_add(shares_to_mint)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        ctx.accounts.deposit_schedule.advance(now);
+
+        emit!(LpDeposited {
+            depositor: owner,
+            pool: ctx.accounts.lp_pool.key(),
+            amount,
+            shares_minted: shares_to_mint,
+        });
+
+        msg!(
+            "Scheduled deposit #{}: {} tokens, {} LP shares",
+            ctx.accounts.deposit_schedule.deposits_made,
+            amount,
+            shares_to_mint
+        );
+        Ok(())
+    }
+
+    /// Stop a deposit schedule and reclaim its rent
+    pub fn cancel_deposit_schedule(_ctx: Context<CancelDepositSchedule>) -> Result<()> {
+        msg!("Deposit schedule cancelled");
+        Ok(())
+    }
+
+    /// Lock `shares` for `days` while utilization is at crisis level, for a boosted
+    /// share of interest until they unlock (see `crisis_lock`)
+    pub fn lock_lp_shares(ctx: Context<LockLpShares>, shares: u64, days: u16) -> Result<()> {
+        require!(shares > 0, LegasiError::InvalidAmount);
+        require!(
+            (MIN_LOCK_DAYS..=MAX_LOCK_DAYS).contains(&days),
+            LegasiError::InvalidLockDuration
+        );
+        require!(
+            ctx.accounts.lp_pool.utilization_bps() >= CRISIS_UTILIZATION_BPS,
+            LegasiError::NoLiquidityCrisis
+        );
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_lp_token_account.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        let rebate_per_share = ctx.accounts.lp_pool.lock_rebate_per_share;
+        let lock = &mut ctx.accounts.lp_lock;
+        lock.owner = ctx.accounts.owner.key();
+        lock.lp_pool = ctx.accounts.lp_pool.key();
+        lock.shares = shares;
+        lock.unlock_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(days as i64 * SECONDS_PER_DAY)
+            .ok_or(LegasiError::MathOverflow)?;
+        lock.rebate_debt = shares as u128 * rebate_per_share / REBATE_PRECISION;
+        lock.bump = ctx.bumps.lp_lock;
+
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.locked_shares = pool
+            .locked_shares
+            .checked_add(shares)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        msg!("Locked {} LP shares for {} days", shares, days);
+        Ok(())
+    }
+
+    /// Return an expired lock's shares to the LP with its rebate (permissionless)
+    pub fn unlock_lp_shares(ctx: Context<UnlockLpShares>) -> Result<()> {
+        let lock = &ctx.accounts.lp_lock;
+        require!(
+            Clock::get()?.unix_timestamp >= lock.unlock_at,
+            LegasiError::LpSharesLocked
+        );
+        let shares = lock.shares;
+        let rebate = lock.rebate(ctx.accounts.lp_pool.lock_rebate_per_share);
+
+        // Shares back to the LP, then close the escrow, signed by the lock
+        let pool_key = ctx.accounts.lp_pool.key();
+        let owner = lock.owner;
+        let lock_seeds: &[&[u8]] = &[
+            LP_LOCK_SEED,
+            pool_key.as_ref(),
+            owner.as_ref(),
+            &[lock.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.owner_lp_token_account.to_account_info(),
+                    authority: ctx.accounts.lp_lock.to_account_info(),
+                },
+                &[lock_seeds],
+            ),
+            shares,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.lp_lock.to_account_info(),
+            },
+            &[lock_seeds],
+        ))?;
+
+        // The rebate was left in the vault uncounted as interest accrued
+        if rebate > 0 {
+            let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+            let pool_seeds: &[&[u8]] = &[
+                LP_POOL_SEED,
+                borrowable_mint.as_ref(),
+                &[ctx.accounts.lp_pool.bump],
+            ];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: ctx.accounts.owner_token_account.to_account_info(),
+                        authority: ctx.accounts.lp_pool.to_account_info(),
+                    },
+                    &[pool_seeds],
+                ),
+                rebate,
+            )?;
+        }
+
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.locked_shares = pool.locked_shares.saturating_sub(shares);
+
+        msg!("Unlocked {} LP shares with a {} rebate", shares, rebate);
+        Ok(())
+    }
+
+    /// Deposit `amount` locked for `days` (30, 90 or 180), its shares earning interest
+    /// at the term's multiplier until they unlock (see `locked_deposit`)
+    pub fn deposit_locked(ctx: Context<DepositLocked>, amount: u64, days: u16) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+        let multiplier_bps = lock_multiplier_bps(days).ok_or(LegasiError::InvalidLockDuration)?;
+        ctx.accounts.protocol.require_not_paused(PAUSE_DEPOSITS)?;
+        ctx.accounts
+            .lp_pool
+            .check_lp_access(ctx.accounts.allowlist_entry.as_ref())?;
+
+        let shares = ctx.accounts.lp_pool.shares_for_deposit(amount)?;
+        require!(shares > 0, LegasiError::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_token_account.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // Shares go straight into the lock's escrow
+        let pool_bump = ctx.accounts.lp_pool.bump;
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let seeds: &[&[u8]] = &[LP_POOL_SEED, borrowable_mint.as_ref(), &[pool_bump]];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            shares,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let weight = lock_weight(shares, multiplier_bps);
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(now)?;
+        pool.total_deposits = pool
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.total_shares = pool
+            .total_shares
+            .checked_add(shares)
+            .ok_or(LegasiError::MathOverflow)?;
+        pool.locked_deposit_weight = pool
+            .locked_deposit_weight
+            .checked_add(weight)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        let lock = &mut ctx.accounts.locked_deposit;
+        lock.owner = ctx.accounts.owner.key();
+        lock.lp_pool = pool.key();
+        lock.shares = shares;
+        lock.multiplier_bps = multiplier_bps;
+        lock.weight = weight;
+        lock.locked_at = now;
+        lock.unlock_at = now
+            .checked_add(days as i64 * SECONDS_PER_DAY)
+            .ok_or(LegasiError::MathOverflow)?;
+        lock.boost_debt = weight as u128 * pool.deposit_boost_per_weight / REBATE_PRECISION;
+        lock.bump = ctx.bumps.locked_deposit;
+
+        emit!(LpDepositLocked {
+            owner: lock.owner,
+            pool: lock.lp_pool,
+            amount,
+            shares,
+            multiplier_bps,
+            unlock_at: lock.unlock_at,
+        });
+
+        msg!(
+            "Locked {} tokens for {} days: {} LP shares at {} bps",
+            amount,
+            days,
+            shares,
+            multiplier_bps
+        );
+        Ok(())
+    }
+
+    /// Redeem a locked deposit in full: with its boost once unlocked, or early for a
+    /// penalty to the insurance fund, the boost going back to the pool
+    pub fn unlock_and_withdraw(ctx: Context<UnlockAndWithdraw>) -> Result<()> {
+        let pool = &ctx.accounts.lp_pool;
+        require!(
+            pool.withdraw_queue.is_empty(),
+            LegasiError::WithdrawQueueActive
+        );
+        pool.check_lp_access(ctx.accounts.allowlist_entry.as_ref())?;
+
+        let lock = &ctx.accounts.locked_deposit;
+        let (shares, weight) = (lock.shares, lock.weight);
+        let (redeemed, amount) = pool.redeem(shares, ctx.accounts.vault.amount)?;
+        require!(redeemed == shares, LegasiError::InsufficientLiquidity);
+
+        let clock = Clock::get()?;
+        let boost = lock.boost(pool.deposit_boost_per_weight);
+        let unlocked = clock.unix_timestamp >= lock.unlock_at;
+        let penalty = lock.early_exit_penalty(amount, clock.unix_timestamp);
+        let paid = amount
+            .checked_sub(penalty)
+            .and_then(|a| a.checked_add(if unlocked { boost } else { 0 }))
+            .ok_or(LegasiError::MathOverflow)?;
+
+        let tvl = pool.total_deposits;
+        ctx.accounts
+            .lp_pool
+            .outflow_limiter
+            .record_outflow(amount, tvl, clock.slot)?;
+
+        // Burn the escrowed shares, then close the escrow, signed by the lock
+        let pool_key = ctx.accounts.lp_pool.key();
+        let owner = ctx.accounts.owner.key();
+        let lock_seeds: &[&[u8]] = &[
+            LOCKED_DEPOSIT_SEED,
+            pool_key.as_ref(),
+            owner.as_ref(),
+            &[ctx.accounts.locked_deposit.bump],
+        ];
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    from: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.locked_deposit.to_account_info(),
+                },
+                &[lock_seeds],
+            ),
+            shares,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.locked_deposit.to_account_info(),
+            },
+            &[lock_seeds],
+        ))?;
+
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let pool_seeds: &[&[u8]] = &[
+            LP_POOL_SEED,
+            borrowable_mint.as_ref(),
+            &[ctx.accounts.lp_pool.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[pool_seeds],
+            ),
+            paid,
+        )?;
+
+        // The penalty stays in the vault uncounted, as insurance money
+        ctx.accounts.lp_pool.insurance_pending = ctx
+            .accounts
+            .lp_pool
+            .insurance_pending
+            .checked_add(penalty)
+            .ok_or(LegasiError::MathOverflow)?;
+        totals::report_insurance_fee(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            penalty,
+        )?;
+
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(clock.unix_timestamp)?;
+        pool.total_deposits = pool.total_deposits.saturating_sub(amount);
+        pool.total_shares = pool.total_shares.saturating_sub(shares);
+        pool.locked_deposit_weight = pool.locked_deposit_weight.saturating_sub(weight);
+        if !unlocked {
+            // A forfeited boost goes back to the LPs it came from
+            pool.total_deposits = pool
+                .total_deposits
+                .checked_add(boost)
+                .ok_or(LegasiError::MathOverflow)?;
+        }
+
+        emit!(LpDepositUnlocked {
+            owner,
+            pool: pool_key,
+            shares,
+            amount_received: paid,
+            boost: if unlocked { boost } else { 0 },
+            penalty,
+        });
+
+        msg!(
+            "Withdrew locked deposit: {} LP shares for {} ({} boost, {} penalty)",
+            shares,
+            paid,
+            if unlocked { boost } else { 0 },
+            penalty
+        );
+        Ok(())
+    }
+
+    /// Queue `shares` for withdrawal behind earlier requests (see `withdraw_queue`)
+    pub fn request_withdraw(ctx: Context<RequestWithdraw>, shares: u64) -> Result<()> {
+        require!(shares > 0, LegasiError::InvalidAmount);
+        ctx.accounts
+            .lp_pool
+            .check_lp_access(ctx.accounts.allowlist_entry.as_deref())?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_lp_token_account.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        let id = ctx.accounts.lp_pool.withdraw_queue.push(shares);
+        let request = &mut ctx.accounts.withdraw_request;
+        request.lp_pool = ctx.accounts.lp_pool.key();
+        request.owner = ctx.accounts.owner.key();
+        request.id = id;
+        request.shares = shares;
+        request.paid = 0;
+        request.requested_at = Clock::get()?.unix_timestamp;
+        request.bump = ctx.bumps.withdraw_request;
+
+        emit!(LpWithdrawQueued {
+            owner: request.owner,
+            pool: request.lp_pool,
+            request_id: id,
+            shares,
+        });
+
+        msg!(
+            "Queued {} LP shares for withdrawal (request {})",
+            shares,
+            id
+        );
+        Ok(())
+    }
+
+    /// Pay the request at the head of the queue out of current liquidity
+    /// (permissionless). Only the shares paid for are burned; a request paid in full
+    /// closes with its escrow and the queue moves on
+    pub fn process_withdraw_queue(ctx: Context<ProcessWithdrawQueue>) -> Result<()> {
+        let request = &ctx.accounts.withdraw_request;
+        let (shares, tokens) = ctx
+            .accounts
+            .lp_pool
+            .redeem(request.shares, ctx.accounts.vault.amount)?;
+        require!(tokens > 0, LegasiError::InsufficientLiquidity);
+
+        let current_slot = Clock::get()?.slot;
+        let tvl = ctx.accounts.lp_pool.total_deposits;
+        ctx.accounts
+            .lp_pool
+            .outflow_limiter
+            .record_outflow(tokens, tvl, current_slot)?;
+
+        // Burn the paid shares out of the escrow, signed by the request
+        let pool_key = ctx.accounts.lp_pool.key();
+        let id_bytes = request.id.to_le_bytes();
+        let request_seeds: &[&[u8]] = &[
+            WITHDRAW_REQUEST_SEED,
+            pool_key.as_ref(),
+            &id_bytes,
+            &[request.bump],
+        ];
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.lp_token_mint.to_account_info(),
+                    from: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.withdraw_request.to_account_info(),
+                },
+                &[request_seeds],
+            ),
+            shares,
+        )?;
+
+        let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+        let pool_seeds: &[&[u8]] = &[
+            LP_POOL_SEED,
+            borrowable_mint.as_ref(),
+            &[ctx.accounts.lp_pool.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.lp_pool.to_account_info(),
+                },
+                &[pool_seeds],
+            ),
+            tokens,
+        )?;
+
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        pool.total_deposits = pool.total_deposits.saturating_sub(tokens);
+        pool.total_shares = pool.total_shares.saturating_sub(shares);
+
+        let request = &mut ctx.accounts.withdraw_request;
+        request.shares -= shares;
+        request.paid = request.paid.saturating_add(tokens);
+        let fully_paid = request.shares == 0;
+        pool.withdraw_queue.redeemed(shares, fully_paid);
+
+        emit!(LpWithdrawRequestPaid {
+            owner: request.owner,
+            pool: pool_key,
+            request_id: request.id,
+            shares_burned: shares,
+            amount_received: tokens,
+            shares_remaining: request.shares,
+        });
+        msg!(
+            "Withdrawal request {}: {} shares paid {} tokens, {} left",
+            request.id,
+            shares,
+            tokens,
+            request.shares
+        );
+
+        if fully_paid {
+            token::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: ctx.accounts.escrow.to_account_info(),
+                    destination: ctx.accounts.owner.to_account_info(),
+                    authority: ctx.accounts.withdraw_request.to_account_info(),
+                },
+                &[request_seeds],
+            ))?;
+            ctx.accounts
+                .withdraw_request
+                .close(ctx.accounts.owner.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    /// Open the pool's reward emissions in `reward_mint` (admin only, see `rewards`).
+    /// Nothing is emitted until the config is funded and given a rate
+    pub fn initialize_rewards(ctx: Context<InitializeRewards>) -> Result<()> {
+        let config = &mut ctx.accounts.reward_config;
+        config.lp_pool = ctx.accounts.lp_pool.key();
+        config.reward_mint = ctx.accounts.reward_mint.key();
+        config.emission_rate = 0;
+        config.reward_per_share = 0;
+        config.last_update = Clock::get()?.unix_timestamp;
+        config.total_staked = 0;
+        config.undistributed = 0;
+        config.bump = ctx.bumps.reward_config;
+
+        msg!("Reward emissions opened in {}", config.reward_mint);
+        Ok(())
+    }
+
+    /// Add `amount` to the rewards left to emit (admin only). Emissions already due
+    /// are settled first, so funding never pays for an unfunded stretch
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        require!(amount > 0, LegasiError::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.admin_token_account.to_account_info(),
+                    to: ctx.accounts.reward_vault.to_account_info(),
+                    authority: ctx.accounts.admin.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let config = &mut ctx.accounts.reward_config;
+        config.update(Clock::get()?.unix_timestamp);
+        config.undistributed = config
+            .undistributed
+            .checked_add(amount)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        emit!(RewardsFunded {
+            pool: config.lp_pool,
+            reward_mint: config.reward_mint,
+            amount,
+            undistributed: config.undistributed,
+        });
+        msg!(
+            "Funded {} rewards, {} left to emit",
+            amount,
+            config.undistributed
+        );
+        Ok(())
+    }
+
+    /// Set the reward tokens emitted per second across all stakers (admin only).
+    /// The old rate applies up to now
+    pub fn set_emission_rate(ctx: Context<SetEmissionRate>, emission_rate: u64) -> Result<()> {
+        let config = &mut ctx.accounts.reward_config;
+        config.update(Clock::get()?.unix_timestamp);
+        config.emission_rate = emission_rate;
+
+        emit!(EmissionRateSet {
+            pool: config.lp_pool,
+            reward_mint: config.reward_mint,
+            emission_rate,
+        });
+        msg!("Emission rate set: {} per second", emission_rate);
+        Ok(())
+    }
+
+    /// Stake `shares` to earn the pool's reward emissions
+    pub fn stake_for_rewards(ctx: Context<StakeForRewards>, shares: u64) -> Result<()> {
+        require!(shares > 0, LegasiError::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_lp_token_account.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        let config = &mut ctx.accounts.reward_config;
+        config.update(Clock::get()?.unix_timestamp);
+        config.total_staked = config
+            .total_staked
+            .checked_add(shares)
+            .ok_or(LegasiError::MathOverflow)?;
+
+        let user = &mut ctx.accounts.user_reward_state;
+        user.owner = ctx.accounts.owner.key();
+        user.reward_config = config.key();
+        user.bump = ctx.bumps.user_reward_state;
+        let staked = user
+            .staked
+            .checked_add(shares)
+            .ok_or(LegasiError::MathOverflow)?;
+        user.restake(staked, config.reward_per_share);
+
+        msg!("Staked {} LP shares for rewards ({} total)", shares, staked);
+        Ok(())
+    }
+
+    /// Take `shares` out of the rewards stake. Rewards earned so far stay claimable
+    pub fn unstake_from_rewards(ctx: Context<UnstakeFromRewards>, shares: u64) -> Result<()> {
+        require!(shares > 0, LegasiError::InvalidAmount);
+        let staked = ctx
+            .accounts
+            .user_reward_state
+            .staked
+            .checked_sub(shares)
+            .ok_or(LegasiError::InvalidAmount)?;
+
+        let config = &mut ctx.accounts.reward_config;
+        config.update(Clock::get()?.unix_timestamp);
+        config.total_staked -= shares;
+        ctx.accounts
+            .user_reward_state
+            .restake(staked, config.reward_per_share);
+
+        let pool_key = config.lp_pool;
+        let config_seeds: &[&[u8]] = &[REWARD_CONFIG_SEED, pool_key.as_ref(), &[config.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    to: ctx.accounts.owner_lp_token_account.to_account_info(),
+                    authority: ctx.accounts.reward_config.to_account_info(),
+                },
+                &[config_seeds],
+            ),
+            shares,
+        )?;
+
+        msg!("Unstaked {} LP shares ({} left)", shares, staked);
+        Ok(())
+    }
+
+    /// Pay out the owner's rewards emitted so far
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        let config = &mut ctx.accounts.reward_config;
+        config.update(Clock::get()?.unix_timestamp);
+        let user = &mut ctx.accounts.user_reward_state;
+        user.settle(config.reward_per_share);
+        let amount = user.pending;
+        require!(amount > 0, LegasiError::NoRewardsToClaim);
+        user.pending = 0;
+
+        let pool_key = config.lp_pool;
+        let config_seeds: &[&[u8]] = &[REWARD_CONFIG_SEED, pool_key.as_ref(), &[config.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.owner_reward_account.to_account_info(),
+                    authority: ctx.accounts.reward_config.to_account_info(),
+                },
+                &[config_seeds],
+            ),
+            amount,
+        )?;
+
+        emit!(RewardsClaimed {
+            owner: ctx.accounts.owner.key(),
+            pool: pool_key,
+            reward_mint: ctx.accounts.reward_config.reward_mint,
+            amount,
+        });
+        msg!("Claimed {} rewards", amount);
+        Ok(())
+    }
+
+    /// Allowlist `lp` on a permissioned pool (admin only)
+    pub fn add_lp_to_allowlist(ctx: Context<AddLpToAllowlist>, lp: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.allowlist_entry;
+        entry.lp_pool = ctx.accounts.lp_pool.key();
+        entry.lp = lp;
+        entry.bump = ctx.bumps.allowlist_entry;
+
+        msg!("LP {} allowlisted", lp);
+        Ok(())
+    }
+
+    /// Remove an LP from a pool's allowlist (admin only). Its shares stay put, but it
+    /// can't deposit or withdraw until allowlisted again
+    pub fn remove_lp_from_allowlist(ctx: Context<RemoveLpFromAllowlist>) -> Result<()> {
+        msg!(
+            "LP {} removed from allowlist",
+            ctx.accounts.allowlist_entry.lp
+        );
+        Ok(())
+    }
+
+    /// Auction `lot` of the pool vault's insurance surplus for LP shares (permissionless)
+    /// The lot is debited from the insurance fund until settlement (see `surplus_auction`)
+    pub fn start_surplus_auction(ctx: Context<StartSurplusAuction>, lot: u64) -> Result<()> {
+        require!(lot > 0, LegasiError::InvalidAmount);
+        let protocol = &ctx.accounts.protocol;
+        require!(
+            lot <= insurance_surplus(protocol.insurance_fund, protocol.insurance_fund_target),
+            LegasiError::NoInsuranceSurplus
+        );
+        // The insurance cut sits in the vault outside `total_deposits`
+        let uncounted = ctx
+            .accounts
+            .vault
+            .amount
+            .saturating_sub(ctx.accounts.lp_pool.available_liquidity());
+        require!(lot <= uncounted, LegasiError::InsufficientLiquidity);
+        ctx.accounts.lp_pool.insurance_pending =
+            ctx.accounts.lp_pool.insurance_pending.saturating_sub(lot);
+
+        totals::report_insurance_payout(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.protocol.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            lot,
+        )?;
+
+        let auction = &mut ctx.accounts.auction;
+        auction.lp_pool = ctx.accounts.lp_pool.key();
+        auction.starter = ctx.accounts.starter.key();
+        auction.lot = lot;
+        auction.bid = 0;
+        auction.bidder = Pubkey::default();
+        auction.ends_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(SURPLUS_AUCTION_DURATION)
+            .ok_or(LegasiError::MathOverflow)?;
+        auction.bump = ctx.bumps.auction;
+
+        emit!(SurplusAuctionStarted {
+            pool: auction.lp_pool,
+            auction: auction.key(),
+            lot,
+            ends_at: auction.ends_at,
+        });
+
+        msg!("Surplus auction of {} started", lot);
+        Ok(())
+    }
+
+    /// Bid `shares` for a surplus lot; the previous top bid is refunded
+    pub fn bid_surplus_auction(ctx: Context<BidSurplusAuction>, shares: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &ctx.accounts.auction;
+        require!(now < auction.ends_at, LegasiError::SurplusAuctionEnded);
+        require!(shares >= auction.min_next_bid(), LegasiError::BidTooLow);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.bidder_lp_token_account.to_account_info(),
+                    to: ctx.accounts.bid_escrow.to_account_info(),
+                    authority: ctx.accounts.bidder.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        if auction.bid > 0 {
+            let refund_to = ctx
+                .accounts
+                .previous_bidder_lp_token_account
+                .as_ref()
+                .ok_or(LegasiError::Unauthorized)?;
+            let pool_key = auction.lp_pool;
+            let auction_seeds: &[&[u8]] =
+                &[SURPLUS_AUCTION_SEED, pool_key.as_ref(), &[auction.bump]];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.bid_escrow.to_account_info(),
+                        to: refund_to.to_account_info(),
+                        authority: ctx.accounts.auction.to_account_info(),
+                    },
+                    &[auction_seeds],
+                ),
+                auction.bid,
+            )?;
+        }
+
+        let auction = &mut ctx.accounts.auction;
+        auction.bid = shares;
+        auction.bidder = ctx.accounts.bidder.key();
+        auction.ends_at = auction.extended_end(now);
+
+        emit!(SurplusBid {
+            auction: auction.key(),
+            bidder: auction.bidder,
+            shares,
+            ends_at: auction.ends_at,
+        });
+
+        msg!("Bid {} LP shares for {} surplus", shares, auction.lot);
+        Ok(())
+    }
+
+    /// Settle an ended surplus auction (permissionless): burn the winning shares and
+    /// pay the lot, or return the lot to the insurance fund if nobody bid
+    pub fn settle_surplus_auction(ctx: Context<SettleSurplusAuction>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        require!(
+            Clock::get()?.unix_timestamp >= auction.ends_at,
+            LegasiError::SurplusAuctionRunning
+        );
+        let (lot, bid, winner) = (auction.lot, auction.bid, auction.bidder);
+        let pool_key = ctx.accounts.lp_pool.key();
+        let auction_seeds: &[&[u8]] = &[SURPLUS_AUCTION_SEED, pool_key.as_ref(), &[auction.bump]];
+
+        if bid > 0 {
+            let winner_token_account = ctx
+                .accounts
+                .winner_token_account
+                .as_ref()
+                .ok_or(LegasiError::Unauthorized)?;
+
+            // Burning the shares leaves total_deposits as is: the lot accrues to LPs
+            token::burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.lp_token_mint.to_account_info(),
+                        from: ctx.accounts.bid_escrow.to_account_info(),
+                        authority: ctx.accounts.auction.to_account_info(),
+                    },
+                    &[auction_seeds],
+                ),
+                bid,
+            )?;
+
+            let borrowable_mint = ctx.accounts.lp_pool.borrowable_mint;
+            let pool_seeds: &[&[u8]] = &[
+                LP_POOL_SEED,
+                borrowable_mint.as_ref(),
+                &[ctx.accounts.lp_pool.bump],
+            ];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: winner_token_account.to_account_info(),
+                        authority: ctx.accounts.lp_pool.to_account_info(),
+                    },
+                    &[pool_seeds],
+                ),
+                lot,
+            )?;
+
+            let pool = &mut ctx.accounts.lp_pool;
+            pool.total_shares = pool.total_shares.saturating_sub(bid);
+        } else {
+            ctx.accounts.lp_pool.insurance_pending = ctx
+                .accounts
+                .lp_pool
+                .insurance_pending
+                .checked_add(lot)
+                .ok_or(LegasiError::MathOverflow)?;
+            totals::report_insurance_fee(
+                &ctx.accounts.core_program.to_account_info(),
+                &ctx.accounts.protocol.to_account_info(),
+                &ctx.accounts.protocol_writer.to_account_info(),
+                ctx.bumps.protocol_writer,
+                lot,
+            )?;
+        }
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.bid_escrow.to_account_info(),
+                destination: ctx.accounts.starter.to_account_info(),
+                authority: ctx.accounts.auction.to_account_info(),
+            },
+            &[auction_seeds],
+        ))?;
+
+        emit!(SurplusAuctionSettled {
+            pool: pool_key,
+            auction: ctx.accounts.auction.key(),
+            winner,
+            lot,
+            shares_burned: bid,
+        });
+
+        msg!("Surplus auction settled: {} for {} LP shares", lot, bid);
+        Ok(())
+    }
+
+    /// Publish the pool's utilization-based rates: the borrow rate goes on its
+    /// `Borrowable.interest_rate_bps`, both in a `RateUpdated` event (permissionless).
+    /// Borrows accrue at the live rate through the borrow index either way
+    pub fn refresh_rates(ctx: Context<RefreshRates>) -> Result<()> {
+        let pool = &mut ctx.accounts.lp_pool;
+        pool.update_borrow_index(Clock::get()?.unix_timestamp)?;
+        let borrow_rate_bps = pool.borrow_rate_bps();
+        let supply_rate_bps = pool.supply_rate_bps();
+
+        totals::report_borrow_rate(
+            &ctx.accounts.core_program.to_account_info(),
+            &ctx.accounts.borrowable.to_account_info(),
+            &ctx.accounts.protocol_writer.to_account_info(),
+            ctx.bumps.protocol_writer,
+            u16::try_from(borrow_rate_bps).map_err(|_| LegasiError::MathOverflow)?,
+        )?;
+
+        emit!(RateUpdated {
+            pool: pool.key(),
+            mint: pool.borrowable_mint,
+            utilization_bps: pool.utilization_bps(),
+            borrow_rate_bps,
+            supply_rate_bps,
+        });
+
+        msg!(
+            "Rates refreshed: {} bps borrow, {} bps supply",
+            borrow_rate_bps,
+            supply_rate_bps
+        );
+        Ok(())
+    }
+
+    /// Get current exchange rate (tokens per LP share)
+    pub fn get_exchange_rate(ctx: Context<GetExchangeRate>) -> Result<u64> {
+        let pool = &ctx.accounts.lp_pool;
+
+        if pool.total_shares == 0 {
+            return Ok(USD_MULTIPLIER); // 1:1 for empty pool
+        }
+
+        let rate = (pool.total_deposits as u128)
+            .checked_mul(USD_MULTIPLIER as u128)
+            .ok_or(LegasiError::MathOverflow)?
+            .checked_div(pool.total_shares as u128)
+            .ok_or(LegasiError::MathOverflow)? as u64;
+
+        Ok(rate)
+    }
+}
+
+// ========== ACCOUNTS ==========
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LpPool::INIT_SPACE,
+        seeds = [LP_POOL_SEED, borrowable_mint.key().as_ref()],
+        bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    pub borrowable_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePoolAccounts<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    #[account(
+        init,
+        payer = admin,
+        mint::decimals = 6,
+        mint::authority = lp_pool,
+        seeds = [LP_TOKEN_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        token::mint = borrowable_mint,
+        token::authority = lp_pool,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    /// The original borrowable mint (USDC, etc.)
+    pub borrowable_mint: Account<'info, Mint>,
+    /// CHECK: Metaplex metadata PDA for the LP token mint - created by the metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), lp_token_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct LpDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_lp_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    /// Protocol state (owned by core program), for the pause flags
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// LP's allowlist entry, required if the pool is permissioned
+    #[account(
+        seeds = [LP_ALLOWLIST_SEED, lp_pool.key().as_ref(), depositor.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, LpAllowlistEntry>>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Receive a CCTP deposit (permissionless - any relayer can submit the attestation)
+#[derive(Accounts)]
+pub struct ReceiveCctpDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Box<Account<'info, LpPool>>,
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub lp_token_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+    /// Beneficiary's CCTP inbox - the mint_recipient of the source-chain burn
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        token::mint = borrowable_mint,
+        token::authority = lp_pool,
+        seeds = [CCTP_INBOX_SEED, lp_pool.key().as_ref(), beneficiary.key().as_ref()],
+        bump
+    )]
+    pub cctp_inbox: Box<Account<'info, TokenAccount>>,
+    #[account(address = lp_pool.borrowable_mint)]
+    pub borrowable_mint: Box<Account<'info, Mint>>,
+    /// CHECK: LP tokens are minted to this wallet's token account
+    pub beneficiary: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = beneficiary_lp_token_account.owner == beneficiary.key() @ LegasiError::Unauthorized,
+        constraint = beneficiary_lp_token_account.mint == lp_token_mint.key() @ LegasiError::InvalidAmount
+    )]
+    pub beneficiary_lp_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: CCTP MessageTransmitter - validated in cctp::receive_message
+    pub message_transmitter: UncheckedAccount<'info>,
+    /// Protocol state (owned by core program), for the pause flags
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// Beneficiary's allowlist entry, required if the pool is permissioned
+    #[account(
+        seeds = [LP_ALLOWLIST_SEED, lp_pool.key().as_ref(), beneficiary.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Box<Account<'info, LpAllowlistEntry>>>,
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    // CCTP receive_message accounts passed via remaining_accounts
+}
+
+#[derive(Accounts)]
+pub struct LpWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_lp_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub withdrawer: Signer<'info>,
+    /// LP's allowlist entry, required if the pool is permissioned
+    #[account(
+        seeds = [LP_ALLOWLIST_SEED, lp_pool.key().as_ref(), withdrawer.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, LpAllowlistEntry>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueInterest<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(constraint = totals::is_protocol_writer(writer.key) @ LegasiError::Unauthorized)]
+    pub writer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Lend<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    /// Borrower's (or payee's) token account
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+    #[account(constraint = totals::is_protocol_writer(writer.key) @ LegasiError::Unauthorized)]
+    pub writer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTotalBorrowed<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(constraint = totals::is_protocol_writer(writer.key) @ LegasiError::Unauthorized)]
+    pub writer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetOutflowLimit<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitReserveVault<'info> {
+    #[account(seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        init,
+        payer = admin,
+        token::mint = borrowable_mint,
+        token::authority = lp_pool,
+        seeds = [RESERVE_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+    #[account(address = lp_pool.borrowable_mint)]
+    pub borrowable_mint: Account<'info, Mint>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepReserves<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [RESERVE_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseReserves<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [RESERVE_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitInsuranceVault<'info> {
+    #[account(seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        init,
+        payer = admin,
+        token::mint = borrowable_mint,
+        token::authority = lp_pool,
+        seeds = [INSURANCE_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+    #[account(address = lp_pool.borrowable_mint)]
+    pub borrowable_mint: Account<'info, Mint>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SweepInsurance<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [INSURANCE_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CoverBadDebt<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [INSURANCE_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Account<'info, Protocol>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimProtocolFees<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// Treasury's account for the pool's asset
+    #[account(
+        mut,
+        token::mint = lp_pool.borrowable_mint,
+        constraint = treasury_token_account.owner == protocol.treasury @ LegasiError::Unauthorized
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDepositSchedule<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + DepositSchedule::INIT_SPACE,
+        seeds = [DEPOSIT_SCHEDULE_SEED, owner.key().as_ref(), lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub deposit_schedule: Account<'info, DepositSchedule>,
+    #[account(seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        constraint = source.owner == owner.key() @ LegasiError::Unauthorized,
+        constraint = source.mint == lp_pool.borrowable_mint @ LegasiError::InvalidAmount
+    )]
+    pub source: Account<'info, TokenAccount>,
+    #[account(
+        constraint = lp_token_account.owner == owner.key() @ LegasiError::Unauthorized,
+        constraint = lp_token_account.mint == lp_pool.lp_token_mint @ LegasiError::InvalidAmount
+    )]
+    pub lp_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Crank a scheduled deposit (permissionless - any keeper can submit it)
+#[derive(Accounts)]
+pub struct CrankScheduledDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [
+            DEPOSIT_SCHEDULE_SEED,
+            deposit_schedule.owner.as_ref(),
+            deposit_schedule.borrowable_mint.as_ref()
+        ],
+        bump = deposit_schedule.bump,
+        has_one = source,
+        has_one = lp_token_account
+    )]
+    pub deposit_schedule: Account<'info, DepositSchedule>,
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, deposit_schedule.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED, deposit_schedule.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, deposit_schedule.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub source: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub lp_token_account: Account<'info, TokenAccount>,
+    /// Schedule owner's allowlist entry, required if the pool is permissioned
+    /// Protocol state (owned by core program), for the pause flags
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    #[account(
+        seeds = [
+            LP_ALLOWLIST_SEED,
+            lp_pool.key().as_ref(),
+            deposit_schedule.owner.as_ref()
+        ],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, LpAllowlistEntry>>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDepositSchedule<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            DEPOSIT_SCHEDULE_SEED,
+            owner.key().as_ref(),
+            deposit_schedule.borrowable_mint.as_ref()
+        ],
+        bump = deposit_schedule.bump,
+        has_one = owner
+    )]
+    pub deposit_schedule: Account<'info, DepositSchedule>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositLocked<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LockedDeposit::INIT_SPACE,
+        seeds = [LOCKED_DEPOSIT_SEED, lp_pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub locked_deposit: Account<'info, LockedDeposit>,
+    /// Locked shares (authority = locked_deposit PDA)
+    #[account(
+        init,
+        payer = owner,
+        token::mint = lp_token_mint,
+        token::authority = locked_deposit,
+        seeds = [LOCKED_DEPOSIT_ESCROW_SEED, locked_deposit.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub lp_token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    /// Protocol state (owned by core program), for the pause flags
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// LP's allowlist entry, required if the pool is permissioned
+    #[account(
+        seeds = [LP_ALLOWLIST_SEED, lp_pool.key().as_ref(), owner.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, LpAllowlistEntry>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockAndWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [LOCKED_DEPOSIT_SEED, lp_pool.key().as_ref(), owner.key().as_ref()],
+        bump = locked_deposit.bump,
+        has_one = owner,
+        has_one = lp_pool
+    )]
+    pub locked_deposit: Account<'info, LockedDeposit>,
+    #[account(mut, seeds = [LOCKED_DEPOSIT_ESCROW_SEED, locked_deposit.key().as_ref()], bump)]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
     pub lp_token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = lp_pool.borrowable_mint)]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    /// LP's allowlist entry, required if the pool is permissioned
+    #[account(
+        seeds = [LP_ALLOWLIST_SEED, lp_pool.key().as_ref(), owner.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, LpAllowlistEntry>>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Box<Account<'info, Protocol>>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct LockLpShares<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
     #[account(
         init,
-        payer = admin,
-        token::mint = borrowable_mint,
-        token::authority = lp_pool,
-        seeds = [b"lp_vault", lp_pool.borrowable_mint.as_ref()],
+        payer = owner,
+        space = 8 + LpLock::INIT_SPACE,
+        seeds = [LP_LOCK_SEED, lp_pool.key().as_ref(), owner.key().as_ref()],
         bump
     )]
-    pub vault: Account<'info, TokenAccount>,
-    /// The original borrowable mint (USDC, etc.)
-    pub borrowable_mint: Account<'info, Mint>,
+    pub lp_lock: Account<'info, LpLock>,
+    /// Locked shares (authority = lp_lock PDA)
+    #[account(
+        init,
+        payer = owner,
+        token::mint = lp_token_mint,
+        token::authority = lp_lock,
+        seeds = [LP_LOCK_ESCROW_SEED, lp_lock.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(address = lp_pool.lp_token_mint)]
+    pub lp_token_mint: Account<'info, Mint>,
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub owner_lp_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
+/// Unlock expired LP shares (permissionless - shares and rebate only go to the owner)
 #[derive(Accounts)]
-pub struct LpDeposit<'info> {
+pub struct UnlockLpShares<'info> {
     #[account(
         mut,
-        seeds = [b"lp_pool", lp_pool.borrowable_mint.as_ref()],
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
         bump = lp_pool.bump
     )]
     pub lp_pool: Account<'info, LpPool>,
     #[account(
         mut,
-        seeds = [b"lp_token", lp_pool.borrowable_mint.as_ref()],
-        bump
+        close = owner,
+        seeds = [LP_LOCK_SEED, lp_pool.key().as_ref(), owner.key().as_ref()],
+        bump = lp_lock.bump,
+        has_one = owner,
+        has_one = lp_pool
     )]
-    pub lp_token_mint: Account<'info, Mint>,
+    pub lp_lock: Account<'info, LpLock>,
+    #[account(mut, seeds = [LP_LOCK_ESCROW_SEED, lp_lock.key().as_ref()], bump)]
+    pub escrow: Account<'info, TokenAccount>,
     #[account(
         mut,
-        seeds = [b"lp_vault", lp_pool.borrowable_mint.as_ref()],
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
         bump
     )]
     pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_lp_token_account.owner == owner.key() @ LegasiError::Unauthorized,
+        token::mint = lp_pool.lp_token_mint
+    )]
+    pub owner_lp_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key() @ LegasiError::Unauthorized,
+        token::mint = lp_pool.borrowable_mint
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    /// CHECK: the lock's owner, receives the rent
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub owner: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + WithdrawRequest::INIT_SPACE,
+        seeds = [
+            WITHDRAW_REQUEST_SEED,
+            lp_pool.key().as_ref(),
+            &lp_pool.withdraw_queue.next_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub withdraw_request: Account<'info, WithdrawRequest>,
+    /// Queued shares (authority = withdraw_request PDA)
+    #[account(
+        init,
+        payer = owner,
+        token::mint = lp_token_mint,
+        token::authority = withdraw_request,
+        seeds = [WITHDRAW_REQUEST_ESCROW_SEED, withdraw_request.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(address = lp_pool.lp_token_mint)]
+    pub lp_token_mint: Account<'info, Mint>,
     #[account(mut)]
-    pub user_lp_token_account: Account<'info, TokenAccount>,
+    pub owner_lp_token_account: Account<'info, TokenAccount>,
+    /// LP's allowlist entry, required if the pool is permissioned
+    #[account(
+        seeds = [LP_ALLOWLIST_SEED, lp_pool.key().as_ref(), owner.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Box<Account<'info, LpAllowlistEntry>>>,
     #[account(mut)]
-    pub depositor: Signer<'info>,
+    pub owner: Signer<'info>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
+/// Pay the head of the withdrawal queue (permissionless - tokens and rent only go to
+/// the request's owner)
 #[derive(Accounts)]
-pub struct LpWithdraw<'info> {
+pub struct ProcessWithdrawQueue<'info> {
     #[account(
         mut,
-        seeds = [b"lp_pool", lp_pool.borrowable_mint.as_ref()],
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
         bump = lp_pool.bump
     )]
     pub lp_pool: Account<'info, LpPool>,
     #[account(
         mut,
-        seeds = [b"lp_token", lp_pool.borrowable_mint.as_ref()],
+        seeds = [WITHDRAW_REQUEST_SEED, lp_pool.key().as_ref(), &withdraw_request.id.to_le_bytes()],
+        bump = withdraw_request.bump,
+        has_one = owner,
+        has_one = lp_pool,
+        constraint = withdraw_request.id == lp_pool.withdraw_queue.head @ LegasiError::NotQueueHead
+    )]
+    pub withdraw_request: Account<'info, WithdrawRequest>,
+    #[account(mut, seeds = [WITHDRAW_REQUEST_ESCROW_SEED, withdraw_request.key().as_ref()], bump)]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [LP_TOKEN_SEED, lp_pool.borrowable_mint.as_ref()],
         bump
     )]
     pub lp_token_mint: Account<'info, Mint>,
     #[account(
         mut,
-        seeds = [b"lp_vault", lp_pool.borrowable_mint.as_ref()],
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
         bump
     )]
     pub vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key() @ LegasiError::Unauthorized,
+        token::mint = lp_pool.borrowable_mint
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    /// CHECK: the request's owner, receives the rent
     #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub owner: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewards<'info> {
+    #[account(seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RewardConfig::INIT_SPACE,
+        seeds = [REWARD_CONFIG_SEED, lp_pool.key().as_ref()],
+        bump
+    )]
+    pub reward_config: Account<'info, RewardConfig>,
+    pub reward_mint: Account<'info, Mint>,
+    /// Funded rewards (authority = reward_config PDA)
+    #[account(
+        init,
+        payer = admin,
+        token::mint = reward_mint,
+        token::authority = reward_config,
+        seeds = [REWARD_VAULT_SEED, reward_config.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    /// Staked shares (authority = reward_config PDA)
+    #[account(
+        init,
+        payer = admin,
+        token::mint = lp_token_mint,
+        token::authority = reward_config,
+        seeds = [REWARD_STAKE_VAULT_SEED, reward_config.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(address = lp_pool.lp_token_mint)]
+    pub lp_token_mint: Account<'info, Mint>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
     #[account(mut)]
-    pub user_lp_token_account: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(
+        mut,
+        seeds = [REWARD_CONFIG_SEED, reward_config.lp_pool.as_ref()],
+        bump = reward_config.bump
+    )]
+    pub reward_config: Account<'info, RewardConfig>,
+    #[account(mut, seeds = [REWARD_VAULT_SEED, reward_config.key().as_ref()], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
     #[account(mut)]
-    pub withdrawer: Signer<'info>,
+    pub admin_token_account: Account<'info, TokenAccount>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    pub admin: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct AccrueInterest<'info> {
+pub struct SetEmissionRate<'info> {
+    #[account(
+        mut,
+        seeds = [REWARD_CONFIG_SEED, reward_config.lp_pool.as_ref()],
+        bump = reward_config.bump
+    )]
+    pub reward_config: Account<'info, RewardConfig>,
+    #[account(seeds = [PROTOCOL_SEED], bump = protocol.bump, has_one = admin)]
+    pub protocol: Account<'info, Protocol>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StakeForRewards<'info> {
+    #[account(
+        mut,
+        seeds = [REWARD_CONFIG_SEED, reward_config.lp_pool.as_ref()],
+        bump = reward_config.bump
+    )]
+    pub reward_config: Account<'info, RewardConfig>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + UserRewardState::INIT_SPACE,
+        seeds = [USER_REWARD_SEED, reward_config.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub user_reward_state: Account<'info, UserRewardState>,
+    #[account(mut, seeds = [REWARD_STAKE_VAULT_SEED, reward_config.key().as_ref()], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner_lp_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeFromRewards<'info> {
+    #[account(
+        mut,
+        seeds = [REWARD_CONFIG_SEED, reward_config.lp_pool.as_ref()],
+        bump = reward_config.bump
+    )]
+    pub reward_config: Account<'info, RewardConfig>,
+    #[account(
+        mut,
+        seeds = [USER_REWARD_SEED, reward_config.key().as_ref(), owner.key().as_ref()],
+        bump = user_reward_state.bump,
+        has_one = owner,
+        has_one = reward_config
+    )]
+    pub user_reward_state: Account<'info, UserRewardState>,
+    #[account(mut, seeds = [REWARD_STAKE_VAULT_SEED, reward_config.key().as_ref()], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = owner_lp_token_account.owner == owner.key() @ LegasiError::Unauthorized
+    )]
+    pub owner_lp_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(
+        mut,
+        seeds = [REWARD_CONFIG_SEED, reward_config.lp_pool.as_ref()],
+        bump = reward_config.bump
+    )]
+    pub reward_config: Account<'info, RewardConfig>,
+    #[account(
+        mut,
+        seeds = [USER_REWARD_SEED, reward_config.key().as_ref(), owner.key().as_ref()],
+        bump = user_reward_state.bump,
+        has_one = owner,
+        has_one = reward_config
+    )]
+    pub user_reward_state: Account<'info, UserRewardState>,
+    #[account(mut, seeds = [REWARD_VAULT_SEED, reward_config.key().as_ref()], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = reward_config.reward_mint)]
+    pub owner_reward_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(lp: Pubkey)]
+pub struct AddLpToAllowlist<'info> {
+    #[account(seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LpAllowlistEntry::INIT_SPACE,
+        seeds = [LP_ALLOWLIST_SEED, lp_pool.key().as_ref(), lp.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, LpAllowlistEntry>,
+    #[account(
+        seeds = [PROTOCOL_SEED],
+        bump = protocol.bump,
+        seeds::program = legasi_core::ID,
+        has_one = admin
+    )]
+    pub protocol: Account<'info, Protocol>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLpFromAllowlist<'info> {
+    #[account(seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [LP_ALLOWLIST_SEED, lp_pool.key().as_ref(), allowlist_entry.lp.as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Account<'info, LpAllowlistEntry>,
+    #[account(
+        seeds = [PROTOCOL_SEED],
+        bump = protocol.bump,
+        seeds::program = legasi_core::ID,
+        has_one = admin
+    )]
+    pub protocol: Account<'info, Protocol>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartSurplusAuction<'info> {
+    #[account(
+        mut,
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = lp_pool.bump
+    )]
+    pub lp_pool: Account<'info, LpPool>,
+    #[account(seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()], bump)]
+    pub vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = starter,
+        space = 8 + SurplusAuction::INIT_SPACE,
+        seeds = [SURPLUS_AUCTION_SEED, lp_pool.key().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, SurplusAuction>,
+    /// Top bid's shares (authority = auction PDA)
+    #[account(
+        init,
+        payer = starter,
+        token::mint = lp_token_mint,
+        token::authority = auction,
+        seeds = [SURPLUS_BID_ESCROW_SEED, auction.key().as_ref()],
+        bump
+    )]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    #[account(address = lp_pool.lp_token_mint)]
+    pub lp_token_mint: Account<'info, Mint>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
+    pub protocol: Account<'info, Protocol>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    #[account(mut)]
+    pub starter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BidSurplusAuction<'info> {
+    #[account(
+        mut,
+        seeds = [SURPLUS_AUCTION_SEED, auction.lp_pool.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, SurplusAuction>,
+    #[account(mut, seeds = [SURPLUS_BID_ESCROW_SEED, auction.key().as_ref()], bump)]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = bid_escrow.mint)]
+    pub bidder_lp_token_account: Account<'info, TokenAccount>,
+    /// Refund of the outbid shares, required once there is a bid
+    #[account(
+        mut,
+        token::mint = bid_escrow.mint,
+        constraint = previous_bidder_lp_token_account.owner == auction.bidder @ LegasiError::Unauthorized
+    )]
+    pub previous_bidder_lp_token_account: Option<Account<'info, TokenAccount>>,
+    pub bidder: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Settle an ended surplus auction (permissionless - the lot only goes to the winner)
+#[derive(Accounts)]
+pub struct SettleSurplusAuction<'info> {
     #[account(
         mut,
-        seeds = [b"lp_pool", lp_pool.borrowable_mint.as_ref()],
+        seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()],
         bump = lp_pool.bump
     )]
     pub lp_pool: Account<'info, LpPool>,
-    #[account(mut, seeds = [b"protocol"], bump = protocol.bump)]
+    #[account(
+        mut,
+        close = starter,
+        seeds = [SURPLUS_AUCTION_SEED, lp_pool.key().as_ref()],
+        bump = auction.bump,
+        has_one = starter
+    )]
+    pub auction: Account<'info, SurplusAuction>,
+    #[account(mut, seeds = [SURPLUS_BID_ESCROW_SEED, auction.key().as_ref()], bump)]
+    pub bid_escrow: Account<'info, TokenAccount>,
+    #[account(mut, address = lp_pool.lp_token_mint)]
+    pub lp_token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [LP_VAULT_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+    /// Winner's account for the lot, required if there was a bid
+    #[account(
+        mut,
+        token::mint = lp_pool.borrowable_mint,
+        constraint = winner_token_account.owner == auction.bidder @ LegasiError::Unauthorized
+    )]
+    pub winner_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut, seeds = [PROTOCOL_SEED], bump = protocol.bump, seeds::program = legasi_core::ID)]
     pub protocol: Account<'info, Protocol>,
-    /// CHECK: Lending program authority
-    pub lending_authority: Signer<'info>,
+    /// CHECK: PDA that signs protocol totals updates
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
+    /// CHECK: paid the auction's rent, receives it back
+    #[account(mut)]
+    pub starter: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshRates<'info> {
+    #[account(mut, seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
+    pub lp_pool: Account<'info, LpPool>,
+    /// Borrowable config of the pool's asset (owned by core program)
+    #[account(
+        mut,
+        seeds = [BORROWABLE_SEED, lp_pool.borrowable_mint.as_ref()],
+        bump = borrowable.bump,
+        seeds::program = legasi_core::ID
+    )]
+    pub borrowable: Account<'info, Borrowable>,
+    /// CHECK: PDA that signs the rate update
+    #[account(seeds = [PROTOCOL_WRITER_SEED], bump)]
+    pub protocol_writer: UncheckedAccount<'info>,
+    pub core_program: Program<'info, LegasiCore>,
 }
 
 #[derive(Accounts)]
 pub struct GetExchangeRate<'info> {
-    #[account(seeds = [b"lp_pool", lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
+    #[account(seeds = [LP_POOL_SEED, lp_pool.borrowable_mint.as_ref()], bump = lp_pool.bump)]
     pub lp_pool: Account<'info, LpPool>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lp_pool_layout() {
+        // Lending, flash, and off-chain clients read this account
+        assert_eq!(
+            LpPool::INIT_SPACE,
+            32 + 32
+                + 8
+                + 8
+                + 8
+                + 8
+                + OutflowLimiter::INIT_SPACE
+                + 2
+                + 8
+                + 16
+                + 1
+                + 8
+                + 16
+                + 8
+                + WithdrawQueue::INIT_SPACE
+                + 8
+                + 16
+                + 1
+                + 8
+                + 2
+                + 8
+                + 1
+        );
+    }
+
+    #[test]
+    fn test_shares_for_deposit() {
+        let mut pool = LpPool {
+            borrowable_mint: Pubkey::default(),
+            lp_token_mint: Pubkey::default(),
+            total_deposits: 0,
+            total_shares: 0,
+            total_borrowed: 0,
+            interest_earned: 0,
+            outflow_limiter: OutflowLimiter::default(),
+            max_utilization_bps: 0,
+            locked_shares: 0,
+            lock_rebate_per_share: 0,
+            permissioned: false,
+            bad_debt: 0,
+            borrow_index: BORROW_INDEX_PRECISION,
+            index_updated_at: 0,
+            withdraw_queue: WithdrawQueue::default(),
+            locked_deposit_weight: 0,
+            deposit_boost_per_weight: 0,
+            protocol_fee_enabled: false,
+            protocol_fees_owed: 0,
+            reserve_factor_bps: 0,
+            reserves_pending: 0,
+            insurance_pending: 0,
+            bump: 0,
+        };
+        // First deposit is 1:1
+        assert_eq!(pool.shares_for_deposit(1_000).unwrap(), 1_000);
+
+        // After 10% yield, the same deposit buys fewer shares
+        pool.total_deposits = 1_100;
+        pool.total_shares = 1_000;
+        assert_eq!(pool.shares_for_deposit(1_100).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_redeem_with_borrows() {
+        let pool = LpPool {
+            borrowable_mint: Pubkey::default(),
+            lp_token_mint: Pubkey::default(),
+            total_deposits: 1_100,
+            total_shares: 1_000,
+            total_borrowed: 800,
+            interest_earned: 100,
+            outflow_limiter: OutflowLimiter::default(),
+            max_utilization_bps: 0,
+            locked_shares: 0,
+            lock_rebate_per_share: 0,
+            permissioned: false,
+            bad_debt: 0,
+            borrow_index: BORROW_INDEX_PRECISION,
+            index_updated_at: 0,
+            withdraw_queue: WithdrawQueue::default(),
+            locked_deposit_weight: 0,
+            deposit_boost_per_weight: 0,
+            protocol_fee_enabled: false,
+            protocol_fees_owed: 0,
+            reserve_factor_bps: 0,
+            reserves_pending: 0,
+            insurance_pending: 0,
+            bump: 0,
+        };
+        assert_eq!(pool.available_liquidity(), 300);
+        assert_eq!(pool.utilization_bps(), 7_272);
+
+        // Borrows don't change the rate: 100 shares are still worth 110
+        assert_eq!(pool.redeem(100, 300).unwrap(), (100, 110));
+
+        // Only 300 is liquid: burn the 272 shares it pays for, keep the rest
+        assert_eq!(pool.redeem(1_000, 300).unwrap(), (272, 299));
+
+        // A short vault caps the fill too
+        assert_eq!(pool.redeem(1_000, 100).unwrap(), (90, 99));
+
+        // Fully lent out: nothing to pay
+        let lent_out = LpPool {
+            total_borrowed: 1_100,
+            ..pool
+        };
+        assert_eq!(lent_out.redeem(1_000, 1_100).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_utilization_pause() {
+        let mut pool = LpPool {
+            borrowable_mint: Pubkey::default(),
+            lp_token_mint: Pubkey::default(),
+            total_deposits: 10_000,
+            total_shares: 10_000,
+            total_borrowed: 9_700,
+            interest_earned: 0,
+            outflow_limiter: OutflowLimiter::default(),
+            max_utilization_bps: 9_800,
+            locked_shares: 0,
+            lock_rebate_per_share: 0,
+            permissioned: false,
+            bad_debt: 0,
+            borrow_index: BORROW_INDEX_PRECISION,
+            index_updated_at: 0,
+            withdraw_queue: WithdrawQueue::default(),
+            locked_deposit_weight: 0,
+            deposit_boost_per_weight: 0,
+            protocol_fee_enabled: false,
+            protocol_fees_owed: 0,
+            reserve_factor_bps: 0,
+            reserves_pending: 0,
+            insurance_pending: 0,
+            bump: 0,
+        };
+        // Up to 98% may be lent, not a unit more
+        assert!(pool.check_utilization(100).is_ok());
+        assert!(pool.check_utilization(101).is_err());
+
+        // Above the cap even flash loans stop, until repays bring it back under
+        pool.total_borrowed = 9_900;
+        assert!(pool.check_utilization(0).is_err());
+        pool.total_borrowed = 9_500;
+        assert!(pool.check_utilization(0).is_ok());
+
+        pool.max_utilization_bps = 0;
+        pool.total_borrowed = 10_000;
+        assert!(pool.check_utilization(1).is_ok());
+    }
+
+    #[test]
+    fn test_lendable_sets_aside_queued_withdrawals() {
+        let mut pool = LpPool {
+            borrowable_mint: Pubkey::default(),
+            lp_token_mint: Pubkey::default(),
+            total_deposits: 11_000,
+            total_shares: 10_000,
+            total_borrowed: 9_000,
+            interest_earned: 0,
+            outflow_limiter: OutflowLimiter::default(),
+            max_utilization_bps: 9_800,
+            locked_shares: 0,
+            lock_rebate_per_share: 0,
+            permissioned: false,
+            bad_debt: 0,
+            borrow_index: BORROW_INDEX_PRECISION,
+            index_updated_at: 0,
+            withdraw_queue: WithdrawQueue::default(),
+            locked_deposit_weight: 0,
+            deposit_boost_per_weight: 0,
+            protocol_fee_enabled: false,
+            protocol_fees_owed: 0,
+            reserve_factor_bps: 0,
+            reserves_pending: 0,
+            insurance_pending: 0,
+            bump: 0,
+        };
+        assert_eq!(pool.lendable(2_000).unwrap(), 2_000);
+
+        // 1,000 queued shares are owed 1,100
+        pool.withdraw_queue.push(1_000);
+        assert_eq!(pool.lendable(2_000).unwrap(), 900);
+        assert_eq!(pool.lendable(1_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_borrow_index_follows_utilization() {
+        let year = SECONDS_PER_YEAR as i64;
+        let mut pool = LpPool {
+            borrowable_mint: Pubkey::default(),
+            lp_token_mint: Pubkey::default(),
+            total_deposits: 10_000,
+            total_shares: 10_000,
+            total_borrowed: 8_000,
+            interest_earned: 0,
+            outflow_limiter: OutflowLimiter::default(),
+            max_utilization_bps: 0,
+            locked_shares: 0,
+            lock_rebate_per_share: 0,
+            permissioned: false,
+            bad_debt: 0,
+            borrow_index: BORROW_INDEX_PRECISION,
+            index_updated_at: 0,
+            withdraw_queue: WithdrawQueue::default(),
+            locked_deposit_weight: 0,
+            deposit_boost_per_weight: 0,
+            protocol_fee_enabled: false,
+            protocol_fees_owed: 0,
+            reserve_factor_bps: 0,
+            reserves_pending: 0,
+            insurance_pending: 0,
+            bump: 0,
+        };
+        // 11% at the 80% kink, read without writing the pool; LPs earn 80% of it
+        // less the 5% insurance cut
+        assert_eq!(pool.borrow_rate_bps(), 1_100);
+        assert_eq!(pool.supply_rate_bps(), 836);
+        // With the fee switch on, 20% more of it goes to the treasury
+        pool.protocol_fee_enabled = true;
+        assert_eq!(pool.supply_rate_bps(), 660);
+        assert_eq!(pool.protocol_fee(1_000), 200);
+        pool.protocol_fee_enabled = false;
+        assert_eq!(pool.protocol_fee(1_000), 0);
+        // A 10% reserve factor
+        pool.reserve_factor_bps = 1_000;
+        assert_eq!(pool.supply_rate_bps(), 748);
+        assert_eq!(pool.reserve_cut(1_000), 100);
+        pool.reserve_factor_bps = 0;
+        assert_eq!(
+            pool.borrow_index_at(year).unwrap(),
+            BORROW_INDEX_PRECISION / 100 * 111
+        );
+
+        // Half a year at 11%, then a repay drops it to the 3% base for the rest
+        pool.update_borrow_index(year / 2).unwrap();
+        assert_eq!(pool.borrow_index, BORROW_INDEX_PRECISION / 1_000 * 1_055);
+        pool.total_borrowed = 0;
+        assert_eq!(pool.supply_rate_bps(), 0);
+        assert_eq!(
+            pool.borrow_index_at(year).unwrap(),
+            BORROW_INDEX_PRECISION / 1_000_000 * 1_070_825
+        );
+
+        // A clock behind the last roll leaves it as is
+        pool.update_borrow_index(0).unwrap();
+        assert_eq!(pool.index_updated_at, year / 2);
+        assert_eq!(pool.borrow_index, BORROW_INDEX_PRECISION / 1_000 * 1_055);
+    }
+
+    #[test]
+    fn test_write_off_bad_debt() {
+        let mut pool = LpPool {
+            borrowable_mint: Pubkey::default(),
+            lp_token_mint: Pubkey::default(),
+            total_deposits: 10_000,
+            total_shares: 10_000,
+            total_borrowed: 5_000,
+            interest_earned: 0,
+            outflow_limiter: OutflowLimiter::default(),
+            max_utilization_bps: 0,
+            locked_shares: 0,
+            lock_rebate_per_share: 0,
+            permissioned: false,
+            bad_debt: 0,
+            borrow_index: BORROW_INDEX_PRECISION,
+            index_updated_at: 0,
+            withdraw_queue: WithdrawQueue::default(),
+            locked_deposit_weight: 0,
+            deposit_boost_per_weight: 0,
+            protocol_fee_enabled: false,
+            protocol_fees_owed: 0,
+            reserve_factor_bps: 0,
+            reserves_pending: 0,
+            insurance_pending: 0,
+            bump: 0,
+        };
+        // 1,000 lost, 400 of it paid from insurance money already in the vault
+        pool.insurance_pending = 500;
+        assert_eq!(pool.insurance_cover(1_000, 400), 400);
+        assert_eq!(pool.insurance_cover(1_000, 2_000), 500);
+        pool.write_off(1_000, 400).unwrap();
+        assert_eq!(pool.total_borrowed, 4_000);
+        assert_eq!(pool.total_deposits, 9_400);
+        assert_eq!(pool.bad_debt, 600);
+        assert_eq!(pool.tokens_for_shares(1_000).unwrap(), 940);
+        assert_eq!(pool.insurance_pending, 100);
+
+        assert!(pool.write_off(100, 101).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Time-locked deposits
+//!
+//! An LP can commit a deposit for 30, 90 or 180 days with deposit_locked. Its shares
+//! are minted into an escrow owned by a `LockedDeposit`, and earn interest as if they
+//! were `lock_multiplier_bps` times as many: the lock's bonus weight (the shares
+//! above 1x) counts as virtual shares when interest is split, and that cut is left
+//! in the vault uncounted, tracked by `LpPool.deposit_boost_per_weight`. The boost
+//! comes out of unlocked LPs' interest, never out of their principal.
+//!
+//! unlock_and_withdraw redeems the escrowed shares to the owner, boost included once
+//! `unlock_at` has passed. Leaving early forfeits the boost back to the pool and
+//! costs an `EARLY_EXIT_PENALTY_BPS` penalty, decaying linearly over the term, which
+//! goes to the insurance fund.
+//!
+//! Flow:
+//! 1. LP calls deposit_locked with an amount and a term
+//! 2. Each repay's interest feeds the boost into `LpPool.deposit_boost_per_weight`
+//! 3. LP calls unlock_and_withdraw, at or before `unlock_at`
+
+use anchor_lang::prelude::*;
+use legasi_core::constants::BPS_DENOMINATOR;
+
+use crate::REBATE_PRECISION;
+
+/// Penalty on leaving at once, decaying linearly to 0 at `unlock_at` (bps)
+pub const EARLY_EXIT_PENALTY_BPS: u64 = 1_000; // 10%
+
+/// Share multiplier of a `days` term (bps), None for an unsupported term
+pub fn lock_multiplier_bps(days: u16) -> Option<u16> {
+    match days {
+        30 => Some(11_000),
+        90 => Some(12_500),
+        180 => Some(15_000),
+        _ => None,
+    }
+}
+
+/// An LP's time-locked deposit in one pool
+#[account]
+#[derive(InitSpace)]
+pub struct LockedDeposit {
+    pub owner: Pubkey,
+    pub lp_pool: Pubkey,
+    /// Shares in escrow
+    pub shares: u64,
+    pub multiplier_bps: u16,
+    /// Bonus weight: `shares` above 1x at `multiplier_bps`
+    pub weight: u64,
+    pub locked_at: i64,
+    pub unlock_at: i64,
+    /// `deposit_boost_per_weight` already accounted for at lock time (scaled)
+    pub boost_debt: u128,
+    pub bump: u8,
+}
+
+impl LockedDeposit {
+    /// Boost earned so far (underlying units)
+    pub fn boost(&self, boost_per_weight: u128) -> u64 {
+        (self.weight as u128 * boost_per_weight / REBATE_PRECISION).saturating_sub(self.boost_debt)
+            as u64
+    }
+
+    /// Penalty on redeeming for `amount` at `now` (0 once unlocked)
+    pub fn early_exit_penalty(&self, amount: u64, now: i64) -> u64 {
+        let term = self.unlock_at.saturating_sub(self.locked_at);
+        let remaining = self.unlock_at.saturating_sub(now);
+        if term <= 0 || remaining <= 0 {
+            return 0;
+        }
+        (amount as u128 * EARLY_EXIT_PENALTY_BPS as u128 * remaining as u128
+            / (BPS_DENOMINATOR as u128 * term as u128)) as u64
+    }
+}
+
+/// Bonus weight of `shares` at `multiplier_bps`
+pub fn lock_weight(shares: u64, multiplier_bps: u16) -> u64 {
+    (shares as u128 * (multiplier_bps as u64).saturating_sub(BPS_DENOMINATOR) as u128
+        / BPS_DENOMINATOR as u128) as u64
+}
+
+/// Split LP interest into (credited to every share, boost for locked deposits), the
+/// boost weighing `weight` virtual shares against `total_shares`
+pub fn split_deposit_boost(lp_interest: u64, total_shares: u64, weight: u64) -> (u64, u64) {
+    if weight == 0 {
+        return (lp_interest, 0);
+    }
+    let boost =
+        (lp_interest as u128 * weight as u128 / (total_shares as u128 + weight as u128)) as u64;
+    (lp_interest - boost, boost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terms() {
+        assert_eq!(lock_multiplier_bps(90), Some(12_500));
+        assert_eq!(lock_multiplier_bps(60), None);
+        assert_eq!(lock_weight(1_000, 12_500), 250);
+        assert_eq!(lock_weight(1_000, 10_000), 0);
+    }
+
+    #[test]
+    fn test_boost_as_virtual_shares() {
+        // 1,000 shares, 500 of them locked at 1.5x: 250 virtual shares on top
+        assert_eq!(split_deposit_boost(1_250, 1_000, 0), (1_250, 0));
+        let (credited, boost) = split_deposit_boost(1_250, 1_000, 250);
+        assert_eq!((credited, boost), (1_000, 250));
+
+        let lock = LockedDeposit {
+            owner: Pubkey::default(),
+            lp_pool: Pubkey::default(),
+            shares: 500,
+            multiplier_bps: 15_000,
+            weight: 250,
+            locked_at: 0,
+            unlock_at: 1_000,
+            boost_debt: 0,
+            bump: 0,
+        };
+        // Its 500 shares get 500 of the credit, plus the whole boost: 1.5x an
+        // unlocked share
+        assert_eq!(lock.boost(boost as u128 * REBATE_PRECISION / 250), 250);
+    }
+
+    #[test]
+    fn test_early_exit_penalty() {
+        let lock = LockedDeposit {
+            owner: Pubkey::default(),
+            lp_pool: Pubkey::default(),
+            shares: 1_000,
+            multiplier_bps: 11_000,
+            weight: 100,
+            locked_at: 1_000,
+            unlock_at: 2_000,
+            boost_debt: 0,
+            bump: 0,
+        };
+        assert_eq!(lock.early_exit_penalty(10_000, 1_000), 1_000);
+        assert_eq!(lock.early_exit_penalty(10_000, 1_500), 500);
+        assert_eq!(lock.early_exit_penalty(10_000, 2_000), 0);
+        assert_eq!(lock.early_exit_penalty(10_000, 3_000), 0);
+    }
+}
@@ -0,0 +1,159 @@
+//! Reward emissions
+//!
+//! A pool can stream a reward token to its LPs without off-chain airdrops. The admin
+//! opens a `RewardConfig` for the pool with initialize_rewards, tops up its reward
+//! vault with fund_rewards and sets a per-second emission rate. LPs stake bUSDC into
+//! the config's stake vault and earn the emissions pro rata to their stake, settled
+//! into `UserRewardState.pending` on every stake, unstake and claim.
+//!
+//! Rewards follow staked shares rather than raw token balances: bUSDC moves freely
+//! between accounts, so balance snapshots could count the same shares twice. Staked
+//! shares stay in `total_shares` and keep earning interest.
+//!
+//! Emissions only run while something is staked, and never past what was funded: an
+//! unfunded or idle stretch emits nothing, and the remainder stays in the vault.
+//!
+//! Flow:
+//! 1. Admin calls initialize_rewards, fund_rewards and set_emission_rate
+//! 2. LPs call stake_for_rewards / unstake_from_rewards
+//! 3. LPs call claim_rewards for their pending rewards
+
+use anchor_lang::prelude::*;
+
+/// Fixed-point scale of `RewardConfig.reward_per_share`
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Reward emissions of one pool
+#[account]
+#[derive(InitSpace)]
+pub struct RewardConfig {
+    pub lp_pool: Pubkey,
+    pub reward_mint: Pubkey,
+    /// Reward tokens emitted per second across all stakers
+    pub emission_rate: u64,
+    /// Rewards per staked share emitted so far, scaled by `REWARD_PRECISION`
+    pub reward_per_share: u128,
+    /// Emissions are settled up to here
+    pub last_update: i64,
+    /// Shares in the stake vault
+    pub total_staked: u64,
+    /// Funded rewards not emitted yet
+    pub undistributed: u64,
+    pub bump: u8,
+}
+
+impl RewardConfig {
+    /// Emit the rewards due since `last_update` into `reward_per_share`. Nothing is
+    /// emitted while nothing is staked, nor beyond `undistributed`. Returns the emitted
+    /// amount
+    pub fn update(&mut self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.last_update).max(0) as u128;
+        self.last_update = self.last_update.max(now);
+        if self.total_staked == 0 {
+            return 0;
+        }
+        let emitted = (self.emission_rate as u128 * elapsed).min(self.undistributed as u128);
+        self.reward_per_share = self
+            .reward_per_share
+            .saturating_add(emitted * REWARD_PRECISION / self.total_staked as u128);
+        self.undistributed -= emitted as u64;
+        emitted as u64
+    }
+}
+
+/// An LP's stake in a pool's emissions
+#[account]
+#[derive(InitSpace)]
+pub struct UserRewardState {
+    pub owner: Pubkey,
+    pub reward_config: Pubkey,
+    pub staked: u64,
+    /// `staked * reward_per_share / REWARD_PRECISION` already settled
+    pub reward_debt: u128,
+    /// Settled rewards not claimed yet
+    pub pending: u64,
+    pub bump: u8,
+}
+
+impl UserRewardState {
+    /// Move the rewards earned since the last settlement into `pending`, for a config
+    /// already updated to `reward_per_share`
+    pub fn settle(&mut self, reward_per_share: u128) {
+        let accrued = self.staked as u128 * reward_per_share / REWARD_PRECISION;
+        let earned = accrued.saturating_sub(self.reward_debt);
+        self.pending = self.pending.saturating_add(earned as u64);
+        self.reward_debt = accrued;
+    }
+
+    /// Settle, then set the stake to `staked` shares
+    pub fn restake(&mut self, staked: u64, reward_per_share: u128) {
+        self.settle(reward_per_share);
+        self.staked = staked;
+        self.reward_debt = staked as u128 * reward_per_share / REWARD_PRECISION;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RewardConfig {
+        RewardConfig {
+            lp_pool: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            emission_rate: 10,
+            reward_per_share: 0,
+            last_update: 1_000,
+            total_staked: 0,
+            undistributed: 1_000_000,
+            bump: 0,
+        }
+    }
+
+    fn user() -> UserRewardState {
+        UserRewardState {
+            owner: Pubkey::default(),
+            reward_config: Pubkey::default(),
+            staked: 0,
+            reward_debt: 0,
+            pending: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_emissions_split_pro_rata() {
+        let mut config = config();
+        // Nothing staked: nothing emitted, the funds wait
+        assert_eq!(config.update(1_100), 0);
+        assert_eq!(config.undistributed, 1_000_000);
+
+        let (mut alice, mut bob) = (user(), user());
+        alice.restake(250, config.reward_per_share);
+        config.total_staked = 250;
+        assert_eq!(config.update(1_200), 1_000);
+
+        // Bob joins with 750: the next 1,000 splits 25/75
+        bob.restake(750, config.reward_per_share);
+        config.total_staked = 1_000;
+        config.update(1_300);
+        alice.settle(config.reward_per_share);
+        bob.settle(config.reward_per_share);
+        assert_eq!(alice.pending, 1_250);
+        assert_eq!(bob.pending, 750);
+
+        // Settling twice pays nothing more
+        alice.settle(config.reward_per_share);
+        assert_eq!(alice.pending, 1_250);
+    }
+
+    #[test]
+    fn test_emissions_stop_at_funding() {
+        let mut config = config();
+        config.undistributed = 500;
+        config.total_staked = 100;
+        assert_eq!(config.update(1_100), 500);
+        assert_eq!(config.update(2_000), 0);
+        assert_eq!(config.undistributed, 0);
+    }
+}
@@ -0,0 +1,109 @@
+//! Surplus auctions
+//!
+//! Insurance cuts of interest stay in each LP vault uncounted and are booked on
+//! `Protocol.insurance_fund`. Once the fund exceeds `Protocol.insurance_fund_target`,
+//! anyone can auction a lot of the surplus from a pool's vault for that pool's LP
+//! shares (bUSDC), Maker flap style: bids are shares, each must beat the last by
+//! `MIN_BID_INCREMENT_BPS`, and the winning shares are burned. Burning shares
+//! without touching `total_deposits` raises the share price, so the surplus flows
+//! back to the pool's LPs.
+//!
+//! Flow:
+//! 1. Anyone calls start_surplus_auction: the lot is debited from the insurance fund
+//! 2. Bidders call bid_surplus_auction; the outbid shares are refunded from escrow
+//! 3. Once `ends_at` passes, anyone calls settle_surplus_auction: the winner gets the
+//!    lot and their shares are burned. With no bids the lot goes back to the fund
+
+use anchor_lang::prelude::*;
+use legasi_core::constants::{BPS_DENOMINATOR, SECONDS_PER_DAY};
+
+/// Auction length from the start (seconds)
+pub const SURPLUS_AUCTION_DURATION: i64 = SECONDS_PER_DAY;
+
+/// A bid this close to the end pushes `ends_at` out to `now + SURPLUS_BID_EXTENSION`
+pub const SURPLUS_BID_EXTENSION: i64 = 3_600; // 1 hour
+
+/// Least a bid must beat the previous one by (bps)
+pub const MIN_BID_INCREMENT_BPS: u64 = 500; // 5%
+
+/// A running auction of insurance surplus for LP shares
+#[account]
+#[derive(InitSpace)]
+pub struct SurplusAuction {
+    pub lp_pool: Pubkey,
+    /// Paid the rent, refunded at settlement
+    pub starter: Pubkey,
+    /// Underlying tokens sold
+    pub lot: u64,
+    /// Top bid in LP shares (0 = no bids)
+    pub bid: u64,
+    pub bidder: Pubkey,
+    pub ends_at: i64,
+    pub bump: u8,
+}
+
+impl SurplusAuction {
+    /// Smallest acceptable next bid
+    pub fn min_next_bid(&self) -> u64 {
+        if self.bid == 0 {
+            return 1;
+        }
+        let increment =
+            (self.bid as u128 * MIN_BID_INCREMENT_BPS as u128 + BPS_DENOMINATOR as u128 - 1)
+                / BPS_DENOMINATOR as u128;
+        self.bid.saturating_add(increment as u64)
+    }
+
+    /// `ends_at` after a bid at `now`
+    pub fn extended_end(&self, now: i64) -> i64 {
+        self.ends_at.max(now.saturating_add(SURPLUS_BID_EXTENSION))
+    }
+}
+
+/// Insurance fund above `target`, available to auction (nothing while `target` is 0)
+pub fn insurance_surplus(insurance_fund: u64, target: u64) -> u64 {
+    if target == 0 {
+        return 0;
+    }
+    insurance_fund.saturating_sub(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auction(bid: u64) -> SurplusAuction {
+        SurplusAuction {
+            lp_pool: Pubkey::default(),
+            starter: Pubkey::default(),
+            lot: 1_000_000,
+            bid,
+            bidder: Pubkey::default(),
+            ends_at: 10_000,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_min_next_bid() {
+        assert_eq!(auction(0).min_next_bid(), 1);
+        assert_eq!(auction(1_000).min_next_bid(), 1_050);
+        // Rounded up, and always strictly higher
+        assert_eq!(auction(1_001).min_next_bid(), 1_052);
+        assert_eq!(auction(1).min_next_bid(), 2);
+    }
+
+    #[test]
+    fn test_late_bid_extends_auction() {
+        let auction = auction(1_000);
+        assert_eq!(auction.extended_end(1_000), 10_000);
+        assert_eq!(auction.extended_end(9_000), 9_000 + SURPLUS_BID_EXTENSION);
+    }
+
+    #[test]
+    fn test_insurance_surplus() {
+        assert_eq!(insurance_surplus(5_000, 0), 0);
+        assert_eq!(insurance_surplus(5_000, 3_000), 2_000);
+        assert_eq!(insurance_surplus(2_000, 3_000), 0);
+    }
+}
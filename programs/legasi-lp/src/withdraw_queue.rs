@@ -0,0 +1,95 @@
+//! Withdrawal queue
+//!
+//! `withdraw` only pays out unlent liquidity, so at high utilization LPs would race
+//! for whatever each repayment frees up. Instead, an LP can queue: request_withdraw
+//! moves its shares into an escrow owned by a `WithdrawRequest`, numbered in arrival
+//! order. Anyone can crank process_withdraw_queue on the request at the head of the
+//! queue; it redeems as many of the escrowed shares as current liquidity pays for,
+//! and moves on to the next request once the head is paid in full.
+//!
+//! Queued shares keep earning interest until they are redeemed. While requests are
+//! queued, `withdraw` is closed so liquidity goes to the queue first.
+//!
+//! Flow:
+//! 1. LP calls request_withdraw with the shares to exit
+//! 2. Anyone cranks process_withdraw_queue as borrowers repay
+//! 3. The request and its escrow close once paid in full, rent back to the LP
+
+use anchor_lang::prelude::*;
+
+/// FIFO withdrawal queue of an `LpPool`
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub struct WithdrawQueue {
+    /// Id the next request gets
+    pub next_id: u64,
+    /// Id of the request paid out next (== `next_id` when the queue is empty)
+    pub head: u64,
+    /// Shares escrowed across queued requests
+    pub queued_shares: u64,
+}
+
+impl WithdrawQueue {
+    pub fn is_empty(&self) -> bool {
+        self.head == self.next_id
+    }
+
+    /// Queue `shares` at the tail. Returns the request's id
+    pub fn push(&mut self, shares: u64) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.queued_shares = self.queued_shares.saturating_add(shares);
+        id
+    }
+
+    /// Record `shares` of the head request redeemed, popping it once `fully_paid`
+    pub fn redeemed(&mut self, shares: u64, fully_paid: bool) {
+        self.queued_shares = self.queued_shares.saturating_sub(shares);
+        if fully_paid {
+            self.head += 1;
+        }
+    }
+}
+
+/// An LP's queued withdrawal
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawRequest {
+    pub lp_pool: Pubkey,
+    pub owner: Pubkey,
+    /// Position in the pool's queue
+    pub id: u64,
+    /// Shares still escrowed
+    pub shares: u64,
+    /// Paid out so far (underlying units)
+    pub paid: u64,
+    pub requested_at: i64,
+    pub bump: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_is_fifo() {
+        let mut queue = WithdrawQueue::default();
+        assert!(queue.is_empty());
+        assert_eq!(queue.push(100), 0);
+        assert_eq!(queue.push(50), 1);
+        assert_eq!(queue.queued_shares, 150);
+
+        // A partial fill keeps the head in place
+        queue.redeemed(60, false);
+        assert_eq!(queue.head, 0);
+        queue.redeemed(40, true);
+        assert_eq!(queue.head, 1);
+        assert!(!queue.is_empty());
+
+        queue.redeemed(50, true);
+        assert!(queue.is_empty());
+        assert_eq!(queue.queued_shares, 0);
+        assert_eq!(queue.push(10), 2);
+    }
+}
@@ -72,9 +72,9 @@ async function main() {
     PROGRAM_IDS.core
   );
   
-  const [lendingVaultPda] = PublicKey.findProgramAddressSync(
-    [Buffer.from('lending_vault'), CIRCLE_USDC_DEVNET.toBuffer()],
-    PROGRAM_IDS.lending
+  const [lpVaultPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('lp_vault'), CIRCLE_USDC_DEVNET.toBuffer()],
+    PROGRAM_IDS.lp
   );
   
   const [lpPoolPda] = PublicKey.findProgramAddressSync(
@@ -84,7 +84,7 @@ async function main() {
   
   console.log('\n📍 PDAs (Circle USDC):');
   console.log('Protocol:', protocolPda.toBase58());
-  console.log('Lending Vault:', lendingVaultPda.toBase58());
+  console.log('LP Vault:', lpVaultPda.toBase58());
   console.log('LP Pool:', lpPoolPda.toBase58());
   
   // Save config
@@ -98,7 +98,7 @@ async function main() {
     },
     pdas: {
       protocol: protocolPda.toBase58(),
-      lendingVault: lendingVaultPda.toBase58(),
+      lpVault: lpVaultPda.toBase58(),
       lpPool: lpPoolPda.toBase58()
     }
   };
@@ -3,7 +3,7 @@
  */
 
 import * as anchor from '@coral-xyz/anchor';
-import { Connection, Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import { Connection, Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from '@solana/web3.js';
 import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
 import * as fs from 'fs';
 import * as path from 'path';
@@ -14,6 +14,9 @@ const PROGRAM_IDS = {
   legasi_lp: new PublicKey('CTwY4VSeueesSBc95G38X3WJYPriJEzyxjcCaZAc5LbY'),
 };
 
+const TOKEN_METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
+const METADATA_URI_BASE = 'https://agentic.legasi.io/tokens';
+
 // Fresh test tokens
 const TEST_USDC_MINT = new PublicKey('3J2i1X4VGSxkEiHdnq4zead7hiSYbQHs9ZZaS36yAfX8');
 const TEST_EURC_MINT = new PublicKey('6KeaPv9QA3VYaf62dfDzC785U8Cfa5VbsgtBH5ZWWf7v');
@@ -42,6 +45,11 @@ async function initPool(
     PROGRAM_IDS.legasi_lp
   );
 
+  const [metadataPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), lpTokenMintPda.toBuffer()],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+
   // Check if pool exists
   const existingPool = await connection.getAccountInfo(lpPoolPda);
   
@@ -70,19 +78,26 @@ async function initPool(
   const existingMint = await connection.getAccountInfo(lpTokenMintPda);
   
   if (!existingMint) {
-    console.log(`   Creating pool accounts (mint + vault)...`);
+    console.log(`   Creating pool accounts (mint + vault + metadata)...`);
     try {
       // @ts-ignore
       await lpProgram.methods
-        .initializePoolAccounts()
+        .initializePoolAccounts(
+          `Legasi ${name}`,
+          `b${name}`,
+          `${METADATA_URI_BASE}/b${name.toLowerCase()}.json`
+        )
         .accounts({
           lpPool: lpPoolPda,
           lpTokenMint: lpTokenMintPda,
           vault: vaultPda,
           borrowableMint: mint,
+          metadata: metadataPda,
           admin: payer.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
+          tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
         })
         .rpc();
       console.log(`   ✅ Pool accounts created`);
@@ -20,6 +20,9 @@ const WRAPPED_SOL_MINT = new PublicKey('So11111111111111111111111111111111111111
 // For devnet, create a test cbBTC
 const TEST_CBBTC_MINT = new PublicKey('2qknSJxAg5gxCFUKZcCzAr9QWDhGSS6uqh67ojmrdpZc'); // Real test cbBTC
 
+// Test EURC - its feed carries EUR/USD, used to value EURC debt in LTV checks
+const TEST_EURC_MINT = new PublicKey('6KeaPv9QA3VYaf62dfDzC785U8Cfa5VbsgtBH5ZWWf7v');
+
 async function main() {
   console.log('📈 Initializing Price Feeds\n');
 
@@ -66,6 +69,12 @@ async function main() {
       price: 45000_000000, // $45,000
       mint: TEST_CBBTC_MINT, // Placeholder
     },
+    {
+      asset: { eurc: {} },
+      name: 'EURC',
+      price: 1_080000, // $1.08 (EUR/USD)
+      mint: TEST_EURC_MINT,
+    },
   ];
 
   for (const feed of priceFeeds) {
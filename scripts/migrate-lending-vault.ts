@@ -0,0 +1,106 @@
+/**
+ * Migrate deprecated Lending Vaults into the LP pool vaults
+ *
+ * Borrows and repays now settle against [lp_vault, mint] (LP program) only.
+ * This moves whatever is left in [lending_vault, mint] (lending program)
+ * into the LP vault and closes the old account. Safe to re-run.
+ */
+
+import * as anchor from '@coral-xyz/anchor';
+import { Connection, Keypair, PublicKey } from '@solana/web3.js';
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import * as fs from 'fs';
+import * as path from 'path';
+
+const DEVNET_RPC = 'https://api.devnet.solana.com';
+
+const PROGRAMS = {
+  core: new PublicKey('4FW9iFaerNuX1GstRKSsWo9UfnTbjtqch3fEHkWMF1Uy'),
+  lending: new PublicKey('9356RoSbLTzWE55ab6GktcTocaNhPuBEDZvsmqjkCZYw'),
+  lp: new PublicKey('CTwY4VSeueesSBc95G38X3WJYPriJEzyxjcCaZAc5LbY'),
+};
+
+const USDC_MINT = new PublicKey('3J2i1X4VGSxkEiHdnq4zead7hiSYbQHs9ZZaS36yAfX8');
+const EURC_MINT = new PublicKey('6KeaPv9QA3VYaf62dfDzC785U8Cfa5VbsgtBH5ZWWf7v');
+
+async function main() {
+  console.log('🏦 Migrating Lending Vaults → LP Vaults\n');
+
+  const connection = new Connection(DEVNET_RPC, 'confirmed');
+  const payer = Keypair.fromSecretKey(
+    new Uint8Array(JSON.parse(fs.readFileSync(
+      path.join(process.env.HOME || '~', '.config/solana/id.json'),
+      'utf-8'
+    )))
+  );
+  
+  const provider = new anchor.AnchorProvider(
+    connection,
+    new anchor.Wallet(payer),
+    { commitment: 'confirmed' }
+  );
+
+  const lendingIdl = JSON.parse(fs.readFileSync(
+    path.join(__dirname, '../target/idl/legasi_lending.json'),
+    'utf-8'
+  ));
+  // @ts-ignore
+  const lendingProgram = new anchor.Program(lendingIdl, provider);
+
+  console.log(`👛 Admin: ${payer.publicKey.toBase58()}`);
+
+  const [protocolPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('protocol')],
+    PROGRAMS.core
+  );
+
+  const mints = [
+    { mint: USDC_MINT, name: 'USDC' },
+    { mint: EURC_MINT, name: 'EURC' },
+  ];
+
+  for (const { mint, name } of mints) {
+    const [lendingVaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('lending_vault'), mint.toBuffer()],
+      PROGRAMS.lending
+    );
+    const [lpVaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('lp_vault'), mint.toBuffer()],
+      PROGRAMS.lp
+    );
+
+    console.log(`\n📍 ${name}`);
+    console.log(`   Lending vault: ${lendingVaultPda.toBase58()}`);
+    console.log(`   LP vault:      ${lpVaultPda.toBase58()}`);
+
+    if (!(await connection.getAccountInfo(lendingVaultPda))) {
+      console.log(`   ℹ️  No lending vault, nothing to migrate`);
+      continue;
+    }
+    if (!(await connection.getAccountInfo(lpVaultPda))) {
+      console.log(`   ❌ LP vault missing - run init-lp-full.ts first`);
+      continue;
+    }
+
+    try {
+      // @ts-ignore
+      const tx = await lendingProgram.methods
+        .migrateLendingVault()
+        .accounts({
+          protocol: protocolPda,
+          lendingVault: lendingVaultPda,
+          lpVault: lpVaultPda,
+          admin: payer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      console.log(`   ✅ Migrated and closed (${tx.slice(0, 20)}...)`);
+    } catch (e: any) {
+      console.log(`   ❌ Error: ${e.message.slice(0, 80)}`);
+    }
+  }
+
+  console.log('\n✅ Migration done');
+}
+
+main().catch(console.error);
@@ -68,9 +68,15 @@ async function main() {
     PROGRAMS.lp
   );
 
-  // Lending vault (owned by lending program)
-  const [lendingVaultPda] = PublicKey.findProgramAddressSync(
-    [Buffer.from('lending_vault'), USDC_MINT.toBuffer()],
+  // LP pool vault - the single vault borrows are paid from and repaid to
+  const [lpVaultPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('lp_vault'), USDC_MINT.toBuffer()],
+    PROGRAMS.lp
+  );
+
+  // Signs protocol totals and LP pool updates on behalf of lending
+  const [protocolWriterPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('protocol_writer')],
     PROGRAMS.lending
   );
 
@@ -113,8 +119,12 @@ async function main() {
       .accounts({
         position: positionPda,
         protocol: protocolPda,
+        protocolWriter: protocolWriterPda,
+        coreProgram: PROGRAMS.core,
         borrowableConfig: borrowableConfigPda,
-        borrowVault: lendingVaultPda,
+        lpPool: lpPoolPda,
+        lpProgram: PROGRAMS.lp,
+        borrowVault: lpVaultPda,
         userTokenAccount: userUsdcAta.address,
         solPriceFeed: solPriceFeedPda,
         solMint: SOL_MINT,
@@ -169,8 +179,13 @@ async function main() {
       .accounts({
         position: positionPda,
         borrowableConfig: borrowableConfigPda,
-        repayVault: lendingVaultPda,
+        lpPool: lpPoolPda,
+        repayVault: lpVaultPda,
         userTokenAccount: userUsdcAta.address,
+        protocol: protocolPda,
+        protocolWriter: protocolWriterPda,
+        coreProgram: PROGRAMS.core,
+        lpProgram: PROGRAMS.lp,
         owner: payer.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
@@ -50,10 +50,9 @@ describe("legasi-core", () => {
       expect(pda).to.not.be.null;
     });
 
-    it("derives lending vault PDA", () => {
-      const mint = Keypair.generate().publicKey;
+    it("derives protocol writer PDA", () => {
       const [pda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("lending_vault"), mint.toBuffer()],
+        [Buffer.from("protocol_writer")],
         LENDING_PROGRAM
       );
       expect(pda).to.not.be.null;
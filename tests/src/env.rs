@@ -0,0 +1,286 @@
+//! Test validator wrapper
+//!
+//! Loads the six Legasi programs plus Metaplex token metadata (needed by
+//! `legasi_lp::initialize_pool_accounts`) into a `ProgramTestContext`, with
+//! `mock_swap` standing in for Jupiter. The context payer doubles as the protocol admin.
+
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token::spl_token;
+use legasi_sdk::instructions::core;
+use legasi_sdk::legasi_core::jupiter_cpi;
+use legasi_sdk::legasi_core::state::PriceFeed;
+use legasi_sdk::pda;
+use legasi_sdk::{
+    CORE_PROGRAM_ID, FLASH_PROGRAM_ID, GAD_PROGRAM_ID, LENDING_PROGRAM_ID, LEVERAGE_PROGRAM_ID,
+    LP_PROGRAM_ID,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::bpf_loader;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+use crate::mock_swap;
+
+/// Dumped mainnet Metaplex program (see crate docs)
+const MPL_TOKEN_METADATA_SO: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/fixtures/mpl_token_metadata.so"
+);
+
+/// `anchor build` output, used when `SBF_OUT_DIR` is not set
+const DEFAULT_SBF_OUT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy");
+
+pub type TxResult<T = ()> = Result<T, BanksClientError>;
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    /// Mints whose price feeds `advance_time` re-syncs, as a price keeper would
+    pub price_mints: Vec<Pubkey>,
+}
+
+impl TestEnv {
+    /// Start a validator with all Legasi programs deployed
+    pub async fn start() -> Self {
+        if std::env::var_os("SBF_OUT_DIR").is_none() {
+            std::env::set_var("SBF_OUT_DIR", DEFAULT_SBF_OUT_DIR);
+        }
+
+        let mut program_test = ProgramTest::default();
+        program_test.prefer_bpf(true);
+        program_test.add_program("legasi_core", CORE_PROGRAM_ID, None);
+        program_test.add_program("legasi_lending", LENDING_PROGRAM_ID, None);
+        program_test.add_program("legasi_lp", LP_PROGRAM_ID, None);
+        program_test.add_program("legasi_gad", GAD_PROGRAM_ID, None);
+        program_test.add_program("legasi_flash", FLASH_PROGRAM_ID, None);
+        program_test.add_program("legasi_leverage", LEVERAGE_PROGRAM_ID, None);
+        program_test.add_builtin_program(
+            "mock_swap",
+            jupiter_cpi::ID,
+            processor!(mock_swap::process_instruction),
+        );
+
+        let elf = std::fs::read(MPL_TOKEN_METADATA_SO)
+            .unwrap_or_else(|_| panic!("missing fixture {}", MPL_TOKEN_METADATA_SO));
+        program_test.add_account(
+            mpl_token_metadata::ID,
+            Account {
+                lamports: 1_000_000_000,
+                data: elf,
+                owner: bpf_loader::id(),
+                executable: true,
+                rent_epoch: 0,
+            },
+        );
+
+        Self {
+            context: program_test.start_with_context().await,
+            price_mints: Vec::new(),
+        }
+    }
+
+    /// Protocol admin (the context payer)
+    pub fn admin(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    /// Send `ixs` in one transaction paid by the admin
+    pub async fn process(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> TxResult {
+        let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+        let tx = {
+            let mut all_signers = vec![&self.context.payer];
+            all_signers.extend_from_slice(signers);
+            Transaction::new_signed_with_payer(
+                ixs,
+                Some(&self.context.payer.pubkey()),
+                &all_signers,
+                blockhash,
+            )
+        };
+        self.context.banks_client.process_transaction(tx).await
+    }
+
+    /// Read and deserialize an Anchor account
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {} not found", address));
+        legasi_sdk::accounts::deserialize(&account.data).unwrap()
+    }
+
+    /// Overwrite an Anchor account's data in place, keeping its owner and lamports,
+    /// for state no instruction can reach in a test (e.g. staked collateral)
+    pub async fn write_account<T: AccountSerialize>(&mut self, address: &Pubkey, value: &T) {
+        let mut account = self
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {} not found", address));
+        let mut data = Vec::with_capacity(account.data.len());
+        value.try_serialize(&mut data).unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        self.context
+            .set_account(address, &AccountSharedData::from(account));
+    }
+
+    /// Place an initialized token account holding `amount` of `mint` at `address`,
+    /// e.g. a PDA vault only a program could create
+    pub async fn set_token_account(
+        &mut self,
+        address: &Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> TxResult {
+        let rent = self.context.banks_client.get_rent().await?;
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        self.context.set_account(
+            address,
+            &AccountSharedData::from(Account {
+                lamports: rent.minimum_balance(data.len()),
+                data,
+                owner: spl_token::ID,
+                executable: false,
+                rent_epoch: 0,
+            }),
+        );
+        Ok(())
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(*address)
+            .await
+            .unwrap()
+    }
+
+    pub async fn token_balance(&mut self, token_account: &Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*token_account)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("token account {} not found", token_account));
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    /// Create a wallet funded with `lamports` from the admin
+    pub async fn funded_wallet(&mut self, lamports: u64) -> TxResult<Keypair> {
+        let wallet = Keypair::new();
+        let ix = system_instruction::transfer(&self.admin(), &wallet.pubkey(), lamports);
+        self.process(&[ix], &[]).await?;
+        Ok(wallet)
+    }
+
+    /// Create an SPL mint with the admin as mint authority
+    pub async fn create_mint(&mut self, decimals: u8) -> TxResult<Pubkey> {
+        let mint = Keypair::new();
+        let rent = self.context.banks_client.get_rent().await?;
+        let admin = self.admin();
+        let ixs = [
+            system_instruction::create_account(
+                &admin,
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &admin,
+                None,
+                decimals,
+            )
+            .unwrap(),
+        ];
+        self.process(&ixs, &[&mint]).await?;
+        Ok(mint.pubkey())
+    }
+
+    /// Create a token account for `mint` owned by `owner`
+    pub async fn create_token_account(
+        &mut self,
+        mint: &Pubkey,
+        owner: &Pubkey,
+    ) -> TxResult<Pubkey> {
+        let account = Keypair::new();
+        let rent = self.context.banks_client.get_rent().await?;
+        let ixs = [
+            system_instruction::create_account(
+                &self.admin(),
+                &account.pubkey(),
+                rent.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_account3(
+                &spl_token::ID,
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ];
+        self.process(&ixs, &[&account]).await?;
+        Ok(account.pubkey())
+    }
+
+    /// Mint `amount` of an admin-controlled mint into `to`
+    pub async fn mint_to(&mut self, mint: &Pubkey, to: &Pubkey, amount: u64) -> TxResult {
+        let ix =
+            spl_token::instruction::mint_to(&spl_token::ID, mint, to, &self.admin(), &[], amount)
+                .unwrap();
+        self.process(&[ix], &[]).await
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.context.banks_client.get_sysvar().await.unwrap()
+    }
+
+    /// Move the clock forward by `seconds` (GAD and interest are time-based), then
+    /// re-sync the `price_mints` feeds at their current prices so they stay fresh
+    pub async fn advance_time(&mut self, seconds: i64) {
+        self.advance_clock(seconds).await;
+        let admin = self.admin();
+        for mint in self.price_mints.clone() {
+            let feed: PriceFeed = self.account(&pda::price_feed(&mint).0).await;
+            let ix = core::update_price(&admin, &mint, feed.price_usd_6dec);
+            self.process(&[ix], &[]).await.unwrap();
+        }
+    }
+
+    /// Move the clock forward by `seconds` without re-syncing any price feed. The
+    /// slot moves too, so repeated transactions get a fresh blockhash
+    pub async fn advance_clock(&mut self, seconds: i64) {
+        let before = self.clock().await;
+        self.context.warp_to_slot(before.slot + 1).unwrap();
+        let mut clock = self.clock().await;
+        clock.unix_timestamp = before.unix_timestamp.saturating_add(seconds);
+        self.context.set_sysvar(&clock);
+    }
+}
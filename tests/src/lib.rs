@@ -0,0 +1,28 @@
+//! # Legasi Integration Tests
+//!
+//! solana-program-test harness that loads every Legasi program from its
+//! compiled `.so` and drives cross-program flows in Rust:
+//! - `env` - test validator wrapper (transactions, SPL helpers, clock control)
+//! - `market` - seeds protocol, collateral/borrowable configs, price feeds, and pools
+//! - `scenario` - step builders for borrower flows (deposit → borrow → price drop → GAD)
+//! - `mock_swap` - Jupiter stand-in the swap cranks route through
+//!
+//! ## Running
+//!
+//! ```bash
+//! anchor build
+//! solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s \
+//!     tests/fixtures/mpl_token_metadata.so
+//! cargo test -p legasi-tests
+//! ```
+//!
+//! Programs are read from `target/deploy` unless `SBF_OUT_DIR` is set.
+
+pub mod env;
+pub mod market;
+pub mod mock_swap;
+pub mod scenario;
+
+pub use env::TestEnv;
+pub use market::Market;
+pub use scenario::{Scenario, Step};
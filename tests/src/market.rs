@@ -0,0 +1,182 @@
+//! Market fixture
+//!
+//! Seeds a SOL-collateral / USDC + EURC-borrow market the way `scripts/init-*.ts`
+//! does on devnet: protocol, asset configs, price feeds, and the USDC and EURC
+//! LP pools whose vaults every borrow is paid out of.
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use legasi_sdk::instructions::{core, lp};
+use legasi_sdk::legasi_core::constants::{DEFAULT_SOL_MAX_LTV_BPS, WSOL_MINT};
+use legasi_sdk::legasi_core::instruction::{RegisterBorrowable, RegisterCollateral};
+use legasi_sdk::legasi_core::state::AssetType;
+use legasi_sdk::pda;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+use crate::env::{TestEnv, TxResult};
+
+/// $100 / SOL (6 decimals)
+pub const INITIAL_SOL_PRICE: u64 = 100_000_000;
+/// $1 / USDC (6 decimals)
+pub const INITIAL_USDC_PRICE: u64 = 1_000_000;
+pub const USDC_DECIMALS: u8 = 6;
+/// $1.08 / EURC (6 decimals)
+pub const INITIAL_EURC_PRICE: u64 = 1_080_000;
+pub const EURC_DECIMALS: u8 = 6;
+
+pub struct Market {
+    pub treasury: Pubkey,
+    pub sol_mint: Pubkey,
+    pub usdc_mint: Pubkey,
+    pub eurc_mint: Pubkey,
+}
+
+impl Market {
+    /// Seed the protocol and a SOL collateral / USDC + EURC market
+    pub async fn setup(env: &mut TestEnv) -> TxResult<Self> {
+        let admin = env.admin();
+        let treasury = Keypair::new().pubkey();
+        let sol_mint = Pubkey::from_str(WSOL_MINT).unwrap();
+        let usdc_mint = env.create_mint(USDC_DECIMALS).await?;
+        let eurc_mint = env.create_mint(EURC_DECIMALS).await?;
+
+        env.process(&[core::initialize_protocol(&admin, &treasury)], &[])
+            .await?;
+        env.process(
+            &[
+                core::register_collateral(
+                    &admin,
+                    &sol_mint,
+                    RegisterCollateral {
+                        oracle: pda::price_feed(&sol_mint).0,
+                        max_ltv_bps: DEFAULT_SOL_MAX_LTV_BPS,
+                        liquidation_threshold_bps: 8000,
+                        liquidation_bonus_bps: 500,
+                        decimals: 9,
+                        asset_type: AssetType::SOL,
+                    },
+                ),
+                core::register_borrowable(
+                    &admin,
+                    &usdc_mint,
+                    RegisterBorrowable {
+                        oracle: pda::price_feed(&usdc_mint).0,
+                        interest_rate_bps: 500,
+                        decimals: USDC_DECIMALS,
+                        asset_type: AssetType::USDC,
+                    },
+                ),
+                core::register_borrowable(
+                    &admin,
+                    &eurc_mint,
+                    RegisterBorrowable {
+                        oracle: pda::price_feed(&eurc_mint).0,
+                        interest_rate_bps: 500,
+                        decimals: EURC_DECIMALS,
+                        asset_type: AssetType::EURC,
+                    },
+                ),
+                core::initialize_price_feed(&admin, &sol_mint, AssetType::SOL, INITIAL_SOL_PRICE),
+                core::initialize_price_feed(
+                    &admin,
+                    &usdc_mint,
+                    AssetType::USDC,
+                    INITIAL_USDC_PRICE,
+                ),
+                core::initialize_price_feed(
+                    &admin,
+                    &eurc_mint,
+                    AssetType::EURC,
+                    INITIAL_EURC_PRICE,
+                ),
+            ],
+            &[],
+        )
+        .await?;
+
+        for (mint, name, symbol) in [
+            (usdc_mint, "Legasi USDC", "bUSDC"),
+            (eurc_mint, "Legasi EURC", "bEURC"),
+        ] {
+            env.process(&[lp::initialize_pool(&admin, &mint, false)], &[])
+                .await?;
+            env.process(
+                &[lp::initialize_pool_accounts(
+                    &admin,
+                    &mint,
+                    name.to_string(),
+                    symbol.to_string(),
+                    String::new(),
+                )],
+                &[],
+            )
+            .await?;
+        }
+
+        env.price_mints = vec![sol_mint, usdc_mint, eurc_mint];
+        Ok(Self {
+            treasury,
+            sol_mint,
+            usdc_mint,
+            eurc_mint,
+        })
+    }
+
+    /// EURC price feed, passed wherever EURC debt is valued
+    pub fn eur_price_feed(&self) -> Pubkey {
+        pda::price_feed(&self.eurc_mint).0
+    }
+
+    /// Every borrowable mint, for instructions that settle the whole position's interest
+    pub fn borrowable_mints(&self) -> [Pubkey; 2] {
+        [self.usdc_mint, self.eurc_mint]
+    }
+
+    /// Deposit `amount` USDC into the LP pool from a fresh LP wallet,
+    /// returns the wallet and its LP token account
+    pub async fn seed_lp(&self, env: &mut TestEnv, amount: u64) -> TxResult<(Keypair, Pubkey)> {
+        self.seed_pool(env, &self.usdc_mint, amount).await
+    }
+
+    /// Deposit `amount` of `mint` into its LP pool from a fresh LP wallet,
+    /// returns the wallet and its LP token account
+    pub async fn seed_pool(
+        &self,
+        env: &mut TestEnv,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> TxResult<(Keypair, Pubkey)> {
+        let lp_wallet = env.funded_wallet(1_000_000_000).await?;
+        let token_account = env.create_token_account(mint, &lp_wallet.pubkey()).await?;
+        let lp_token_account = env
+            .create_token_account(&pda::lp_token_mint(mint).0, &lp_wallet.pubkey())
+            .await?;
+        env.mint_to(mint, &token_account, amount).await?;
+
+        env.process(
+            &[lp::deposit(
+                &lp_wallet.pubkey(),
+                mint,
+                &token_account,
+                &lp_token_account,
+                amount,
+                false,
+            )],
+            &[&lp_wallet],
+        )
+        .await?;
+        Ok((lp_wallet, lp_token_account))
+    }
+
+    /// Set the SOL price (admin fallback path)
+    pub async fn set_sol_price(&self, env: &mut TestEnv, price_usd: u64) -> TxResult {
+        let admin = env.admin();
+        env.process(
+            &[core::update_price(&admin, &self.sol_mint, price_usd)],
+            &[],
+        )
+        .await
+    }
+}
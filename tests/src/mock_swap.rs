@@ -0,0 +1,61 @@
+//! Swap route stand-in
+//!
+//! Loaded at the Jupiter program ID so swap cranks can run without mainnet routes.
+//! A "swap" is two SPL transfers: `amount_in` from the seller's vault to a sink, and
+//! `amount_out` from a pre-funded reserve into the seller's output vault.
+//!
+//! Accounts: `[source, sink, source_authority, reserve, destination,
+//! reserve_authority, token_program]`. Data: `amount_in` then `amount_out`, u64 LE.
+
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_spl::token::spl_token;
+
+/// Route data for a swap of `amount_in` for `amount_out`
+pub fn route_data(amount_in: u64, amount_out: u64) -> Vec<u8> {
+    [amount_in.to_le_bytes(), amount_out.to_le_bytes()].concat()
+}
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let [source, sink, source_authority, reserve, destination, reserve_authority, token_program] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let amount = |at: usize| {
+        data.get(at..at + 8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::InvalidInstructionData)
+    };
+
+    for (from, to, authority, amount) in [
+        (source, sink, source_authority, amount(0)?),
+        (reserve, destination, reserve_authority, amount(8)?),
+    ] {
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program.key,
+                from.key,
+                to.key,
+                authority.key,
+                &[],
+                amount,
+            )?,
+            &[
+                from.clone(),
+                to.clone(),
+                authority.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
+    Ok(())
+}
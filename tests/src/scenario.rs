@@ -0,0 +1,384 @@
+//! Borrower scenario builder
+//!
+//! A scenario is an ordered list of steps run against one borrower:
+//!
+//! ```ignore
+//! Scenario::new()
+//!     .deposit_sol(10 * LAMPORTS_PER_SOL)
+//!     .borrow(600_000_000)
+//!     .set_sol_price(70_000_000)
+//!     .advance_time(MIN_GAD_CRANK_INTERVAL)
+//!     .crank_gad()
+//!     .run(&mut env, &market, &borrower)
+//!     .await?;
+//! ```
+//!
+//! `run` stops at the first failing step and returns it with the error, so
+//! a test can assert *where* a flow is rejected, not just that it was.
+
+use legasi_sdk::instructions::{gad, lending};
+use legasi_sdk::legasi_core::market::EModeCategory;
+use legasi_sdk::pda;
+use solana_program_test::BanksClientError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::env::{TestEnv, TxResult};
+use crate::market::Market;
+
+/// A borrower with an open position and USDC / EURC token accounts
+pub struct Borrower {
+    pub wallet: Keypair,
+    pub usdc_account: Pubkey,
+    pub eurc_account: Pubkey,
+    /// Referrer the position was opened with
+    pub referrer: Option<Pubkey>,
+    /// Allowlist entry or membership token account shown to gated markets
+    pub gate_pass: Option<Pubkey>,
+    /// Market passed to borrows, withdrawals and eMode changes, for its effective LTV
+    pub market_id: Option<u16>,
+}
+
+impl Borrower {
+    /// Fund a wallet with `lamports` and open its position
+    pub async fn open(env: &mut TestEnv, market: &Market, lamports: u64) -> TxResult<Self> {
+        Self::open_referred(env, market, lamports, None).await
+    }
+
+    /// Like `open`, under a registered `referrer`
+    pub async fn open_referred(
+        env: &mut TestEnv,
+        market: &Market,
+        lamports: u64,
+        referrer: Option<Pubkey>,
+    ) -> TxResult<Self> {
+        let wallet = env.funded_wallet(lamports).await?;
+        let usdc_account = env
+            .create_token_account(&market.usdc_mint, &wallet.pubkey())
+            .await?;
+        let eurc_account = env
+            .create_token_account(&market.eurc_mint, &wallet.pubkey())
+            .await?;
+        env.process(
+            &[lending::initialize_position(
+                &wallet.pubkey(),
+                referrer.as_ref(),
+            )],
+            &[&wallet],
+        )
+        .await?;
+        Ok(Self {
+            wallet,
+            usdc_account,
+            eurc_account,
+            referrer,
+            gate_pass: None,
+            market_id: None,
+        })
+    }
+
+    pub fn position(&self) -> Pubkey {
+        pda::position(&self.wallet.pubkey()).0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Deposit SOL collateral (lamports)
+    DepositSol(u64),
+    /// Withdraw SOL collateral (lamports)
+    WithdrawSol(u64),
+    /// Borrow USDC (6 decimals)
+    Borrow(u64),
+    /// Repay USDC (6 decimals)
+    Repay(u64),
+    /// Borrow EURC (6 decimals)
+    BorrowEurc(u64),
+    /// Repay EURC (6 decimals)
+    RepayEurc(u64),
+    /// Commit to repaying USDC (6 decimals) every week
+    CommitSchedule(u64),
+    /// Repay USDC (6 decimals), counting toward the repayment schedule
+    RepayOnSchedule(u64),
+    /// Open a USDC credit line with this limit (6 decimals)
+    OpenCreditLine(u64),
+    /// Draw USDC (6 decimals) on the credit line
+    DrawCreditLine(u64),
+    /// Book the standby fee on the credit line
+    AccrueStandbyFee,
+    /// Close the credit line
+    CloseCreditLine,
+    /// Cap the position's total debt (USD, 6 decimals)
+    SetDebtCap(u64),
+    /// Switch the position's eMode category
+    SetEMode(EModeCategory),
+    /// Admin sets the SOL price (6 decimals)
+    SetSolPrice(u64),
+    /// Move the clock forward (seconds)
+    AdvanceTime(i64),
+    /// Crank GAD on the position, the admin is the cranker
+    CrankGad,
+}
+
+#[derive(Default)]
+pub struct Scenario {
+    steps: Vec<Step>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn deposit_sol(self, lamports: u64) -> Self {
+        self.step(Step::DepositSol(lamports))
+    }
+
+    pub fn withdraw_sol(self, lamports: u64) -> Self {
+        self.step(Step::WithdrawSol(lamports))
+    }
+
+    pub fn borrow(self, amount: u64) -> Self {
+        self.step(Step::Borrow(amount))
+    }
+
+    pub fn repay(self, amount: u64) -> Self {
+        self.step(Step::Repay(amount))
+    }
+
+    pub fn borrow_eurc(self, amount: u64) -> Self {
+        self.step(Step::BorrowEurc(amount))
+    }
+
+    pub fn repay_eurc(self, amount: u64) -> Self {
+        self.step(Step::RepayEurc(amount))
+    }
+
+    pub fn commit_schedule(self, amount_per_period: u64) -> Self {
+        self.step(Step::CommitSchedule(amount_per_period))
+    }
+
+    pub fn repay_on_schedule(self, amount: u64) -> Self {
+        self.step(Step::RepayOnSchedule(amount))
+    }
+
+    pub fn open_credit_line(self, limit: u64) -> Self {
+        self.step(Step::OpenCreditLine(limit))
+    }
+
+    pub fn draw_credit_line(self, amount: u64) -> Self {
+        self.step(Step::DrawCreditLine(amount))
+    }
+
+    pub fn accrue_standby_fee(self) -> Self {
+        self.step(Step::AccrueStandbyFee)
+    }
+
+    pub fn close_credit_line(self) -> Self {
+        self.step(Step::CloseCreditLine)
+    }
+
+    pub fn set_debt_cap(self, max_debt_usd: u64) -> Self {
+        self.step(Step::SetDebtCap(max_debt_usd))
+    }
+
+    pub fn set_emode(self, category: EModeCategory) -> Self {
+        self.step(Step::SetEMode(category))
+    }
+
+    pub fn set_sol_price(self, price_usd: u64) -> Self {
+        self.step(Step::SetSolPrice(price_usd))
+    }
+
+    pub fn advance_time(self, seconds: i64) -> Self {
+        self.step(Step::AdvanceTime(seconds))
+    }
+
+    pub fn crank_gad(self) -> Self {
+        self.step(Step::CrankGad)
+    }
+
+    /// Run every step in order, stopping at the first failure
+    pub async fn run(
+        &self,
+        env: &mut TestEnv,
+        market: &Market,
+        borrower: &Borrower,
+    ) -> Result<(), (Step, BanksClientError)> {
+        for &step in &self.steps {
+            run_step(env, market, borrower, step)
+                .await
+                .map_err(|e| (step, e))?;
+        }
+        Ok(())
+    }
+}
+
+async fn run_step(env: &mut TestEnv, market: &Market, borrower: &Borrower, step: Step) -> TxResult {
+    let owner = borrower.wallet.pubkey();
+    match step {
+        Step::DepositSol(lamports) => {
+            env.process(
+                &[lending::deposit_sol(&owner, lamports, borrower.gate_pass)],
+                &[&borrower.wallet],
+            )
+            .await
+        }
+        Step::WithdrawSol(lamports) => {
+            let ix = lending::withdraw_sol(
+                &owner,
+                lamports,
+                false,
+                None,
+                Some(market.eur_price_feed()),
+                None,
+                &market.borrowable_mints(),
+                borrower.market_id,
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::Borrow(amount) => {
+            let ix = lending::borrow(
+                &owner,
+                &market.usdc_mint,
+                &borrower.usdc_account,
+                amount,
+                Some(market.eur_price_feed()),
+                None,
+                None,
+                borrower.gate_pass,
+                borrower.market_id,
+                false,
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::Repay(amount) => {
+            let ix = lending::repay(
+                &owner,
+                &market.usdc_mint,
+                &borrower.usdc_account,
+                amount,
+                Some(market.eur_price_feed()),
+                borrower.referrer.as_ref(),
+                None,
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::BorrowEurc(amount) => {
+            let ix = lending::borrow(
+                &owner,
+                &market.eurc_mint,
+                &borrower.eurc_account,
+                amount,
+                Some(market.eur_price_feed()),
+                None,
+                None,
+                borrower.gate_pass,
+                borrower.market_id,
+                false,
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::RepayEurc(amount) => {
+            let ix = lending::repay(
+                &owner,
+                &market.eurc_mint,
+                &borrower.eurc_account,
+                amount,
+                Some(market.eur_price_feed()),
+                borrower.referrer.as_ref(),
+                None,
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::CommitSchedule(amount_per_period) => {
+            let ix = lending::commit_repayment_schedule(
+                &owner,
+                amount_per_period,
+                &market.borrowable_mints(),
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::RepayOnSchedule(amount) => {
+            let ix = lending::repay_on_schedule(
+                &owner,
+                &market.usdc_mint,
+                &borrower.usdc_account,
+                amount,
+                Some(market.eur_price_feed()),
+                borrower.referrer.as_ref(),
+                None,
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::OpenCreditLine(limit) => {
+            let ix = lending::open_credit_line(
+                &owner,
+                &market.usdc_mint,
+                limit,
+                Some(market.eur_price_feed()),
+                &market.borrowable_mints(),
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::DrawCreditLine(amount) => {
+            let ix = lending::draw_credit_line(
+                &owner,
+                &market.usdc_mint,
+                &borrower.usdc_account,
+                amount,
+                Some(market.eur_price_feed()),
+                borrower.gate_pass,
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::AccrueStandbyFee => {
+            env.process(&[lending::accrue_standby_fee(&owner)], &[])
+                .await
+        }
+        Step::CloseCreditLine => {
+            env.process(&[lending::close_credit_line(&owner)], &[&borrower.wallet])
+                .await
+        }
+        Step::SetDebtCap(max_debt_usd) => {
+            env.process(
+                &[lending::set_debt_cap(&owner, max_debt_usd)],
+                &[&borrower.wallet],
+            )
+            .await
+        }
+        Step::SetEMode(category) => {
+            let ix = lending::set_emode(
+                &owner,
+                category,
+                borrower.market_id,
+                Some(market.eur_price_feed()),
+                &market.borrowable_mints(),
+            );
+            env.process(&[ix], &[&borrower.wallet]).await
+        }
+        Step::SetSolPrice(price_usd) => market.set_sol_price(env, price_usd).await,
+        Step::AdvanceTime(seconds) => {
+            env.advance_time(seconds).await;
+            Ok(())
+        }
+        Step::CrankGad => {
+            let ix = gad::crank_gad(
+                &owner,
+                &market.treasury,
+                &pda::price_feed(&market.sol_mint).0,
+                Some(market.eur_price_feed()),
+                None,
+                None,
+                &market.borrowable_mints(),
+                &env.admin(),
+            );
+            env.process(&[ix], &[]).await
+        }
+    }
+}
@@ -0,0 +1,3139 @@
+use anchor_lang::solana_program::instruction::AccountMeta;
+use legasi_sdk::instructions::{core, gad, lending, lp};
+use legasi_sdk::legasi_core::admin::AdminOp;
+use legasi_sdk::legasi_core::caps::AssetCaps;
+use legasi_sdk::legasi_core::circuit_breaker::CircuitBreakerConfig;
+use legasi_sdk::legasi_core::constants::{
+    DEFAULT_SOL_MAX_LTV_BPS, LAMPORTS_PER_SOL, MIN_GAD_CRANK_INTERVAL, PAUSE_BORROWS,
+    PRICE_STALENESS_THRESHOLD, SECONDS_PER_DAY,
+};
+use legasi_sdk::legasi_core::gad::LiquidationSplit;
+use legasi_sdk::legasi_core::gad_schedule::{ThrottleWindow, SECONDS_PER_WEEK};
+use legasi_sdk::legasi_core::gate::GateKind;
+use legasi_sdk::legasi_core::instruction::RegisterCollateral;
+use legasi_sdk::legasi_core::interest::accrued_interest;
+use legasi_sdk::legasi_core::market::{EModeCategory, MarketPreset};
+use legasi_sdk::legasi_core::state::{AssetType, Borrowable, PriceFeed, Protocol};
+use legasi_sdk::legasi_core::swap_router::SwapRoute;
+use legasi_sdk::legasi_core::twap::TWAP_WINDOW;
+use legasi_sdk::legasi_gad::LIQUIDATION_AUCTION_DURATION;
+use legasi_sdk::legasi_lending::{
+    fixed_rate_bps, points_leaf, verify_points_proof, voting_epoch, AgentConfig,
+    AutoDeleverageOrder, DeleverageSwap, NotifyService, NotifySubscription, PointsLedger,
+    PointsSnapshot, Position, ProceedsMode, Referrer, RepaymentSchedule, StatementLedger,
+    Succession, VotingPower, WithdrawalAllowlist, ALLOWLIST_CHANGE_DELAY, MATURITY_PENALTY_BPS,
+    MIN_FIXED_TERM, MIN_INACTIVITY_PERIOD, NOTIFY_COOLDOWN, REPAYMENT_PERIOD, STATEMENT_PERIOD,
+    SUCCESSION_CHALLENGE_WINDOW,
+};
+use legasi_sdk::legasi_lp::{LockedDeposit, LpLock, LpPool, WithdrawRequest};
+use legasi_sdk::{math, pda};
+use legasi_tests::mock_swap;
+use legasi_tests::scenario::Borrower;
+use legasi_tests::{Market, Scenario, Step, TestEnv};
+
+async fn setup() -> (TestEnv, Market, Borrower) {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    market.seed_lp(&mut env, 10_000_000_000).await.unwrap();
+    let borrower = Borrower::open(&mut env, &market, 20 * LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+    (env, market, borrower)
+}
+
+#[tokio::test]
+async fn test_deposit_sol_records_collateral() {
+    let (mut env, market, borrower) = setup().await;
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.collaterals.len(), 1);
+    assert_eq!(position.collaterals[0].asset_type, AssetType::SOL);
+    assert_eq!(position.collaterals[0].amount, 10 * LAMPORTS_PER_SOL);
+
+    let sol_vault = pda::sol_vault(&borrower.position()).0;
+    assert!(env.lamports(&sol_vault).await >= 10 * LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn test_withdraw_sol_as_wsol() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let wsol_account =
+        anchor_spl::associated_token::get_associated_token_address(&owner, &market.sol_mint);
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // The flag needs the wSOL account and token programs
+    let mut ix =
+        lending::withdraw_sol(&owner, LAMPORTS_PER_SOL, false, None, None, None, &[], None);
+    ix.data =
+        lending::withdraw_sol(&owner, LAMPORTS_PER_SOL, true, None, None, None, &[], None).data;
+    assert!(env.process(&[ix], &[&borrower.wallet]).await.is_err());
+
+    // The first withdrawal creates the ATA, the second tops it up
+    for _ in 0..2 {
+        env.process(
+            &[lending::withdraw_sol(
+                &owner,
+                LAMPORTS_PER_SOL,
+                true,
+                None,
+                None,
+                None,
+                &[],
+                None,
+            )],
+            &[&borrower.wallet],
+        )
+        .await
+        .unwrap();
+    }
+    assert_eq!(env.token_balance(&wsol_account).await, 2 * LAMPORTS_PER_SOL);
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.collaterals[0].amount, 8 * LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn test_withdrawal_allowlist_pins_destinations() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let cold = solana_sdk::pubkey::Pubkey::new_unique();
+    let attacker = solana_sdk::pubkey::Pubkey::new_unique();
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let withdraw = |amount, destination| {
+        lending::withdraw_sol(&owner, amount, false, destination, None, None, &[], None)
+    };
+    env.process(
+        &[lending::set_withdrawal_allowlist(&owner, vec![cold])],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+
+    // Only the listed wallet can receive collateral, not even the owner's
+    for destination in [None, Some(attacker)] {
+        assert!(env
+            .process(
+                &[withdraw(LAMPORTS_PER_SOL, destination)],
+                &[&borrower.wallet]
+            )
+            .await
+            .is_err());
+    }
+    env.process(
+        &[withdraw(LAMPORTS_PER_SOL, Some(cold))],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.lamports(&cold).await, LAMPORTS_PER_SOL);
+
+    // A new list waits out the delay, and the owner can cancel it meanwhile
+    env.process(
+        &[lending::set_withdrawal_allowlist(&owner, vec![attacker])],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    assert!(env
+        .process(
+            &[lending::apply_withdrawal_allowlist(&owner)],
+            &[&borrower.wallet]
+        )
+        .await
+        .is_err());
+    env.process(
+        &[lending::cancel_withdrawal_allowlist_change(&owner)],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let allowlist: WithdrawalAllowlist = env
+        .account(&pda::withdrawal_allowlist(&borrower.position()).0)
+        .await;
+    assert_eq!(allowlist.destinations, vec![cold]);
+    assert_eq!(allowlist.pending_after, 0);
+
+    // Clearing the list is timelocked too
+    env.process(
+        &[lending::set_withdrawal_allowlist(&owner, vec![])],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    env.advance_time(ALLOWLIST_CHANGE_DELAY).await;
+    env.process(
+        &[lending::apply_withdrawal_allowlist(&owner)],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    env.process(&[withdraw(LAMPORTS_PER_SOL / 2, None)], &[&borrower.wallet])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_beneficiary_inherits_inactive_position() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let heir = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let heir_key = solana_sdk::signer::Signer::pubkey(&heir);
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(
+        &[lending::designate_beneficiary(
+            &owner,
+            &heir_key,
+            MIN_INACTIVITY_PERIOD,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+
+    env.advance_time(MIN_INACTIVITY_PERIOD).await;
+    env.process(
+        &[lending::start_succession_claim(&owner, &heir_key)],
+        &[&heir],
+    )
+    .await
+    .unwrap();
+    env.advance_time(SUCCESSION_CHALLENGE_WINDOW).await;
+
+    let old: Position = env.account(&borrower.position()).await;
+    let vault_lamports = env.lamports(&pda::sol_vault(&borrower.position()).0).await;
+    env.process(
+        &[lending::complete_succession(&owner, &heir_key, false)],
+        &[&heir],
+    )
+    .await
+    .unwrap();
+
+    // Collateral, debt and the SOL behind them now belong to the heir
+    let new_position = pda::position(&heir_key).0;
+    let inherited: Position = env.account(&new_position).await;
+    assert_eq!(inherited.owner, heir_key);
+    assert_eq!(inherited.collaterals[0].amount, 10 * LAMPORTS_PER_SOL);
+    assert_eq!(inherited.total_debt().unwrap(), old.total_debt().unwrap());
+    assert_eq!(
+        env.lamports(&pda::sol_vault(&new_position).0).await,
+        vault_lamports
+    );
+    let emptied: Position = env.account(&borrower.position()).await;
+    assert!(emptied.collaterals.is_empty() && emptied.borrows.is_empty());
+    assert_eq!(
+        env.lamports(&pda::succession(&borrower.position()).0).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_owner_check_in_cancels_succession_claim() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let heir = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let heir_key = solana_sdk::signer::Signer::pubkey(&heir);
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(
+        &[lending::designate_beneficiary(
+            &owner,
+            &heir_key,
+            MIN_INACTIVITY_PERIOD,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+
+    // Nothing to claim while the owner is active
+    assert!(env
+        .process(
+            &[lending::start_succession_claim(&owner, &heir_key)],
+            &[&heir]
+        )
+        .await
+        .is_err());
+
+    // The owner answers the claim within the challenge window
+    env.advance_time(MIN_INACTIVITY_PERIOD).await;
+    env.process(
+        &[
+            lending::start_succession_claim(&owner, &heir_key),
+            lending::succession_check_in(&owner),
+        ],
+        &[&heir, &borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let succession: Succession = env.account(&pda::succession(&borrower.position()).0).await;
+    assert_eq!(succession.claim_started_at, 0);
+
+    env.advance_time(SUCCESSION_CHALLENGE_WINDOW).await;
+    assert!(env
+        .process(
+            &[lending::complete_succession(&owner, &heir_key, false)],
+            &[&heir]
+        )
+        .await
+        .is_err());
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.owner, owner);
+    assert!(!position.collaterals.is_empty());
+}
+
+#[tokio::test]
+async fn test_borrow_over_ltv_is_rejected() {
+    let (mut env, market, borrower) = setup().await;
+
+    // 10 SOL at $100 = $1,000 collateral, 75% LTV caps debt at $750
+    let result = Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(700_000_000)
+        .borrow(100_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::Borrow(100_000_000));
+}
+
+#[tokio::test]
+async fn test_debt_cap_bounds_every_borrow() {
+    let (mut env, market, borrower) = setup().await;
+
+    // Collateral allows $750, the owner caps debt at $300
+    let result = Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .set_debt_cap(300_000_000)
+        .borrow(200_000_000)
+        .borrow(150_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::Borrow(150_000_000));
+
+    // A credit line can't commit past the cap either: $200 drawn + $200 unused
+    let result = Scenario::new()
+        .open_credit_line(400_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::OpenCreditLine(400_000_000));
+
+    // Lifting the cap lets borrowing resume
+    Scenario::new()
+        .set_debt_cap(0)
+        .borrow(150_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.max_debt_usd, 0);
+    assert_eq!(position.borrows[0].amount, 350_000_000);
+}
+
+#[tokio::test]
+async fn test_emode_lifts_the_market_ltv() {
+    let (mut env, market, mut borrower) = setup().await;
+    let admin = env.admin();
+    let mut params = MarketPreset::sol_usdc();
+    params.emode_max_ltv_bps = 8500;
+    params.emode_category = EModeCategory::SolCorrelated;
+    env.process(
+        &[core::create_market(
+            &admin,
+            1,
+            &market.sol_mint,
+            &market.usdc_mint,
+            params,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    borrower.market_id = Some(1);
+
+    // $1,000 collateral: the market's base 75% LTV stops a $800 borrow
+    let result = Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(800_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::Borrow(800_000_000));
+
+    // In the market's category it gets the 85% eMode LTV
+    Scenario::new()
+        .set_emode(EModeCategory::SolCorrelated)
+        .borrow(800_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.emode.category, EModeCategory::SolCorrelated);
+
+    // At 80% LTV the position can't leave eMode or switch to another category
+    for category in [EModeCategory::None, EModeCategory::Stablecoins] {
+        let result = Scenario::new()
+            .set_emode(category)
+            .run(&mut env, &market, &borrower)
+            .await;
+        let (failed_step, _) = result.unwrap_err();
+        assert_eq!(failed_step, Step::SetEMode(category));
+    }
+
+    // Withdrawals are checked at 85% too: $950 left backs $807.50, $930 only $790.50
+    let result = Scenario::new()
+        .withdraw_sol(LAMPORTS_PER_SOL / 2)
+        .withdraw_sol(LAMPORTS_PER_SOL / 5)
+        .run(&mut env, &market, &borrower)
+        .await;
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::WithdrawSol(LAMPORTS_PER_SOL / 5));
+
+    // EURC debt is outside a SOL/USDC market
+    let result = Scenario::new()
+        .borrow_eurc(10_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::BorrowEurc(10_000_000));
+}
+
+#[tokio::test]
+async fn test_pause_flags_stop_new_risk_only() {
+    let (mut env, market, borrower) = setup().await;
+    let admin = env.admin();
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // Borrowing alone paused: deposits, repays and withdrawals go through
+    let ops = vec![AdminOp::SetPauseFlags {
+        flags: PAUSE_BORROWS,
+    }];
+    env.process(&[core::execute_admin_ops(&admin, ops)], &[])
+        .await
+        .unwrap();
+    let result = Scenario::new()
+        .borrow(50_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::Borrow(50_000_000));
+    Scenario::new()
+        .deposit_sol(LAMPORTS_PER_SOL)
+        .repay(100_000_000)
+        .withdraw_sol(LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // The global flag also stops deposits, never repays
+    let ops = vec![
+        AdminOp::SetPauseFlags { flags: 0 },
+        AdminOp::SetPaused { paused: true },
+    ];
+    env.process(&[core::execute_admin_ops(&admin, ops)], &[])
+        .await
+        .unwrap();
+    let result = Scenario::new()
+        .deposit_sol(2 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await;
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::DepositSol(2 * LAMPORTS_PER_SOL));
+    Scenario::new()
+        .repay(50_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let ops = vec![AdminOp::SetPaused { paused: false }];
+    env.process(&[core::execute_admin_ops(&admin, ops)], &[])
+        .await
+        .unwrap();
+    Scenario::new()
+        .borrow(40_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_halts_borrows_after_sharp_move() {
+    let (mut env, market, borrower) = setup().await;
+    let admin = env.admin();
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // Halt for an hour on a 10% move within 10 minutes
+    let config = CircuitBreakerConfig {
+        max_move_bps: 1_000,
+        window: 600,
+        cooldown: 3_600,
+    };
+    let mut ix = core::execute_admin_ops(
+        &admin,
+        vec![AdminOp::SetCircuitBreaker {
+            mint: market.sol_mint,
+            config,
+        }],
+    );
+    ix.accounts.push(solana_sdk::instruction::AccountMeta::new(
+        pda::collateral(&market.sol_mint).0,
+        false,
+    ));
+    env.process(&[ix], &[]).await.unwrap();
+
+    // Two 6% steps add up past the threshold
+    market.set_sol_price(&mut env, 94_000_000).await.unwrap();
+    env.advance_time(60).await;
+    market.set_sol_price(&mut env, 88_000_000).await.unwrap();
+    let feed: PriceFeed = env.account(&pda::price_feed(&market.sol_mint).0).await;
+    let now = env.clock().await.unix_timestamp;
+    assert!(feed.circuit_breaker.is_tripped(now));
+
+    for step in [
+        Step::Borrow(50_000_000),
+        Step::WithdrawSol(LAMPORTS_PER_SOL),
+    ] {
+        let result = Scenario::new()
+            .step(step)
+            .run(&mut env, &market, &borrower)
+            .await;
+        assert_eq!(result.unwrap_err().0, step);
+    }
+    // Repayments and deposits stay open
+    Scenario::new()
+        .repay(100_000_000)
+        .deposit_sol(LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    env.advance_time(config.cooldown).await;
+    Scenario::new()
+        .borrow(40_000_000)
+        .withdraw_sol(LAMPORTS_PER_SOL / 2)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_stale_price_blocks_borrows_not_repays() {
+    let (mut env, market, borrower) = setup().await;
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // No keeper syncs for longer than the staleness threshold
+    env.advance_clock(PRICE_STALENESS_THRESHOLD + 1).await;
+    for step in [
+        Step::Borrow(50_000_000),
+        Step::WithdrawSol(LAMPORTS_PER_SOL),
+    ] {
+        let result = Scenario::new()
+            .step(step)
+            .run(&mut env, &market, &borrower)
+            .await;
+        assert_eq!(result.unwrap_err().0, step);
+    }
+    Scenario::new()
+        .repay(100_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // A fresh sync reopens them
+    market.set_sol_price(&mut env, 100_000_000).await.unwrap();
+    let admin = env.admin();
+    env.process(
+        &[core::update_price(&admin, &market.eurc_mint, 1_080_000)],
+        &[],
+    )
+    .await
+    .unwrap();
+    Scenario::new()
+        .borrow(40_000_000)
+        .withdraw_sol(LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_letter_of_credit_reserves_collateral_until_claimed() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let beneficiary = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let beneficiary_key = solana_sdk::signer::Signer::pubkey(&beneficiary);
+    let beneficiary_account = env
+        .create_token_account(&market.usdc_mint, &beneficiary_key)
+        .await
+        .unwrap();
+
+    // $500 promised out of the $750 the collateral allows
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let expires_at = env.clock().await.unix_timestamp + SECONDS_PER_DAY;
+    env.process(
+        &[lending::issue_letter_of_credit(
+            &owner,
+            &market.usdc_mint,
+            1,
+            &beneficiary_key,
+            500_000_000,
+            expires_at,
+            None,
+            &market.borrowable_mints(),
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+
+    // Only the other $250 can be borrowed or freed
+    let result = Scenario::new()
+        .borrow(300_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(result.unwrap_err().0, Step::Borrow(300_000_000));
+    let result = Scenario::new()
+        .withdraw_sol(4 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(
+        result.unwrap_err().0,
+        Step::WithdrawSol(4 * LAMPORTS_PER_SOL)
+    );
+
+    // The owner can't back out before expiry, the beneficiary claims
+    assert!(env
+        .process(
+            &[lending::release_letter_of_credit(&owner, &owner, 1)],
+            &[&borrower.wallet],
+        )
+        .await
+        .is_err());
+    env.process(
+        &[lending::claim_letter_of_credit(
+            &beneficiary_key,
+            &owner,
+            &market.usdc_mint,
+            1,
+            &beneficiary_account,
+            None,
+            None,
+        )],
+        &[&beneficiary],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&beneficiary_account).await, 500_000_000);
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].amount, 500_000_000);
+    assert_eq!(position.committed_letters_usd, 0);
+
+    // An expired letter can't be claimed, and the owner releases it
+    let expires_at = env.clock().await.unix_timestamp + SECONDS_PER_DAY;
+    env.process(
+        &[lending::issue_letter_of_credit(
+            &owner,
+            &market.usdc_mint,
+            2,
+            &beneficiary_key,
+            100_000_000,
+            expires_at,
+            None,
+            &market.borrowable_mints(),
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    env.advance_time(2 * SECONDS_PER_DAY).await;
+    let claim = lending::claim_letter_of_credit(
+        &beneficiary_key,
+        &owner,
+        &market.usdc_mint,
+        2,
+        &beneficiary_account,
+        None,
+        None,
+    );
+    assert!(env.process(&[claim], &[&beneficiary]).await.is_err());
+    env.process(
+        &[lending::release_letter_of_credit(&owner, &owner, 2)],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.committed_letters_usd, 0);
+}
+
+#[tokio::test]
+async fn test_gated_market_requires_allowlist_entry() {
+    let (mut env, market, mut borrower) = setup().await;
+    let admin = env.admin();
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    env.process(
+        &[core::set_market_gate(
+            &admin,
+            &market.usdc_mint,
+            GateKind::Allowlist,
+            &Default::default(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // SOL collateral is still open, the USDC market is not
+    let result = Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(100_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(result.unwrap_err().0, Step::Borrow(100_000_000));
+
+    // Allowlisted, but the entry must also be presented
+    env.process(
+        &[core::add_to_allowlist(&admin, &market.usdc_mint, &owner)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let result = Scenario::new()
+        .borrow(110_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(result.unwrap_err().0, Step::Borrow(110_000_000));
+
+    let gate = pda::market_gate(&market.usdc_mint).0;
+    borrower.gate_pass = Some(pda::allowlist_entry(&gate, &owner).0);
+    Scenario::new()
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // Delisted: no more borrows until the gate is lifted
+    env.process(
+        &[core::remove_from_allowlist(
+            &admin,
+            &market.usdc_mint,
+            &owner,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let result = Scenario::new()
+        .borrow(50_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(result.unwrap_err().0, Step::Borrow(50_000_000));
+
+    env.process(&[core::remove_market_gate(&admin, &market.usdc_mint)], &[])
+        .await
+        .unwrap();
+    Scenario::new()
+        .borrow(60_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].amount, 260_000_000);
+}
+
+#[tokio::test]
+async fn test_deleverage_repays_down_to_target_ltv() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+
+    // $1,000 collateral, $700 debt (70% LTV)
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(700_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // 50% takes a $200 repayment, sized on-chain
+    env.process(
+        &[lending::deleverage_to_ltv(
+            &owner,
+            &market.usdc_mint,
+            &borrower.usdc_account,
+            5000,
+            Some(market.eur_price_feed()),
+            None,
+            None,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].amount, 500_000_000);
+    assert_eq!(env.token_balance(&borrower.usdc_account).await, 500_000_000);
+
+    // Already below a 60% target
+    let ix = lending::deleverage_to_ltv(
+        &owner,
+        &market.usdc_mint,
+        &borrower.usdc_account,
+        6000,
+        Some(market.eur_price_feed()),
+        None,
+        None,
+    );
+    assert!(env.process(&[ix], &[&borrower.wallet]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_auto_deleverage_order_waits_for_trigger() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+
+    // $1,000 collateral, $600 debt (60% LTV)
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(600_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // Target must sit below the trigger
+    let ix = lending::set_auto_deleverage(
+        &owner,
+        &market.usdc_mint,
+        5000,
+        5000,
+        50,
+        ProceedsMode::Repay,
+    );
+    assert!(env.process(&[ix], &[&borrower.wallet]).await.is_err());
+
+    env.process(
+        &[lending::set_auto_deleverage(
+            &owner,
+            &market.usdc_mint,
+            6500,
+            5000,
+            50,
+            ProceedsMode::Repay,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let order_address = pda::auto_deleverage_order(&borrower.position()).0;
+    let order: AutoDeleverageOrder = env.account(&order_address).await;
+    assert_eq!(order.trigger_ltv_bps, 6500);
+    assert_eq!(order.proceeds, ProceedsMode::Repay);
+    assert!(order.armed);
+
+    // 60% is under the 65% trigger: keepers can't sell yet
+    let crank = lending::crank_auto_deleverage(
+        &owner,
+        &market.usdc_mint,
+        &borrower.usdc_account,
+        &env.admin(),
+        &borrower.usdc_account,
+        Some(market.eur_price_feed()),
+        DeleverageSwap {
+            route_data: vec![],
+            min_out_amount: 1,
+        },
+    );
+    assert!(env.process(&[crank], &[]).await.is_err());
+
+    env.process(
+        &[lending::cancel_auto_deleverage(&owner)],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.lamports(&order_address).await, 0);
+}
+
+#[tokio::test]
+async fn test_protocol_totals_track_lending() {
+    let (mut env, market, borrower) = setup().await;
+
+    // 10 SOL at $100, borrow $400, repay $100
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .repay(100_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_collateral_usd, 1_000_000_000);
+    assert_eq!(protocol.total_borrowed_usd, 300_000_000);
+}
+
+#[tokio::test]
+async fn test_lp_pool_tracks_borrows() {
+    let (mut env, market, borrower) = setup().await;
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .repay(100_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_borrowed, 300_000_000);
+
+    // Borrows and repays both settle against the LP vault
+    let lp_vault = pda::lp_vault(&market.usdc_mint).0;
+    assert_eq!(env.token_balance(&lp_vault).await, 9_700_000_000);
+}
+
+#[tokio::test]
+async fn test_borrows_pause_above_utilization_cap() {
+    let (mut env, market, borrower) = setup().await;
+
+    // Cap the $10,000 pool at 5% utilization
+    let admin = env.admin();
+    env.process(
+        &[legasi_sdk::instructions::lp::set_max_utilization(
+            &admin,
+            &market.usdc_mint,
+            500,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // $400 lent is 4%, another $200 would make 6%
+    let result = Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    let (failed_step, _) = result.unwrap_err();
+    assert_eq!(failed_step, Step::Borrow(200_000_000));
+
+    // Repaying back under the cap releases the pause
+    Scenario::new()
+        .repay(300_000_000)
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_borrowed, 300_000_000);
+}
+
+#[tokio::test]
+async fn test_lp_crisis_lock_earns_boosted_interest() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    let (lp_wallet, lp_token_account) = market.seed_lp(&mut env, 1_000_000_000).await.unwrap();
+    let lp_owner = solana_sdk::signer::Signer::pubkey(&lp_wallet);
+    let borrower = Borrower::open(&mut env, &market, 20 * LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+    let admin = env.admin();
+    env.process(
+        &[lp::set_outflow_limit(&admin, &market.usdc_mint, 0, 1)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Nothing is lent yet: no crisis to lock through
+    let lock = lp::lock_lp_shares(
+        &lp_owner,
+        &market.usdc_mint,
+        &lp_token_account,
+        500_000_000,
+        30,
+    );
+    assert!(env.process(&[lock.clone()], &[&lp_wallet]).await.is_err());
+
+    // $950 of the $1,000 pool lent (95%)
+    Scenario::new()
+        .deposit_sol(14 * LAMPORTS_PER_SOL)
+        .borrow(950_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(&[lock], &[&lp_wallet]).await.unwrap();
+    assert_eq!(env.token_balance(&lp_token_account).await, 500_000_000);
+    let lp_pool_address = pda::lp_pool(&market.usdc_mint).0;
+    let lock_address = pda::lp_lock(&lp_pool_address, &lp_owner).0;
+    let pool: LpPool = env.account(&lp_pool_address).await;
+    assert_eq!(pool.locked_shares, 500_000_000);
+
+    // Still locked
+    let rebate_account = env
+        .create_token_account(&market.usdc_mint, &lp_owner)
+        .await
+        .unwrap();
+    let unlock = lp::unlock_lp_shares(
+        &lp_owner,
+        &market.usdc_mint,
+        &lp_token_account,
+        &rebate_account,
+        &admin,
+    );
+    assert!(env.process(&[unlock.clone()], &[]).await.is_err());
+
+    // A year later the borrower pays interest: locked shares split the boost
+    Scenario::new()
+        .advance_time(31_557_600)
+        .repay(50_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let pool: LpPool = env.account(&lp_pool_address).await;
+    let lp_lock: LpLock = env.account(&lock_address).await;
+    let rebate = lp_lock.rebate(pool.lock_rebate_per_share);
+    assert!(rebate > 0);
+
+    env.process(&[unlock], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&lp_token_account).await, 1_000_000_000);
+    assert_eq!(env.token_balance(&rebate_account).await, rebate);
+    let pool: LpPool = env.account(&lp_pool_address).await;
+    assert_eq!(pool.locked_shares, 0);
+    assert_eq!(env.lamports(&lock_address).await, 0);
+}
+
+#[tokio::test]
+async fn test_lp_locked_deposit_boost_and_early_exit_penalty() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    market.seed_lp(&mut env, 1_000_000_000).await.unwrap();
+    let borrower = Borrower::open(&mut env, &market, 20 * LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+    let admin = env.admin();
+    env.process(
+        &[lp::set_outflow_limit(&admin, &market.usdc_mint, 0, 1)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let lp_pool_address = pda::lp_pool(&market.usdc_mint).0;
+
+    let mut lockers = Vec::new();
+    for amount in [500_000_000, 100_000_000] {
+        let wallet = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+        let owner = solana_sdk::signer::Signer::pubkey(&wallet);
+        let token_account = env
+            .create_token_account(&market.usdc_mint, &owner)
+            .await
+            .unwrap();
+        env.mint_to(&market.usdc_mint, &token_account, amount)
+            .await
+            .unwrap();
+        lockers.push((wallet, owner, token_account));
+    }
+    let (long_wallet, long_owner, long_account) = &lockers[0];
+    let (short_wallet, short_owner, short_account) = &lockers[1];
+
+    // Only 30, 90 and 180 day terms
+    let odd_term = lp::deposit_locked(
+        long_owner,
+        &market.usdc_mint,
+        long_account,
+        500_000_000,
+        60,
+        false,
+    );
+    assert!(env.process(&[odd_term], &[long_wallet]).await.is_err());
+    env.process(
+        &[lp::deposit_locked(
+            long_owner,
+            &market.usdc_mint,
+            long_account,
+            500_000_000,
+            180,
+            false,
+        )],
+        &[long_wallet],
+    )
+    .await
+    .unwrap();
+    let pool: LpPool = env.account(&lp_pool_address).await;
+    assert_eq!(pool.locked_deposit_weight, 250_000_000);
+
+    // Leaving a 30 day lock at once costs the full 10% penalty, to insurance
+    env.process(
+        &[lp::deposit_locked(
+            short_owner,
+            &market.usdc_mint,
+            short_account,
+            100_000_000,
+            30,
+            false,
+        )],
+        &[short_wallet],
+    )
+    .await
+    .unwrap();
+    let insurance_before = env
+        .account::<Protocol>(&pda::protocol().0)
+        .await
+        .insurance_fund;
+    env.process(
+        &[lp::unlock_and_withdraw(
+            short_owner,
+            &market.usdc_mint,
+            short_account,
+            false,
+        )],
+        &[short_wallet],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(short_account).await, 90_000_000);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.insurance_fund, insurance_before + 10_000_000);
+
+    // A year of interest: the 180 day lock earns its shares' interest at 1.5x
+    Scenario::new()
+        .deposit_sol(14 * LAMPORTS_PER_SOL)
+        .borrow(500_000_000)
+        .advance_time(31_557_600)
+        .repay(50_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let pool: LpPool = env.account(&lp_pool_address).await;
+    let lock_address = pda::locked_deposit(&lp_pool_address, long_owner).0;
+    let lock: LockedDeposit = env.account(&lock_address).await;
+    let boost = lock.boost(pool.deposit_boost_per_weight);
+    let redemption = pool.tokens_for_shares(lock.shares).unwrap();
+    assert!(boost > 0);
+    assert!(redemption > 500_000_000);
+
+    env.process(
+        &[lp::unlock_and_withdraw(
+            long_owner,
+            &market.usdc_mint,
+            long_account,
+            false,
+        )],
+        &[long_wallet],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(long_account).await, redemption + boost);
+    let pool: LpPool = env.account(&lp_pool_address).await;
+    assert_eq!(pool.locked_deposit_weight, 0);
+    assert_eq!(env.lamports(&lock_address).await, 0);
+}
+
+#[tokio::test]
+async fn test_lp_withdraw_queue_pays_out_as_borrowers_repay() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    let (lp_wallet, lp_token_account) = market.seed_lp(&mut env, 1_000_000_000).await.unwrap();
+    let lp_owner = solana_sdk::signer::Signer::pubkey(&lp_wallet);
+    let borrower = Borrower::open(&mut env, &market, 20 * LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+    let admin = env.admin();
+    env.process(
+        &[lp::set_outflow_limit(&admin, &market.usdc_mint, 0, 1)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let usdc_account = env
+        .create_token_account(&market.usdc_mint, &lp_owner)
+        .await
+        .unwrap();
+
+    // $950 of the $1,000 pool lent, then the LP queues half its shares
+    Scenario::new()
+        .deposit_sol(14 * LAMPORTS_PER_SOL)
+        .borrow(950_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(
+        &[lp::request_withdraw(
+            &lp_owner,
+            &market.usdc_mint,
+            &lp_token_account,
+            500_000_000,
+            0,
+            false,
+        )],
+        &[&lp_wallet],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&lp_token_account).await, 500_000_000);
+
+    // The queue goes first: no direct exit around it
+    let withdraw = lp::withdraw(
+        &lp_owner,
+        &market.usdc_mint,
+        &usdc_account,
+        &lp_token_account,
+        10_000_000,
+        false,
+    );
+    assert!(env.process(&[withdraw], &[&lp_wallet]).await.is_err());
+
+    // Only the $50 left in the vault is paid out for now
+    env.process(
+        &[lp::process_withdraw_queue(
+            &lp_owner,
+            &market.usdc_mint,
+            0,
+            &usdc_account,
+            &admin,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&usdc_account).await, 50_000_000);
+    let lp_pool_address = pda::lp_pool(&market.usdc_mint).0;
+    let request_address = pda::withdraw_request(&lp_pool_address, 0).0;
+    let request: WithdrawRequest = env.account(&request_address).await;
+    assert_eq!(request.shares, 450_000_000);
+
+    // The borrower's repayment pays the rest and closes the request
+    Scenario::new()
+        .repay(450_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(
+        &[lp::process_withdraw_queue(
+            &lp_owner,
+            &market.usdc_mint,
+            0,
+            &usdc_account,
+            &lp_owner,
+        )],
+        &[&lp_wallet],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&usdc_account).await, 500_000_000);
+    assert_eq!(env.lamports(&request_address).await, 0);
+    let pool: LpPool = env.account(&lp_pool_address).await;
+    assert!(pool.withdraw_queue.is_empty());
+    assert_eq!(pool.withdraw_queue.queued_shares, 0);
+    assert_eq!(pool.total_shares, 500_000_000);
+}
+
+#[tokio::test]
+async fn test_lp_rewards_stream_pro_rata_to_stakers() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    let (alice, alice_shares) = market.seed_lp(&mut env, 600_000_000).await.unwrap();
+    let (bob, bob_shares) = market.seed_lp(&mut env, 400_000_000).await.unwrap();
+    let alice_key = solana_sdk::signer::Signer::pubkey(&alice);
+    let bob_key = solana_sdk::signer::Signer::pubkey(&bob);
+    let admin = env.admin();
+    let reward_mint = env.create_mint(6).await.unwrap();
+    let admin_rewards = env
+        .create_token_account(&reward_mint, &admin)
+        .await
+        .unwrap();
+    env.mint_to(&reward_mint, &admin_rewards, 1_000_000)
+        .await
+        .unwrap();
+    let alice_rewards = env
+        .create_token_account(&reward_mint, &alice_key)
+        .await
+        .unwrap();
+    let bob_rewards = env
+        .create_token_account(&reward_mint, &bob_key)
+        .await
+        .unwrap();
+
+    env.process(
+        &[
+            lp::initialize_rewards(&admin, &market.usdc_mint, &reward_mint),
+            lp::fund_rewards(&admin, &market.usdc_mint, &admin_rewards, 150_000),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[lp::stake_for_rewards(
+            &alice_key,
+            &market.usdc_mint,
+            &alice_shares,
+            600_000_000,
+        )],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[lp::stake_for_rewards(
+            &bob_key,
+            &market.usdc_mint,
+            &bob_shares,
+            400_000_000,
+        )],
+        &[&bob],
+    )
+    .await
+    .unwrap();
+
+    // 100 per second for 1,000 seconds, split 60/40
+    env.process(
+        &[lp::set_emission_rate(&admin, &market.usdc_mint, 100)],
+        &[],
+    )
+    .await
+    .unwrap();
+    env.advance_clock(1_000).await;
+    env.process(
+        &[lp::claim_rewards(
+            &alice_key,
+            &market.usdc_mint,
+            &alice_rewards,
+        )],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&alice_rewards).await, 60_000);
+
+    // Alice leaves: the rest of the 150,000 funded goes to Bob alone, then emissions stop
+    env.process(
+        &[lp::unstake_from_rewards(
+            &alice_key,
+            &market.usdc_mint,
+            &alice_shares,
+            600_000_000,
+        )],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&alice_shares).await, 600_000_000);
+    env.advance_clock(1_000).await;
+    env.process(
+        &[lp::claim_rewards(&bob_key, &market.usdc_mint, &bob_rewards)],
+        &[&bob],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&bob_rewards).await, 90_000);
+
+    // Nothing left to claim until more is funded
+    env.advance_clock(100).await;
+    let claim = lp::claim_rewards(&alice_key, &market.usdc_mint, &alice_rewards);
+    assert!(env.process(&[claim], &[&alice]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_insurance_surplus_auctioned_for_lp_shares() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    let (lp_wallet, lp_shares) = market.seed_lp(&mut env, 10_000_000_000).await.unwrap();
+    let lp_owner = solana_sdk::signer::Signer::pubkey(&lp_wallet);
+    let borrower = Borrower::open(&mut env, &market, 20 * LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+    let admin = env.admin();
+
+    // $400 of $10,000 lent (3.4% APR) for a year: $13.60 interest, 5% ($0.68) to insurance
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .advance_time(31_557_600)
+        .repay(13_600_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // No target set: nothing to auction
+    let start = lp::start_surplus_auction(&admin, &market.usdc_mint, 500_000);
+    assert!(env.process(&[start.clone()], &[]).await.is_err());
+
+    // $0.18 target leaves a $0.50 surplus
+    env.process(
+        &[core::execute_admin_ops(
+            &admin,
+            vec![AdminOp::SetInsuranceFundTarget { target: 180_000 }],
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let too_big = lp::start_surplus_auction(&admin, &market.usdc_mint, 500_001);
+    assert!(env.process(&[too_big], &[]).await.is_err());
+    env.process(&[start], &[]).await.unwrap();
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.insurance_fund, 180_000);
+
+    // Bids must beat the last one by 5%
+    let bid = |shares, previous: Option<&solana_sdk::pubkey::Pubkey>| {
+        lp::bid_surplus_auction(&lp_owner, &market.usdc_mint, &lp_shares, previous, shares)
+    };
+    env.process(&[bid(500_000, None)], &[&lp_wallet])
+        .await
+        .unwrap();
+    assert!(env
+        .process(&[bid(520_000, Some(&lp_shares))], &[&lp_wallet])
+        .await
+        .is_err());
+    env.process(&[bid(525_000, Some(&lp_shares))], &[&lp_wallet])
+        .await
+        .unwrap();
+
+    // Settles once the auction ends: the lot for the burned shares
+    let winner_account = env
+        .create_token_account(&market.usdc_mint, &lp_owner)
+        .await
+        .unwrap();
+    let settle =
+        lp::settle_surplus_auction(&admin, &market.usdc_mint, &admin, Some(&winner_account));
+    assert!(env.process(&[settle.clone()], &[]).await.is_err());
+    env.advance_time(SECONDS_PER_DAY).await;
+    env.process(&[settle], &[]).await.unwrap();
+
+    assert_eq!(env.token_balance(&winner_account).await, 500_000);
+    assert_eq!(
+        env.token_balance(&lp_shares).await,
+        10_000_000_000 - 525_000
+    );
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_shares, 10_000_000_000 - 525_000);
+    assert_eq!(pool.total_deposits, 10_012_920_000);
+    let auction = pda::surplus_auction(&pda::lp_pool(&market.usdc_mint).0).0;
+    assert_eq!(env.lamports(&auction).await, 0);
+}
+
+#[tokio::test]
+async fn test_repaying_above_optimal_utilization_waives_interest() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    market.seed_lp(&mut env, 1_000_000_000).await.unwrap();
+    let borrower = Borrower::open(&mut env, &market, 20 * LAMPORTS_PER_SOL)
+        .await
+        .unwrap();
+    let admin = env.admin();
+    env.process(
+        &[
+            lp::set_outflow_limit(&admin, &market.usdc_mint, 0, 1),
+            lp::set_protocol_fee_switch(&admin, &market.usdc_mint, true),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // $850 of the $1,000 pool lent (85%, 29.75% APR) for a year: $252.875 interest,
+    // repaid in full
+    Scenario::new()
+        .deposit_sol(12 * LAMPORTS_PER_SOL)
+        .borrow(850_000_000)
+        .advance_time(31_557_600)
+        .repay(252_875_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // 2.5% of the interest ($6.321875) is waived and comes off the principal
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].accrued_interest, 0);
+    assert_eq!(position.borrows[0].amount, 843_678_125);
+
+    // LPs still get their 75% and insurance its 5%, the protocol fee pays for the
+    // incentive
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_deposits, 1_189_656_250);
+    assert_eq!(pool.insurance_pending, 12_643_750);
+    assert_eq!(pool.protocol_fees_owed, 50_575_000 - 6_321_875);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.insurance_fund, 12_643_750);
+}
+
+#[tokio::test]
+async fn test_referrer_earns_a_cut_of_interest_paid() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    market.seed_lp(&mut env, 10_000_000_000).await.unwrap();
+
+    let referrer = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let referrer_key = solana_sdk::signer::Signer::pubkey(&referrer);
+    env.process(
+        &[
+            lending::register_referrer(&referrer_key),
+            lending::open_referral_vault(&referrer_key, &market.usdc_mint),
+        ],
+        &[&referrer],
+    )
+    .await
+    .unwrap();
+    let borrower =
+        Borrower::open_referred(&mut env, &market, 20 * LAMPORTS_PER_SOL, Some(referrer_key))
+            .await
+            .unwrap();
+
+    // $400 for a year at 3.4%: $13.60 interest, 10% of it to the referrer
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .advance_time(31_557_600)
+        .repay(13_600_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let vault = pda::referral_vault(&referrer_key, &market.usdc_mint).0;
+    assert_eq!(env.token_balance(&vault).await, 1_360_000);
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.referrer, referrer_key);
+    assert_eq!(position.borrows[0].amount, 400_000_000);
+
+    // LPs and insurance split the other $12.24 as usual
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_deposits, 10_011_628_000);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.insurance_fund, 612_000);
+
+    let destination = env
+        .create_token_account(&market.usdc_mint, &referrer_key)
+        .await
+        .unwrap();
+    env.process(
+        &[lending::claim_referral_rewards(
+            &referrer_key,
+            &market.usdc_mint,
+            &destination,
+        )],
+        &[&referrer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&destination).await, 1_360_000);
+    let registry: Referrer = env.account(&pda::referrer(&referrer_key).0).await;
+    assert_eq!(registry.referred_positions, 1);
+    assert_eq!(registry.total_claimed, 1_360_000);
+}
+
+#[tokio::test]
+async fn test_points_accrue_per_usd_day() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(&[lending::open_points_ledger(&owner)], &[&borrower.wallet])
+        .await
+        .unwrap();
+
+    // The first crank only records balances
+    env.advance_time(SECONDS_PER_DAY).await;
+    env.process(&[lending::accrue_points(&owner, None)], &[])
+        .await
+        .unwrap();
+    let ledger_address = pda::points_ledger(&borrower.position()).0;
+    let ledger: PointsLedger = env.account(&ledger_address).await;
+    assert_eq!(ledger.points, 0);
+
+    // A day of $1,000 collateral at 1x and $400 debt at 2x: 1,800 points,
+    // exported in a one-leaf snapshot
+    env.advance_time(SECONDS_PER_DAY).await;
+    let root = points_leaf(&owner, 1_800_000_000);
+    env.process(
+        &[
+            lending::accrue_points(&owner, None),
+            lending::post_points_snapshot(&env.admin(), 1, root, 1_800_000_000, 1),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let ledger: PointsLedger = env.account(&ledger_address).await;
+    assert_eq!(ledger.points, 1_800_000_000);
+
+    let snapshot: PointsSnapshot = env.account(&pda::points_snapshot(1).0).await;
+    assert_eq!(snapshot.total_points, 1_800_000_000);
+    assert!(verify_points_proof(
+        &snapshot.merkle_root,
+        points_leaf(&ledger.owner, ledger.points),
+        &[]
+    ));
+}
+
+#[tokio::test]
+async fn test_position_statement_once_per_period() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let debt = [market.usdc_mint];
+    let crank = lending::crank_position_statement(&owner, None, &debt, false);
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(
+        &[lending::open_statement_ledger(&owner, &debt, false)],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let ledger_address = pda::statement_ledger(&borrower.position()).0;
+    let opened: StatementLedger = env.account(&ledger_address).await;
+
+    // Not before the period is over
+    env.advance_time(STATEMENT_PERIOD - 60).await;
+    assert!(env.process(&[crank.clone()], &[]).await.is_err());
+
+    // The crank settles the month's interest and starts the next period from it
+    env.advance_time(60).await;
+    env.process(&[crank], &[]).await.unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    let ledger: StatementLedger = env.account(&ledger_address).await;
+    assert!(position.borrows[0].accrued_interest > 0);
+    assert_eq!(
+        position.statement_totals.usdc.interest,
+        position.borrows[0].accrued_interest
+    );
+    assert_eq!(ledger.totals_at_start, position.statement_totals);
+    assert_eq!(ledger.period_start, opened.period_start + STATEMENT_PERIOD);
+    assert_eq!(ledger.statements, 1);
+}
+
+#[tokio::test]
+async fn test_notify_subscriber_pays_service_at_alert_ltv() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let service_wallet = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let service = solana_sdk::signer::Signer::pubkey(&service_wallet);
+    let debt = [market.usdc_mint];
+    let fee = 10_000;
+
+    // $1,000 of SOL against $400 of debt: 40% LTV, alert at 50%
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(
+        &[lending::register_notify_service(&service, fee)],
+        &[&service_wallet],
+    )
+    .await
+    .unwrap();
+    env.process(
+        &[lending::subscribe_notifications(
+            &owner,
+            &service,
+            5_000,
+            LAMPORTS_PER_SOL / 100,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    // The fee is locked in: raising it only charges new subscribers
+    env.process(
+        &[lending::update_notify_service(&service, 5 * fee, true)],
+        &[&service_wallet],
+    )
+    .await
+    .unwrap();
+
+    // Healthy: no notice
+    let notify = lending::notify_subscriber(&owner, &service, None, &debt);
+    assert!(env.process(&[notify.clone()], &[]).await.is_err());
+
+    // SOL at $70: 57% LTV, the service is paid and notified once
+    market.set_sol_price(&mut env, 70_000_000).await.unwrap();
+    env.advance_clock(1).await;
+    let service_before = env.lamports(&service).await;
+    env.process(&[notify.clone()], &[]).await.unwrap();
+    assert_eq!(env.lamports(&service).await, service_before + fee);
+    env.advance_clock(60).await;
+    assert!(env.process(&[notify.clone()], &[]).await.is_err());
+
+    let position = borrower.position();
+    let subscription_address = pda::notify_subscription(&position, &service).0;
+    let subscription: NotifySubscription = env.account(&subscription_address).await;
+    let notify_service: NotifyService = env.account(&pda::notify_service(&service).0).await;
+    assert_eq!(subscription.notices, 1);
+    assert_eq!(notify_service.notices_sent, 1);
+    assert_eq!(notify_service.subscribers, 1);
+
+    // Past the cooldown it goes out again
+    env.advance_time(NOTIFY_COOLDOWN).await;
+    env.process(&[notify], &[]).await.unwrap();
+    assert_eq!(env.lamports(&service).await, service_before + 2 * fee);
+
+    // Unsubscribing refunds the rest of the vault
+    let fee_vault = pda::notify_fee_vault(&subscription_address).0;
+    let refund = env.lamports(&fee_vault).await;
+    assert_eq!(refund, LAMPORTS_PER_SOL / 100 - 2 * fee);
+    let owner_before = env.lamports(&owner).await;
+    env.process(
+        &[lending::unsubscribe_notifications(&owner, &service)],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.lamports(&fee_vault).await, 0);
+    assert!(env.lamports(&owner).await > owner_before + refund);
+    let notify_service: NotifyService = env.account(&pda::notify_service(&service).0).await;
+    assert_eq!(notify_service.subscribers, 0);
+}
+
+#[tokio::test]
+async fn test_voting_power_snapshots_lp_shares_and_net_deposits() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+
+    // $1,000 of SOL against $400 of debt, $100 of it deposited back for bUSDC
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let lp_token_account = env
+        .create_token_account(&pda::lp_token_mint(&market.usdc_mint).0, &owner)
+        .await
+        .unwrap();
+    env.process(
+        &[lp::deposit(
+            &owner,
+            &market.usdc_mint,
+            &borrower.usdc_account,
+            &lp_token_account,
+            100_000_000,
+            false,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+
+    // Only the current epoch can be snapshotted
+    let epoch = voting_epoch(env.clock().await.unix_timestamp);
+    let snapshot = |epoch| {
+        lending::snapshot_voting_power(
+            &owner,
+            &market.usdc_mint,
+            epoch,
+            true,
+            Some(lp_token_account),
+            None,
+        )
+    };
+    assert!(env
+        .process(&[snapshot(epoch + 1)], &[&borrower.wallet])
+        .await
+        .is_err());
+    env.process(&[snapshot(epoch)], &[&borrower.wallet])
+        .await
+        .unwrap();
+
+    let power: VotingPower = env.account(&pda::voting_power(&owner, epoch).0).await;
+    assert_eq!(power.lp_shares, 100_000_000);
+    assert_eq!(power.lp_value, 100_000_000);
+    assert_eq!(power.net_deposits_usd, 600_000_000);
+    assert_eq!(power.voting_power, 700_000_000);
+}
+
+#[tokio::test]
+async fn test_eurc_debt_valued_at_eur_usd() {
+    let (mut env, market, borrower) = setup().await;
+    market
+        .seed_pool(&mut env, &market.eurc_mint, 10_000_000_000)
+        .await
+        .unwrap();
+
+    // $1,000 collateral allows $750: 700 EURC is $756 at $1.08
+    let result = Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow_eurc(700_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(result.unwrap_err().0, Step::BorrowEurc(700_000_000));
+
+    // 690 EURC is $745.20, leaving no room for another $10 of USDC
+    let result = Scenario::new()
+        .borrow_eurc(690_000_000)
+        .borrow(10_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(result.unwrap_err().0, Step::Borrow(10_000_000));
+
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].asset_type, AssetType::EURC);
+    assert_eq!(position.borrows[0].amount, 690_000_000);
+    assert_eq!(env.token_balance(&borrower.eurc_account).await, 690_000_000);
+
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_borrowed_usd, 745_200_000);
+
+    // Repaying 190 EURC removes $205.20 of protocol debt
+    Scenario::new()
+        .repay_eurc(190_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_borrowed_usd, 540_000_000);
+    let pool: LpPool = env.account(&pda::lp_pool(&market.eurc_mint).0).await;
+    assert_eq!(pool.total_borrowed, 500_000_000);
+}
+
+#[tokio::test]
+async fn test_agent_borrows_from_any_pool_against_usd_limit() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    market
+        .seed_pool(&mut env, &market.eurc_mint, 10_000_000_000)
+        .await
+        .unwrap();
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(
+        &[lending::configure_agent(
+            &owner,
+            500_000_000,
+            true,
+            false,
+            8_000,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let eur_feed = Some(market.eur_price_feed());
+    let agent_borrow =
+        |mint: &solana_sdk::pubkey::Pubkey, account: &solana_sdk::pubkey::Pubkey, amount| {
+            lending::agent_borrow(&owner, mint, account, amount, eur_feed, None)
+        };
+
+    // The $500 limit counts EURC at $1.08: 500 EURC is $540
+    let too_much = agent_borrow(&market.eurc_mint, &borrower.eurc_account, 500_000_000);
+    assert!(env.process(&[too_much], &[&borrower.wallet]).await.is_err());
+    env.process(
+        &[agent_borrow(
+            &market.eurc_mint,
+            &borrower.eurc_account,
+            400_000_000,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].asset_type, AssetType::EURC);
+    assert_eq!(position.borrows[0].amount, 400_000_000);
+    assert_eq!(env.token_balance(&borrower.eurc_account).await, 400_000_000);
+    let config: AgentConfig = env
+        .account(&pda::agent_config(&borrower.position()).0)
+        .await;
+    assert_eq!(config.daily_borrowed, 432_000_000);
+
+    // USDC draws on the same limit
+    let over = agent_borrow(&market.usdc_mint, &borrower.usdc_account, 70_000_000);
+    assert!(env.process(&[over], &[&borrower.wallet]).await.is_err());
+    env.process(
+        &[agent_borrow(
+            &market.usdc_mint,
+            &borrower.usdc_account,
+            60_000_000,
+        )],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_borrowed_usd, 492_000_000);
+
+    // Repaying on the EURC pool leaves the USDC debt alone
+    let repay = lending::agent_auto_repay(
+        &owner,
+        &market.eurc_mint,
+        &borrower.eurc_account,
+        100_000_000,
+        eur_feed,
+    );
+    env.process(&[repay], &[&borrower.wallet]).await.unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    let owed = |asset_type| {
+        position
+            .borrows
+            .iter()
+            .find(|b| b.asset_type == asset_type)
+            .map(|b| b.amount + b.accrued_interest)
+    };
+    assert_eq!(owed(AssetType::EURC), Some(300_000_000));
+    assert_eq!(owed(AssetType::USDC), Some(60_000_000));
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_borrowed_usd, 384_000_000);
+}
+
+#[tokio::test]
+async fn test_hard_liquidation_and_bad_debt() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let liquidator = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let liquidator_key = solana_sdk::signer::Signer::pubkey(&liquidator);
+    let liquidator_usdc = env
+        .create_token_account(&market.usdc_mint, &liquidator_key)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &liquidator_usdc, 1_000_000_000)
+        .await
+        .unwrap();
+    let liquidate = |amount| {
+        lending::liquidate_position(
+            &liquidator_key,
+            &owner,
+            &market.usdc_mint,
+            &liquidator_usdc,
+            amount,
+            Some(market.eur_price_feed()),
+        )
+    };
+
+    // $1,000 collateral, $700 debt: 70% is below the 90% hard threshold
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(700_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let ix = liquidate(200_000_000);
+    assert!(env.process(&[ix], &[&liquidator]).await.is_err());
+
+    // At $75 the LTV is 93%: $200 repaid takes $210 of SOL, 2.8 SOL
+    market.set_sol_price(&mut env, 75_000_000).await.unwrap();
+    env.process(&[liquidate(200_000_000)], &[&liquidator])
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].amount, 500_000_000);
+    assert_eq!(position.collaterals[0].amount, 7_200_000_000);
+    assert_eq!(
+        env.lamports(&liquidator_key).await,
+        LAMPORTS_PER_SOL + 2_800_000_000
+    );
+
+    // At $10 the 7.2 SOL left pays the bonus on $68.57; the rest is bad debt the
+    // empty insurance fund can't cover, so the LPs absorb it
+    market.set_sol_price(&mut env, 10_000_000).await.unwrap();
+    env.process(&[liquidate(u64::MAX)], &[&liquidator])
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert!(position.borrows.is_empty());
+    assert!(position.collaterals.is_empty());
+    assert_eq!(
+        env.token_balance(&liquidator_usdc).await,
+        1_000_000_000 - 200_000_000 - 68_571_428
+    );
+
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_borrowed, 0);
+    assert_eq!(pool.bad_debt, 431_428_572);
+    assert_eq!(pool.total_deposits, 10_000_000_000 - 431_428_572);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_borrowed_usd, 0);
+}
+
+#[tokio::test]
+async fn test_gad_schedule_raises_hard_threshold_in_window() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let admin = env.admin();
+    let liquidator = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let liquidator_key = solana_sdk::signer::Signer::pubkey(&liquidator);
+    let liquidator_usdc = env
+        .create_token_account(&market.usdc_mint, &liquidator_key)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &liquidator_usdc, 1_000_000_000)
+        .await
+        .unwrap();
+    let liquidate = |amount| {
+        lending::liquidate_position(
+            &liquidator_key,
+            &owner,
+            &market.usdc_mint,
+            &liquidator_usdc,
+            amount,
+            Some(market.eur_price_feed()),
+        )
+    };
+
+    // A one-hour window opening now, demanding 5% more LTV
+    let now = env.clock().await.unix_timestamp;
+    let start = (now + 3 * 86_400).rem_euclid(SECONDS_PER_WEEK);
+    let window = ThrottleWindow {
+        start: start as u32,
+        end: ((start + 3_600) % SECONDS_PER_WEEK) as u32,
+    };
+    let too_strict = core::set_gad_schedule(&admin, vec![window], 1_001);
+    assert!(env.process(&[too_strict], &[]).await.is_err());
+    env.process(&[core::set_gad_schedule(&admin, vec![window], 500)], &[])
+        .await
+        .unwrap();
+
+    // At $75 the LTV is 93%: past the usual 90%, short of the window's 95%
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(700_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    market.set_sol_price(&mut env, 75_000_000).await.unwrap();
+    let ix = liquidate(200_000_000);
+    assert!(env.process(&[ix], &[&liquidator]).await.is_err());
+
+    // Once the window closes the usual threshold applies again
+    env.advance_time(3_600).await;
+    env.process(&[liquidate(200_000_000)], &[&liquidator])
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.collaterals[0].amount, 7_200_000_000);
+}
+
+#[tokio::test]
+async fn test_asset_caps_bound_deposits_and_borrows() {
+    let (mut env, market, borrower) = setup().await;
+    let admin = env.admin();
+    let stranger = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let stranger_key = solana_sdk::signer::Signer::pubkey(&stranger);
+
+    // At most 15 SOL of collateral and 500 USDC lent out
+    let ix = core::set_supply_cap(&stranger_key, &market.sol_mint, 1);
+    assert!(env.process(&[ix], &[&stranger]).await.is_err());
+    env.process(
+        &[
+            core::set_supply_cap(&admin, &market.sol_mint, 15 * LAMPORTS_PER_SOL),
+            core::set_borrow_cap(&admin, &market.usdc_mint, 500_000_000),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let caps: AssetCaps = env.account(&pda::asset_caps(&market.usdc_mint).0).await;
+    assert_eq!((caps.supply_cap, caps.borrow_cap), (0, 500_000_000));
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_sol_collateral, 10 * LAMPORTS_PER_SOL);
+
+    // Past either cap is refused
+    let result = Scenario::new()
+        .deposit_sol(6 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert!(result.is_err());
+    let result = Scenario::new()
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert!(result.is_err());
+
+    // Withdrawals free up supply room, lifting the cap frees up borrows
+    Scenario::new()
+        .withdraw_sol(LAMPORTS_PER_SOL)
+        .deposit_sol(6 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_sol_collateral, 15 * LAMPORTS_PER_SOL);
+
+    env.process(&[core::set_borrow_cap(&admin, &market.usdc_mint, 0)], &[])
+        .await
+        .unwrap();
+    Scenario::new()
+        .borrow(200_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_borrowed, 600_000_000);
+}
+
+#[tokio::test]
+async fn test_liquidation_waits_for_twap() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let liquidator = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let liquidator_key = solana_sdk::signer::Signer::pubkey(&liquidator);
+    let liquidator_usdc = env
+        .create_token_account(&market.usdc_mint, &liquidator_key)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &liquidator_usdc, 1_000_000_000)
+        .await
+        .unwrap();
+    let liquidate = |amount| {
+        lending::liquidate_position(
+            &liquidator_key,
+            &owner,
+            &market.usdc_mint,
+            &liquidator_usdc,
+            amount,
+            Some(market.eur_price_feed()),
+        )
+    };
+
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(700_000_000)
+        .advance_time(TWAP_WINDOW)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // A $75 print is 93% LTV at spot, but the TWAP is still $100
+    market.set_sol_price(&mut env, 75_000_000).await.unwrap();
+    let feed: PriceFeed = env.account(&pda::price_feed(&market.sol_mint).0).await;
+    let now = env.clock().await.unix_timestamp;
+    assert_eq!(feed.twap_price(now), 100_000_000);
+    assert!(env
+        .process(&[liquidate(200_000_000)], &[&liquidator])
+        .await
+        .is_err());
+
+    // Once the price has stood for the window, the position is liquidatable
+    env.advance_time(TWAP_WINDOW).await;
+    env.process(&[liquidate(100_000_000)], &[&liquidator])
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert!(position.collaterals[0].amount < 10 * LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn test_convert_eurc_debt_to_usdc() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    market
+        .seed_pool(&mut env, &market.eurc_mint, 10_000_000_000)
+        .await
+        .unwrap();
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow_eurc(100_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let convert = |from: &solana_sdk::pubkey::Pubkey, to: &solana_sdk::pubkey::Pubkey, amount| {
+        lending::convert_debt(&owner, from, to, amount, Some(market.eur_price_feed()))
+    };
+    let same = convert(&market.eurc_mint, &market.eurc_mint, 1);
+    assert!(env.process(&[same], &[&borrower.wallet]).await.is_err());
+
+    // 100 EURC at $1.08 becomes 108 USDC, plus a 0.1% fee booked as interest
+    env.process(
+        &[convert(&market.eurc_mint, &market.usdc_mint, u64::MAX)],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows.len(), 1);
+    assert_eq!(position.borrows[0].asset_type, AssetType::USDC);
+    assert_eq!(position.borrows[0].amount, 108_000_000);
+    assert!(position.borrows[0].accrued_interest >= 108_000);
+
+    // The principal moved between pools; the borrowed EURC stays with the borrower
+    let eurc_pool: LpPool = env.account(&pda::lp_pool(&market.eurc_mint).0).await;
+    let usdc_pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(eurc_pool.total_borrowed, 0);
+    assert_eq!(usdc_pool.total_borrowed, 108_000_000);
+    assert_eq!(env.token_balance(&borrower.eurc_account).await, 100_000_000);
+
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_borrowed_usd, 108_000_000);
+}
+
+#[tokio::test]
+async fn test_fixed_term_loan_rolls_to_variable_at_maturity() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let term = 30 * SECONDS_PER_DAY;
+    let borrow_fixed = |amount, term_secs, max_rate_bps| {
+        lending::borrow_fixed_term(
+            &owner,
+            &market.usdc_mint,
+            &borrower.usdc_account,
+            amount,
+            term_secs,
+            max_rate_bps,
+            Some(market.eur_price_feed()),
+            None,
+            borrower.gate_pass,
+            borrower.market_id,
+            false,
+        )
+    };
+    let repay = || {
+        lending::repay(
+            &owner,
+            &market.usdc_mint,
+            &borrower.usdc_account,
+            1,
+            Some(market.eur_price_feed()),
+            None,
+            None,
+        )
+    };
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // The quote is the pool's current rate plus the premium; a lower cap or a term
+    // under the minimum is refused
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    let rate = fixed_rate_bps(pool.borrow_rate_bps());
+    let too_cheap = borrow_fixed(400_000_000, term, rate - 1);
+    assert!(env
+        .process(&[too_cheap], &[&borrower.wallet])
+        .await
+        .is_err());
+    let too_short = borrow_fixed(400_000_000, MIN_FIXED_TERM - 1, rate);
+    assert!(env
+        .process(&[too_short], &[&borrower.wallet])
+        .await
+        .is_err());
+    env.process(
+        &[borrow_fixed(400_000_000, term, rate)],
+        &[&borrower.wallet],
+    )
+    .await
+    .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].fixed_rate_bps, rate);
+    assert_eq!(
+        position.borrows[0].maturity,
+        position.borrows[0].last_accrued + term
+    );
+
+    // A variable USDC borrow can't merge into the fixed-term entry
+    let variable = Scenario::new().borrow(100_000_000);
+    assert!(variable.run(&mut env, &market, &borrower).await.is_err());
+
+    // Up to maturity the loan accrues the quoted rate, whatever the pool's does
+    env.advance_time(term).await;
+    env.process(&[repay()], &[&borrower.wallet]).await.unwrap();
+    let fixed = accrued_interest(400_000_000, rate, term).unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].accrued_interest, fixed - 1);
+
+    // Past it: the variable rate plus the penalty (with the two units repaid)
+    env.advance_time(term).await;
+    env.process(&[repay()], &[&borrower.wallet]).await.unwrap();
+    let penalty = accrued_interest(400_000_000, MATURITY_PENALTY_BPS, term).unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert!(position.borrows[0].accrued_interest + 2 > fixed + penalty);
+}
+
+#[tokio::test]
+async fn test_repaid_interest_credits_lps_and_insurance() {
+    let (mut env, market, borrower) = setup().await;
+
+    // $400 of $10,000 lent (4% utilization, 3.4% APR) for a year accrues $13.60 of interest
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .advance_time(31_557_600)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &borrower.usdc_account, 13_600_000)
+        .await
+        .unwrap();
+    Scenario::new()
+        .repay(413_600_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let position: Position = env.account(&borrower.position()).await;
+    assert!(position.borrows.is_empty());
+
+    // 95% of the interest raises the bUSDC rate, 5% goes to insurance
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_borrowed, 0);
+    assert_eq!(pool.interest_earned, 12_920_000);
+    assert_eq!(pool.total_deposits, 10_012_920_000);
+
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.insurance_fund, 680_000);
+    assert_eq!(protocol.total_borrowed_usd, 0);
+
+    let lp_vault = pda::lp_vault(&market.usdc_mint).0;
+    assert_eq!(env.token_balance(&lp_vault).await, 10_013_600_000);
+}
+
+#[tokio::test]
+async fn test_protocol_fee_switch_routes_interest_to_treasury() {
+    let (mut env, market, borrower) = setup().await;
+    let admin = env.admin();
+    let treasury_account = env
+        .create_token_account(&market.usdc_mint, &market.treasury)
+        .await
+        .unwrap();
+    env.process(
+        &[lp::set_protocol_fee_switch(&admin, &market.usdc_mint, true)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // The same $13.60 of interest: 20% to the treasury, 5% to insurance, 75% to LPs
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .advance_time(31_557_600)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &borrower.usdc_account, 13_600_000)
+        .await
+        .unwrap();
+    Scenario::new()
+        .repay(413_600_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.protocol_fees_owed, 2_720_000);
+    assert_eq!(pool.total_deposits, 10_010_200_000);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.insurance_fund, 680_000);
+
+    // Only the treasury's account can receive them
+    let stranger_account = env
+        .create_token_account(&market.usdc_mint, &admin)
+        .await
+        .unwrap();
+    let misdirected = lp::claim_protocol_fees(&market.usdc_mint, &stranger_account, &admin);
+    assert!(env.process(&[misdirected], &[]).await.is_err());
+
+    let claim = lp::claim_protocol_fees(&market.usdc_mint, &treasury_account, &admin);
+    env.process(&[claim.clone()], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&treasury_account).await, 2_720_000);
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.protocol_fees_owed, 0);
+    assert_eq!(pool.total_deposits, 10_010_200_000);
+
+    // Nothing left to claim
+    env.advance_clock(1).await;
+    assert!(env.process(&[claim], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_reserve_factor_funds_pool_backstop() {
+    let (mut env, market, borrower) = setup().await;
+    let admin = env.admin();
+    env.process(
+        &[
+            lp::init_reserve_vault(&admin, &market.usdc_mint),
+            lp::set_reserve_factor(&admin, &market.usdc_mint, 1_000),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    // Capped at 50%
+    let too_high = lp::set_reserve_factor(&admin, &market.usdc_mint, 5_001);
+    assert!(env.process(&[too_high], &[]).await.is_err());
+
+    // The same $13.60 of interest: 10% to reserves, 5% to insurance, 85% to LPs
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .advance_time(31_557_600)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &borrower.usdc_account, 13_600_000)
+        .await
+        .unwrap();
+    Scenario::new()
+        .repay(413_600_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let pool_address = pda::lp_pool(&market.usdc_mint).0;
+    let pool: LpPool = env.account(&pool_address).await;
+    assert_eq!(pool.reserves_pending, 1_360_000);
+    assert_eq!(pool.total_deposits, 10_011_560_000);
+
+    let reserve_vault = pda::reserve_vault(&market.usdc_mint).0;
+    env.process(&[lp::sweep_reserves(&market.usdc_mint, &admin)], &[])
+        .await
+        .unwrap();
+    assert_eq!(env.token_balance(&reserve_vault).await, 1_360_000);
+    let pool: LpPool = env.account(&pool_address).await;
+    assert_eq!(pool.reserves_pending, 0);
+
+    // Only the admin releases them, into the pool's LPs
+    let stranger = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let stranger_key = solana_sdk::signer::Signer::pubkey(&stranger);
+    let unauthorized = lp::release_reserves(&stranger_key, &market.usdc_mint, 1_000_000);
+    assert!(env.process(&[unauthorized], &[&stranger]).await.is_err());
+    env.process(
+        &[lp::release_reserves(&admin, &market.usdc_mint, 1_000_000)],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(&reserve_vault).await, 360_000);
+    let pool: LpPool = env.account(&pool_address).await;
+    assert_eq!(pool.total_deposits, 10_012_560_000);
+}
+
+#[tokio::test]
+async fn test_insurance_vault_covers_bad_debt() {
+    let (mut env, market, borrower) = setup().await;
+    let admin = env.admin();
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let cranker = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let cranker_key = solana_sdk::signer::Signer::pubkey(&cranker);
+    env.process(&[lp::init_insurance_vault(&admin, &market.usdc_mint)], &[])
+        .await
+        .unwrap();
+
+    // $13.60 of interest repaid: its 5% insurance cut waits in the pool vault
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .advance_time(31_557_600)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &borrower.usdc_account, 13_600_000)
+        .await
+        .unwrap();
+    Scenario::new()
+        .repay(413_600_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let pool_address = pda::lp_pool(&market.usdc_mint).0;
+    let pool: LpPool = env.account(&pool_address).await;
+    assert_eq!(pool.insurance_pending, 680_000);
+
+    // Anyone sweeps it into the insurance vault, still booked on the fund
+    let insurance_vault = pda::insurance_vault(&market.usdc_mint).0;
+    let sweep = lp::sweep_insurance(&market.usdc_mint, &cranker_key);
+    env.process(&[sweep.clone()], &[&cranker]).await.unwrap();
+    assert_eq!(env.token_balance(&insurance_vault).await, 680_000);
+    let pool: LpPool = env.account(&pool_address).await;
+    assert_eq!(pool.insurance_pending, 0);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.insurance_fund, 680_000);
+    env.advance_clock(1).await;
+    assert!(env.process(&[sweep], &[&cranker]).await.is_err());
+
+    // Liquidated down to nothing, the position leaves $431.43 of bad debt. None of the
+    // swept insurance is left in the pool vault, so the LPs absorb all of it
+    market.set_sol_price(&mut env, 100_000_000).await.unwrap();
+    Scenario::new()
+        .borrow(700_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let liquidator_usdc = env
+        .create_token_account(&market.usdc_mint, &cranker_key)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &liquidator_usdc, 1_000_000_000)
+        .await
+        .unwrap();
+    let liquidate = |amount| {
+        lending::liquidate_position(
+            &cranker_key,
+            &owner,
+            &market.usdc_mint,
+            &liquidator_usdc,
+            amount,
+            Some(market.eur_price_feed()),
+        )
+    };
+    market.set_sol_price(&mut env, 75_000_000).await.unwrap();
+    env.process(&[liquidate(200_000_000)], &[&cranker])
+        .await
+        .unwrap();
+    market.set_sol_price(&mut env, 10_000_000).await.unwrap();
+    env.process(&[liquidate(u64::MAX)], &[&cranker])
+        .await
+        .unwrap();
+    let pool: LpPool = env.account(&pool_address).await;
+    assert_eq!(pool.bad_debt, 431_428_572);
+    let deposits = pool.total_deposits;
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.bad_debt, 431_428_572);
+
+    // The insurance vault pays back what it holds
+    let cover = lp::cover_bad_debt(&market.usdc_mint, &cranker_key, u64::MAX);
+    env.process(&[cover.clone()], &[&cranker]).await.unwrap();
+    assert_eq!(env.token_balance(&insurance_vault).await, 0);
+    let pool: LpPool = env.account(&pool_address).await;
+    assert_eq!(pool.bad_debt, 431_428_572 - 680_000);
+    assert_eq!(pool.total_deposits, deposits + 680_000);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.insurance_fund, 0);
+
+    // Nothing left to cover it with
+    env.advance_clock(1).await;
+    assert!(env.process(&[cover], &[&cranker]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_write_off_bad_debt_behind_dust_collateral() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let cranker = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let cranker_key = solana_sdk::signer::Signer::pubkey(&cranker);
+    market.set_sol_price(&mut env, 100_000_000).await.unwrap();
+    Scenario::new()
+        .deposit_sol(LAMPORTS_PER_SOL)
+        .borrow(50_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let pool_address = pda::lp_pool(&market.usdc_mint).0;
+    let pool: LpPool = env.account(&pool_address).await;
+    let (deposits, borrowed) = (pool.total_deposits, pool.total_borrowed);
+    let write_off = lending::write_off_bad_debt(
+        &cranker_key,
+        &owner,
+        &market.usdc_mint,
+        None,
+        Some(market.eur_price_feed()),
+    );
+
+    // $100 of SOL still backs the debt
+    assert!(env
+        .process(&[write_off.clone()], &[&cranker])
+        .await
+        .is_err());
+
+    // At $0.50 the SOL is dust: the $50 is written off, the LPs absorbing it with an
+    // empty insurance fund, and the dust stays on the position
+    market.set_sol_price(&mut env, 500_000).await.unwrap();
+    env.process(&[write_off.clone()], &[&cranker])
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert!(position.borrows.is_empty());
+    assert_eq!(position.collaterals[0].amount, LAMPORTS_PER_SOL);
+    let pool: LpPool = env.account(&pool_address).await;
+    assert_eq!(pool.bad_debt, 50_000_000);
+    assert_eq!(pool.total_deposits, deposits - 50_000_000);
+    assert_eq!(pool.total_borrowed, borrowed - 50_000_000);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.bad_debt, 50_000_000);
+
+    // Nothing left to write off
+    env.advance_clock(1).await;
+    assert!(env.process(&[write_off], &[&cranker]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_borrows_accrue_against_their_pool_index() {
+    let (mut env, market, borrower) = setup().await;
+    market
+        .seed_pool(&mut env, &market.eurc_mint, 10_000_000_000)
+        .await
+        .unwrap();
+
+    // $400 USDC for a year at 3.4%, 100 EURC only for the second half at 3.1%
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .advance_time(15_778_800)
+        .borrow_eurc(100_000_000)
+        .advance_time(15_778_800)
+        .repay(6_800_000)
+        .repay_eurc(550_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    let position: Position = env.account(&borrower.position()).await;
+    let usdc = &position.borrows[0];
+    let eurc = &position.borrows[1];
+    assert_eq!(usdc.asset_type, AssetType::USDC);
+    assert_eq!(eurc.asset_type, AssetType::EURC);
+
+    // $13.60 and 1.55 EURC accrued, the partial repayments paid part of each
+    assert_eq!(usdc.amount, 400_000_000);
+    assert_eq!(usdc.accrued_interest, 6_800_000);
+    assert_eq!(eurc.amount, 100_000_000);
+    assert_eq!(eurc.accrued_interest, 1_000_000);
+
+    // Each entry is settled up to its own pool's index
+    let usdc_pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    let eurc_pool: LpPool = env.account(&pda::lp_pool(&market.eurc_mint).0).await;
+    assert_eq!(usdc.borrow_index, usdc_pool.borrow_index);
+    assert_eq!(eurc.borrow_index, eurc_pool.borrow_index);
+    assert_eq!(usdc.last_accrued, position.last_update);
+    assert_eq!(eurc.last_accrued, position.last_update);
+}
+
+#[tokio::test]
+async fn test_refresh_rates_publishes_the_utilization_rate() {
+    let (mut env, market, borrower) = setup().await;
+
+    // $400 of $10,000 lent: 4% utilization
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    env.process(&[lp::refresh_rates(&market.usdc_mint)], &[])
+        .await
+        .unwrap();
+
+    // 3% base plus 4/80 of the 8% slope, replacing the registered 5%
+    let borrowable: Borrowable = env.account(&pda::borrowable(&market.usdc_mint).0).await;
+    assert_eq!(borrowable.interest_rate_bps, 340);
+}
+
+#[tokio::test]
+async fn test_wash_repayments_earn_no_reputation() {
+    let (mut env, market, borrower) = setup().await;
+
+    // Repaying debt opened in the same day earns nothing
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .repay(100_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.reputation.successful_repayments, 0);
+
+    // A day later a meaningful repayment counts, once per day, dust never does
+    Scenario::new()
+        .advance_time(SECONDS_PER_DAY)
+        .repay(1_000_000)
+        .repay(100_000_000)
+        .repay(90_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.reputation.successful_repayments, 1);
+}
+
+#[tokio::test]
+async fn test_repayment_schedule_lapses_after_missed_week() {
+    let (mut env, market, borrower) = setup().await;
+
+    // $400 debt: the weekly commitment must be at least $4
+    let result = Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(400_000_000)
+        .commit_schedule(3_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(result.unwrap_err().0, Step::CommitSchedule(3_000_000));
+
+    Scenario::new()
+        .commit_schedule(10_000_000)
+        .repay_on_schedule(10_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let schedule_key = pda::repayment_schedule(&borrower.position()).0;
+    let schedule: RepaymentSchedule = env.account(&schedule_key).await;
+    assert_eq!(schedule.paid_this_period, 10_000_000);
+    assert!(!schedule.lapsed);
+
+    // Skipping the second week lapses the schedule for good
+    Scenario::new()
+        .advance_time(2 * REPAYMENT_PERIOD)
+        .repay_on_schedule(11_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let schedule: RepaymentSchedule = env.account(&schedule_key).await;
+    assert!(schedule.lapsed);
+}
+
+#[tokio::test]
+async fn test_credit_line_locks_collateral_and_charges_standby_fee() {
+    let (mut env, market, borrower) = setup().await;
+
+    // $1,000 collateral backs a $600 line; 7 SOL would only back $525
+    let result = Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .open_credit_line(600_000_000)
+        .withdraw_sol(3 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(
+        result.unwrap_err().0,
+        Step::WithdrawSol(3 * LAMPORTS_PER_SOL)
+    );
+
+    // A year with $400 unused costs 0.5%, booked as USDC interest
+    let result = Scenario::new()
+        .draw_credit_line(200_000_000)
+        .advance_time(31_557_600)
+        .accrue_standby_fee()
+        .draw_credit_line(401_000_000)
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert_eq!(result.unwrap_err().0, Step::DrawCreditLine(401_000_000));
+    assert_eq!(env.token_balance(&borrower.usdc_account).await, 200_000_000);
+
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.borrows[0].amount, 200_000_000);
+    assert_eq!(position.borrows[0].accrued_interest, 2_000_000);
+
+    // Closing the line releases the collateral behind the unused limit
+    Scenario::new()
+        .close_credit_line()
+        .withdraw_sol(3 * LAMPORTS_PER_SOL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let line = pda::credit_line(&borrower.position()).0;
+    assert_eq!(env.lamports(&line).await, 0);
+}
+
+#[tokio::test]
+async fn test_sweep_moves_stray_lamports_to_treasury() {
+    let (mut env, market, borrower) = setup().await;
+    let admin = env.admin();
+    let position = borrower.position();
+    let position_rent = env.lamports(&position).await;
+
+    // Stray SOL sent straight to the position PDA
+    env.process(
+        &[solana_sdk::system_instruction::transfer(
+            &admin,
+            &position,
+            LAMPORTS_PER_SOL,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let treasury_before = env.lamports(&market.treasury).await;
+    env.process(
+        &[lending::sweep_excess_lamports(
+            &admin,
+            &market.treasury,
+            &[position],
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(env.lamports(&position).await, position_rent);
+    assert_eq!(
+        env.lamports(&market.treasury).await,
+        treasury_before + LAMPORTS_PER_SOL
+    );
+
+    // Wallets and other non-lending accounts can't be swept
+    let wallet = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let result = env
+        .process(
+            &[lending::sweep_excess_lamports(
+                &admin,
+                &market.treasury,
+                &[wallet],
+            )],
+            &[],
+        )
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_deposit_borrow_price_drop_gad() {
+    let (mut env, market, borrower) = setup().await;
+
+    // $1,000 collateral, $600 debt (60% LTV)
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(600_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // Healthy position: GAD refuses to run
+    let result = Scenario::new()
+        .advance_time(MIN_GAD_CRANK_INTERVAL)
+        .crank_gad()
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert!(result.is_err());
+
+    // SOL drops 30%: $700 collateral, ~86% LTV. Without a liquidation split the
+    // whole slice is LP recovery, which crank_gad can't deliver, so it has nothing
+    // to do and the collateral stays for a swap crank
+    let result = Scenario::new()
+        .set_sol_price(70_000_000)
+        .advance_time(MIN_GAD_CRANK_INTERVAL)
+        .crank_gad()
+        .run(&mut env, &market, &borrower)
+        .await;
+    assert!(result.is_err());
+
+    let position: Position = env.account(&borrower.position()).await;
+    assert_eq!(position.collaterals[0].amount, 10 * LAMPORTS_PER_SOL);
+    assert_eq!(position.borrows[0].amount, 600_000_000);
+    assert_eq!(position.total_gad_liquidated_usd, 0);
+}
+
+#[tokio::test]
+async fn test_gad_honors_liquidation_split() {
+    let (mut env, market, borrower) = setup().await;
+    let admin = env.admin();
+
+    // A split must add up to 100%
+    let split = LiquidationSplit {
+        lp_recovery_bps: 8_000,
+        treasury_bps: 1_000,
+        insurance_bps: 500,
+        cranker_bps: 500,
+    };
+    let set_split =
+        |split| core::execute_admin_ops(&admin, vec![AdminOp::SetLiquidationSplit { split }]);
+    let uneven = LiquidationSplit {
+        treasury_bps: 2_000,
+        ..split
+    };
+    assert!(env.process(&[set_split(uneven)], &[]).await.is_err());
+    env.process(&[set_split(split)], &[]).await.unwrap();
+
+    // $1,000 collateral, $600 debt, then SOL drops 30%
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(600_000_000)
+        .set_sol_price(70_000_000)
+        .advance_time(MIN_GAD_CRANK_INTERVAL)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    let position: Position = env.account(&borrower.position()).await;
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    let now = env.clock().await.unix_timestamp;
+    let debt_before = math::owed_at(&position.borrows[0], &pool, now);
+    let treasury_before = env.lamports(&market.treasury).await;
+    Scenario::new()
+        .crank_gad()
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // crank_gad can't deliver the 80% LP recovery share to the pool, so it stays on
+    // the position and no debt is repaid; lending only settles the hour of interest.
+    // The treasury holds the treasury and insurance shares, 15 of the 20% taken
+    let position: Position = env.account(&borrower.position()).await;
+    let debt = position.borrows[0].amount + position.borrows[0].accrued_interest;
+    assert_eq!(debt, debt_before);
+    let taken = 10 * LAMPORTS_PER_SOL - position.collaterals[0].amount;
+    let treasury_gain = env.lamports(&market.treasury).await - treasury_before;
+    assert!(treasury_gain > 0);
+    assert!(treasury_gain.abs_diff(taken * 3 / 4) <= 3);
+    assert!(position.total_gad_liquidated_usd > 0);
+}
+
+#[tokio::test]
+async fn test_liquidation_auction_and_shortfall() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let admin = env.admin();
+    let sol_price_feed = pda::price_feed(&market.sol_mint).0;
+    let bidder = env.funded_wallet(LAMPORTS_PER_SOL).await.unwrap();
+    let bidder_key = solana_sdk::signer::Signer::pubkey(&bidder);
+    let bidder_usdc = env
+        .create_token_account(&market.usdc_mint, &bidder_key)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &bidder_usdc, 1_000_000_000)
+        .await
+        .unwrap();
+    let start = || {
+        gad::start_auction(
+            &owner,
+            &market.usdc_mint,
+            &sol_price_feed,
+            Some(market.eur_price_feed()),
+            None,
+            &[market.usdc_mint],
+            &admin,
+        )
+    };
+    let bid = |amount| {
+        gad::bid_auction(
+            &owner,
+            &market.usdc_mint,
+            &bidder_key,
+            &bidder_usdc,
+            amount,
+            0,
+            &sol_price_feed,
+            Some(market.eur_price_feed()),
+            &[market.usdc_mint],
+        )
+    };
+    let settle = || {
+        gad::settle_auction(
+            &owner,
+            &market.usdc_mint,
+            &admin,
+            Some(market.eur_price_feed()),
+            &[market.usdc_mint],
+        )
+    };
+
+    // $1,000 collateral, $700 debt: 70% is below the 90% hard threshold
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(700_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+    assert!(env.process(&[start()], &[]).await.is_err());
+
+    // At $75 the LTV is 93%; half way through, the ask is 10% under the oracle
+    market.set_sol_price(&mut env, 75_000_000).await.unwrap();
+    env.process(&[start()], &[]).await.unwrap();
+    env.advance_time(LIQUIDATION_AUCTION_DURATION / 2).await;
+    env.process(&[bid(200_000_000)], &[&bidder]).await.unwrap();
+    assert_eq!(
+        env.lamports(&bidder_key).await,
+        LAMPORTS_PER_SOL + 200_000_000 * LAMPORTS_PER_SOL / 67_500_000
+    );
+
+    // SOL is left and the discount is still growing
+    assert!(env.process(&[settle()], &[]).await.is_err());
+
+    // At $10, once it has stood for the TWAP window, the rest of the SOL can't cover
+    // the debt: it is sold, and settling writes the shortfall off the pool
+    market.set_sol_price(&mut env, 10_000_000).await.unwrap();
+    env.advance_time(TWAP_WINDOW).await;
+    env.process(&[bid(u64::MAX)], &[&bidder]).await.unwrap();
+    env.process(&[settle()], &[]).await.unwrap();
+
+    let position: Position = env.account(&borrower.position()).await;
+    assert!(position.collaterals.is_empty());
+    assert!(position.borrows.is_empty());
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(pool.total_borrowed, 0);
+    assert!(pool.bad_debt > 0);
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.total_borrowed_usd, 0);
+}
+
+#[tokio::test]
+async fn test_gad_sells_staked_collateral() {
+    let (mut env, market, borrower) = setup().await;
+    let owner = solana_sdk::signer::Signer::pubkey(&borrower.wallet);
+    let admin = env.admin();
+    let position_key = borrower.position();
+    let msol_vault = pda::msol_vault(&position_key).0;
+
+    // $1,000 collateral, $600 debt
+    Scenario::new()
+        .deposit_sol(10 * LAMPORTS_PER_SOL)
+        .borrow(600_000_000)
+        .run(&mut env, &market, &borrower)
+        .await
+        .unwrap();
+
+    // Marinade isn't loaded, so stand the deposit in as staked: 10 mSOL in the
+    // position's lending mSOL vault, held by the position PDA
+    let msol_mint = env.create_mint(9).await.unwrap();
+    env.process(
+        &[core::register_collateral(
+            &admin,
+            &msol_mint,
+            RegisterCollateral {
+                oracle: pda::price_feed(&market.sol_mint).0,
+                max_ltv_bps: DEFAULT_SOL_MAX_LTV_BPS,
+                liquidation_threshold_bps: 8000,
+                liquidation_bonus_bps: 500,
+                decimals: 9,
+                asset_type: AssetType::MSOL,
+            },
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    env.set_token_account(
+        &msol_vault,
+        &msol_mint,
+        &position_key,
+        10 * LAMPORTS_PER_SOL,
+    )
+    .await
+    .unwrap();
+    let mut position: Position = env.account(&position_key).await;
+    position.collaterals[0].asset_type = AssetType::MSOL;
+    env.write_account(&position_key, &position).await;
+
+    // The route sells 0.01 mSOL for $0.70 of USDC into the LP vault
+    let sink = env.create_token_account(&msol_mint, &admin).await.unwrap();
+    let reserve = env
+        .create_token_account(&market.usdc_mint, &admin)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &reserve, 1_000_000)
+        .await
+        .unwrap();
+    let lp_vault = pda::lp_vault(&market.usdc_mint).0;
+    let lp_vault_before = env.token_balance(&lp_vault).await;
+    let crank = || {
+        let mut ix = gad::crank_gad_lst_with_swap(
+            &owner,
+            &msol_mint,
+            SwapRoute::Jupiter,
+            &lp_vault,
+            &market.usdc_mint,
+            &pda::price_feed(&market.sol_mint).0,
+            Some(market.eur_price_feed()),
+            None,
+            None,
+            &[market.usdc_mint],
+            None,
+            None,
+            &admin,
+            mock_swap::route_data(10_000_000, 700_000),
+            700_000,
+        );
+        ix.accounts.extend([
+            AccountMeta::new(msol_vault, false),
+            AccountMeta::new(sink, false),
+            AccountMeta::new_readonly(position_key, false),
+            AccountMeta::new(reserve, false),
+            AccountMeta::new(lp_vault, false),
+            AccountMeta::new_readonly(admin, true),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        ]);
+        ix
+    };
+
+    // Healthy position: GAD refuses to run
+    env.advance_time(MIN_GAD_CRANK_INTERVAL).await;
+    assert!(env.process(&[crank()], &[]).await.is_err());
+
+    // SOL drops 30%: ~86% LTV, the crank sells mSOL signed by the position PDA.
+    // Lending settles the interest due first
+    market.set_sol_price(&mut env, 70_000_000).await.unwrap();
+    env.advance_time(MIN_GAD_CRANK_INTERVAL).await;
+    let pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    let now = env.clock().await.unix_timestamp;
+    let debt_before = math::owed_at(&position.borrows[0], &pool, now);
+    env.process(&[crank()], &[]).await.unwrap();
+
+    assert_eq!(
+        env.token_balance(&msol_vault).await,
+        10 * LAMPORTS_PER_SOL - 10_000_000
+    );
+    assert_eq!(
+        env.token_balance(&lp_vault).await,
+        lp_vault_before + 700_000
+    );
+    let position: Position = env.account(&position_key).await;
+    assert_eq!(position.collaterals[0].asset_type, AssetType::MSOL);
+    assert_eq!(
+        position.collaterals[0].amount,
+        10 * LAMPORTS_PER_SOL - 10_000_000
+    );
+    assert_eq!(
+        position.borrows[0].amount + position.borrows[0].accrued_interest,
+        debt_before - 700_000
+    );
+    assert_eq!(position.total_gad_liquidated_usd, 700_000);
+    assert_eq!(position.reputation.gad_events, 1);
+}
@@ -0,0 +1,500 @@
+use anchor_spl::token::{spl_token, TokenAccount};
+use legasi_sdk::instructions::{core, lending, lp};
+use legasi_sdk::legasi_core::admin::AdminOp;
+use legasi_sdk::legasi_core::constants::SECONDS_PER_DAY;
+use legasi_sdk::legasi_core::listing::{CollateralParams, ListingProposal, LISTING_BOND_LAMPORTS};
+use legasi_sdk::legasi_core::market::{
+    EModeCategory, GadProceedsMode, Market as LendingMarket, MarketPreset,
+};
+use legasi_sdk::legasi_core::state::{AssetType, Borrowable, Collateral, PriceFeed, Protocol};
+use legasi_sdk::legasi_lending::{OfframpRequest, OfframpStatus};
+use legasi_sdk::legasi_lp::{DepositSchedule, LpPool};
+use legasi_sdk::pda;
+use legasi_tests::market::{INITIAL_EURC_PRICE, INITIAL_SOL_PRICE, INITIAL_USDC_PRICE};
+use legasi_tests::{Market, TestEnv};
+use solana_sdk::pubkey::Pubkey;
+
+#[tokio::test]
+async fn test_market_setup_seeds_core_state() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.admin, env.admin());
+    assert_eq!(protocol.treasury, market.treasury);
+    assert!(!protocol.paused);
+
+    let collateral: Collateral = env.account(&pda::collateral(&market.sol_mint).0).await;
+    assert_eq!(collateral.asset_type, AssetType::SOL);
+    assert!(collateral.is_active);
+
+    let borrowable: Borrowable = env.account(&pda::borrowable(&market.usdc_mint).0).await;
+    assert_eq!(borrowable.asset_type, AssetType::USDC);
+    let borrowable: Borrowable = env.account(&pda::borrowable(&market.eurc_mint).0).await;
+    assert_eq!(borrowable.asset_type, AssetType::EURC);
+
+    let sol_feed: PriceFeed = env.account(&pda::price_feed(&market.sol_mint).0).await;
+    assert_eq!(sol_feed.price_usd_6dec, INITIAL_SOL_PRICE);
+    let usdc_feed: PriceFeed = env.account(&pda::price_feed(&market.usdc_mint).0).await;
+    assert_eq!(usdc_feed.price_usd_6dec, INITIAL_USDC_PRICE);
+    let eurc_feed: PriceFeed = env.account(&market.eur_price_feed()).await;
+    assert_eq!(eurc_feed.price_usd_6dec, INITIAL_EURC_PRICE);
+
+    let lp_pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(lp_pool.borrowable_mint, market.usdc_mint);
+    assert_eq!(
+        lp_pool.lp_token_mint,
+        pda::lp_token_mint(&market.usdc_mint).0
+    );
+    let eurc_pool: LpPool = env.account(&pda::lp_pool(&market.eurc_mint).0).await;
+    assert_eq!(eurc_pool.borrowable_mint, market.eurc_mint);
+}
+
+#[tokio::test]
+async fn test_lp_deposit_mints_shares_one_to_one() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+
+    let (_, lp_token_account) = market.seed_lp(&mut env, 1_000_000_000).await.unwrap();
+
+    // First deposit into an empty pool is 1:1
+    assert_eq!(env.token_balance(&lp_token_account).await, 1_000_000_000);
+    assert_eq!(
+        env.token_balance(&pda::lp_vault(&market.usdc_mint).0).await,
+        1_000_000_000
+    );
+    let lp_pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(lp_pool.total_deposits, 1_000_000_000);
+    assert_eq!(lp_pool.total_shares, 1_000_000_000);
+}
+
+#[tokio::test]
+async fn test_lp_withdraw_to_offramp_escrows_liquidity() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    let (lp_wallet, lp_token_account) = market.seed_lp(&mut env, 1_000_000_000).await.unwrap();
+    let owner = solana_sdk::signer::Signer::pubkey(&lp_wallet);
+
+    env.process(
+        &[lending::withdraw_to_offramp(
+            &owner,
+            &market.usdc_mint,
+            &lp_token_account,
+            1,
+            100_000_000,
+            "DE89370400440532013000",
+            "Savings Holder",
+            false,
+        )],
+        &[&lp_wallet],
+    )
+    .await
+    .unwrap();
+
+    let request_address = pda::offramp_request(&owner, 1).0;
+    let request: OfframpRequest = env.account(&request_address).await;
+    assert_eq!(request.amount, 100_000_000);
+    assert_eq!(request.status, OfframpStatus::Pending);
+    let escrow = pda::offramp_escrow(&request_address).0;
+    assert_eq!(env.token_balance(&escrow).await, 100_000_000);
+    assert_eq!(env.token_balance(&lp_token_account).await, 900_000_000);
+    let lp_pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(lp_pool.total_deposits, 900_000_000);
+
+    // A failed transfer refunds the owner, and only once
+    let refund = env
+        .create_token_account(&market.usdc_mint, &owner)
+        .await
+        .unwrap();
+    let settle = lending::settle_offramp_escrow(&env.admin(), &owner, 1, &refund, false);
+    env.process(&[settle], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&refund).await, 100_000_000);
+    let request: OfframpRequest = env.account(&request_address).await;
+    assert_eq!(request.status, OfframpStatus::Failed);
+
+    let settle_again = lending::settle_offramp_escrow(&env.admin(), &owner, 1, &refund, true);
+    assert!(env.process(&[settle_again], &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_scheduled_deposits_are_cranked_into_the_pool() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    market.seed_lp(&mut env, 1_000_000_000).await.unwrap();
+
+    let saver = env.funded_wallet(1_000_000_000).await.unwrap();
+    let owner = solana_sdk::signer::Signer::pubkey(&saver);
+    let source = env
+        .create_token_account(&market.usdc_mint, &owner)
+        .await
+        .unwrap();
+    let lp_token_account = env
+        .create_token_account(&pda::lp_token_mint(&market.usdc_mint).0, &owner)
+        .await
+        .unwrap();
+    env.mint_to(&market.usdc_mint, &source, 300_000_000)
+        .await
+        .unwrap();
+
+    // $100 a day, with a $300 allowance
+    let schedule = pda::deposit_schedule(&owner, &market.usdc_mint).0;
+    env.process(
+        &[
+            lp::create_deposit_schedule(
+                &owner,
+                &market.usdc_mint,
+                &source,
+                &lp_token_account,
+                100_000_000,
+                SECONDS_PER_DAY,
+            ),
+            spl_token::instruction::approve(
+                &spl_token::ID,
+                &source,
+                &schedule,
+                &owner,
+                &[],
+                300_000_000,
+            )
+            .unwrap(),
+        ],
+        &[&saver],
+    )
+    .await
+    .unwrap();
+
+    // The first deposit is due right away, the next one a day later
+    let crank = |cranker: &Pubkey| {
+        lp::crank_scheduled_deposit(
+            cranker,
+            &owner,
+            &market.usdc_mint,
+            &source,
+            &lp_token_account,
+            false,
+        )
+    };
+    env.process(&[crank(&env.admin())], &[]).await.unwrap();
+    assert_eq!(env.token_balance(&lp_token_account).await, 100_000_000);
+    assert!(env.process(&[crank(&owner)], &[&saver]).await.is_err());
+
+    env.advance_time(SECONDS_PER_DAY).await;
+    let keeper = env.funded_wallet(1_000_000_000).await.unwrap();
+    let keeper_key = solana_sdk::signer::Signer::pubkey(&keeper);
+    env.process(&[crank(&keeper_key)], &[&keeper])
+        .await
+        .unwrap();
+    assert_eq!(env.token_balance(&lp_token_account).await, 200_000_000);
+    assert_eq!(env.token_balance(&source).await, 100_000_000);
+
+    let schedule: DepositSchedule = env.account(&schedule).await;
+    assert_eq!(schedule.deposits_made, 2);
+    let lp_pool: LpPool = env.account(&pda::lp_pool(&market.usdc_mint).0).await;
+    assert_eq!(lp_pool.total_deposits, 1_200_000_000);
+}
+
+#[tokio::test]
+async fn test_permissioned_pool_serves_allowlisted_lps_only() {
+    let mut env = TestEnv::start().await;
+    Market::setup(&mut env).await.unwrap();
+    let admin = env.admin();
+
+    let mint = env.create_mint(6).await.unwrap();
+    env.process(&[lp::initialize_pool(&admin, &mint, true)], &[])
+        .await
+        .unwrap();
+    env.process(
+        &[lp::initialize_pool_accounts(
+            &admin,
+            &mint,
+            "Legasi Partner USDC".to_string(),
+            "pUSDC".to_string(),
+            String::new(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let lp_pool: LpPool = env.account(&pda::lp_pool(&mint).0).await;
+    assert!(lp_pool.permissioned);
+
+    let partner = env.funded_wallet(1_000_000_000).await.unwrap();
+    let owner = solana_sdk::signer::Signer::pubkey(&partner);
+    let token_account = env.create_token_account(&mint, &owner).await.unwrap();
+    let lp_token_account = env
+        .create_token_account(&pda::lp_token_mint(&mint).0, &owner)
+        .await
+        .unwrap();
+    env.mint_to(&mint, &token_account, 500_000_000)
+        .await
+        .unwrap();
+
+    // Not allowlisted yet
+    let deposit = |amount, allowlisted| {
+        lp::deposit(
+            &owner,
+            &mint,
+            &token_account,
+            &lp_token_account,
+            amount,
+            allowlisted,
+        )
+    };
+    assert!(env
+        .process(&[deposit(100_000_000, false)], &[&partner])
+        .await
+        .is_err());
+
+    // Same share accounting as an open pool
+    env.process(&[lp::add_lp_to_allowlist(&admin, &mint, &owner)], &[])
+        .await
+        .unwrap();
+    env.process(&[deposit(200_000_000, true)], &[&partner])
+        .await
+        .unwrap();
+    assert_eq!(env.token_balance(&lp_token_account).await, 200_000_000);
+
+    // Removed LPs keep their shares but can't withdraw them
+    env.process(&[lp::remove_lp_from_allowlist(&admin, &mint, &owner)], &[])
+        .await
+        .unwrap();
+    let withdraw = lp::withdraw(
+        &owner,
+        &mint,
+        &token_account,
+        &lp_token_account,
+        50_000_000,
+        false,
+    );
+    assert!(env.process(&[withdraw], &[&partner]).await.is_err());
+    assert_eq!(env.token_balance(&lp_token_account).await, 200_000_000);
+}
+
+#[tokio::test]
+async fn test_price_update_is_admin_only() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+
+    market.set_sol_price(&mut env, 70_000_000).await.unwrap();
+    let sol_feed: PriceFeed = env.account(&pda::price_feed(&market.sol_mint).0).await;
+    assert_eq!(sol_feed.price_usd_6dec, 70_000_000);
+    // $70 -> $100 across the recorded syncs
+    assert_eq!(sol_feed.volatility_bps(), 4285);
+
+    let intruder = env.funded_wallet(1_000_000_000).await.unwrap();
+    let ix = legasi_sdk::instructions::core::update_price(
+        &solana_sdk::signer::Signer::pubkey(&intruder),
+        &market.sol_mint,
+        1,
+    );
+    assert!(env.process(&[ix], &[&intruder]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_admin_creates_and_manages_markets() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    let admin = env.admin();
+    let address = pda::market(1).0;
+
+    // Parameters are checked before anything is created
+    let mut params = MarketPreset::sol_usdc();
+    params.optimal_utilization_bps = 0;
+    let bad = core::create_market(&admin, 1, &market.sol_mint, &market.usdc_mint, params);
+    assert!(env.process(&[bad], &[]).await.is_err());
+
+    env.process(
+        &[core::create_market(
+            &admin,
+            1,
+            &market.sol_mint,
+            &market.usdc_mint,
+            MarketPreset::sol_usdc(),
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let created: LendingMarket = env.account(&address).await;
+    assert_eq!(created.name, "SOL/USDC");
+    assert_eq!(created.collateral_asset, AssetType::SOL);
+    assert_eq!(created.collateral_mint, market.sol_mint);
+    assert_eq!(created.borrow_mint, market.usdc_mint);
+    assert!(created.is_active && created.borrow_enabled && created.collateral_enabled);
+    // A SOL market holds GAD proceeds until told otherwise
+    assert_eq!(created.gad_proceeds, GadProceedsMode::Hold);
+
+    let mut params = MarketPreset::sol_usdc();
+    params.borrow_cap = 5_000_000_000;
+    params.emode_category = EModeCategory::SolCorrelated;
+    env.process(
+        &[
+            core::update_market_params(&admin, 1, params),
+            core::toggle_market(&admin, 1, true, false, true),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let updated: LendingMarket = env.account(&address).await;
+    assert_eq!(updated.borrow_cap, 5_000_000_000);
+    assert_eq!(updated.emode_category, EModeCategory::SolCorrelated);
+    assert!(!updated.can_borrow(1));
+
+    // GAD proceeds sold over 4 cranks; a single-slice TWAP is rejected
+    let one_slice = core::set_market_gad_proceeds(&admin, 1, GadProceedsMode::Twap, 1);
+    assert!(env.process(&[one_slice], &[]).await.is_err());
+    env.process(
+        &[core::set_market_gad_proceeds(
+            &admin,
+            1,
+            GadProceedsMode::Twap,
+            4,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let updated: LendingMarket = env.account(&address).await;
+    assert_eq!(updated.gad_proceeds, GadProceedsMode::Twap);
+    assert_eq!(updated.gad_sale_slices(), 4);
+
+    // Admin only
+    let intruder = env.funded_wallet(1_000_000_000).await.unwrap();
+    let ix = core::toggle_market(
+        &solana_sdk::signer::Signer::pubkey(&intruder),
+        1,
+        false,
+        false,
+        false,
+    );
+    assert!(env.process(&[ix], &[&intruder]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_treasury_vaults_are_owned_by_the_treasury_pda() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    let admin = env.admin();
+    let treasury = pda::treasury().0;
+
+    env.process(
+        &[
+            core::execute_admin_ops(&admin, vec![AdminOp::SetTreasury { treasury }]),
+            core::init_treasury_vault(&admin, &market.usdc_mint),
+            core::init_treasury_vault(&admin, &market.eurc_mint),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let protocol: Protocol = env.account(&pda::protocol().0).await;
+    assert_eq!(protocol.treasury, treasury);
+    let vault: TokenAccount = env.account(&pda::treasury_vault(&market.eurc_mint).0).await;
+    assert_eq!(vault.owner, treasury);
+    assert_eq!(vault.mint, market.eurc_mint);
+
+    // USDC is what the crank buys, it can't be the holding sold
+    let ix = core::convert_treasury_to_usdc(
+        &admin,
+        &market.usdc_mint,
+        &market.usdc_mint,
+        1_000_000,
+        0,
+        Vec::new(),
+    );
+    assert!(env.process(&[ix], &[]).await.is_err());
+
+    // An empty vault has nothing to convert
+    let ix = core::convert_treasury_to_usdc(
+        &admin,
+        &market.eurc_mint,
+        &market.usdc_mint,
+        1_000_000,
+        0,
+        Vec::new(),
+    );
+    assert!(env.process(&[ix], &[]).await.is_err());
+
+    // Admin only
+    let intruder = env.funded_wallet(1_000_000_000).await.unwrap();
+    let ix = core::init_treasury_vault(
+        &solana_sdk::signer::Signer::pubkey(&intruder),
+        &market.sol_mint,
+    );
+    assert!(env.process(&[ix], &[&intruder]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_collateral_listing_proposals_are_bonded() {
+    let mut env = TestEnv::start().await;
+    let market = Market::setup(&mut env).await.unwrap();
+    let admin = env.admin();
+    let proposer = env.funded_wallet(20 * 1_000_000_000).await.unwrap();
+    let proposer_key = solana_sdk::signer::Signer::pubkey(&proposer);
+    let starting_lamports = env.lamports(&proposer_key).await;
+    let mint = env.create_mint(8).await.unwrap();
+    let params = CollateralParams {
+        oracle: pda::price_feed(&mint).0,
+        max_ltv_bps: 6_000,
+        liquidation_threshold_bps: 7_000,
+        liquidation_bonus_bps: 800,
+        decimals: 8,
+        asset_type: AssetType::CbBTC,
+    };
+
+    // Risk params are checked up front, and decimals must match the mint
+    let unsafe_params = CollateralParams {
+        max_ltv_bps: 7_000,
+        ..params
+    };
+    let ix = core::propose_collateral_listing(&proposer_key, &mint, unsafe_params);
+    assert!(env.process(&[ix], &[&proposer]).await.is_err());
+    let wrong_decimals = CollateralParams {
+        decimals: 6,
+        ..params
+    };
+    let ix = core::propose_collateral_listing(&proposer_key, &mint, wrong_decimals);
+    assert!(env.process(&[ix], &[&proposer]).await.is_err());
+
+    let ix = core::propose_collateral_listing(&proposer_key, &mint, params);
+    env.process(&[ix], &[&proposer]).await.unwrap();
+    let proposal: ListingProposal = env.account(&pda::listing_proposal(&mint).0).await;
+    assert_eq!(proposal.proposer, proposer_key);
+    assert_eq!(proposal.params, params);
+    assert_eq!(proposal.bond_lamports, LISTING_BOND_LAMPORTS);
+    assert!(env.lamports(&proposer_key).await < starting_lamports - LISTING_BOND_LAMPORTS);
+
+    // Only the admin decides
+    let ix = core::approve_collateral_listing(&proposer_key, &mint, &proposer_key);
+    assert!(env.process(&[ix], &[&proposer]).await.is_err());
+
+    // Approval lists the collateral and refunds the bond and rent
+    let ix = core::approve_collateral_listing(&admin, &mint, &proposer_key);
+    env.process(&[ix], &[]).await.unwrap();
+    let collateral: Collateral = env.account(&pda::collateral(&mint).0).await;
+    assert_eq!(collateral.max_ltv_bps, 6_000);
+    assert_eq!(collateral.asset_type, AssetType::CbBTC);
+    assert!(collateral.is_active);
+    assert_eq!(env.lamports(&proposer_key).await, starting_lamports);
+
+    // A listed mint can't be proposed again
+    let ix = core::propose_collateral_listing(&proposer_key, &mint, params);
+    assert!(env.process(&[ix], &[&proposer]).await.is_err());
+
+    // A slashed rejection sends the bond to the treasury, the rent back
+    let spam = env.create_mint(8).await.unwrap();
+    let ix = core::propose_collateral_listing(&proposer_key, &spam, params);
+    env.process(&[ix], &[&proposer]).await.unwrap();
+    let treasury_before = env.lamports(&market.treasury).await;
+    let ix = core::reject_collateral_listing(&admin, &spam, &proposer_key, &market.treasury, true);
+    env.process(&[ix], &[]).await.unwrap();
+    assert_eq!(
+        env.lamports(&market.treasury).await,
+        treasury_before + LISTING_BOND_LAMPORTS
+    );
+    assert_eq!(
+        env.lamports(&proposer_key).await,
+        starting_lamports - LISTING_BOND_LAMPORTS
+    );
+}